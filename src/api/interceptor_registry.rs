@@ -0,0 +1,147 @@
+use crate::api::media_engine::MediaEngine;
+use crate::interceptor::nack::{generator::Generator, responder::Responder};
+use crate::interceptor::registry::Registry;
+use crate::interceptor::remb::Remb;
+use crate::interceptor::report::{receiver::ReceiverReport, sender::SenderReport};
+use crate::interceptor::twcc::{receiver::Receiver, sender::Sender};
+use crate::media::rtp::rtp_codec::{RTPCodecType, RTPHeaderExtensionCapability};
+use crate::media::rtp::{
+    RTCPFeedback, TYPE_RTCP_FB_GOOG_REMB, TYPE_RTCP_FB_NACK, TYPE_RTCP_FB_TRANSPORT_CC,
+};
+
+use anyhow::Result;
+
+/// register_default_interceptors will register some useful interceptors.
+/// If you want to customize which interceptors are loaded, you should copy the
+/// code from this method and remove unwanted interceptors.
+pub fn register_default_interceptors(
+    mut registry: Registry,
+    media_engine: &mut MediaEngine,
+) -> Result<Registry> {
+    registry = configure_nack(registry, media_engine);
+
+    registry = configure_rtcp_reports(registry);
+
+    registry = configure_twcc_receiver_only(registry, media_engine)?;
+
+    Ok(registry)
+}
+
+/// configure_rtcp_reports will setup everything necessary for generating Sender and Receiver Reports
+pub fn configure_rtcp_reports(mut registry: Registry) -> Registry {
+    registry.add(Box::new(ReceiverReport::builder()));
+    registry.add(Box::new(SenderReport::builder()));
+    registry
+}
+
+/// configure_nack will setup everything necessary for handling generating/responding to nack messages.
+/// Only the streams whose codec negotiated generic nack feedback are handled.
+pub fn configure_nack(mut registry: Registry, media_engine: &mut MediaEngine) -> Registry {
+    media_engine.register_feedback(
+        RTCPFeedback {
+            typ: TYPE_RTCP_FB_NACK.to_owned(),
+            parameter: "".to_owned(),
+        },
+        RTPCodecType::Video,
+    );
+    media_engine.register_feedback(
+        RTCPFeedback {
+            typ: TYPE_RTCP_FB_NACK.to_owned(),
+            parameter: "pli".to_owned(),
+        },
+        RTPCodecType::Video,
+    );
+
+    registry.add(Box::new(Responder::builder()));
+    registry.add(Box::new(Generator::builder()));
+    registry
+}
+
+/// configure_remb will setup everything necessary for sending REMB packets with the
+/// bitrate estimated for the remote streams. Only the streams whose codec negotiated
+/// goog-remb feedback are estimated.
+pub fn configure_remb(mut registry: Registry, media_engine: &mut MediaEngine) -> Registry {
+    media_engine.register_feedback(
+        RTCPFeedback {
+            typ: TYPE_RTCP_FB_GOOG_REMB.to_owned(),
+            parameter: "".to_owned(),
+        },
+        RTPCodecType::Video,
+    );
+
+    registry.add(Box::new(Remb::builder()));
+    registry
+}
+
+/// configure_twcc will setup everything necessary for adding
+/// a TWCC header extension to outgoing RTP packets and generating TWCC reports.
+/// It must not be used along with the CongestionController, which stamps the
+/// outgoing RTP packets itself.
+pub fn configure_twcc(mut registry: Registry, media_engine: &mut MediaEngine) -> Result<Registry> {
+    register_twcc(media_engine)?;
+
+    registry.add(Box::new(Sender::builder()));
+    registry.add(Box::new(Receiver::builder()));
+    Ok(registry)
+}
+
+/// configure_twcc_receiver_only will setup everything necessary for generating TWCC reports,
+/// without stamping the outgoing RTP packets.
+pub fn configure_twcc_receiver_only(
+    mut registry: Registry,
+    media_engine: &mut MediaEngine,
+) -> Result<Registry> {
+    register_twcc(media_engine)?;
+
+    registry.add(Box::new(Receiver::builder()));
+    Ok(registry)
+}
+
+/// register_twcc negotiates the transport-cc feedback and header extension for video
+fn register_twcc(media_engine: &mut MediaEngine) -> Result<()> {
+    media_engine.register_feedback(
+        RTCPFeedback {
+            typ: TYPE_RTCP_FB_TRANSPORT_CC.to_owned(),
+            parameter: "".to_owned(),
+        },
+        RTPCodecType::Video,
+    );
+    media_engine.register_header_extension(
+        RTPHeaderExtensionCapability {
+            uri: sdp::extmap::TRANSPORT_CC_URI.to_owned(),
+        },
+        RTPCodecType::Video,
+        vec![],
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_configure_nack() -> Result<()> {
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+        let _ = configure_nack(Registry::new(), &mut m);
+
+        let nack = RTCPFeedback {
+            typ: TYPE_RTCP_FB_NACK.to_owned(),
+            parameter: "".to_owned(),
+        };
+        for c in m.get_codecs_by_kind(RTPCodecType::Video).await {
+            let count = c
+                .capability
+                .rtcp_feedback
+                .iter()
+                .filter(|fb| **fb == nack)
+                .count();
+            assert_eq!(count, 1);
+        }
+        for c in m.get_codecs_by_kind(RTPCodecType::Audio).await {
+            assert!(!c.capability.rtcp_feedback.contains(&nack));
+        }
+
+        Ok(())
+    }
+}
@@ -1,4 +1,23 @@
+use crate::error::Error;
+use crate::media::rtp::fmtp::{codec_answer_fmtp_line, parse_fmtp};
+use crate::media::rtp::rtp_codec::{
+    codec_parameters_fuzzy_search, CodecMatchType, RTPCodecCapability, RTPCodecParameters,
+    RTPCodecType, RTPHeaderExtensionCapability, RTPHeaderExtensionParameter, RTPParameters,
+};
+use crate::media::rtp::rtp_transceiver_direction::{
+    have_rtp_transceiver_direction_intersection, RTPTransceiverDirection,
+};
+use crate::media::rtp::{
+    PayloadType, RTCPFeedback, TYPE_RTCP_FB_CCM, TYPE_RTCP_FB_GOOG_REMB, TYPE_RTCP_FB_NACK,
+};
+
+use crate::peer::sdp::{codecs_from_media_description, rtp_extensions_from_media_description};
+use anyhow::Result;
+use sdp::session_description::SessionDescription;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
 
 /// MIME_TYPE_H264 H264 MIME type.
 /// Note: Matching should be case insensitive.
@@ -12,6 +31,9 @@ pub const MIME_TYPE_VP8: &str = "video/VP8";
 /// MIME_TYPE_VP9 VP9 MIME type
 /// Note: Matching should be case insensitive.
 pub const MIME_TYPE_VP9: &str = "video/VP9";
+/// MIME_TYPE_AV1 AV1 MIME type
+/// Note: Matching should be case insensitive.
+pub const MIME_TYPE_AV1: &str = "video/AV1";
 /// MIME_TYPE_G722 G722 MIME type
 /// Note: Matching should be case insensitive.
 pub const MIME_TYPE_G722: &str = "audio/G722";
@@ -21,13 +43,28 @@ pub const MIME_TYPE_PCMU: &str = "audio/PCMU";
 /// MIME_TYPE_PCMA PCMA MIME type
 /// Note: Matching should be case insensitive.
 pub const MIME_TYPE_PCMA: &str = "audio/PCMA";
+/// MIME_TYPE_RTX RTX MIME type, the retransmission payload format of RFC 4588
+/// Note: Matching should be case insensitive.
+pub const MIME_TYPE_RTX: &str = "video/rtx";
 
+#[derive(Default, Debug, Clone)]
 pub(crate) struct MediaEngineHeaderExtension {
     uri: String,
     is_audio: bool,
     is_video: bool,
     // If set only Transceivers of this direction are allowed
-    //TODO: allowedDirections []RTPTransceiverDirection
+    allowed_directions: Vec<RTPTransceiverDirection>,
+}
+
+/// NegotiatedState is what a MediaEngine has negotiated from the remote
+/// descriptions, kept so that a rolled back remote offer can be undone
+#[derive(Default, Debug, Clone)]
+pub(crate) struct NegotiatedState {
+    video: bool,
+    audio: bool,
+    video_codecs: Vec<RTPCodecParameters>,
+    audio_codecs: Vec<RTPCodecParameters>,
+    header_extensions: HashMap<usize, MediaEngineHeaderExtension>,
 }
 
 /// A MediaEngine defines the codecs supported by a PeerConnection, and the
@@ -36,267 +73,615 @@ pub(crate) struct MediaEngineHeaderExtension {
 #[derive(Default)]
 pub struct MediaEngine {
     // If we have attempted to negotiate a codec type yet.
-    negotiated_video: bool,
-    negotiated_audio: bool,
+    pub(crate) negotiated_video: AtomicBool,
+    pub(crate) negotiated_audio: AtomicBool,
+
+    pub(crate) video_codecs: Vec<RTPCodecParameters>,
+    pub(crate) audio_codecs: Vec<RTPCodecParameters>,
+    pub(crate) negotiated_video_codecs: Mutex<Vec<RTPCodecParameters>>,
+    pub(crate) negotiated_audio_codecs: Mutex<Vec<RTPCodecParameters>>,
 
-    //TODO: videoCodecs, audioCodecs                     []RTPCodecParameters
-    //TODO: negotiatedVideoCodecs, negotiatedAudioCodecs []RTPCodecParameters
     header_extensions: Vec<MediaEngineHeaderExtension>,
-    negotiated_header_extensions: HashMap<usize, MediaEngineHeaderExtension>,
+    negotiated_header_extensions: Mutex<HashMap<usize, MediaEngineHeaderExtension>>,
 }
 
-/*
-// RegisterDefaultCodecs registers the default codecs supported by Pion WebRTC.
-// RegisterDefaultCodecs is not safe for concurrent use.
-func (m *MediaEngine) RegisterDefaultCodecs() error {
-    // Default Pion Audio Codecs
-    for _, codec := range []RTPCodecParameters{
-        {
-            RTPCodecCapability: RTPCodecCapability{MIME_TYPE_OPUS, 48000, 2, "minptime=10;useinbandfec=1", nil},
-            PayloadType:        111,
-        },
-        {
-            RTPCodecCapability: RTPCodecCapability{MIME_TYPE_G722, 8000, 0, "", nil},
-            PayloadType:        9,
-        },
-        {
-            RTPCodecCapability: RTPCodecCapability{MIME_TYPE_PCMU, 8000, 0, "", nil},
-            PayloadType:        0,
-        },
-        {
-            RTPCodecCapability: RTPCodecCapability{MIME_TYPE_PCMA, 8000, 0, "", nil},
-            PayloadType:        8,
-        },
-    } {
-        if err := m.RegisterCodec(codec, RTPCodecTypeAudio); err != nil {
-            return err
+impl MediaEngine {
+    /// register_default_codecs registers the default codecs supported by WebRTC.
+    /// register_default_codecs is not safe for concurrent use.
+    pub fn register_default_codecs(&mut self) -> Result<()> {
+        // Default Audio Codecs
+        for codec in [
+            RTPCodecParameters {
+                capability: RTPCodecCapability {
+                    mime_type: MIME_TYPE_OPUS.to_owned(),
+                    clock_rate: 48000,
+                    channels: 2,
+                    sdp_fmtp_line: "minptime=10;useinbandfec=1".to_owned(),
+                    rtcp_feedback: vec![],
+                },
+                payload_type: 111,
+                ..Default::default()
+            },
+            RTPCodecParameters {
+                capability: RTPCodecCapability {
+                    mime_type: MIME_TYPE_G722.to_owned(),
+                    clock_rate: 8000,
+                    channels: 0,
+                    sdp_fmtp_line: "".to_owned(),
+                    rtcp_feedback: vec![],
+                },
+                payload_type: 9,
+                ..Default::default()
+            },
+            RTPCodecParameters {
+                capability: RTPCodecCapability {
+                    mime_type: MIME_TYPE_PCMU.to_owned(),
+                    clock_rate: 8000,
+                    channels: 0,
+                    sdp_fmtp_line: "".to_owned(),
+                    rtcp_feedback: vec![],
+                },
+                payload_type: 0,
+                ..Default::default()
+            },
+            RTPCodecParameters {
+                capability: RTPCodecCapability {
+                    mime_type: MIME_TYPE_PCMA.to_owned(),
+                    clock_rate: 8000,
+                    channels: 0,
+                    sdp_fmtp_line: "".to_owned(),
+                    rtcp_feedback: vec![],
+                },
+                payload_type: 8,
+                ..Default::default()
+            },
+        ] {
+            self.register_codec(codec, RTPCodecType::Audio)?;
         }
-    }
 
-    videoRTCPFeedback := []rtcpfeedback{{"goog-remb", ""}, {"ccm", "fir"}, {"nack", ""}, {"nack", "pli"}}
-    for _, codec := range []RTPCodecParameters{
-        {
-            RTPCodecCapability: RTPCodecCapability{MIME_TYPE_VP8, 90000, 0, "", videoRTCPFeedback},
-            PayloadType:        96,
-        },
-        {
-            RTPCodecCapability: RTPCodecCapability{"video/rtx", 90000, 0, "apt=96", nil},
-            PayloadType:        97,
-        },
+        let video_rtcp_feedback = vec![
+            RTCPFeedback {
+                typ: TYPE_RTCP_FB_GOOG_REMB.to_owned(),
+                parameter: "".to_owned(),
+            },
+            RTCPFeedback {
+                typ: TYPE_RTCP_FB_CCM.to_owned(),
+                parameter: "fir".to_owned(),
+            },
+            RTCPFeedback {
+                typ: TYPE_RTCP_FB_NACK.to_owned(),
+                parameter: "".to_owned(),
+            },
+            RTCPFeedback {
+                typ: TYPE_RTCP_FB_NACK.to_owned(),
+                parameter: "pli".to_owned(),
+            },
+        ];
+
+        // Default Video Codecs, each one followed by its rtx codec
+        for (mime_type, sdp_fmtp_line, payload_type, rtx_payload_type) in [
+            (MIME_TYPE_VP8, "", 96, 97),
+            (MIME_TYPE_VP9, "profile-id=0", 98, 99),
+            (MIME_TYPE_VP9, "profile-id=1", 100, 101),
+            (
+                MIME_TYPE_H264,
+                "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42001f",
+                102,
+                121,
+            ),
+            (
+                MIME_TYPE_H264,
+                "level-asymmetry-allowed=1;packetization-mode=0;profile-level-id=42001f",
+                127,
+                120,
+            ),
+            (
+                MIME_TYPE_H264,
+                "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e01f",
+                125,
+                107,
+            ),
+            (
+                MIME_TYPE_H264,
+                "level-asymmetry-allowed=1;packetization-mode=0;profile-level-id=42e01f",
+                108,
+                109,
+            ),
+            (
+                MIME_TYPE_H264,
+                "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=640032",
+                123,
+                118,
+            ),
+        ] {
+            self.register_codec(
+                RTPCodecParameters {
+                    capability: RTPCodecCapability {
+                        mime_type: mime_type.to_owned(),
+                        clock_rate: 90000,
+                        channels: 0,
+                        sdp_fmtp_line: sdp_fmtp_line.to_owned(),
+                        rtcp_feedback: video_rtcp_feedback.clone(),
+                    },
+                    payload_type,
+                    ..Default::default()
+                },
+                RTPCodecType::Video,
+            )?;
+
+            self.register_codec(
+                RTPCodecParameters {
+                    capability: RTPCodecCapability {
+                        mime_type: MIME_TYPE_RTX.to_owned(),
+                        clock_rate: 90000,
+                        channels: 0,
+                        sdp_fmtp_line: format!("apt={}", payload_type),
+                        rtcp_feedback: vec![],
+                    },
+                    payload_type: rtx_payload_type,
+                    ..Default::default()
+                },
+                RTPCodecType::Video,
+            )?;
+        }
 
-        {
-            RTPCodecCapability: RTPCodecCapability{MIME_TYPE_VP9, 90000, 0, "profile-id=0", videoRTCPFeedback},
-            PayloadType:        98,
-        },
-        {
-            RTPCodecCapability: RTPCodecCapability{"video/rtx", 90000, 0, "apt=98", nil},
-            PayloadType:        99,
-        },
+        self.register_codec(
+            RTPCodecParameters {
+                capability: RTPCodecCapability {
+                    mime_type: "video/ulpfec".to_owned(),
+                    clock_rate: 90000,
+                    channels: 0,
+                    sdp_fmtp_line: "".to_owned(),
+                    rtcp_feedback: vec![],
+                },
+                payload_type: 116,
+                ..Default::default()
+            },
+            RTPCodecType::Video,
+        )
+    }
 
-        {
-            RTPCodecCapability: RTPCodecCapability{MIME_TYPE_VP9, 90000, 0, "profile-id=1", videoRTCPFeedback},
-            PayloadType:        100,
-        },
-        {
-            RTPCodecCapability: RTPCodecCapability{"video/rtx", 90000, 0, "apt=100", nil},
-            PayloadType:        101,
-        },
+    /// add_codec will append codec if it not exists, and fail if its payload
+    /// type is already taken by a different codec
+    fn add_codec(codecs: &mut Vec<RTPCodecParameters>, codec: RTPCodecParameters) -> Result<()> {
+        for c in codecs.iter() {
+            if c.payload_type == codec.payload_type {
+                if c.capability.mime_type == codec.capability.mime_type
+                    && c.capability.sdp_fmtp_line == codec.capability.sdp_fmtp_line
+                {
+                    return Ok(());
+                }
+                return Err(Error::ErrCodecPayloadTypeAlreadyRegistered.into());
+            }
+        }
+        codecs.push(codec);
+        Ok(())
+    }
 
+    /// register_codec adds codec to the MediaEngine
+    /// These are the list of codecs supported by this PeerConnection.
+    /// register_codec is not safe for concurrent use.
+    pub fn register_codec(
+        &mut self,
+        mut codec: RTPCodecParameters,
+        typ: RTPCodecType,
+    ) -> Result<()> {
+        // payload types are shared by audio and video within a session
+        let (codecs, other_codecs) = match typ {
+            RTPCodecType::Audio => (&mut self.audio_codecs, &self.video_codecs),
+            RTPCodecType::Video => (&mut self.video_codecs, &self.audio_codecs),
+            _ => return Err(Error::ErrUnknownType.into()),
+        };
+        if other_codecs
+            .iter()
+            .any(|c| c.payload_type == codec.payload_type)
         {
-            RTPCodecCapability: RTPCodecCapability{MIME_TYPE_H264, 90000, 0, "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42001f", videoRTCPFeedback},
-            PayloadType:        102,
-        },
-        {
-            RTPCodecCapability: RTPCodecCapability{"video/rtx", 90000, 0, "apt=102", nil},
-            PayloadType:        121,
-        },
+            return Err(Error::ErrCodecPayloadTypeAlreadyRegistered.into());
+        }
 
-        {
-            RTPCodecCapability: RTPCodecCapability{MIME_TYPE_H264, 90000, 0, "level-asymmetry-allowed=1;packetization-mode=0;profile-level-id=42001f", videoRTCPFeedback},
-            PayloadType:        127,
-        },
-        {
-            RTPCodecCapability: RTPCodecCapability{"video/rtx", 90000, 0, "apt=127", nil},
-            PayloadType:        120,
-        },
+        codec.stats_id = format!(
+            "RTPCodec-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_nanos())
+        );
+        MediaEngine::add_codec(codecs, codec)
+    }
 
-        {
-            RTPCodecCapability: RTPCodecCapability{MIME_TYPE_H264, 90000, 0, "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e01f", videoRTCPFeedback},
-            PayloadType:        125,
-        },
-        {
-            RTPCodecCapability: RTPCodecCapability{"video/rtx", 90000, 0, "apt=125", nil},
-            PayloadType:        107,
-        },
+    /// register_feedback adds feedback mechanism to already registered codecs.
+    /// Codecs which already carry the feedback are left untouched.
+    pub fn register_feedback(&mut self, feedback: RTCPFeedback, typ: RTPCodecType) {
+        let codecs = match typ {
+            RTPCodecType::Video => &mut self.video_codecs,
+            RTPCodecType::Audio => &mut self.audio_codecs,
+            _ => return,
+        };
+
+        for c in codecs {
+            if !c.capability.rtcp_feedback.contains(&feedback) {
+                c.capability.rtcp_feedback.push(feedback.clone());
+            }
+        }
+    }
 
-        {
-            RTPCodecCapability: RTPCodecCapability{MIME_TYPE_H264, 90000, 0, "level-asymmetry-allowed=1;packetization-mode=0;profile-level-id=42e01f", videoRTCPFeedback},
-            PayloadType:        108,
-        },
-        {
-            RTPCodecCapability: RTPCodecCapability{"video/rtx", 90000, 0, "apt=108", nil},
-            PayloadType:        109,
-        },
+    /// register_header_extension adds a header extension to the MediaEngine
+    /// To determine the negotiated value use get_header_extension_id after signaling is complete
+    pub fn register_header_extension(
+        &mut self,
+        extension: RTPHeaderExtensionCapability,
+        typ: RTPCodecType,
+        mut allowed_directions: Vec<RTPTransceiverDirection>,
+    ) -> Result<()> {
+        if allowed_directions.is_empty() {
+            allowed_directions = vec![
+                RTPTransceiverDirection::Recvonly,
+                RTPTransceiverDirection::Sendonly,
+            ];
+        }
 
-        {
-            RTPCodecCapability: RTPCodecCapability{MIME_TYPE_H264, 90000, 0, "level-asymmetry-allowed=1;packetization-mode=0;profile-level-id=42001f", videoRTCPFeedback},
-            PayloadType:        127,
-        },
-        {
-            RTPCodecCapability: RTPCodecCapability{"video/rtx", 90000, 0, "apt=127", nil},
-            PayloadType:        120,
-        },
+        for direction in &allowed_directions {
+            if *direction != RTPTransceiverDirection::Recvonly
+                && *direction != RTPTransceiverDirection::Sendonly
+            {
+                return Err(Error::ErrRegisterHeaderExtensionInvalidDirection.into());
+            }
+        }
 
+        let extension_index = match self
+            .header_extensions
+            .iter()
+            .position(|h| h.uri == extension.uri)
         {
-            RTPCodecCapability: RTPCodecCapability{MIME_TYPE_H264, 90000, 0, "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=640032", videoRTCPFeedback},
-            PayloadType:        123,
-        },
-        {
-            RTPCodecCapability: RTPCodecCapability{"video/rtx", 90000, 0, "apt=123", nil},
-            PayloadType:        118,
-        },
+            Some(index) => index,
+            None => {
+                self.header_extensions
+                    .push(MediaEngineHeaderExtension::default());
+                self.header_extensions.len() - 1
+            }
+        };
 
-        {
-            RTPCodecCapability: RTPCodecCapability{"video/ulpfec", 90000, 0, "", nil},
-            PayloadType:        116,
-        },
-    } {
-        if err := m.RegisterCodec(codec, RTPCodecTypeVideo); err != nil {
-            return err
+        let h = &mut self.header_extensions[extension_index];
+        if typ == RTPCodecType::Audio {
+            h.is_audio = true;
+        } else if typ == RTPCodecType::Video {
+            h.is_video = true;
         }
-    }
+        h.uri = extension.uri;
+        h.allowed_directions = allowed_directions;
 
-    return nil
-}
+        Ok(())
+    }
 
-// addCodec will append codec if it not exists
-func (m *MediaEngine) addCodec(codecs []RTPCodecParameters, codec RTPCodecParameters) []RTPCodecParameters {
-    for _, c := range codecs {
-        if c.mime_type == codec.mime_type && c.PayloadType == codec.PayloadType {
-            return codecs
+    /// get_header_extension_id returns the negotiated ID for a header extension,
+    /// and whether it was negotiated for audio and video.
+    /// If the Header Extension isn't enabled the returned ID is 0
+    pub async fn get_header_extension_id(
+        &self,
+        extension: RTPHeaderExtensionCapability,
+    ) -> (usize, bool, bool) {
+        let negotiated_header_extensions = self.negotiated_header_extensions.lock().await;
+        for (id, h) in &*negotiated_header_extensions {
+            if extension.uri == h.uri {
+                return (*id, h.is_audio, h.is_video);
+            }
         }
-    }
-    return append(codecs, codec)
-}
 
-// RegisterCodec adds codec to the MediaEngine
-// These are the list of codecs supported by this PeerConnection.
-// RegisterCodec is not safe for concurrent use.
-func (m *MediaEngine) RegisterCodec(codec RTPCodecParameters, typ RTPCodecType) error {
-    codec.statsID = fmt.Sprintf("RTPCodec-%d", time.Now().UnixNano())
-    switch typ {
-    case RTPCodecTypeAudio:
-        m.audioCodecs = m.addCodec(m.audioCodecs, codec)
-    case RTPCodecTypeVideo:
-        m.videoCodecs = m.addCodec(m.videoCodecs, codec)
-    default:
-        return ErrUnknownType
+        (0, false, false)
     }
-    return nil
-}
 
-// RegisterHeaderExtension adds a header extension to the MediaEngine
-// To determine the negotiated value use `GetHeaderExtensionID` after signaling is complete
-func (m *MediaEngine) RegisterHeaderExtension(extension RTPHeaderExtensionCapability, typ RTPCodecType, allowedDirections ...RTPTransceiverDirection) error {
-    if m.negotiated_header_extensions == nil {
-        m.negotiated_header_extensions = map[int]mediaEngineHeaderExtension{}
-    }
+    pub(crate) async fn get_codec_by_payload(
+        &self,
+        payload_type: PayloadType,
+    ) -> Result<(RTPCodecParameters, RTPCodecType)> {
+        {
+            let negotiated_video_codecs = self.negotiated_video_codecs.lock().await;
+            for codec in &*negotiated_video_codecs {
+                if codec.payload_type == payload_type {
+                    return Ok((codec.clone(), RTPCodecType::Video));
+                }
+            }
+        }
+        {
+            let negotiated_audio_codecs = self.negotiated_audio_codecs.lock().await;
+            for codec in &*negotiated_audio_codecs {
+                if codec.payload_type == payload_type {
+                    return Ok((codec.clone(), RTPCodecType::Audio));
+                }
+            }
+        }
 
-    if len(allowedDirections) == 0 {
-        allowedDirections = []RTPTransceiverDirection{RTPTransceiverDirectionRecvonly, RTPTransceiverDirectionSendonly}
+        Err(Error::ErrCodecNotFound.into())
     }
 
-    for _, direction := range allowedDirections {
-        if direction != RTPTransceiverDirectionRecvonly && direction != RTPTransceiverDirectionSendonly {
-            return ErrRegisterHeaderExtensionInvalidDirection
+    pub(crate) async fn get_codecs_by_kind(&self, typ: RTPCodecType) -> Vec<RTPCodecParameters> {
+        if typ == RTPCodecType::Video {
+            if self.negotiated_video.load(Ordering::SeqCst) {
+                let negotiated_video_codecs = self.negotiated_video_codecs.lock().await;
+                negotiated_video_codecs.clone()
+            } else {
+                self.video_codecs.clone()
+            }
+        } else if typ == RTPCodecType::Audio {
+            if self.negotiated_audio.load(Ordering::SeqCst) {
+                let negotiated_audio_codecs = self.negotiated_audio_codecs.lock().await;
+                negotiated_audio_codecs.clone()
+            } else {
+                self.audio_codecs.clone()
+            }
+        } else {
+            vec![]
         }
     }
 
-    extensionIndex := -1
-    for i := range m.header_extensions {
-        if extension.URI == m.header_extensions[i].uri {
-            extensionIndex = i
+    /// clone_to copies any user modifiable state of the MediaEngine
+    /// all internal state is reset
+    pub(crate) fn clone_to(&self) -> Self {
+        MediaEngine {
+            video_codecs: self.video_codecs.clone(),
+            audio_codecs: self.audio_codecs.clone(),
+            header_extensions: self.header_extensions.clone(),
+            ..Default::default()
         }
     }
 
-    if extensionIndex == -1 {
-        m.header_extensions = append(m.header_extensions, mediaEngineHeaderExtension{})
-        extensionIndex = len(m.header_extensions) - 1
+    /// negotiated_state returns a copy of the codecs and header extensions
+    /// negotiated so far
+    pub(crate) async fn negotiated_state(&self) -> NegotiatedState {
+        NegotiatedState {
+            video: self.negotiated_video.load(Ordering::SeqCst),
+            audio: self.negotiated_audio.load(Ordering::SeqCst),
+            video_codecs: self.negotiated_video_codecs.lock().await.clone(),
+            audio_codecs: self.negotiated_audio_codecs.lock().await.clone(),
+            header_extensions: self.negotiated_header_extensions.lock().await.clone(),
+        }
     }
 
-    if typ == RTPCodecTypeAudio {
-        m.header_extensions[extensionIndex].is_audio = true
-    } else if typ == RTPCodecTypeVideo {
-        m.header_extensions[extensionIndex].is_video = true
+    /// restore_negotiated_state puts back the codecs and header extensions
+    /// negotiated before a remote offer that is rolled back
+    pub(crate) async fn restore_negotiated_state(&self, state: NegotiatedState) {
+        self.negotiated_video.store(state.video, Ordering::SeqCst);
+        self.negotiated_audio.store(state.audio, Ordering::SeqCst);
+        *self.negotiated_video_codecs.lock().await = state.video_codecs;
+        *self.negotiated_audio_codecs.lock().await = state.audio_codecs;
+        *self.negotiated_header_extensions.lock().await = state.header_extensions;
     }
 
-    m.header_extensions[extensionIndex].uri = extension.URI
-    m.header_extensions[extensionIndex].allowedDirections = allowedDirections
+    /// match_remote_codec looks up a codec and enables it if it exists
+    fn match_remote_codec(
+        &self,
+        remote_codec: &RTPCodecParameters,
+        typ: RTPCodecType,
+        exact_matches: &[RTPCodecParameters],
+        partial_matches: &[RTPCodecParameters],
+    ) -> Result<CodecMatchType> {
+        let codecs = if typ == RTPCodecType::Audio {
+            &self.audio_codecs
+        } else {
+            &self.video_codecs
+        };
+
+        let remote_fmtp = parse_fmtp(&remote_codec.capability.sdp_fmtp_line);
+        if let Some(apt) = remote_fmtp.get("apt") {
+            let payload_type = apt
+                .parse::<PayloadType>()
+                .map_err(|_| Error::ErrSessionDescriptionInvalidPayloadType)?;
+
+            let mut apt_match = CodecMatchType::None;
+            if exact_matches
+                .iter()
+                .any(|codec| codec.payload_type == payload_type)
+            {
+                apt_match = CodecMatchType::Exact;
+            } else if partial_matches
+                .iter()
+                .any(|codec| codec.payload_type == payload_type)
+            {
+                apt_match = CodecMatchType::Partial;
+            }
 
-    return nil
-}
+            if apt_match == CodecMatchType::None {
+                return Ok(CodecMatchType::None); // not an error, we just ignore this codec we don't support
+            }
 
-// RegisterFeedback adds feedback mechanism to already registered codecs.
-func (m *MediaEngine) RegisterFeedback(feedback rtcpfeedback, typ RTPCodecType) {
-    switch typ {
-    case RTPCodecTypeVideo:
-        for i, v := range m.videoCodecs {
-            v.rtcpfeedback = append(v.rtcpfeedback, feedback)
-            m.videoCodecs[i] = v
-        }
-    case RTPCodecTypeAudio:
-        for i, v := range m.audioCodecs {
-            v.rtcpfeedback = append(v.rtcpfeedback, feedback)
-            m.audioCodecs[i] = v
+            // if apt's media codec is partial match, then apt codec must be partial match too
+            let (_, mut match_type) = codec_parameters_fuzzy_search(remote_codec, codecs);
+            if match_type == CodecMatchType::Exact && apt_match == CodecMatchType::Partial {
+                match_type = CodecMatchType::Partial;
+            }
+            return Ok(match_type);
         }
+
+        let (_, match_type) = codec_parameters_fuzzy_search(remote_codec, codecs);
+        Ok(match_type)
     }
-}
 
-// getHeaderExtensionID returns the negotiated ID for a header extension.
-// If the Header Extension isn't enabled ok will be false
-func (m *MediaEngine) getHeaderExtensionID(extension RTPHeaderExtensionCapability) (val int, audioNegotiated, videoNegotiated bool) {
-    if m.negotiated_header_extensions == nil {
-        return 0, false, false
+    /// update_header_extension looks up a header extension and enables it if it exists
+    async fn update_header_extension(&self, id: usize, extension: &str, typ: RTPCodecType) {
+        let mut negotiated_header_extensions = self.negotiated_header_extensions.lock().await;
+
+        for local_extension in &self.header_extensions {
+            if local_extension.uri == extension {
+                let h = negotiated_header_extensions.entry(id).or_insert_with(|| {
+                    MediaEngineHeaderExtension {
+                        uri: extension.to_owned(),
+                        allowed_directions: local_extension.allowed_directions.clone(),
+                        ..Default::default()
+                    }
+                });
+
+                if local_extension.is_audio && typ == RTPCodecType::Audio {
+                    h.is_audio = true;
+                } else if local_extension.is_video && typ == RTPCodecType::Video {
+                    h.is_video = true;
+                }
+            }
+        }
     }
 
-    for id, h := range m.negotiated_header_extensions {
-        if extension.URI == h.uri {
-            return id, h.is_audio, h.is_video
+    async fn push_codecs(&self, codecs: Vec<RTPCodecParameters>, typ: RTPCodecType) {
+        let mut negotiated_codecs = if typ == RTPCodecType::Audio {
+            self.negotiated_audio_codecs.lock().await
+        } else {
+            self.negotiated_video_codecs.lock().await
+        };
+
+        for codec in codecs {
+            if !negotiated_codecs.iter().any(|c| {
+                c.payload_type == codec.payload_type
+                    && c.capability.mime_type == codec.capability.mime_type
+            }) {
+                negotiated_codecs.push(codec);
+            }
         }
     }
 
-    return
-}
+    /// update_from_remote_description updates the MediaEngine from a remote description.
+    /// The codecs of a remote offer are negotiated with the parameters they are answered with
+    pub(crate) async fn update_from_remote_description(
+        &self,
+        desc: &SessionDescription,
+        is_offer: bool,
+    ) -> Result<()> {
+        for media in &desc.media_descriptions {
+            let typ = if media.media_name.media.eq_ignore_ascii_case("audio")
+                && !self.negotiated_audio.load(Ordering::SeqCst)
+            {
+                self.negotiated_audio.store(true, Ordering::SeqCst);
+                RTPCodecType::Audio
+            } else if media.media_name.media.eq_ignore_ascii_case("video")
+                && !self.negotiated_video.load(Ordering::SeqCst)
+            {
+                self.negotiated_video.store(true, Ordering::SeqCst);
+                RTPCodecType::Video
+            } else {
+                continue;
+            };
+
+            let codecs = codecs_from_media_description(media)?;
+
+            let mut exact_matches = vec![];
+            let mut partial_matches = vec![];
+
+            for mut codec in codecs {
+                match self.match_remote_codec(&codec, typ, &exact_matches, &partial_matches)? {
+                    CodecMatchType::Exact => {
+                        if is_offer {
+                            let local_codecs = if typ == RTPCodecType::Audio {
+                                &self.audio_codecs
+                            } else {
+                                &self.video_codecs
+                            };
+                            let (local_codec, _) =
+                                codec_parameters_fuzzy_search(&codec, local_codecs);
+                            codec.capability.sdp_fmtp_line = codec_answer_fmtp_line(
+                                &codec.capability.mime_type,
+                                &local_codec.capability.sdp_fmtp_line,
+                                &codec.capability.sdp_fmtp_line,
+                            );
+                        }
+                        exact_matches.push(codec)
+                    }
+                    CodecMatchType::Partial => partial_matches.push(codec),
+                    CodecMatchType::None => {}
+                }
+            }
 
-// copy copies any user modifiable state of the MediaEngine
-// all internal state is reset
-func (m *MediaEngine) copy() *MediaEngine {
-    cloned := &MediaEngine{
-        videoCodecs:      append([]RTPCodecParameters{}, m.videoCodecs...),
-        audioCodecs:      append([]RTPCodecParameters{}, m.audioCodecs...),
-        header_extensions: append([]mediaEngineHeaderExtension{}, m.header_extensions...),
-    }
-    if len(m.header_extensions) > 0 {
-        cloned.negotiated_header_extensions = map[int]mediaEngineHeaderExtension{}
-    }
-    return cloned
-}
+            // use exact matches when they exist, otherwise fall back to partial
+            if !exact_matches.is_empty() {
+                self.push_codecs(exact_matches, typ).await;
+            } else if !partial_matches.is_empty() {
+                self.push_codecs(partial_matches, typ).await;
+            } else {
+                // no match, not negotiated
+                continue;
+            }
 
-func (m *MediaEngine) getCodecByPayload(payloadType PayloadType) (RTPCodecParameters, RTPCodecType, error) {
-    for _, codec := range m.negotiatedVideoCodecs {
-        if codec.PayloadType == payloadType {
-            return codec, RTPCodecTypeVideo, nil
+            let extensions = rtp_extensions_from_media_description(media)?;
+            for (extension, id) in extensions {
+                self.update_header_extension(id, &extension, typ).await;
+            }
         }
+
+        Ok(())
     }
-    for _, codec := range m.negotiatedAudioCodecs {
-        if codec.PayloadType == payloadType {
-            return codec, RTPCodecTypeAudio, nil
+
+    /// get_rtp_parameters_by_kind returns the codecs and header extensions of
+    /// the given kind which are usable by a transceiver of the given directions.
+    /// Until the kind is negotiated the header extensions use their local IDs
+    pub(crate) async fn get_rtp_parameters_by_kind(
+        &self,
+        typ: RTPCodecType,
+        directions: &[RTPTransceiverDirection],
+    ) -> RTPParameters {
+        let is_kind = |h: &MediaEngineHeaderExtension| {
+            (h.is_audio && typ == RTPCodecType::Audio) || (h.is_video && typ == RTPCodecType::Video)
+        };
+
+        let mut header_extensions = vec![];
+        if (self.negotiated_video.load(Ordering::SeqCst) && typ == RTPCodecType::Video)
+            || (self.negotiated_audio.load(Ordering::SeqCst) && typ == RTPCodecType::Audio)
+        {
+            let negotiated_header_extensions = self.negotiated_header_extensions.lock().await;
+            for (id, e) in &*negotiated_header_extensions {
+                if have_rtp_transceiver_direction_intersection(&e.allowed_directions, directions)
+                    && is_kind(e)
+                {
+                    header_extensions.push(RTPHeaderExtensionParameter {
+                        id: *id,
+                        uri: e.uri.clone(),
+                    });
+                }
+            }
+        } else {
+            for (id, e) in self.header_extensions.iter().enumerate() {
+                if have_rtp_transceiver_direction_intersection(&e.allowed_directions, directions)
+                    && is_kind(e)
+                {
+                    header_extensions.push(RTPHeaderExtensionParameter {
+                        id: id + 1,
+                        uri: e.uri.clone(),
+                    });
+                }
+            }
+        }
+
+        RTPParameters {
+            header_extensions,
+            codecs: self.get_codecs_by_kind(typ).await,
         }
     }
 
-    return RTPCodecParameters{}, 0, ErrCodecNotFound
+    /// get_rtp_parameters_by_payload_type returns the negotiated codec of the
+    /// payload type together with the negotiated header extensions of its kind
+    pub(crate) async fn get_rtp_parameters_by_payload_type(
+        &self,
+        payload_type: PayloadType,
+    ) -> Result<RTPParameters> {
+        let (codec, typ) = self.get_codec_by_payload(payload_type).await?;
+
+        let mut header_extensions = vec![];
+        {
+            let negotiated_header_extensions = self.negotiated_header_extensions.lock().await;
+            for (id, e) in &*negotiated_header_extensions {
+                if (e.is_audio && typ == RTPCodecType::Audio)
+                    || (e.is_video && typ == RTPCodecType::Video)
+                {
+                    header_extensions.push(RTPHeaderExtensionParameter {
+                        id: *id,
+                        uri: e.uri.clone(),
+                    });
+                }
+            }
+        }
+
+        Ok(RTPParameters {
+            header_extensions,
+            codecs: vec![codec],
+        })
+    }
 }
 
+/*
 func (m *MediaEngine) collectStats(collector *statsReportCollector) {
     statsLoop := func(codecs []RTPCodecParameters) {
         for _, codec := range codecs {
@@ -319,228 +704,509 @@ func (m *MediaEngine) collectStats(collector *statsReportCollector) {
     statsLoop(m.videoCodecs)
     statsLoop(m.audioCodecs)
 }
+*/
 
-// Look up a codec and enable if it exists
-func (m *MediaEngine) matchRemoteCodec(remoteCodec RTPCodecParameters, typ RTPCodecType, exactMatches, partialMatches []RTPCodecParameters) (codecMatchType, error) {
-    codecs := m.videoCodecs
-    if typ == RTPCodecTypeAudio {
-        codecs = m.audioCodecs
-    }
-
-    remoteFmtp := parse_fmtp(remoteCodec.RTPCodecCapability.sdpfmtp_line)
-    if apt, hasApt := remoteFmtp["apt"]; hasApt {
-        payloadType, err := strconv.Atoi(apt)
-        if err != nil {
-            return codecMatchNone, err
-        }
-
-        aptMatch := codecMatchNone
-        for _, codec := range exactMatches {
-            if codec.PayloadType == PayloadType(payloadType) {
-                aptMatch = codecMatchExact
-                break
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::media::rtp::TYPE_RTCP_FB_TRANSPORT_CC;
+
+    #[test]
+    fn test_register_default_codecs() -> Result<()> {
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+
+        assert_eq!(m.audio_codecs.len(), 4);
+        assert!(m
+            .audio_codecs
+            .iter()
+            .any(|c| c.capability.mime_type == MIME_TYPE_OPUS && c.payload_type == 111));
+
+        for mime_type in &[MIME_TYPE_VP8, MIME_TYPE_VP9, MIME_TYPE_H264] {
+            let codecs: Vec<&RTPCodecParameters> = m
+                .video_codecs
+                .iter()
+                .filter(|c| c.capability.mime_type == *mime_type)
+                .collect();
+            assert!(!codecs.is_empty(), "{} is not registered", mime_type);
+            for c in codecs {
+                assert!(c
+                    .capability
+                    .rtcp_feedback
+                    .iter()
+                    .any(|fb| fb.typ == TYPE_RTCP_FB_NACK && fb.parameter == "pli"));
             }
         }
 
-        if aptMatch == codecMatchNone {
-            for _, codec := range partialMatches {
-                if codec.PayloadType == PayloadType(payloadType) {
-                    aptMatch = codecMatchPartial
-                    break
-                }
+        // registering the defaults twice is harmless
+        m.register_default_codecs()?;
+        assert_eq!(m.audio_codecs.len(), 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_register_codec_duplicate_payload_type() -> Result<()> {
+        let mut m = MediaEngine::default();
+        m.register_codec(
+            RTPCodecParameters {
+                capability: RTPCodecCapability {
+                    mime_type: MIME_TYPE_VP8.to_owned(),
+                    clock_rate: 90000,
+                    ..Default::default()
+                },
+                payload_type: 96,
+                ..Default::default()
+            },
+            RTPCodecType::Video,
+        )?;
+
+        for (mime_type, typ) in &[
+            (MIME_TYPE_VP9, RTPCodecType::Video),
+            (MIME_TYPE_OPUS, RTPCodecType::Audio),
+        ] {
+            if let Err(err) = m.register_codec(
+                RTPCodecParameters {
+                    capability: RTPCodecCapability {
+                        mime_type: mime_type.to_string(),
+                        clock_rate: 90000,
+                        ..Default::default()
+                    },
+                    payload_type: 96,
+                    ..Default::default()
+                },
+                *typ,
+            ) {
+                assert!(Error::ErrCodecPayloadTypeAlreadyRegistered.equal(&err));
+            } else {
+                panic!("expected error, but got ok");
             }
         }
 
-        if aptMatch == codecMatchNone {
-            return codecMatchNone, nil // not an error, we just ignore this codec we don't support
+        if let Err(err) = m.register_codec(RTPCodecParameters::default(), RTPCodecType::Unspecified)
+        {
+            assert!(Error::ErrUnknownType.equal(&err));
+        } else {
+            panic!("expected error, but got ok");
         }
 
-        // if apt's media codec is partial match, then apt codec must be partial match too
-        _, matchType := codec_parameters_fuzzy_search(remoteCodec, codecs)
-        if matchType == codecMatchExact && aptMatch == codecMatchPartial {
-            matchType = codecMatchPartial
-        }
-        return matchType, nil
+        Ok(())
     }
 
-    _, matchType := codec_parameters_fuzzy_search(remoteCodec, codecs)
-    return matchType, nil
-}
+    #[tokio::test]
+    async fn test_register_feedback() -> Result<()> {
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
 
-// Look up a header extension and enable if it exists
-func (m *MediaEngine) updateHeaderExtension(id int, extension string, typ RTPCodecType) error {
-    if m.negotiated_header_extensions == nil {
-        return nil
-    }
-
-    for _, localExtension := range m.header_extensions {
-        if localExtension.uri == extension {
-            h := mediaEngineHeaderExtension{uri: extension, allowedDirections: localExtension.allowedDirections}
-            if existingValue, ok := m.negotiated_header_extensions[id]; ok {
-                h = existingValue
-            }
+        let transport_cc = RTCPFeedback {
+            typ: TYPE_RTCP_FB_TRANSPORT_CC.to_owned(),
+            parameter: "".to_owned(),
+        };
+        m.register_feedback(transport_cc.clone(), RTPCodecType::Audio);
 
-            switch {
-            case localExtension.is_audio && typ == RTPCodecTypeAudio:
-                h.is_audio = true
-            case localExtension.is_video && typ == RTPCodecTypeVideo:
-                h.is_video = true
-            }
-
-            m.negotiated_header_extensions[id] = h
-        }
-    }
-    return nil
-}
-
-func (m *MediaEngine) pushCodecs(codecs []RTPCodecParameters, typ RTPCodecType) {
-    for _, codec := range codecs {
-        if typ == RTPCodecTypeAudio {
-            m.negotiatedAudioCodecs = m.addCodec(m.negotiatedAudioCodecs, codec)
-        } else if typ == RTPCodecTypeVideo {
-            m.negotiatedVideoCodecs = m.addCodec(m.negotiatedVideoCodecs, codec)
+        for c in m.get_codecs_by_kind(RTPCodecType::Audio).await {
+            assert!(c.capability.rtcp_feedback.contains(&transport_cc));
         }
-    }
-}
-
-// Update the MediaEngine from a remote description
-func (m *MediaEngine) updateFromRemoteDescription(desc sdp.SessionDescription) error {
-    for _, media := range desc.MediaDescriptions {
-        var typ RTPCodecType
-        switch {
-        case !m.negotiated_audio && strings.EqualFold(media.MediaName.Media, "audio"):
-            m.negotiated_audio = true
-            typ = RTPCodecTypeAudio
-        case !m.negotiated_video && strings.EqualFold(media.MediaName.Media, "video"):
-            m.negotiated_video = true
-            typ = RTPCodecTypeVideo
-        default:
-            continue
+        for c in m.get_codecs_by_kind(RTPCodecType::Video).await {
+            assert!(!c.capability.rtcp_feedback.contains(&transport_cc));
         }
 
-        codecs, err := codecsFromMediaDescription(media)
-        if err != nil {
-            return err
+        // registering the same feedback again doesn't duplicate it
+        m.register_feedback(transport_cc.clone(), RTPCodecType::Audio);
+        for c in m.get_codecs_by_kind(RTPCodecType::Audio).await {
+            let count = c
+                .capability
+                .rtcp_feedback
+                .iter()
+                .filter(|fb| **fb == transport_cc)
+                .count();
+            assert_eq!(count, 1);
         }
 
-        exactMatches := make([]RTPCodecParameters, 0, len(codecs))
-        partialMatches := make([]RTPCodecParameters, 0, len(codecs))
+        Ok(())
+    }
 
-        for _, codec := range codecs {
-            matchType, mErr := m.matchRemoteCodec(codec, typ, exactMatches, partialMatches)
-            if mErr != nil {
-                return mErr
-            }
+    fn parse_sdp(raw: &str) -> Result<SessionDescription> {
+        let mut reader = std::io::Cursor::new(raw.as_bytes());
+        SessionDescription::unmarshal(&mut reader)
+    }
 
-            if matchType == codecMatchExact {
-                exactMatches = append(exactMatches, codec)
-            } else if matchType == codecMatchPartial {
-                partialMatches = append(partialMatches, codec)
-            }
+    #[tokio::test]
+    async fn test_update_from_remote_description() -> Result<()> {
+        const NO_MATCH: &str = "v=0
+o=- 4596489990601351948 2 IN IP4 127.0.0.1
+s=-
+t=0 0
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=rtpmap:111 ebsOpus/48000/2
+";
+        const OPUS_SAME_PAYLOAD: &str = "v=0
+o=- 4596489990601351948 2 IN IP4 127.0.0.1
+s=-
+t=0 0
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=rtpmap:111 opus/48000/2
+a=fmtp:111 minptime=10; useinbandfec=1
+";
+        const OPUS_DIFFERENT_PAYLOAD: &str = "v=0
+o=- 4596489990601351948 2 IN IP4 127.0.0.1
+s=-
+t=0 0
+m=audio 9 UDP/TLS/RTP/SAVPF 112
+a=rtpmap:112 opus/48000/2
+a=fmtp:112 minptime=10; useinbandfec=1
+";
+        const H264_PARTIAL_MATCH: &str = "v=0
+o=- 4596489990601351948 2 IN IP4 127.0.0.1
+s=-
+t=0 0
+m=video 60323 UDP/TLS/RTP/SAVPF 98 99
+a=rtpmap:98 H264/90000
+a=fmtp:98 level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=4d001f
+a=rtpmap:99 rtx/90000
+a=fmtp:99 apt=98
+";
+
+        // no match
+        {
+            let mut m = MediaEngine::default();
+            m.register_default_codecs()?;
+            m.update_from_remote_description(&parse_sdp(NO_MATCH)?, true)
+                .await?;
+
+            assert!(m.negotiated_audio.load(Ordering::SeqCst));
+            assert!(!m.negotiated_video.load(Ordering::SeqCst));
+            assert!(m.negotiated_audio_codecs.lock().await.is_empty());
         }
 
-        // use exact matches when they exist, otherwise fall back to partial
-        switch {
-        case len(exactMatches) > 0:
-            m.pushCodecs(exactMatches, typ)
-        case len(partialMatches) > 0:
-            m.pushCodecs(partialMatches, typ)
-        default:
-            // no match, not negotiated
-            continue
+        // same payload type
+        {
+            let mut m = MediaEngine::default();
+            m.register_default_codecs()?;
+            m.update_from_remote_description(&parse_sdp(OPUS_SAME_PAYLOAD)?, true)
+                .await?;
+
+            let (codec, typ) = m.get_codec_by_payload(111).await?;
+            assert_eq!(typ, RTPCodecType::Audio);
+            assert_eq!(codec.capability.mime_type, MIME_TYPE_OPUS);
         }
 
-        extensions, err := rtpExtensionsFromMediaDescription(media)
-        if err != nil {
-            return err
+        // different payload type, the remote one is used
+        {
+            let mut m = MediaEngine::default();
+            m.register_default_codecs()?;
+            m.update_from_remote_description(&parse_sdp(OPUS_DIFFERENT_PAYLOAD)?, true)
+                .await?;
+
+            assert!(m.get_codec_by_payload(111).await.is_err());
+            let (codec, _) = m.get_codec_by_payload(112).await?;
+            assert_eq!(codec.capability.mime_type, "audio/opus");
         }
 
-        for extension, id := range extensions {
-            if err = m.updateHeaderExtension(id, extension, typ); err != nil {
-                return err
-            }
+        // partial match, and the rtx codec follows its apt
+        {
+            let mut m = MediaEngine::default();
+            m.register_default_codecs()?;
+            m.update_from_remote_description(&parse_sdp(H264_PARTIAL_MATCH)?, true)
+                .await?;
+
+            assert!(m.negotiated_video.load(Ordering::SeqCst));
+            let codecs = m.get_codecs_by_kind(RTPCodecType::Video).await;
+            assert_eq!(codecs.len(), 2);
+            assert_eq!(codecs[0].payload_type, 98);
+            assert_eq!(codecs[1].payload_type, 99);
         }
+
+        Ok(())
     }
-    return nil
-}
 
-func (m *MediaEngine) getCodecsByKind(typ RTPCodecType) []RTPCodecParameters {
-    if typ == RTPCodecTypeVideo {
-        if m.negotiated_video {
-            return m.negotiatedVideoCodecs
-        }
+    #[tokio::test]
+    async fn test_update_from_remote_description_h264_profiles() -> Result<()> {
+        const H264_PROFILES: &str = "v=0
+o=- 4596489990601351948 2 IN IP4 127.0.0.1
+s=-
+t=0 0
+m=video 60323 UDP/TLS/RTP/SAVPF 100 102 104
+a=rtpmap:100 H264/90000
+a=fmtp:100 level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=4d0032
+a=rtpmap:102 H264/90000
+a=fmtp:102 level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e034
+a=rtpmap:104 H264/90000
+a=fmtp:104 level-asymmetry-allowed=1;packetization-mode=2;profile-level-id=42e01f
+";
+
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+        m.update_from_remote_description(&parse_sdp(H264_PROFILES)?, true)
+            .await?;
+
+        // only the constrained baseline profile in packetization-mode 1 is supported
+        let codecs = m.get_codecs_by_kind(RTPCodecType::Video).await;
+        assert_eq!(codecs.len(), 1);
+        assert_eq!(codecs[0].payload_type, 102);
+
+        Ok(())
+    }
 
-        return m.videoCodecs
-    } else if typ == RTPCodecTypeAudio {
-        if m.negotiated_audio {
-            return m.negotiatedAudioCodecs
+    #[tokio::test]
+    async fn test_update_from_remote_description_h264_levels() -> Result<()> {
+        const H264_LEVEL_31: &str = "v=0
+o=- 4596489990601351948 2 IN IP4 127.0.0.1
+s=-
+t=0 0
+m=video 60323 UDP/TLS/RTP/SAVPF 102
+a=rtpmap:102 H264/90000
+a=fmtp:102 packetization-mode=1;profile-level-id=42e01f
+";
+
+        for (local_fmtp_line, is_offer, expected_fmtp_line) in &[
+            // the lower level of the offer is answered
+            (
+                "packetization-mode=1;profile-level-id=42e034",
+                true,
+                "packetization-mode=1;profile-level-id=42e01f",
+            ),
+            // the lower local level is answered
+            (
+                "packetization-mode=1;profile-level-id=42e00a",
+                true,
+                "packetization-mode=1;profile-level-id=42e00a",
+            ),
+            // the level of an answer is taken as is
+            (
+                "packetization-mode=1;profile-level-id=42e00a",
+                false,
+                "packetization-mode=1;profile-level-id=42e01f",
+            ),
+        ] {
+            let mut m = MediaEngine::default();
+            m.register_codec(
+                RTPCodecParameters {
+                    capability: RTPCodecCapability {
+                        mime_type: MIME_TYPE_H264.to_owned(),
+                        clock_rate: 90000,
+                        sdp_fmtp_line: (*local_fmtp_line).to_owned(),
+                        ..Default::default()
+                    },
+                    payload_type: 102,
+                    ..Default::default()
+                },
+                RTPCodecType::Video,
+            )?;
+            m.update_from_remote_description(&parse_sdp(H264_LEVEL_31)?, *is_offer)
+                .await?;
+
+            let codecs = m.get_codecs_by_kind(RTPCodecType::Video).await;
+            assert_eq!(codecs.len(), 1, "{}", local_fmtp_line);
+            assert_eq!(codecs[0].capability.sdp_fmtp_line, *expected_fmtp_line);
         }
 
-        return m.audioCodecs
+        Ok(())
     }
 
-    return nil
-}
-
-func (m *MediaEngine) getRTPParametersByKind(typ RTPCodecType, directions []RTPTransceiverDirection) RTPParameters {
-    header_extensions := make([]RTPHeaderExtensionParameter, 0)
-
-    if m.negotiated_video && typ == RTPCodecTypeVideo ||
-        m.negotiated_audio && typ == RTPCodecTypeAudio {
-        for id, e := range m.negotiated_header_extensions {
-            if haveRTPTransceiverDirectionIntersection(e.allowedDirections, directions) && (e.is_audio && typ == RTPCodecTypeAudio || e.is_video && typ == RTPCodecTypeVideo) {
-                header_extensions = append(header_extensions, RTPHeaderExtensionParameter{ID: id, URI: e.uri})
-            }
-        }
-    } else {
-        for id, e := range m.header_extensions {
-            if haveRTPTransceiverDirectionIntersection(e.allowedDirections, directions) && (e.is_audio && typ == RTPCodecTypeAudio || e.is_video && typ == RTPCodecTypeVideo) {
-                header_extensions = append(header_extensions, RTPHeaderExtensionParameter{ID: id + 1, URI: e.uri})
-            }
-        }
+    #[tokio::test]
+    async fn test_update_from_remote_description_header_extensions() -> Result<()> {
+        const HEADER_EXTENSIONS: &str = "v=0
+o=- 4596489990601351948 2 IN IP4 127.0.0.1
+s=-
+t=0 0
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=extmap:7 urn:ietf:params:rtp-hdrext:sdes:mid
+a=extmap:5 urn:ietf:params:rtp-hdrext:ssrc-audio-level
+a=rtpmap:111 opus/48000/2
+";
+
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+        m.register_header_extension(
+            RTPHeaderExtensionCapability {
+                uri: "urn:ietf:params:rtp-hdrext:sdes:mid".to_owned(),
+            },
+            RTPCodecType::Audio,
+            vec![],
+        )?;
+
+        m.update_from_remote_description(&parse_sdp(HEADER_EXTENSIONS)?, true)
+            .await?;
+
+        let negotiated_header_extensions = m.negotiated_header_extensions.lock().await;
+        assert_eq!(negotiated_header_extensions.len(), 1);
+        let h = &negotiated_header_extensions[&7];
+        assert_eq!(h.uri, "urn:ietf:params:rtp-hdrext:sdes:mid");
+        assert!(h.is_audio);
+        assert!(!h.is_video);
+
+        Ok(())
     }
 
-    return RTPParameters{
-        header_extensions: header_extensions,
-        Codecs:           m.getCodecsByKind(typ),
+    #[tokio::test]
+    async fn test_restore_negotiated_state() -> Result<()> {
+        const OPUS_WITH_MID: &str = "v=0
+o=- 4596489990601351948 2 IN IP4 127.0.0.1
+s=-
+t=0 0
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=extmap:7 urn:ietf:params:rtp-hdrext:sdes:mid
+a=rtpmap:111 opus/48000/2
+";
+
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+        m.register_header_extension(
+            RTPHeaderExtensionCapability {
+                uri: "urn:ietf:params:rtp-hdrext:sdes:mid".to_owned(),
+            },
+            RTPCodecType::Audio,
+            vec![],
+        )?;
+
+        let state = m.negotiated_state().await;
+        m.update_from_remote_description(&parse_sdp(OPUS_WITH_MID)?, true)
+            .await?;
+        assert!(m.negotiated_audio.load(Ordering::SeqCst));
+        assert_eq!(m.get_codecs_by_kind(RTPCodecType::Audio).await.len(), 1);
+        assert_eq!(
+            m.get_header_extension_id(RTPHeaderExtensionCapability {
+                uri: "urn:ietf:params:rtp-hdrext:sdes:mid".to_owned(),
+            })
+            .await,
+            (7, true, false)
+        );
+
+        m.restore_negotiated_state(state).await;
+        assert!(!m.negotiated_audio.load(Ordering::SeqCst));
+        assert!(m.negotiated_audio_codecs.lock().await.is_empty());
+        assert!(m.negotiated_header_extensions.lock().await.is_empty());
+        assert_eq!(
+            m.get_codecs_by_kind(RTPCodecType::Audio).await,
+            m.audio_codecs
+        );
+
+        Ok(())
     }
-}
 
-func (m *MediaEngine) getRTPParametersByPayloadType(payloadType PayloadType) (RTPParameters, error) {
-    codec, typ, err := m.getCodecByPayload(payloadType)
-    if err != nil {
-        return RTPParameters{}, err
-    }
+    #[test]
+    fn test_clone_to() -> Result<()> {
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+        m.negotiated_audio.store(true, Ordering::SeqCst);
 
-    header_extensions := make([]RTPHeaderExtensionParameter, 0)
-    for id, e := range m.negotiated_header_extensions {
-        if e.is_audio && typ == RTPCodecTypeAudio || e.is_video && typ == RTPCodecTypeVideo {
-            header_extensions = append(header_extensions, RTPHeaderExtensionParameter{ID: id, URI: e.uri})
-        }
+        let cloned = m.clone_to();
+        assert_eq!(cloned.audio_codecs, m.audio_codecs);
+        assert_eq!(cloned.video_codecs, m.video_codecs);
+        assert!(!cloned.negotiated_audio.load(Ordering::SeqCst));
+
+        Ok(())
     }
 
-    return RTPParameters{
-        header_extensions: header_extensions,
-        Codecs:           []RTPCodecParameters{codec},
-    }, nil
-}
+    #[tokio::test]
+    async fn test_register_header_extension() -> Result<()> {
+        const MID_URI: &str = "urn:ietf:params:rtp-hdrext:sdes:mid";
+        const RID_URI: &str = "urn:ietf:params:rtp-hdrext:sdes:rtp-stream-id";
+        const AUDIO_LEVEL_URI: &str = "urn:ietf:params:rtp-hdrext:ssrc-audio-level";
+
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+
+        if let Err(err) = m.register_header_extension(
+            RTPHeaderExtensionCapability {
+                uri: MID_URI.to_owned(),
+            },
+            RTPCodecType::Video,
+            vec![RTPTransceiverDirection::Sendrecv],
+        ) {
+            assert!(Error::ErrRegisterHeaderExtensionInvalidDirection.equal(&err));
+        } else {
+            panic!("expected error, but got ok");
+        }
 
-func payloaderForCodec(codec RTPCodecCapability) (rtp.Payloader, error) {
-    switch strings.ToLower(codec.mime_type) {
-    case strings.ToLower(MIME_TYPE_H264):
-        return &codecs.H264Payloader{}, nil
-    case strings.ToLower(MIME_TYPE_OPUS):
-        return &codecs.OpusPayloader{}, nil
-    case strings.ToLower(MIME_TYPE_VP8):
-        return &codecs.VP8Payloader{}, nil
-    case strings.ToLower(MIME_TYPE_VP9):
-        return &codecs.VP9Payloader{}, nil
-    case strings.ToLower(MIME_TYPE_G722):
-        return &codecs.G722Payloader{}, nil
-    case strings.ToLower(MIME_TYPE_PCMU), strings.ToLower(MIME_TYPE_PCMA):
-        return &codecs.G711Payloader{}, nil
-    default:
-        return nil, ErrNoPayloaderForCodec
+        for (uri, typ, directions) in [
+            (MID_URI, RTPCodecType::Audio, vec![]),
+            (MID_URI, RTPCodecType::Video, vec![]),
+            (
+                RID_URI,
+                RTPCodecType::Video,
+                vec![RTPTransceiverDirection::Recvonly],
+            ),
+            (AUDIO_LEVEL_URI, RTPCodecType::Audio, vec![]),
+        ] {
+            m.register_header_extension(
+                RTPHeaderExtensionCapability {
+                    uri: uri.to_owned(),
+                },
+                typ,
+                directions,
+            )?;
+        }
+        assert_eq!(m.header_extensions.len(), 3);
+
+        // before negotiation the local IDs are offered, filtered by direction
+        let params = m
+            .get_rtp_parameters_by_kind(RTPCodecType::Video, &[RTPTransceiverDirection::Sendonly])
+            .await;
+        assert_eq!(params.header_extensions.len(), 1);
+        assert_eq!(params.header_extensions[0].uri, MID_URI);
+        assert_eq!(params.header_extensions[0].id, 1);
+
+        let params = m
+            .get_rtp_parameters_by_kind(RTPCodecType::Video, &[RTPTransceiverDirection::Recvonly])
+            .await;
+        assert_eq!(params.header_extensions.len(), 2);
+
+        let (id, audio_negotiated, video_negotiated) = m
+            .get_header_extension_id(RTPHeaderExtensionCapability {
+                uri: MID_URI.to_owned(),
+            })
+            .await;
+        assert_eq!(id, 0);
+        assert!(!audio_negotiated && !video_negotiated);
+
+        // the remote IDs are used after negotiation
+        const HEADER_EXTENSIONS: &str = "v=0
+o=- 4596489990601351948 2 IN IP4 127.0.0.1
+s=-
+t=0 0
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=extmap:4 urn:ietf:params:rtp-hdrext:sdes:mid
+a=extmap:5 urn:ietf:params:rtp-hdrext:sdes:rtp-stream-id
+a=rtpmap:111 opus/48000/2
+m=video 9 UDP/TLS/RTP/SAVPF 96
+a=extmap:4 urn:ietf:params:rtp-hdrext:sdes:mid
+a=extmap:5 urn:ietf:params:rtp-hdrext:sdes:rtp-stream-id
+a=rtpmap:96 VP8/90000
+";
+        m.update_from_remote_description(&parse_sdp(HEADER_EXTENSIONS)?, true)
+            .await?;
+
+        let (id, audio_negotiated, video_negotiated) = m
+            .get_header_extension_id(RTPHeaderExtensionCapability {
+                uri: MID_URI.to_owned(),
+            })
+            .await;
+        assert_eq!(id, 4);
+        assert!(audio_negotiated && video_negotiated);
+
+        // rid was only registered for video
+        let (id, audio_negotiated, video_negotiated) = m
+            .get_header_extension_id(RTPHeaderExtensionCapability {
+                uri: RID_URI.to_owned(),
+            })
+            .await;
+        assert_eq!(id, 5);
+        assert!(!audio_negotiated && video_negotiated);
+
+        // audio level wasn't offered by the remote
+        let (id, _, _) = m
+            .get_header_extension_id(RTPHeaderExtensionCapability {
+                uri: AUDIO_LEVEL_URI.to_owned(),
+            })
+            .await;
+        assert_eq!(id, 0);
+
+        let params = m.get_rtp_parameters_by_payload_type(96).await?;
+        assert_eq!(params.codecs[0].capability.mime_type, "video/VP8");
+        assert_eq!(params.header_extensions.len(), 2);
+
+        Ok(())
     }
 }
-*/
@@ -1,12 +1,16 @@
+use crate::interceptor::registry::Registry;
 use crate::media::dtls_transport::DTLSTransport;
 use crate::media::ice_transport::ICETransport;
+use crate::peer::configuration::Configuration;
 use crate::peer::ice::ice_gather::ice_gatherer::ICEGatherer;
 use crate::peer::ice::ice_gather::ICEGatherOptions;
+use crate::peer::peer_connection::PeerConnection;
 
 use dtls::crypto::Certificate;
 use media_engine::*;
 use setting_engine::*;
 
+pub mod interceptor_registry;
 pub mod media_engine;
 pub mod setting_engine;
 
@@ -22,12 +26,20 @@ use std::sync::Arc;
 /// defaultAPI object. Note that the global version of the API
 /// may be phased out in the future.
 pub struct Api {
-    setting_engine: SettingEngine,
-    media_engine: MediaEngine,
-    //TODO: interceptor   interceptor.Interceptor
+    pub(crate) setting_engine: SettingEngine,
+    pub(crate) media_engine: Arc<MediaEngine>,
+    pub(crate) interceptor_registry: Registry,
 }
 
 impl Api {
+    /// new_peer_connection creates a new PeerConnection with the provided configuration against the received API object
+    pub async fn new_peer_connection(
+        &self,
+        configuration: Configuration,
+    ) -> Result<PeerConnection> {
+        PeerConnection::new(self, configuration).await
+    }
+
     /// new_ice_gatherer creates a new ice gatherer.
     /// This constructor is part of the ORTC API. It is not
     /// meant to be used together with the basic WebRTC API.
@@ -50,7 +62,7 @@ impl Api {
     /// new_ice_transport creates a new ice transport.
     /// This constructor is part of the ORTC API. It is not
     /// meant to be used together with the basic WebRTC API.
-    pub fn new_ice_transport(&self, gatherer: ICEGatherer) -> Result<ICETransport> {
+    pub fn new_ice_transport(&self, gatherer: Arc<ICEGatherer>) -> Result<ICETransport> {
         Ok(ICETransport::new(gatherer))
     }
 
@@ -59,28 +71,14 @@ impl Api {
     /// meant to be used together with the basic WebRTC API.
     pub fn new_dtls_transport(
         &self,
-        ice_transport: ICETransport,
-        certificates: Vec<Certificate>,
+        ice_transport: Arc<ICETransport>,
+        mut certificates: Vec<Certificate>,
     ) -> Result<DTLSTransport> {
-        /*TODO: if !certificates.is_empty() {
-            now := time.Now()
-            for _, x509Cert := range certificates {
-                if !x509Cert.Expires().IsZero() && now.After(x509Cert.Expires()) {
-                    return nil, &rtcerr.InvalidAccessError{Err: ErrCertificateExpired}
-                }
-                t.certificates = append(t.certificates, x509Cert)
-            }
-        } else {
-            sk, err := ecdsa.GenerateKey(elliptic.P256(), rand.Reader)
-            if err != nil {
-                return nil, &rtcerr.UnknownError{Err: err}
-            }
-            certificate, err := GenerateCertificate(sk)
-            if err != nil {
-                return nil, err
-            }
-            t.certificates = []Certificate{*certificate}
-        }*/
+        if certificates.is_empty() {
+            certificates.push(Certificate::generate_self_signed(vec![
+                "webrtc-rs".to_owned()
+            ])?);
+        }
 
         Ok(DTLSTransport::new(
             ice_transport,
@@ -128,7 +126,8 @@ impl Default for ApiBuilder {
         ApiBuilder {
             api: Api {
                 setting_engine: SettingEngine::default(),
-                media_engine: MediaEngine::default(),
+                media_engine: Arc::new(MediaEngine::default()),
+                interceptor_registry: Registry::default(),
             },
         }
     }
@@ -153,16 +152,14 @@ impl ApiBuilder {
     /// WithMediaEngine allows providing a MediaEngine to the API.
     /// Settings can be changed after passing the engine to an API.
     pub fn with_media_engine(mut self, media_engine: MediaEngine) -> Self {
-        self.api.media_engine = media_engine;
+        self.api.media_engine = Arc::new(media_engine);
         self
     }
 
-    //TODO:
-    // WithInterceptorRegistry allows providing Interceptors to the API.
-    // Settings should not be changed after passing the registry to an API.
-    /*pub WithInterceptorRegistry(interceptorRegistry *interceptor.Registry) func(a *API) {
-        return func(a *API) {
-            a.interceptor = interceptorRegistry.Build()
-        }
-    }*/
+    /// WithInterceptorRegistry allows providing Interceptors to the API.
+    /// Settings should not be changed after passing the registry to an API.
+    pub fn with_interceptor_registry(mut self, interceptor_registry: Registry) -> Self {
+        self.api.interceptor_registry = interceptor_registry;
+        self
+    }
 }
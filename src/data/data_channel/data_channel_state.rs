@@ -1,8 +1,9 @@
 use std::fmt;
 
 /// DataChannelState indicates the state of a data channel.
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
 pub enum DataChannelState {
+    #[default]
     Unspecified = 0,
 
     /// DataChannelStateConnecting indicates that the data channel is being
@@ -23,12 +24,6 @@ pub enum DataChannelState {
     Closed,
 }
 
-impl Default for DataChannelState {
-    fn default() -> Self {
-        DataChannelState::Unspecified
-    }
-}
-
 const DATA_CHANNEL_STATE_CONNECTING_STR: &str = "Connecting";
 const DATA_CHANNEL_STATE_OPEN_STR: &str = "Open";
 const DATA_CHANNEL_STATE_CLOSING_STR: &str = "Closing";
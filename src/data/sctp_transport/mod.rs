@@ -74,9 +74,9 @@ pub struct SCTPTransport {
     on_data_channel_opened_handler: Arc<Mutex<Option<OnDataChannelOpenedHdlrFn>>>,
 
     // DataChannels
-    data_channels: Arc<Mutex<Vec<Arc<DataChannel>>>>,
-    data_channels_opened: Arc<AtomicU32>,
-    data_channels_requested: Arc<AtomicU32>,
+    pub(crate) data_channels: Arc<Mutex<Vec<Arc<DataChannel>>>>,
+    pub(crate) data_channels_opened: Arc<AtomicU32>,
+    pub(crate) data_channels_requested: Arc<AtomicU32>,
     data_channels_accepted: Arc<AtomicU32>,
 
     setting_engine: SettingEngine,
@@ -126,7 +126,7 @@ impl SCTPTransport {
         self.is_started.store(true, Ordering::SeqCst);
 
         let dtls_transport = self.transport();
-        if let Some(net_conn) = &dtls_transport.conn {
+        if let Some(net_conn) = &dtls_transport.conn().await {
             let sctp_association = Arc::new(
                 sctp::association::Association::client(sctp::association::Config {
                     net_conn: Arc::clone(net_conn) as Arc<dyn Conn + Send + Sync>,
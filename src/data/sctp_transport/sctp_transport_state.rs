@@ -1,8 +1,9 @@
 use std::fmt;
 
 /// SCTPTransportState indicates the state of the SCTP transport.
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
 pub enum SCTPTransportState {
+    #[default]
     Unspecified,
 
     /// SCTPTransportStateConnecting indicates the SCTPTransport is in the
@@ -21,12 +22,6 @@ pub enum SCTPTransportState {
     Closed,
 }
 
-impl Default for SCTPTransportState {
-    fn default() -> Self {
-        SCTPTransportState::Unspecified
-    }
-}
-
 const SCTP_TRANSPORT_STATE_CONNECTING_STR: &str = "Connecting";
 const SCTP_TRANSPORT_STATE_CONNECTED_STR: &str = "Connected";
 const SCTP_TRANSPORT_STATE_CLOSED_STR: &str = "Closed";
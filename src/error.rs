@@ -93,6 +93,11 @@ pub enum Error {
     #[error("codec not found")]
     ErrCodecNotFound,
 
+    /// ErrCodecPayloadTypeAlreadyRegistered is returned when a codec is registered
+    /// with a payload type which is already used by a different codec
+    #[error("payload type is already registered by a different codec")]
+    ErrCodecPayloadTypeAlreadyRegistered,
+
     /// ErrNoRemoteDescription indicates that an operation was rejected because
     /// the remote description is not set
     #[error("remote description is not set")]
@@ -152,6 +157,16 @@ pub enum Error {
     #[error("SetRemoteDescription called with multiple conflicting ice-pwd values")]
     ErrSessionDescriptionConflictingIcePwd,
 
+    /// ErrSessionDescriptionInvalidPayloadType indicates a media section carries
+    /// a format or a rtpmap, fmtp or rtcp-fb line which isn't a valid payload type
+    #[error("SetRemoteDescription called with an invalid payload type")]
+    ErrSessionDescriptionInvalidPayloadType,
+
+    /// ErrSessionDescriptionInvalidExtMap indicates a media section carries an
+    /// a=extmap line which can't be parsed
+    #[error("SetRemoteDescription called with an invalid extmap")]
+    ErrSessionDescriptionInvalidExtMap,
+
     /// ErrNoSRTPProtectionProfile indicates that the DTLS handshake completed and no SRTP Protection Profile was chosen
     #[error("DTLS Handshake completed and no SRTP Protection Profile was chosen")]
     ErrNoSRTPProtectionProfile,
@@ -176,6 +191,62 @@ pub enum Error {
     #[error("the requested codec does not have a payloader")]
     ErrNoPayloaderForCodec,
 
+    /// ErrNoDepacketizerForCodec indicates that the requested codec does not have a depacketizer
+    #[error("the requested codec does not have a depacketizer")]
+    ErrNoDepacketizerForCodec,
+
+    /// ErrShortPacket indicates that a RTP payload is too short to be depacketized
+    #[error("packet is not large enough")]
+    ErrShortPacket,
+
+    /// ErrUnhandledNALUType indicates that a H264 payload carries a NAL unit type we can't depacketize
+    #[error("NALU Type is unhandled")]
+    ErrUnhandledNALUType,
+
+    /// ErrInvalidLEB128 indicates that an AV1 payload carries a malformed leb128 value
+    #[error("invalid leb128 value")]
+    ErrInvalidLEB128,
+
+    /// ErrSignatureMismatch indicates that an IVF file doesn't start with the DKIF signature
+    #[error("IVF signature mismatch")]
+    ErrSignatureMismatch,
+
+    /// ErrUnknownIVFVersion indicates that an IVF file has a version besides 0
+    #[error("IVF version unknown, parser may not parse correctly")]
+    ErrUnknownIVFVersion,
+
+    /// ErrBadIDPageSignature indicates that the first page of an Ogg file doesn't start with OggS
+    #[error("bad header signature")]
+    ErrBadIDPageSignature,
+
+    /// ErrBadIDPageType indicates that the first page of an Ogg file isn't the beginning of the stream
+    #[error("wrong header, expected beginning of stream")]
+    ErrBadIDPageType,
+
+    /// ErrBadIDPageLength indicates that the ID page of an Ogg file has an unexpected size
+    #[error("payload for id page must be 19 bytes")]
+    ErrBadIDPageLength,
+
+    /// ErrBadIDPagePayloadSignature indicates that the ID page of an Ogg file doesn't carry OpusHead
+    #[error("bad payload signature")]
+    ErrBadIDPagePayloadSignature,
+
+    /// ErrChecksumMismatch indicates that the checksum of an Ogg page doesn't match its content
+    #[error("expected and actual checksum do not match")]
+    ErrChecksumMismatch,
+
+    /// ErrDataIsNotH264Stream indicates that a stream doesn't start with a H264 NAL prefix
+    #[error("data is not a H264 bitstream")]
+    ErrDataIsNotH264Stream,
+
+    /// ErrIoEOF indicates that there is no more data to read
+    #[error("IO EOF")]
+    ErrIoEOF,
+
+    /// ErrInvalidSize indicates that the buffer size of a NACK interceptor is out of range
+    #[error("invalid buffer size")]
+    ErrInvalidSize,
+
     /// ErrRegisterHeaderExtensionInvalidDirection indicates that a extension was registered with a direction besides `sendonly` or `recvonly`
     #[error("a header extension must be registered as 'recvonly', 'sendonly' or both")]
     ErrRegisterHeaderExtensionInvalidDirection,
@@ -272,16 +343,34 @@ pub enum Error {
     ErrRTPReceiverForSSRCTrackStreamNotFound,
     #[error("no trackStreams found for RID")]
     ErrRTPReceiverForRIDTrackStreamNotFound,
+    #[error("RTPReceiver has been stopped")]
+    ErrRTPReceiverStopped,
     #[error("Track must not be nil")]
     ErrRTPSenderTrackNil,
     #[error("DTLSTransport must not be nil")]
     ErrRTPSenderDTLSTransportNil,
     #[error("Send has already been called")]
     ErrRTPSenderSendAlreadyCalled,
+    #[error("RTPSender has been stopped")]
+    ErrRTPSenderStopped,
+    #[error("new track must be of the same kind as previous")]
+    ErrRTPSenderNewTrackHasIncorrectKind,
+    #[error("RTPSender can only add encodings of tracks with a rid")]
+    ErrRTPSenderRidNil,
+    #[error("RTPSender has no base encoding with a rid")]
+    ErrRTPSenderNoBaseEncoding,
+    #[error("encoding must have the same id, stream_id and kind as the base encoding")]
+    ErrRTPSenderBaseEncodingMismatch,
+    #[error("RTPSender already has an encoding with this rid")]
+    ErrRTPSenderRIDCollision,
+    #[error("RTPSender has no encoding with the rid of the new track")]
+    ErrRTPSenderNoTrackForRID,
     #[error("errRTPSenderTrackNil")]
     ErrRTPTransceiverCannotChangeMid,
     #[error("invalid state change in RTPTransceiver.setSending")]
     ErrRTPTransceiverSetSendingInvalidState,
+    #[error("unsupported codec type by this transceiver")]
+    ErrRTPTransceiverCodecUnsupported,
     #[error("DTLS not established")]
     ErrSCTPTransportDTLS,
     #[error("addTransceiverSDP() called with 0 transceivers")]
@@ -306,6 +395,8 @@ pub enum Error {
     ErrCertificatePEMFormatError,
     #[error("SCTP is not established")]
     ErrSCTPNotEstablished,
+    #[error("no certificate")]
+    ErrNonCertificate,
 
     #[error("DataChannel is not opened")]
     ErrClosedPipe,
@@ -317,7 +408,17 @@ pub enum Error {
 
 impl Error {
     pub fn equal(&self, err: &anyhow::Error) -> bool {
-        err.downcast_ref::<Self>().map_or(false, |e| e == self)
+        err.downcast_ref::<Self>() == Some(self)
+    }
+}
+
+/// flatten_errs flattens multiple errors into one
+pub(crate) fn flatten_errs(errs: Vec<anyhow::Error>) -> anyhow::Result<()> {
+    if errs.is_empty() {
+        Ok(())
+    } else {
+        let errs_strs: Vec<String> = errs.into_iter().map(|e| e.to_string()).collect();
+        Err(Error::new(errs_strs.join("\n")).into())
     }
 }
 
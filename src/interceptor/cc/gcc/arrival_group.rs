@@ -0,0 +1,110 @@
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// BURST_INTERVAL is the time within which the packets sent are grouped together
+const BURST_INTERVAL: Duration = Duration::from_millis(5);
+
+/// ArrivalGroup is a burst of packets sent within BURST_INTERVAL
+#[derive(Debug, Clone, Copy)]
+struct ArrivalGroup {
+    first_departure: Instant,
+    last_departure: Instant,
+    /// last_arrival is in microseconds on the clock of the remote peer
+    last_arrival: i64,
+}
+
+/// DelayVariation is the variation of the one way delay between two consecutive groups
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct DelayVariation {
+    /// delta_ms is how much later than expected, in ms, the last group arrived
+    pub(crate) delta_ms: f64,
+    /// arrival_ms is the arrival time of the last group, in ms
+    pub(crate) arrival_ms: f64,
+}
+
+/// ArrivalGroupAccumulator groups the received packets and measures the
+/// variation of the delay between the groups
+#[derive(Default)]
+pub(crate) struct ArrivalGroupAccumulator {
+    current: Option<ArrivalGroup>,
+    previous: Option<ArrivalGroup>,
+}
+
+impl ArrivalGroupAccumulator {
+    /// add adds a received packet, the delay variation is returned every time a group is completed
+    pub(crate) fn add(&mut self, departure: Instant, arrival: i64) -> Option<DelayVariation> {
+        let group = match &mut self.current {
+            Some(group) => group,
+            None => {
+                self.current = Some(ArrivalGroup {
+                    first_departure: departure,
+                    last_departure: departure,
+                    last_arrival: arrival,
+                });
+                return None;
+            }
+        };
+
+        if departure < group.first_departure {
+            // reordered packet of a previous group
+            return None;
+        }
+        if departure - group.first_departure < BURST_INTERVAL {
+            group.last_departure = std::cmp::max(group.last_departure, departure);
+            group.last_arrival = std::cmp::max(group.last_arrival, arrival);
+            return None;
+        }
+
+        let completed = *group;
+        self.current = Some(ArrivalGroup {
+            first_departure: departure,
+            last_departure: departure,
+            last_arrival: arrival,
+        });
+        let variation = self.previous.map(|previous| {
+            let inter_arrival_ms = (completed.last_arrival - previous.last_arrival) as f64 / 1000.0;
+            let inter_departure_ms = completed
+                .last_departure
+                .saturating_duration_since(previous.last_departure)
+                .as_secs_f64()
+                * 1000.0;
+            DelayVariation {
+                delta_ms: inter_arrival_ms - inter_departure_ms,
+                arrival_ms: completed.last_arrival as f64 / 1000.0,
+            }
+        });
+        self.previous = Some(completed);
+
+        variation
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_arrival_group_accumulator() {
+        let start = Instant::now();
+        let mut acc = ArrivalGroupAccumulator::default();
+
+        // two packets per group, every 10ms, each group arriving 2ms later than expected
+        let mut variations = vec![];
+        for i in 0..4u64 {
+            for j in 0..2u64 {
+                let departure = start + Duration::from_millis(i * 10 + j);
+                let arrival = (i * 12_000 + j * 1000) as i64;
+                if let Some(v) = acc.add(departure, arrival) {
+                    variations.push(v);
+                }
+            }
+        }
+
+        // the last group isn't complete yet
+        assert_eq!(variations.len(), 2);
+        for v in &variations {
+            assert!((v.delta_ms - 2.0).abs() < 1e-6, "{:?}", v);
+        }
+        assert!((variations[1].arrival_ms - 25.0).abs() < 1e-6);
+    }
+}
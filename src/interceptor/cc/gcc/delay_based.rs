@@ -0,0 +1,368 @@
+use super::arrival_group::{ArrivalGroupAccumulator, DelayVariation};
+use super::feedback_adapter::Acknowledgment;
+
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::time::Instant;
+
+const TRENDLINE_WINDOW_SIZE: usize = 20;
+const TRENDLINE_SMOOTHING: f64 = 0.9;
+const TRENDLINE_THRESHOLD_GAIN: f64 = 4.0;
+const TRENDLINE_MAX_DELTAS: usize = 60;
+
+/// TrendlineEstimator estimates the trend of the one way delay with a linear
+/// regression of the accumulated delay variations
+#[derive(Default)]
+pub(crate) struct TrendlineEstimator {
+    num_deltas: usize,
+    first_arrival_ms: Option<f64>,
+    accumulated_delay: f64,
+    smoothed_delay: f64,
+    history: VecDeque<(f64, f64)>,
+    trend: f64,
+}
+
+impl TrendlineEstimator {
+    /// update returns the modified trend of the delay after adding the variation
+    pub(crate) fn update(&mut self, variation: DelayVariation) -> f64 {
+        let first_arrival_ms = *self.first_arrival_ms.get_or_insert(variation.arrival_ms);
+        self.num_deltas = std::cmp::min(self.num_deltas + 1, TRENDLINE_MAX_DELTAS);
+
+        self.accumulated_delay += variation.delta_ms;
+        self.smoothed_delay = TRENDLINE_SMOOTHING * self.smoothed_delay
+            + (1.0 - TRENDLINE_SMOOTHING) * self.accumulated_delay;
+
+        self.history
+            .push_back((variation.arrival_ms - first_arrival_ms, self.smoothed_delay));
+        if self.history.len() > TRENDLINE_WINDOW_SIZE {
+            self.history.pop_front();
+        }
+        if self.history.len() == TRENDLINE_WINDOW_SIZE {
+            if let Some(trend) = linear_fit_slope(&self.history) {
+                self.trend = trend;
+            }
+        }
+
+        self.num_deltas as f64 * self.trend * TRENDLINE_THRESHOLD_GAIN
+    }
+}
+
+/// linear_fit_slope returns the slope of the least squares line through the points
+fn linear_fit_slope(points: &VecDeque<(f64, f64)>) -> Option<f64> {
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+
+    let mut numerator = 0.0;
+    let mut denominator = 0.0;
+    for (x, y) in points {
+        numerator += (x - mean_x) * (y - mean_y);
+        denominator += (x - mean_x) * (x - mean_x);
+    }
+
+    if denominator == 0.0 {
+        None
+    } else {
+        Some(numerator / denominator)
+    }
+}
+
+/// Usage is the state of the network path detected from the delay trend
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum Usage {
+    Normal,
+    Overuse,
+    Underuse,
+}
+
+const OVERUSE_TIME_THRESHOLD: Duration = Duration::from_millis(10);
+const INITIAL_THRESHOLD: f64 = 12.5;
+const MIN_THRESHOLD: f64 = 6.0;
+const MAX_THRESHOLD: f64 = 600.0;
+const THRESHOLD_GAIN_UP: f64 = 0.0087;
+const THRESHOLD_GAIN_DOWN: f64 = 0.039;
+const MAX_ADAPT_OFFSET: f64 = 15.0;
+
+/// OveruseDetector compares the delay trend with an adaptive threshold
+pub(crate) struct OveruseDetector {
+    threshold: f64,
+    last_update: Option<Instant>,
+    overuse_start: Option<Instant>,
+    overuse_count: usize,
+    prev_trend: f64,
+    usage: Usage,
+}
+
+impl Default for OveruseDetector {
+    fn default() -> Self {
+        OveruseDetector {
+            threshold: INITIAL_THRESHOLD,
+            last_update: None,
+            overuse_start: None,
+            overuse_count: 0,
+            prev_trend: 0.0,
+            usage: Usage::Normal,
+        }
+    }
+}
+
+impl OveruseDetector {
+    pub(crate) fn detect(&mut self, trend: f64, now: Instant) -> Usage {
+        if trend > self.threshold {
+            let overuse_start = *self.overuse_start.get_or_insert(now);
+            self.overuse_count += 1;
+            // the overuse must last a while and still be growing before it is signaled
+            if now - overuse_start >= OVERUSE_TIME_THRESHOLD
+                && self.overuse_count > 1
+                && trend >= self.prev_trend
+            {
+                self.overuse_start = None;
+                self.overuse_count = 0;
+                self.usage = Usage::Overuse;
+            }
+        } else {
+            self.overuse_start = None;
+            self.overuse_count = 0;
+            self.usage = if trend < -self.threshold {
+                Usage::Underuse
+            } else {
+                Usage::Normal
+            };
+        }
+        self.prev_trend = trend;
+
+        self.update_threshold(trend, now);
+
+        self.usage
+    }
+
+    fn update_threshold(&mut self, trend: f64, now: Instant) {
+        let last_update = self.last_update.replace(now).unwrap_or(now);
+        let abs_trend = trend.abs();
+        if abs_trend > self.threshold + MAX_ADAPT_OFFSET {
+            // spikes don't adapt the threshold
+            return;
+        }
+
+        let gain = if abs_trend < self.threshold {
+            THRESHOLD_GAIN_DOWN
+        } else {
+            THRESHOLD_GAIN_UP
+        };
+        let elapsed_ms = std::cmp::min(now - last_update, Duration::from_millis(100)).as_millis();
+        self.threshold += gain * (abs_trend - self.threshold) * elapsed_ms as f64;
+        self.threshold = self.threshold.clamp(MIN_THRESHOLD, MAX_THRESHOLD);
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum RateControlState {
+    Hold,
+    Increase,
+}
+
+const RATE_INCREASE_PER_SECOND: f64 = 1.08;
+const RATE_DECREASE_FACTOR: f64 = 0.85;
+const RECEIVED_RATE_WINDOW: Duration = Duration::from_millis(500);
+
+/// RateController adapts the delay based bitrate to the usage, increasing it
+/// multiplicatively and decreasing it to a fraction of the received bitrate
+pub(crate) struct RateController {
+    bitrate: u64,
+    min_bitrate: u64,
+    max_bitrate: u64,
+    state: RateControlState,
+    last_update: Option<Instant>,
+}
+
+impl RateController {
+    pub(crate) fn new(bitrate: u64, min_bitrate: u64, max_bitrate: u64) -> Self {
+        RateController {
+            bitrate,
+            min_bitrate,
+            max_bitrate,
+            state: RateControlState::Hold,
+            last_update: None,
+        }
+    }
+
+    pub(crate) fn update(
+        &mut self,
+        usage: Usage,
+        received_bitrate: Option<u64>,
+        now: Instant,
+    ) -> u64 {
+        let last_update = self.last_update.replace(now).unwrap_or(now);
+        let elapsed = std::cmp::min(now - last_update, Duration::from_secs(1));
+
+        let mut bitrate = self.bitrate as f64;
+        match usage {
+            Usage::Overuse => {
+                bitrate = received_bitrate.unwrap_or(self.bitrate) as f64 * RATE_DECREASE_FACTOR;
+                self.state = RateControlState::Hold;
+            }
+            Usage::Underuse => self.state = RateControlState::Hold,
+            Usage::Normal => {
+                if self.state == RateControlState::Increase {
+                    bitrate *= RATE_INCREASE_PER_SECOND.powf(elapsed.as_secs_f64());
+                }
+                self.state = RateControlState::Increase;
+            }
+        }
+
+        // don't go too far beyond what the path has proven to deliver
+        if let Some(received_bitrate) = received_bitrate {
+            bitrate = bitrate.min(1.5 * received_bitrate as f64 + 10_000.0);
+        }
+
+        self.bitrate = (bitrate as u64).max(self.min_bitrate).min(self.max_bitrate);
+        self.bitrate
+    }
+}
+
+/// ReceivedRate measures the bitrate received by the remote peer
+#[derive(Default)]
+struct ReceivedRate {
+    /// arrival time in microseconds and size of the packets received in the last RECEIVED_RATE_WINDOW
+    window: VecDeque<(i64, usize)>,
+}
+
+impl ReceivedRate {
+    fn add(&mut self, arrival: i64, size: usize) {
+        self.window.push_back((arrival, size));
+        let window_us = RECEIVED_RATE_WINDOW.as_micros() as i64;
+        while let Some((first, _)) = self.window.front() {
+            if arrival - *first <= window_us {
+                break;
+            }
+            self.window.pop_front();
+        }
+    }
+
+    /// bitrate returns the received bitrate, once enough packets were received to measure it
+    fn bitrate(&self) -> Option<u64> {
+        let (first, last) = match (self.window.front(), self.window.back()) {
+            (Some((first, _)), Some((last, _))) => (*first, *last),
+            _ => return None,
+        };
+        let elapsed_us = last - first;
+        if elapsed_us < RECEIVED_RATE_WINDOW.as_micros() as i64 / 5 {
+            return None;
+        }
+
+        let bytes: usize = self.window.iter().map(|(_, size)| size).sum();
+        Some(bytes as u64 * 8 * 1_000_000 / elapsed_us as u64)
+    }
+}
+
+/// DelayBasedBWE estimates the bitrate from the variation of the one way delay
+/// of the acknowledged packets
+pub(crate) struct DelayBasedBWE {
+    accumulator: ArrivalGroupAccumulator,
+    trendline: TrendlineEstimator,
+    detector: OveruseDetector,
+    rate_controller: RateController,
+    received_rate: ReceivedRate,
+    usage: Usage,
+}
+
+impl DelayBasedBWE {
+    pub(crate) fn new(bitrate: u64, min_bitrate: u64, max_bitrate: u64) -> Self {
+        DelayBasedBWE {
+            accumulator: ArrivalGroupAccumulator::default(),
+            trendline: TrendlineEstimator::default(),
+            detector: OveruseDetector::default(),
+            rate_controller: RateController::new(bitrate, min_bitrate, max_bitrate),
+            received_rate: ReceivedRate::default(),
+            usage: Usage::Normal,
+        }
+    }
+
+    /// update returns the delay based bitrate after a batch of acknowledgments
+    pub(crate) fn update(&mut self, acks: &[Acknowledgment], now: Instant) -> u64 {
+        for ack in acks {
+            let arrival = match ack.arrival {
+                Some(arrival) => arrival,
+                None => continue,
+            };
+            self.received_rate.add(arrival, ack.size);
+            if let Some(variation) = self.accumulator.add(ack.departure, arrival) {
+                let trend = self.trendline.update(variation);
+                self.usage = self.detector.detect(trend, now);
+            }
+        }
+
+        self.rate_controller
+            .update(self.usage, self.received_rate.bitrate(), now)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_overuse_detector() {
+        let start = Instant::now();
+        let mut trendline = TrendlineEstimator::default();
+        let mut detector = OveruseDetector::default();
+
+        // every group arrives 3ms later than the previous one
+        let mut usage = Usage::Normal;
+        for i in 0..40u64 {
+            let trend = trendline.update(DelayVariation {
+                delta_ms: 3.0,
+                arrival_ms: i as f64 * 10.0,
+            });
+            usage = detector.detect(trend, start + Duration::from_millis(i * 10));
+        }
+        assert_eq!(usage, Usage::Overuse);
+
+        // the queue drains
+        for i in 40..80u64 {
+            let trend = trendline.update(DelayVariation {
+                delta_ms: -3.0,
+                arrival_ms: i as f64 * 10.0,
+            });
+            usage = detector.detect(trend, start + Duration::from_millis(i * 10));
+        }
+        assert_eq!(usage, Usage::Underuse);
+
+        // the delay is stable again
+        for i in 80..200u64 {
+            let trend = trendline.update(DelayVariation {
+                delta_ms: 0.0,
+                arrival_ms: i as f64 * 10.0,
+            });
+            usage = detector.detect(trend, start + Duration::from_millis(i * 10));
+        }
+        assert_eq!(usage, Usage::Normal);
+    }
+
+    #[test]
+    fn test_rate_controller() {
+        let start = Instant::now();
+        let mut controller = RateController::new(1_000_000, 10_000, 10_000_000);
+
+        // the first normal usage only starts the increase
+        assert_eq!(controller.update(Usage::Normal, None, start), 1_000_000);
+        let bitrate = controller.update(Usage::Normal, None, start + Duration::from_secs(1));
+        assert_eq!(bitrate, 1_080_000);
+
+        // the increase is limited by the received bitrate
+        let bitrate =
+            controller.update(Usage::Normal, Some(500_000), start + Duration::from_secs(2));
+        assert_eq!(bitrate, 760_000);
+
+        // overuse decreases to a fraction of the received bitrate
+        let bitrate = controller.update(
+            Usage::Overuse,
+            Some(500_000),
+            start + Duration::from_secs(3),
+        );
+        assert_eq!(bitrate, 425_000);
+        let bitrate =
+            controller.update(Usage::Normal, Some(500_000), start + Duration::from_secs(4));
+        assert_eq!(bitrate, 425_000);
+    }
+}
@@ -0,0 +1,152 @@
+use rtcp::transport_feedbacks::transport_layer_cc::{
+    PacketStatusChunk, SymbolTypeTcc, TransportLayerCc,
+};
+use tokio::time::Instant;
+
+/// PACKET_HISTORY_SIZE is the number of sent packets remembered until their feedback arrives
+const PACKET_HISTORY_SIZE: usize = 1 << 13;
+
+#[derive(Debug, Clone, Copy)]
+struct SentPacket {
+    sequence_number: u16,
+    size: usize,
+    departure: Instant,
+}
+
+/// Acknowledgment is the fate of a sent packet, as reported by the TWCC feedback
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Acknowledgment {
+    pub(crate) sequence_number: u16,
+    pub(crate) size: usize,
+    pub(crate) departure: Instant,
+    /// arrival is in microseconds on the clock of the remote peer, None when the packet was lost
+    pub(crate) arrival: Option<i64>,
+}
+
+/// FeedbackAdapter matches the packets reported by the TWCC feedback with the sent packets
+pub(crate) struct FeedbackAdapter {
+    history: Vec<Option<SentPacket>>,
+}
+
+impl FeedbackAdapter {
+    pub(crate) fn new() -> Self {
+        FeedbackAdapter {
+            history: vec![None; PACKET_HISTORY_SIZE],
+        }
+    }
+
+    /// on_sent records a packet sent with the transport wide sequence number sequence_number
+    pub(crate) fn on_sent(&mut self, sequence_number: u16, size: usize, departure: Instant) {
+        self.history[sequence_number as usize % PACKET_HISTORY_SIZE] = Some(SentPacket {
+            sequence_number,
+            size,
+            departure,
+        });
+    }
+
+    /// on_transport_cc_feedback returns the acknowledgments of the sent packets
+    /// reported by the feedback, in the order of their sequence numbers
+    pub(crate) fn on_transport_cc_feedback(
+        &mut self,
+        feedback: &TransportLayerCc,
+    ) -> Vec<Acknowledgment> {
+        let mut statuses = vec![];
+        for chunk in &feedback.packet_chunks {
+            match chunk {
+                PacketStatusChunk::RunLengthChunk(c) => statuses.extend(std::iter::repeat_n(
+                    has_delta(&c.packet_status_symbol),
+                    c.run_length as usize,
+                )),
+                PacketStatusChunk::StatusVectorChunk(c) => {
+                    statuses.extend(c.symbol_list.iter().map(has_delta))
+                }
+            }
+        }
+        // the last status vector may be padded with symbols of no packet
+        statuses.truncate(feedback.packet_status_count as usize);
+
+        let mut deltas = feedback.recv_deltas.iter();
+        // the reference time is expressed in multiples of 64ms
+        let mut arrival_time = feedback.reference_time as i64 * 64000;
+        let mut acks = Vec::with_capacity(statuses.len());
+        for (i, received) in statuses.into_iter().enumerate() {
+            let sequence_number = feedback.base_sequence_number.wrapping_add(i as u16);
+            let arrival = if received {
+                deltas.next().map(|d| {
+                    arrival_time += d.delta;
+                    arrival_time
+                })
+            } else {
+                None
+            };
+
+            let index = sequence_number as usize % PACKET_HISTORY_SIZE;
+            if let Some(sent) = self.history[index] {
+                if sent.sequence_number == sequence_number {
+                    self.history[index] = None;
+                    acks.push(Acknowledgment {
+                        sequence_number,
+                        size: sent.size,
+                        departure: sent.departure,
+                        arrival,
+                    });
+                }
+            }
+        }
+
+        acks
+    }
+}
+
+/// has_delta tells if a packet status comes with the arrival time of the packet
+fn has_delta(symbol: &SymbolTypeTcc) -> bool {
+    matches!(
+        symbol,
+        SymbolTypeTcc::PacketReceivedSmallDelta | SymbolTypeTcc::PacketReceivedLargeDelta
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::interceptor::twcc::Recorder;
+
+    #[test]
+    fn test_feedback_adapter() {
+        let start = Instant::now();
+        let mut adapter = FeedbackAdapter::new();
+        for seq in 0..5u16 {
+            adapter.on_sent(seq, 1000, start);
+        }
+
+        // 2 is lost and 4 isn't reported yet
+        let mut recorder = Recorder::new(1);
+        recorder.record(2, 0, 10_000);
+        recorder.record(2, 1, 12_000);
+        recorder.record(2, 3, 17_000);
+        let pkts = recorder.build_feedback_packet();
+        let feedback = pkts[0]
+            .as_any()
+            .downcast_ref::<TransportLayerCc>()
+            .expect("transport layer cc expected");
+
+        let acks = adapter.on_transport_cc_feedback(feedback);
+        let got: Vec<(u16, Option<i64>)> = acks
+            .iter()
+            .map(|ack| (ack.sequence_number, ack.arrival))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                (0, Some(10_000)),
+                (1, Some(12_000)),
+                (2, None),
+                (3, Some(17_000))
+            ]
+        );
+        assert!(acks.iter().all(|ack| ack.size == 1000));
+
+        // an acknowledged packet isn't reported twice
+        assert!(adapter.on_transport_cc_feedback(feedback).is_empty());
+    }
+}
@@ -0,0 +1,361 @@
+use super::*;
+use crate::interceptor::cc::CongestionController;
+use crate::interceptor::chain::Chain;
+use crate::interceptor::report::receiver::ReceiverReport;
+use crate::interceptor::twcc::receiver::Receiver;
+use crate::media::rtp::rtp_codec::RTPHeaderExtensionParameter;
+
+use bytes::{Bytes, BytesMut};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use util::marshal::Marshal;
+use util::vnet::chunk::Chunk;
+use util::vnet::net::{Net, NetConfig};
+use util::vnet::router::{Router, RouterConfig};
+use util::Conn;
+
+/// CAPACITY is the bitrate of the simulated bottleneck, in bits per second
+const CAPACITY: u64 = 500_000;
+const PAYLOAD_SIZE: usize = 1000;
+
+/// TokenBucket drops the packets exceeding the capacity of the bottleneck
+struct TokenBucket {
+    last: std::time::Instant,
+    tokens: f64,
+}
+
+impl TokenBucket {
+    const BURST: f64 = 10.0 * PAYLOAD_SIZE as f64;
+
+    fn consume(&mut self, size: usize) -> bool {
+        let now = std::time::Instant::now();
+        self.tokens = (self.tokens + (now - self.last).as_secs_f64() * CAPACITY as f64 / 8.0)
+            .min(TokenBucket::BURST);
+        self.last = now;
+
+        if self.tokens < size as f64 {
+            return false;
+        }
+        self.tokens -= size as f64;
+        true
+    }
+}
+
+/// VNetStream carries the packets of a stream over a connection of the virtual network
+struct VNetStream {
+    conn: Arc<dyn Conn + Send + Sync>,
+    remote: SocketAddr,
+}
+
+#[async_trait]
+impl RTPWriter for VNetStream {
+    async fn write(&self, pkt: &rtp::packet::Packet, _attributes: &Attributes) -> Result<usize> {
+        let raw = pkt.marshal()?;
+        Ok(self.conn.send_to(&raw, self.remote).await?)
+    }
+}
+
+#[async_trait]
+impl RTCPWriter for VNetStream {
+    async fn write(
+        &self,
+        pkts: &[Box<dyn rtcp::packet::Packet + Send + Sync>],
+        _attributes: &Attributes,
+    ) -> Result<usize> {
+        let mut raw = BytesMut::new();
+        for p in pkts {
+            raw.extend_from_slice(&p.marshal()?);
+        }
+        Ok(self.conn.send_to(&raw, self.remote).await?)
+    }
+}
+
+#[async_trait]
+impl RTPReader for VNetStream {
+    async fn read(&self, buf: &mut [u8], a: &Attributes) -> Result<(usize, Attributes)> {
+        let (n, _) = self.conn.recv_from(buf).await?;
+        Ok((n, a.clone()))
+    }
+}
+
+#[async_trait]
+impl RTCPReader for VNetStream {
+    async fn read(&self, buf: &mut [u8], a: &Attributes) -> Result<(usize, Attributes)> {
+        let (n, _) = self.conn.recv_from(buf).await?;
+        Ok((n, a.clone()))
+    }
+}
+
+/// add_net connects a new virtual network to the router and returns its ip
+async fn add_net(wan: &Arc<Mutex<Router>>) -> Result<(Net, IpAddr)> {
+    let net = Net::new(Some(NetConfig::default()));
+    let nic = net.get_nic()?;
+    {
+        let mut w = wan.lock().await;
+        w.add_net(Arc::clone(&nic)).await?;
+    }
+
+    let n = nic.lock().await;
+    n.set_router(Arc::clone(wan)).await?;
+    let eth0 = n
+        .get_interface("eth0")
+        .await
+        .ok_or_else(|| Error::new("eth0 not found".to_owned()))?;
+    let ip = eth0
+        .addrs()
+        .first()
+        .ok_or_else(|| Error::new("no address assigned to eth0".to_owned()))?
+        .addr();
+
+    Ok((net, ip))
+}
+
+/// new_bottleneck connects a sender and a receiver through a router, the media sent to
+/// the receiver is limited to the CAPACITY of the bottleneck while its feedback isn't
+async fn new_bottleneck() -> Result<(Arc<Mutex<Router>>, Arc<VNetStream>, Arc<VNetStream>)> {
+    let wan = Arc::new(Mutex::new(Router::new(RouterConfig {
+        cidr: "1.2.3.0/24".to_owned(),
+        min_delay: Duration::from_millis(10),
+        ..Default::default()
+    })?));
+    let (sender_net, sender_ip) = add_net(&wan).await?;
+    let (receiver_net, receiver_ip) = add_net(&wan).await?;
+
+    let bucket = std::sync::Mutex::new(TokenBucket {
+        last: std::time::Instant::now(),
+        tokens: TokenBucket::BURST,
+    });
+    {
+        let w = wan.lock().await;
+        w.add_chunk_filter(Box::new(move |c: &(dyn Chunk + Send + Sync)| -> bool {
+            if c.get_destination_ip() != receiver_ip {
+                return true;
+            }
+            match bucket.lock() {
+                Ok(mut bucket) => bucket.consume(c.user_data().len()),
+                Err(_) => false,
+            }
+        }))
+        .await;
+    }
+    {
+        let mut w = wan.lock().await;
+        w.start().await?;
+    }
+
+    let sender_addr = SocketAddr::new(sender_ip, 5000);
+    let receiver_addr = SocketAddr::new(receiver_ip, 5000);
+    let sender_io = Arc::new(VNetStream {
+        conn: sender_net.bind(sender_addr).await?,
+        remote: receiver_addr,
+    });
+    let receiver_io = Arc::new(VNetStream {
+        conn: receiver_net.bind(receiver_addr).await?,
+        remote: sender_addr,
+    });
+
+    Ok((wan, sender_io, receiver_io))
+}
+
+fn new_stream_info() -> StreamInfo {
+    StreamInfo {
+        ssrc: 1,
+        clock_rate: 90000,
+        rtp_header_extensions: vec![RTPHeaderExtensionParameter {
+            uri: sdp::extmap::TRANSPORT_CC_URI.to_owned(),
+            id: 1,
+        }],
+        ..Default::default()
+    }
+}
+
+/// bind_sender binds the interceptor of the sender to its end of the bottleneck, and
+/// returns the writer of the local stream
+async fn bind_sender(
+    sender: &Chain,
+    sender_io: &Arc<VNetStream>,
+    info: &StreamInfo,
+) -> Arc<dyn RTPWriter + Send + Sync> {
+    sender
+        .bind_rtcp_writer(Arc::clone(sender_io) as Arc<dyn RTCPWriter + Send + Sync>)
+        .await;
+    let rtp_writer = sender
+        .bind_local_stream(
+            info,
+            Arc::clone(sender_io) as Arc<dyn RTPWriter + Send + Sync>,
+        )
+        .await;
+    let rtcp_reader = sender
+        .bind_rtcp_reader(Arc::clone(sender_io) as Arc<dyn RTCPReader + Send + Sync>)
+        .await;
+    tokio::spawn(async move {
+        let mut buf = vec![0u8; 1500];
+        let a = Attributes::new();
+        while rtcp_reader.read(&mut buf, &a).await.is_ok() {}
+    });
+
+    rtp_writer
+}
+
+/// start_receiver reads the stream on the receiver, which sends TWCC feedback and
+/// receiver reports back to the sender
+async fn start_receiver(receiver_io: Arc<VNetStream>, info: &StreamInfo) -> Result<Chain> {
+    let receiver = Chain::new(vec![
+        Receiver::builder()
+            .with_interval(Duration::from_millis(50))
+            .build("")?,
+        ReceiverReport::builder()
+            .with_interval(Duration::from_millis(200))
+            .build("")?,
+    ]);
+    receiver
+        .bind_rtcp_writer(Arc::clone(&receiver_io) as Arc<dyn RTCPWriter + Send + Sync>)
+        .await;
+    let rtp_reader = receiver
+        .bind_remote_stream(info, receiver_io as Arc<dyn RTPReader + Send + Sync>)
+        .await;
+    tokio::spawn(async move {
+        let mut buf = vec![0u8; 1500];
+        let a = Attributes::new();
+        while rtp_reader.read(&mut buf, &a).await.is_ok() {}
+    });
+
+    Ok(receiver)
+}
+
+fn new_packet(sequence_number: u16) -> rtp::packet::Packet {
+    rtp::packet::Packet {
+        header: rtp::header::Header {
+            version: 2,
+            ssrc: 1,
+            sequence_number,
+            ..Default::default()
+        },
+        payload: Bytes::from(vec![0u8; PAYLOAD_SIZE]),
+    }
+}
+
+#[tokio::test]
+async fn test_send_side_bwe_bottleneck() -> Result<()> {
+    let (wan, sender_io, receiver_io) = new_bottleneck().await?;
+    let info = new_stream_info();
+
+    // the sender starts at twice the capacity of the bottleneck
+    let (estimator_tx, mut estimator_rx) = mpsc::channel(1);
+    let sender = Chain::new(vec![CongestionController::builder(Box::new(
+        SendSideBWE::builder().with_initial_bitrate(2 * CAPACITY),
+    ))
+    .with_on_new_peer_connection(Box::new(
+        move |_id: &str, estimator: Arc<dyn BandwidthEstimator + Send + Sync>| {
+            let _ = estimator_tx.try_send(estimator);
+        },
+    ))
+    .build("")?]);
+    let estimator = estimator_rx.recv().await.expect("an estimator");
+
+    let notified_bitrate = Arc::new(AtomicU64::new(0));
+    let notified_bitrate2 = Arc::clone(&notified_bitrate);
+    estimator
+        .on_target_bitrate_change(Box::new(move |bitrate: u64| {
+            notified_bitrate2.store(bitrate, Ordering::SeqCst);
+            Box::pin(async {})
+        }))
+        .await;
+
+    let rtp_writer = bind_sender(&sender, &sender_io, &info).await;
+    let receiver = start_receiver(receiver_io, &info).await?;
+
+    // send at the target bitrate for a while
+    let a = Attributes::new();
+    let mut sequence_number = 0u16;
+    let mut budget = 0u64;
+    let mut ticker = tokio::time::interval(Duration::from_millis(10));
+    for _ in 0..400 {
+        ticker.tick().await;
+
+        budget += estimator.get_target_bitrate().await / 8 / 100;
+        while budget >= PAYLOAD_SIZE as u64 {
+            budget -= PAYLOAD_SIZE as u64;
+            rtp_writer.write(&new_packet(sequence_number), &a).await?;
+            sequence_number = sequence_number.wrapping_add(1);
+        }
+    }
+
+    // stop the feedback before looking at the estimation
+    receiver.close().await?;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let target_bitrate = estimator.get_target_bitrate().await;
+    assert!(
+        target_bitrate > CAPACITY / 4 && target_bitrate < CAPACITY * 3 / 2,
+        "target bitrate {} doesn't match the capacity {}",
+        target_bitrate,
+        CAPACITY
+    );
+    // the application was told about the target bitrate
+    assert_eq!(notified_bitrate.load(Ordering::SeqCst), target_bitrate);
+
+    sender.close().await?;
+    let mut w = wan.lock().await;
+    w.stop().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_send_side_bwe_padding() -> Result<()> {
+    let (wan, sender_io, receiver_io) = new_bottleneck().await?;
+    let info = new_stream_info();
+
+    let (estimator_tx, mut estimator_rx) = mpsc::channel(1);
+    let sender = Chain::new(vec![CongestionController::builder(Box::new(
+        SendSideBWE::builder().with_initial_bitrate(CAPACITY / 2),
+    ))
+    .with_padding(true)
+    .with_on_new_peer_connection(Box::new(
+        move |_id: &str, estimator: Arc<dyn BandwidthEstimator + Send + Sync>| {
+            let _ = estimator_tx.try_send(estimator);
+        },
+    ))
+    .build("")?]);
+    let estimator = estimator_rx.recv().await.expect("an estimator");
+
+    let rtp_writer = bind_sender(&sender, &sender_io, &info).await;
+    let receiver = start_receiver(receiver_io, &info).await?;
+
+    // the stream is sent far below the capacity, the padding lets the estimator ramp
+    // up instead of following the bitrate of the stream down
+    let a = Attributes::new();
+    let mut sequence_number = 0u16;
+    let mut budget = 0u64;
+    let mut ticker = tokio::time::interval(Duration::from_millis(10));
+    for _ in 0..400 {
+        ticker.tick().await;
+
+        budget += CAPACITY / 10 / 8 / 100;
+        while budget >= PAYLOAD_SIZE as u64 {
+            budget -= PAYLOAD_SIZE as u64;
+            rtp_writer.write(&new_packet(sequence_number), &a).await?;
+            sequence_number = sequence_number.wrapping_add(1);
+        }
+    }
+
+    receiver.close().await?;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let target_bitrate = estimator.get_target_bitrate().await;
+    assert!(
+        target_bitrate > CAPACITY / 2,
+        "target bitrate {} didn't ramp up from {}",
+        target_bitrate,
+        CAPACITY / 2
+    );
+
+    sender.close().await?;
+    let mut w = wan.lock().await;
+    w.stop().await?;
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+/// LOSS_INCREASE_THRESHOLD is the fraction of loss below which the bitrate is increased
+const LOSS_INCREASE_THRESHOLD: f64 = 0.02;
+/// LOSS_DECREASE_THRESHOLD is the fraction of loss above which the bitrate is decreased
+const LOSS_DECREASE_THRESHOLD: f64 = 0.1;
+const LOSS_INCREASE_FACTOR: f64 = 1.05;
+
+/// LossBasedBWE estimates the bitrate from the fraction of packets lost
+/// reported by the receiver reports
+pub(crate) struct LossBasedBWE {
+    min_bitrate: u64,
+    max_bitrate: u64,
+}
+
+impl LossBasedBWE {
+    pub(crate) fn new(min_bitrate: u64, max_bitrate: u64) -> Self {
+        LossBasedBWE {
+            min_bitrate,
+            max_bitrate,
+        }
+    }
+
+    /// update returns the loss based bitrate, given the current target bitrate
+    /// and the fraction lost of a reception report
+    pub(crate) fn update(&self, target_bitrate: u64, fraction_lost: u8) -> u64 {
+        let loss = fraction_lost as f64 / 256.0;
+        let bitrate = if loss < LOSS_INCREASE_THRESHOLD {
+            target_bitrate as f64 * LOSS_INCREASE_FACTOR
+        } else if loss > LOSS_DECREASE_THRESHOLD {
+            target_bitrate as f64 * (1.0 - 0.5 * loss)
+        } else {
+            target_bitrate as f64
+        };
+
+        (bitrate as u64).max(self.min_bitrate).min(self.max_bitrate)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_loss_based_bwe() {
+        let bwe = LossBasedBWE::new(10_000, 1_000_000);
+
+        assert_eq!(bwe.update(100_000, 0), 105_000);
+        // 5% of loss holds the bitrate
+        assert_eq!(bwe.update(100_000, 13), 100_000);
+        // 50% of loss
+        assert_eq!(bwe.update(100_000, 128), 75_000);
+
+        assert_eq!(bwe.update(990_000, 0), 1_000_000);
+        assert_eq!(bwe.update(15_000, 255), 10_000);
+    }
+}
@@ -0,0 +1,234 @@
+mod arrival_group;
+pub(crate) mod delay_based;
+pub(crate) mod feedback_adapter;
+#[cfg(test)]
+mod gcc_test;
+mod loss_based;
+
+use super::{BandwidthEstimator, BandwidthEstimatorBuilder, OnTargetBitrateChangeHdlrFn};
+use crate::error::Error;
+use crate::interceptor::stream_info::StreamInfo;
+use crate::interceptor::twcc::transport_cc_extension_id;
+use crate::interceptor::*;
+use delay_based::DelayBasedBWE;
+use feedback_adapter::FeedbackAdapter;
+use loss_based::LossBasedBWE;
+
+use rtcp::transport_feedbacks::transport_layer_cc::TransportLayerCc;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use util::marshal::MarshalSize;
+
+const DEFAULT_INITIAL_BITRATE: u64 = 300_000;
+const DEFAULT_MIN_BITRATE: u64 = 30_000;
+const DEFAULT_MAX_BITRATE: u64 = 10_000_000;
+
+/// SendSideBWEBuilder can be used to configure the SendSideBWE of the PeerConnections
+#[derive(Default)]
+pub struct SendSideBWEBuilder {
+    initial_bitrate: Option<u64>,
+    min_bitrate: Option<u64>,
+    max_bitrate: Option<u64>,
+}
+
+impl SendSideBWEBuilder {
+    /// with_initial_bitrate sets the bitrate, in bits per second, the estimation starts from.
+    pub fn with_initial_bitrate(mut self, bitrate: u64) -> SendSideBWEBuilder {
+        self.initial_bitrate = Some(bitrate);
+        self
+    }
+
+    /// with_min_bitrate sets the lowest target bitrate, in bits per second.
+    pub fn with_min_bitrate(mut self, bitrate: u64) -> SendSideBWEBuilder {
+        self.min_bitrate = Some(bitrate);
+        self
+    }
+
+    /// with_max_bitrate sets the highest target bitrate, in bits per second.
+    pub fn with_max_bitrate(mut self, bitrate: u64) -> SendSideBWEBuilder {
+        self.max_bitrate = Some(bitrate);
+        self
+    }
+}
+
+impl BandwidthEstimatorBuilder for SendSideBWEBuilder {
+    fn build(&self) -> Result<Arc<dyn BandwidthEstimator + Send + Sync>> {
+        let min_bitrate = self.min_bitrate.unwrap_or(DEFAULT_MIN_BITRATE);
+        let max_bitrate = self.max_bitrate.unwrap_or(DEFAULT_MAX_BITRATE);
+        if min_bitrate > max_bitrate {
+            return Err(Error::new(format!(
+                "min bitrate {} is greater than max bitrate {}",
+                min_bitrate, max_bitrate
+            ))
+            .into());
+        }
+        let initial_bitrate = self
+            .initial_bitrate
+            .unwrap_or(DEFAULT_INITIAL_BITRATE)
+            .max(min_bitrate)
+            .min(max_bitrate);
+
+        Ok(Arc::new(SendSideBWE {
+            internal: Arc::new(Mutex::new(SendSideBWEInternal {
+                feedback_adapter: FeedbackAdapter::new(),
+                delay_based: DelayBasedBWE::new(initial_bitrate, min_bitrate, max_bitrate),
+                loss_based: LossBasedBWE::new(min_bitrate, max_bitrate),
+                delay_based_bitrate: initial_bitrate,
+                // without any report of loss, only the delay limits the bitrate
+                loss_based_bitrate: max_bitrate,
+                target_bitrate: initial_bitrate,
+            })),
+            on_target_bitrate_change_handler: Arc::new(Mutex::new(None)),
+        }))
+    }
+}
+
+struct SendSideBWEInternal {
+    feedback_adapter: FeedbackAdapter,
+    delay_based: DelayBasedBWE,
+    loss_based: LossBasedBWE,
+
+    delay_based_bitrate: u64,
+    loss_based_bitrate: u64,
+    target_bitrate: u64,
+}
+
+/// SendSideBWEStream records the packets of a local stream stamped with a
+/// transport wide sequence number
+struct SendSideBWEStream {
+    next_rtp_writer: Arc<dyn RTPWriter + Send + Sync>,
+    hdr_ext_id: u8,
+    internal: Arc<Mutex<SendSideBWEInternal>>,
+}
+
+#[async_trait]
+impl RTPWriter for SendSideBWEStream {
+    async fn write(&self, pkt: &rtp::packet::Packet, a: &Attributes) -> Result<usize> {
+        if let Some(ext) = pkt.header.get_extension(self.hdr_ext_id) {
+            if ext.len() >= 2 {
+                let sequence_number = u16::from_be_bytes([ext[0], ext[1]]);
+                let mut internal = self.internal.lock().await;
+                // padding packets have no payload, what they weigh is added when marshaled
+                internal.feedback_adapter.on_sent(
+                    sequence_number,
+                    pkt.marshal_size(),
+                    Instant::now(),
+                );
+            }
+        }
+
+        self.next_rtp_writer.write(pkt, a).await
+    }
+}
+
+/// SendSideBWE is the Google Congestion Control estimator, as specified in
+/// https://datatracker.ietf.org/doc/html/draft-ietf-rmcat-gcc-02.
+/// Its target bitrate is the lowest of a delay based estimation, fed by the TWCC
+/// feedback, and of a loss based estimation, fed by the receiver reports.
+pub struct SendSideBWE {
+    internal: Arc<Mutex<SendSideBWEInternal>>,
+    on_target_bitrate_change_handler: Arc<Mutex<Option<OnTargetBitrateChangeHdlrFn>>>,
+}
+
+impl SendSideBWE {
+    /// builder returns a new SendSideBWEBuilder.
+    pub fn builder() -> SendSideBWEBuilder {
+        SendSideBWEBuilder::default()
+    }
+
+    async fn target_bitrate_change(&self, target_bitrate: u64) {
+        let mut handler = self.on_target_bitrate_change_handler.lock().await;
+        if let Some(f) = &mut *handler {
+            f(target_bitrate).await;
+        }
+    }
+}
+
+#[async_trait]
+impl BandwidthEstimator for SendSideBWE {
+    /// add_stream records the packets of the streams which negotiated the
+    /// transport-cc header extension
+    async fn add_stream(
+        &self,
+        info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Arc<dyn RTPWriter + Send + Sync> {
+        match transport_cc_extension_id(info) {
+            Some(hdr_ext_id) => Arc::new(SendSideBWEStream {
+                next_rtp_writer: writer,
+                hdr_ext_id,
+                internal: Arc::clone(&self.internal),
+            }),
+            None => writer,
+        }
+    }
+
+    async fn write_rtcp(
+        &self,
+        pkts: &[Box<dyn rtcp::packet::Packet + Send + Sync>],
+        _attributes: &Attributes,
+    ) -> Result<()> {
+        let now = Instant::now();
+        let changed = {
+            let mut internal = self.internal.lock().await;
+            let internal = &mut *internal;
+            for p in pkts {
+                if let Some(feedback) = p.as_any().downcast_ref::<TransportLayerCc>() {
+                    let acks = internal.feedback_adapter.on_transport_cc_feedback(feedback);
+                    if !acks.is_empty() {
+                        internal.delay_based_bitrate = internal.delay_based.update(&acks, now);
+                    }
+                    continue;
+                }
+
+                let reports = if let Some(rr) = p
+                    .as_any()
+                    .downcast_ref::<rtcp::receiver_report::ReceiverReport>()
+                {
+                    &rr.reports
+                } else if let Some(sr) = p
+                    .as_any()
+                    .downcast_ref::<rtcp::sender_report::SenderReport>()
+                {
+                    &sr.reports
+                } else {
+                    continue;
+                };
+                for report in reports {
+                    internal.loss_based_bitrate = internal
+                        .loss_based
+                        .update(internal.target_bitrate, report.fraction_lost);
+                }
+            }
+
+            let target_bitrate =
+                std::cmp::min(internal.delay_based_bitrate, internal.loss_based_bitrate);
+            if target_bitrate != internal.target_bitrate {
+                internal.target_bitrate = target_bitrate;
+                Some(target_bitrate)
+            } else {
+                None
+            }
+        };
+
+        if let Some(target_bitrate) = changed {
+            self.target_bitrate_change(target_bitrate).await;
+        }
+
+        Ok(())
+    }
+
+    async fn get_target_bitrate(&self) -> u64 {
+        let internal = self.internal.lock().await;
+        internal.target_bitrate
+    }
+
+    async fn on_target_bitrate_change(&self, f: OnTargetBitrateChangeHdlrFn) {
+        let mut handler = self.on_target_bitrate_change_handler.lock().await;
+        *handler = Some(f);
+    }
+
+    async fn close(&self) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,205 @@
+pub mod gcc;
+mod pacer;
+
+use pacer::Pacer;
+
+use crate::error::Error;
+use crate::interceptor::stream_info::StreamInfo;
+use crate::interceptor::*;
+
+use std::future::Future;
+use std::pin::Pin;
+
+pub type OnTargetBitrateChangeHdlrFn =
+    Box<dyn (FnMut(u64) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>) + Send + Sync>;
+
+pub type OnNewPeerConnectionHdlrFn =
+    Box<dyn Fn(&str, Arc<dyn BandwidthEstimator + Send + Sync>) + Send + Sync>;
+
+/// BandwidthEstimator estimates the bitrate available to the local streams of a PeerConnection
+#[async_trait]
+pub trait BandwidthEstimator {
+    /// add_stream is called for every local stream, the packets written to the returned
+    /// writer are the ones the estimator learns from.
+    async fn add_stream(
+        &self,
+        info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Arc<dyn RTPWriter + Send + Sync>;
+
+    /// write_rtcp feeds the estimator with a batch of rtcp packets read from the remote peer.
+    async fn write_rtcp(
+        &self,
+        pkts: &[Box<dyn rtcp::packet::Packet + Send + Sync>],
+        attributes: &Attributes,
+    ) -> Result<()>;
+
+    /// get_target_bitrate returns the bitrate the local streams should be sent at, in bits per second.
+    async fn get_target_bitrate(&self) -> u64;
+
+    /// on_target_bitrate_change sets a handler that is fired every time the target bitrate changes.
+    async fn on_target_bitrate_change(&self, f: OnTargetBitrateChangeHdlrFn);
+
+    /// close closes the BandwidthEstimator.
+    async fn close(&self) -> Result<()>;
+}
+
+/// BandwidthEstimatorBuilder provides an interface for constructing the
+/// BandwidthEstimator of every PeerConnection
+pub trait BandwidthEstimatorBuilder {
+    fn build(&self) -> Result<Arc<dyn BandwidthEstimator + Send + Sync>>;
+}
+
+/// CongestionControllerBuilder can be used to configure the CongestionController Interceptor
+pub struct CongestionControllerBuilder {
+    estimator_builder: Box<dyn BandwidthEstimatorBuilder + Send + Sync>,
+    on_new_peer_connection: Option<OnNewPeerConnectionHdlrFn>,
+    padding: bool,
+}
+
+impl CongestionControllerBuilder {
+    /// with_on_new_peer_connection sets a handler which is handed the BandwidthEstimator
+    /// of every new PeerConnection, so the application can follow its target bitrate.
+    pub fn with_on_new_peer_connection(
+        mut self,
+        f: OnNewPeerConnectionHdlrFn,
+    ) -> CongestionControllerBuilder {
+        self.on_new_peer_connection = Some(f);
+        self
+    }
+
+    /// with_padding makes the pacer fill the part of the target bitrate the streams
+    /// don't use with padding packets, so the estimator can ramp up while the
+    /// streams are sent below the available bandwidth.
+    pub fn with_padding(mut self, padding: bool) -> CongestionControllerBuilder {
+        self.padding = padding;
+        self
+    }
+}
+
+impl InterceptorBuilder for CongestionControllerBuilder {
+    fn build(&self, id: &str) -> Result<Arc<dyn Interceptor + Send + Sync>> {
+        let estimator = self.estimator_builder.build()?;
+        if let Some(f) = &self.on_new_peer_connection {
+            f(id, Arc::clone(&estimator));
+        }
+
+        Ok(Arc::new(CongestionController {
+            pacer: Arc::new(Pacer::new(Arc::clone(&estimator), self.padding)),
+            estimator,
+        }))
+    }
+}
+
+/// CongestionControllerRTCPReader hands the RTCP read from the remote peer to the estimator
+struct CongestionControllerRTCPReader {
+    parent_rtcp_reader: Arc<dyn RTCPReader + Send + Sync>,
+    estimator: Arc<dyn BandwidthEstimator + Send + Sync>,
+    pacer: Arc<Pacer>,
+}
+
+#[async_trait]
+impl RTCPReader for CongestionControllerRTCPReader {
+    async fn read(&self, buf: &mut [u8], a: &Attributes) -> Result<(usize, Attributes)> {
+        let (n, attr) = self.parent_rtcp_reader.read(buf, a).await?;
+
+        let pkts = unmarshal_rtcp(&buf[..n])?;
+
+        // the nacks of the remote peer hold the sequence numbers the packets were sent
+        // with, the interceptors reading after this one know them by their original ones
+        let n = match self.pacer.translate_nacks(&pkts).await? {
+            Some(raw) => {
+                if raw.len() > buf.len() {
+                    return Err(Error::new("buffer is too short".to_owned()).into());
+                }
+                buf[..raw.len()].copy_from_slice(&raw);
+                raw.len()
+            }
+            None => n,
+        };
+
+        if let Err(err) = self.estimator.write_rtcp(&pkts, &attr).await {
+            log::warn!("failed feeding rtcp to the bandwidth estimator: {}", err);
+        }
+
+        Ok((n, attr))
+    }
+}
+
+/// CongestionController interceptor runs a BandwidthEstimator for the local streams
+/// of the PeerConnection, and paces the packets of the streams to its target bitrate.
+/// The packets are stamped with their transport wide sequence number as they leave
+/// the pacer, so it must not be registered along with the TWCC Sender, which would
+/// stamp them a second time: use configure_twcc_receiver_only rather than
+/// configure_twcc with it.
+pub struct CongestionController {
+    estimator: Arc<dyn BandwidthEstimator + Send + Sync>,
+    pacer: Arc<Pacer>,
+}
+
+impl CongestionController {
+    /// builder returns a new CongestionControllerBuilder which builds the
+    /// estimators with the given BandwidthEstimatorBuilder.
+    pub fn builder(
+        estimator_builder: Box<dyn BandwidthEstimatorBuilder + Send + Sync>,
+    ) -> CongestionControllerBuilder {
+        CongestionControllerBuilder {
+            estimator_builder,
+            on_new_peer_connection: None,
+            padding: false,
+        }
+    }
+}
+
+#[async_trait]
+impl Interceptor for CongestionController {
+    async fn bind_rtcp_reader(
+        &self,
+        reader: Arc<dyn RTCPReader + Send + Sync>,
+    ) -> Arc<dyn RTCPReader + Send + Sync> {
+        Arc::new(CongestionControllerRTCPReader {
+            parent_rtcp_reader: reader,
+            estimator: Arc::clone(&self.estimator),
+            pacer: Arc::clone(&self.pacer),
+        })
+    }
+
+    /// bind_rtcp_writer starts the pacer
+    async fn bind_rtcp_writer(
+        &self,
+        writer: Arc<dyn RTCPWriter + Send + Sync>,
+    ) -> Arc<dyn RTCPWriter + Send + Sync> {
+        self.pacer.start().await;
+
+        writer
+    }
+
+    /// bind_local_stream paces the packets of the stream, the estimator sees them as they are sent
+    async fn bind_local_stream(
+        &self,
+        info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Arc<dyn RTPWriter + Send + Sync> {
+        let writer = self.estimator.add_stream(info, writer).await;
+        self.pacer.add_stream(info, writer).await
+    }
+
+    async fn unbind_local_stream(&self, info: &StreamInfo) {
+        self.pacer.remove_stream(info).await;
+    }
+
+    async fn bind_remote_stream(
+        &self,
+        _info: &StreamInfo,
+        reader: Arc<dyn RTPReader + Send + Sync>,
+    ) -> Arc<dyn RTPReader + Send + Sync> {
+        reader
+    }
+
+    async fn unbind_remote_stream(&self, _info: &StreamInfo) {}
+
+    async fn close(&self) -> Result<()> {
+        self.pacer.close().await;
+        self.estimator.close().await
+    }
+}
@@ -0,0 +1,305 @@
+mod pacer_stream;
+#[cfg(test)]
+mod pacer_test;
+
+use pacer_stream::PacerStream;
+
+use super::BandwidthEstimator;
+use crate::interceptor::nack::nack_pairs_from_sequence_numbers;
+use crate::interceptor::stream_info::StreamInfo;
+use crate::interceptor::twcc::transport_cc_extension_id;
+use crate::interceptor::*;
+use crate::media::rtp::{PayloadType, SSRC};
+
+use bytes::{Bytes, BytesMut};
+use rtcp::transport_feedbacks::transport_layer_nack::TransportLayerNack;
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+use tokio::time::Instant;
+use util::marshal::{Marshal, MarshalSize};
+
+/// PACING_INTERVAL is how often the pacer lets the queued packets go
+const PACING_INTERVAL: Duration = Duration::from_millis(5);
+/// PACING_FACTOR is how much faster than the target bitrate the packets are sent,
+/// so the pacer doesn't delay the streams sent at the target bitrate
+const PACING_FACTOR: f64 = 2.5;
+/// MAX_QUEUE_TIME is how long the queued packets may wait, the pacing rate is
+/// raised above the target bitrate to drain the queue in time
+const MAX_QUEUE_TIME: Duration = Duration::from_secs(2);
+
+/// Priority is the order the queued packets are sent in
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(super) enum Priority {
+    Audio = 0,
+    Retransmission = 1,
+    Video = 2,
+}
+
+struct QueuedPacket {
+    stream: Arc<PacerStream>,
+    pkt: rtp::packet::Packet,
+    attributes: Attributes,
+}
+
+#[derive(Default)]
+struct PacerQueues {
+    /// one queue per Priority
+    queues: [VecDeque<QueuedPacket>; 3],
+    size: usize,
+}
+
+impl PacerQueues {
+    fn push(&mut self, priority: Priority, q: QueuedPacket) {
+        self.size += q.pkt.marshal_size();
+        self.queues[priority as usize].push_back(q);
+    }
+
+    fn pop(&mut self) -> Option<QueuedPacket> {
+        let q = self.queues.iter_mut().find_map(|queue| queue.pop_front())?;
+        self.size -= q.pkt.marshal_size();
+        Some(q)
+    }
+}
+
+struct PacerInternal {
+    estimator: Arc<dyn BandwidthEstimator + Send + Sync>,
+    padding: bool,
+    queues: Mutex<PacerQueues>,
+    streams: Mutex<HashMap<SSRC, Arc<PacerStream>>>,
+}
+
+impl PacerInternal {
+    async fn pop(&self) -> Option<QueuedPacket> {
+        let mut queues = self.queues.lock().await;
+        queues.pop()
+    }
+
+    async fn queued_bytes(&self) -> usize {
+        let queues = self.queues.lock().await;
+        queues.size
+    }
+}
+
+/// PacerWriter queues the packets written to a local stream
+struct PacerWriter {
+    stream: Arc<PacerStream>,
+    internal: Arc<PacerInternal>,
+}
+
+#[async_trait]
+impl RTPWriter for PacerWriter {
+    async fn write(&self, pkt: &rtp::packet::Packet, a: &Attributes) -> Result<usize> {
+        let priority = if a.contains_key(&ATTR_RETRANSMISSION) {
+            Priority::Retransmission
+        } else {
+            self.stream.priority
+        };
+
+        let mut queues = self.internal.queues.lock().await;
+        queues.push(
+            priority,
+            QueuedPacket {
+                stream: Arc::clone(&self.stream),
+                pkt: pkt.clone(),
+                attributes: a.clone(),
+            },
+        );
+
+        Ok(pkt.payload.len())
+    }
+}
+
+/// Pacer is a leaky bucket sending the packets of the local streams at a pace
+/// following the target bitrate of the BandwidthEstimator, so the bursts of
+/// packets of the video keyframes don't overflow the queues of the network.
+/// The audio packets go first, then the retransmissions, then the video.
+///
+/// The pacer stamps the packets with their transport wide sequence number as
+/// it sends them, and when padding is enabled it fills the target bitrate
+/// unused by the streams with padding packets, so the estimator can probe for
+/// more bandwidth.
+pub(super) struct Pacer {
+    internal: Arc<PacerInternal>,
+
+    close_tx: Mutex<Option<mpsc::Sender<()>>>,
+    close_rx: Mutex<Option<mpsc::Receiver<()>>>,
+}
+
+impl Pacer {
+    pub(super) fn new(estimator: Arc<dyn BandwidthEstimator + Send + Sync>, padding: bool) -> Self {
+        let (close_tx, close_rx) = mpsc::channel(1);
+        Pacer {
+            internal: Arc::new(PacerInternal {
+                estimator,
+                padding,
+                queues: Mutex::new(PacerQueues::default()),
+                streams: Mutex::new(HashMap::new()),
+            }),
+            close_tx: Mutex::new(Some(close_tx)),
+            close_rx: Mutex::new(Some(close_rx)),
+        }
+    }
+
+    /// start starts sending the queued packets
+    pub(super) async fn start(&self) {
+        let close_rx = {
+            let mut close_rx = self.close_rx.lock().await;
+            close_rx.take()
+        };
+
+        if let Some(close_rx) = close_rx {
+            let internal = Arc::clone(&self.internal);
+            tokio::spawn(async move {
+                Pacer::run(internal, close_rx).await;
+            });
+        }
+    }
+
+    /// add_stream returns the writer queueing the packets of a local stream
+    pub(super) async fn add_stream(
+        &self,
+        info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Arc<dyn RTPWriter + Send + Sync> {
+        let stream = Arc::new(PacerStream::new(info, writer));
+        {
+            let mut streams = self.internal.streams.lock().await;
+            streams.insert(info.ssrc, Arc::clone(&stream));
+        }
+
+        Arc::new(PacerWriter {
+            stream,
+            internal: Arc::clone(&self.internal),
+        })
+    }
+
+    pub(super) async fn remove_stream(&self, info: &StreamInfo) {
+        let mut streams = self.internal.streams.lock().await;
+        streams.remove(&info.ssrc);
+    }
+
+    /// translate_nacks rewrites the nacks of a batch of rtcp packets with the original sequence
+    /// numbers of the packets, the remote peer only knows the ones they were sent with.
+    /// None is returned when there is nothing to translate.
+    pub(super) async fn translate_nacks(
+        &self,
+        pkts: &[Box<dyn rtcp::packet::Packet + Send + Sync>],
+    ) -> Result<Option<Bytes>> {
+        let mut translated = false;
+        let mut raw = BytesMut::new();
+        for p in pkts {
+            if let Some(nack) = p.as_any().downcast_ref::<TransportLayerNack>() {
+                if let Some(nack) = self.translate_nack(nack).await {
+                    raw.extend_from_slice(&nack.marshal()?);
+                    translated = true;
+                    continue;
+                }
+            }
+            raw.extend_from_slice(&p.marshal()?);
+        }
+
+        Ok(if translated { Some(raw.freeze()) } else { None })
+    }
+
+    async fn translate_nack(&self, nack: &TransportLayerNack) -> Option<TransportLayerNack> {
+        let stream = {
+            let streams = self.internal.streams.lock().await;
+            Arc::clone(streams.get(&nack.media_ssrc)?)
+        };
+
+        let seqs: Vec<u16> = nack
+            .nacks
+            .iter()
+            .flat_map(|pair| pair.packet_list())
+            .collect();
+        let seqs = stream.original_sequence_numbers(&seqs).await?;
+
+        Some(TransportLayerNack {
+            sender_ssrc: nack.sender_ssrc,
+            media_ssrc: nack.media_ssrc,
+            nacks: nack_pairs_from_sequence_numbers(&seqs),
+        })
+    }
+
+    pub(super) async fn close(&self) {
+        let mut close_tx = self.close_tx.lock().await;
+        close_tx.take();
+    }
+
+    async fn run(internal: Arc<PacerInternal>, mut close_rx: mpsc::Receiver<()>) {
+        let mut ticker = tokio::time::interval(PACING_INTERVAL);
+        let mut last_tick = Instant::now();
+        let mut media_budget = 0.0;
+        let mut padding_budget = 0.0;
+        let mut transport_sequence_number = 0u16;
+        let mut padding_stream: Option<Arc<PacerStream>> = None;
+        loop {
+            tokio::select! {
+                _ = close_rx.recv() => {
+                    return;
+                }
+                _ = ticker.tick() => {}
+            }
+
+            let now = Instant::now();
+            let elapsed = (now - last_tick).as_secs_f64();
+            last_tick = now;
+
+            let target_bitrate = internal.estimator.get_target_bitrate().await as f64;
+            let queued_bytes = internal.queued_bytes().await as f64;
+            let pacing_rate = (target_bitrate * PACING_FACTOR)
+                .max(queued_bytes * 8.0 / MAX_QUEUE_TIME.as_secs_f64());
+
+            // the budget doesn't build up while the queue is empty, or the next
+            // burst would go through unpaced
+            media_budget = (media_budget + pacing_rate / 8.0 * elapsed)
+                .min(pacing_rate / 8.0 * PACING_INTERVAL.as_secs_f64());
+            let mut sent = 0.0;
+            while media_budget > 0.0 {
+                let q = match internal.pop().await {
+                    Some(q) => q,
+                    None => break,
+                };
+
+                let size = q.pkt.marshal_size() as f64;
+                media_budget -= size;
+                sent += size;
+                if q.stream.priority == Priority::Video {
+                    padding_stream = Some(Arc::clone(&q.stream));
+                }
+                if let Err(err) = q
+                    .stream
+                    .send(q.pkt, &q.attributes, &mut transport_sequence_number)
+                    .await
+                {
+                    log::warn!("failed sending paced packet: {}", err);
+                }
+            }
+
+            if !internal.padding {
+                continue;
+            }
+
+            // the padding fills the part of the target bitrate the streams don't use
+            padding_budget = (padding_budget + target_bitrate / 8.0 * elapsed - sent)
+                .max(0.0)
+                .min(target_bitrate / 8.0 * PACING_INTERVAL.as_secs_f64());
+            if internal.queued_bytes().await > 0 {
+                continue;
+            }
+            if let Some(stream) = &padding_stream {
+                while padding_budget > 0.0 {
+                    match stream.send_padding(&mut transport_sequence_number).await {
+                        Ok(0) => break,
+                        Ok(size) => padding_budget -= size as f64,
+                        Err(err) => {
+                            log::warn!("failed sending padding: {}", err);
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
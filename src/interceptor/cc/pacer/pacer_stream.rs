@@ -0,0 +1,227 @@
+use super::*;
+
+/// SEQUENCE_HISTORY_SIZE is the number of rewritten sequence numbers remembered
+/// to translate the nacks and the retransmissions
+const SEQUENCE_HISTORY_SIZE: usize = 1 << 10;
+
+/// SequenceRewriter shifts the sequence numbers of a stream to make room for
+/// the padding packets sent on it
+pub(super) struct SequenceRewriter {
+    offset: u16,
+    last_sent: Option<u16>,
+    /// the original and rewritten sequence numbers of the last media packets,
+    /// indexed by original and by rewritten sequence number
+    by_original: Vec<Option<(u16, u16)>>,
+    by_rewritten: Vec<Option<(u16, u16)>>,
+}
+
+impl SequenceRewriter {
+    pub(super) fn new() -> Self {
+        SequenceRewriter {
+            offset: 0,
+            last_sent: None,
+            by_original: vec![None; SEQUENCE_HISTORY_SIZE],
+            by_rewritten: vec![None; SEQUENCE_HISTORY_SIZE],
+        }
+    }
+
+    /// rewrite returns the sequence number a media packet is sent with. A retransmission
+    /// is sent with the sequence number of the packet it retransmits.
+    pub(super) fn rewrite(&mut self, seq: u16, retransmission: bool) -> u16 {
+        if retransmission {
+            return match self.by_original[seq as usize % SEQUENCE_HISTORY_SIZE] {
+                Some((original, rewritten)) if original == seq => rewritten,
+                _ => seq.wrapping_add(self.offset),
+            };
+        }
+
+        let rewritten = seq.wrapping_add(self.offset);
+        self.by_original[seq as usize % SEQUENCE_HISTORY_SIZE] = Some((seq, rewritten));
+        self.by_rewritten[rewritten as usize % SEQUENCE_HISTORY_SIZE] = Some((seq, rewritten));
+        self.last_sent = Some(rewritten);
+        rewritten
+    }
+
+    /// next_padding returns the sequence number of a new padding packet, if a
+    /// media packet was already sent
+    pub(super) fn next_padding(&mut self) -> Option<u16> {
+        let seq = self.last_sent?.wrapping_add(1);
+        self.offset = self.offset.wrapping_add(1);
+        self.last_sent = Some(seq);
+        Some(seq)
+    }
+
+    /// original returns the original sequence number of a media packet sent with the
+    /// rewritten one, None for padding packets and forgotten packets
+    pub(super) fn original(&self, rewritten: u16) -> Option<u16> {
+        match self.by_rewritten[rewritten as usize % SEQUENCE_HISTORY_SIZE] {
+            Some((original, r)) if r == rewritten => Some(original),
+            _ => None,
+        }
+    }
+
+    /// is_rewriting tells if the stream is sent with other sequence numbers than its original ones
+    pub(super) fn is_rewriting(&self) -> bool {
+        self.offset != 0
+    }
+}
+
+struct PacerStreamInternal {
+    rewriter: SequenceRewriter,
+    last_timestamp: u32,
+}
+
+/// PacerStream sends the packets of a local stream once the pacer lets them go
+pub(super) struct PacerStream {
+    pub(super) ssrc: SSRC,
+    pub(super) priority: Priority,
+    payload_type: PayloadType,
+    hdr_ext_id: Option<u8>,
+    next_rtp_writer: Arc<dyn RTPWriter + Send + Sync>,
+    internal: Mutex<PacerStreamInternal>,
+}
+
+impl PacerStream {
+    pub(super) fn new(info: &StreamInfo, writer: Arc<dyn RTPWriter + Send + Sync>) -> Self {
+        let priority = if info.mime_type.to_lowercase().starts_with("audio/") {
+            Priority::Audio
+        } else {
+            Priority::Video
+        };
+
+        PacerStream {
+            ssrc: info.ssrc,
+            priority,
+            payload_type: info.payload_type,
+            hdr_ext_id: transport_cc_extension_id(info),
+            next_rtp_writer: writer,
+            internal: Mutex::new(PacerStreamInternal {
+                rewriter: SequenceRewriter::new(),
+                last_timestamp: 0,
+            }),
+        }
+    }
+
+    /// send writes a media packet, stamped with the next transport wide sequence number
+    pub(super) async fn send(
+        &self,
+        mut pkt: rtp::packet::Packet,
+        a: &Attributes,
+        transport_sequence_number: &mut u16,
+    ) -> Result<usize> {
+        {
+            let mut internal = self.internal.lock().await;
+            let retransmission = a.contains_key(&ATTR_RETRANSMISSION);
+            if pkt.header.ssrc != self.ssrc && pkt.payload.len() >= 2 {
+                // a RTX packet keeps its own sequence number, the one of the packet
+                // it retransmits leads its payload
+                let original = u16::from_be_bytes([pkt.payload[0], pkt.payload[1]]);
+                let rewritten = internal.rewriter.rewrite(original, true);
+                let mut payload = pkt.payload.to_vec();
+                payload[..2].copy_from_slice(&rewritten.to_be_bytes());
+                pkt.payload = Bytes::from(payload);
+            } else {
+                pkt.header.sequence_number = internal
+                    .rewriter
+                    .rewrite(pkt.header.sequence_number, retransmission);
+            }
+            if !retransmission {
+                internal.last_timestamp = pkt.header.timestamp;
+            }
+        }
+        self.stamp(&mut pkt, transport_sequence_number)?;
+
+        self.next_rtp_writer.write(&pkt, a).await
+    }
+
+    /// send_padding writes a packet holding nothing but padding, and returns its size
+    /// on the wire. The padding itself is added when the packet is marshaled.
+    pub(super) async fn send_padding(&self, transport_sequence_number: &mut u16) -> Result<usize> {
+        let (sequence_number, timestamp) = {
+            let mut internal = self.internal.lock().await;
+            match internal.rewriter.next_padding() {
+                Some(sequence_number) => (sequence_number, internal.last_timestamp),
+                None => return Ok(0),
+            }
+        };
+
+        let mut pkt = rtp::packet::Packet {
+            header: rtp::header::Header {
+                version: 2,
+                padding: true,
+                payload_type: self.payload_type,
+                sequence_number,
+                timestamp,
+                ssrc: self.ssrc,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        self.stamp(&mut pkt, transport_sequence_number)?;
+
+        self.next_rtp_writer.write(&pkt, &Attributes::new()).await?;
+        Ok(pkt.marshal_size())
+    }
+
+    /// original_sequence_numbers translates the sequence numbers the packets were sent with
+    /// to their original ones, None is returned when the stream isn't rewritten
+    pub(super) async fn original_sequence_numbers(&self, seqs: &[u16]) -> Option<Vec<u16>> {
+        let internal = self.internal.lock().await;
+        if !internal.rewriter.is_rewriting() {
+            return None;
+        }
+
+        Some(
+            seqs.iter()
+                .filter_map(|seq| internal.rewriter.original(*seq))
+                .collect(),
+        )
+    }
+
+    fn stamp(
+        &self,
+        pkt: &mut rtp::packet::Packet,
+        transport_sequence_number: &mut u16,
+    ) -> Result<()> {
+        if let Some(hdr_ext_id) = self.hdr_ext_id {
+            pkt.header.set_extension(
+                hdr_ext_id,
+                Bytes::copy_from_slice(&transport_sequence_number.to_be_bytes()),
+            )?;
+            *transport_sequence_number = transport_sequence_number.wrapping_add(1);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sequence_rewriter() {
+        let mut r = SequenceRewriter::new();
+        assert_eq!(r.next_padding(), None);
+
+        assert_eq!(r.rewrite(65534, false), 65534);
+        assert!(!r.is_rewriting());
+        assert_eq!(r.next_padding(), Some(65535));
+        assert_eq!(r.next_padding(), Some(0));
+        assert!(r.is_rewriting());
+
+        // the media packets following the padding are shifted
+        assert_eq!(r.rewrite(65535, false), 1);
+        assert_eq!(r.rewrite(0, false), 2);
+
+        // a retransmission is sent again with the same sequence number
+        assert_eq!(r.rewrite(65534, true), 65534);
+        assert_eq!(r.rewrite(65535, true), 1);
+
+        assert_eq!(r.original(65534), Some(65534));
+        assert_eq!(r.original(65535), None);
+        assert_eq!(r.original(0), None);
+        assert_eq!(r.original(1), Some(65535));
+        assert_eq!(r.original(2), Some(0));
+        assert_eq!(r.original(3), None);
+    }
+}
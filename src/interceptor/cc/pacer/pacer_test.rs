@@ -0,0 +1,260 @@
+use super::super::OnTargetBitrateChangeHdlrFn;
+use super::*;
+use crate::media::rtp::rtp_codec::RTPHeaderExtensionParameter;
+
+use util::marshal::Unmarshal;
+
+/// FixedEstimator is a BandwidthEstimator with a constant target bitrate
+struct FixedEstimator {
+    bitrate: u64,
+}
+
+#[async_trait]
+impl BandwidthEstimator for FixedEstimator {
+    async fn add_stream(
+        &self,
+        _info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Arc<dyn RTPWriter + Send + Sync> {
+        writer
+    }
+
+    async fn write_rtcp(
+        &self,
+        _pkts: &[Box<dyn rtcp::packet::Packet + Send + Sync>],
+        _attributes: &Attributes,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    async fn get_target_bitrate(&self) -> u64 {
+        self.bitrate
+    }
+
+    async fn on_target_bitrate_change(&self, _f: OnTargetBitrateChangeHdlrFn) {}
+
+    async fn close(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// PacketCollector collects the packets sent by the pacer
+struct PacketCollector {
+    tx: mpsc::Sender<rtp::packet::Packet>,
+}
+
+#[async_trait]
+impl RTPWriter for PacketCollector {
+    async fn write(&self, pkt: &rtp::packet::Packet, _attributes: &Attributes) -> Result<usize> {
+        let _ = self.tx.send(pkt.clone()).await;
+        Ok(pkt.payload.len())
+    }
+}
+
+fn new_pacer(bitrate: u64, padding: bool) -> Pacer {
+    Pacer::new(Arc::new(FixedEstimator { bitrate }), padding)
+}
+
+fn new_packet(ssrc: SSRC, sequence_number: u16, size: usize) -> rtp::packet::Packet {
+    rtp::packet::Packet {
+        header: rtp::header::Header {
+            version: 2,
+            ssrc,
+            sequence_number,
+            timestamp: 1234,
+            ..Default::default()
+        },
+        payload: Bytes::from(vec![0u8; size]),
+    }
+}
+
+async fn next_packet(rx: &mut mpsc::Receiver<rtp::packet::Packet>) -> rtp::packet::Packet {
+    tokio::time::timeout(Duration::from_secs(1), rx.recv())
+        .await
+        .expect("a packet sent in time")
+        .expect("a packet")
+}
+
+#[tokio::test]
+async fn test_pacer_smooths_bursts() -> Result<()> {
+    // 400kbps are paced at 1Mbps, 125 bytes every millisecond
+    let pacer = new_pacer(400_000, false);
+    pacer.start().await;
+
+    let (tx, mut rx) = mpsc::channel(100);
+    let writer = pacer
+        .add_stream(
+            &StreamInfo {
+                ssrc: 1,
+                mime_type: "video/VP8".to_owned(),
+                ..Default::default()
+            },
+            Arc::new(PacketCollector { tx }),
+        )
+        .await;
+
+    let a = Attributes::new();
+    let start = Instant::now();
+    for i in 0..20 {
+        assert_eq!(writer.write(&new_packet(1, i, 1000), &a).await?, 1000);
+    }
+
+    for i in 0..20 {
+        let p = next_packet(&mut rx).await;
+        assert_eq!(p.header.sequence_number, i);
+    }
+    // 20000 bytes take 160ms to go through
+    let elapsed = Instant::now() - start;
+    assert!(
+        elapsed >= Duration::from_millis(120),
+        "the burst went through in {:?}",
+        elapsed
+    );
+
+    pacer.close().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_pacer_priority() -> Result<()> {
+    let pacer = new_pacer(400_000, false);
+    pacer.start().await;
+
+    let (tx, mut rx) = mpsc::channel(100);
+    let video = pacer
+        .add_stream(
+            &StreamInfo {
+                ssrc: 1,
+                mime_type: "video/VP8".to_owned(),
+                ..Default::default()
+            },
+            Arc::new(PacketCollector { tx: tx.clone() }),
+        )
+        .await;
+    let audio = pacer
+        .add_stream(
+            &StreamInfo {
+                ssrc: 2,
+                mime_type: "audio/opus".to_owned(),
+                ..Default::default()
+            },
+            Arc::new(PacketCollector { tx }),
+        )
+        .await;
+
+    // a keyframe is queued, then a retransmission and an audio packet
+    let a = Attributes::new();
+    for i in 0..20 {
+        video.write(&new_packet(1, i, 1000), &a).await?;
+    }
+    let mut rtx = Attributes::new();
+    rtx.insert(ATTR_RETRANSMISSION, 1);
+    video.write(&new_packet(1, 100, 1000), &rtx).await?;
+    audio.write(&new_packet(2, 0, 100), &a).await?;
+
+    let mut sent = vec![];
+    for _ in 0..22 {
+        let p = next_packet(&mut rx).await;
+        sent.push((p.header.ssrc, p.header.sequence_number));
+    }
+    let audio_pos = sent
+        .iter()
+        .position(|p| *p == (2, 0))
+        .expect("the audio packet");
+    let rtx_pos = sent
+        .iter()
+        .position(|p| *p == (1, 100))
+        .expect("the retransmission");
+    assert!(audio_pos < rtx_pos, "{:?}", sent);
+    assert!(rtx_pos < 5, "{:?}", sent);
+
+    pacer.close().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_pacer_padding() -> Result<()> {
+    let pacer = new_pacer(400_000, true);
+    pacer.start().await;
+
+    let (tx, mut rx) = mpsc::channel(1000);
+    let writer = pacer
+        .add_stream(
+            &StreamInfo {
+                ssrc: 1,
+                mime_type: "video/VP8".to_owned(),
+                rtp_header_extensions: vec![RTPHeaderExtensionParameter {
+                    uri: sdp::extmap::TRANSPORT_CC_URI.to_owned(),
+                    id: 1,
+                }],
+                ..Default::default()
+            },
+            Arc::new(PacketCollector { tx }),
+        )
+        .await;
+    let transport_cc_seq = |pkt: &rtp::packet::Packet| {
+        pkt.header
+            .get_extension(1)
+            .map(|ext| u16::from_be_bytes([ext[0], ext[1]]))
+    };
+
+    let a = Attributes::new();
+    writer.write(&new_packet(1, 10, 100), &a).await?;
+    let p = next_packet(&mut rx).await;
+    assert_eq!(p.header.sequence_number, 10);
+    assert_eq!(transport_cc_seq(&p), Some(0));
+
+    // the unused bitrate is filled with padding packets following the stream
+    for i in 1..=3u16 {
+        let p = next_packet(&mut rx).await;
+        assert!(p.header.padding);
+        assert_eq!(p.header.ssrc, 1);
+        assert_eq!(p.header.timestamp, 1234);
+        assert_eq!(p.header.sequence_number, 10 + i);
+        assert_eq!(transport_cc_seq(&p), Some(i));
+        // the padding is added when the packet is marshaled, it is all the packet holds
+        assert!(p.payload.is_empty());
+        let raw = p.marshal()?;
+        assert_eq!(raw.len(), p.marshal_size());
+        let unmarshaled = rtp::packet::Packet::unmarshal(&mut &raw[..])?;
+        assert!(unmarshaled.payload.is_empty());
+    }
+
+    // the next media packet is sent after the padding
+    writer.write(&new_packet(1, 11, 100), &a).await?;
+    let mut last_seq = 13;
+    let p = loop {
+        let p = next_packet(&mut rx).await;
+        if !p.header.padding {
+            break p;
+        }
+        last_seq = p.header.sequence_number;
+    };
+    assert_eq!(p.header.sequence_number, last_seq + 1);
+
+    // the nacks of the remote peer are translated back to the original sequence numbers
+    let nack = TransportLayerNack {
+        sender_ssrc: 2,
+        media_ssrc: 1,
+        nacks: nack_pairs_from_sequence_numbers(&[10, 11, p.header.sequence_number]),
+    };
+    let pkts: Vec<Box<dyn rtcp::packet::Packet + Send + Sync>> = vec![Box::new(nack)];
+    let raw = pacer
+        .translate_nacks(&pkts)
+        .await?
+        .expect("the nack translated");
+    let pkts = unmarshal_rtcp(&raw)?;
+    let nack = pkts[0]
+        .as_any()
+        .downcast_ref::<TransportLayerNack>()
+        .expect("a nack");
+    let seqs: Vec<u16> = nack
+        .nacks
+        .iter()
+        .flat_map(|pair| pair.packet_list())
+        .collect();
+    assert_eq!(seqs, vec![10, 11]);
+
+    pacer.close().await;
+    Ok(())
+}
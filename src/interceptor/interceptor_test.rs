@@ -0,0 +1,320 @@
+use super::chain::Chain;
+use super::noop::NoOp;
+use super::registry::Registry;
+use super::*;
+use crate::api::ApiBuilder;
+use crate::error::Error;
+use crate::media::rtp::SSRC;
+use crate::peer::configuration::Configuration;
+
+use tokio::sync::Mutex;
+
+/// RecordingInterceptor logs its name for every packet which goes through the
+/// writers it binds, and counts the streams bound to it
+struct RecordingInterceptor {
+    name: &'static str,
+    log: Arc<Mutex<Vec<&'static str>>>,
+    local_streams: Mutex<Vec<SSRC>>,
+    fail_to_close: bool,
+}
+
+impl RecordingInterceptor {
+    fn new(name: &'static str, log: &Arc<Mutex<Vec<&'static str>>>) -> Self {
+        RecordingInterceptor {
+            name,
+            log: Arc::clone(log),
+            local_streams: Mutex::new(vec![]),
+            fail_to_close: false,
+        }
+    }
+}
+
+struct RecordingRTPWriter {
+    name: &'static str,
+    log: Arc<Mutex<Vec<&'static str>>>,
+    next: Arc<dyn RTPWriter + Send + Sync>,
+}
+
+#[async_trait]
+impl RTPWriter for RecordingRTPWriter {
+    async fn write(&self, pkt: &rtp::packet::Packet, attributes: &Attributes) -> Result<usize> {
+        {
+            let mut log = self.log.lock().await;
+            log.push(self.name);
+        }
+        self.next.write(pkt, attributes).await
+    }
+}
+
+struct RecordingRTCPWriter {
+    name: &'static str,
+    log: Arc<Mutex<Vec<&'static str>>>,
+    next: Arc<dyn RTCPWriter + Send + Sync>,
+}
+
+#[async_trait]
+impl RTCPWriter for RecordingRTCPWriter {
+    async fn write(
+        &self,
+        pkts: &[Box<dyn rtcp::packet::Packet + Send + Sync>],
+        attributes: &Attributes,
+    ) -> Result<usize> {
+        {
+            let mut log = self.log.lock().await;
+            log.push(self.name);
+        }
+        self.next.write(pkts, attributes).await
+    }
+}
+
+#[async_trait]
+impl Interceptor for RecordingInterceptor {
+    async fn bind_rtcp_reader(
+        &self,
+        reader: Arc<dyn RTCPReader + Send + Sync>,
+    ) -> Arc<dyn RTCPReader + Send + Sync> {
+        reader
+    }
+
+    async fn bind_rtcp_writer(
+        &self,
+        writer: Arc<dyn RTCPWriter + Send + Sync>,
+    ) -> Arc<dyn RTCPWriter + Send + Sync> {
+        Arc::new(RecordingRTCPWriter {
+            name: self.name,
+            log: Arc::clone(&self.log),
+            next: writer,
+        })
+    }
+
+    async fn bind_local_stream(
+        &self,
+        info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Arc<dyn RTPWriter + Send + Sync> {
+        {
+            let mut local_streams = self.local_streams.lock().await;
+            local_streams.push(info.ssrc);
+        }
+        Arc::new(RecordingRTPWriter {
+            name: self.name,
+            log: Arc::clone(&self.log),
+            next: writer,
+        })
+    }
+
+    async fn unbind_local_stream(&self, info: &StreamInfo) {
+        let mut local_streams = self.local_streams.lock().await;
+        local_streams.retain(|ssrc| *ssrc != info.ssrc);
+    }
+
+    async fn bind_remote_stream(
+        &self,
+        _info: &StreamInfo,
+        reader: Arc<dyn RTPReader + Send + Sync>,
+    ) -> Arc<dyn RTPReader + Send + Sync> {
+        reader
+    }
+
+    async fn unbind_remote_stream(&self, _info: &StreamInfo) {}
+
+    async fn close(&self) -> Result<()> {
+        if self.fail_to_close {
+            Err(Error::new(format!("{} failed to close", self.name)).into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+struct RecordingInterceptorBuilder {
+    name: &'static str,
+    log: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl InterceptorBuilder for RecordingInterceptorBuilder {
+    fn build(&self, _id: &str) -> Result<Arc<dyn Interceptor + Send + Sync>> {
+        Ok(Arc::new(RecordingInterceptor::new(self.name, &self.log)))
+    }
+}
+
+/// CountingRTPWriter is the end of the writer chains of the tests
+#[derive(Default)]
+struct CountingRTPWriter {
+    count: Mutex<usize>,
+}
+
+#[async_trait]
+impl RTPWriter for CountingRTPWriter {
+    async fn write(&self, pkt: &rtp::packet::Packet, _attributes: &Attributes) -> Result<usize> {
+        let mut count = self.count.lock().await;
+        *count += 1;
+        Ok(pkt.payload.len())
+    }
+}
+
+#[tokio::test]
+async fn test_chain_binds_in_order() -> Result<()> {
+    let log = Arc::new(Mutex::new(vec![]));
+    let first = Arc::new(RecordingInterceptor::new("first", &log));
+    let second = Arc::new(RecordingInterceptor::new("second", &log));
+
+    let mut chain = Chain::new(vec![Arc::clone(&first) as _]);
+    chain.add(Arc::clone(&second) as _);
+
+    let info = StreamInfo {
+        ssrc: 1234,
+        ..Default::default()
+    };
+    let counting_writer = Arc::new(CountingRTPWriter::default());
+    let writer = chain
+        .bind_local_stream(&info, Arc::clone(&counting_writer) as _)
+        .await;
+    assert_eq!(*first.local_streams.lock().await, vec![1234]);
+    assert_eq!(*second.local_streams.lock().await, vec![1234]);
+
+    // the interceptor bound last wraps the others, so it sees the packets first
+    let pkt = rtp::packet::Packet {
+        payload: bytes::Bytes::from_static(&[0x01, 0x02]),
+        ..Default::default()
+    };
+    assert_eq!(writer.write(&pkt, &Attributes::new()).await?, 2);
+    assert_eq!(*log.lock().await, vec!["second", "first"]);
+    assert_eq!(*counting_writer.count.lock().await, 1);
+
+    chain.unbind_local_stream(&info).await;
+    assert!(first.local_streams.lock().await.is_empty());
+    assert!(second.local_streams.lock().await.is_empty());
+
+    chain.close().await
+}
+
+#[tokio::test]
+async fn test_chain_close_all() -> Result<()> {
+    let log = Arc::new(Mutex::new(vec![]));
+    let mut failing = RecordingInterceptor::new("failing", &log);
+    failing.fail_to_close = true;
+    let failing = Arc::new(failing);
+    let other = Arc::new(RecordingInterceptor::new("other", &log));
+
+    // all the interceptors are closed, even if one of them fails
+    let chain = Chain::new(vec![failing as _, other as _]);
+    if let Err(err) = chain.close().await {
+        assert!(err.to_string().contains("failing failed to close"));
+    } else {
+        panic!("expected error, but got ok");
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_registry_build() -> Result<()> {
+    // an empty registry builds an interceptor which doesn't touch the packets
+    let registry = Registry::new();
+    let interceptor = registry.build("")?;
+    let counting_writer = Arc::new(CountingRTPWriter::default());
+    let writer = interceptor
+        .bind_local_stream(&StreamInfo::default(), Arc::clone(&counting_writer) as _)
+        .await;
+    writer
+        .write(&rtp::packet::Packet::default(), &Attributes::new())
+        .await?;
+    assert_eq!(*counting_writer.count.lock().await, 1);
+
+    let log = Arc::new(Mutex::new(vec![]));
+    let mut registry = Registry::new();
+    registry.add(Box::new(RecordingInterceptorBuilder {
+        name: "first",
+        log: Arc::clone(&log),
+    }));
+    registry.add(Box::new(RecordingInterceptorBuilder {
+        name: "second",
+        log: Arc::clone(&log),
+    }));
+
+    let interceptor = registry.build("")?;
+    let writer = interceptor
+        .bind_local_stream(&StreamInfo::default(), Arc::new(NoOpRTPWriter) as _)
+        .await;
+    writer
+        .write(&rtp::packet::Packet::default(), &Attributes::new())
+        .await?;
+    assert_eq!(*log.lock().await, vec!["second", "first"]);
+
+    interceptor.close().await
+}
+
+struct NoOpRTPWriter;
+
+#[async_trait]
+impl RTPWriter for NoOpRTPWriter {
+    async fn write(&self, _pkt: &rtp::packet::Packet, _attributes: &Attributes) -> Result<usize> {
+        Ok(0)
+    }
+}
+
+#[tokio::test]
+async fn test_peer_connection_write_rtcp_through_interceptors() -> Result<()> {
+    let log = Arc::new(Mutex::new(vec![]));
+    let mut registry = Registry::new();
+    registry.add(Box::new(RecordingInterceptorBuilder {
+        name: "rtcp",
+        log: Arc::clone(&log),
+    }));
+
+    let api = ApiBuilder::new()
+        .with_interceptor_registry(registry)
+        .build();
+    let pc = api.new_peer_connection(Configuration::default()).await?;
+
+    // nothing is sent before the DTLS transport is started
+    let pkts: Vec<Box<dyn rtcp::packet::Packet + Send + Sync>> = vec![Box::new(
+        rtcp::payload_feedbacks::picture_loss_indication::PictureLossIndication {
+            sender_ssrc: 0,
+            media_ssrc: 1234,
+        },
+    )];
+    assert_eq!(pc.write_rtcp(&pkts).await?, 0);
+    assert_eq!(*log.lock().await, vec!["rtcp"]);
+
+    pc.close().await
+}
+
+#[test]
+fn test_noop_is_an_interceptor() {
+    let _interceptor: Arc<dyn Interceptor + Send + Sync> = Arc::new(NoOp);
+}
+
+#[test]
+fn test_unmarshal_rtcp_flattens_compound_packets() -> Result<()> {
+    use rtcp::payload_feedbacks::picture_loss_indication::PictureLossIndication;
+    use rtcp::receiver_report::ReceiverReport;
+    use util::marshal::Marshal;
+
+    let rr = ReceiverReport {
+        ssrc: 1,
+        ..Default::default()
+    };
+    let pli = PictureLossIndication {
+        sender_ssrc: 1,
+        media_ssrc: 1234,
+    };
+    let mut raw = rr.marshal()?.to_vec();
+    raw.extend_from_slice(&pli.marshal()?);
+
+    let pkts = unmarshal_rtcp(&raw)?;
+    assert_eq!(pkts.len(), 2);
+    assert_eq!(pkts[0].as_any().downcast_ref::<ReceiverReport>(), Some(&rr));
+    assert_eq!(
+        pkts[1].as_any().downcast_ref::<PictureLossIndication>(),
+        Some(&pli)
+    );
+
+    // a single packet is returned alone
+    assert_eq!(unmarshal_rtcp(&pli.marshal()?)?.len(), 1);
+    assert!(unmarshal_rtcp(&[]).is_err());
+    assert!(unmarshal_rtcp(&raw[..raw.len() - 1]).is_err());
+
+    Ok(())
+}
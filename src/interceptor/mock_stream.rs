@@ -0,0 +1,264 @@
+use super::report::FnTimeGen;
+use super::stream_info::StreamInfo;
+use super::*;
+use crate::error::Error;
+
+use bytes::{Bytes, BytesMut};
+use std::sync::Mutex as SyncMutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{mpsc, Mutex};
+use util::marshal::{Marshal, Unmarshal};
+
+type RTCPPackets = Vec<Box<dyn rtcp::packet::Packet + Send + Sync>>;
+
+/// MockStream binds an interceptor to a fake local and remote stream, and to
+/// the fake RTCP of the PeerConnection, so the tests can feed and collect the
+/// packets going through the interceptor.
+pub(crate) struct MockStream {
+    interceptor: Arc<dyn Interceptor + Send + Sync>,
+
+    rtcp_writer: Mutex<Option<Arc<dyn RTCPWriter + Send + Sync>>>,
+    rtp_writer: Mutex<Option<Arc<dyn RTPWriter + Send + Sync>>>,
+
+    rtcp_out_modified_tx: mpsc::Sender<Bytes>,
+    rtp_out_modified_tx: mpsc::Sender<rtp::packet::Packet>,
+    rtcp_in_rx: Mutex<mpsc::Receiver<RTCPPackets>>,
+    rtp_in_rx: Mutex<mpsc::Receiver<rtp::packet::Packet>>,
+
+    rtcp_out_modified_rx: Mutex<mpsc::Receiver<Bytes>>,
+    rtp_out_modified_rx: Mutex<mpsc::Receiver<rtp::packet::Packet>>,
+    rtcp_in_tx: Mutex<Option<mpsc::Sender<RTCPPackets>>>,
+    rtp_in_tx: Mutex<Option<mpsc::Sender<rtp::packet::Packet>>>,
+
+    rtp_in_modified_rx: Mutex<mpsc::Receiver<rtp::packet::Packet>>,
+}
+
+impl MockStream {
+    pub(crate) async fn new(
+        info: &StreamInfo,
+        interceptor: Arc<dyn Interceptor + Send + Sync>,
+    ) -> Arc<Self> {
+        let (rtcp_in_tx, rtcp_in_rx) = mpsc::channel(1000);
+        let (rtp_in_tx, rtp_in_rx) = mpsc::channel(1000);
+        let (rtcp_out_modified_tx, rtcp_out_modified_rx) = mpsc::channel(1000);
+        let (rtp_out_modified_tx, rtp_out_modified_rx) = mpsc::channel(1000);
+        let (rtp_in_modified_tx, rtp_in_modified_rx) = mpsc::channel(1000);
+
+        let stream = Arc::new(MockStream {
+            interceptor: Arc::clone(&interceptor),
+
+            rtcp_writer: Mutex::new(None),
+            rtp_writer: Mutex::new(None),
+
+            rtcp_out_modified_tx,
+            rtp_out_modified_tx,
+            rtcp_in_rx: Mutex::new(rtcp_in_rx),
+            rtp_in_rx: Mutex::new(rtp_in_rx),
+
+            rtcp_out_modified_rx: Mutex::new(rtcp_out_modified_rx),
+            rtp_out_modified_rx: Mutex::new(rtp_out_modified_rx),
+            rtcp_in_tx: Mutex::new(Some(rtcp_in_tx)),
+            rtp_in_tx: Mutex::new(Some(rtp_in_tx)),
+
+            rtp_in_modified_rx: Mutex::new(rtp_in_modified_rx),
+        });
+
+        let rtcp_writer = interceptor
+            .bind_rtcp_writer(Arc::clone(&stream) as Arc<dyn RTCPWriter + Send + Sync>)
+            .await;
+        {
+            let mut w = stream.rtcp_writer.lock().await;
+            *w = Some(rtcp_writer);
+        }
+        let rtp_writer = interceptor
+            .bind_local_stream(
+                info,
+                Arc::clone(&stream) as Arc<dyn RTPWriter + Send + Sync>,
+            )
+            .await;
+        {
+            let mut w = stream.rtp_writer.lock().await;
+            *w = Some(rtp_writer);
+        }
+
+        // the RTCP is read the same way the RTPSender and RTPReceiver do
+        let rtcp_reader = interceptor
+            .bind_rtcp_reader(Arc::clone(&stream) as Arc<dyn RTCPReader + Send + Sync>)
+            .await;
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; 1500];
+            let a = Attributes::new();
+            while rtcp_reader.read(&mut buf, &a).await.is_ok() {}
+        });
+
+        let rtp_reader = interceptor
+            .bind_remote_stream(
+                info,
+                Arc::clone(&stream) as Arc<dyn RTPReader + Send + Sync>,
+            )
+            .await;
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; 1500];
+            let a = Attributes::new();
+            while let Ok((n, _)) = rtp_reader.read(&mut buf, &a).await {
+                let pkt = match rtp::packet::Packet::unmarshal(&mut &buf[..n]) {
+                    Ok(pkt) => pkt,
+                    Err(_) => break,
+                };
+                if rtp_in_modified_tx.send(pkt).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        stream
+    }
+
+    /// write_rtcp writes a batch of rtcp packets through the interceptor
+    pub(crate) async fn write_rtcp(
+        &self,
+        pkts: &[Box<dyn rtcp::packet::Packet + Send + Sync>],
+    ) -> Result<usize> {
+        let rtcp_writer = self.rtcp_writer.lock().await;
+        if let Some(writer) = &*rtcp_writer {
+            writer.write(pkts, &Attributes::new()).await
+        } else {
+            Err(Error::new("rtcp_writer isn't bound".to_owned()).into())
+        }
+    }
+
+    /// write_rtp writes a rtp packet to the local stream, through the interceptor
+    pub(crate) async fn write_rtp(&self, pkt: &rtp::packet::Packet) -> Result<usize> {
+        let rtp_writer = self.rtp_writer.lock().await;
+        if let Some(writer) = &*rtp_writer {
+            writer.write(pkt, &Attributes::new()).await
+        } else {
+            Err(Error::new("rtp_writer isn't bound".to_owned()).into())
+        }
+    }
+
+    /// receive_rtcp schedules a batch of rtcp packets to be read by the interceptor
+    pub(crate) async fn receive_rtcp(&self, pkts: RTCPPackets) {
+        let rtcp_in_tx = self.rtcp_in_tx.lock().await;
+        if let Some(tx) = &*rtcp_in_tx {
+            let _ = tx.send(pkts).await;
+        }
+    }
+
+    /// receive_rtp schedules a rtp packet to be read from the remote stream by the interceptor
+    pub(crate) async fn receive_rtp(&self, pkt: rtp::packet::Packet) {
+        let rtp_in_tx = self.rtp_in_tx.lock().await;
+        if let Some(tx) = &*rtp_in_tx {
+            let _ = tx.send(pkt).await;
+        }
+    }
+
+    /// written_rtcp returns the next batch of rtcp packets written by the interceptor
+    pub(crate) async fn written_rtcp(&self) -> Option<RTCPPackets> {
+        let raw = {
+            let mut rtcp_out_modified_rx = self.rtcp_out_modified_rx.lock().await;
+            rtcp_out_modified_rx.recv().await?
+        };
+        unmarshal_rtcp(&raw).ok()
+    }
+
+    /// written_rtp returns the next rtp packet written by the interceptor
+    pub(crate) async fn written_rtp(&self) -> Option<rtp::packet::Packet> {
+        let mut rtp_out_modified_rx = self.rtp_out_modified_rx.lock().await;
+        rtp_out_modified_rx.recv().await
+    }
+
+    /// read_rtp returns the next rtp packet read through the interceptor
+    pub(crate) async fn read_rtp(&self) -> Option<rtp::packet::Packet> {
+        let mut rtp_in_modified_rx = self.rtp_in_modified_rx.lock().await;
+        rtp_in_modified_rx.recv().await
+    }
+
+    /// close closes the stream and the interceptor
+    pub(crate) async fn close(&self) -> Result<()> {
+        {
+            let mut rtcp_in_tx = self.rtcp_in_tx.lock().await;
+            rtcp_in_tx.take();
+        }
+        {
+            let mut rtp_in_tx = self.rtp_in_tx.lock().await;
+            rtp_in_tx.take();
+        }
+        self.interceptor.close().await
+    }
+}
+
+/// mock_time returns a clock for the interceptors which only moves when the
+/// tests set it, starting on 2009-10-23
+pub(crate) fn mock_time() -> (Arc<SyncMutex<SystemTime>>, FnTimeGen) {
+    let now = Arc::new(SyncMutex::new(
+        UNIX_EPOCH + Duration::from_secs(1_256_256_000),
+    ));
+    let now2 = Arc::clone(&now);
+    (now, Arc::new(move || *now2.lock().unwrap()))
+}
+
+/// marshal_rtcp serializes a batch of rtcp packets into a compound packet
+fn marshal_rtcp(pkts: &[Box<dyn rtcp::packet::Packet + Send + Sync>]) -> Result<Bytes> {
+    let mut raw = BytesMut::new();
+    for p in pkts {
+        raw.extend_from_slice(&p.marshal()?);
+    }
+    Ok(raw.freeze())
+}
+
+#[async_trait]
+impl RTCPWriter for MockStream {
+    async fn write(
+        &self,
+        pkts: &[Box<dyn rtcp::packet::Packet + Send + Sync>],
+        _attributes: &Attributes,
+    ) -> Result<usize> {
+        let _ = self.rtcp_out_modified_tx.send(marshal_rtcp(pkts)?).await;
+        Ok(0)
+    }
+}
+
+#[async_trait]
+impl RTCPReader for MockStream {
+    async fn read(&self, buf: &mut [u8], a: &Attributes) -> Result<(usize, Attributes)> {
+        let pkts = {
+            let mut rtcp_in = self.rtcp_in_rx.lock().await;
+            rtcp_in.recv().await.ok_or(Error::ErrIoEOF)?
+        };
+
+        let raw = marshal_rtcp(&pkts)?;
+        if raw.len() > buf.len() {
+            return Err(Error::new("buffer is too short".to_owned()).into());
+        }
+        buf[..raw.len()].copy_from_slice(&raw);
+
+        Ok((raw.len(), a.clone()))
+    }
+}
+
+#[async_trait]
+impl RTPWriter for MockStream {
+    async fn write(&self, pkt: &rtp::packet::Packet, _attributes: &Attributes) -> Result<usize> {
+        let _ = self.rtp_out_modified_tx.send(pkt.clone()).await;
+        Ok(0)
+    }
+}
+
+#[async_trait]
+impl RTPReader for MockStream {
+    async fn read(&self, buf: &mut [u8], a: &Attributes) -> Result<(usize, Attributes)> {
+        let pkt = {
+            let mut rtp_in = self.rtp_in_rx.lock().await;
+            rtp_in.recv().await.ok_or(Error::ErrIoEOF)?
+        };
+
+        let raw = pkt.marshal()?;
+        if raw.len() > buf.len() {
+            return Err(Error::new("buffer is too short".to_owned()).into());
+        }
+        buf[..raw.len()].copy_from_slice(&raw);
+
+        Ok((raw.len(), a.clone()))
+    }
+}
@@ -0,0 +1,185 @@
+#[cfg(test)]
+mod interceptor_test;
+#[cfg(test)]
+pub(crate) mod mock_stream;
+
+pub mod cc;
+pub mod chain;
+pub mod nack;
+pub mod noop;
+pub mod registry;
+pub mod remb;
+pub mod report;
+pub mod stream_info;
+pub mod twcc;
+
+use stream_info::StreamInfo;
+
+use crate::media::dtls_transport::srtp_session::SRTPStream;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use rtcp::goodbye::Goodbye;
+use rtcp::header::*;
+use rtcp::payload_feedbacks::full_intra_request::FullIntraRequest;
+use rtcp::payload_feedbacks::picture_loss_indication::PictureLossIndication;
+use rtcp::payload_feedbacks::receiver_estimated_maximum_bitrate::ReceiverEstimatedMaximumBitrate;
+use rtcp::payload_feedbacks::slice_loss_indication::SliceLossIndication;
+use rtcp::raw_packet::RawPacket;
+use rtcp::receiver_report::ReceiverReport;
+use rtcp::sender_report::SenderReport;
+use rtcp::source_description::SourceDescription;
+use rtcp::transport_feedbacks::rapid_resynchronization_request::RapidResynchronizationRequest;
+use rtcp::transport_feedbacks::transport_layer_cc::TransportLayerCc;
+use rtcp::transport_feedbacks::transport_layer_nack::TransportLayerNack;
+use std::collections::HashMap;
+use std::sync::Arc;
+use util::marshal::Unmarshal;
+
+/// Attributes are a generic key/value store used by interceptors
+pub type Attributes = HashMap<usize, usize>;
+
+/// ATTR_RETRANSMISSION is set in the Attributes of the rtp packets sent again
+/// because the remote peer lost them
+pub const ATTR_RETRANSMISSION: usize = 1;
+
+/// InterceptorBuilder provides an interface for constructing interceptors
+pub trait InterceptorBuilder {
+    /// build constructs the Interceptor of the PeerConnection with the given id
+    fn build(&self, id: &str) -> Result<Arc<dyn Interceptor + Send + Sync>>;
+}
+
+/// Interceptor can be used to add functionality to you PeerConnections by modifying any incoming/outgoing rtp/rtcp
+/// packets, or sending your own packets as needed.
+#[async_trait]
+pub trait Interceptor {
+    /// bind_rtcp_reader lets you modify any incoming RTCP packets. It is called once per sender/receiver, however this might
+    /// change in the future. The returned method will be called once per packet batch.
+    async fn bind_rtcp_reader(
+        &self,
+        reader: Arc<dyn RTCPReader + Send + Sync>,
+    ) -> Arc<dyn RTCPReader + Send + Sync>;
+
+    /// bind_rtcp_writer lets you modify any outgoing RTCP packets. It is called once per PeerConnection. The returned method
+    /// will be called once per packet batch.
+    async fn bind_rtcp_writer(
+        &self,
+        writer: Arc<dyn RTCPWriter + Send + Sync>,
+    ) -> Arc<dyn RTCPWriter + Send + Sync>;
+
+    /// bind_local_stream lets you modify any outgoing RTP packets. It is called once for per LocalStream. The returned method
+    /// will be called once per rtp packet.
+    async fn bind_local_stream(
+        &self,
+        info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Arc<dyn RTPWriter + Send + Sync>;
+
+    /// unbind_local_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
+    async fn unbind_local_stream(&self, info: &StreamInfo);
+
+    /// bind_remote_stream lets you modify any incoming RTP packets. It is called once for per RemoteStream. The returned method
+    /// will be called once per rtp packet.
+    async fn bind_remote_stream(
+        &self,
+        info: &StreamInfo,
+        reader: Arc<dyn RTPReader + Send + Sync>,
+    ) -> Arc<dyn RTPReader + Send + Sync>;
+
+    /// unbind_remote_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
+    async fn unbind_remote_stream(&self, info: &StreamInfo);
+
+    /// close closes the Interceptor, cleaning up any data if necessary.
+    async fn close(&self) -> Result<()>;
+}
+
+/// RTPWriter is used by Interceptor.bind_local_stream.
+#[async_trait]
+pub trait RTPWriter {
+    /// write a rtp packet
+    async fn write(&self, pkt: &rtp::packet::Packet, attributes: &Attributes) -> Result<usize>;
+}
+
+/// RTPReader is used by Interceptor.bind_remote_stream.
+#[async_trait]
+pub trait RTPReader {
+    /// read a rtp packet
+    async fn read(&self, buf: &mut [u8], attributes: &Attributes) -> Result<(usize, Attributes)>;
+}
+
+/// RTCPWriter is used by Interceptor.bind_rtcp_writer.
+#[async_trait]
+pub trait RTCPWriter {
+    /// write a batch of rtcp packets
+    async fn write(
+        &self,
+        pkts: &[Box<dyn rtcp::packet::Packet + Send + Sync>],
+        attributes: &Attributes,
+    ) -> Result<usize>;
+}
+
+/// RTCPReader is used by Interceptor.bind_rtcp_reader.
+#[async_trait]
+pub trait RTCPReader {
+    /// read a batch of rtcp packets
+    async fn read(&self, buf: &mut [u8], attributes: &Attributes) -> Result<(usize, Attributes)>;
+}
+
+/// The SRTP streams of a SSRC are at the end of the reader chains of its
+/// remote stream and of its RTCP
+#[async_trait]
+impl RTPReader for SRTPStream {
+    async fn read(&self, buf: &mut [u8], attributes: &Attributes) -> Result<(usize, Attributes)> {
+        Ok((SRTPStream::read(self, buf).await?, attributes.clone()))
+    }
+}
+
+#[async_trait]
+impl RTCPReader for SRTPStream {
+    async fn read(&self, buf: &mut [u8], attributes: &Attributes) -> Result<(usize, Attributes)> {
+        Ok((SRTPStream::read(self, buf).await?, attributes.clone()))
+    }
+}
+
+/// unmarshal_rtcp unmarshals a batch of RTCP read by a RTCPReader, the packets of
+/// a compound packet are returned one by one
+pub fn unmarshal_rtcp(mut raw: &[u8]) -> Result<Vec<Box<dyn rtcp::packet::Packet + Send + Sync>>> {
+    let mut pkts = vec![];
+    while !raw.is_empty() {
+        let h = Header::unmarshal(&mut &raw[..])?;
+        let length = (h.length as usize + 1) * 4;
+        if length > raw.len() {
+            return Err(rtcp::error::Error::PacketTooShort.into());
+        }
+        let in_packet = &mut &raw[..length];
+        raw = &raw[length..];
+
+        let p: Box<dyn rtcp::packet::Packet + Send + Sync> = match h.packet_type {
+            PacketType::SenderReport => Box::new(SenderReport::unmarshal(in_packet)?),
+            PacketType::ReceiverReport => Box::new(ReceiverReport::unmarshal(in_packet)?),
+            PacketType::SourceDescription => Box::new(SourceDescription::unmarshal(in_packet)?),
+            PacketType::Goodbye => Box::new(Goodbye::unmarshal(in_packet)?),
+            PacketType::TransportSpecificFeedback => match h.count {
+                FORMAT_TLN => Box::new(TransportLayerNack::unmarshal(in_packet)?),
+                FORMAT_RRR => Box::new(RapidResynchronizationRequest::unmarshal(in_packet)?),
+                FORMAT_TCC => Box::new(TransportLayerCc::unmarshal(in_packet)?),
+                _ => Box::new(RawPacket::unmarshal(in_packet)?),
+            },
+            PacketType::PayloadSpecificFeedback => match h.count {
+                FORMAT_PLI => Box::new(PictureLossIndication::unmarshal(in_packet)?),
+                FORMAT_SLI => Box::new(SliceLossIndication::unmarshal(in_packet)?),
+                FORMAT_REMB => Box::new(ReceiverEstimatedMaximumBitrate::unmarshal(in_packet)?),
+                FORMAT_FIR => Box::new(FullIntraRequest::unmarshal(in_packet)?),
+                _ => Box::new(RawPacket::unmarshal(in_packet)?),
+            },
+            _ => Box::new(RawPacket::unmarshal(in_packet)?),
+        };
+        pkts.push(p);
+    }
+
+    if pkts.is_empty() {
+        Err(rtcp::error::Error::InvalidHeader.into())
+    } else {
+        Ok(pkts)
+    }
+}
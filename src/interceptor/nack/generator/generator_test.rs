@@ -0,0 +1,106 @@
+use super::*;
+use crate::interceptor::mock_stream::MockStream;
+use crate::media::rtp::RTCPFeedback;
+
+#[tokio::test]
+async fn test_generator_interceptor() -> Result<()> {
+    const INTERVAL: Duration = Duration::from_millis(10);
+    let icpr = Generator::builder()
+        .with_log2_size_minus_6(0)
+        .with_skip_last_n(2)
+        .with_interval(INTERVAL)
+        .build("")?;
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 1,
+            rtcp_feedback: vec![RTCPFeedback {
+                typ: "nack".to_owned(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    for seq_num in &[10, 11, 12, 14, 16, 18] {
+        stream
+            .receive_rtp(rtp::packet::Packet {
+                header: rtp::header::Header {
+                    sequence_number: *seq_num,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .await;
+
+        let p = tokio::time::timeout(Duration::from_millis(10), stream.read_rtp())
+            .await?
+            .expect("a packet read");
+        assert_eq!(p.header.sequence_number, *seq_num);
+    }
+
+    tokio::time::sleep(INTERVAL * 2).await; // wait for at least 2 nack packets
+
+    // ignore the first nack, it might only contain the sequence id 13 as missing
+    let _ = stream.written_rtcp().await;
+
+    let pkts = tokio::time::timeout(Duration::from_millis(10), stream.written_rtcp())
+        .await?
+        .expect("a nack written");
+    if let Some(nack) = pkts[0].as_any().downcast_ref::<TransportLayerNack>() {
+        assert_eq!(nack.media_ssrc, 1);
+        assert_eq!(nack.nacks[0].packet_id, 13);
+        // we want packets: 13, 15 (not packet 17, because skip_last_n is set to 2)
+        assert_eq!(nack.nacks[0].lost_packets, 0b10);
+    } else {
+        panic!("single packet RTCP Compound Packet expected");
+    }
+
+    stream.close().await
+}
+
+#[tokio::test]
+async fn test_generator_interceptor_without_nack_feedback() -> Result<()> {
+    const INTERVAL: Duration = Duration::from_millis(10);
+    let icpr = Generator::builder().with_interval(INTERVAL).build("")?;
+
+    // the codec of the stream didn't negotiate nack, so the losses are ignored
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 1,
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    for seq_num in &[10, 12] {
+        stream
+            .receive_rtp(rtp::packet::Packet {
+                header: rtp::header::Header {
+                    sequence_number: *seq_num,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .await;
+        let _ = stream.read_rtp().await;
+    }
+
+    let result = tokio::time::timeout(INTERVAL * 3, stream.written_rtcp()).await;
+    assert!(result.is_err(), "no nack expected");
+
+    stream.close().await
+}
+
+#[test]
+fn test_generator_interceptor_invalid_size() {
+    // 1 << (10 + 6) doesn't fit in the u16 sequence number space
+    if let Err(err) = Generator::builder().with_log2_size_minus_6(10).build("") {
+        assert!(Error::ErrInvalidSize.equal(&err));
+    } else {
+        panic!("expected error, but got ok");
+    }
+}
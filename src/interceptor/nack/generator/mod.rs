@@ -0,0 +1,209 @@
+mod generator_stream;
+#[cfg(test)]
+mod generator_test;
+
+use generator_stream::GeneratorStream;
+
+use super::{nack_pairs_from_sequence_numbers, stream_support_nack};
+use crate::error::Error;
+use crate::interceptor::stream_info::StreamInfo;
+use crate::interceptor::*;
+use crate::media::rtp::SSRC;
+
+use rtcp::transport_feedbacks::transport_layer_nack::TransportLayerNack;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+
+/// GeneratorBuilder can be used to configure Generator Interceptor
+#[derive(Default)]
+pub struct GeneratorBuilder {
+    log2_size_minus_6: Option<u8>,
+    skip_last_n: Option<u16>,
+    interval: Option<Duration>,
+}
+
+impl GeneratorBuilder {
+    /// with_log2_size_minus_6 sets the size of the interceptor.
+    /// Size must be one of: 64, 128, 256, 512, 1024, 2048, 4096, 8192, 16384, 32768
+    pub fn with_log2_size_minus_6(mut self, log2_size_minus_6: u8) -> GeneratorBuilder {
+        self.log2_size_minus_6 = Some(log2_size_minus_6);
+        self
+    }
+
+    /// with_skip_last_n sets the number of packets (n-1 packets before the last received packets) to ignore when generating
+    /// nack requests.
+    pub fn with_skip_last_n(mut self, skip_last_n: u16) -> GeneratorBuilder {
+        self.skip_last_n = Some(skip_last_n);
+        self
+    }
+
+    /// with_interval sets the nack send interval for the interceptor
+    pub fn with_interval(mut self, interval: Duration) -> GeneratorBuilder {
+        self.interval = Some(interval);
+        self
+    }
+}
+
+impl InterceptorBuilder for GeneratorBuilder {
+    fn build(&self, _id: &str) -> Result<Arc<dyn Interceptor + Send + Sync>> {
+        let log2_size_minus_6 = self.log2_size_minus_6.unwrap_or(13 - 6); // 8192 = 1 << 13
+        if log2_size_minus_6 > 15 - 6 {
+            return Err(Error::ErrInvalidSize.into());
+        }
+
+        let (close_tx, close_rx) = mpsc::channel(1);
+        Ok(Arc::new(Generator {
+            internal: Arc::new(GeneratorInternal {
+                log2_size_minus_6,
+                skip_last_n: self.skip_last_n.unwrap_or(0),
+                interval: self.interval.unwrap_or(Duration::from_millis(100)),
+                streams: Mutex::new(HashMap::new()),
+            }),
+            close_tx: Mutex::new(Some(close_tx)),
+            close_rx: Mutex::new(Some(close_rx)),
+        }))
+    }
+}
+
+struct GeneratorInternal {
+    log2_size_minus_6: u8,
+    skip_last_n: u16,
+    interval: Duration,
+
+    streams: Mutex<HashMap<SSRC, Arc<GeneratorStream>>>,
+}
+
+/// Generator interceptor tracks the sequence numbers received on every remote
+/// stream, and periodically asks the sender for the missing packets with
+/// generic NACK feedback messages.
+pub struct Generator {
+    internal: Arc<GeneratorInternal>,
+
+    close_tx: Mutex<Option<mpsc::Sender<()>>>,
+    close_rx: Mutex<Option<mpsc::Receiver<()>>>,
+}
+
+impl Generator {
+    /// builder returns a new GeneratorBuilder.
+    pub fn builder() -> GeneratorBuilder {
+        GeneratorBuilder::default()
+    }
+
+    async fn run(
+        rtcp_writer: Arc<dyn RTCPWriter + Send + Sync>,
+        internal: Arc<GeneratorInternal>,
+        mut close_rx: mpsc::Receiver<()>,
+    ) {
+        let mut ticker = tokio::time::interval(internal.interval);
+        let sender_ssrc = rand::random::<u32>();
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    let nacks = {
+                        let mut nacks = vec![];
+                        let streams = internal.streams.lock().await;
+                        for (ssrc, stream) in streams.iter() {
+                            let missing = stream.missing_seq_numbers(internal.skip_last_n).await;
+                            if missing.is_empty() {
+                                continue;
+                            }
+
+                            nacks.push(TransportLayerNack {
+                                sender_ssrc,
+                                media_ssrc: *ssrc,
+                                nacks: nack_pairs_from_sequence_numbers(&missing),
+                            });
+                        }
+                        nacks
+                    };
+
+                    let a = Attributes::new();
+                    for nack in nacks {
+                        if let Err(err) = rtcp_writer.write(&[Box::new(nack)], &a).await {
+                            log::warn!("failed sending nack: {}", err);
+                        }
+                    }
+                }
+                _ = close_rx.recv() => {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Interceptor for Generator {
+    async fn bind_rtcp_reader(
+        &self,
+        reader: Arc<dyn RTCPReader + Send + Sync>,
+    ) -> Arc<dyn RTCPReader + Send + Sync> {
+        reader
+    }
+
+    /// bind_rtcp_writer starts sending the nacks through the writer of the PeerConnection
+    async fn bind_rtcp_writer(
+        &self,
+        writer: Arc<dyn RTCPWriter + Send + Sync>,
+    ) -> Arc<dyn RTCPWriter + Send + Sync> {
+        let close_rx = {
+            let mut close_rx = self.close_rx.lock().await;
+            close_rx.take()
+        };
+
+        // the nacks are sent by a single routine, which stops when the interceptor is closed
+        if let Some(close_rx) = close_rx {
+            let writer2 = Arc::clone(&writer);
+            let internal = Arc::clone(&self.internal);
+            tokio::spawn(async move {
+                Generator::run(writer2, internal, close_rx).await;
+            });
+        }
+
+        writer
+    }
+
+    async fn bind_local_stream(
+        &self,
+        _info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Arc<dyn RTPWriter + Send + Sync> {
+        writer
+    }
+
+    async fn unbind_local_stream(&self, _info: &StreamInfo) {}
+
+    /// bind_remote_stream records the sequence numbers of the streams which negotiated nack
+    async fn bind_remote_stream(
+        &self,
+        info: &StreamInfo,
+        reader: Arc<dyn RTPReader + Send + Sync>,
+    ) -> Arc<dyn RTPReader + Send + Sync> {
+        if !stream_support_nack(info) {
+            return reader;
+        }
+
+        let stream = Arc::new(GeneratorStream::new(
+            self.internal.log2_size_minus_6,
+            reader,
+        ));
+        {
+            let mut streams = self.internal.streams.lock().await;
+            streams.insert(info.ssrc, Arc::clone(&stream));
+        }
+
+        stream
+    }
+
+    async fn unbind_remote_stream(&self, info: &StreamInfo) {
+        let mut streams = self.internal.streams.lock().await;
+        streams.remove(&info.ssrc);
+    }
+
+    async fn close(&self) -> Result<()> {
+        let mut close_tx = self.close_tx.lock().await;
+        close_tx.take();
+
+        Ok(())
+    }
+}
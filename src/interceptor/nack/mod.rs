@@ -0,0 +1,69 @@
+use super::stream_info::StreamInfo;
+use crate::media::rtp::TYPE_RTCP_FB_NACK;
+
+use rtcp::transport_feedbacks::transport_layer_nack::NackPair;
+
+pub mod generator;
+pub mod responder;
+
+const UINT16SIZE_HALF: u16 = 1 << 15;
+
+/// stream_support_nack tells if the codec of the stream has been negotiated
+/// with generic NACK feedback
+fn stream_support_nack(info: &StreamInfo) -> bool {
+    info.rtcp_feedback
+        .iter()
+        .any(|fb| fb.typ == TYPE_RTCP_FB_NACK && fb.parameter.is_empty())
+}
+
+/// nack_pairs_from_sequence_numbers packs the ordered sequence numbers of lost
+/// packets into the pairs of a generic NACK, each pair covering the packet_id
+/// and the 16 packets that follow it
+pub(crate) fn nack_pairs_from_sequence_numbers(seq_nos: &[u16]) -> Vec<NackPair> {
+    let mut pairs: Vec<NackPair> = vec![];
+    for &seq in seq_nos {
+        if let Some(pair) = pairs.last_mut() {
+            let diff = seq.wrapping_sub(pair.packet_id);
+            if diff > 0 && diff <= 16 {
+                pair.lost_packets |= 1 << (diff - 1);
+                continue;
+            }
+        }
+        pairs.push(NackPair {
+            packet_id: seq,
+            lost_packets: 0,
+        });
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_nack_pairs_from_sequence_numbers() {
+        assert!(nack_pairs_from_sequence_numbers(&[]).is_empty());
+        assert_eq!(
+            nack_pairs_from_sequence_numbers(&[1, 2, 17, 18]),
+            vec![
+                NackPair {
+                    packet_id: 1,
+                    lost_packets: 0x8001,
+                },
+                NackPair {
+                    packet_id: 18,
+                    lost_packets: 0,
+                },
+            ]
+        );
+        // the sequence numbers can wrap around
+        assert_eq!(
+            nack_pairs_from_sequence_numbers(&[65535, 0, 2]),
+            vec![NackPair {
+                packet_id: 65535,
+                lost_packets: 0b101,
+            }]
+        );
+    }
+}
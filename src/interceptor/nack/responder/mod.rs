@@ -0,0 +1,174 @@
+mod responder_stream;
+#[cfg(test)]
+mod responder_test;
+
+use responder_stream::ResponderStream;
+
+use super::stream_support_nack;
+use crate::error::Error;
+use crate::interceptor::stream_info::StreamInfo;
+use crate::interceptor::*;
+use crate::media::rtp::SSRC;
+
+use rtcp::transport_feedbacks::transport_layer_nack::TransportLayerNack;
+use tokio::sync::Mutex;
+
+/// ResponderBuilder can be used to configure Responder Interceptor
+#[derive(Default)]
+pub struct ResponderBuilder {
+    log2_size: Option<u8>,
+}
+
+impl ResponderBuilder {
+    /// with_log2_size sets the size of the interceptor.
+    /// Size must be one of: 1, 2, 4, 8, 16, 32, 64, 128, 256, 512, 1024, 2048, 4096, 8192, 16384, 32768
+    pub fn with_log2_size(mut self, log2_size: u8) -> ResponderBuilder {
+        self.log2_size = Some(log2_size);
+        self
+    }
+}
+
+impl InterceptorBuilder for ResponderBuilder {
+    fn build(&self, _id: &str) -> Result<Arc<dyn Interceptor + Send + Sync>> {
+        let log2_size = self.log2_size.unwrap_or(13); // 8192 = 1 << 13
+        if log2_size > 15 {
+            return Err(Error::ErrInvalidSize.into());
+        }
+
+        Ok(Arc::new(Responder {
+            internal: Arc::new(ResponderInternal {
+                log2_size,
+                streams: Mutex::new(HashMap::new()),
+            }),
+        }))
+    }
+}
+
+struct ResponderInternal {
+    log2_size: u8,
+    streams: Mutex<HashMap<SSRC, Arc<ResponderStream>>>,
+}
+
+impl ResponderInternal {
+    /// resend_packets sends again the packets of the nack which are still in the buffer of the stream
+    async fn resend_packets(&self, nack: &TransportLayerNack) {
+        let stream = {
+            let streams = self.streams.lock().await;
+            match streams.get(&nack.media_ssrc) {
+                Some(stream) => Arc::clone(stream),
+                None => return,
+            }
+        };
+
+        let mut a = Attributes::new();
+        a.insert(ATTR_RETRANSMISSION, 1);
+        for pair in &nack.nacks {
+            for seq in pair.packet_list() {
+                if let Some(pkt) = stream.get(seq).await {
+                    let pkt = stream.retransmission(pkt);
+                    if let Err(err) = stream.next_rtp_writer.write(&pkt, &a).await {
+                        log::warn!("failed resending nacked packet: {}", err);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// ResponderRTCPReader looks for the nacks in the RTCP read by the RTPSender
+struct ResponderRTCPReader {
+    parent_rtcp_reader: Arc<dyn RTCPReader + Send + Sync>,
+    internal: Arc<ResponderInternal>,
+}
+
+#[async_trait]
+impl RTCPReader for ResponderRTCPReader {
+    async fn read(&self, buf: &mut [u8], a: &Attributes) -> Result<(usize, Attributes)> {
+        let (n, attr) = self.parent_rtcp_reader.read(buf, a).await?;
+
+        let pkts = unmarshal_rtcp(&buf[..n])?;
+        for p in &pkts {
+            if let Some(nack) = p.as_any().downcast_ref::<TransportLayerNack>() {
+                let nack = nack.clone();
+                let internal = Arc::clone(&self.internal);
+                tokio::spawn(async move {
+                    internal.resend_packets(&nack).await;
+                });
+            }
+        }
+
+        Ok((n, attr))
+    }
+}
+
+/// Responder interceptor keeps the last packets sent on every local stream,
+/// and sends them again when the receiver asks for them with a generic NACK.
+pub struct Responder {
+    internal: Arc<ResponderInternal>,
+}
+
+impl Responder {
+    /// builder returns a new ResponderBuilder.
+    pub fn builder() -> ResponderBuilder {
+        ResponderBuilder::default()
+    }
+}
+
+#[async_trait]
+impl Interceptor for Responder {
+    /// bind_rtcp_reader resends the packets of the nacks read
+    async fn bind_rtcp_reader(
+        &self,
+        reader: Arc<dyn RTCPReader + Send + Sync>,
+    ) -> Arc<dyn RTCPReader + Send + Sync> {
+        Arc::new(ResponderRTCPReader {
+            parent_rtcp_reader: reader,
+            internal: Arc::clone(&self.internal),
+        })
+    }
+
+    async fn bind_rtcp_writer(
+        &self,
+        writer: Arc<dyn RTCPWriter + Send + Sync>,
+    ) -> Arc<dyn RTCPWriter + Send + Sync> {
+        writer
+    }
+
+    /// bind_local_stream buffers the packets of the streams which negotiated nack
+    async fn bind_local_stream(
+        &self,
+        info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Arc<dyn RTPWriter + Send + Sync> {
+        if !stream_support_nack(info) {
+            return writer;
+        }
+
+        let stream = Arc::new(ResponderStream::new(self.internal.log2_size, info, writer));
+        {
+            let mut streams = self.internal.streams.lock().await;
+            streams.insert(info.ssrc, Arc::clone(&stream));
+        }
+
+        stream
+    }
+
+    async fn unbind_local_stream(&self, info: &StreamInfo) {
+        let mut streams = self.internal.streams.lock().await;
+        streams.remove(&info.ssrc);
+    }
+
+    async fn bind_remote_stream(
+        &self,
+        _info: &StreamInfo,
+        reader: Arc<dyn RTPReader + Send + Sync>,
+    ) -> Arc<dyn RTPReader + Send + Sync> {
+        reader
+    }
+
+    async fn unbind_remote_stream(&self, _info: &StreamInfo) {}
+
+    async fn close(&self) -> Result<()> {
+        Ok(())
+    }
+}
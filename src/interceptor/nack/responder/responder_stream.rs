@@ -0,0 +1,198 @@
+use super::super::UINT16SIZE_HALF;
+use super::*;
+use crate::media::rtp::PayloadType;
+
+use bytes::{BufMut, BytesMut};
+use std::sync::atomic::{AtomicU16, Ordering};
+
+/// ResponderStreamInternal is a ring buffer of the last sent packets
+struct ResponderStreamInternal {
+    packets: Vec<Option<rtp::packet::Packet>>,
+    size: u16,
+    last_added: u16,
+    started: bool,
+}
+
+impl ResponderStreamInternal {
+    fn new(log2_size: u8) -> Self {
+        ResponderStreamInternal {
+            packets: vec![None; 1 << log2_size],
+            size: 1 << log2_size,
+            last_added: 0,
+            started: false,
+        }
+    }
+
+    fn add(&mut self, packet: &rtp::packet::Packet) {
+        let seq = packet.header.sequence_number;
+        if !self.started {
+            self.packets[(seq % self.size) as usize] = Some(packet.clone());
+            self.last_added = seq;
+            self.started = true;
+            return;
+        }
+
+        let diff = seq.wrapping_sub(self.last_added);
+        if diff == 0 {
+            return;
+        } else if diff < UINT16SIZE_HALF {
+            // clear the packets skipped since the last one, they were never sent
+            let mut i = self.last_added.wrapping_add(1);
+            while i != seq {
+                self.packets[(i % self.size) as usize] = None;
+                i = i.wrapping_add(1);
+            }
+        }
+
+        self.packets[(seq % self.size) as usize] = Some(packet.clone());
+        self.last_added = seq;
+    }
+
+    fn get(&self, seq: u16) -> Option<&rtp::packet::Packet> {
+        let diff = self.last_added.wrapping_sub(seq);
+        if diff >= UINT16SIZE_HALF || diff >= self.size {
+            return None;
+        }
+
+        self.packets[(seq % self.size) as usize].as_ref()
+    }
+}
+
+/// ResponderStream buffers the packets written to a local stream
+pub(super) struct ResponderStream {
+    internal: Mutex<ResponderStreamInternal>,
+    /// ssrc and payload type of the RTX stream, when one was negotiated
+    rtx: Option<(SSRC, PayloadType)>,
+    rtx_sequence_number: AtomicU16,
+    pub(super) next_rtp_writer: Arc<dyn RTPWriter + Send + Sync>,
+}
+
+impl ResponderStream {
+    pub(super) fn new(
+        log2_size: u8,
+        info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Self {
+        let rtx = if info.ssrc_retransmission != 0 && info.payload_type_retransmission != 0 {
+            Some((info.ssrc_retransmission, info.payload_type_retransmission))
+        } else {
+            None
+        };
+
+        ResponderStream {
+            internal: Mutex::new(ResponderStreamInternal::new(log2_size)),
+            rtx,
+            rtx_sequence_number: AtomicU16::new(rand::random::<u16>()),
+            next_rtp_writer: writer,
+        }
+    }
+
+    /// retransmission returns the packet to send again for the given one, which is wrapped
+    /// in a RTX packet as specified in https://datatracker.ietf.org/doc/html/rfc4588#section-4
+    /// when the stream has a RTX stream
+    pub(super) fn retransmission(&self, pkt: rtp::packet::Packet) -> rtp::packet::Packet {
+        let (ssrc, payload_type) = match self.rtx {
+            Some(rtx) => rtx,
+            None => return pkt,
+        };
+
+        let mut payload = BytesMut::with_capacity(2 + pkt.payload.len());
+        payload.put_u16(pkt.header.sequence_number);
+        payload.extend_from_slice(&pkt.payload);
+
+        let mut header = pkt.header;
+        header.ssrc = ssrc;
+        header.payload_type = payload_type;
+        header.sequence_number = self.rtx_sequence_number.fetch_add(1, Ordering::SeqCst);
+
+        rtp::packet::Packet {
+            header,
+            payload: payload.freeze(),
+        }
+    }
+
+    async fn add(&self, pkt: &rtp::packet::Packet) {
+        let mut internal = self.internal.lock().await;
+        internal.add(pkt);
+    }
+
+    pub(super) async fn get(&self, seq: u16) -> Option<rtp::packet::Packet> {
+        let internal = self.internal.lock().await;
+        internal.get(seq).cloned()
+    }
+}
+
+#[async_trait]
+impl RTPWriter for ResponderStream {
+    async fn write(&self, pkt: &rtp::packet::Packet, a: &Attributes) -> Result<usize> {
+        self.add(pkt).await;
+
+        self.next_rtp_writer.write(pkt, a).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_responder_stream() {
+        let tests: Vec<u16> = vec![
+            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 511, 512, 513, 32767, 32768, 32769, 65527, 65528, 65529,
+            65530, 65531, 65532, 65533, 65534, 65535,
+        ];
+        for start in tests {
+            let mut sb = ResponderStreamInternal::new(3);
+
+            let add = |sb: &mut ResponderStreamInternal, nums: &[u16]| {
+                for n in nums {
+                    sb.add(&rtp::packet::Packet {
+                        header: rtp::header::Header {
+                            sequence_number: start.wrapping_add(*n),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    });
+                }
+            };
+
+            let assert_get = |sb: &ResponderStreamInternal, nums: &[u16]| {
+                for n in nums {
+                    let seq = start.wrapping_add(*n);
+                    if let Some(packet) = sb.get(seq) {
+                        assert_eq!(packet.header.sequence_number, seq);
+                    } else {
+                        panic!("packet not found: {}", seq);
+                    }
+                }
+            };
+
+            let assert_not_get = |sb: &ResponderStreamInternal, nums: &[u16]| {
+                for n in nums {
+                    let seq = start.wrapping_add(*n);
+                    assert!(sb.get(seq).is_none(), "packet found for {}", seq);
+                }
+            };
+
+            add(&mut sb, &[0, 1, 2, 3, 4, 5, 6, 7]);
+            assert_get(&sb, &[0, 1, 2, 3, 4, 5, 6, 7]);
+
+            add(&mut sb, &[8]);
+            assert_get(&sb, &[8]);
+            assert_not_get(&sb, &[0]);
+
+            add(&mut sb, &[10]);
+            assert_get(&sb, &[10]);
+            assert_not_get(&sb, &[1, 2, 9]);
+
+            add(&mut sb, &[22]);
+            assert_get(&sb, &[22]);
+            assert_not_get(
+                &sb,
+                &[
+                    3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21,
+                ],
+            );
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use super::*;
+use crate::interceptor::mock_stream::MockStream;
+use crate::media::rtp::RTCPFeedback;
+
+use bytes::Bytes;
+use rtcp::transport_feedbacks::transport_layer_nack::NackPair;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_responder_interceptor() -> Result<()> {
+    let icpr = Responder::builder().with_log2_size(3).build("")?;
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 1,
+            rtcp_feedback: vec![RTCPFeedback {
+                typ: "nack".to_owned(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    for seq_num in &[10, 11, 12, 14, 15] {
+        stream
+            .write_rtp(&rtp::packet::Packet {
+                header: rtp::header::Header {
+                    sequence_number: *seq_num,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .await?;
+
+        let p = tokio::time::timeout(Duration::from_millis(10), stream.written_rtp())
+            .await?
+            .expect("a packet written");
+        assert_eq!(p.header.sequence_number, *seq_num);
+    }
+
+    stream
+        .receive_rtcp(vec![Box::new(TransportLayerNack {
+            sender_ssrc: 2,
+            media_ssrc: 1,
+            nacks: vec![NackPair {
+                packet_id: 11,
+                lost_packets: 0b1011, // sequence numbers: 11, 12, 13, 15
+            }],
+        })])
+        .await;
+
+    // seq number 13 was never sent, so it can't be resent
+    for seq_num in &[11, 12, 15] {
+        let p = tokio::time::timeout(Duration::from_millis(50), stream.written_rtp())
+            .await?
+            .expect("a packet resent");
+        assert_eq!(p.header.sequence_number, *seq_num);
+    }
+
+    let result = tokio::time::timeout(Duration::from_millis(10), stream.written_rtp()).await;
+    assert!(result.is_err(), "no more rtp packets expected");
+
+    stream.close().await
+}
+
+#[tokio::test]
+async fn test_responder_interceptor_rtx() -> Result<()> {
+    let icpr = Responder::builder().with_log2_size(3).build("")?;
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 1,
+            payload_type: 96,
+            ssrc_retransmission: 2,
+            payload_type_retransmission: 97,
+            rtcp_feedback: vec![RTCPFeedback {
+                typ: "nack".to_owned(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    for seq_num in &[10, 11, 12] {
+        stream
+            .write_rtp(&rtp::packet::Packet {
+                header: rtp::header::Header {
+                    sequence_number: *seq_num,
+                    ssrc: 1,
+                    payload_type: 96,
+                    ..Default::default()
+                },
+                payload: Bytes::from_static(&[0xAA, 0xBB]),
+            })
+            .await?;
+
+        let p = tokio::time::timeout(Duration::from_millis(10), stream.written_rtp())
+            .await?
+            .expect("a packet written");
+        assert_eq!(p.header.sequence_number, *seq_num);
+    }
+
+    stream
+        .receive_rtcp(vec![Box::new(TransportLayerNack {
+            sender_ssrc: 3,
+            media_ssrc: 1,
+            nacks: vec![NackPair {
+                packet_id: 10,
+                lost_packets: 0b10, // sequence numbers: 10, 12
+            }],
+        })])
+        .await;
+
+    // the packets are resent on the RTX stream, prefixed with their original sequence number
+    let mut rtx_seq_num = None;
+    for seq_num in &[10u16, 12] {
+        let p = tokio::time::timeout(Duration::from_millis(50), stream.written_rtp())
+            .await?
+            .expect("a packet resent");
+        assert_eq!(p.header.ssrc, 2);
+        assert_eq!(p.header.payload_type, 97);
+        let mut payload = seq_num.to_be_bytes().to_vec();
+        payload.extend_from_slice(&[0xAA, 0xBB]);
+        assert_eq!(&p.payload[..], &payload[..]);
+
+        if let Some(prev) = rtx_seq_num {
+            assert_eq!(p.header.sequence_number, u16::wrapping_add(prev, 1));
+        }
+        rtx_seq_num = Some(p.header.sequence_number);
+    }
+
+    stream.close().await
+}
+
+#[test]
+fn test_responder_interceptor_invalid_size() {
+    // 1 << 16 doesn't fit in the u16 sequence number space
+    if let Err(err) = Responder::builder().with_log2_size(16).build("") {
+        assert!(Error::ErrInvalidSize.equal(&err));
+    } else {
+        panic!("expected error, but got ok");
+    }
+}
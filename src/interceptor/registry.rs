@@ -0,0 +1,36 @@
+use super::chain::Chain;
+use super::noop::NoOp;
+use super::*;
+
+/// Registry is a collector for interceptors. Every PeerConnection builds its
+/// own interceptors from the registry of its API.
+#[derive(Default)]
+pub struct Registry {
+    builders: Vec<Box<dyn InterceptorBuilder + Send + Sync>>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Registry::default()
+    }
+
+    /// add adds a new InterceptorBuilder to the registry, the interceptors
+    /// are chained in the order they are added
+    pub fn add(&mut self, builder: Box<dyn InterceptorBuilder + Send + Sync>) {
+        self.builders.push(builder);
+    }
+
+    /// build constructs a single Interceptor from an InterceptorRegistry
+    pub fn build(&self, id: &str) -> Result<Arc<dyn Interceptor + Send + Sync>> {
+        if self.builders.is_empty() {
+            return Ok(Arc::new(NoOp));
+        }
+
+        let mut interceptors = vec![];
+        for builder in &self.builders {
+            interceptors.push(builder.build(id)?);
+        }
+
+        Ok(Arc::new(Chain::new(interceptors)))
+    }
+}
@@ -0,0 +1,209 @@
+mod remb_stream;
+#[cfg(test)]
+mod remb_test;
+
+use remb_stream::RembStream;
+
+use crate::interceptor::cc::gcc::delay_based::DelayBasedBWE;
+use crate::interceptor::cc::gcc::feedback_adapter::Acknowledgment;
+use crate::interceptor::stream_info::StreamInfo;
+use crate::interceptor::*;
+use crate::media::rtp::{SSRC, TYPE_RTCP_FB_GOOG_REMB};
+
+use rtcp::payload_feedbacks::receiver_estimated_maximum_bitrate::ReceiverEstimatedMaximumBitrate;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+use tokio::time::Instant;
+use util::marshal::Unmarshal;
+
+const DEFAULT_INITIAL_BITRATE: u64 = 300_000;
+const MIN_BITRATE: u64 = 30_000;
+const MAX_BITRATE: u64 = 10_000_000;
+
+/// stream_support_remb tells if the codec of the stream has been negotiated
+/// with goog-remb feedback
+fn stream_support_remb(info: &StreamInfo) -> bool {
+    info.rtcp_feedback
+        .iter()
+        .any(|fb| fb.typ == TYPE_RTCP_FB_GOOG_REMB)
+}
+
+/// RembBuilder can be used to configure the Remb Interceptor
+#[derive(Default)]
+pub struct RembBuilder {
+    interval: Option<Duration>,
+    initial_bitrate: Option<u64>,
+}
+
+impl RembBuilder {
+    /// with_interval sets send interval for the interceptor.
+    pub fn with_interval(mut self, interval: Duration) -> RembBuilder {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// with_initial_bitrate sets the bitrate the estimation of every remote stream starts at.
+    pub fn with_initial_bitrate(mut self, bitrate: u64) -> RembBuilder {
+        self.initial_bitrate = Some(bitrate);
+        self
+    }
+}
+
+impl InterceptorBuilder for RembBuilder {
+    fn build(&self, _id: &str) -> Result<Arc<dyn Interceptor + Send + Sync>> {
+        let (close_tx, close_rx) = mpsc::channel(1);
+        Ok(Arc::new(Remb {
+            interval: self.interval.unwrap_or(Duration::from_secs(1)),
+            initial_bitrate: self.initial_bitrate.unwrap_or(DEFAULT_INITIAL_BITRATE),
+            start_time: Instant::now(),
+            streams: Arc::new(Mutex::new(HashMap::new())),
+            close_tx: Mutex::new(Some(close_tx)),
+            close_rx: Mutex::new(Some(close_rx)),
+        }))
+    }
+}
+
+/// Remb interceptor estimates the bitrate available to the remote streams which
+/// negotiated goog-remb, and sends the estimation to the remote peer in REMB packets,
+/// as specified in https://datatracker.ietf.org/doc/html/draft-alvestrand-rmcat-remb-03
+pub struct Remb {
+    interval: Duration,
+    initial_bitrate: u64,
+    start_time: Instant,
+    streams: Arc<Mutex<HashMap<SSRC, Arc<RembStream>>>>,
+
+    close_tx: Mutex<Option<mpsc::Sender<()>>>,
+    close_rx: Mutex<Option<mpsc::Receiver<()>>>,
+}
+
+impl Remb {
+    /// builder returns a new RembBuilder.
+    pub fn builder() -> RembBuilder {
+        RembBuilder::default()
+    }
+
+    async fn run(
+        rtcp_writer: Arc<dyn RTCPWriter + Send + Sync>,
+        interval: Duration,
+        streams: Arc<Mutex<HashMap<SSRC, Arc<RembStream>>>>,
+        mut close_rx: mpsc::Receiver<()>,
+    ) {
+        let sender_ssrc = rand::random::<u32>();
+        let a = Attributes::new();
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            tokio::select! {
+                _ = close_rx.recv() => {
+                    return;
+                }
+                _ = ticker.tick() => {
+                    let streams: Vec<(SSRC, Arc<RembStream>)> = {
+                        let streams = streams.lock().await;
+                        streams.iter().map(|(ssrc, s)| (*ssrc, Arc::clone(s))).collect()
+                    };
+
+                    // the estimation covers all the streams which received packets
+                    let mut bitrate = 0;
+                    let mut ssrcs = vec![];
+                    for (ssrc, stream) in streams {
+                        if let Some(b) = stream.bitrate().await {
+                            bitrate += b;
+                            ssrcs.push(ssrc);
+                        }
+                    }
+                    if ssrcs.is_empty() {
+                        continue;
+                    }
+
+                    let pkts: Vec<Box<dyn rtcp::packet::Packet + Send + Sync>> =
+                        vec![Box::new(ReceiverEstimatedMaximumBitrate {
+                            sender_ssrc,
+                            bitrate,
+                            ssrcs,
+                        })];
+                    if let Err(err) = rtcp_writer.write(&pkts, &a).await {
+                        log::warn!("failed sending remb: {}", err);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Interceptor for Remb {
+    async fn bind_rtcp_reader(
+        &self,
+        reader: Arc<dyn RTCPReader + Send + Sync>,
+    ) -> Arc<dyn RTCPReader + Send + Sync> {
+        reader
+    }
+
+    /// bind_rtcp_writer starts sending the REMB packets through the writer of the PeerConnection
+    async fn bind_rtcp_writer(
+        &self,
+        writer: Arc<dyn RTCPWriter + Send + Sync>,
+    ) -> Arc<dyn RTCPWriter + Send + Sync> {
+        let close_rx = {
+            let mut close_rx = self.close_rx.lock().await;
+            close_rx.take()
+        };
+
+        if let Some(close_rx) = close_rx {
+            let writer2 = Arc::clone(&writer);
+            let interval = self.interval;
+            let streams = Arc::clone(&self.streams);
+            tokio::spawn(async move {
+                Remb::run(writer2, interval, streams, close_rx).await;
+            });
+        }
+
+        writer
+    }
+
+    async fn bind_local_stream(
+        &self,
+        _info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Arc<dyn RTPWriter + Send + Sync> {
+        writer
+    }
+
+    async fn unbind_local_stream(&self, _info: &StreamInfo) {}
+
+    /// bind_remote_stream estimates the bitrate of the streams which negotiated goog-remb
+    async fn bind_remote_stream(
+        &self,
+        info: &StreamInfo,
+        reader: Arc<dyn RTPReader + Send + Sync>,
+    ) -> Arc<dyn RTPReader + Send + Sync> {
+        if !stream_support_remb(info) || info.clock_rate == 0 {
+            return reader;
+        }
+
+        let stream = Arc::new(RembStream::new(
+            info.clock_rate,
+            self.initial_bitrate,
+            self.start_time,
+            reader,
+        ));
+        {
+            let mut streams = self.streams.lock().await;
+            streams.insert(info.ssrc, Arc::clone(&stream));
+        }
+
+        stream
+    }
+
+    async fn unbind_remote_stream(&self, info: &StreamInfo) {
+        let mut streams = self.streams.lock().await;
+        streams.remove(&info.ssrc);
+    }
+
+    async fn close(&self) -> Result<()> {
+        let mut close_tx = self.close_tx.lock().await;
+        close_tx.take();
+
+        Ok(())
+    }
+}
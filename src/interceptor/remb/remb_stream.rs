@@ -0,0 +1,128 @@
+use super::*;
+
+/// RembStreamInternal estimates the bitrate of a remote stream from the variation
+/// of the delay of its packets. The departure of the packets is derived from their
+/// rtp timestamps, so the packets of a frame are grouped together.
+struct RembStreamInternal {
+    estimator: DelayBasedBWE,
+    bitrate: Option<u64>,
+    clock_rate: u32,
+    /// base is the first unwrapped rtp timestamp and its local time
+    base: Option<(i64, Instant)>,
+    last_timestamp: i64,
+}
+
+impl RembStreamInternal {
+    fn new(clock_rate: u32, initial_bitrate: u64) -> Self {
+        RembStreamInternal {
+            estimator: DelayBasedBWE::new(initial_bitrate, MIN_BITRATE, MAX_BITRATE),
+            bitrate: None,
+            clock_rate,
+            base: None,
+            last_timestamp: 0,
+        }
+    }
+
+    fn add(&mut self, pkt: &rtp::packet::Packet, arrival: i64, now: Instant) {
+        let timestamp = self.unwrap_timestamp(pkt.header.timestamp);
+        let (base_timestamp, base_time) = *self.base.get_or_insert((timestamp, now));
+        if timestamp < base_timestamp {
+            return;
+        }
+
+        let departure = base_time
+            + Duration::from_secs_f64((timestamp - base_timestamp) as f64 / self.clock_rate as f64);
+        self.bitrate = Some(self.estimator.update(
+            &[Acknowledgment {
+                sequence_number: pkt.header.sequence_number,
+                size: pkt.payload.len(),
+                departure,
+                arrival: Some(arrival),
+            }],
+            now,
+        ));
+    }
+
+    /// unwrap_timestamp extends a rtp timestamp to 64 bits, counting for rollovers
+    fn unwrap_timestamp(&mut self, timestamp: u32) -> i64 {
+        if self.base.is_none() {
+            self.last_timestamp = timestamp as i64;
+            return self.last_timestamp;
+        }
+
+        let diff = timestamp.wrapping_sub(self.last_timestamp as u32) as i32 as i64;
+        let unwrapped = self.last_timestamp + diff;
+        if diff > 0 {
+            self.last_timestamp = unwrapped;
+        }
+        unwrapped
+    }
+}
+
+/// RembStream feeds the packets read from a remote stream to its estimator
+pub(super) struct RembStream {
+    parent_rtp_reader: Arc<dyn RTPReader + Send + Sync>,
+    start_time: Instant,
+    internal: Mutex<RembStreamInternal>,
+}
+
+impl RembStream {
+    pub(super) fn new(
+        clock_rate: u32,
+        initial_bitrate: u64,
+        start_time: Instant,
+        reader: Arc<dyn RTPReader + Send + Sync>,
+    ) -> Self {
+        RembStream {
+            parent_rtp_reader: reader,
+            start_time,
+            internal: Mutex::new(RembStreamInternal::new(clock_rate, initial_bitrate)),
+        }
+    }
+
+    /// bitrate returns the estimated bitrate of the stream, None until a packet is read
+    pub(super) async fn bitrate(&self) -> Option<u64> {
+        let internal = self.internal.lock().await;
+        internal.bitrate
+    }
+}
+
+#[async_trait]
+impl RTPReader for RembStream {
+    async fn read(&self, buf: &mut [u8], a: &Attributes) -> Result<(usize, Attributes)> {
+        let (n, attr) = self.parent_rtp_reader.read(buf, a).await?;
+
+        let pkt = rtp::packet::Packet::unmarshal(&mut &buf[..n])?;
+        let now = Instant::now();
+        {
+            let mut internal = self.internal.lock().await;
+            internal.add(&pkt, (now - self.start_time).as_micros() as i64, now);
+        }
+
+        Ok((n, attr))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_unwrap_timestamp() {
+        let mut s = RembStreamInternal::new(90000, 300_000);
+        let mut unwrap = |timestamp: u32| {
+            let unwrapped = s.unwrap_timestamp(timestamp);
+            s.base.get_or_insert((unwrapped, Instant::now()));
+            unwrapped
+        };
+
+        assert_eq!(unwrap(0xffff_ff00), 0xffff_ff00);
+        assert_eq!(unwrap(0xffff_fff0), 0xffff_fff0);
+        // reordered packet
+        assert_eq!(unwrap(0xffff_ff80), 0xffff_ff80);
+        // rollover
+        assert_eq!(unwrap(0x10), 0x1_0000_0010);
+        assert_eq!(unwrap(0xffff_fff8), 0xffff_fff8);
+        assert_eq!(unwrap(0x20), 0x1_0000_0020);
+    }
+}
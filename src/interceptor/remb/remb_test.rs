@@ -0,0 +1,104 @@
+use super::*;
+use crate::interceptor::mock_stream::MockStream;
+use crate::media::rtp::RTCPFeedback;
+
+use bytes::Bytes;
+
+fn new_packet(sequence_number: u16, timestamp: u32) -> rtp::packet::Packet {
+    rtp::packet::Packet {
+        header: rtp::header::Header {
+            version: 2,
+            ssrc: 1,
+            sequence_number,
+            timestamp,
+            ..Default::default()
+        },
+        payload: Bytes::from(vec![0u8; 1000]),
+    }
+}
+
+#[tokio::test]
+async fn test_remb_interceptor() -> Result<()> {
+    const INTERVAL: Duration = Duration::from_millis(50);
+    let icpr = Remb::builder()
+        .with_interval(INTERVAL)
+        .with_initial_bitrate(500_000)
+        .build("")?;
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 1,
+            clock_rate: 90000,
+            rtcp_feedback: vec![RTCPFeedback {
+                typ: TYPE_RTCP_FB_GOOG_REMB.to_owned(),
+                parameter: "".to_owned(),
+            }],
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    // a frame of 3 packets every 10ms
+    for i in 0..30u16 {
+        stream
+            .receive_rtp(new_packet(i, (i / 3) as u32 * 900))
+            .await;
+        tokio::time::timeout(Duration::from_millis(10), stream.read_rtp())
+            .await?
+            .expect("a packet read");
+        if i % 3 == 2 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    let pkts = tokio::time::timeout(INTERVAL * 5, stream.written_rtcp())
+        .await?
+        .expect("a remb written");
+    if let Some(remb) = pkts[0]
+        .as_any()
+        .downcast_ref::<ReceiverEstimatedMaximumBitrate>()
+    {
+        assert_eq!(remb.ssrcs, vec![1]);
+        // the estimate may grow from the initial bitrate, the stream is received
+        // at a much higher rate without any delay building up
+        assert!(remb.bitrate >= MIN_BITRATE && remb.bitrate <= MAX_BITRATE);
+    } else {
+        panic!("remb expected");
+    }
+
+    stream.close().await
+}
+
+#[tokio::test]
+async fn test_remb_interceptor_without_feedback() -> Result<()> {
+    const INTERVAL: Duration = Duration::from_millis(10);
+    let icpr = Remb::builder().with_interval(INTERVAL).build("")?;
+
+    // goog-remb wasn't negotiated, no remb is sent
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 1,
+            clock_rate: 90000,
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    for i in 0..3u16 {
+        stream.receive_rtp(new_packet(i, 0)).await;
+        tokio::time::timeout(Duration::from_millis(10), stream.read_rtp())
+            .await?
+            .expect("a packet read");
+    }
+
+    assert!(
+        tokio::time::timeout(INTERVAL * 5, stream.written_rtcp())
+            .await
+            .is_err(),
+        "no remb expected"
+    );
+
+    stream.close().await
+}
@@ -0,0 +1,109 @@
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{mpsc, Mutex};
+
+pub mod receiver;
+pub mod sender;
+
+use crate::interceptor::{Interceptor, InterceptorBuilder};
+use receiver::{ReceiverReport, ReceiverReportInternal};
+use sender::{SenderReport, SenderReportInternal};
+
+use anyhow::Result;
+use std::collections::HashMap;
+
+pub type FnTimeGen = Arc<dyn Fn() -> SystemTime + Send + Sync + 'static>;
+
+/// NTP_EPOCH_OFFSET is the number of seconds between the NTP epoch (1900) and the UNIX epoch (1970)
+const NTP_EPOCH_OFFSET: u64 = 2_208_988_800;
+
+/// ReportBuilder can be used to configure the SenderReport and ReceiverReport Interceptors.
+#[derive(Default)]
+pub struct ReportBuilder {
+    is_rr: bool,
+    interval: Option<Duration>,
+    now: Option<FnTimeGen>,
+}
+
+impl ReportBuilder {
+    /// with_interval sets send interval for the interceptor.
+    pub fn with_interval(mut self, interval: Duration) -> ReportBuilder {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// with_now_fn sets an alternative for the SystemTime::now function.
+    pub fn with_now_fn(mut self, now: FnTimeGen) -> ReportBuilder {
+        self.now = Some(now);
+        self
+    }
+
+    fn build_rr(&self) -> ReceiverReport {
+        let (close_tx, close_rx) = mpsc::channel(1);
+        ReceiverReport {
+            internal: Arc::new(ReceiverReportInternal {
+                interval: self.interval.unwrap_or(Duration::from_secs(1)),
+                now: self.now.clone(),
+                streams: Mutex::new(HashMap::new()),
+            }),
+            close_tx: Mutex::new(Some(close_tx)),
+            close_rx: Mutex::new(Some(close_rx)),
+        }
+    }
+
+    fn build_sr(&self) -> SenderReport {
+        let (close_tx, close_rx) = mpsc::channel(1);
+        SenderReport {
+            internal: Arc::new(SenderReportInternal {
+                interval: self.interval.unwrap_or(Duration::from_secs(1)),
+                now: self.now.clone(),
+                streams: Mutex::new(HashMap::new()),
+            }),
+            close_tx: Mutex::new(Some(close_tx)),
+            close_rx: Mutex::new(Some(close_rx)),
+        }
+    }
+}
+
+impl InterceptorBuilder for ReportBuilder {
+    fn build(&self, _id: &str) -> Result<Arc<dyn Interceptor + Send + Sync>> {
+        if self.is_rr {
+            Ok(Arc::new(self.build_rr()))
+        } else {
+            Ok(Arc::new(self.build_sr()))
+        }
+    }
+}
+
+/// ntp_time converts a SystemTime to the 64 bits NTP timestamp format
+fn ntp_time(t: SystemTime) -> u64 {
+    let d = t.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = d.as_secs() + NTP_EPOCH_OFFSET;
+    let frac = ((d.subsec_nanos() as u64) << 32) / 1_000_000_000;
+    (secs << 32) | frac
+}
+
+/// round_trip_time computes the round-trip time to the remote receiver from one of the
+/// reception reports it sent about a local stream, as described in RFC 3550 section 6.4.1.
+/// now is the time when the report has been received. None is returned when the receiver
+/// hasn't got any sender report yet.
+pub fn round_trip_time(
+    now: SystemTime,
+    report: &rtcp::reception_report::ReceptionReport,
+) -> Option<Duration> {
+    if report.last_sender_report == 0 {
+        return None;
+    }
+
+    // the middle 32 bits of the NTP timestamps, in units of 1/65536 seconds
+    let now = (ntp_time(now) >> 16) as u32;
+    let rtt = now
+        .wrapping_sub(report.last_sender_report)
+        .wrapping_sub(report.delay);
+    if rtt >= 1 << 31 {
+        // the clock of the report is ahead of the local one
+        return None;
+    }
+
+    Some(Duration::from_secs_f64(rtt as f64 / 65536.0))
+}
@@ -0,0 +1,191 @@
+mod receiver_stream;
+#[cfg(test)]
+mod receiver_test;
+
+use super::*;
+use crate::interceptor::stream_info::StreamInfo;
+use crate::interceptor::{
+    unmarshal_rtcp, Attributes, RTCPReader, RTCPWriter, RTPReader, RTPWriter,
+};
+use crate::media::rtp::SSRC;
+use receiver_stream::ReceiverStream;
+
+use async_trait::async_trait;
+use util::marshal::Unmarshal;
+
+pub(crate) struct ReceiverReportInternal {
+    pub(crate) interval: Duration,
+    pub(crate) now: Option<FnTimeGen>,
+    pub(crate) streams: Mutex<HashMap<SSRC, Arc<ReceiverStream>>>,
+}
+
+impl ReceiverReportInternal {
+    fn now(&self) -> SystemTime {
+        match &self.now {
+            Some(f) => f(),
+            None => SystemTime::now(),
+        }
+    }
+}
+
+/// ReceiverReportRTCPReader looks for the sender reports of the remote streams,
+/// their NTP timestamps are reflected in the receiver reports
+struct ReceiverReportRTCPReader {
+    parent_rtcp_reader: Arc<dyn RTCPReader + Send + Sync>,
+    internal: Arc<ReceiverReportInternal>,
+}
+
+#[async_trait]
+impl RTCPReader for ReceiverReportRTCPReader {
+    async fn read(&self, buf: &mut [u8], a: &Attributes) -> Result<(usize, Attributes)> {
+        let (n, attr) = self.parent_rtcp_reader.read(buf, a).await?;
+
+        let pkts = unmarshal_rtcp(&buf[..n])?;
+        let now = self.internal.now();
+        for p in &pkts {
+            if let Some(sr) = p
+                .as_any()
+                .downcast_ref::<rtcp::sender_report::SenderReport>()
+            {
+                let stream = {
+                    let streams = self.internal.streams.lock().await;
+                    streams.get(&sr.ssrc).cloned()
+                };
+                if let Some(stream) = stream {
+                    stream.process_sender_report(now, sr).await;
+                }
+            }
+        }
+
+        Ok((n, attr))
+    }
+}
+
+/// ReceiverReport interceptor periodically sends a receiver report for every remote stream.
+pub struct ReceiverReport {
+    pub(crate) internal: Arc<ReceiverReportInternal>,
+
+    pub(crate) close_tx: Mutex<Option<mpsc::Sender<()>>>,
+    pub(crate) close_rx: Mutex<Option<mpsc::Receiver<()>>>,
+}
+
+impl ReceiverReport {
+    /// builder returns a new ReportBuilder.
+    pub fn builder() -> ReportBuilder {
+        ReportBuilder {
+            is_rr: true,
+            ..Default::default()
+        }
+    }
+
+    async fn run(
+        rtcp_writer: Arc<dyn RTCPWriter + Send + Sync>,
+        internal: Arc<ReceiverReportInternal>,
+        mut close_rx: mpsc::Receiver<()>,
+    ) {
+        // the first report is sent after an interval, once there is something to report
+        let mut ticker = tokio::time::interval_at(
+            tokio::time::Instant::now() + internal.interval,
+            internal.interval,
+        );
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    let now = internal.now();
+                    let streams: Vec<Arc<ReceiverStream>> = {
+                        let streams = internal.streams.lock().await;
+                        streams.values().cloned().collect()
+                    };
+                    for stream in streams {
+                        let pkt = stream.generate_report(now).await;
+
+                        let a = Attributes::new();
+                        if let Err(err) = rtcp_writer.write(&[Box::new(pkt)], &a).await {
+                            log::warn!("failed sending receiver report: {}", err);
+                        }
+                    }
+                }
+                _ = close_rx.recv() => {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Interceptor for ReceiverReport {
+    /// bind_rtcp_reader records the time of the sender reports read
+    async fn bind_rtcp_reader(
+        &self,
+        reader: Arc<dyn RTCPReader + Send + Sync>,
+    ) -> Arc<dyn RTCPReader + Send + Sync> {
+        Arc::new(ReceiverReportRTCPReader {
+            parent_rtcp_reader: reader,
+            internal: Arc::clone(&self.internal),
+        })
+    }
+
+    /// bind_rtcp_writer starts sending the reports through the writer of the PeerConnection
+    async fn bind_rtcp_writer(
+        &self,
+        writer: Arc<dyn RTCPWriter + Send + Sync>,
+    ) -> Arc<dyn RTCPWriter + Send + Sync> {
+        let close_rx = {
+            let mut close_rx = self.close_rx.lock().await;
+            close_rx.take()
+        };
+
+        if let Some(close_rx) = close_rx {
+            let writer2 = Arc::clone(&writer);
+            let internal = Arc::clone(&self.internal);
+            tokio::spawn(async move {
+                ReceiverReport::run(writer2, internal, close_rx).await;
+            });
+        }
+
+        writer
+    }
+
+    async fn bind_local_stream(
+        &self,
+        _info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Arc<dyn RTPWriter + Send + Sync> {
+        writer
+    }
+
+    async fn unbind_local_stream(&self, _info: &StreamInfo) {}
+
+    /// bind_remote_stream records the losses and the jitter of the stream
+    async fn bind_remote_stream(
+        &self,
+        info: &StreamInfo,
+        reader: Arc<dyn RTPReader + Send + Sync>,
+    ) -> Arc<dyn RTPReader + Send + Sync> {
+        let stream = Arc::new(ReceiverStream::new(
+            info.ssrc,
+            info.clock_rate,
+            reader,
+            self.internal.now.clone(),
+        ));
+        {
+            let mut streams = self.internal.streams.lock().await;
+            streams.insert(info.ssrc, Arc::clone(&stream));
+        }
+
+        stream
+    }
+
+    async fn unbind_remote_stream(&self, info: &StreamInfo) {
+        let mut streams = self.internal.streams.lock().await;
+        streams.remove(&info.ssrc);
+    }
+
+    async fn close(&self) -> Result<()> {
+        let mut close_tx = self.close_tx.lock().await;
+        close_tx.take();
+
+        Ok(())
+    }
+}
@@ -0,0 +1,205 @@
+use super::*;
+
+struct ReceiverStreamInternal {
+    ssrc: SSRC,
+    receiver_ssrc: SSRC,
+    clock_rate: f64,
+
+    packets: Vec<u64>,
+    started: bool,
+    seq_num_cycles: u16,
+    last_seq_num: u16,
+    last_report_seq_num: u16,
+    last_rtp_time_rtp: u32,
+    last_rtp_time_time: SystemTime,
+    jitter: f64,
+    last_sender_report: u32,
+    last_sender_report_time: Option<SystemTime>,
+    total_lost: u32,
+}
+
+impl ReceiverStreamInternal {
+    fn set_received(&mut self, seq: u16) {
+        let pos = (seq as usize) % (self.packets.len() * 64);
+        self.packets[pos / 64] |= 1u64 << (pos % 64);
+    }
+
+    fn del_received(&mut self, seq: u16) {
+        let pos = (seq as usize) % (self.packets.len() * 64);
+        self.packets[pos / 64] &= !(1u64 << (pos % 64));
+    }
+
+    fn get_received(&self, seq: u16) -> bool {
+        let pos = (seq as usize) % (self.packets.len() * 64);
+        (self.packets[pos / 64] & (1u64 << (pos % 64))) != 0
+    }
+
+    fn process_rtp(&mut self, now: SystemTime, pkt: &rtp::packet::Packet) {
+        let seq = pkt.header.sequence_number;
+        if !self.started {
+            // first packet
+            self.started = true;
+            self.set_received(seq);
+            self.last_seq_num = seq;
+            self.last_report_seq_num = seq.wrapping_sub(1);
+        } else {
+            self.set_received(seq);
+
+            let diff = seq as i32 - self.last_seq_num as i32;
+            // packets which are late by less than 0x0FFF are reordered, not new
+            if !(-0x0FFF..=0).contains(&diff) {
+                if diff < -0x0FFF {
+                    // the sequence number wrapped around
+                    self.seq_num_cycles = self.seq_num_cycles.wrapping_add(1);
+                }
+
+                // the packets skipped are missing, until they are received late
+                let mut i = self.last_seq_num.wrapping_add(1);
+                while i != seq {
+                    self.del_received(i);
+                    i = i.wrapping_add(1);
+                }
+
+                self.last_seq_num = seq;
+            }
+
+            // interarrival jitter, see https://tools.ietf.org/html/rfc3550#page-39
+            let arrival = now
+                .duration_since(self.last_rtp_time_time)
+                .unwrap_or_default()
+                .as_secs_f64()
+                * self.clock_rate;
+            let transit = pkt.header.timestamp.wrapping_sub(self.last_rtp_time_rtp) as i32 as f64;
+            self.jitter += ((arrival - transit).abs() - self.jitter) / 16.0;
+        }
+
+        self.last_rtp_time_rtp = pkt.header.timestamp;
+        self.last_rtp_time_time = now;
+    }
+
+    fn process_sender_report(&mut self, now: SystemTime, sr: &rtcp::sender_report::SenderReport) {
+        // the middle 32 bits of the NTP timestamp are reflected back in the reports
+        self.last_sender_report = (sr.ntp_time >> 16) as u32;
+        self.last_sender_report_time = Some(now);
+    }
+
+    fn generate_report(&mut self, now: SystemTime) -> rtcp::receiver_report::ReceiverReport {
+        let total_since_report = self.last_seq_num.wrapping_sub(self.last_report_seq_num) as u32;
+        let mut total_lost_since_report = 0u32;
+        let mut i = self.last_report_seq_num.wrapping_add(1);
+        while i != self.last_seq_num {
+            if !self.get_received(i) {
+                total_lost_since_report += 1;
+            }
+            i = i.wrapping_add(1);
+        }
+
+        // the cumulative number of packets lost is a 24 bits field
+        self.total_lost = (self.total_lost + total_lost_since_report).min(0xFFFFFF);
+
+        let fraction_lost = (total_lost_since_report * 256)
+            .checked_div(total_since_report)
+            .map_or(0, |f| f.min(255) as u8);
+
+        // the delay since the last sender report is expressed in units of 1/65536 seconds
+        let delay = match self.last_sender_report_time {
+            Some(t) => (now.duration_since(t).unwrap_or_default().as_secs_f64() * 65536.0) as u32,
+            None => 0,
+        };
+
+        let r = rtcp::receiver_report::ReceiverReport {
+            ssrc: self.receiver_ssrc,
+            reports: vec![rtcp::reception_report::ReceptionReport {
+                ssrc: self.ssrc,
+                last_sequence_number: (self.seq_num_cycles as u32) << 16
+                    | (self.last_seq_num as u32),
+                last_sender_report: self.last_sender_report,
+                fraction_lost,
+                total_lost: self.total_lost,
+                delay,
+                jitter: self.jitter as u32,
+            }],
+            ..Default::default()
+        };
+
+        self.last_report_seq_num = self.last_seq_num;
+
+        r
+    }
+}
+
+/// ReceiverStream records the statistics of the packets read from a remote stream
+pub(crate) struct ReceiverStream {
+    parent_rtp_reader: Arc<dyn RTPReader + Send + Sync>,
+    now: Option<FnTimeGen>,
+
+    internal: Mutex<ReceiverStreamInternal>,
+}
+
+impl ReceiverStream {
+    pub(crate) fn new(
+        ssrc: SSRC,
+        clock_rate: u32,
+        reader: Arc<dyn RTPReader + Send + Sync>,
+        now: Option<FnTimeGen>,
+    ) -> Self {
+        ReceiverStream {
+            parent_rtp_reader: reader,
+            now,
+
+            internal: Mutex::new(ReceiverStreamInternal {
+                ssrc,
+                receiver_ssrc: rand::random::<u32>(),
+                clock_rate: clock_rate as f64,
+
+                packets: vec![0u64; 128],
+                started: false,
+                seq_num_cycles: 0,
+                last_seq_num: 0,
+                last_report_seq_num: 0,
+                last_rtp_time_rtp: 0,
+                last_rtp_time_time: SystemTime::UNIX_EPOCH,
+                jitter: 0.0,
+                last_sender_report: 0,
+                last_sender_report_time: None,
+                total_lost: 0,
+            }),
+        }
+    }
+
+    pub(crate) async fn process_sender_report(
+        &self,
+        now: SystemTime,
+        sr: &rtcp::sender_report::SenderReport,
+    ) {
+        let mut internal = self.internal.lock().await;
+        internal.process_sender_report(now, sr);
+    }
+
+    pub(crate) async fn generate_report(
+        &self,
+        now: SystemTime,
+    ) -> rtcp::receiver_report::ReceiverReport {
+        let mut internal = self.internal.lock().await;
+        internal.generate_report(now)
+    }
+}
+
+#[async_trait]
+impl RTPReader for ReceiverStream {
+    async fn read(&self, buf: &mut [u8], a: &Attributes) -> Result<(usize, Attributes)> {
+        let (n, attr) = self.parent_rtp_reader.read(buf, a).await?;
+
+        let pkt = rtp::packet::Packet::unmarshal(&mut &buf[..n])?;
+        let now = match &self.now {
+            Some(f) => f(),
+            None => SystemTime::now(),
+        };
+        {
+            let mut internal = self.internal.lock().await;
+            internal.process_rtp(now, &pkt);
+        }
+
+        Ok((n, attr))
+    }
+}
@@ -0,0 +1,142 @@
+use super::*;
+use crate::interceptor::mock_stream::{mock_time, MockStream};
+
+async fn new_stream(time_gen: FnTimeGen) -> Result<Arc<MockStream>> {
+    let icpr = ReceiverReport::builder()
+        .with_interval(Duration::from_millis(50))
+        .with_now_fn(time_gen)
+        .build("")?;
+
+    Ok(MockStream::new(
+        &StreamInfo {
+            ssrc: 123456,
+            clock_rate: 90000,
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await)
+}
+
+async fn receive_rtp(stream: &MockStream, sequence_numbers: &[u16]) {
+    for seq in sequence_numbers {
+        stream
+            .receive_rtp(rtp::packet::Packet {
+                header: rtp::header::Header {
+                    sequence_number: *seq,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .await;
+        // the packet has gone through the interceptor once it is read
+        let _ = stream.read_rtp().await;
+    }
+}
+
+async fn written_reception_report(stream: &MockStream) -> rtcp::reception_report::ReceptionReport {
+    let pkts = stream
+        .written_rtcp()
+        .await
+        .expect("a receiver report written");
+    assert_eq!(pkts.len(), 1);
+    if let Some(rr) = pkts[0]
+        .as_any()
+        .downcast_ref::<rtcp::receiver_report::ReceiverReport>()
+    {
+        assert_eq!(rr.reports.len(), 1);
+        rr.reports[0].clone()
+    } else {
+        panic!("receiver report expected");
+    }
+}
+
+#[tokio::test]
+async fn test_receiver_interceptor_after_rtp_packets() -> Result<()> {
+    let (_, time_gen) = mock_time();
+    let stream = new_stream(time_gen).await?;
+
+    receive_rtp(&stream, &(0..10).collect::<Vec<u16>>()).await;
+
+    let report = written_reception_report(&stream).await;
+    assert_eq!(report.ssrc, 123456);
+    assert_eq!(report.last_sequence_number, 9);
+    assert_eq!(report.last_sender_report, 0);
+    assert_eq!(report.fraction_lost, 0);
+    assert_eq!(report.total_lost, 0);
+    assert_eq!(report.delay, 0);
+    assert_eq!(report.jitter, 0);
+
+    stream.close().await
+}
+
+#[tokio::test]
+async fn test_receiver_interceptor_packet_loss() -> Result<()> {
+    let (_, time_gen) = mock_time();
+    let stream = new_stream(time_gen).await?;
+
+    receive_rtp(&stream, &[1, 2, 4, 5, 6, 8, 9, 10]).await;
+
+    let report = written_reception_report(&stream).await;
+    assert_eq!(report.last_sequence_number, 10);
+    // 2 packets lost out of 10
+    assert_eq!(report.fraction_lost, (2 * 256 / 10) as u8);
+    assert_eq!(report.total_lost, 2);
+
+    // a lost packet received late isn't counted in the next report
+    receive_rtp(&stream, &[7, 11, 12]).await;
+    let report = written_reception_report(&stream).await;
+    assert_eq!(report.last_sequence_number, 12);
+    assert_eq!(report.fraction_lost, 0);
+    assert_eq!(report.total_lost, 2);
+
+    stream.close().await
+}
+
+#[tokio::test]
+async fn test_receiver_interceptor_overflow() -> Result<()> {
+    let (_, time_gen) = mock_time();
+    let stream = new_stream(time_gen).await?;
+
+    receive_rtp(&stream, &[0xfffe, 0x0001]).await;
+
+    let report = written_reception_report(&stream).await;
+    assert_eq!(report.last_sequence_number, 1 << 16 | 0x0001);
+    // 0xffff and 0x0000 are missing
+    assert_eq!(report.total_lost, 2);
+
+    stream.close().await
+}
+
+#[tokio::test]
+async fn test_receiver_interceptor_after_sender_report() -> Result<()> {
+    let (now, time_gen) = mock_time();
+    let stream = new_stream(time_gen).await?;
+
+    receive_rtp(&stream, &[0, 1]).await;
+
+    let ntp = ntp_time(*now.lock().unwrap());
+    stream
+        .receive_rtcp(vec![Box::new(rtcp::sender_report::SenderReport {
+            ssrc: 123456,
+            ntp_time: ntp,
+            ..Default::default()
+        })])
+        .await;
+    // the report is sent one second after the sender report is received
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    {
+        let mut now = now.lock().unwrap();
+        *now += Duration::from_secs(1);
+    }
+
+    let mut report = written_reception_report(&stream).await;
+    if report.last_sender_report == 0 {
+        // the sender report was read after the first report
+        report = written_reception_report(&stream).await;
+    }
+    assert_eq!(report.last_sender_report, (ntp >> 16) as u32);
+    assert_eq!(report.delay, 65536);
+
+    stream.close().await
+}
@@ -0,0 +1,214 @@
+mod sender_stream;
+#[cfg(test)]
+mod sender_test;
+
+use super::*;
+use crate::interceptor::stream_info::StreamInfo;
+use crate::interceptor::{
+    unmarshal_rtcp, Attributes, RTCPReader, RTCPWriter, RTPReader, RTPWriter,
+};
+use crate::media::rtp::SSRC;
+use sender_stream::SenderStream;
+
+use async_trait::async_trait;
+
+pub(crate) struct SenderReportInternal {
+    pub(crate) interval: Duration,
+    pub(crate) now: Option<FnTimeGen>,
+    pub(crate) streams: Mutex<HashMap<SSRC, Arc<SenderStream>>>,
+}
+
+impl SenderReportInternal {
+    fn now(&self) -> SystemTime {
+        match &self.now {
+            Some(f) => f(),
+            None => SystemTime::now(),
+        }
+    }
+}
+
+/// SenderReportRTCPReader looks for the receiver reports about the local streams,
+/// to keep track of the round-trip time to the remote receiver
+struct SenderReportRTCPReader {
+    parent_rtcp_reader: Arc<dyn RTCPReader + Send + Sync>,
+    internal: Arc<SenderReportInternal>,
+}
+
+#[async_trait]
+impl RTCPReader for SenderReportRTCPReader {
+    async fn read(&self, buf: &mut [u8], a: &Attributes) -> Result<(usize, Attributes)> {
+        let (n, attr) = self.parent_rtcp_reader.read(buf, a).await?;
+
+        let pkts = unmarshal_rtcp(&buf[..n])?;
+        let now = self.internal.now();
+        for p in &pkts {
+            let reports = if let Some(rr) = p
+                .as_any()
+                .downcast_ref::<rtcp::receiver_report::ReceiverReport>()
+            {
+                &rr.reports
+            } else if let Some(sr) = p
+                .as_any()
+                .downcast_ref::<rtcp::sender_report::SenderReport>()
+            {
+                &sr.reports
+            } else {
+                continue;
+            };
+
+            for report in reports {
+                let stream = {
+                    let streams = self.internal.streams.lock().await;
+                    streams.get(&report.ssrc).cloned()
+                };
+                if let Some(stream) = stream {
+                    stream.process_reception_report(now, report).await;
+                }
+            }
+        }
+
+        Ok((n, attr))
+    }
+}
+
+/// SenderReport interceptor periodically sends a sender report for every local stream.
+pub struct SenderReport {
+    pub(crate) internal: Arc<SenderReportInternal>,
+
+    pub(crate) close_tx: Mutex<Option<mpsc::Sender<()>>>,
+    pub(crate) close_rx: Mutex<Option<mpsc::Receiver<()>>>,
+}
+
+impl SenderReport {
+    /// builder returns a new ReportBuilder.
+    pub fn builder() -> ReportBuilder {
+        ReportBuilder {
+            is_rr: false,
+            ..Default::default()
+        }
+    }
+
+    /// round_trip_time returns the last round-trip time computed from the reports
+    /// of the remote receiver of the given local stream
+    pub async fn round_trip_time(&self, ssrc: SSRC) -> Option<Duration> {
+        let stream = {
+            let streams = self.internal.streams.lock().await;
+            streams.get(&ssrc).cloned()
+        };
+        match stream {
+            Some(stream) => stream.round_trip_time().await,
+            None => None,
+        }
+    }
+
+    async fn run(
+        rtcp_writer: Arc<dyn RTCPWriter + Send + Sync>,
+        internal: Arc<SenderReportInternal>,
+        mut close_rx: mpsc::Receiver<()>,
+    ) {
+        // the first report is sent after an interval, once there is something to report
+        let mut ticker = tokio::time::interval_at(
+            tokio::time::Instant::now() + internal.interval,
+            internal.interval,
+        );
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    let now = internal.now();
+                    let streams: Vec<Arc<SenderStream>> = {
+                        let streams = internal.streams.lock().await;
+                        streams.values().cloned().collect()
+                    };
+                    for stream in streams {
+                        let pkt = stream.generate_report(now).await;
+
+                        let a = Attributes::new();
+                        if let Err(err) = rtcp_writer.write(&[Box::new(pkt)], &a).await {
+                            log::warn!("failed sending sender report: {}", err);
+                        }
+                    }
+                }
+                _ = close_rx.recv() => {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Interceptor for SenderReport {
+    /// bind_rtcp_reader computes the round-trip time from the reports read
+    async fn bind_rtcp_reader(
+        &self,
+        reader: Arc<dyn RTCPReader + Send + Sync>,
+    ) -> Arc<dyn RTCPReader + Send + Sync> {
+        Arc::new(SenderReportRTCPReader {
+            parent_rtcp_reader: reader,
+            internal: Arc::clone(&self.internal),
+        })
+    }
+
+    /// bind_rtcp_writer starts sending the reports through the writer of the PeerConnection
+    async fn bind_rtcp_writer(
+        &self,
+        writer: Arc<dyn RTCPWriter + Send + Sync>,
+    ) -> Arc<dyn RTCPWriter + Send + Sync> {
+        let close_rx = {
+            let mut close_rx = self.close_rx.lock().await;
+            close_rx.take()
+        };
+
+        if let Some(close_rx) = close_rx {
+            let writer2 = Arc::clone(&writer);
+            let internal = Arc::clone(&self.internal);
+            tokio::spawn(async move {
+                SenderReport::run(writer2, internal, close_rx).await;
+            });
+        }
+
+        writer
+    }
+
+    /// bind_local_stream counts the packets and octets sent on the stream
+    async fn bind_local_stream(
+        &self,
+        info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Arc<dyn RTPWriter + Send + Sync> {
+        let stream = Arc::new(SenderStream::new(
+            info.ssrc,
+            info.clock_rate,
+            writer,
+            self.internal.now.clone(),
+        ));
+        {
+            let mut streams = self.internal.streams.lock().await;
+            streams.insert(info.ssrc, Arc::clone(&stream));
+        }
+
+        stream
+    }
+
+    async fn unbind_local_stream(&self, info: &StreamInfo) {
+        let mut streams = self.internal.streams.lock().await;
+        streams.remove(&info.ssrc);
+    }
+
+    async fn bind_remote_stream(
+        &self,
+        _info: &StreamInfo,
+        reader: Arc<dyn RTPReader + Send + Sync>,
+    ) -> Arc<dyn RTPReader + Send + Sync> {
+        reader
+    }
+
+    async fn unbind_remote_stream(&self, _info: &StreamInfo) {}
+
+    async fn close(&self) -> Result<()> {
+        let mut close_tx = self.close_tx.lock().await;
+        close_tx.take();
+
+        Ok(())
+    }
+}
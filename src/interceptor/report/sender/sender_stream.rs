@@ -0,0 +1,117 @@
+use super::*;
+
+struct SenderStreamInternal {
+    ssrc: SSRC,
+    clock_rate: f64,
+
+    /// data from rtp packets
+    last_rtp_time_rtp: u32,
+    last_rtp_time_time: SystemTime,
+    packet_count: u32,
+    octet_count: u32,
+
+    round_trip_time: Option<Duration>,
+}
+
+impl SenderStreamInternal {
+    fn process_rtp(&mut self, now: SystemTime, pkt: &rtp::packet::Packet) {
+        // always update time to minimize errors
+        self.last_rtp_time_rtp = pkt.header.timestamp;
+        self.last_rtp_time_time = now;
+
+        // the counters wrap around, as allowed by RFC 3550
+        self.packet_count = self.packet_count.wrapping_add(1);
+        self.octet_count = self.octet_count.wrapping_add(pkt.payload.len() as u32);
+    }
+
+    fn generate_report(&self, now: SystemTime) -> rtcp::sender_report::SenderReport {
+        // the RTP timestamp of the report is extrapolated from the last packet sent
+        let elapsed = now
+            .duration_since(self.last_rtp_time_time)
+            .unwrap_or_default()
+            .as_secs_f64();
+
+        rtcp::sender_report::SenderReport {
+            ssrc: self.ssrc,
+            ntp_time: ntp_time(now),
+            rtp_time: self
+                .last_rtp_time_rtp
+                .wrapping_add((elapsed * self.clock_rate) as u32),
+            packet_count: self.packet_count,
+            octet_count: self.octet_count,
+            ..Default::default()
+        }
+    }
+}
+
+/// SenderStream counts the packets written to a local stream
+pub(crate) struct SenderStream {
+    next_rtp_writer: Arc<dyn RTPWriter + Send + Sync>,
+    now: Option<FnTimeGen>,
+
+    internal: Mutex<SenderStreamInternal>,
+}
+
+impl SenderStream {
+    pub(crate) fn new(
+        ssrc: SSRC,
+        clock_rate: u32,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+        now: Option<FnTimeGen>,
+    ) -> Self {
+        SenderStream {
+            next_rtp_writer: writer,
+            now,
+
+            internal: Mutex::new(SenderStreamInternal {
+                ssrc,
+                clock_rate: clock_rate as f64,
+                last_rtp_time_rtp: 0,
+                last_rtp_time_time: SystemTime::UNIX_EPOCH,
+                packet_count: 0,
+                octet_count: 0,
+                round_trip_time: None,
+            }),
+        }
+    }
+
+    pub(crate) async fn generate_report(
+        &self,
+        now: SystemTime,
+    ) -> rtcp::sender_report::SenderReport {
+        let internal = self.internal.lock().await;
+        internal.generate_report(now)
+    }
+
+    pub(crate) async fn process_reception_report(
+        &self,
+        now: SystemTime,
+        report: &rtcp::reception_report::ReceptionReport,
+    ) {
+        if let Some(rtt) = round_trip_time(now, report) {
+            let mut internal = self.internal.lock().await;
+            internal.round_trip_time = Some(rtt);
+        }
+    }
+
+    pub(crate) async fn round_trip_time(&self) -> Option<Duration> {
+        let internal = self.internal.lock().await;
+        internal.round_trip_time
+    }
+}
+
+#[async_trait]
+impl RTPWriter for SenderStream {
+    async fn write(&self, pkt: &rtp::packet::Packet, a: &Attributes) -> Result<usize> {
+        let now = match &self.now {
+            Some(f) => f(),
+            None => SystemTime::now(),
+        };
+        {
+            let mut internal = self.internal.lock().await;
+            internal.process_rtp(now, pkt);
+        }
+
+        self.next_rtp_writer.write(pkt, a).await
+    }
+}
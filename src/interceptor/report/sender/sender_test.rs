@@ -0,0 +1,145 @@
+use super::*;
+use crate::interceptor::mock_stream::{mock_time, MockStream};
+
+use bytes::Bytes;
+use std::sync::Mutex as SyncMutex;
+
+fn advance(now: &SyncMutex<SystemTime>, d: Duration) {
+    let mut now = now.lock().unwrap();
+    *now += d;
+}
+
+#[tokio::test]
+async fn test_sender_interceptor_after_rtp_packets() -> Result<()> {
+    let (now, time_gen) = mock_time();
+    let icpr = SenderReport::builder()
+        .with_interval(Duration::from_millis(50))
+        .with_now_fn(time_gen)
+        .build("")?;
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 123456,
+            clock_rate: 90000,
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    for i in 0..10u16 {
+        stream
+            .write_rtp(&rtp::packet::Packet {
+                header: rtp::header::Header {
+                    sequence_number: i,
+                    timestamp: 3000,
+                    ..Default::default()
+                },
+                payload: Bytes::from_static(b"\x00\x00"),
+            })
+            .await?;
+    }
+
+    // one second after the last packet, the RTP clock moved by one second too
+    advance(&now, Duration::from_secs(1));
+
+    let pkts = stream
+        .written_rtcp()
+        .await
+        .expect("a sender report written");
+    assert_eq!(pkts.len(), 1);
+    if let Some(sr) = pkts[0]
+        .as_any()
+        .downcast_ref::<rtcp::sender_report::SenderReport>()
+    {
+        assert_eq!(sr.ssrc, 123456);
+        assert_eq!(sr.ntp_time, ntp_time(*now.lock().unwrap()));
+        assert_eq!(sr.rtp_time, 3000 + 90000);
+        assert_eq!(sr.packet_count, 10);
+        assert_eq!(sr.octet_count, 20);
+    } else {
+        panic!("sender report expected");
+    }
+
+    stream.close().await
+}
+
+#[tokio::test]
+async fn test_sender_interceptor_round_trip_time() -> Result<()> {
+    let (now, time_gen) = mock_time();
+    let icpr = Arc::new(
+        SenderReport::builder()
+            .with_interval(Duration::from_secs(10))
+            .with_now_fn(time_gen)
+            .build_sr(),
+    );
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 123456,
+            clock_rate: 90000,
+            ..Default::default()
+        },
+        Arc::clone(&icpr) as Arc<dyn Interceptor + Send + Sync>,
+    )
+    .await;
+    assert_eq!(icpr.round_trip_time(123456).await, None);
+
+    // the receiver held the sender report for one second, and its report took
+    // half a second to come back
+    let last_sender_report = (ntp_time(*now.lock().unwrap()) >> 16) as u32;
+    advance(&now, Duration::from_millis(1500));
+    stream
+        .receive_rtcp(vec![Box::new(rtcp::receiver_report::ReceiverReport {
+            ssrc: 654321,
+            reports: vec![rtcp::reception_report::ReceptionReport {
+                ssrc: 123456,
+                last_sender_report,
+                delay: 65536,
+                ..Default::default()
+            }],
+            ..Default::default()
+        })])
+        .await;
+
+    let mut rtt = None;
+    for _ in 0..10 {
+        rtt = icpr.round_trip_time(123456).await;
+        if rtt.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let rtt = rtt.expect("a round-trip time");
+    assert!(
+        rtt > Duration::from_millis(499) && rtt < Duration::from_millis(501),
+        "unexpected round-trip time {:?}",
+        rtt
+    );
+
+    stream.close().await
+}
+
+#[test]
+fn test_round_trip_time() {
+    let now = UNIX_EPOCH + Duration::from_secs(1_256_256_000);
+
+    // no sender report has been received yet
+    let report = rtcp::reception_report::ReceptionReport::default();
+    assert_eq!(round_trip_time(now, &report), None);
+
+    // the report can't be from the future
+    let report = rtcp::reception_report::ReceptionReport {
+        last_sender_report: (ntp_time(now + Duration::from_secs(1)) >> 16) as u32,
+        ..Default::default()
+    };
+    assert_eq!(round_trip_time(now, &report), None);
+
+    let report = rtcp::reception_report::ReceptionReport {
+        last_sender_report: (ntp_time(now - Duration::from_secs(2)) >> 16) as u32,
+        delay: 65536,
+        ..Default::default()
+    };
+    let rtt = round_trip_time(now, &report).expect("a round-trip time");
+    assert!(rtt > Duration::from_millis(999) && rtt < Duration::from_millis(1001));
+}
@@ -0,0 +1,49 @@
+use super::Attributes;
+use crate::media::rtp::rtp_codec::{RTPCodecCapability, RTPHeaderExtensionParameter};
+use crate::media::rtp::{PayloadType, RTCPFeedback, SSRC};
+
+/// StreamInfo is the Context passed when a StreamLocal or StreamRemote has been Binded or Unbinded
+#[derive(Default, Debug, Clone)]
+pub struct StreamInfo {
+    pub id: String,
+    pub attributes: Attributes,
+    pub ssrc: SSRC,
+    pub payload_type: PayloadType,
+    pub rtp_header_extensions: Vec<RTPHeaderExtensionParameter>,
+    pub mime_type: String,
+    pub clock_rate: u32,
+    pub channels: u16,
+    pub sdp_fmtp_line: String,
+    pub rtcp_feedback: Vec<RTCPFeedback>,
+    /// ssrc_retransmission and payload_type_retransmission describe the RTX stream the
+    /// packets of the stream are retransmitted on, they are 0 without RTX
+    pub ssrc_retransmission: SSRC,
+    pub payload_type_retransmission: PayloadType,
+}
+
+impl StreamInfo {
+    /// new describes the stream of the given SSRC, which is sent or received
+    /// with the given codec and header extensions
+    pub(crate) fn new(
+        id: String,
+        ssrc: SSRC,
+        payload_type: PayloadType,
+        codec: &RTPCodecCapability,
+        header_extensions: &[RTPHeaderExtensionParameter],
+    ) -> Self {
+        StreamInfo {
+            id,
+            attributes: Attributes::new(),
+            ssrc,
+            payload_type,
+            rtp_header_extensions: header_extensions.to_vec(),
+            mime_type: codec.mime_type.clone(),
+            clock_rate: codec.clock_rate,
+            channels: codec.channels,
+            sdp_fmtp_line: codec.sdp_fmtp_line.clone(),
+            rtcp_feedback: codec.rtcp_feedback.clone(),
+            ssrc_retransmission: 0,
+            payload_type_retransmission: 0,
+        }
+    }
+}
@@ -0,0 +1,203 @@
+#[cfg(test)]
+mod receiver_test;
+
+use super::{transport_cc_extension_id, Recorder};
+use crate::interceptor::stream_info::StreamInfo;
+use crate::interceptor::*;
+use crate::media::rtp::SSRC;
+
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+use tokio::time::Instant;
+use util::marshal::Unmarshal;
+
+/// ReceiverBuilder can be used to configure the TWCC Receiver Interceptor
+#[derive(Default)]
+pub struct ReceiverBuilder {
+    interval: Option<Duration>,
+}
+
+impl ReceiverBuilder {
+    /// with_interval sets send interval for the interceptor.
+    pub fn with_interval(mut self, interval: Duration) -> ReceiverBuilder {
+        self.interval = Some(interval);
+        self
+    }
+}
+
+impl InterceptorBuilder for ReceiverBuilder {
+    fn build(&self, _id: &str) -> Result<Arc<dyn Interceptor + Send + Sync>> {
+        let (close_tx, close_rx) = mpsc::channel(1);
+        let (packet_tx, packet_rx) = mpsc::channel(1000);
+        Ok(Arc::new(Receiver {
+            interval: self.interval.unwrap_or(Duration::from_millis(100)),
+            start_time: Instant::now(),
+            packet_tx,
+            packet_rx: Mutex::new(Some(packet_rx)),
+            close_tx: Mutex::new(Some(close_tx)),
+            close_rx: Mutex::new(Some(close_rx)),
+        }))
+    }
+}
+
+/// Packet is the arrival of a packet with a transport wide sequence number
+struct Packet {
+    ssrc: SSRC,
+    sequence_number: u16,
+    arrival_time: i64,
+}
+
+/// Receiver sends transport wide congestion control feedback of the packets
+/// received on all the remote streams, as specified in
+/// https://datatracker.ietf.org/doc/html/draft-holmer-rmcat-transport-wide-cc-extensions-01
+pub struct Receiver {
+    interval: Duration,
+    start_time: Instant,
+
+    packet_tx: mpsc::Sender<Packet>,
+    packet_rx: Mutex<Option<mpsc::Receiver<Packet>>>,
+    close_tx: Mutex<Option<mpsc::Sender<()>>>,
+    close_rx: Mutex<Option<mpsc::Receiver<()>>>,
+}
+
+impl Receiver {
+    /// builder returns a new ReceiverBuilder.
+    pub fn builder() -> ReceiverBuilder {
+        ReceiverBuilder::default()
+    }
+
+    async fn run(
+        rtcp_writer: Arc<dyn RTCPWriter + Send + Sync>,
+        interval: Duration,
+        mut packet_rx: mpsc::Receiver<Packet>,
+        mut close_rx: mpsc::Receiver<()>,
+    ) {
+        let mut recorder = Recorder::new(rand::random::<u32>());
+        let a = Attributes::new();
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            tokio::select! {
+                _ = close_rx.recv() => {
+                    return;
+                }
+                p = packet_rx.recv() => {
+                    if let Some(p) = p {
+                        recorder.record(p.ssrc, p.sequence_number, p.arrival_time);
+                    }
+                }
+                _ = ticker.tick() => {
+                    let pkts = recorder.build_feedback_packet();
+                    if pkts.is_empty() {
+                        continue;
+                    }
+
+                    if let Err(err) = rtcp_writer.write(&pkts, &a).await {
+                        log::warn!("failed sending transport-cc feedback: {}", err);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// ReceiverStream reports the arrival of the packets of a remote stream
+struct ReceiverStream {
+    parent_rtp_reader: Arc<dyn RTPReader + Send + Sync>,
+    hdr_ext_id: u8,
+    ssrc: SSRC,
+    packet_tx: mpsc::Sender<Packet>,
+    start_time: Instant,
+}
+
+#[async_trait]
+impl RTPReader for ReceiverStream {
+    async fn read(&self, buf: &mut [u8], a: &Attributes) -> Result<(usize, Attributes)> {
+        let (n, attr) = self.parent_rtp_reader.read(buf, a).await?;
+
+        let pkt = rtp::packet::Packet::unmarshal(&mut &buf[..n])?;
+        if let Some(ext) = pkt.header.get_extension(self.hdr_ext_id) {
+            if ext.len() >= 2 {
+                let _ = self
+                    .packet_tx
+                    .send(Packet {
+                        ssrc: self.ssrc,
+                        sequence_number: u16::from_be_bytes([ext[0], ext[1]]),
+                        arrival_time: (Instant::now() - self.start_time).as_micros() as i64,
+                    })
+                    .await;
+            }
+        }
+
+        Ok((n, attr))
+    }
+}
+
+#[async_trait]
+impl Interceptor for Receiver {
+    async fn bind_rtcp_reader(
+        &self,
+        reader: Arc<dyn RTCPReader + Send + Sync>,
+    ) -> Arc<dyn RTCPReader + Send + Sync> {
+        reader
+    }
+
+    /// bind_rtcp_writer starts sending the feedback through the writer of the PeerConnection
+    async fn bind_rtcp_writer(
+        &self,
+        writer: Arc<dyn RTCPWriter + Send + Sync>,
+    ) -> Arc<dyn RTCPWriter + Send + Sync> {
+        let (packet_rx, close_rx) = {
+            let mut packet_rx = self.packet_rx.lock().await;
+            let mut close_rx = self.close_rx.lock().await;
+            (packet_rx.take(), close_rx.take())
+        };
+
+        if let (Some(packet_rx), Some(close_rx)) = (packet_rx, close_rx) {
+            let writer2 = Arc::clone(&writer);
+            let interval = self.interval;
+            tokio::spawn(async move {
+                Receiver::run(writer2, interval, packet_rx, close_rx).await;
+            });
+        }
+
+        writer
+    }
+
+    async fn bind_local_stream(
+        &self,
+        _info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Arc<dyn RTPWriter + Send + Sync> {
+        writer
+    }
+
+    async fn unbind_local_stream(&self, _info: &StreamInfo) {}
+
+    /// bind_remote_stream records the arrival of the packets of the streams which
+    /// negotiated the transport-cc header extension
+    async fn bind_remote_stream(
+        &self,
+        info: &StreamInfo,
+        reader: Arc<dyn RTPReader + Send + Sync>,
+    ) -> Arc<dyn RTPReader + Send + Sync> {
+        match transport_cc_extension_id(info) {
+            Some(hdr_ext_id) => Arc::new(ReceiverStream {
+                parent_rtp_reader: reader,
+                hdr_ext_id,
+                ssrc: info.ssrc,
+                packet_tx: self.packet_tx.clone(),
+                start_time: self.start_time,
+            }),
+            None => reader,
+        }
+    }
+
+    async fn unbind_remote_stream(&self, _info: &StreamInfo) {}
+
+    async fn close(&self) -> Result<()> {
+        let mut close_tx = self.close_tx.lock().await;
+        close_tx.take();
+
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+use super::*;
+use crate::interceptor::mock_stream::MockStream;
+use crate::media::rtp::rtp_codec::RTPHeaderExtensionParameter;
+
+use bytes::Bytes;
+use rtcp::transport_feedbacks::transport_layer_cc::TransportLayerCc;
+
+#[tokio::test]
+async fn test_receiver_interceptor() -> Result<()> {
+    const INTERVAL: Duration = Duration::from_millis(10);
+    let icpr = Receiver::builder().with_interval(INTERVAL).build("")?;
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 1,
+            rtp_header_extensions: vec![RTPHeaderExtensionParameter {
+                uri: sdp::extmap::TRANSPORT_CC_URI.to_owned(),
+                id: 1,
+            }],
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    for seq_num in &[0u16, 1, 3, 4] {
+        let mut pkt = rtp::packet::Packet::default();
+        pkt.header
+            .set_extension(1, Bytes::copy_from_slice(&seq_num.to_be_bytes()))?;
+        stream.receive_rtp(pkt).await;
+
+        tokio::time::timeout(Duration::from_millis(10), stream.read_rtp())
+            .await?
+            .expect("a packet read");
+    }
+
+    let pkts = tokio::time::timeout(INTERVAL * 5, stream.written_rtcp())
+        .await?
+        .expect("a feedback written");
+    if let Some(cc) = pkts[0].as_any().downcast_ref::<TransportLayerCc>() {
+        assert_eq!(cc.media_ssrc, 1);
+        assert_eq!(cc.base_sequence_number, 0);
+        // the missing packet 2 is reported too
+        assert_eq!(cc.packet_status_count, 5);
+        assert_eq!(cc.recv_deltas.len(), 4);
+    } else {
+        panic!("transport layer cc expected");
+    }
+
+    stream.close().await
+}
+
+#[tokio::test]
+async fn test_receiver_interceptor_without_extension() -> Result<()> {
+    const INTERVAL: Duration = Duration::from_millis(10);
+    let icpr = Receiver::builder().with_interval(INTERVAL).build("")?;
+
+    // the transport-cc header extension wasn't negotiated, no feedback is sent
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 1,
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    for seq_num in &[0u16, 1, 2] {
+        let mut pkt = rtp::packet::Packet::default();
+        pkt.header
+            .set_extension(1, Bytes::copy_from_slice(&seq_num.to_be_bytes()))?;
+        stream.receive_rtp(pkt).await;
+
+        tokio::time::timeout(Duration::from_millis(10), stream.read_rtp())
+            .await?
+            .expect("a packet read");
+    }
+
+    assert!(
+        tokio::time::timeout(INTERVAL * 3, stream.written_rtcp())
+            .await
+            .is_err(),
+        "no feedback expected"
+    );
+
+    stream.close().await
+}
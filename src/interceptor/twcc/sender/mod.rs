@@ -0,0 +1,116 @@
+#[cfg(test)]
+mod sender_test;
+
+use super::transport_cc_extension_id;
+use crate::interceptor::stream_info::StreamInfo;
+use crate::interceptor::*;
+
+use bytes::Bytes;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// SenderBuilder can be used to configure the TWCC Sender Interceptor
+#[derive(Default)]
+pub struct SenderBuilder {
+    init_sequence_nr: u32,
+}
+
+impl SenderBuilder {
+    /// with_init_sequence_nr sets the init sequence number of the interceptor.
+    pub fn with_init_sequence_nr(mut self, init_sequence_nr: u32) -> SenderBuilder {
+        self.init_sequence_nr = init_sequence_nr;
+        self
+    }
+}
+
+impl InterceptorBuilder for SenderBuilder {
+    fn build(&self, _id: &str) -> Result<Arc<dyn Interceptor + Send + Sync>> {
+        Ok(Arc::new(Sender {
+            next_sequence_nr: Arc::new(AtomicU32::new(self.init_sequence_nr)),
+        }))
+    }
+}
+
+/// Sender stamps every outgoing RTP packet with a transport wide sequence number,
+/// shared by all the local streams of the PeerConnection
+pub struct Sender {
+    next_sequence_nr: Arc<AtomicU32>,
+}
+
+impl Sender {
+    /// builder returns a new SenderBuilder.
+    pub fn builder() -> SenderBuilder {
+        SenderBuilder::default()
+    }
+}
+
+/// SenderStream sets the transport-cc header extension of the packets of a local stream
+struct SenderStream {
+    next_rtp_writer: Arc<dyn RTPWriter + Send + Sync>,
+    next_sequence_nr: Arc<AtomicU32>,
+    hdr_ext_id: u8,
+}
+
+#[async_trait]
+impl RTPWriter for SenderStream {
+    async fn write(&self, pkt: &rtp::packet::Packet, a: &Attributes) -> Result<usize> {
+        let sequence_number = self.next_sequence_nr.fetch_add(1, Ordering::SeqCst) as u16;
+
+        let mut pkt = pkt.clone();
+        pkt.header.set_extension(
+            self.hdr_ext_id,
+            Bytes::copy_from_slice(&sequence_number.to_be_bytes()),
+        )?;
+
+        self.next_rtp_writer.write(&pkt, a).await
+    }
+}
+
+#[async_trait]
+impl Interceptor for Sender {
+    async fn bind_rtcp_reader(
+        &self,
+        reader: Arc<dyn RTCPReader + Send + Sync>,
+    ) -> Arc<dyn RTCPReader + Send + Sync> {
+        reader
+    }
+
+    async fn bind_rtcp_writer(
+        &self,
+        writer: Arc<dyn RTCPWriter + Send + Sync>,
+    ) -> Arc<dyn RTCPWriter + Send + Sync> {
+        writer
+    }
+
+    /// bind_local_stream stamps the packets of the streams which negotiated the
+    /// transport-cc header extension
+    async fn bind_local_stream(
+        &self,
+        info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Arc<dyn RTPWriter + Send + Sync> {
+        match transport_cc_extension_id(info) {
+            Some(hdr_ext_id) => Arc::new(SenderStream {
+                next_rtp_writer: writer,
+                next_sequence_nr: Arc::clone(&self.next_sequence_nr),
+                hdr_ext_id,
+            }),
+            None => writer,
+        }
+    }
+
+    async fn unbind_local_stream(&self, _info: &StreamInfo) {}
+
+    async fn bind_remote_stream(
+        &self,
+        _info: &StreamInfo,
+        reader: Arc<dyn RTPReader + Send + Sync>,
+    ) -> Arc<dyn RTPReader + Send + Sync> {
+        reader
+    }
+
+    async fn unbind_remote_stream(&self, _info: &StreamInfo) {}
+
+    async fn close(&self) -> Result<()> {
+        Ok(())
+    }
+}
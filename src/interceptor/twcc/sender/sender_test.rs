@@ -0,0 +1,83 @@
+use super::*;
+use crate::interceptor::mock_stream::MockStream;
+use crate::media::rtp::rtp_codec::RTPHeaderExtensionParameter;
+
+use std::time::Duration;
+
+fn transport_cc_seq(pkt: &rtp::packet::Packet, id: u8) -> Option<u16> {
+    pkt.header
+        .get_extension(id)
+        .map(|ext| u16::from_be_bytes([ext[0], ext[1]]))
+}
+
+#[tokio::test]
+async fn test_sender_interceptor() -> Result<()> {
+    let icpr = Sender::builder().with_init_sequence_nr(65534).build("")?;
+
+    let info = |ssrc| StreamInfo {
+        ssrc,
+        rtp_header_extensions: vec![RTPHeaderExtensionParameter {
+            uri: sdp::extmap::TRANSPORT_CC_URI.to_owned(),
+            id: 1,
+        }],
+        ..Default::default()
+    };
+    let stream1 = MockStream::new(&info(1), Arc::clone(&icpr)).await;
+    let stream2 = MockStream::new(&info(2), Arc::clone(&icpr)).await;
+
+    // the sequence numbers are shared by all the streams, and roll over
+    for (i, want) in [65534u16, 65535, 0, 1].iter().enumerate() {
+        let stream = if i % 2 == 0 { &stream1 } else { &stream2 };
+        stream
+            .write_rtp(&rtp::packet::Packet {
+                header: rtp::header::Header {
+                    sequence_number: i as u16,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .await?;
+
+        let p = tokio::time::timeout(Duration::from_millis(10), stream.written_rtp())
+            .await?
+            .expect("a packet written");
+        assert_eq!(p.header.sequence_number, i as u16);
+        assert_eq!(transport_cc_seq(&p, 1), Some(*want));
+    }
+
+    stream1.close().await?;
+    stream2.close().await
+}
+
+#[tokio::test]
+async fn test_sender_interceptor_without_extension() -> Result<()> {
+    let icpr = Sender::builder().build("")?;
+
+    // the transport-cc header extension wasn't negotiated, the packets are left untouched
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 1,
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    stream
+        .write_rtp(&rtp::packet::Packet {
+            header: rtp::header::Header {
+                sequence_number: 7,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .await?;
+
+    let p = tokio::time::timeout(Duration::from_millis(10), stream.written_rtp())
+        .await?
+        .expect("a packet written");
+    assert_eq!(p.header.sequence_number, 7);
+    assert_eq!(transport_cc_seq(&p, 1), None);
+
+    stream.close().await
+}
@@ -0,0 +1,161 @@
+use super::*;
+
+fn transport_layer_cc(pkt: &dyn rtcp::packet::Packet) -> &TransportLayerCc {
+    pkt.as_any()
+        .downcast_ref::<TransportLayerCc>()
+        .expect("transport layer cc expected")
+}
+
+#[test]
+fn test_chunk_run_length() {
+    let mut c = Chunk::default();
+    for _ in 0..3 {
+        let delta = SymbolTypeTcc::PacketReceivedSmallDelta as u16;
+        assert!(c.can_add(delta));
+        c.add(delta);
+    }
+
+    if let PacketStatusChunk::RunLengthChunk(chunk) = c.encode() {
+        assert!(matches!(
+            chunk.packet_status_symbol,
+            SymbolTypeTcc::PacketReceivedSmallDelta
+        ));
+        assert_eq!(chunk.run_length, 3);
+    } else {
+        panic!("run length chunk expected");
+    }
+    assert!(c.deltas.is_empty());
+}
+
+#[test]
+fn test_chunk_one_bit_status_vector() {
+    let mut c = Chunk::default();
+    for i in 0..MAX_ONE_BIT_CAP {
+        let delta = if i % 2 == 0 {
+            SymbolTypeTcc::PacketReceivedSmallDelta as u16
+        } else {
+            SymbolTypeTcc::PacketNotReceived as u16
+        };
+        assert!(c.can_add(delta));
+        c.add(delta);
+    }
+    // a one bit vector is full with 14 symbols
+    assert!(!c.can_add(SymbolTypeTcc::PacketReceivedSmallDelta as u16));
+
+    if let PacketStatusChunk::StatusVectorChunk(chunk) = c.encode() {
+        assert!(matches!(chunk.symbol_size, SymbolSizeTypeTcc::OneBit));
+        assert_eq!(chunk.symbol_list.len(), MAX_ONE_BIT_CAP);
+        assert!(matches!(
+            chunk.symbol_list[1],
+            SymbolTypeTcc::PacketNotReceived
+        ));
+    } else {
+        panic!("status vector chunk expected");
+    }
+    assert!(c.deltas.is_empty());
+}
+
+#[test]
+fn test_chunk_two_bit_status_vector() {
+    let mut c = Chunk::default();
+    c.add(SymbolTypeTcc::PacketReceivedLargeDelta as u16);
+    for _ in 1..MAX_TWO_BIT_CAP {
+        c.add(SymbolTypeTcc::PacketReceivedSmallDelta as u16);
+    }
+    // a large delta needs two bits per symbol, so only 7 of them fit
+    assert!(!c.can_add(SymbolTypeTcc::PacketReceivedSmallDelta as u16));
+
+    if let PacketStatusChunk::StatusVectorChunk(chunk) = c.encode() {
+        assert!(matches!(chunk.symbol_size, SymbolSizeTypeTcc::TwoBit));
+        assert_eq!(chunk.symbol_list.len(), MAX_TWO_BIT_CAP);
+        assert!(matches!(
+            chunk.symbol_list[0],
+            SymbolTypeTcc::PacketReceivedLargeDelta
+        ));
+    } else {
+        panic!("status vector chunk expected");
+    }
+    assert!(c.deltas.is_empty());
+    assert!(!c.has_large_delta);
+}
+
+#[test]
+fn test_recorder_not_enough_packets() {
+    let mut r = Recorder::new(5000);
+    assert!(r.build_feedback_packet().is_empty());
+    r.record(5000, 0, 0);
+    assert!(r.build_feedback_packet().is_empty());
+}
+
+#[test]
+fn test_recorder_missing_packets() {
+    let mut r = Recorder::new(5000);
+    for (seq, arrival_time) in &[(1u16, 64000i64), (0, 64500), (3, 65000), (4, 130000)] {
+        r.record(1234, *seq, *arrival_time);
+    }
+
+    let pkts = r.build_feedback_packet();
+    assert_eq!(pkts.len(), 1);
+    let cc = transport_layer_cc(pkts[0].as_ref());
+    assert_eq!(cc.sender_ssrc, 5000);
+    assert_eq!(cc.media_ssrc, 1234);
+    assert_eq!(cc.fb_pkt_count, 0);
+    assert_eq!(cc.base_sequence_number, 0);
+    assert_eq!(cc.packet_status_count, 5);
+    assert_eq!(cc.reference_time, 1);
+
+    // the packets are reported in the order of their sequence numbers, so the
+    // delta of the second one is negative
+    let deltas: Vec<i64> = cc.recv_deltas.iter().map(|d| d.delta).collect();
+    assert_eq!(deltas, vec![500, -500, 1000, 65000]);
+    assert!(matches!(
+        cc.recv_deltas[1].type_tcc_packet,
+        SymbolTypeTcc::PacketReceivedLargeDelta
+    ));
+    assert!(matches!(
+        cc.recv_deltas[3].type_tcc_packet,
+        SymbolTypeTcc::PacketReceivedLargeDelta
+    ));
+
+    // the recorded packets are only reported once
+    assert!(r.build_feedback_packet().is_empty());
+}
+
+#[test]
+fn test_recorder_large_delta_splits_feedback() {
+    let mut r = Recorder::new(5000);
+    r.record(1234, 0, 0);
+    r.record(1234, 1, 1000);
+    // more than 8.19 seconds later, the delta doesn't fit in 16 bits
+    r.record(1234, 2, 10_000_000);
+
+    let pkts = r.build_feedback_packet();
+    assert_eq!(pkts.len(), 2);
+
+    let first = transport_layer_cc(pkts[0].as_ref());
+    assert_eq!(first.fb_pkt_count, 0);
+    assert_eq!(first.base_sequence_number, 0);
+    assert_eq!(first.packet_status_count, 2);
+
+    let second = transport_layer_cc(pkts[1].as_ref());
+    assert_eq!(second.fb_pkt_count, 1);
+    assert_eq!(second.base_sequence_number, 2);
+    assert_eq!(second.packet_status_count, 1);
+    assert_eq!(second.reference_time, (10_000_000 / 64000) as u32);
+}
+
+#[test]
+fn test_recorder_sequence_number_rollover() {
+    let mut r = Recorder::new(5000);
+    r.record(1234, 0xfffe, 0);
+    r.record(1234, 0xffff, 1000);
+    r.record(1234, 0x0001, 2000);
+
+    let pkts = r.build_feedback_packet();
+    assert_eq!(pkts.len(), 1);
+    let cc = transport_layer_cc(pkts[0].as_ref());
+    // 0x0000 is missing
+    assert_eq!(cc.base_sequence_number, 0xfffe);
+    assert_eq!(cc.packet_status_count, 4);
+    assert_eq!(cc.recv_deltas.len(), 3);
+}
@@ -9,6 +9,7 @@ use ::dtls::extension::extension_use_srtp::SrtpProtectionProfile;
 pub mod api;
 pub mod data;
 pub mod error;
+pub mod interceptor;
 pub mod media;
 pub mod peer;
 pub mod stats;
@@ -1,8 +1,9 @@
 use std::fmt;
 
 /// DTLSTransportState indicates the DTLS transport establishment state.
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
 pub enum DTLSTransportState {
+    #[default]
     Unspecified = 0,
 
     /// DTLSTransportStateNew indicates that DTLS has not started negotiating
@@ -28,12 +29,6 @@ pub enum DTLSTransportState {
     Failed = 5,
 }
 
-impl Default for DTLSTransportState {
-    fn default() -> Self {
-        DTLSTransportState::Unspecified
-    }
-}
-
 const DTLS_TRANSPORT_STATE_NEW_STR: &str = "New";
 const DTLS_TRANSPORT_STATE_CONNECTING_STR: &str = "Connecting";
 const DTLS_TRANSPORT_STATE_CONNECTED_STR: &str = "Connected";
@@ -53,6 +48,19 @@ impl From<&str> for DTLSTransportState {
     }
 }
 
+impl From<u8> for DTLSTransportState {
+    fn from(v: u8) -> Self {
+        match v {
+            1 => DTLSTransportState::New,
+            2 => DTLSTransportState::Connecting,
+            3 => DTLSTransportState::Connected,
+            4 => DTLSTransportState::Closed,
+            5 => DTLSTransportState::Failed,
+            _ => DTLSTransportState::Unspecified,
+        }
+    }
+}
+
 impl fmt::Display for DTLSTransportState {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let s = match *self {
@@ -2,33 +2,40 @@ pub mod dtls_fingerprint;
 pub mod dtls_parameters;
 pub mod dtls_role;
 pub mod dtls_transport_state;
+pub(crate) mod srtp_session;
 
 use dtls_role::*;
 
 use crate::api::setting_engine::SettingEngine;
 use crate::default_srtp_protection_profiles;
-use crate::error::Error;
+use crate::error::{flatten_errs, Error};
+use crate::interceptor::{Attributes, RTCPWriter};
 use crate::media::dtls_transport::dtls_transport_state::DTLSTransportState;
 use crate::media::ice_transport::ice_transport_state::ICETransportState;
 use crate::media::ice_transport::ICETransport;
+use crate::media::rtp::SSRC;
 use crate::peer::ice::ice_role::ICERole;
 use crate::util::mux::endpoint::Endpoint;
 use crate::util::mux::mux_func::{match_dtls, match_srtcp, match_srtp, MatchFunc};
+use crate::media::dtls_transport::dtls_fingerprint::DTLSFingerprint;
+use crate::media::dtls_transport::srtp_session::{SRTPSession, SRTPStream};
 use bytes::Bytes;
 use dtls::config::ClientAuthType;
 use dtls::conn::DTLSConn;
 use dtls::crypto::Certificate;
 use srtp::protection_profile::ProtectionProfile;
 use srtp::session::Session;
-use srtp::stream::Stream;
+use sha2::{Digest, Sha256};
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicU8, Ordering};
 use std::sync::Arc;
 use tokio::sync::{mpsc, Mutex};
 use util::Conn;
 
 use crate::media::dtls_transport::dtls_parameters::DTLSParameters;
 use anyhow::Result;
+use async_trait::async_trait;
 
 pub type OnStateChangeHdlrFn = Box<
     dyn (FnMut(DTLSTransportState) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>)
@@ -42,32 +49,32 @@ pub type OnStateChangeHdlrFn = Box<
 /// and received by data channels.
 #[derive(Default)]
 pub struct DTLSTransport {
-    pub(crate) ice_transport: ICETransport,
+    pub(crate) ice_transport: Arc<ICETransport>,
     pub(crate) certificates: Vec<Certificate>,
     pub(crate) setting_engine: SettingEngine,
 
-    pub(crate) remote_parameters: DTLSParameters,
-    pub(crate) remote_certificate: Bytes,
-    pub(crate) state: DTLSTransportState,
-    pub(crate) srtp_protection_profile: ProtectionProfile,
+    pub(crate) remote_parameters: Mutex<DTLSParameters>,
+    pub(crate) remote_certificate: Mutex<Bytes>,
+    pub(crate) state: AtomicU8, //DTLSTransportState,
+    pub(crate) srtp_protection_profile: Mutex<ProtectionProfile>,
     pub(crate) on_state_change_handler: Arc<Mutex<Option<OnStateChangeHdlrFn>>>,
-    pub(crate) conn: Option<Arc<DTLSConn>>,
+    pub(crate) conn: Mutex<Option<Arc<DTLSConn>>>,
 
-    pub(crate) srtp_session: Option<Session>,
-    pub(crate) srtcp_session: Option<Session>,
-    pub(crate) srtp_endpoint: Option<Arc<Endpoint>>,
-    pub(crate) srtcp_endpoint: Option<Arc<Endpoint>>,
+    pub(crate) srtp_session: Mutex<Option<Arc<SRTPSession>>>,
+    pub(crate) srtcp_session: Mutex<Option<Arc<SRTPSession>>>,
+    pub(crate) srtp_endpoint: Mutex<Option<Arc<Endpoint>>>,
+    pub(crate) srtcp_endpoint: Mutex<Option<Arc<Endpoint>>>,
 
-    pub(crate) simulcast_streams: Vec<Stream>,
-    pub(crate) srtp_ready_tx: Option<mpsc::Sender<()>>,
-    pub(crate) srtp_ready_rx: Option<mpsc::Receiver<()>>,
+    pub(crate) simulcast_streams: Mutex<Vec<Arc<SRTPStream>>>,
+    pub(crate) srtp_ready_tx: Mutex<Option<mpsc::Sender<()>>>,
+    pub(crate) srtp_ready_rx: Mutex<Option<mpsc::Receiver<()>>>,
 
     pub(crate) dtls_matcher: Option<MatchFunc>,
 }
 
 impl DTLSTransport {
     pub fn new(
-        ice_transport: ICETransport,
+        ice_transport: Arc<ICETransport>,
         certificates: Vec<Certificate>,
         setting_engine: SettingEngine,
     ) -> Self {
@@ -76,9 +83,9 @@ impl DTLSTransport {
             ice_transport,
             certificates,
             setting_engine,
-            srtp_ready_tx: Some(srtp_ready_tx),
-            srtp_ready_rx: Some(srtp_ready_rx),
-            state: DTLSTransportState::New,
+            srtp_ready_tx: Mutex::new(Some(srtp_ready_tx)),
+            srtp_ready_rx: Mutex::new(Some(srtp_ready_rx)),
+            state: AtomicU8::new(DTLSTransportState::New as u8),
             dtls_matcher: Some(Box::new(match_dtls)),
             ..Default::default()
         }
@@ -86,13 +93,17 @@ impl DTLSTransport {
 
     /// returns the currently-configured ICETransport or None
     /// if one has not been configured
-    pub fn ice_transport(&self) -> &ICETransport {
-        &self.ice_transport
+    pub fn ice_transport(&self) -> Arc<ICETransport> {
+        Arc::clone(&self.ice_transport)
     }
 
     /// state_change requires the caller holds the lock
-    async fn state_change(&mut self, state: DTLSTransportState) {
-        self.state = state;
+    async fn state_change(&self, state: DTLSTransportState) {
+        self.state.store(state as u8, Ordering::SeqCst);
+        if state == DTLSTransportState::Failed || state == DTLSTransportState::Closed {
+            // the SRTP sessions will not be created anymore
+            self.srtp_ready().await;
+        }
         let mut handler = self.on_state_change_handler.lock().await;
         if let Some(f) = &mut *handler {
             f(state).await;
@@ -108,13 +119,13 @@ impl DTLSTransport {
 
     /// state returns the current dtls_transport transport state.
     pub fn state(&self) -> DTLSTransportState {
-        self.state
+        self.state.load(Ordering::SeqCst).into()
     }
 
     /// write_rtcp sends a user provided RTCP packet to the connected peer. If no peer is connected the
     /// packet is discarded.
-    pub async fn write_rtcp(&mut self, pkt: &(dyn rtcp::packet::Packet)) -> Result<usize> {
-        if let Some(srtcp_session) = &mut self.srtcp_session {
+    pub async fn write_rtcp(&self, pkt: &(dyn rtcp::packet::Packet + Send + Sync)) -> Result<usize> {
+        if let Some(srtcp_session) = self.get_srtcp_session().await {
             Ok(srtcp_session.write_rtcp(pkt).await?)
         } else {
             Ok(0)
@@ -123,11 +134,10 @@ impl DTLSTransport {
 
     /// get_local_parameters returns the DTLS parameters of the local DTLSTransport upon construction.
     pub fn get_local_parameters(&self) -> Result<DTLSParameters> {
-        let fingerprints = vec![];
+        let mut fingerprints = vec![];
 
-        for _c in &self.certificates {
-            /*TODO: prints := c.GetFingerprints()?;
-            fingerprints.push(prints);*/
+        for c in &self.certificates {
+            fingerprints.extend(get_fingerprints(c)?);
         }
 
         Ok(DTLSParameters {
@@ -138,87 +148,129 @@ impl DTLSTransport {
 
     /// get_remote_certificate returns the certificate chain in use by the remote side
     /// returns an empty list prior to selection of the remote certificate
-    pub fn get_remote_certificate(&self) -> Bytes {
-        self.remote_certificate.clone()
+    pub async fn get_remote_certificate(&self) -> Bytes {
+        let remote_certificate = self.remote_certificate.lock().await;
+        remote_certificate.clone()
     }
 
-    pub(crate) async fn start_srtp(&mut self) -> Result<()> {
-        let mut srtp_config = srtp::config::Config {
-            profile: self.srtp_protection_profile,
-            ..Default::default()
-        };
-        let mut srtcp_config = srtp::config::Config {
-            profile: self.srtp_protection_profile,
-            ..Default::default()
+    pub(crate) async fn start_srtp(&self) -> Result<()> {
+        let profile = {
+            let srtp_protection_profile = self.srtp_protection_profile.lock().await;
+            *srtp_protection_profile
         };
 
-        if self.setting_engine.replay_protection.srtp != 0 {
-            srtp_config.remote_rtp_options = Some(srtp::option::srtp_replay_protection(
-                self.setting_engine.replay_protection.srtp,
-            ));
-        } else if self.setting_engine.disable_srtp_replay_protection {
-            srtp_config.remote_rtp_options = Some(srtp::option::srtp_no_replay_protection());
-        }
-
-        if self.setting_engine.replay_protection.srtcp != 0 {
-            srtcp_config.remote_rtcp_options = Some(srtp::option::srtcp_replay_protection(
-                self.setting_engine.replay_protection.srtcp,
-            ));
-        } else if self.setting_engine.disable_srtcp_replay_protection {
-            srtcp_config.remote_rtcp_options = Some(srtp::option::srtcp_no_replay_protection());
-        }
-
-        if let Some(conn) = &self.conn {
-            let conn_state = conn.connection_state().await;
-            srtp_config
-                .extract_session_keys_from_dtls(conn_state, self.role() == DTLSRole::Client)
-                .await?;
+        let conn = if let Some(conn) = self.conn().await {
+            conn
         } else {
             return Err(Error::ErrDtlsTransportNotStarted.into());
-        }
-
-        self.srtp_session = if let Some(srtp_endpoint) = &self.srtp_endpoint {
-            Some(
-                Session::new(
-                    Arc::clone(srtp_endpoint) as Arc<dyn Conn + Send + Sync>,
-                    srtp_config,
-                    true,
-                )
-                .await?,
-            )
-        } else {
-            None
         };
+        let is_client = self.role().await == DTLSRole::Client;
 
-        self.srtcp_session = if let Some(srtcp_endpoint) = &self.srtcp_endpoint {
-            Some(
-                Session::new(
-                    Arc::clone(&srtcp_endpoint) as Arc<dyn Conn + Send + Sync>,
-                    srtcp_config,
-                    false,
-                )
-                .await?,
-            )
-        } else {
-            None
+        let srtp_endpoint = {
+            let srtp_endpoint = self.srtp_endpoint.lock().await;
+            srtp_endpoint.clone()
         };
+        if let Some(srtp_endpoint) = srtp_endpoint {
+            let replay_protection = self.setting_engine.replay_protection.srtp;
+            let disable_replay_protection = self.setting_engine.disable_srtp_replay_protection;
+            let session = new_srtp_session(
+                srtp_endpoint,
+                move || {
+                    let mut srtp_config = srtp::config::Config {
+                        profile,
+                        ..Default::default()
+                    };
+                    if replay_protection != 0 {
+                        srtp_config.remote_rtp_options =
+                            Some(srtp::option::srtp_replay_protection(replay_protection));
+                    } else if disable_replay_protection {
+                        srtp_config.remote_rtp_options =
+                            Some(srtp::option::srtp_no_replay_protection());
+                    }
+                    srtp_config
+                },
+                conn.connection_state().await,
+                is_client,
+                true,
+            )
+            .await?;
+            let mut srtp_session = self.srtp_session.lock().await;
+            *srtp_session = Some(Arc::new(session));
+        }
 
-        self.srtp_ready_tx.take();
+        let srtcp_endpoint = {
+            let srtcp_endpoint = self.srtcp_endpoint.lock().await;
+            srtcp_endpoint.clone()
+        };
+        if let Some(srtcp_endpoint) = srtcp_endpoint {
+            let replay_protection = self.setting_engine.replay_protection.srtcp;
+            let disable_replay_protection = self.setting_engine.disable_srtcp_replay_protection;
+            let session = new_srtp_session(
+                srtcp_endpoint,
+                move || {
+                    let mut srtcp_config = srtp::config::Config {
+                        profile,
+                        ..Default::default()
+                    };
+                    if replay_protection != 0 {
+                        srtcp_config.remote_rtcp_options =
+                            Some(srtp::option::srtcp_replay_protection(replay_protection));
+                    } else if disable_replay_protection {
+                        srtcp_config.remote_rtcp_options =
+                            Some(srtp::option::srtcp_no_replay_protection());
+                    }
+                    srtcp_config
+                },
+                conn.connection_state().await,
+                is_client,
+                false,
+            )
+            .await?;
+            let mut srtcp_session = self.srtcp_session.lock().await;
+            *srtcp_session = Some(Arc::new(session));
+        }
 
         Ok(())
     }
 
-    fn get_srtp_session(&self) -> Option<&Session> {
-        self.srtp_session.as_ref()
+    pub(crate) async fn get_srtp_session(&self) -> Option<Arc<SRTPSession>> {
+        let srtp_session = self.srtp_session.lock().await;
+        srtp_session.clone()
+    }
+
+    pub(crate) async fn get_srtcp_session(&self) -> Option<Arc<SRTPSession>> {
+        let srtcp_session = self.srtcp_session.lock().await;
+        srtcp_session.clone()
     }
 
-    fn get_srtcp_session(&self) -> Option<&Session> {
-        self.srtcp_session.as_ref()
+    /// srtp_ready wakes up the callers of wait_srtp_ready
+    async fn srtp_ready(&self) {
+        let mut srtp_ready_tx = self.srtp_ready_tx.lock().await;
+        srtp_ready_tx.take();
+    }
+
+    /// wait_srtp_ready blocks until start_srtp has created the SRTP and SRTCP sessions,
+    /// or until the DTLSTransport failed or got closed without creating them
+    pub(crate) async fn wait_srtp_ready(&self) {
+        let mut srtp_ready_rx = self.srtp_ready_rx.lock().await;
+        if let Some(srtp_ready_rx) = &mut *srtp_ready_rx {
+            srtp_ready_rx.recv().await;
+        }
     }
 
-    pub(crate) fn role(&self) -> DTLSRole {
+    /// conn returns the underlying DTLS connection once the handshake has completed.
+    pub(crate) async fn conn(&self) -> Option<Arc<DTLSConn>> {
+        let conn = self.conn.lock().await;
+        conn.clone()
+    }
+
+    pub(crate) async fn role(&self) -> DTLSRole {
         // If remote has an explicit role use the inverse
-        match self.remote_parameters.role {
+        let remote_role = {
+            let remote_parameters = self.remote_parameters.lock().await;
+            remote_parameters.role
+        };
+        match remote_role {
             DTLSRole::Client => return DTLSRole::Server,
             DTLSRole::Server => return DTLSRole::Client,
             _ => {}
@@ -240,24 +292,33 @@ impl DTLSTransport {
     }
 
     async fn prepare_transport(
-        &mut self,
+        &self,
         remote_parameters: DTLSParameters,
     ) -> Result<(DTLSRole, dtls::config::Config)> {
         self.ensure_ice_conn()?;
 
-        if self.state != DTLSTransportState::New {
+        if self.state() != DTLSTransportState::New {
             return Err(Error::ErrInvalidDTLSStart.into());
         }
 
-        self.srtp_endpoint = self.ice_transport.new_endpoint(Box::new(match_srtp)).await;
-        self.srtcp_endpoint = self.ice_transport.new_endpoint(Box::new(match_srtcp)).await;
-        self.remote_parameters = remote_parameters;
+        {
+            let mut srtp_endpoint = self.srtp_endpoint.lock().await;
+            *srtp_endpoint = self.ice_transport.new_endpoint(Box::new(match_srtp)).await;
+        }
+        {
+            let mut srtcp_endpoint = self.srtcp_endpoint.lock().await;
+            *srtcp_endpoint = self.ice_transport.new_endpoint(Box::new(match_srtcp)).await;
+        }
+        {
+            let mut rp = self.remote_parameters.lock().await;
+            *rp = remote_parameters;
+        }
 
         let cert = self.certificates[0].clone();
         self.state_change(DTLSTransportState::Connecting).await;
 
         Ok((
-            self.role(),
+            self.role().await,
             dtls::config::Config {
                 certificates: vec![cert],
                 srtp_protection_profiles: if !self
@@ -277,7 +338,7 @@ impl DTLSTransport {
     }
 
     /// start DTLS transport negotiation with the parameters of the remote DTLS transport
-    pub async fn start(&mut self, remote_parameters: DTLSParameters) -> Result<()> {
+    pub async fn start(&self, remote_parameters: DTLSParameters) -> Result<()> {
         let dtls_conn_result = if let Some(dtls_endpoint) =
             self.ice_transport.new_endpoint(Box::new(match_dtls)).await
         {
@@ -318,24 +379,21 @@ impl DTLSTransport {
         };
 
         let srtp_profile = dtls_conn.selected_srtpprotection_profile();
-        match srtp_profile {
-            dtls::extension::extension_use_srtp::SrtpProtectionProfile::Srtp_Aead_Aes_128_Gcm=> {
-                self.srtp_protection_profile = srtp::protection_profile::ProtectionProfile::AeadAes128Gcm;
-            }
-            dtls::extension::extension_use_srtp::SrtpProtectionProfile::Srtp_Aes128_Cm_Hmac_Sha1_80=> {
-                self.srtp_protection_profile = srtp::protection_profile::ProtectionProfile::Aes128CmHmacSha1_80;
-            }
-            _=> {
-                self.state_change(DTLSTransportState::Failed).await;
-                return Err(Error::ErrNoSRTPProtectionProfile.into());
-            }
-        };
-
-        if self
-            .setting_engine
-            .disable_certificate_fingerprint_verification
         {
-            return Ok(());
+            let mut srtp_protection_profile = self.srtp_protection_profile.lock().await;
+            match srtp_profile {
+                dtls::extension::extension_use_srtp::SrtpProtectionProfile::Srtp_Aead_Aes_128_Gcm=> {
+                    *srtp_protection_profile = srtp::protection_profile::ProtectionProfile::AeadAes128Gcm;
+                }
+                dtls::extension::extension_use_srtp::SrtpProtectionProfile::Srtp_Aes128_Cm_Hmac_Sha1_80=> {
+                    *srtp_protection_profile = srtp::protection_profile::ProtectionProfile::Aes128CmHmacSha1_80;
+                }
+                _=> {
+                    drop(srtp_protection_profile);
+                    self.state_change(DTLSTransportState::Failed).await;
+                    return Err(Error::ErrNoSRTPProtectionProfile.into());
+                }
+            };
         }
 
         // Check the fingerprint if a certificate was exchanged
@@ -344,38 +402,45 @@ impl DTLSTransport {
             self.state_change(DTLSTransportState::Failed).await;
             return Err(Error::ErrNoRemoteCertificate.into());
         }
-        self.remote_certificate = Bytes::from(remote_certs[0].clone());
-
-        /*TODO: let parsedRemoteCert = x509.ParseCertificate(t.remote_certificate)
-        if err != nil {
-            if closeErr := dtlsConn.Close(); closeErr != nil {
-                t.log.Error(err.Error())
-            }
-
-            t.onStateChange(DTLSTransportStateFailed)
-            return err
+        {
+            let mut remote_certificate = self.remote_certificate.lock().await;
+            *remote_certificate = Bytes::from(remote_certs[0].clone());
         }
 
-        if err = t.validate_finger_print(parsedRemoteCert); err != nil {
-            if closeErr := dtlsConn.Close(); closeErr != nil {
-                t.log.Error(err.Error())
-            }
+        if !self
+            .setting_engine
+            .disable_certificate_fingerprint_verification
+        {
+            if let Err(err) = self.validate_fingerprint(&remote_certs[0]).await {
+                if let Err(close_err) = dtls_conn.close().await {
+                    log::error!("{}", close_err);
+                }
 
-            t.onStateChange(DTLSTransportStateFailed)
-            return err
-        }*/
+                self.state_change(DTLSTransportState::Failed).await;
+                return Err(err);
+            }
+        }
 
-        self.conn = Some(Arc::new(dtls_conn));
+        {
+            let mut conn = self.conn.lock().await;
+            *conn = Some(Arc::new(dtls_conn));
+        }
         self.state_change(DTLSTransportState::Connected).await;
 
-        self.start_srtp().await
+        let result = self.start_srtp().await;
+        self.srtp_ready().await;
+        result
     }
 
     /// stops and closes the DTLSTransport object.
-    pub async fn stop(&mut self) -> Result<()> {
+    pub async fn stop(&self) -> Result<()> {
         // Try closing everything and collect the errors
         let mut close_errs: Vec<anyhow::Error> = vec![];
-        if let Some(mut srtp_session) = self.srtp_session.take() {
+        let srtp_session = {
+            let mut srtp_session = self.srtp_session.lock().await;
+            srtp_session.take()
+        };
+        if let Some(srtp_session) = srtp_session {
             match srtp_session.close().await {
                 Ok(_) => {}
                 Err(err) => {
@@ -384,7 +449,11 @@ impl DTLSTransport {
             };
         }
 
-        if let Some(mut srtcp_session) = self.srtcp_session.take() {
+        let srtcp_session = {
+            let mut srtcp_session = self.srtcp_session.lock().await;
+            srtcp_session.take()
+        };
+        if let Some(srtcp_session) = srtcp_session {
             match srtcp_session.close().await {
                 Ok(_) => {}
                 Err(err) => {
@@ -393,16 +462,23 @@ impl DTLSTransport {
             };
         }
 
-        for ss in &mut self.simulcast_streams {
-            match ss.close().await {
-                Ok(_) => {}
-                Err(err) => {
-                    close_errs.push(err);
-                }
-            };
+        {
+            let mut simulcast_streams = self.simulcast_streams.lock().await;
+            for ss in simulcast_streams.drain(..) {
+                match ss.close().await {
+                    Ok(_) => {}
+                    Err(err) => {
+                        close_errs.push(err);
+                    }
+                };
+            }
         }
 
-        if let Some(conn) = self.conn.take() {
+        let conn = {
+            let mut conn = self.conn.lock().await;
+            conn.take()
+        };
+        if let Some(conn) = conn {
             // dtls_transport connection may be closed on sctp close.
             match conn.close().await {
                 Ok(_) => {}
@@ -416,34 +492,23 @@ impl DTLSTransport {
 
         self.state_change(DTLSTransportState::Closed).await;
 
-        if close_errs.is_empty() {
-            Ok(())
-        } else {
-            let close_errs_strs: Vec<String> =
-                close_errs.into_iter().map(|e| e.to_string()).collect();
-            Err(Error::new(close_errs_strs.join("\n")).into())
-        }
+        flatten_errs(close_errs)
     }
 
-    pub(crate) fn validate_fingerprint(&self, _remote_cert: &[u8]) -> Result<()> {
-        /*TODO: for  fp in self.remote_parameters.fingerprints {
-            hashAlgo, err := fingerprint.HashFromString(fp.algorithm);
-            if err != nil {
-                return err
+    pub(crate) async fn validate_fingerprint(&self, remote_cert: &[u8]) -> Result<()> {
+        let remote_parameters = self.remote_parameters.lock().await;
+        for fp in &remote_parameters.fingerprints {
+            if fp.algorithm != FINGERPRINT_ALGORITHM_SHA256 {
+                continue;
             }
 
-            remoteValue, err := fingerprint.Fingerprint(remoteCert, hashAlgo)
-            if err != nil {
-                return err
-            }
-
-            if strings.EqualFold(remoteValue, fp.Value) {
-                return nil
+            let remote_value = fingerprint_sha256(remote_cert);
+            if remote_value.eq_ignore_ascii_case(&fp.value) {
+                return Ok(());
             }
         }
 
-        return errNoMatchingCertificateFingerprint*/
-        Ok(())
+        Err(Error::ErrNoMatchingCertificateFingerprint.into())
     }
 
     pub(crate) fn ensure_ice_conn(&self) -> Result<()> {
@@ -454,9 +519,82 @@ impl DTLSTransport {
         }
     }
 
-    pub(crate) fn store_simulcast_stream(&mut self, stream: Stream) {
-        self.simulcast_streams.push(stream)
+    pub(crate) async fn store_simulcast_stream(&self, stream: Arc<SRTPStream>) {
+        let mut simulcast_streams = self.simulcast_streams.lock().await;
+        simulcast_streams.push(stream)
+    }
+
+    pub(crate) async fn remove_simulcast_stream(&self, ssrc: SSRC) {
+        let mut simulcast_streams = self.simulcast_streams.lock().await;
+        simulcast_streams.retain(|s| s.get_ssrc() != ssrc);
+    }
+}
+
+/// The DTLSTransport is at the end of the RTCP writer chain of a PeerConnection
+#[async_trait]
+impl RTCPWriter for DTLSTransport {
+    async fn write(
+        &self,
+        pkts: &[Box<dyn rtcp::packet::Packet + Send + Sync>],
+        _attributes: &Attributes,
+    ) -> Result<usize> {
+        let mut n = 0;
+        for pkt in pkts {
+            n += self.write_rtcp(pkt.as_ref()).await?;
+        }
+        Ok(n)
+    }
+}
+
+/// new_srtp_session creates the SRTP or SRTCP session of an endpoint with the keys
+/// exported from the DTLS connection. The srtp Config isn't Send as its replay
+/// protection options aren't, so the session is created on a blocking thread for
+/// the futures holding it to stay out of the ones of the DTLSTransport.
+async fn new_srtp_session(
+    endpoint: Arc<Endpoint>,
+    new_config: impl FnOnce() -> srtp::config::Config + Send + 'static,
+    state: dtls::state::State,
+    is_client: bool,
+    is_rtp: bool,
+) -> Result<SRTPSession> {
+    let handle = tokio::runtime::Handle::current();
+    let session = tokio::task::spawn_blocking(move || {
+        handle.block_on(async move {
+            let mut config = new_config();
+            config
+                .extract_session_keys_from_dtls(state, is_client)
+                .await?;
+            Session::new(endpoint as Arc<dyn Conn + Send + Sync>, config, is_rtp).await
+        })
+    })
+    .await??;
+
+    Ok(SRTPSession::new(session, is_rtp))
+}
+
+const FINGERPRINT_ALGORITHM_SHA256: &str = "sha-256";
+
+/// fingerprint_sha256 returns the colon separated, lowercase hex SHA-256
+/// digest of a DER encoded certificate as described in RFC 4572.
+fn fingerprint_sha256(der: &[u8]) -> String {
+    let digest = Sha256::digest(der);
+    digest
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<String>>()
+        .join(":")
+}
+
+/// get_fingerprints returns the DTLSFingerprints of a certificate.
+pub(crate) fn get_fingerprints(certificate: &Certificate) -> Result<Vec<DTLSFingerprint>> {
+    if certificate.certificate.0.is_empty() {
+        return Err(Error::ErrFailedToGenerateCertificateFingerprint.into());
     }
+
+    Ok(vec![DTLSFingerprint {
+        algorithm: FINGERPRINT_ALGORITHM_SHA256.to_owned(),
+        value: fingerprint_sha256(&certificate.certificate.0),
+    }])
 }
 
 #[cfg(test)]
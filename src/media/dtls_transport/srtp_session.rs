@@ -0,0 +1,312 @@
+use crate::error::Error;
+use crate::media::rtp::SSRC;
+use crate::RECEIVE_MTU;
+
+use anyhow::Result;
+use bytes::Bytes;
+use srtp::session::Session;
+use srtp::stream::Stream;
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+use tokio::sync::{mpsc, oneshot, Mutex};
+use util::marshal::Marshal;
+
+/// ACCEPT_BACKLOG is the number of accepted streams that can wait on accept
+const ACCEPT_BACKLOG: usize = 8;
+
+type WriteRequest = (Bytes, oneshot::Sender<Result<usize>>);
+type SRTPStreams = Mutex<HashMap<SSRC, Arc<SRTPStream>>>;
+
+enum SessionEvent {
+    Accept(Result<Stream>),
+    Write(Option<WriteRequest>),
+    Close,
+}
+
+/// SRTPSession shares a SRTP or SRTCP session between the RTPSenders, RTPReceivers
+/// and the PeerConnection. The session itself is owned by a task which writes the
+/// packets handed to it and accepts the streams of every incoming SSRC, handing them
+/// to open when the SSRC is expected and to accept otherwise.
+pub(crate) struct SRTPSession {
+    is_rtp: bool,
+    streams: Arc<SRTPStreams>,
+    write_tx: mpsc::Sender<WriteRequest>,
+    accept_rx: Mutex<mpsc::Receiver<Arc<SRTPStream>>>,
+    close_tx: Mutex<Option<mpsc::Sender<()>>>,
+}
+
+impl SRTPSession {
+    pub(crate) fn new(session: Session, is_rtp: bool) -> Self {
+        let streams = Arc::new(Mutex::new(HashMap::new()));
+        let (write_tx, write_rx) = mpsc::channel(1);
+        let (accept_tx, accept_rx) = mpsc::channel(ACCEPT_BACKLOG);
+        let (close_tx, close_rx) = mpsc::channel(1);
+
+        tokio::spawn(SRTPSession::run(
+            session,
+            is_rtp,
+            Arc::clone(&streams),
+            write_rx,
+            accept_tx,
+            close_rx,
+        ));
+
+        SRTPSession {
+            is_rtp,
+            streams,
+            write_tx,
+            accept_rx: Mutex::new(accept_rx),
+            close_tx: Mutex::new(Some(close_tx)),
+        }
+    }
+
+    async fn run(
+        mut session: Session,
+        is_rtp: bool,
+        streams: Arc<SRTPStreams>,
+        mut write_rx: mpsc::Receiver<WriteRequest>,
+        accept_tx: mpsc::Sender<Arc<SRTPStream>>,
+        mut close_rx: mpsc::Receiver<()>,
+    ) {
+        loop {
+            let event = tokio::select! {
+                result = session.accept() => SessionEvent::Accept(result),
+                request = write_rx.recv() => SessionEvent::Write(request),
+                _ = close_rx.recv() => SessionEvent::Close,
+            };
+
+            match event {
+                SessionEvent::Accept(Ok(stream)) => {
+                    let ssrc = stream.get_ssrc();
+                    let (srtp_stream, is_new) = {
+                        let mut streams_map = streams.lock().await;
+                        if let Some(srtp_stream) = streams_map.get(&ssrc) {
+                            (Arc::clone(srtp_stream), false)
+                        } else {
+                            let srtp_stream = Arc::new(SRTPStream::new(ssrc, &streams));
+                            streams_map.insert(ssrc, Arc::clone(&srtp_stream));
+                            (srtp_stream, true)
+                        }
+                    };
+                    srtp_stream.attach(stream).await;
+
+                    // A stream that doesn't fit in the backlog is closed, so that a later
+                    // packet of its SSRC is accepted again.
+                    if is_new {
+                        if let Err(err) = accept_tx.try_send(Arc::clone(&srtp_stream)) {
+                            log::warn!("Failed to accept incoming SSRC {}: {}", ssrc, err);
+                            let _ = srtp_stream.close().await;
+                        }
+                    }
+                }
+                SessionEvent::Write(Some((raw, result_tx))) => {
+                    let _ = result_tx.send(session.write(&raw, is_rtp).await);
+                }
+                SessionEvent::Accept(Err(_)) | SessionEvent::Write(None) | SessionEvent::Close => {
+                    break;
+                }
+            }
+        }
+
+        if let Err(err) = session.close().await {
+            log::warn!("Failed to close SRTP session: {}", err);
+        }
+    }
+
+    /// open returns the stream of the given SSRC, whose packets are not handed
+    /// to accept
+    pub(crate) async fn open(&self, ssrc: SSRC) -> Arc<SRTPStream> {
+        let mut streams = self.streams.lock().await;
+        if let Some(stream) = streams.get(&ssrc) {
+            Arc::clone(stream)
+        } else {
+            let stream = Arc::new(SRTPStream::new(ssrc, &self.streams));
+            streams.insert(ssrc, Arc::clone(&stream));
+            stream
+        }
+    }
+
+    /// accept returns the stream of the next incoming SSRC that wasn't opened
+    pub(crate) async fn accept(&self) -> Result<Arc<SRTPStream>> {
+        let mut accept_rx = self.accept_rx.lock().await;
+        if let Some(stream) = accept_rx.recv().await {
+            Ok(stream)
+        } else {
+            Err(Error::ErrConnectionClosed.into())
+        }
+    }
+
+    async fn write(&self, raw: Bytes) -> Result<usize> {
+        let (result_tx, result_rx) = oneshot::channel();
+        if self.write_tx.send((raw, result_tx)).await.is_err() {
+            return Err(Error::ErrConnectionClosed.into());
+        }
+        match result_rx.await {
+            Ok(result) => result,
+            Err(_) => Err(Error::ErrConnectionClosed.into()),
+        }
+    }
+
+    pub(crate) async fn write_rtp(&self, pkt: &rtp::packet::Packet) -> Result<usize> {
+        if !self.is_rtp {
+            return Err(Error::new("SRTCP session can't write RTP".to_owned()).into());
+        }
+        self.write(pkt.marshal()?).await
+    }
+
+    pub(crate) async fn write_rtcp(
+        &self,
+        pkt: &(dyn rtcp::packet::Packet + Send + Sync),
+    ) -> Result<usize> {
+        if self.is_rtp {
+            return Err(Error::new("SRTP session can't write RTCP".to_owned()).into());
+        }
+        self.write(pkt.marshal()?).await
+    }
+
+    /// close stops the session and ends the reads of all of its streams
+    pub(crate) async fn close(&self) -> Result<()> {
+        {
+            let mut close_tx = self.close_tx.lock().await;
+            close_tx.take();
+        }
+
+        let streams: Vec<Arc<SRTPStream>> = {
+            let streams = self.streams.lock().await;
+            streams.values().cloned().collect()
+        };
+        for stream in streams {
+            stream.close().await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// SRTPStream reads the packets of a single SSRC of a SRTPSession. The srtp stream
+/// is read by a task of its own, so a read can be dropped without losing a packet
+/// and the stream can be closed while it is being read.
+pub(crate) struct SRTPStream {
+    ssrc: SSRC,
+    streams: Weak<SRTPStreams>,
+    // packets_tx and close_rx are handed to the task reading the srtp stream once
+    // its first packet arrives, read returns EOF when every packets_tx is dropped
+    packets_tx: Mutex<Option<mpsc::Sender<Bytes>>>,
+    packets_rx: Mutex<mpsc::Receiver<Bytes>>,
+    close_tx: Mutex<Option<mpsc::Sender<()>>>,
+    close_rx: Mutex<Option<mpsc::Receiver<()>>>,
+}
+
+impl SRTPStream {
+    fn new(ssrc: SSRC, streams: &Arc<SRTPStreams>) -> Self {
+        let (packets_tx, packets_rx) = mpsc::channel(1);
+        let (close_tx, close_rx) = mpsc::channel(1);
+        SRTPStream {
+            ssrc,
+            streams: Arc::downgrade(streams),
+            packets_tx: Mutex::new(Some(packets_tx)),
+            packets_rx: Mutex::new(packets_rx),
+            close_tx: Mutex::new(Some(close_tx)),
+            close_rx: Mutex::new(Some(close_rx)),
+        }
+    }
+
+    /// attach starts reading the srtp stream of the SSRC, it is closed right away
+    /// when the SRTPStream is closed already
+    async fn attach(&self, mut stream: Stream) {
+        let packets_tx = {
+            let mut packets_tx = self.packets_tx.lock().await;
+            packets_tx.take()
+        };
+        let close_rx = {
+            let mut close_rx = self.close_rx.lock().await;
+            close_rx.take()
+        };
+        let (packets_tx, mut close_rx) = match (packets_tx, close_rx) {
+            (Some(packets_tx), Some(close_rx)) => (packets_tx, close_rx),
+            _ => {
+                let _ = stream.close().await;
+                return;
+            }
+        };
+
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; RECEIVE_MTU];
+            loop {
+                let n = tokio::select! {
+                    result = stream.read(&mut buf) => match result {
+                        Ok(n) => n,
+                        Err(err) => {
+                            if util::buffer::error::Error::ErrBufferShort.equal(&err) {
+                                log::warn!("Dropped packet larger than {} bytes", RECEIVE_MTU);
+                                continue;
+                            }
+                            break;
+                        }
+                    },
+                    _ = close_rx.recv() => break,
+                };
+
+                tokio::select! {
+                    result = packets_tx.send(Bytes::copy_from_slice(&buf[..n])) => {
+                        if result.is_err() {
+                            break;
+                        }
+                    }
+                    _ = close_rx.recv() => break,
+                };
+            }
+
+            if let Err(err) = stream.close().await {
+                log::trace!("Failed to close SRTP stream: {}", err);
+            }
+        });
+    }
+
+    pub(crate) fn get_ssrc(&self) -> SSRC {
+        self.ssrc
+    }
+
+    /// read reads the next packet of the stream into buf. It is cancel safe, a packet
+    /// is only taken from the stream once read is about to return it.
+    pub(crate) async fn read(&self, buf: &mut [u8]) -> Result<usize> {
+        let packet = {
+            let mut packets_rx = self.packets_rx.lock().await;
+            packets_rx.recv().await
+        };
+
+        if let Some(packet) = packet {
+            if buf.len() < packet.len() {
+                return Err(Error::new("buffer is too short".to_owned()).into());
+            }
+            buf[..packet.len()].copy_from_slice(&packet);
+            Ok(packet.len())
+        } else {
+            Err(Error::ErrIoEOF.into())
+        }
+    }
+
+    /// close ends the reads of the stream and removes it from its session, a later
+    /// packet of the SSRC is handed to accept again
+    pub(crate) async fn close(&self) -> Result<()> {
+        {
+            let mut close_tx = self.close_tx.lock().await;
+            close_tx.take();
+        }
+        {
+            let mut packets_tx = self.packets_tx.lock().await;
+            packets_tx.take();
+        }
+
+        if let Some(streams) = self.streams.upgrade() {
+            let mut streams = streams.lock().await;
+            if let Some(stream) = streams.get(&self.ssrc) {
+                if std::ptr::eq(stream.as_ref(), self) {
+                    streams.remove(&self.ssrc);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -2,8 +2,9 @@ use ice::state::ConnectionState;
 use std::fmt;
 
 /// ICETransportState represents the current state of the ICE transport.
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
 pub enum ICETransportState {
+    #[default]
     Unspecified,
 
     /// ICETransportStateNew indicates the ICETransport is waiting
@@ -43,12 +44,6 @@ pub enum ICETransportState {
     Closed,
 }
 
-impl Default for ICETransportState {
-    fn default() -> Self {
-        ICETransportState::Unspecified
-    }
-}
-
 const ICE_TRANSPORT_STATE_NEW_STR: &str = "New";
 const ICE_TRANSPORT_STATE_CHECKING_STR: &str = "Checking";
 const ICE_TRANSPORT_STATE_CONNECTED_STR: &str = "Connected";
@@ -41,22 +41,22 @@ pub type OnSelectedCandidatePairChangeHdlrFn = Box<
 
 /// ICETransport allows an application access to information about the ICE
 /// transport over which packets are sent and received.
-#[derive(Default, Clone)]
+#[derive(Default)]
 pub struct ICETransport {
-    gatherer: ICEGatherer,
-    role: ICERole,
+    gatherer: Arc<ICEGatherer>,
+    role: AtomicU8, //ICERole,
     on_connection_state_change_handler: Arc<Mutex<Option<OnConnectionStateChangeHdlrFn>>>,
     on_selected_candidate_pair_change_handler:
         Arc<Mutex<Option<OnSelectedCandidatePairChangeHdlrFn>>>,
-    state: Arc<AtomicU8>,                      // ICETransportState
-    conn: Option<Arc<dyn Conn + Send + Sync>>, //AgentConn
-    mux: Option<Mux>,
-    cancel_tx: Option<mpsc::Sender<()>>,
+    state: Arc<AtomicU8>, // ICETransportState
+    conn: Mutex<Option<Arc<dyn Conn + Send + Sync>>>, //AgentConn
+    mux: Mutex<Option<Mux>>,
+    cancel_tx: Mutex<Option<mpsc::Sender<()>>>,
 }
 
 impl ICETransport {
     /// creates a new new_icetransport.
-    pub fn new(gatherer: ICEGatherer) -> Self {
+    pub fn new(gatherer: Arc<ICEGatherer>) -> Self {
         ICETransport {
             state: Arc::new(AtomicU8::new(ICETransportState::New as u8)),
             gatherer,
@@ -64,10 +64,15 @@ impl ICETransport {
         }
     }
 
+    /// gatherer returns the ICEGatherer this ICETransport was created with.
+    pub fn gatherer(&self) -> Arc<ICEGatherer> {
+        Arc::clone(&self.gatherer)
+    }
+
     /// get_selected_candidate_pair returns the selected candidate pair on which packets are sent
     /// if there is no selected pair nil is returned
     pub async fn get_selected_candidate_pair(&self) -> Option<ICECandidatePair> {
-        if let Some(agent) = self.gatherer.get_agent() {
+        if let Some(agent) = self.gatherer.get_agent().await {
             if let Some(ice_pair) = agent.get_selected_candidate_pair().await {
                 let local = ICECandidate::from(&ice_pair.local);
                 let remote = ICECandidate::from(&ice_pair.remote);
@@ -79,7 +84,7 @@ impl ICETransport {
 
     /// Start incoming connectivity checks based on its configured role.
     pub async fn start(
-        &mut self,
+        &self,
         //gatherer: Option<ICEGatherer>,
         params: ICEParameters,
         role: Option<ICERole>,
@@ -90,7 +95,7 @@ impl ICETransport {
 
         self.ensure_gatherer().await?;
 
-        if let Some(agent) = self.gatherer.get_agent() {
+        if let Some(agent) = self.gatherer.get_agent().await {
             let state = Arc::clone(&self.state);
 
             let on_connection_state_change_handler =
@@ -104,7 +109,7 @@ impl ICETransport {
                     Box::pin(async move {
                         let mut handler = on_connection_state_change_handler_clone.lock().await;
                         if let Some(f) = &mut *handler {
-                            f(s);
+                            f(s).await;
                         }
                     })
                 }))
@@ -124,22 +129,27 @@ impl ICETransport {
                             let mut handler =
                                 on_selected_candidate_pair_change_handler_clone.lock().await;
                             if let Some(f) = &mut *handler {
-                                f(ICECandidatePair::new(local, remote));
+                                f(ICECandidatePair::new(local, remote)).await;
                             }
                         })
                     },
                 ))
                 .await;
 
-            self.role = if let Some(role) = role {
+            let role = if let Some(role) = role {
                 role
             } else {
                 ICERole::Controlled
             };
+            self.role.store(role as u8, Ordering::SeqCst);
 
             let (cancel_tx, cancel_rx) = mpsc::channel(1);
+            {
+                let mut internal_cancel_tx = self.cancel_tx.lock().await;
+                *internal_cancel_tx = Some(cancel_tx);
+            }
 
-            let conn: Arc<dyn Conn + Send + Sync> = match self.role {
+            let conn: Arc<dyn Conn + Send + Sync> = match role {
                 ICERole::Controlling => {
                     agent
                         .dial(
@@ -163,14 +173,19 @@ impl ICETransport {
                 _ => return Err(Error::ErrICERoleUnknown.into()),
             };
 
-            self.cancel_tx = Some(cancel_tx);
-            self.conn = Some(Arc::clone(&conn));
+            {
+                let mut internal_conn = self.conn.lock().await;
+                *internal_conn = Some(Arc::clone(&conn));
+            }
 
             let config = Config {
                 conn,
                 buffer_size: RECEIVE_MTU,
             };
-            self.mux = Some(Mux::new(config));
+            {
+                let mut mux = self.mux.lock().await;
+                *mux = Some(Mux::new(config));
+            }
 
             Ok(())
         } else {
@@ -180,8 +195,8 @@ impl ICETransport {
 
     /// restart is not exposed currently because ORTC has users create a whole new ICETransport
     /// so for now lets keep it private so we don't cause ORTC users to depend on non-standard APIs
-    pub(crate) async fn restart(&mut self) -> Result<()> {
-        if let Some(agent) = self.gatherer.get_agent() {
+    pub(crate) async fn restart(&self) -> Result<()> {
+        if let Some(agent) = self.gatherer.get_agent().await {
             agent
                 .restart(
                     self.gatherer
@@ -199,12 +214,19 @@ impl ICETransport {
     }
 
     /// Stop irreversibly stops the ICETransport.
-    pub async fn stop(&mut self) -> Result<()> {
+    pub async fn stop(&self) -> Result<()> {
         self.set_state(ICETransportState::Closed);
 
-        self.cancel_tx.take();
+        {
+            let mut cancel_tx = self.cancel_tx.lock().await;
+            cancel_tx.take();
+        }
 
-        if let Some(mut mux) = self.mux.take() {
+        let mux = {
+            let mut mux = self.mux.lock().await;
+            mux.take()
+        };
+        if let Some(mut mux) = mux {
             mux.close().await;
         }
 
@@ -231,17 +253,14 @@ impl ICETransport {
 
     /// Role indicates the current role of the ICE transport.
     pub fn role(&self) -> ICERole {
-        self.role
+        self.role.load(Ordering::SeqCst).into()
     }
 
     /// set_remote_candidates sets the sequence of candidates associated with the remote ICETransport.
-    pub async fn set_remote_candidates(
-        &mut self,
-        remote_candidates: &[ICECandidate],
-    ) -> Result<()> {
+    pub async fn set_remote_candidates(&self, remote_candidates: &[ICECandidate]) -> Result<()> {
         self.ensure_gatherer().await?;
 
-        if let Some(agent) = self.gatherer.get_agent() {
+        if let Some(agent) = self.gatherer.get_agent().await {
             for rc in remote_candidates {
                 let c: Arc<dyn Candidate + Send + Sync> = Arc::new(rc.to_ice().await?);
                 agent.add_remote_candidate(&c).await?;
@@ -253,13 +272,10 @@ impl ICETransport {
     }
 
     /// adds a candidate associated with the remote ICETransport.
-    pub async fn add_remote_candidate(
-        &mut self,
-        remote_candidate: Option<ICECandidate>,
-    ) -> Result<()> {
+    pub async fn add_remote_candidate(&self, remote_candidate: Option<ICECandidate>) -> Result<()> {
         self.ensure_gatherer().await?;
 
-        if let Some(agent) = self.gatherer.get_agent() {
+        if let Some(agent) = self.gatherer.get_agent().await {
             if let Some(r) = remote_candidate {
                 let c: Arc<dyn Candidate + Send + Sync> = Arc::new(r.to_ice().await?);
                 agent.add_remote_candidate(&c).await?;
@@ -276,20 +292,21 @@ impl ICETransport {
         ICETransportState::from(self.state.load(Ordering::SeqCst))
     }
 
-    pub(crate) fn set_state(&mut self, s: ICETransportState) {
+    pub(crate) fn set_state(&self, s: ICETransportState) {
         self.state.store(s as u8, Ordering::SeqCst)
     }
 
     pub(crate) async fn new_endpoint(&self, f: MatchFunc) -> Option<Arc<Endpoint>> {
-        if let Some(mux) = &self.mux {
+        let mux = self.mux.lock().await;
+        if let Some(mux) = &*mux {
             Some(mux.new_endpoint(f).await)
         } else {
             None
         }
     }
 
-    pub(crate) async fn ensure_gatherer(&mut self) -> Result<()> {
-        if self.gatherer.get_agent().is_none() {
+    pub(crate) async fn ensure_gatherer(&self) -> Result<()> {
+        if self.gatherer.get_agent().await.is_none() {
             self.gatherer.create_agent().await
         } else {
             Ok(())
@@ -324,7 +341,7 @@ impl ICETransport {
         new_ufrag: String,
         new_pwd: String,
     ) -> bool {
-        if let Some(agent) = self.gatherer.get_agent() {
+        if let Some(agent) = self.gatherer.get_agent().await {
             let (ufrag, upwd) = agent.get_remote_user_credentials().await;
             ufrag != new_ufrag || upwd != new_pwd
         } else {
@@ -337,7 +354,7 @@ impl ICETransport {
         new_ufrag: String,
         new_pwd: String,
     ) -> Result<()> {
-        if let Some(agent) = self.gatherer.get_agent() {
+        if let Some(agent) = self.gatherer.get_agent().await {
             Ok(agent.set_remote_credentials(new_ufrag, new_pwd).await?)
         } else {
             Err(Error::ErrICEAgentNotExist.into())
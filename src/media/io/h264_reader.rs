@@ -0,0 +1,284 @@
+use crate::error::Error;
+
+use anyhow::Result;
+use bytes::{BufMut, BytesMut};
+use std::fmt;
+use std::io::Read;
+
+/// NalUnitType is the type of a NAL
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum NalUnitType {
+    /// Unspecified
+    Unspecified = 0,
+    /// Coded slice of a non-IDR picture
+    CodedSliceNonIdr = 1,
+    /// Coded slice data partition A
+    CodedSliceDataPartitionA = 2,
+    /// Coded slice data partition B
+    CodedSliceDataPartitionB = 3,
+    /// Coded slice data partition C
+    CodedSliceDataPartitionC = 4,
+    /// Coded slice of an IDR picture
+    CodedSliceIdr = 5,
+    /// Supplemental enhancement information (SEI)
+    SEI = 6,
+    /// Sequence parameter set
+    SPS = 7,
+    /// Picture parameter set
+    PPS = 8,
+    /// Access unit delimiter
+    AUD = 9,
+    /// End of sequence
+    EndOfSequence = 10,
+    /// End of stream
+    EndOfStream = 11,
+    /// Filler data
+    Filler = 12,
+    /// Sequence parameter set extension
+    SpsExt = 13,
+    /// Coded slice of an auxiliary coded picture without partitioning
+    CodedSliceAux = 19,
+    /// Reserved (14..18, 20..23) or unspecified (24..31)
+    Reserved,
+}
+
+impl fmt::Display for NalUnitType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match *self {
+            NalUnitType::Unspecified => "Unspecified",
+            NalUnitType::CodedSliceNonIdr => "CodedSliceNonIdr",
+            NalUnitType::CodedSliceDataPartitionA => "CodedSliceDataPartitionA",
+            NalUnitType::CodedSliceDataPartitionB => "CodedSliceDataPartitionB",
+            NalUnitType::CodedSliceDataPartitionC => "CodedSliceDataPartitionC",
+            NalUnitType::CodedSliceIdr => "CodedSliceIdr",
+            NalUnitType::SEI => "SEI",
+            NalUnitType::SPS => "SPS",
+            NalUnitType::PPS => "PPS",
+            NalUnitType::AUD => "AUD",
+            NalUnitType::EndOfSequence => "EndOfSequence",
+            NalUnitType::EndOfStream => "EndOfStream",
+            NalUnitType::Filler => "Filler",
+            NalUnitType::SpsExt => "SpsExt",
+            NalUnitType::CodedSliceAux => "CodedSliceAux",
+            NalUnitType::Reserved => "Reserved",
+        };
+        write!(f, "{}({})", s, *self as u8)
+    }
+}
+
+impl From<u8> for NalUnitType {
+    fn from(v: u8) -> Self {
+        match v {
+            0 => NalUnitType::Unspecified,
+            1 => NalUnitType::CodedSliceNonIdr,
+            2 => NalUnitType::CodedSliceDataPartitionA,
+            3 => NalUnitType::CodedSliceDataPartitionB,
+            4 => NalUnitType::CodedSliceDataPartitionC,
+            5 => NalUnitType::CodedSliceIdr,
+            6 => NalUnitType::SEI,
+            7 => NalUnitType::SPS,
+            8 => NalUnitType::PPS,
+            9 => NalUnitType::AUD,
+            10 => NalUnitType::EndOfSequence,
+            11 => NalUnitType::EndOfStream,
+            12 => NalUnitType::Filler,
+            13 => NalUnitType::SpsExt,
+            19 => NalUnitType::CodedSliceAux,
+            _ => NalUnitType::Reserved,
+        }
+    }
+}
+
+/// NAL H.264 Network Abstraction Layer
+#[derive(Debug, Clone, PartialEq)]
+pub struct NAL {
+    pub picture_order_count: u32,
+
+    // NAL header
+    pub forbidden_zero_bit: bool,
+    pub ref_idc: u8,
+    pub unit_type: NalUnitType,
+
+    /// header byte + rbsp
+    pub data: BytesMut,
+}
+
+impl NAL {
+    fn new(data: BytesMut) -> Self {
+        let first_byte = data[0];
+        NAL {
+            picture_order_count: 0,
+            forbidden_zero_bit: (first_byte & 0x80) != 0,
+            ref_idc: (first_byte & 0x60) >> 5,
+            unit_type: NalUnitType::from(first_byte & 0x1F),
+            data,
+        }
+    }
+}
+
+const NAL_PREFIX_3BYTES: [u8; 3] = [0, 0, 1];
+const NAL_PREFIX_4BYTES: [u8; 4] = [0, 0, 0, 1];
+const READ_BUFFER_SIZE: usize = 4096;
+
+/// H264Reader reads data from stream and constructs h264 nal units
+pub struct H264Reader<R: Read> {
+    reader: R,
+    nal_buffer: BytesMut,
+    count_of_consecutive_zero_bytes: usize,
+    nal_prefix_parsed: bool,
+    read_buffer: Vec<u8>,
+    read_offset: usize,
+}
+
+impl<R: Read> H264Reader<R> {
+    /// new creates new H264Reader
+    pub fn new(reader: R) -> Self {
+        H264Reader {
+            reader,
+            nal_buffer: BytesMut::new(),
+            count_of_consecutive_zero_bytes: 0,
+            nal_prefix_parsed: false,
+            read_buffer: vec![],
+            read_offset: 0,
+        }
+    }
+
+    /// read_byte returns the next byte of the stream, or None at its end
+    fn read_byte(&mut self) -> Result<Option<u8>> {
+        if self.read_offset == self.read_buffer.len() {
+            self.read_buffer.resize(READ_BUFFER_SIZE, 0);
+            let n = loop {
+                match self.reader.read(&mut self.read_buffer) {
+                    Ok(n) => break n,
+                    Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                    Err(err) => return Err(err.into()),
+                }
+            };
+            self.read_buffer.truncate(n);
+            self.read_offset = 0;
+
+            if n == 0 {
+                return Ok(None);
+            }
+        }
+
+        let b = self.read_buffer[self.read_offset];
+        self.read_offset += 1;
+        Ok(Some(b))
+    }
+
+    fn bit_stream_starts_with_h264prefix(&mut self) -> Result<()> {
+        let mut prefix_buffer = Vec::with_capacity(NAL_PREFIX_4BYTES.len());
+        while prefix_buffer.len() < NAL_PREFIX_4BYTES.len() {
+            match self.read_byte()? {
+                Some(b) => prefix_buffer.push(b),
+                None => break,
+            }
+        }
+
+        let n = prefix_buffer.len();
+        if n == 0 {
+            return Err(Error::ErrIoEOF.into());
+        }
+        if n < NAL_PREFIX_3BYTES.len() {
+            return Err(Error::ErrDataIsNotH264Stream.into());
+        }
+
+        let nal_prefix3bytes_found = prefix_buffer[..3] == NAL_PREFIX_3BYTES;
+        if n == 3 {
+            return if nal_prefix3bytes_found {
+                Err(Error::ErrIoEOF.into())
+            } else {
+                Err(Error::ErrDataIsNotH264Stream.into())
+            };
+        }
+
+        if nal_prefix3bytes_found {
+            // the fourth byte already belongs to the first NAL
+            self.nal_buffer.put_u8(prefix_buffer[3]);
+            Ok(())
+        } else if prefix_buffer[..] == NAL_PREFIX_4BYTES {
+            Ok(())
+        } else {
+            Err(Error::ErrDataIsNotH264Stream.into())
+        }
+    }
+
+    /// next_nal reads from stream and returns the next NAL. SEI NALs are
+    /// skipped, and ErrIoEOF is returned when no more NALs are available.
+    pub fn next_nal(&mut self) -> Result<NAL> {
+        if !self.nal_prefix_parsed {
+            self.bit_stream_starts_with_h264prefix()?;
+            self.nal_prefix_parsed = true;
+        }
+
+        loop {
+            let read_byte = match self.read_byte()? {
+                Some(b) => b,
+                None => {
+                    // the last NAL of the stream is ended by its end
+                    self.trim_trailing_zeros();
+                    break;
+                }
+            };
+
+            if self.process_byte(read_byte) {
+                if self.nal_buffer.is_empty() {
+                    // consecutive start codes, there is no NAL in between
+                    continue;
+                }
+                if NalUnitType::from(self.nal_buffer[0] & 0x1F) == NalUnitType::SEI {
+                    self.nal_buffer.clear();
+                    continue;
+                }
+                break;
+            }
+
+            self.nal_buffer.put_u8(read_byte);
+        }
+
+        if self.nal_buffer.is_empty() {
+            return Err(Error::ErrIoEOF.into());
+        }
+
+        let nal_buffer = self.nal_buffer.split();
+        if NalUnitType::from(nal_buffer[0] & 0x1F) == NalUnitType::SEI {
+            return Err(Error::ErrIoEOF.into());
+        }
+
+        Ok(NAL::new(nal_buffer))
+    }
+
+    /// process_byte returns true when read_byte completes a start code. The
+    /// zero bytes of the start code are removed from nal_buffer
+    fn process_byte(&mut self, read_byte: u8) -> bool {
+        let mut start_code_found = false;
+
+        match read_byte {
+            0 => {
+                self.count_of_consecutive_zero_bytes += 1;
+            }
+            1 => {
+                if self.count_of_consecutive_zero_bytes >= 2 {
+                    self.trim_trailing_zeros();
+                    start_code_found = true;
+                }
+                self.count_of_consecutive_zero_bytes = 0;
+            }
+            _ => {
+                self.count_of_consecutive_zero_bytes = 0;
+            }
+        }
+
+        start_code_found
+    }
+
+    fn trim_trailing_zeros(&mut self) {
+        let nal_unit_length = self
+            .nal_buffer
+            .len()
+            .saturating_sub(self.count_of_consecutive_zero_bytes);
+        self.nal_buffer.truncate(nal_unit_length);
+        self.count_of_consecutive_zero_bytes = 0;
+    }
+}
@@ -0,0 +1,53 @@
+use super::Writer;
+use crate::media::rtp::codecs::h264::H264Depacketizer;
+use crate::media::rtp::codecs::Depacketizer;
+
+use anyhow::Result;
+use std::io::{Seek, Write};
+
+/// H264Writer is used to take RTP packets of an H264 track and write
+/// them as an Annex B byte stream to disk
+pub struct H264Writer<W: Write + Seek> {
+    writer: W,
+    depacketizer: H264Depacketizer,
+    has_key_frame: bool,
+}
+
+impl<W: Write + Seek> H264Writer<W> {
+    /// new initializes a new H264 writer with an io.Writer output
+    pub fn new(writer: W) -> Self {
+        H264Writer {
+            writer,
+            depacketizer: H264Depacketizer::default(),
+            has_key_frame: false,
+        }
+    }
+}
+
+impl<W: Write + Seek> Writer for H264Writer<W> {
+    /// write_rtp adds a new packet and writes the appropriate headers for it
+    fn write_rtp(&mut self, packet: &rtp::packet::Packet) -> Result<()> {
+        if packet.payload.is_empty() {
+            return Ok(());
+        }
+
+        if !self.has_key_frame {
+            // the recording starts with the first key frame, so the file can be decoded
+            if !self.depacketizer.is_keyframe(&packet.payload) {
+                return Ok(());
+            }
+            self.has_key_frame = true;
+        }
+
+        let payload = self.depacketizer.depacketize(&packet.payload)?;
+        self.writer.write_all(&payload)?;
+
+        Ok(())
+    }
+
+    /// close stops the recording
+    fn close(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
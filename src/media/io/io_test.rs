@@ -0,0 +1,432 @@
+use super::h264_reader::*;
+use super::h264_writer::*;
+use super::ivf_reader::*;
+use super::ivf_writer::*;
+use super::ogg_reader::*;
+use super::ogg_writer::*;
+use super::*;
+use crate::api::media_engine::MIME_TYPE_VP8;
+use crate::error::Error;
+use crate::media::rtp::rtp_codec::{RTPCodecCapability, RTPCodecParameters, RTPParameters};
+use crate::media::track::track_local::track_local_static_sample::TrackLocalStaticSample;
+use crate::media::track::track_local::{TrackLocal, TrackLocalContext, TrackLocalWriter};
+use crate::media::Sample;
+
+use async_trait::async_trait;
+use bytes::{BufMut, Bytes, BytesMut};
+use std::io::Cursor;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use util::marshal::Unmarshal;
+
+fn vp8_file_header() -> IVFFileHeader {
+    IVFFileHeader {
+        signature: *b"DKIF",
+        version: 0,
+        header_size: IVF_FILE_HEADER_SIZE as u16,
+        four_cc: *b"VP80",
+        width: 640,
+        height: 480,
+        timebase_denominator: 30,
+        timebase_numerator: 1,
+        num_frames: 0,
+        unused: 0,
+    }
+}
+
+fn build_ivf(header: &IVFFileHeader, frames: &[Bytes]) -> Vec<u8> {
+    let mut b = BytesMut::new();
+    b.put(&header.signature[..]);
+    b.put_u16_le(header.version);
+    b.put_u16_le(header.header_size);
+    b.put(&header.four_cc[..]);
+    b.put_u16_le(header.width);
+    b.put_u16_le(header.height);
+    b.put_u32_le(header.timebase_denominator);
+    b.put_u32_le(header.timebase_numerator);
+    b.put_u32_le(frames.len() as u32);
+    b.put_u32_le(header.unused);
+    for (i, frame) in frames.iter().enumerate() {
+        b.put_u32_le(frame.len() as u32);
+        b.put_u64_le(i as u64);
+        b.put(&frame[..]);
+    }
+    b.to_vec()
+}
+
+#[test]
+fn test_ivf_reader_parse_frames() -> Result<()> {
+    let frames = vec![
+        Bytes::from_static(&[0x10, 0x01, 0x02]),
+        Bytes::from_static(&[0x11, 0x03]),
+    ];
+    let data = build_ivf(&vp8_file_header(), &frames);
+
+    let (mut reader, header) = IVFReader::new(Cursor::new(data))?;
+    assert_eq!(header.four_cc, *b"VP80");
+    assert_eq!(header.width, 640);
+    assert_eq!(header.height, 480);
+    assert_eq!(header.num_frames, 2);
+    assert_eq!(reader.bytes_read(), IVF_FILE_HEADER_SIZE);
+
+    for (i, expected) in frames.iter().enumerate() {
+        let (frame, frame_header) = reader.parse_next_frame()?;
+        assert_eq!(&frame, expected);
+        assert_eq!(frame_header.frame_size as usize, expected.len());
+        assert_eq!(frame_header.timestamp, i as u64);
+    }
+    assert_eq!(
+        reader.bytes_read(),
+        IVF_FILE_HEADER_SIZE + 2 * IVF_FRAME_HEADER_SIZE + 5
+    );
+
+    assert!(
+        reader.parse_next_frame().is_err(),
+        "no more frames are available"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_ivf_reader_invalid_header() {
+    let mut header = vp8_file_header();
+    header.signature = *b"DKIX";
+    if let Err(err) = IVFReader::new(Cursor::new(build_ivf(&header, &[]))) {
+        assert!(Error::ErrSignatureMismatch.equal(&err));
+    } else {
+        panic!("expected error, but got ok");
+    }
+
+    let mut header = vp8_file_header();
+    header.version = 1;
+    if let Err(err) = IVFReader::new(Cursor::new(build_ivf(&header, &[]))) {
+        assert!(Error::ErrUnknownIVFVersion.equal(&err));
+    } else {
+        panic!("expected error, but got ok");
+    }
+}
+
+#[test]
+fn test_ivf_writer_unsupported_codec() {
+    let mut header = vp8_file_header();
+    header.four_cc = *b"H264";
+
+    let mut buffer = vec![];
+    if let Err(err) = IVFWriter::new(Cursor::new(&mut buffer), &header) {
+        assert!(Error::ErrNoDepacketizerForCodec.equal(&err));
+    } else {
+        panic!("expected error, but got ok");
+    }
+}
+
+#[test]
+fn test_ivf_writer_waits_for_keyframe() -> Result<()> {
+    let vp8_packet = |marker: bool, payload: &'static [u8]| rtp::packet::Packet {
+        header: rtp::header::Header {
+            marker,
+            ..Default::default()
+        },
+        payload: Bytes::from_static(payload),
+    };
+
+    let mut buffer = vec![];
+    {
+        let mut writer = IVFWriter::new(Cursor::new(&mut buffer), &vp8_file_header())?;
+        // interframe, dropped
+        writer.write_rtp(&vp8_packet(true, &[0x10, 0x01, 0xAA]))?;
+        // keyframe split over two packets
+        writer.write_rtp(&vp8_packet(false, &[0x10, 0x00, 0xBB]))?;
+        writer.write_rtp(&vp8_packet(true, &[0x00, 0xCC]))?;
+        // continuation of a frame whose head was lost, dropped
+        writer.write_rtp(&vp8_packet(true, &[0x00, 0xDD]))?;
+        writer.close()?;
+    }
+
+    let (mut reader, header) = IVFReader::new(Cursor::new(buffer))?;
+    assert_eq!(header.num_frames, 1);
+    let (frame, _) = reader.parse_next_frame()?;
+    assert_eq!(&frame[..], &[0x00, 0xBB, 0xCC]);
+    assert!(reader.parse_next_frame().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_ogg_writer_reader_round_trip() -> Result<()> {
+    let payloads = [
+        Bytes::from_static(&[0x98, 0x36, 0xbe, 0x88, 0x9e]),
+        // larger than a single lacing segment
+        Bytes::from(vec![0x42u8; 600]),
+    ];
+
+    let mut buffer = vec![];
+    {
+        let mut writer = OggWriter::new(Cursor::new(&mut buffer), 48000, 2)?;
+        for (i, payload) in payloads.iter().enumerate() {
+            writer.write_rtp(&rtp::packet::Packet {
+                header: rtp::header::Header {
+                    timestamp: 1000 + 960 * i as u32,
+                    ..Default::default()
+                },
+                payload: payload.clone(),
+            })?;
+        }
+        writer.close()?;
+    }
+
+    let (mut reader, header) = OggReader::new(Cursor::new(buffer), true)?;
+    assert_eq!(header.channels, 2);
+    assert_eq!(header.sample_rate, 48000);
+    assert_eq!(header.pre_skip, DEFAULT_PRE_SKIP);
+    assert_eq!(header.version, 1);
+
+    // comment page
+    let (payload, page_header) = reader.parse_next_page()?;
+    assert_eq!(&payload[..8], COMMENT_PAGE_SIGNATURE);
+    assert_eq!(page_header.granule_position, 0);
+
+    let (payload, page_header) = reader.parse_next_page()?;
+    assert_eq!(payload, payloads[0]);
+    assert_eq!(
+        page_header.header_type,
+        PAGE_HEADER_TYPE_CONTINUATION_OF_STREAM
+    );
+    assert_eq!(page_header.granule_position, 1);
+    assert_eq!(page_header.index, 2);
+
+    // the last page is rewritten in place to mark the end of the stream
+    let (payload, page_header) = reader.parse_next_page()?;
+    assert_eq!(payload, payloads[1]);
+    assert_eq!(page_header.header_type, PAGE_HEADER_TYPE_END_OF_STREAM);
+    assert_eq!(page_header.granule_position, 961);
+    assert_eq!(page_header.index, 3);
+
+    assert!(reader.parse_next_page().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_ogg_reader_checksum_mismatch() -> Result<()> {
+    let mut buffer = vec![];
+    OggWriter::new(Cursor::new(&mut buffer), 48000, 2)?;
+
+    // corrupt the payload of the ID page
+    buffer[PAGE_HEADER_SIZE + 1 + 10] ^= 0xFF;
+
+    if let Err(err) = OggReader::new(Cursor::new(buffer.clone()), true) {
+        assert!(Error::ErrChecksumMismatch.equal(&err));
+    } else {
+        panic!("expected error, but got ok");
+    }
+
+    // the checksum is not verified unless requested
+    OggReader::new(Cursor::new(buffer), false)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_ogg_reader_invalid_signature() {
+    let mut data = vec![0u8; PAGE_HEADER_SIZE];
+    data[..4].copy_from_slice(b"OggX");
+
+    if let Err(err) = OggReader::new(Cursor::new(data), false) {
+        assert!(Error::ErrBadIDPageSignature.equal(&err));
+    } else {
+        panic!("expected error, but got ok");
+    }
+}
+
+#[test]
+fn test_h264_reader_next_nal() -> Result<()> {
+    let data = vec![
+        0x00, 0x00, 0x00, 0x01, 0x67, 0x42, 0x00, // SPS, 4 bytes prefix
+        0x00, 0x00, 0x01, 0x06, 0x05, 0xFF, // SEI, skipped
+        0x00, 0x00, 0x01, 0x68, 0xCE, // PPS, 3 bytes prefix
+        0x00, 0x00, 0x00, 0x01, 0x65, 0x88, 0x84, // IDR, ended by the stream
+    ];
+
+    let mut reader = H264Reader::new(Cursor::new(data));
+
+    let nal = reader.next_nal()?;
+    assert_eq!(nal.unit_type, NalUnitType::SPS);
+    assert_eq!(nal.ref_idc, 3);
+    assert!(!nal.forbidden_zero_bit);
+    assert_eq!(&nal.data[..], &[0x67, 0x42]);
+
+    let nal = reader.next_nal()?;
+    assert_eq!(nal.unit_type, NalUnitType::PPS);
+    assert_eq!(&nal.data[..], &[0x68, 0xCE]);
+
+    let nal = reader.next_nal()?;
+    assert_eq!(nal.unit_type, NalUnitType::CodedSliceIdr);
+    assert_eq!(&nal.data[..], &[0x65, 0x88, 0x84]);
+
+    if let Err(err) = reader.next_nal() {
+        assert!(Error::ErrIoEOF.equal(&err));
+    } else {
+        panic!("expected error, but got ok");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_h264_reader_invalid_stream() {
+    let mut reader = H264Reader::new(Cursor::new(vec![0x00, 0x01, 0x02, 0x03]));
+    if let Err(err) = reader.next_nal() {
+        assert!(Error::ErrDataIsNotH264Stream.equal(&err));
+    } else {
+        panic!("expected error, but got ok");
+    }
+
+    let mut reader = H264Reader::new(Cursor::new(vec![]));
+    if let Err(err) = reader.next_nal() {
+        assert!(Error::ErrIoEOF.equal(&err));
+    } else {
+        panic!("expected error, but got ok");
+    }
+}
+
+#[test]
+fn test_h264_writer_waits_for_keyframe() -> Result<()> {
+    let h264_packet = |payload: &'static [u8]| rtp::packet::Packet {
+        payload: Bytes::from_static(payload),
+        ..Default::default()
+    };
+
+    let mut buffer = vec![];
+    {
+        let mut writer = H264Writer::new(Cursor::new(&mut buffer));
+        // non-IDR slice, dropped
+        writer.write_rtp(&h264_packet(&[0x41, 0x9A]))?;
+        // STAP-A with SPS and PPS
+        writer.write_rtp(&h264_packet(&[
+            0x78, 0x00, 0x02, 0x67, 0x42, 0x00, 0x02, 0x68, 0xCE,
+        ]))?;
+        writer.write_rtp(&h264_packet(&[0x65, 0x88]))?;
+        writer.write_rtp(&h264_packet(&[0x41, 0x9A]))?;
+        writer.close()?;
+    }
+
+    assert_eq!(
+        buffer,
+        vec![
+            0x00, 0x00, 0x00, 0x01, 0x67, 0x42, 0x00, 0x00, 0x00, 0x01, 0x68, 0xCE, 0x00, 0x00,
+            0x00, 0x01, 0x65, 0x88, 0x00, 0x00, 0x00, 0x01, 0x41, 0x9A,
+        ]
+    );
+
+    // the recorded stream can be read back
+    let mut reader = H264Reader::new(Cursor::new(buffer));
+    let mut unit_types = vec![];
+    while let Ok(nal) = reader.next_nal() {
+        unit_types.push(nal.unit_type);
+    }
+    assert_eq!(
+        unit_types,
+        vec![
+            NalUnitType::SPS,
+            NalUnitType::PPS,
+            NalUnitType::CodedSliceIdr,
+            NalUnitType::CodedSliceNonIdr,
+        ]
+    );
+
+    Ok(())
+}
+
+#[derive(Default)]
+struct RecordingWriter {
+    packets: Mutex<Vec<rtp::packet::Packet>>,
+}
+
+#[async_trait]
+impl TrackLocalWriter for RecordingWriter {
+    async fn write_rtp(&self, p: &rtp::packet::Packet) -> Result<usize> {
+        let mut packets = self.packets.lock().await;
+        packets.push(p.clone());
+        Ok(p.payload.len())
+    }
+
+    async fn write(&self, b: &[u8]) -> Result<usize> {
+        let pkt = rtp::packet::Packet::unmarshal(&mut &b[..])?;
+        self.write_rtp(&pkt).await
+    }
+}
+
+#[tokio::test]
+async fn test_ivf_play_from_disk_save_to_disk() -> Result<()> {
+    let frames = vec![
+        Bytes::from(vec![0x10u8; 2500]), // keyframe, split over several packets
+        Bytes::from(vec![0x11u8; 100]),
+        Bytes::from(vec![0x11u8; 1500]),
+    ];
+    let input = build_ivf(&vp8_file_header(), &frames);
+
+    let track = TrackLocalStaticSample::new(
+        RTPCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    );
+    let recording = Arc::new(RecordingWriter::default());
+    track
+        .bind(&TrackLocalContext {
+            id: "a".to_owned(),
+            params: RTPParameters {
+                header_extensions: vec![],
+                codecs: vec![RTPCodecParameters {
+                    capability: RTPCodecCapability {
+                        mime_type: MIME_TYPE_VP8.to_owned(),
+                        clock_rate: 90000,
+                        ..Default::default()
+                    },
+                    payload_type: 96,
+                    ..Default::default()
+                }],
+            },
+            ssrc: 1234,
+            write_stream: Some(Arc::clone(&recording) as Arc<dyn TrackLocalWriter + Send + Sync>),
+        })
+        .await?;
+
+    // play from disk
+    let (mut reader, header) = IVFReader::new(Cursor::new(input))?;
+    while let Ok((frame, _)) = reader.parse_next_frame() {
+        track
+            .write_sample(&Sample {
+                data: frame,
+                duration: Duration::from_millis(33),
+                ..Default::default()
+            })
+            .await?;
+    }
+
+    // save to disk
+    let mut output = vec![];
+    {
+        let mut writer = IVFWriter::new(Cursor::new(&mut output), &header)?;
+        let packets = recording.packets.lock().await;
+        assert!(packets.len() > frames.len());
+        for p in packets.iter() {
+            writer.write_rtp(p)?;
+        }
+        writer.close()?;
+    }
+
+    let (mut reader, saved_header) = IVFReader::new(Cursor::new(output))?;
+    assert_eq!(saved_header.num_frames, frames.len() as u32);
+    for expected in &frames {
+        let (frame, _) = reader.parse_next_frame()?;
+        assert_eq!(&frame, expected);
+    }
+    assert!(reader.parse_next_frame().is_err());
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use crate::error::Error;
+
+use anyhow::Result;
+use bytes::{Bytes, BytesMut};
+use std::io::Read;
+
+pub const IVF_FILE_HEADER_SIGNATURE: &[u8] = b"DKIF";
+pub const IVF_FILE_HEADER_SIZE: usize = 32;
+pub const IVF_FRAME_HEADER_SIZE: usize = 12;
+
+/// IVFFileHeader 32-byte header for IVF files
+/// https://wiki.multimedia.cx/index.php/IVF
+#[derive(Default, Debug, Copy, Clone, PartialEq)]
+pub struct IVFFileHeader {
+    pub signature: [u8; 4],        // 0-3
+    pub version: u16,              // 4-5
+    pub header_size: u16,          // 6-7
+    pub four_cc: [u8; 4],          // 8-11
+    pub width: u16,                // 12-13
+    pub height: u16,               // 14-15
+    pub timebase_denominator: u32, // 16-19
+    pub timebase_numerator: u32,   // 20-23
+    pub num_frames: u32,           // 24-27
+    pub unused: u32,               // 28-31
+}
+
+/// IVFFrameHeader 12-byte header for IVF frames
+/// https://wiki.multimedia.cx/index.php/IVF
+#[derive(Default, Debug, Copy, Clone, PartialEq)]
+pub struct IVFFrameHeader {
+    pub frame_size: u32, // 0-3
+    pub timestamp: u64,  // 4-11
+}
+
+/// IVFReader is used to read IVF files and return frame payloads
+pub struct IVFReader<R: Read> {
+    reader: R,
+    bytes_read: usize,
+}
+
+impl<R: Read> IVFReader<R> {
+    /// new returns a new IVF reader and IVF file header
+    /// with an io.Reader input
+    pub fn new(reader: R) -> Result<(IVFReader<R>, IVFFileHeader)> {
+        let mut r = IVFReader {
+            reader,
+            bytes_read: 0,
+        };
+
+        let header = r.parse_file_header()?;
+
+        Ok((r, header))
+    }
+
+    /// bytes_read returns how many bytes of the stream have been parsed so far
+    pub fn bytes_read(&self) -> usize {
+        self.bytes_read
+    }
+
+    /// parse_next_frame reads from stream and returns IVF frame payload and header.
+    /// An io error is returned once no more frames are available, or if
+    /// the frame data is incomplete.
+    pub fn parse_next_frame(&mut self) -> Result<(Bytes, IVFFrameHeader)> {
+        let mut h = [0u8; IVF_FRAME_HEADER_SIZE];
+        self.reader.read_exact(&mut h)?;
+
+        let header = IVFFrameHeader {
+            frame_size: u32::from_le_bytes([h[0], h[1], h[2], h[3]]),
+            timestamp: u64::from_le_bytes([h[4], h[5], h[6], h[7], h[8], h[9], h[10], h[11]]),
+        };
+
+        let mut payload = BytesMut::new();
+        payload.resize(header.frame_size as usize, 0);
+        self.reader.read_exact(&mut payload)?;
+
+        self.bytes_read += IVF_FRAME_HEADER_SIZE + header.frame_size as usize;
+
+        Ok((payload.freeze(), header))
+    }
+
+    /// parse_file_header reads 32 bytes from stream and returns
+    /// IVF file header. This is always called before parse_next_frame()
+    fn parse_file_header(&mut self) -> Result<IVFFileHeader> {
+        let mut h = [0u8; IVF_FILE_HEADER_SIZE];
+        self.reader.read_exact(&mut h)?;
+
+        let header = IVFFileHeader {
+            signature: [h[0], h[1], h[2], h[3]],
+            version: u16::from_le_bytes([h[4], h[5]]),
+            header_size: u16::from_le_bytes([h[6], h[7]]),
+            four_cc: [h[8], h[9], h[10], h[11]],
+            width: u16::from_le_bytes([h[12], h[13]]),
+            height: u16::from_le_bytes([h[14], h[15]]),
+            timebase_denominator: u32::from_le_bytes([h[16], h[17], h[18], h[19]]),
+            timebase_numerator: u32::from_le_bytes([h[20], h[21], h[22], h[23]]),
+            num_frames: u32::from_le_bytes([h[24], h[25], h[26], h[27]]),
+            unused: u32::from_le_bytes([h[28], h[29], h[30], h[31]]),
+        };
+
+        if header.signature != IVF_FILE_HEADER_SIGNATURE {
+            return Err(Error::ErrSignatureMismatch.into());
+        } else if header.version != 0 {
+            return Err(Error::ErrUnknownIVFVersion.into());
+        }
+
+        self.bytes_read += IVF_FILE_HEADER_SIZE;
+
+        Ok(header)
+    }
+}
@@ -0,0 +1,116 @@
+use super::ivf_reader::IVFFileHeader;
+use super::Writer;
+use crate::api::media_engine::{MIME_TYPE_AV1, MIME_TYPE_VP8, MIME_TYPE_VP9};
+use crate::error::Error;
+use crate::media::rtp::codecs::{depacketizer_for_mime_type, Depacketizer};
+
+use anyhow::Result;
+use bytes::BytesMut;
+use std::io::{Seek, SeekFrom, Write};
+
+/// IVFWriter is used to take RTP packets of a VP8, VP9 or AV1 track
+/// and write them to an IVF on disk
+pub struct IVFWriter<W: Write + Seek> {
+    writer: W,
+    depacketizer: Box<dyn Depacketizer + Send + Sync>,
+    count: u64,
+    seen_key_frame: bool,
+    current_frame: Option<BytesMut>,
+}
+
+impl<W: Write + Seek> IVFWriter<W> {
+    /// new initializes a new IVF writer with an io.Writer output. The codec
+    /// of the RTP packets is taken from the FOURCC of the header
+    pub fn new(writer: W, header: &IVFFileHeader) -> Result<Self> {
+        let mime_type = match &header.four_cc {
+            b"VP80" => MIME_TYPE_VP8,
+            b"VP90" => MIME_TYPE_VP9,
+            b"AV01" => MIME_TYPE_AV1,
+            _ => return Err(Error::ErrNoDepacketizerForCodec.into()),
+        };
+
+        let mut w = IVFWriter {
+            writer,
+            depacketizer: depacketizer_for_mime_type(mime_type)?,
+            count: 0,
+            seen_key_frame: false,
+            current_frame: None,
+        };
+
+        w.write_header(header)?;
+
+        Ok(w)
+    }
+
+    fn write_header(&mut self, header: &IVFFileHeader) -> Result<()> {
+        self.writer.write_all(&header.signature)?; // DKIF
+        self.writer.write_all(&header.version.to_le_bytes())?; // version
+        self.writer.write_all(&header.header_size.to_le_bytes())?; // Header size
+        self.writer.write_all(&header.four_cc)?; // FOURCC
+        self.writer.write_all(&header.width.to_le_bytes())?; // Width in pixels
+        self.writer.write_all(&header.height.to_le_bytes())?; // Height in pixels
+        self.writer
+            .write_all(&header.timebase_denominator.to_le_bytes())?; // Framerate denominator
+        self.writer
+            .write_all(&header.timebase_numerator.to_le_bytes())?; // Framerate numerator
+        self.writer.write_all(&header.num_frames.to_le_bytes())?; // Frame count, will be updated on close
+        self.writer.write_all(&header.unused.to_le_bytes())?; // Unused
+
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> Writer for IVFWriter<W> {
+    /// write_rtp adds a new packet and writes the appropriate headers for it
+    fn write_rtp(&mut self, packet: &rtp::packet::Packet) -> Result<()> {
+        if packet.payload.is_empty() {
+            return Ok(());
+        }
+
+        let is_partition_head = self.depacketizer.is_partition_head(&packet.payload);
+        if !self.seen_key_frame {
+            // the recording starts with the first key frame, so the file can be decoded
+            if !is_partition_head || !self.depacketizer.is_keyframe(&packet.payload) {
+                return Ok(());
+            }
+            self.seen_key_frame = true;
+        }
+
+        // the head of this frame has been lost, wait for the next one
+        if self.current_frame.is_none() && !is_partition_head {
+            return Ok(());
+        }
+
+        let payload = self.depacketizer.depacketize(&packet.payload)?;
+        self.current_frame
+            .get_or_insert_with(BytesMut::new)
+            .extend_from_slice(&payload);
+
+        if !packet.header.marker {
+            return Ok(());
+        }
+
+        let frame = match self.current_frame.take() {
+            Some(frame) if !frame.is_empty() => frame,
+            _ => return Ok(()),
+        };
+
+        self.writer.write_all(&(frame.len() as u32).to_le_bytes())?; // Frame length
+        self.writer.write_all(&self.count.to_le_bytes())?; // PTS
+        self.writer.write_all(&frame)?;
+        self.count += 1;
+
+        Ok(())
+    }
+
+    /// close stops the recording
+    fn close(&mut self) -> Result<()> {
+        // Update the frame count
+        self.writer.seek(SeekFrom::Start(24))?;
+        self.writer.write_all(&(self.count as u32).to_le_bytes())?;
+        self.writer.seek(SeekFrom::End(0))?;
+
+        self.writer.flush()?;
+        Ok(())
+    }
+}
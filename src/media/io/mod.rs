@@ -0,0 +1,21 @@
+#[cfg(test)]
+mod io_test;
+
+pub mod h264_reader;
+pub mod h264_writer;
+pub mod ivf_reader;
+pub mod ivf_writer;
+pub mod ogg_reader;
+pub mod ogg_writer;
+
+use anyhow::Result;
+
+/// Writer defines an interface to handle the creation of media files
+/// from the RTP packets of a TrackRemote
+pub trait Writer {
+    /// write_rtp adds the content of an RTP packet to the media
+    fn write_rtp(&mut self, pkt: &rtp::packet::Packet) -> Result<()>;
+
+    /// close the media
+    fn close(&mut self) -> Result<()>;
+}
@@ -0,0 +1,177 @@
+use crate::error::Error;
+
+use anyhow::Result;
+use bytes::{Bytes, BytesMut};
+use std::io::Read;
+
+pub const PAGE_HEADER_TYPE_CONTINUATION_OF_STREAM: u8 = 0x00;
+pub const PAGE_HEADER_TYPE_BEGINNING_OF_STREAM: u8 = 0x02;
+pub const PAGE_HEADER_TYPE_END_OF_STREAM: u8 = 0x04;
+pub const DEFAULT_PRE_SKIP: u16 = 3840; // 3840 recommended in the RFC
+pub const PAGE_HEADER_SIGNATURE: &[u8] = b"OggS";
+pub const ID_PAGE_SIGNATURE: &[u8] = b"OpusHead";
+pub const COMMENT_PAGE_SIGNATURE: &[u8] = b"OpusTags";
+pub const PAGE_HEADER_SIZE: usize = 27;
+pub const ID_PAGE_PAYLOAD_SIZE: usize = 19;
+
+/// OggReader is used to read Ogg files and return page payloads
+pub struct OggReader<R: Read> {
+    reader: R,
+    bytes_read: usize,
+    checksum_table: [u32; 256],
+    do_checksum: bool,
+}
+
+/// OggHeader is the metadata from the first two pages
+/// in the file (ID and Comment)
+/// https://tools.ietf.org/html/rfc7845.html#section-3
+#[derive(Default, Debug, Copy, Clone, PartialEq)]
+pub struct OggHeader {
+    pub channel_map: u8,
+    pub channels: u8,
+    pub output_gain: u16,
+    pub pre_skip: u16,
+    pub sample_rate: u32,
+    pub version: u8,
+}
+
+/// OggPageHeader is the metadata for a Page
+/// Pages are the fundamental unit of multiplexing in an Ogg stream
+/// https://tools.ietf.org/html/rfc7845.html#section-1
+#[derive(Default, Debug, Copy, Clone, PartialEq)]
+pub struct OggPageHeader {
+    pub granule_position: u64,
+
+    pub sig: [u8; 4],
+    pub version: u8,
+    pub header_type: u8,
+    pub serial: u32,
+    pub index: u32,
+    pub segments_count: u8,
+}
+
+impl<R: Read> OggReader<R> {
+    /// new returns a new Ogg reader and Ogg header
+    /// with an io.Reader input
+    pub fn new(reader: R, do_checksum: bool) -> Result<(OggReader<R>, OggHeader)> {
+        let mut r = OggReader {
+            reader,
+            bytes_read: 0,
+            checksum_table: generate_checksum_table(),
+            do_checksum,
+        };
+
+        let header = r.read_headers()?;
+
+        Ok((r, header))
+    }
+
+    /// bytes_read returns how many bytes of the stream have been parsed so far
+    pub fn bytes_read(&self) -> usize {
+        self.bytes_read
+    }
+
+    fn read_headers(&mut self) -> Result<OggHeader> {
+        let (payload, page_header) = self.parse_next_page()?;
+
+        if page_header.sig != PAGE_HEADER_SIGNATURE {
+            return Err(Error::ErrBadIDPageSignature.into());
+        }
+
+        if page_header.header_type != PAGE_HEADER_TYPE_BEGINNING_OF_STREAM {
+            return Err(Error::ErrBadIDPageType.into());
+        }
+
+        if payload.len() != ID_PAGE_PAYLOAD_SIZE {
+            return Err(Error::ErrBadIDPageLength.into());
+        }
+
+        if &payload[..8] != ID_PAGE_SIGNATURE {
+            return Err(Error::ErrBadIDPagePayloadSignature.into());
+        }
+
+        Ok(OggHeader {
+            version: payload[8],
+            channels: payload[9],
+            pre_skip: u16::from_le_bytes([payload[10], payload[11]]),
+            sample_rate: u32::from_le_bytes([payload[12], payload[13], payload[14], payload[15]]),
+            output_gain: u16::from_le_bytes([payload[16], payload[17]]),
+            channel_map: payload[18],
+        })
+    }
+
+    /// parse_next_page reads from stream and returns Ogg page payload and header.
+    /// An io error is returned once no more pages are available, or if
+    /// the page data is incomplete.
+    pub fn parse_next_page(&mut self) -> Result<(Bytes, OggPageHeader)> {
+        let mut h = [0u8; PAGE_HEADER_SIZE];
+        self.reader.read_exact(&mut h)?;
+
+        let page_header = OggPageHeader {
+            sig: [h[0], h[1], h[2], h[3]],
+            version: h[4],
+            header_type: h[5],
+            granule_position: u64::from_le_bytes([
+                h[6], h[7], h[8], h[9], h[10], h[11], h[12], h[13],
+            ]),
+            serial: u32::from_le_bytes([h[14], h[15], h[16], h[17]]),
+            index: u32::from_le_bytes([h[18], h[19], h[20], h[21]]),
+            segments_count: h[26],
+        };
+        let checksum = u32::from_le_bytes([h[22], h[23], h[24], h[25]]);
+
+        let mut size_buffer = vec![0u8; page_header.segments_count as usize];
+        self.reader.read_exact(&mut size_buffer)?;
+
+        let payload_size: usize = size_buffer.iter().map(|s| *s as usize).sum();
+        let mut payload = BytesMut::new();
+        payload.resize(payload_size, 0);
+        self.reader.read_exact(&mut payload)?;
+
+        if self.do_checksum {
+            let mut sum = 0;
+
+            for (index, v) in h.iter().enumerate() {
+                // Don't include expected checksum in our generation
+                if (22..26).contains(&index) {
+                    sum = self.update_checksum(0, sum);
+                } else {
+                    sum = self.update_checksum(*v, sum);
+                }
+            }
+            for v in size_buffer.iter().chain(payload.iter()) {
+                sum = self.update_checksum(*v, sum);
+            }
+
+            if sum != checksum {
+                return Err(Error::ErrChecksumMismatch.into());
+            }
+        }
+
+        self.bytes_read += PAGE_HEADER_SIZE + size_buffer.len() + payload_size;
+
+        Ok((payload.freeze(), page_header))
+    }
+
+    fn update_checksum(&self, v: u8, sum: u32) -> u32 {
+        (sum << 8) ^ self.checksum_table[(((sum >> 24) as u8) ^ v) as usize]
+    }
+}
+
+pub(crate) fn generate_checksum_table() -> [u32; 256] {
+    const POLY: u32 = 0x04c11db7;
+
+    let mut table = [0u32; 256];
+    for (i, t) in table.iter_mut().enumerate() {
+        let mut r = (i as u32) << 24;
+        for _ in 0..8 {
+            if (r & 0x80000000) != 0 {
+                r = (r << 1) ^ POLY;
+            } else {
+                r <<= 1;
+            }
+        }
+        *t = r;
+    }
+    table
+}
@@ -0,0 +1,190 @@
+use super::ogg_reader::*;
+use super::Writer;
+use crate::media::rtp::codecs::opus::OpusDepacketizer;
+use crate::media::rtp::codecs::Depacketizer;
+
+use anyhow::Result;
+use bytes::{BufMut, Bytes, BytesMut};
+use std::io::{Seek, SeekFrom, Write};
+
+/// OggWriter is used to take RTP packets of an Opus track and write them to an OGG on disk
+pub struct OggWriter<W: Write + Seek> {
+    writer: W,
+    sample_rate: u32,
+    channel_count: u8,
+    serial: u32,
+    page_index: u32,
+    checksum_table: [u32; 256],
+    previous_granule_position: u64,
+    previous_timestamp: u32,
+    last_payload: Bytes,
+    last_granule_position: u64,
+    last_page_offset: u64,
+    depacketizer: OpusDepacketizer,
+}
+
+impl<W: Write + Seek> OggWriter<W> {
+    /// new initializes a new OGG Opus writer with an io.Writer output
+    pub fn new(writer: W, sample_rate: u32, channel_count: u8) -> Result<Self> {
+        let mut w = OggWriter {
+            writer,
+            sample_rate,
+            channel_count,
+            serial: rand::random::<u32>(),
+            page_index: 0,
+            checksum_table: generate_checksum_table(),
+
+            // Timestamp and Granule MUST start from 1
+            // Only headers can have 0 values
+            previous_timestamp: 1,
+            previous_granule_position: 1,
+            last_payload: Bytes::new(),
+            last_granule_position: 0,
+            last_page_offset: 0,
+            depacketizer: OpusDepacketizer,
+        };
+
+        w.write_headers()?;
+
+        Ok(w)
+    }
+
+    //    Page 0         Pages 1 ... n        Pages (n+1) ...
+    // +------------+ +---+ +---+ ... +---+ +-----------+ +---------+ +--
+    // |            | |   | |   |     |   | |           | |         | |
+    // |+----------+| |+-----------------+| |+-------------------+ +-----
+    // |||ID Header|| ||  Comment Header || ||Audio Data Packet 1| | ...
+    // |+----------+| |+-----------------+| |+-------------------+ +-----
+    // |            | |   | |   |     |   | |           | |         | |
+    // +------------+ +---+ +---+ ... +---+ +-----------+ +---------+ +--
+    // https://tools.ietf.org/html/rfc7845.html#section-3
+    fn write_headers(&mut self) -> Result<()> {
+        // ID Header
+        let mut ogg_id_header = BytesMut::with_capacity(ID_PAGE_PAYLOAD_SIZE);
+        ogg_id_header.put(ID_PAGE_SIGNATURE); // Magic Signature 'OpusHead'
+        ogg_id_header.put_u8(1); // Version
+        ogg_id_header.put_u8(self.channel_count); // Channel count
+        ogg_id_header.put_u16_le(DEFAULT_PRE_SKIP); // pre-skip
+        ogg_id_header.put_u32_le(self.sample_rate); // original sample rate, any valid sample e.g 48000
+        ogg_id_header.put_u16_le(0); // output gain
+        ogg_id_header.put_u8(0); // channel map 0 = one stream: mono or stereo
+
+        // The ID Header page should have a granule position of 0 and a Header Type set to 2 (StartOfStream)
+        // https://tools.ietf.org/html/rfc7845.html#page-6
+        self.write_page(
+            &ogg_id_header.freeze(),
+            PAGE_HEADER_TYPE_BEGINNING_OF_STREAM,
+            0,
+            self.page_index,
+        )?;
+        self.page_index += 1;
+
+        // Comment Header
+        let vendor = b"WebRTC.rs";
+        let mut ogg_comment_header = BytesMut::new();
+        ogg_comment_header.put(COMMENT_PAGE_SIGNATURE); // Magic Signature 'OpusTags'
+        ogg_comment_header.put_u32_le(vendor.len() as u32); // Vendor Length
+        ogg_comment_header.put(&vendor[..]); // Vendor name 'WebRTC.rs'
+        ogg_comment_header.put_u32_le(0); // User Comment List Length
+
+        // The page where the CommentHeader completes should have a granule position of 0
+        self.write_page(
+            &ogg_comment_header.freeze(),
+            PAGE_HEADER_TYPE_CONTINUATION_OF_STREAM,
+            0,
+            self.page_index,
+        )?;
+        self.page_index += 1;
+
+        Ok(())
+    }
+
+    fn write_page(
+        &mut self,
+        payload: &Bytes,
+        header_type: u8,
+        granule_pos: u64,
+        page_index: u32,
+    ) -> Result<()> {
+        self.last_payload = payload.clone();
+        self.last_granule_position = granule_pos;
+        self.last_page_offset = self.writer.stream_position()?;
+
+        // the payload is laced into segments of 255 bytes, ended by a shorter segment
+        let segments_count = payload.len() / 255 + 1;
+
+        let mut page = BytesMut::with_capacity(PAGE_HEADER_SIZE + segments_count + payload.len());
+        page.put(PAGE_HEADER_SIGNATURE); // page headers starts with 'OggS'
+        page.put_u8(0); // Version
+        page.put_u8(header_type); // 1 = continuation, 2 = beginning of stream, 4 = end of stream
+        page.put_u64_le(granule_pos); // granule position
+        page.put_u32_le(self.serial); // Bitstream serial number
+        page.put_u32_le(page_index); // Page sequence number
+        page.put_u32_le(0); // Checksum reserve
+        page.put_u8(segments_count as u8); // Number of segments in page
+        for _ in 0..segments_count - 1 {
+            page.put_u8(255);
+        }
+        page.put_u8((payload.len() % 255) as u8);
+        page.put(&payload[..]);
+
+        let mut checksum = 0u32;
+        for v in page.iter() {
+            checksum =
+                (checksum << 8) ^ self.checksum_table[(((checksum >> 24) as u8) ^ (*v)) as usize];
+        }
+        page[22..26].copy_from_slice(&checksum.to_le_bytes());
+
+        self.writer.write_all(&page)?;
+
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> Writer for OggWriter<W> {
+    /// write_rtp adds a new packet and writes the appropriate headers for it
+    fn write_rtp(&mut self, packet: &rtp::packet::Packet) -> Result<()> {
+        if packet.payload.is_empty() {
+            return Ok(());
+        }
+
+        let payload = self.depacketizer.depacketize(&packet.payload)?;
+
+        // Should be equivalent to sample_rate * duration
+        if self.previous_timestamp != 1 {
+            let increment = packet
+                .header
+                .timestamp
+                .wrapping_sub(self.previous_timestamp);
+            self.previous_granule_position += increment as u64;
+        }
+        self.previous_timestamp = packet.header.timestamp;
+
+        self.write_page(
+            &payload,
+            PAGE_HEADER_TYPE_CONTINUATION_OF_STREAM,
+            self.previous_granule_position,
+            self.page_index,
+        )?;
+        self.page_index += 1;
+
+        Ok(())
+    }
+
+    /// close stops the recording, rewriting the last page to mark the end of the stream
+    fn close(&mut self) -> Result<()> {
+        // The rewritten page has the same size, it only differs in its header type
+        // and checksum
+        let payload = self.last_payload.clone();
+        self.writer.seek(SeekFrom::Start(self.last_page_offset))?;
+        self.write_page(
+            &payload,
+            PAGE_HEADER_TYPE_END_OF_STREAM,
+            self.last_granule_position,
+            self.page_index - 1,
+        )?;
+
+        self.writer.flush()?;
+        Ok(())
+    }
+}
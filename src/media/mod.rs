@@ -1,4 +1,31 @@
 pub mod dtls_transport;
 pub mod ice_transport;
+pub mod io;
 pub mod rtp;
+pub mod sample_builder;
 pub mod track;
+
+use bytes::Bytes;
+use std::time::{Duration, SystemTime};
+
+/// A Sample contains encoded media and timing information
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    pub data: Bytes,
+    pub timestamp: SystemTime,
+    pub duration: Duration,
+    pub packet_timestamp: u32,
+    pub prev_dropped_packets: u16,
+}
+
+impl Default for Sample {
+    fn default() -> Self {
+        Sample {
+            data: Bytes::new(),
+            timestamp: SystemTime::now(),
+            duration: Duration::from_secs(0),
+            packet_timestamp: 0,
+            prev_dropped_packets: 0,
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use super::{leb128_size, read_leb128, write_leb128, Depacketizer};
+use crate::error::Error;
+
+use anyhow::Result;
+use bytes::{BufMut, Bytes, BytesMut};
+use rtp::packetizer::Payloader;
+
+const AV1_AGGREGATION_HEADER_SIZE: usize = 1;
+
+const AV1_Z_BITMASK: u8 = 0x80;
+const AV1_Y_BITMASK: u8 = 0x40;
+const AV1_W_BITMASK: u8 = 0x30;
+const AV1_W_BITSHIFT: u8 = 4;
+const AV1_N_BITMASK: u8 = 0x08;
+
+const OBU_TYPE_BITMASK: u8 = 0x78;
+const OBU_TYPE_BITSHIFT: u8 = 3;
+const OBU_EXTENSION_FLAG_BITMASK: u8 = 0x04;
+const OBU_HAS_SIZE_FIELD_BITMASK: u8 = 0x02;
+
+const OBU_TYPE_SEQUENCE_HEADER: u8 = 1;
+const OBU_TYPE_TEMPORAL_DELIMITER: u8 = 2;
+const OBU_TYPE_TILE_LIST: u8 = 8;
+
+/// obu_header_size returns the size of the OBU header, including the extension header
+fn obu_header_size(header: u8) -> usize {
+    if header & OBU_EXTENSION_FLAG_BITMASK != 0 {
+        2
+    } else {
+        1
+    }
+}
+
+/// Av1Payloader payloads AV1 temporal units, given as a sequence of OBUs in the
+/// low overhead bitstream format, as described in
+/// https://aomediacodec.github.io/av1-rtp-spec/#4-payload-format
+#[derive(Default, Debug, Copy, Clone)]
+pub struct Av1Payloader;
+
+impl Av1Payloader {
+    /// split_obus splits a temporal unit into OBUs without their size field,
+    /// dropping the OBUs that must not be transmitted
+    fn split_obus(payload: &Bytes) -> Result<(Vec<Bytes>, bool)> {
+        let mut obus = vec![];
+        let mut new_sequence = false;
+
+        let mut index = 0;
+        while index < payload.len() {
+            let header = payload[index];
+            let header_size = obu_header_size(header);
+            if index + header_size > payload.len() {
+                return Err(Error::ErrShortPacket.into());
+            }
+
+            let (obu_size, size_field_size) = if header & OBU_HAS_SIZE_FIELD_BITMASK != 0 {
+                read_leb128(&payload[index + header_size..])?
+            } else {
+                (payload.len() - index - header_size, 0)
+            };
+
+            let start = index + header_size + size_field_size;
+            let end = start + obu_size;
+            if end > payload.len() {
+                return Err(Error::ErrShortPacket.into());
+            }
+
+            let obu_type = (header & OBU_TYPE_BITMASK) >> OBU_TYPE_BITSHIFT;
+            if obu_type == OBU_TYPE_SEQUENCE_HEADER {
+                new_sequence = true;
+            }
+
+            // Temporal delimiters and tile lists should be removed when transmitted
+            // https://aomediacodec.github.io/av1-rtp-spec/#5-packetization-rules
+            if obu_type != OBU_TYPE_TEMPORAL_DELIMITER && obu_type != OBU_TYPE_TILE_LIST {
+                let mut obu = BytesMut::with_capacity(header_size + obu_size);
+                obu.put_u8(header & !OBU_HAS_SIZE_FIELD_BITMASK);
+                obu.put(&payload[index + 1..index + header_size]);
+                obu.put(&payload[start..end]);
+                obus.push(obu.freeze());
+            }
+
+            index = end;
+        }
+
+        Ok((obus, new_sequence))
+    }
+
+    fn aggregation_header(z: bool, y: bool, n: bool) -> u8 {
+        let mut header = 0;
+        if z {
+            header |= AV1_Z_BITMASK;
+        }
+        if y {
+            header |= AV1_Y_BITMASK;
+        }
+        if n {
+            header |= AV1_N_BITMASK;
+        }
+        header
+    }
+}
+
+impl Payloader for Av1Payloader {
+    /// payload fragments an AV1 temporal unit across one or more byte arrays.
+    /// Every OBU element carries its length, so W is always 0
+    fn payload(&self, mtu: usize, payload: &Bytes) -> Result<Vec<Bytes>> {
+        if payload.is_empty() || mtu <= AV1_AGGREGATION_HEADER_SIZE + 1 {
+            return Ok(vec![]);
+        }
+
+        let (obus, new_sequence) = Av1Payloader::split_obus(payload)?;
+        let max_elements_size = mtu - AV1_AGGREGATION_HEADER_SIZE;
+
+        let mut payloads = vec![];
+        let mut elements = BytesMut::new();
+        let mut continued = false;
+        let mut flush = |elements: &mut BytesMut, z: bool, y: bool| {
+            let n = new_sequence && payloads.is_empty();
+            let mut out = BytesMut::with_capacity(AV1_AGGREGATION_HEADER_SIZE + elements.len());
+            out.put_u8(Av1Payloader::aggregation_header(z, y, n));
+            out.put(&elements[..]);
+            payloads.push(out.freeze());
+            elements.clear();
+        };
+
+        for obu in &obus {
+            let mut offset = 0;
+            while offset < obu.len() {
+                let space = max_elements_size - elements.len();
+                // there must be room for the length field and at least one byte
+                if space <= leb128_size(space) {
+                    flush(&mut elements, continued, false);
+                    continued = false;
+                    continue;
+                }
+
+                let size = std::cmp::min(obu.len() - offset, space - leb128_size(space));
+                write_leb128(&mut elements, size);
+                elements.put(&obu[offset..offset + size]);
+                offset += size;
+
+                // the OBU is fragmented, its remaining continues in the next packet
+                if offset < obu.len() {
+                    flush(&mut elements, continued, true);
+                    continued = true;
+                }
+            }
+        }
+
+        if !elements.is_empty() {
+            flush(&mut elements, continued, false);
+        }
+
+        Ok(payloads)
+    }
+
+    fn clone_to(&self) -> Box<dyn Payloader + Send + Sync> {
+        Box::new(*self)
+    }
+}
+
+/// Av1Depacketizer depacketizes AV1 payloads, and outputs the OBUs in the low
+/// overhead bitstream format, every OBU carrying its size field
+#[derive(Default, Debug, Clone)]
+pub struct Av1Depacketizer {
+    fragment: BytesMut,
+}
+
+impl Av1Depacketizer {
+    /// push_obu appends the OBU with its size field to out
+    fn push_obu(out: &mut BytesMut, obu: &[u8]) -> Result<()> {
+        if obu.is_empty() {
+            return Ok(());
+        }
+
+        let header = obu[0];
+        let header_size = obu_header_size(header);
+        if obu.len() < header_size {
+            return Err(Error::ErrShortPacket.into());
+        }
+
+        if header & OBU_HAS_SIZE_FIELD_BITMASK != 0 {
+            out.put(obu);
+        } else {
+            out.put_u8(header | OBU_HAS_SIZE_FIELD_BITMASK);
+            out.put(&obu[1..header_size]);
+            write_leb128(out, obu.len() - header_size);
+            out.put(&obu[header_size..]);
+        }
+
+        Ok(())
+    }
+}
+
+impl Depacketizer for Av1Depacketizer {
+    fn depacketize(&mut self, payload: &Bytes) -> Result<Bytes> {
+        if payload.len() <= AV1_AGGREGATION_HEADER_SIZE {
+            return Err(Error::ErrShortPacket.into());
+        }
+
+        //  0 1 2 3 4 5 6 7
+        // +-+-+-+-+-+-+-+-+
+        // |Z|Y| W |N|-|-|-|
+        // +-+-+-+-+-+-+-+-+
+        let z = payload[0] & AV1_Z_BITMASK != 0;
+        let y = payload[0] & AV1_Y_BITMASK != 0;
+        let w = ((payload[0] & AV1_W_BITMASK) >> AV1_W_BITSHIFT) as usize;
+
+        // the first element isn't a continuation, anything left over was lost
+        if !z {
+            self.fragment.clear();
+        }
+
+        let mut out = BytesMut::new();
+        let mut index = AV1_AGGREGATION_HEADER_SIZE;
+        let mut element = 0;
+        while index < payload.len() {
+            element += 1;
+
+            // when W is set, the last element doesn't carry its length
+            let size = if w != 0 && element == w {
+                payload.len() - index
+            } else {
+                let (size, size_field_size) = read_leb128(&payload[index..])?;
+                index += size_field_size;
+                size
+            };
+            if index + size > payload.len() {
+                return Err(Error::ErrShortPacket.into());
+            }
+
+            let data = &payload[index..index + size];
+            index += size;
+            let is_first = element == 1;
+            let is_last = index >= payload.len();
+
+            if is_first && z {
+                // the beginning of this OBU has been lost, drop the rest of it
+                if self.fragment.is_empty() {
+                    continue;
+                }
+                self.fragment.put(data);
+                if !(is_last && y) {
+                    let obu = self.fragment.split();
+                    Av1Depacketizer::push_obu(&mut out, &obu)?;
+                }
+            } else if is_last && y {
+                self.fragment.put(data);
+            } else {
+                Av1Depacketizer::push_obu(&mut out, data)?;
+            }
+        }
+
+        Ok(out.freeze())
+    }
+
+    /// A frame starts with a packet whose first OBU isn't a continuation
+    fn is_partition_head(&self, payload: &Bytes) -> bool {
+        !payload.is_empty() && payload[0] & AV1_Z_BITMASK == 0
+    }
+
+    fn is_partition_tail(&self, marker: bool, _payload: &Bytes) -> bool {
+        marker
+    }
+
+    /// The N bit is set on the first packet of a coded video sequence
+    fn is_keyframe(&self, payload: &Bytes) -> bool {
+        !payload.is_empty() && payload[0] & AV1_N_BITMASK != 0
+    }
+
+    fn clone_to(&self) -> Box<dyn Depacketizer + Send + Sync> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,259 @@
+use super::av1::*;
+use super::h264::*;
+use super::vp8::*;
+use super::vp9::*;
+use super::*;
+
+#[test]
+fn test_codecs_lookup_by_mime_type() -> Result<()> {
+    assert!(payloader_for_mime_type("video/vp8").is_ok());
+    assert!(payloader_for_mime_type(MIME_TYPE_AV1).is_ok());
+    assert!(depacketizer_for_mime_type("AUDIO/OPUS").is_ok());
+    assert!(depacketizer_for_mime_type(MIME_TYPE_PCMU).is_ok());
+
+    if let Err(err) = payloader_for_mime_type("video/unknown") {
+        assert!(Error::ErrNoPayloaderForCodec.equal(&err));
+    } else {
+        panic!("expected error, but got ok");
+    }
+
+    if let Err(err) = depacketizer_for_mime_type("video/unknown") {
+        assert!(Error::ErrNoDepacketizerForCodec.equal(&err));
+    } else {
+        panic!("expected error, but got ok");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_leb128() -> Result<()> {
+    for value in &[0usize, 1, 127, 128, 300, 16383, 16384, 1 << 20] {
+        let mut b = BytesMut::new();
+        write_leb128(&mut b, *value);
+        assert_eq!(b.len(), leb128_size(*value));
+        assert_eq!(read_leb128(&b)?, (*value, b.len()));
+    }
+
+    if let Err(err) = read_leb128(&[0x80, 0x80]) {
+        assert!(Error::ErrInvalidLEB128.equal(&err));
+    } else {
+        panic!("expected error, but got ok");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_vp8_depacketizer() -> Result<()> {
+    let mut d = Vp8Depacketizer;
+
+    // X, S, I with a 15 bits picture id, then a key frame payload header
+    let keyframe = Bytes::from_static(&[0x90, 0x80, 0x81, 0x02, 0x10, 0xAA]);
+    assert!(d.is_partition_head(&keyframe));
+    assert!(d.is_keyframe(&keyframe));
+    assert_eq!(d.depacketize(&keyframe)?, Bytes::from_static(&[0x10, 0xAA]));
+
+    // S without any extension, inter frame
+    let interframe = Bytes::from_static(&[0x10, 0x01, 0xAA]);
+    assert!(d.is_partition_head(&interframe));
+    assert!(!d.is_keyframe(&interframe));
+
+    // continuation of partition 0
+    let continuation = Bytes::from_static(&[0x00, 0xAA]);
+    assert!(!d.is_partition_head(&continuation));
+    assert!(!d.is_keyframe(&continuation));
+    assert!(d.is_partition_tail(true, &continuation));
+
+    if let Err(err) = d.depacketize(&Bytes::from_static(&[0x90, 0x80])) {
+        assert!(Error::ErrShortPacket.equal(&err));
+    } else {
+        panic!("expected error, but got ok");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_vp8_payloader() -> Result<()> {
+    let p = Vp8Payloader;
+    let frame = Bytes::from(vec![0xAAu8; 10]);
+
+    let payloads = p.payload(6, &frame)?;
+    assert_eq!(payloads.len(), 2);
+    // S is only set on the first payload, every payload has a descriptor
+    assert_eq!(payloads[0][0], 0x10);
+    assert_eq!(payloads[0].len(), 6);
+    assert_eq!(payloads[1][0], 0x00);
+    assert_eq!(payloads[1].len(), 6);
+
+    let mut d = Vp8Depacketizer;
+    assert!(d.is_partition_head(&payloads[0]));
+    assert!(!d.is_partition_head(&payloads[1]));
+    let mut out = BytesMut::new();
+    for payload in &payloads {
+        out.extend_from_slice(&d.depacketize(payload)?);
+    }
+    assert_eq!(out.freeze(), frame);
+
+    assert!(p.payload(1, &frame)?.is_empty());
+    assert!(p.payload(100, &Bytes::new())?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_vp9_depacketizer() -> Result<()> {
+    let mut d = Vp9Depacketizer;
+
+    // I with a 7 bits picture id, B, not inter-picture predicted
+    let keyframe = Bytes::from_static(&[0x88, 0x01, 0xAA, 0xBB]);
+    assert!(d.is_partition_head(&keyframe));
+    assert!(d.is_keyframe(&keyframe));
+    assert_eq!(d.depacketize(&keyframe)?, Bytes::from_static(&[0xAA, 0xBB]));
+
+    // P is set
+    let interframe = Bytes::from_static(&[0xC8, 0x01, 0xAA]);
+    assert!(d.is_partition_head(&interframe));
+    assert!(!d.is_keyframe(&interframe));
+
+    // L is set, spatial layer 1 of a key picture
+    let upper_layer = Bytes::from_static(&[0xA8, 0x01, 0x02, 0x00, 0xAA]);
+    assert!(!d.is_keyframe(&upper_layer));
+
+    let continuation = Bytes::from_static(&[0x80, 0x01, 0xAA]);
+    assert!(!d.is_partition_head(&continuation));
+
+    Ok(())
+}
+
+#[test]
+fn test_vp9_payloader() -> Result<()> {
+    let p = Vp9Payloader::new(0x7FFF);
+    let frame = Bytes::from(vec![0xAAu8; 10]);
+
+    let payloads = p.payload(8, &frame)?;
+    assert_eq!(payloads.len(), 2);
+    // I, F, B with the 15 bits picture id
+    assert_eq!(&payloads[0][..3], &[0x98, 0xFF, 0xFF]);
+    assert_eq!(payloads[0].len(), 8);
+    // I, F, E
+    assert_eq!(&payloads[1][..3], &[0x94, 0xFF, 0xFF]);
+
+    let mut d = Vp9Depacketizer;
+    assert!(d.is_partition_head(&payloads[0]));
+    assert!(!d.is_partition_head(&payloads[1]));
+    let mut out = BytesMut::new();
+    for payload in &payloads {
+        out.extend_from_slice(&d.depacketize(payload)?);
+    }
+    assert_eq!(out.freeze(), frame);
+
+    // the picture id of the next frame wraps around, a frame that fits in a
+    // single payload is both its beginning and its end
+    let payloads = p.payload(100, &frame)?;
+    assert_eq!(payloads.len(), 1);
+    assert_eq!(&payloads[0][..3], &[0x9C, 0x80, 0x00]);
+
+    assert!(p.payload(3, &frame)?.is_empty());
+    assert!(p.payload(100, &Bytes::new())?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_h264_depacketizer() -> Result<()> {
+    let mut d = H264Depacketizer::default();
+
+    // single NAL unit
+    let single = Bytes::from_static(&[0x65, 0xAA, 0xBB]);
+    assert!(d.is_partition_head(&single));
+    assert!(d.is_keyframe(&single));
+    assert_eq!(
+        d.depacketize(&single)?,
+        Bytes::from_static(&[0x00, 0x00, 0x00, 0x01, 0x65, 0xAA, 0xBB])
+    );
+
+    // STAP-A carrying SPS and PPS
+    let stapa = Bytes::from_static(&[0x78, 0x00, 0x02, 0x67, 0x01, 0x00, 0x02, 0x68, 0x02]);
+    assert!(d.is_keyframe(&stapa));
+    assert_eq!(
+        d.depacketize(&stapa)?,
+        Bytes::from_static(&[
+            0x00, 0x00, 0x00, 0x01, 0x67, 0x01, 0x00, 0x00, 0x00, 0x01, 0x68, 0x02
+        ])
+    );
+
+    // FU-A fragmented IDR slice
+    let fua_start = Bytes::from_static(&[0x7C, 0x85, 0x01, 0x02]);
+    let fua_middle = Bytes::from_static(&[0x7C, 0x05, 0x03]);
+    let fua_end = Bytes::from_static(&[0x7C, 0x45, 0x04]);
+    assert!(d.is_partition_head(&fua_start));
+    assert!(d.is_keyframe(&fua_start));
+    assert!(!d.is_partition_head(&fua_middle));
+    assert!(!d.is_keyframe(&fua_middle));
+
+    assert!(d.depacketize(&fua_start)?.is_empty());
+    assert!(d.depacketize(&fua_middle)?.is_empty());
+    assert_eq!(
+        d.depacketize(&fua_end)?,
+        Bytes::from_static(&[0x00, 0x00, 0x00, 0x01, 0x65, 0x01, 0x02, 0x03, 0x04])
+    );
+
+    // fragments whose start has been lost are dropped
+    assert!(d.depacketize(&fua_middle)?.is_empty());
+    assert!(d.depacketize(&fua_end)?.is_empty());
+
+    // non-IDR slice
+    assert!(!d.is_keyframe(&Bytes::from_static(&[0x41, 0xAA])));
+
+    if let Err(err) = d.depacketize(&Bytes::from_static(&[0x1E, 0x00])) {
+        assert!(Error::ErrUnhandledNALUType.equal(&err));
+    } else {
+        panic!("expected error, but got ok");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_av1_payloader_depacketizer() -> Result<()> {
+    // temporal delimiter, sequence header and a frame OBU, all with size fields
+    let mut temporal_unit = BytesMut::new();
+    temporal_unit.extend_from_slice(&[0x12, 0x00]);
+    temporal_unit.extend_from_slice(&[0x0A, 0x03, 0x01, 0x02, 0x03]);
+    temporal_unit.extend_from_slice(&[0x32, 0x80, 0x02]);
+    temporal_unit.extend_from_slice(&[0xAB; 256]);
+    let temporal_unit = temporal_unit.freeze();
+
+    let p = Av1Payloader;
+    let payloads = p.payload(100, &temporal_unit)?;
+    assert!(payloads.len() > 1);
+    for payload in &payloads {
+        assert!(payload.len() <= 100);
+    }
+
+    let mut d = Av1Depacketizer::default();
+    assert!(d.is_partition_head(&payloads[0]));
+    assert!(d.is_keyframe(&payloads[0]));
+    assert!(!d.is_partition_head(&payloads[1]));
+    assert!(!d.is_keyframe(&payloads[1]));
+
+    let mut out = BytesMut::new();
+    for payload in &payloads {
+        out.extend_from_slice(&d.depacketize(payload)?);
+    }
+
+    // the temporal delimiter is not transmitted
+    assert_eq!(out.freeze(), temporal_unit.slice(2..));
+
+    // the first packet of a fragmented OBU got lost
+    let mut d = Av1Depacketizer::default();
+    let mut out = BytesMut::new();
+    for payload in &payloads[1..] {
+        out.extend_from_slice(&d.depacketize(payload)?);
+    }
+    assert!(out.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use super::Depacketizer;
+use crate::error::Error;
+
+use anyhow::Result;
+use bytes::Bytes;
+
+/// G7xxDepacketizer depacketizes G722, PCMU and PCMA payloads. The payloads
+/// carry raw samples, so they are passed through as is
+#[derive(Default, Debug, Copy, Clone)]
+pub struct G7xxDepacketizer;
+
+impl Depacketizer for G7xxDepacketizer {
+    fn depacketize(&mut self, payload: &Bytes) -> Result<Bytes> {
+        if payload.is_empty() {
+            Err(Error::ErrShortPacket.into())
+        } else {
+            Ok(payload.clone())
+        }
+    }
+
+    fn is_partition_head(&self, _payload: &Bytes) -> bool {
+        true
+    }
+
+    fn is_partition_tail(&self, _marker: bool, _payload: &Bytes) -> bool {
+        true
+    }
+
+    fn is_keyframe(&self, _payload: &Bytes) -> bool {
+        true
+    }
+
+    fn clone_to(&self) -> Box<dyn Depacketizer + Send + Sync> {
+        Box::new(*self)
+    }
+}
@@ -0,0 +1,154 @@
+use super::Depacketizer;
+use crate::error::Error;
+
+use anyhow::Result;
+use bytes::{BufMut, Bytes, BytesMut};
+
+const STAPA_NALU_TYPE: u8 = 24;
+const FUA_NALU_TYPE: u8 = 28;
+const FUA_HEADER_SIZE: usize = 2;
+const STAPA_NALU_LENGTH_SIZE: usize = 2;
+
+const IDR_NALU_TYPE: u8 = 5;
+const SPS_NALU_TYPE: u8 = 7;
+
+const NALU_TYPE_BITMASK: u8 = 0x1F;
+const NALU_REF_IDC_BITMASK: u8 = 0x60;
+const FU_START_BITMASK: u8 = 0x80;
+const FU_END_BITMASK: u8 = 0x40;
+
+const ANNEXB_NALU_START_CODE: &[u8] = &[0x00, 0x00, 0x00, 0x01];
+
+/// H264Depacketizer depacketizes H264 payloads as described in
+/// https://tools.ietf.org/html/rfc6184, and outputs NAL units in Annex B format
+#[derive(Default, Debug, Clone)]
+pub struct H264Depacketizer {
+    fua_buffer: Option<BytesMut>,
+}
+
+impl H264Depacketizer {
+    fn is_keyframe_nalu(nalu_type: u8) -> bool {
+        nalu_type == IDR_NALU_TYPE || nalu_type == SPS_NALU_TYPE
+    }
+}
+
+impl Depacketizer for H264Depacketizer {
+    fn depacketize(&mut self, payload: &Bytes) -> Result<Bytes> {
+        if payload.len() < 2 {
+            return Err(Error::ErrShortPacket.into());
+        }
+
+        // NALU Types
+        // https://tools.ietf.org/html/rfc6184#section-5.4
+        let nalu_type = payload[0] & NALU_TYPE_BITMASK;
+        match nalu_type {
+            1..=23 => {
+                let mut out = BytesMut::with_capacity(ANNEXB_NALU_START_CODE.len() + payload.len());
+                out.put(ANNEXB_NALU_START_CODE);
+                out.put(&payload[..]);
+                Ok(out.freeze())
+            }
+            STAPA_NALU_TYPE => {
+                let mut out = BytesMut::new();
+                let mut index = 1;
+                while index < payload.len() {
+                    if index + STAPA_NALU_LENGTH_SIZE > payload.len() {
+                        return Err(Error::ErrShortPacket.into());
+                    }
+                    let nalu_size = ((payload[index] as usize) << 8) | payload[index + 1] as usize;
+                    index += STAPA_NALU_LENGTH_SIZE;
+
+                    if index + nalu_size > payload.len() {
+                        return Err(Error::ErrShortPacket.into());
+                    }
+                    out.put(ANNEXB_NALU_START_CODE);
+                    out.put(&payload[index..index + nalu_size]);
+                    index += nalu_size;
+                }
+                Ok(out.freeze())
+            }
+            FUA_NALU_TYPE => {
+                if payload[1] & FU_START_BITMASK != 0 {
+                    self.fua_buffer = Some(BytesMut::new());
+                }
+
+                // the start of this NAL unit has been lost, drop the fragment
+                let fua_buffer = match &mut self.fua_buffer {
+                    Some(fua_buffer) => fua_buffer,
+                    None => return Ok(Bytes::new()),
+                };
+                fua_buffer.put(&payload[FUA_HEADER_SIZE..]);
+
+                if payload[1] & FU_END_BITMASK == 0 {
+                    return Ok(Bytes::new());
+                }
+
+                let nalu_ref_idc = payload[0] & NALU_REF_IDC_BITMASK;
+                let fragmented_nalu_type = payload[1] & NALU_TYPE_BITMASK;
+
+                let mut out =
+                    BytesMut::with_capacity(ANNEXB_NALU_START_CODE.len() + 1 + fua_buffer.len());
+                out.put(ANNEXB_NALU_START_CODE);
+                out.put_u8(nalu_ref_idc | fragmented_nalu_type);
+                out.put(&fua_buffer[..]);
+                self.fua_buffer = None;
+
+                Ok(out.freeze())
+            }
+            _ => Err(Error::ErrUnhandledNALUType.into()),
+        }
+    }
+
+    /// Every packet is the head of a NAL unit, except for FU-A fragments
+    /// after the first one
+    fn is_partition_head(&self, payload: &Bytes) -> bool {
+        if payload.len() < 2 {
+            return false;
+        }
+
+        if payload[0] & NALU_TYPE_BITMASK == FUA_NALU_TYPE {
+            payload[1] & FU_START_BITMASK != 0
+        } else {
+            true
+        }
+    }
+
+    fn is_partition_tail(&self, marker: bool, _payload: &Bytes) -> bool {
+        marker
+    }
+
+    /// A key frame starts with an IDR slice, or with the SPS that precedes it
+    fn is_keyframe(&self, payload: &Bytes) -> bool {
+        if payload.len() < 2 {
+            return false;
+        }
+
+        let nalu_type = payload[0] & NALU_TYPE_BITMASK;
+        match nalu_type {
+            STAPA_NALU_TYPE => {
+                let mut index = 1;
+                while index + STAPA_NALU_LENGTH_SIZE < payload.len() {
+                    let nalu_size = ((payload[index] as usize) << 8) | payload[index + 1] as usize;
+                    index += STAPA_NALU_LENGTH_SIZE;
+                    if nalu_size == 0 || index + nalu_size > payload.len() {
+                        return false;
+                    }
+                    if H264Depacketizer::is_keyframe_nalu(payload[index] & NALU_TYPE_BITMASK) {
+                        return true;
+                    }
+                    index += nalu_size;
+                }
+                false
+            }
+            FUA_NALU_TYPE => {
+                payload[1] & FU_START_BITMASK != 0
+                    && H264Depacketizer::is_keyframe_nalu(payload[1] & NALU_TYPE_BITMASK)
+            }
+            _ => H264Depacketizer::is_keyframe_nalu(nalu_type),
+        }
+    }
+
+    fn clone_to(&self) -> Box<dyn Depacketizer + Send + Sync> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,166 @@
+#[cfg(test)]
+mod codecs_test;
+
+pub mod av1;
+pub mod g7xx;
+pub mod h264;
+pub mod opus;
+pub mod vp8;
+pub mod vp9;
+
+use crate::api::media_engine::{
+    MIME_TYPE_AV1, MIME_TYPE_G722, MIME_TYPE_H264, MIME_TYPE_OPUS, MIME_TYPE_PCMA, MIME_TYPE_PCMU,
+    MIME_TYPE_VP8, MIME_TYPE_VP9,
+};
+use crate::error::Error;
+
+use anyhow::Result;
+use bytes::{BufMut, Bytes, BytesMut};
+use rtp::packetizer::Payloader;
+use std::fmt;
+
+/// Depacketizer turns the payloads of RTP packets back into media data, and
+/// tells where frames start, where they end and whether they can be decoded on their own
+pub trait Depacketizer: fmt::Debug {
+    /// depacketize removes any RTP specific data from the payload. An empty Bytes
+    /// is returned while the payload is only a fragment of a larger unit
+    fn depacketize(&mut self, payload: &Bytes) -> Result<Bytes>;
+
+    /// is_partition_head checks if the payload is at the beginning of a frame.
+    /// This returns false if the result could not be determined, in which case
+    /// the caller relies on timestamp discontinuities.
+    fn is_partition_head(&self, payload: &Bytes) -> bool;
+
+    /// is_partition_tail checks if the payload is at the end of a frame.
+    /// This returns false if the result could not be determined.
+    fn is_partition_tail(&self, marker: bool, payload: &Bytes) -> bool;
+
+    /// is_keyframe checks if the payload starts a frame that can be decoded
+    /// without any previous frame
+    fn is_keyframe(&self, payload: &Bytes) -> bool;
+
+    fn clone_to(&self) -> Box<dyn Depacketizer + Send + Sync>;
+}
+
+impl Clone for Box<dyn Depacketizer + Send + Sync> {
+    fn clone(&self) -> Box<dyn Depacketizer + Send + Sync> {
+        self.clone_to()
+    }
+}
+
+/// PayloaderFn creates the payloader of a codec
+pub type PayloaderFn = fn() -> Box<dyn Payloader + Send + Sync>;
+
+/// DepacketizerFn creates the depacketizer of a codec
+pub type DepacketizerFn = fn() -> Box<dyn Depacketizer + Send + Sync>;
+
+struct RegisteredCodec {
+    mime_type: &'static str,
+    payloader: PayloaderFn,
+    depacketizer: DepacketizerFn,
+}
+
+fn new_depacketizer<D>() -> Box<dyn Depacketizer + Send + Sync>
+where
+    D: Depacketizer + Default + Send + Sync + 'static,
+{
+    Box::new(D::default())
+}
+
+/// The codecs we know how to packetize and depacketize, keyed by MIME type
+static REGISTERED_CODECS: &[RegisteredCodec] = &[
+    RegisteredCodec {
+        mime_type: MIME_TYPE_H264,
+        payloader: || Box::new(rtp::codecs::h264::H264Payloader),
+        depacketizer: new_depacketizer::<h264::H264Depacketizer>,
+    },
+    RegisteredCodec {
+        mime_type: MIME_TYPE_VP8,
+        payloader: || Box::new(vp8::Vp8Payloader),
+        depacketizer: new_depacketizer::<vp8::Vp8Depacketizer>,
+    },
+    RegisteredCodec {
+        mime_type: MIME_TYPE_VP9,
+        payloader: || Box::new(vp9::Vp9Payloader::default()),
+        depacketizer: new_depacketizer::<vp9::Vp9Depacketizer>,
+    },
+    RegisteredCodec {
+        mime_type: MIME_TYPE_AV1,
+        payloader: || Box::new(av1::Av1Payloader),
+        depacketizer: new_depacketizer::<av1::Av1Depacketizer>,
+    },
+    RegisteredCodec {
+        mime_type: MIME_TYPE_OPUS,
+        payloader: || Box::new(rtp::codecs::opus::OpusPayloader),
+        depacketizer: new_depacketizer::<opus::OpusDepacketizer>,
+    },
+    RegisteredCodec {
+        mime_type: MIME_TYPE_G722,
+        payloader: || Box::new(rtp::codecs::g7xx::G7xxPayloader),
+        depacketizer: new_depacketizer::<g7xx::G7xxDepacketizer>,
+    },
+    RegisteredCodec {
+        mime_type: MIME_TYPE_PCMU,
+        payloader: || Box::new(rtp::codecs::g7xx::G7xxPayloader),
+        depacketizer: new_depacketizer::<g7xx::G7xxDepacketizer>,
+    },
+    RegisteredCodec {
+        mime_type: MIME_TYPE_PCMA,
+        payloader: || Box::new(rtp::codecs::g7xx::G7xxPayloader),
+        depacketizer: new_depacketizer::<g7xx::G7xxDepacketizer>,
+    },
+];
+
+fn find_codec(mime_type: &str) -> Option<&'static RegisteredCodec> {
+    REGISTERED_CODECS
+        .iter()
+        .find(|c| c.mime_type.eq_ignore_ascii_case(mime_type))
+}
+
+/// payloader_for_mime_type returns a payloader that splits samples of the codec into RTP payloads
+pub fn payloader_for_mime_type(mime_type: &str) -> Result<Box<dyn Payloader + Send + Sync>> {
+    match find_codec(mime_type) {
+        Some(codec) => Ok((codec.payloader)()),
+        None => Err(Error::ErrNoPayloaderForCodec.into()),
+    }
+}
+
+/// depacketizer_for_mime_type returns a depacketizer that turns RTP payloads of the codec back into samples
+pub fn depacketizer_for_mime_type(mime_type: &str) -> Result<Box<dyn Depacketizer + Send + Sync>> {
+    match find_codec(mime_type) {
+        Some(codec) => Ok((codec.depacketizer)()),
+        None => Err(Error::ErrNoDepacketizerForCodec.into()),
+    }
+}
+
+/// read_leb128 reads an unsigned leb128 value, returning the value and the number of bytes it used
+pub(crate) fn read_leb128(b: &[u8]) -> Result<(usize, usize)> {
+    let mut value = 0usize;
+    for (i, byte) in b.iter().enumerate().take(8) {
+        value |= ((byte & 0x7f) as usize) << (i * 7);
+        if byte & 0x80 == 0 {
+            return Ok((value, i + 1));
+        }
+    }
+
+    Err(Error::ErrInvalidLEB128.into())
+}
+
+/// leb128_size returns the number of bytes needed to encode value as leb128
+pub(crate) fn leb128_size(mut value: usize) -> usize {
+    let mut size = 1;
+    while value >= 0x80 {
+        value >>= 7;
+        size += 1;
+    }
+    size
+}
+
+/// write_leb128 appends value encoded as unsigned leb128
+pub(crate) fn write_leb128(out: &mut BytesMut, mut value: usize) {
+    while value >= 0x80 {
+        out.put_u8((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    out.put_u8(value as u8);
+}
@@ -0,0 +1,139 @@
+use super::Depacketizer;
+use crate::error::Error;
+
+use anyhow::Result;
+use bytes::{BufMut, Bytes, BytesMut};
+use rtp::packetizer::Payloader;
+
+/// VP8_HEADER_SIZE is the size of the payload descriptor of Vp8Payloader
+const VP8_HEADER_SIZE: usize = 1;
+
+/// Vp8Payloader payloads VP8 frames as described in
+/// https://tools.ietf.org/html/rfc7741#section-4.2, every payload starting
+/// with a payload descriptor
+#[derive(Default, Debug, Copy, Clone)]
+pub struct Vp8Payloader;
+
+impl Payloader for Vp8Payloader {
+    /// payload fragments a VP8 frame across one or more byte arrays
+    fn payload(&self, mtu: usize, payload: &Bytes) -> Result<Vec<Bytes>> {
+        //       0 1 2 3 4 5 6 7
+        //      +-+-+-+-+-+-+-+-+
+        //      |X|R|N|S|R| PID | (REQUIRED)
+        //      +-+-+-+-+-+-+-+-+
+        //  S: Start of VP8 partition. The S bit MUST be set to 1 for the
+        //     first packet of each encoded frame, and MUST NOT be 1 otherwise.
+        if payload.is_empty() || mtu <= VP8_HEADER_SIZE {
+            return Ok(vec![]);
+        }
+
+        let max_fragment_size = mtu - VP8_HEADER_SIZE;
+
+        let mut payloads = vec![];
+        let mut index = 0;
+        while index < payload.len() {
+            let fragment_size = std::cmp::min(max_fragment_size, payload.len() - index);
+
+            let mut out = BytesMut::with_capacity(VP8_HEADER_SIZE + fragment_size);
+            out.put_u8(if index == 0 { 0x10 } else { 0x00 });
+            out.put(&payload[index..index + fragment_size]);
+            payloads.push(out.freeze());
+
+            index += fragment_size;
+        }
+
+        Ok(payloads)
+    }
+
+    fn clone_to(&self) -> Box<dyn Payloader + Send + Sync> {
+        Box::new(*self)
+    }
+}
+
+/// Vp8Depacketizer depacketizes VP8 payloads as described in
+/// https://tools.ietf.org/html/rfc7741#section-4.2
+#[derive(Default, Debug, Copy, Clone)]
+pub struct Vp8Depacketizer;
+
+impl Vp8Depacketizer {
+    /// descriptor_size returns the size of the VP8 payload descriptor
+    fn descriptor_size(payload: &[u8]) -> Result<usize> {
+        if payload.is_empty() {
+            return Err(Error::ErrShortPacket.into());
+        }
+
+        //    0 1 2 3 4 5 6 7
+        //   +-+-+-+-+-+-+-+-+
+        //   |X|R|N|S|R| PID | (REQUIRED)
+        //   +-+-+-+-+-+-+-+-+
+        //X: |I|L|T|K| RSV   | (OPTIONAL)
+        //   +-+-+-+-+-+-+-+-+
+        //I: |M| PictureID   | (OPTIONAL)
+        //   +-+-+-+-+-+-+-+-+
+        //L: |   TL0PICIDX   | (OPTIONAL)
+        //   +-+-+-+-+-+-+-+-+
+        //T/K: |TID|Y| KEYIDX  | (OPTIONAL)
+        //   +-+-+-+-+-+-+-+-+
+        let mut size = 1;
+        if payload[0] & 0x80 != 0 {
+            if payload.len() <= size {
+                return Err(Error::ErrShortPacket.into());
+            }
+            let extension = payload[size];
+            size += 1;
+
+            if extension & 0x80 != 0 {
+                if payload.len() <= size {
+                    return Err(Error::ErrShortPacket.into());
+                }
+                // M bit signals a 15 bits PictureID
+                size += if payload[size] & 0x80 != 0 { 2 } else { 1 };
+            }
+            if extension & 0x40 != 0 {
+                size += 1;
+            }
+            if extension & 0x30 != 0 {
+                size += 1;
+            }
+        }
+
+        if payload.len() <= size {
+            return Err(Error::ErrShortPacket.into());
+        }
+
+        Ok(size)
+    }
+}
+
+impl Depacketizer for Vp8Depacketizer {
+    fn depacketize(&mut self, payload: &Bytes) -> Result<Bytes> {
+        let size = Vp8Depacketizer::descriptor_size(payload)?;
+        Ok(payload.slice(size..))
+    }
+
+    /// A frame starts with the first packet of partition 0
+    fn is_partition_head(&self, payload: &Bytes) -> bool {
+        !payload.is_empty() && payload[0] & 0x10 != 0 && payload[0] & 0x07 == 0
+    }
+
+    fn is_partition_tail(&self, marker: bool, _payload: &Bytes) -> bool {
+        marker
+    }
+
+    /// The P bit of the VP8 payload header is 0 for key frames
+    /// https://tools.ietf.org/html/rfc7741#section-4.3
+    fn is_keyframe(&self, payload: &Bytes) -> bool {
+        if !self.is_partition_head(payload) {
+            return false;
+        }
+
+        match Vp8Depacketizer::descriptor_size(payload) {
+            Ok(size) => payload[size] & 0x01 == 0,
+            Err(_) => false,
+        }
+    }
+
+    fn clone_to(&self) -> Box<dyn Depacketizer + Send + Sync> {
+        Box::new(*self)
+    }
+}
@@ -0,0 +1,229 @@
+use super::Depacketizer;
+use crate::error::Error;
+
+use anyhow::Result;
+use bytes::{BufMut, Bytes, BytesMut};
+use rtp::packetizer::Payloader;
+use std::sync::atomic::{AtomicU16, Ordering};
+
+/// VP9_HEADER_SIZE is the size of the payload descriptor of Vp9Payloader, in
+/// flexible mode with a 15 bits picture ID
+const VP9_HEADER_SIZE: usize = 3;
+
+const VP9_PICTURE_ID_MASK: u16 = 0x7FFF;
+
+/// Vp9Payloader payloads VP9 frames in flexible mode as described in
+/// https://tools.ietf.org/html/draft-ietf-payload-vp9-13#section-4.2,
+/// the picture ID of the frames starting at a random value
+#[derive(Debug)]
+pub struct Vp9Payloader {
+    picture_id: AtomicU16,
+}
+
+impl Default for Vp9Payloader {
+    fn default() -> Self {
+        Vp9Payloader::new(rand::random::<u16>())
+    }
+}
+
+impl Vp9Payloader {
+    /// new creates a Vp9Payloader whose first frame has the given picture ID
+    pub fn new(initial_picture_id: u16) -> Self {
+        Vp9Payloader {
+            picture_id: AtomicU16::new(initial_picture_id & VP9_PICTURE_ID_MASK),
+        }
+    }
+}
+
+impl Payloader for Vp9Payloader {
+    /// payload fragments a VP9 frame across one or more byte arrays
+    fn payload(&self, mtu: usize, payload: &Bytes) -> Result<Vec<Bytes>> {
+        //       0 1 2 3 4 5 6 7
+        //      +-+-+-+-+-+-+-+-+
+        //      |I|P|L|F|B|E|V|Z| (REQUIRED)
+        //      +-+-+-+-+-+-+-+-+
+        // I:   |M| PICTURE ID  | (REQUIRED)
+        //      +-+-+-+-+-+-+-+-+
+        // M:   | EXTENDED PID  | (RECOMMENDED)
+        //      +-+-+-+-+-+-+-+-+
+        if payload.is_empty() || mtu <= VP9_HEADER_SIZE {
+            return Ok(vec![]);
+        }
+
+        let picture_id = self.picture_id.load(Ordering::SeqCst);
+        let max_fragment_size = mtu - VP9_HEADER_SIZE;
+
+        let mut payloads = vec![];
+        let mut index = 0;
+        while index < payload.len() {
+            let fragment_size = std::cmp::min(max_fragment_size, payload.len() - index);
+
+            // I=1 F=1
+            let mut header = 0x90;
+            if index == 0 {
+                // B=1
+                header |= 0x08;
+            }
+            if index + fragment_size == payload.len() {
+                // E=1
+                header |= 0x04;
+            }
+
+            let mut out = BytesMut::with_capacity(VP9_HEADER_SIZE + fragment_size);
+            out.put_u8(header);
+            out.put_u16(picture_id | 0x8000);
+            out.put(&payload[index..index + fragment_size]);
+            payloads.push(out.freeze());
+
+            index += fragment_size;
+        }
+
+        self.picture_id.store(
+            picture_id.wrapping_add(1) & VP9_PICTURE_ID_MASK,
+            Ordering::SeqCst,
+        );
+
+        Ok(payloads)
+    }
+
+    fn clone_to(&self) -> Box<dyn Payloader + Send + Sync> {
+        Box::new(Vp9Payloader::new(self.picture_id.load(Ordering::SeqCst)))
+    }
+}
+
+/// Vp9Depacketizer depacketizes VP9 payloads as described in
+/// https://tools.ietf.org/html/draft-ietf-payload-vp9-13#section-4.2
+#[derive(Default, Debug, Copy, Clone)]
+pub struct Vp9Depacketizer;
+
+impl Vp9Depacketizer {
+    /// descriptor_size returns the size of the VP9 payload descriptor
+    fn descriptor_size(payload: &[u8]) -> Result<usize> {
+        if payload.is_empty() {
+            return Err(Error::ErrShortPacket.into());
+        }
+
+        //       0 1 2 3 4 5 6 7
+        //      +-+-+-+-+-+-+-+-+
+        //      |I|P|L|F|B|E|V|Z| (REQUIRED)
+        //      +-+-+-+-+-+-+-+-+
+        // I:   |M| PICTURE ID  | (REQUIRED)
+        //      +-+-+-+-+-+-+-+-+
+        // M:   | EXTENDED PID  | (RECOMMENDED)
+        //      +-+-+-+-+-+-+-+-+
+        // L:   |  T  |U|  S  |D| (CONDITIONALLY RECOMMENDED)
+        //      +-+-+-+-+-+-+-+-+
+        //      |   TL0PICIDX   | (CONDITIONALLY REQUIRED, non-flexible mode)
+        //      +-+-+-+-+-+-+-+-+
+        // P,F: | P_DIFF      |N| (CONDITIONALLY REQUIRED, up to 3 times)
+        //      +-+-+-+-+-+-+-+-+
+        // V:   | SS            |
+        //      | ..            |
+        //      +-+-+-+-+-+-+-+-+
+        let header = payload[0];
+        let byte_at = |i: usize| -> Result<u8> {
+            payload
+                .get(i)
+                .copied()
+                .ok_or_else(|| Error::ErrShortPacket.into())
+        };
+
+        let mut size = 1;
+        if header & 0x80 != 0 {
+            // M bit signals a 15 bits PictureID
+            size += if byte_at(size)? & 0x80 != 0 { 2 } else { 1 };
+        }
+        if header & 0x20 != 0 {
+            size += 1;
+            if header & 0x10 == 0 {
+                size += 1;
+            }
+        }
+        if header & 0x10 != 0 && header & 0x40 != 0 {
+            for _ in 0..3 {
+                let p_diff = byte_at(size)?;
+                size += 1;
+                if p_diff & 0x01 == 0 {
+                    break;
+                }
+            }
+        }
+        if header & 0x02 != 0 {
+            //      +-+-+-+-+-+-+-+-+
+            // V:   | N_S |Y|G|-|-|-|
+            //      +-+-+-+-+-+-+-+-+
+            // Y:   |  WIDTH/HEIGHT | (N_S + 1) times, 4 bytes each
+            //      +-+-+-+-+-+-+-+-+
+            // G:   |      N_G      |
+            //      +-+-+-+-+-+-+-+-+
+            //      |  T  |U| R |-|-| (N_G times), followed by R P_DIFF bytes
+            //      +-+-+-+-+-+-+-+-+
+            let ss = byte_at(size)?;
+            size += 1;
+            let n_s = ((ss >> 5) + 1) as usize;
+            if ss & 0x10 != 0 {
+                size += 4 * n_s;
+            }
+            if ss & 0x08 != 0 {
+                let n_g = byte_at(size)?;
+                size += 1;
+                for _ in 0..n_g {
+                    let r = ((byte_at(size)? >> 2) & 0x03) as usize;
+                    size += 1 + r;
+                }
+            }
+        }
+
+        if payload.len() <= size {
+            return Err(Error::ErrShortPacket.into());
+        }
+
+        Ok(size)
+    }
+}
+
+impl Depacketizer for Vp9Depacketizer {
+    fn depacketize(&mut self, payload: &Bytes) -> Result<Bytes> {
+        let size = Vp9Depacketizer::descriptor_size(payload)?;
+        Ok(payload.slice(size..))
+    }
+
+    /// The B bit marks the beginning of a frame
+    fn is_partition_head(&self, payload: &Bytes) -> bool {
+        !payload.is_empty() && payload[0] & 0x08 != 0
+    }
+
+    fn is_partition_tail(&self, marker: bool, _payload: &Bytes) -> bool {
+        marker
+    }
+
+    /// A key frame is the beginning of a frame that is not inter-picture predicted
+    /// in its lowest spatial layer
+    fn is_keyframe(&self, payload: &Bytes) -> bool {
+        if !self.is_partition_head(payload) || payload[0] & 0x40 != 0 {
+            return false;
+        }
+
+        if payload[0] & 0x20 != 0 {
+            let mut index = 1;
+            if payload[0] & 0x80 != 0 {
+                match payload.get(index) {
+                    Some(pid) if pid & 0x80 != 0 => index += 2,
+                    Some(_) => index += 1,
+                    None => return false,
+                };
+            }
+            // the spatial layer id must be 0
+            match payload.get(index) {
+                Some(layer) if (layer >> 1) & 0x07 == 0 => {}
+                _ => return false,
+            };
+        }
+
+        Vp9Depacketizer::descriptor_size(payload).is_ok()
+    }
+
+    fn clone_to(&self) -> Box<dyn Depacketizer + Send + Sync> {
+        Box::new(*self)
+    }
+}
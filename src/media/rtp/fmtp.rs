@@ -1,3 +1,5 @@
+use crate::api::media_engine::MIME_TYPE_H264;
+
 use std::collections::HashMap;
 
 type Fmtp = HashMap<String, String>;
@@ -38,6 +40,161 @@ pub(crate) fn fmtp_consist(a: &Fmtp, b: &Fmtp) -> bool {
     true
 }
 
+/// codec_fmtp_consist checks that two FMTP parameters of the given codec are
+/// not inconsistent, using the codec specific rules where the codec has some.
+pub(crate) fn codec_fmtp_consist(mime_type: &str, a: &Fmtp, b: &Fmtp) -> bool {
+    if mime_type.eq_ignore_ascii_case(MIME_TYPE_H264) {
+        h264_fmtp_consist(a, b)
+    } else {
+        fmtp_consist(a, b)
+    }
+}
+
+/// H264Profile is the profile a H264 profile-level-id describes, as
+/// classified by libwebrtc.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum H264Profile {
+    ConstrainedBaseline,
+    Baseline,
+    Main,
+    ConstrainedHigh,
+    High,
+    PredictiveHigh444,
+}
+
+/// H264_PROFILE_PATTERNS maps a profile_idc and a pattern of its profile-iop
+/// constraint bits, most significant bit first, to the profile they describe.
+/// An 'x' bit may have any value.
+/// https://tools.ietf.org/html/rfc6184#section-8.1
+const H264_PROFILE_PATTERNS: &[(u8, &str, H264Profile)] = &[
+    (0x42, "x1xx0000", H264Profile::ConstrainedBaseline),
+    (0x4D, "1xxx0000", H264Profile::ConstrainedBaseline),
+    (0x58, "11xx0000", H264Profile::ConstrainedBaseline),
+    (0x42, "x0xx0000", H264Profile::Baseline),
+    (0x58, "10xx0000", H264Profile::Baseline),
+    (0x4D, "0x0x0000", H264Profile::Main),
+    (0x64, "00000000", H264Profile::High),
+    (0x64, "00001100", H264Profile::ConstrainedHigh),
+    (0xF4, "00000000", H264Profile::PredictiveHigh444),
+];
+
+/// matches_bit_pattern checks the bits of value against a pattern of '0', '1'
+/// and 'x' characters.
+fn matches_bit_pattern(value: u8, pattern: &str) -> bool {
+    pattern.bytes().enumerate().all(|(i, p)| {
+        let bit = (value >> (7 - i)) & 1;
+        match p {
+            b'0' => bit == 0,
+            b'1' => bit == 1,
+            _ => true,
+        }
+    })
+}
+
+/// parse_profile_level_id splits a H264 profile-level-id into the profile it
+/// describes and its level_idc. It returns None when the profile-level-id is
+/// malformed or its profile is unknown.
+/// https://tools.ietf.org/html/rfc6184#section-8.1
+fn parse_profile_level_id(profile_level_id: &str) -> Option<(H264Profile, u8)> {
+    if profile_level_id.len() != 6 || !profile_level_id.is_ascii() {
+        return None;
+    }
+    let byte = |i: usize| u8::from_str_radix(&profile_level_id[i..i + 2], 16).ok();
+    let (profile_idc, profile_iop, level_idc) = (byte(0)?, byte(2)?, byte(4)?);
+
+    H264_PROFILE_PATTERNS
+        .iter()
+        .find(|(idc, pattern, _)| *idc == profile_idc && matches_bit_pattern(profile_iop, pattern))
+        .map(|(_, _, profile)| (*profile, level_idc))
+}
+
+/// h264_profile_level_id returns the profile-level-id of H264 FMTP parameters,
+/// which defaults to 42000A, Baseline profile at level 1
+fn h264_profile_level_id(f: &Fmtp) -> &str {
+    f.get("profile-level-id").map_or("42000a", |v| v.as_str())
+}
+
+fn h264_level_asymmetry_allowed(f: &Fmtp) -> bool {
+    f.get("level-asymmetry-allowed").is_some_and(|v| v == "1")
+}
+
+/// h264_fmtp_consist checks that two H264 FMTP parameters describe the same
+/// codec: packetization-mode and the profile must be equal. The levels may
+/// differ, the level of the answer is picked by codec_answer_fmtp_line.
+/// https://tools.ietf.org/html/rfc6184#section-8.2.2
+fn h264_fmtp_consist(a: &Fmtp, b: &Fmtp) -> bool {
+    // packetization-mode defaults to 0 (single NAL unit mode)
+    let packetization_mode = |f: &Fmtp| {
+        f.get("packetization-mode")
+            .map_or("0".to_owned(), |v| v.clone())
+    };
+    if packetization_mode(a) != packetization_mode(b) {
+        return false;
+    }
+
+    match (
+        parse_profile_level_id(h264_profile_level_id(a)),
+        parse_profile_level_id(h264_profile_level_id(b)),
+    ) {
+        (Some((a_profile, _)), Some((b_profile, _))) => a_profile == b_profile,
+        _ => false,
+    }
+}
+
+/// codec_answer_fmtp_line returns the FMTP line with which a remote offered codec
+/// is answered, given the FMTP line of the matching local codec. For H264 the
+/// level of the answer is the local one when both sides set level-asymmetry-allowed,
+/// and the lower of the two levels otherwise. Other codecs are answered as offered.
+/// https://tools.ietf.org/html/rfc6184#section-8.2.2
+pub(crate) fn codec_answer_fmtp_line(mime_type: &str, local: &str, remote: &str) -> String {
+    if !mime_type.eq_ignore_ascii_case(MIME_TYPE_H264) {
+        return remote.to_owned();
+    }
+
+    let (local_fmtp, remote_fmtp) = (parse_fmtp(local), parse_fmtp(remote));
+    let remote_profile_level_id = h264_profile_level_id(&remote_fmtp);
+    let (local_level, remote_level) = match (
+        parse_profile_level_id(h264_profile_level_id(&local_fmtp)),
+        parse_profile_level_id(remote_profile_level_id),
+    ) {
+        (Some((_, local_level)), Some((_, remote_level))) => (local_level, remote_level),
+        _ => return remote.to_owned(),
+    };
+
+    let answer_level = if h264_level_asymmetry_allowed(&local_fmtp)
+        && h264_level_asymmetry_allowed(&remote_fmtp)
+    {
+        local_level
+    } else {
+        local_level.min(remote_level)
+    };
+    if answer_level == remote_level {
+        return remote.to_owned();
+    }
+
+    // the profile is kept as the remote wrote it, only its level is replaced
+    let answer_profile_level_id = format!(
+        "profile-level-id={}{:02x}",
+        &remote_profile_level_id[..4],
+        answer_level
+    );
+    let mut params = vec![];
+    let mut replaced = false;
+    for p in remote.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+        let key = p.split('=').next().unwrap_or_default();
+        if key.eq_ignore_ascii_case("profile-level-id") {
+            params.push(answer_profile_level_id.clone());
+            replaced = true;
+        } else {
+            params.push(p.to_owned());
+        }
+    }
+    if !replaced {
+        params.push(answer_profile_level_id);
+    }
+    params.join(";")
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -163,4 +320,190 @@ mod test {
             check(b, a);
         }
     }
+
+    #[test]
+    fn test_h264_fmtp_consist() {
+        let tests = vec![
+            (
+                "Equal",
+                "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e01f",
+                "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e01f",
+                true,
+            ),
+            (
+                "EqualWithCase",
+                "packetization-mode=1;profile-level-id=42E01F",
+                "packetization-mode=1;profile-level-id=42e01f",
+                true,
+            ),
+            (
+                "DifferentLevelWithAsymmetry",
+                "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=640c1f",
+                "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=640c34",
+                true,
+            ),
+            (
+                "DifferentLevelWithoutAsymmetry",
+                "packetization-mode=1;profile-level-id=640c1f",
+                "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=640c34",
+                true,
+            ),
+            (
+                "DifferentLevelOfConstrainedBaseline",
+                "packetization-mode=1;profile-level-id=42e01f",
+                "packetization-mode=1;profile-level-id=42e034",
+                true,
+            ),
+            (
+                "DifferentProfileIdc",
+                "packetization-mode=1;profile-level-id=42e01f",
+                "packetization-mode=1;profile-level-id=64e01f",
+                false,
+            ),
+            (
+                "DifferentProfileIop",
+                "packetization-mode=1;profile-level-id=42001f",
+                "packetization-mode=1;profile-level-id=42e01f",
+                false,
+            ),
+            (
+                "ConstrainedBaselineWithOtherConstraintBits",
+                "packetization-mode=1;profile-level-id=42c01f",
+                "packetization-mode=1;profile-level-id=42e01f",
+                true,
+            ),
+            (
+                "ConstrainedBaselineWithMainProfileIdc",
+                "packetization-mode=1;profile-level-id=4de01f",
+                "packetization-mode=1;profile-level-id=42e01f",
+                true,
+            ),
+            (
+                "MainAndHigh",
+                "packetization-mode=1;profile-level-id=4d001f",
+                "packetization-mode=1;profile-level-id=64001f",
+                false,
+            ),
+            (
+                "UnknownProfile",
+                "packetization-mode=1;profile-level-id=64e01f",
+                "packetization-mode=1;profile-level-id=64e01f",
+                false,
+            ),
+            (
+                "DifferentPacketizationMode",
+                "packetization-mode=0;profile-level-id=42e01f",
+                "packetization-mode=1;profile-level-id=42e01f",
+                false,
+            ),
+            (
+                "DefaultPacketizationMode",
+                "profile-level-id=42e01f",
+                "packetization-mode=0;profile-level-id=42e01f",
+                true,
+            ),
+            (
+                "DefaultProfileLevelId",
+                "packetization-mode=1",
+                "packetization-mode=1;profile-level-id=42000a",
+                true,
+            ),
+            (
+                "InvalidProfileLevelId",
+                "packetization-mode=1;profile-level-id=42e0",
+                "packetization-mode=1;profile-level-id=42e0",
+                false,
+            ),
+            (
+                "IgnoresOtherParams",
+                "packetization-mode=1;profile-level-id=42e01f;sprop-parameter-sets=Z0IAH5WoFAFuQA==,aM48gA==",
+                "packetization-mode=1;profile-level-id=42e01f",
+                true,
+            ),
+        ];
+
+        for (name, a, b, consist) in tests {
+            let check = |a, b| {
+                let c = codec_fmtp_consist(MIME_TYPE_H264, &parse_fmtp(a), &parse_fmtp(b));
+                assert_eq!(
+                    c, consist,
+                    "{}: '{}' and '{}' are expected to be consistent: {}",
+                    name, a, b, consist
+                );
+            };
+
+            check(a, b);
+            check(b, a);
+        }
+
+        // codecs without specific rules fall back to the generic comparison
+        assert!(!codec_fmtp_consist(
+            "video/VP9",
+            &parse_fmtp("profile-id=0"),
+            &parse_fmtp("profile-id=1"),
+        ));
+    }
+
+    #[test]
+    fn test_codec_answer_fmtp_line() {
+        let tests = vec![
+            (
+                "LowerRemoteLevel",
+                "packetization-mode=1;profile-level-id=42e034",
+                "packetization-mode=1;profile-level-id=42e01f",
+                "packetization-mode=1;profile-level-id=42e01f",
+            ),
+            (
+                "LowerLocalLevel",
+                "packetization-mode=1;profile-level-id=42e01f",
+                "packetization-mode=1;profile-level-id=42E034;sprop-parameter-sets=Z0IAH5WoFAFuQA==",
+                "packetization-mode=1;profile-level-id=42E01f;sprop-parameter-sets=Z0IAH5WoFAFuQA==",
+            ),
+            (
+                "LowerLocalLevelWithAsymmetry",
+                "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e01f",
+                "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e034",
+                "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e01f",
+            ),
+            (
+                "HigherLocalLevelWithAsymmetry",
+                "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e034",
+                "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e01f",
+                "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e034",
+            ),
+            (
+                "HigherLocalLevelWithRemoteAsymmetryOnly",
+                "packetization-mode=1;profile-level-id=42e034",
+                "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e01f",
+                "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e01f",
+            ),
+            (
+                "DefaultRemoteProfileLevelId",
+                "packetization-mode=1;profile-level-id=42e01f",
+                "packetization-mode=1",
+                "packetization-mode=1",
+            ),
+            (
+                "InvalidRemoteProfileLevelId",
+                "packetization-mode=1;profile-level-id=42e01f",
+                "packetization-mode=1;profile-level-id=42e0",
+                "packetization-mode=1;profile-level-id=42e0",
+            ),
+        ];
+
+        for (name, local, remote, expected) in tests {
+            assert_eq!(
+                codec_answer_fmtp_line(MIME_TYPE_H264, local, remote),
+                expected,
+                "{}",
+                name
+            );
+        }
+
+        // codecs without specific rules are answered as offered
+        assert_eq!(
+            codec_answer_fmtp_line("video/VP9", "profile-id=0", "profile-id=1"),
+            "profile-id=1"
+        );
+    }
 }
@@ -1,7 +1,13 @@
-mod fmtp;
+pub(crate) mod fmtp;
+pub mod codecs;
 pub mod rtp_codec;
+pub mod rtp_receiver;
+pub mod rtp_sender;
+pub mod rtp_transceiver;
+pub mod rtp_transceiver_direction;
 
 use rtp_codec::*;
+use rtp_transceiver_direction::RTPTransceiverDirection;
 
 use serde::{Deserialize, Serialize};
 
@@ -36,7 +42,7 @@ pub const TYPE_RTCP_FB_NACK: &str = "nack";
 
 /// rtcpfeedback signals the connection to use additional RTCP packet types.
 /// https://draft.ortc.org/#dom-rtcrtcpfeedback
-#[derive(Default, Debug, Clone)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct RTCPFeedback {
     /// Type is the type of feedback.
     /// see: https://draft.ortc.org/#dom-rtcrtcpfeedback
@@ -56,6 +62,13 @@ pub struct RTPCapabilities {
     pub header_extensions: Vec<RTPHeaderExtensionCapability>,
 }
 
+/// RTPRtxParameters dictionary contains information relating to retransmission (RTX) settings.
+/// https://draft.ortc.org/#dom-rtcrtprtxparameters
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct RTPRtxParameters {
+    pub ssrc: SSRC,
+}
+
 /// RTPCodingParameters provides information relating to both encoding and decoding.
 /// This is a subset of the RFC since Pion WebRTC doesn't implement encoding/decoding itself
 /// http://draft.ortc.org/#dom-rtcrtpcodingparameters
@@ -64,6 +77,7 @@ pub struct RTPCodingParameters {
     pub rid: String,
     pub ssrc: SSRC,
     pub payload_type: PayloadType,
+    pub rtx: RTPRtxParameters,
 }
 
 /// RTPDecodingParameters provides information relating to both encoding and decoding.
@@ -77,6 +91,22 @@ pub type RTPDecodingParameters = RTPCodingParameters;
 pub type RTPEncodingParameters = RTPCodingParameters;
 
 /// RTPReceiveParameters contains the RTP stack settings used by receivers
+#[derive(Default, Debug, Clone)]
 pub struct RTPReceiveParameters {
     pub encodings: Vec<RTPDecodingParameters>,
 }
+
+/// RTPSendParameters contains the RTP stack settings used by senders
+#[derive(Default, Debug, Clone)]
+pub struct RTPSendParameters {
+    pub rtp_parameters: RTPParameters,
+    pub encodings: Vec<RTPEncodingParameters>,
+}
+
+/// RTPTransceiverInit dictionary is used when calling the add_transceiver() method.
+#[derive(Default, Debug, Clone)]
+pub struct RTPTransceiverInit {
+    pub direction: RTPTransceiverDirection,
+    pub send_encodings: Vec<RTPEncodingParameters>,
+    // Streams       []*Track
+}
@@ -1,11 +1,14 @@
 use super::*;
+use crate::api::media_engine::MIME_TYPE_RTX;
 use crate::media::rtp::fmtp::*;
 
+use anyhow::Result;
 use std::fmt;
 
 /// RTPCodecType determines the type of a codec
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
 pub enum RTPCodecType {
+    #[default]
     Unspecified = 0,
 
     /// RTPCodecTypeAudio indicates this is an audio codec
@@ -15,17 +18,21 @@ pub enum RTPCodecType {
     Video = 2,
 }
 
-impl Default for RTPCodecType {
-    fn default() -> Self {
-        RTPCodecType::Unspecified
-    }
-}
-
 impl From<&str> for RTPCodecType {
     fn from(raw: &str) -> Self {
         match raw {
-            "Audio" => RTPCodecType::Audio,
-            "Video" => RTPCodecType::Video,
+            "audio" => RTPCodecType::Audio,
+            "video" => RTPCodecType::Video,
+            _ => RTPCodecType::Unspecified,
+        }
+    }
+}
+
+impl From<u8> for RTPCodecType {
+    fn from(v: u8) -> Self {
+        match v {
+            1 => RTPCodecType::Audio,
+            2 => RTPCodecType::Video,
             _ => RTPCodecType::Unspecified,
         }
     }
@@ -34,8 +41,8 @@ impl From<&str> for RTPCodecType {
 impl fmt::Display for RTPCodecType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let s = match *self {
-            RTPCodecType::Audio => "Audio",
-            RTPCodecType::Video => "Video",
+            RTPCodecType::Audio => "audio",
+            RTPCodecType::Video => "video",
             RTPCodecType::Unspecified => crate::UNSPECIFIED_STR,
         };
         write!(f, "{}", s)
@@ -44,7 +51,7 @@ impl fmt::Display for RTPCodecType {
 
 /// RTPCodecCapability provides information about codec capabilities.
 /// https://w3c.github.io/webrtc-pc/#dictionary-rtcrtpcodeccapability-members
-#[derive(Default, Debug, Clone)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct RTPCodecCapability {
     pub mime_type: String,
     pub clock_rate: u32,
@@ -53,6 +60,22 @@ pub struct RTPCodecCapability {
     pub rtcp_feedback: Vec<RTCPFeedback>,
 }
 
+impl RTPCodecCapability {
+    /// payloader_for_codec returns the payloader that splits samples of this
+    /// codec into RTP payloads
+    pub(crate) fn payloader_for_codec(
+        &self,
+    ) -> Result<Box<dyn rtp::packetizer::Payloader + Send + Sync>> {
+        codecs::payloader_for_mime_type(&self.mime_type)
+    }
+
+    /// depacketizer_for_codec returns the depacketizer that turns RTP payloads
+    /// of this codec back into samples
+    pub fn depacketizer_for_codec(&self) -> Result<Box<dyn codecs::Depacketizer + Send + Sync>> {
+        codecs::depacketizer_for_mime_type(&self.mime_type)
+    }
+}
+
 /// RTPHeaderExtensionCapability is used to define a RFC5285 RTP header extension supported by the codec.
 /// https://w3c.github.io/webrtc-pc/#dom-rtcrtpcapabilities-headerextensions
 #[derive(Default, Debug, Clone)]
@@ -72,7 +95,7 @@ pub struct RTPHeaderExtensionParameter {
 /// will choose from, as well as entries for RTX, RED and FEC mechanisms. This also
 /// includes the PayloadType that has been negotiated
 /// https://w3c.github.io/webrtc-pc/#rtcrtpcodecparameters
-#[derive(Default, Debug, Clone)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct RTPCodecParameters {
     pub capability: RTPCodecCapability,
     pub payload_type: PayloadType,
@@ -81,11 +104,42 @@ pub struct RTPCodecParameters {
 
 /// RTPParameters is a list of negotiated codecs and header extensions
 /// https://w3c.github.io/webrtc-pc/#dictionary-rtcrtpparameters-members
+#[derive(Default, Debug, Clone)]
 pub struct RTPParameters {
     pub header_extensions: Vec<RTPHeaderExtensionParameter>,
     pub codecs: Vec<RTPCodecParameters>,
 }
 
+/// rtx_associated_payload_type returns the payload type a RTX codec retransmits, which
+/// its apt fmtp parameter points to. None is returned for the other codecs.
+pub(crate) fn rtx_associated_payload_type(codec: &RTPCodecParameters) -> Option<PayloadType> {
+    if !codec
+        .capability
+        .mime_type
+        .eq_ignore_ascii_case(MIME_TYPE_RTX)
+    {
+        return None;
+    }
+
+    parse_fmtp(&codec.capability.sdp_fmtp_line)
+        .get("apt")?
+        .parse::<PayloadType>()
+        .ok()
+}
+
+/// find_rtx_payload_type returns the payload type of the RTX codec retransmitting
+/// the codec of the given payload type, if any
+pub(crate) fn find_rtx_payload_type(
+    payload_type: PayloadType,
+    codecs: &[RTPCodecParameters],
+) -> Option<PayloadType> {
+    codecs
+        .iter()
+        .find(|c| rtx_associated_payload_type(c) == Some(payload_type))
+        .map(|c| c.payload_type)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub(crate) enum CodecMatchType {
     None = 0,
     Partial = 1,
@@ -106,7 +160,11 @@ pub(crate) fn codec_parameters_fuzzy_search(
     // First attempt to match on mime_type + sdpfmtp_line
     for c in haystack {
         if c.capability.mime_type.to_uppercase() == needle.capability.mime_type.to_uppercase()
-            && fmtp_consist(&needle_fmtp, &parse_fmtp(&c.capability.sdp_fmtp_line))
+            && codec_fmtp_consist(
+                &c.capability.mime_type,
+                &needle_fmtp,
+                &parse_fmtp(&c.capability.sdp_fmtp_line),
+            )
         {
             return (c.clone(), CodecMatchType::Exact);
         }
@@ -0,0 +1,620 @@
+use crate::api::media_engine::MediaEngine;
+use crate::error::{flatten_errs, Error};
+use crate::interceptor::stream_info::StreamInfo;
+use crate::interceptor::{unmarshal_rtcp, Attributes, Interceptor, RTCPReader, RTPReader};
+use crate::media::dtls_transport::srtp_session::SRTPStream;
+use crate::media::dtls_transport::DTLSTransport;
+use crate::media::rtp::rtp_codec::{rtx_associated_payload_type, RTPCodecType, RTPParameters};
+use crate::media::rtp::rtp_transceiver_direction::RTPTransceiverDirection;
+use crate::media::rtp::{PayloadType, RTPReceiveParameters, SSRC};
+use crate::media::track::track_remote::TrackRemote;
+use crate::RECEIVE_MTU;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+use util::marshal::{Marshal, Unmarshal};
+
+/// RTXReader reads the packets of a remote stream along with the ones retransmitted
+/// on its RTX repair stream, which are unwrapped back into packets of the stream
+/// (RFC 4588). It sits at the end of the interceptor chain of the remote stream,
+/// so the interceptors and the track don't tell the retransmissions apart.
+pub(crate) struct RTXReader {
+    rtp_read_stream: Arc<SRTPStream>,
+    rtx_rx: Mutex<mpsc::Receiver<Bytes>>,
+}
+
+impl RTXReader {
+    /// new starts reading the repair stream, the payload types of its packets are
+    /// mapped back to the ones they retransmit with the given associated payload types
+    pub(crate) fn new(
+        ssrc: SSRC,
+        rtp_read_stream: Arc<SRTPStream>,
+        repair_rtp_read_stream: Arc<SRTPStream>,
+        associated_payload_types: HashMap<PayloadType, PayloadType>,
+    ) -> Self {
+        let (rtx_tx, rtx_rx) = mpsc::channel(128);
+
+        tokio::spawn(async move {
+            let mut b = vec![0u8; RECEIVE_MTU];
+            // the repair stream is closed along with the stream by RTPReceiver.stop
+            while let Ok(n) = repair_rtp_read_stream.read(&mut b).await {
+                let pkt = match rtp::packet::Packet::unmarshal(&mut &b[..n]) {
+                    Ok(pkt) => pkt,
+                    Err(err) => {
+                        log::warn!("failed to unmarshal RTX packet: {}", err);
+                        continue;
+                    }
+                };
+
+                let pkt = match unwrap_rtx(pkt, ssrc, &associated_payload_types) {
+                    Some(pkt) => pkt,
+                    None => continue,
+                };
+                match pkt.marshal() {
+                    Ok(raw) => {
+                        if rtx_tx.send(raw).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(err) => log::warn!("failed to marshal unwrapped RTX packet: {}", err),
+                }
+            }
+        });
+
+        RTXReader {
+            rtp_read_stream,
+            rtx_rx: Mutex::new(rtx_rx),
+        }
+    }
+}
+
+#[async_trait]
+impl RTPReader for RTXReader {
+    async fn read(&self, buf: &mut [u8], a: &Attributes) -> Result<(usize, Attributes)> {
+        // the read of the stream dropped when a retransmission comes first doesn't
+        // lose a packet, SRTPStream::read is cancel safe
+        let mut rtx_rx = self.rtx_rx.lock().await;
+        tokio::select! {
+            result = self.rtp_read_stream.read(buf) => Ok((result?, a.clone())),
+            raw = rtx_rx.recv() => match raw {
+                Some(raw) => {
+                    if raw.len() > buf.len() {
+                        return Err(Error::new("buffer is too short".to_owned()).into());
+                    }
+                    buf[..raw.len()].copy_from_slice(&raw);
+                    Ok((raw.len(), a.clone()))
+                }
+                // the repair stream is gone, only the stream is left
+                None => Ok((self.rtp_read_stream.read(buf).await?, a.clone())),
+            },
+        }
+    }
+}
+
+/// unwrap_rtx turns a RTX packet back into the packet it retransmits, whose sequence
+/// number leads its payload. None is returned for the RTX packets without one, like
+/// the padding only ones, and for the ones of an unknown payload type.
+fn unwrap_rtx(
+    mut pkt: rtp::packet::Packet,
+    ssrc: SSRC,
+    associated_payload_types: &HashMap<PayloadType, PayloadType>,
+) -> Option<rtp::packet::Packet> {
+    let payload_type = *associated_payload_types.get(&pkt.header.payload_type)?;
+    if pkt.payload.len() < 2 {
+        return None;
+    }
+
+    pkt.header.ssrc = ssrc;
+    pkt.header.payload_type = payload_type;
+    pkt.header.sequence_number = u16::from_be_bytes([pkt.payload[0], pkt.payload[1]]);
+    pkt.payload = pkt.payload.slice(2..);
+
+    Some(pkt)
+}
+
+/// TrackStreams maintains a mapping of RTP/RTCP streams to a specific track,
+/// the track reads from the interceptors bound to its RTP stream. The streams
+/// of a RID are only known once its SSRC is probed.
+#[derive(Clone)]
+pub(crate) struct TrackStreams {
+    pub(crate) track: Arc<TrackRemote>,
+
+    pub(crate) stream_info: Option<StreamInfo>,
+    pub(crate) rtp_read_stream: Option<Arc<SRTPStream>>,
+    pub(crate) repair_rtp_read_stream: Option<Arc<SRTPStream>>,
+    pub(crate) rtcp_read_stream: Option<Arc<SRTPStream>>,
+    pub(crate) rtcp_interceptor: Option<Arc<dyn RTCPReader + Send + Sync>>,
+}
+
+/// RTPReceiver allows an application to inspect the receipt of a Track
+pub struct RTPReceiver {
+    pub(crate) kind: RTPCodecType,
+    pub(crate) transport: Arc<DTLSTransport>,
+    pub(crate) media_engine: Arc<MediaEngine>,
+    pub(crate) interceptor: Arc<dyn Interceptor + Send + Sync>,
+
+    pub(crate) tracks: Mutex<Vec<TrackStreams>>,
+
+    // the sender side of each pair is dropped to signal the event
+    received_tx: Mutex<Option<mpsc::Sender<()>>>,
+    received_rx: Mutex<mpsc::Receiver<()>>,
+    closed_tx: Mutex<Option<mpsc::Sender<()>>>,
+    closed_rx: Mutex<mpsc::Receiver<()>>,
+}
+
+impl RTPReceiver {
+    /// new constructs a new RTPReceiver of the given kind, which receives over
+    /// the given DTLSTransport through the given interceptor
+    pub fn new(
+        kind: RTPCodecType,
+        transport: Arc<DTLSTransport>,
+        media_engine: Arc<MediaEngine>,
+        interceptor: Arc<dyn Interceptor + Send + Sync>,
+    ) -> Self {
+        let (received_tx, received_rx) = mpsc::channel(1);
+        let (closed_tx, closed_rx) = mpsc::channel(1);
+
+        RTPReceiver {
+            kind,
+            transport,
+            media_engine,
+            interceptor,
+
+            tracks: Mutex::new(vec![]),
+
+            received_tx: Mutex::new(Some(received_tx)),
+            received_rx: Mutex::new(received_rx),
+            closed_tx: Mutex::new(Some(closed_tx)),
+            closed_rx: Mutex::new(closed_rx),
+        }
+    }
+
+    /// kind returns the kind of media this RTPReceiver receives
+    pub fn kind(&self) -> RTPCodecType {
+        self.kind
+    }
+
+    /// transport returns the currently-configured DTLSTransport
+    /// or nil if one has not yet been configured
+    pub fn transport(&self) -> Arc<DTLSTransport> {
+        Arc::clone(&self.transport)
+    }
+
+    /// track returns the RTPTransceiver TrackRemote
+    pub async fn track(&self) -> Option<Arc<TrackRemote>> {
+        let tracks = self.tracks.lock().await;
+        if tracks.len() != 1 {
+            None
+        } else {
+            Some(Arc::clone(&tracks[0].track))
+        }
+    }
+
+    /// tracks returns the RTPTransceiver tracks
+    /// A RTPReceiver to support Simulcast may now have multiple tracks
+    pub async fn tracks(&self) -> Vec<Arc<TrackRemote>> {
+        let tracks = self.tracks.lock().await;
+        tracks.iter().map(|t| Arc::clone(&t.track)).collect()
+    }
+
+    /// get_parameters describes the current configuration for the encoding and
+    /// transmission of media on the receiver's track.
+    pub async fn get_parameters(&self) -> RTPParameters {
+        self.media_engine
+            .get_rtp_parameters_by_kind(self.kind, &[RTPTransceiverDirection::Recvonly])
+            .await
+    }
+
+    /// receive initialize the track and starts all the transports
+    pub async fn receive(&self, parameters: &RTPReceiveParameters) -> Result<()> {
+        let mut received_tx = self.received_tx.lock().await;
+        if received_tx.is_none() {
+            return Err(Error::ErrRTPReceiverReceiveAlreadyCalled.into());
+        }
+        // the tracks are locked before signalling, so readers only see them
+        // once they are set up. receive can only be attempted once, even if
+        // it fails
+        let mut tracks = self.tracks.lock().await;
+        received_tx.take();
+
+        if parameters.encodings.len() == 1 && parameters.encodings[0].ssrc != 0 {
+            let ssrc = parameters.encodings[0].ssrc;
+            let (rtp_read_stream, rtcp_read_stream) = self.streams_for_ssrc(ssrc).await?;
+            let repair_rtp_read_stream = match parameters.encodings[0].rtx.ssrc {
+                0 => None,
+                repair_ssrc => Some(self.repair_stream_for_ssrc(repair_ssrc).await?),
+            };
+            let track = Arc::new(TrackRemote::new(self.kind, ssrc, String::new(), None));
+            let stream_info = self.stream_info(ssrc).await;
+            tracks.push(
+                self.bind_interceptors(
+                    track,
+                    stream_info,
+                    rtp_read_stream,
+                    repair_rtp_read_stream,
+                    rtcp_read_stream,
+                )
+                .await,
+            );
+        } else {
+            for encoding in &parameters.encodings {
+                tracks.push(TrackStreams {
+                    track: Arc::new(TrackRemote::new(self.kind, 0, encoding.rid.clone(), None)),
+                    stream_info: None,
+                    rtp_read_stream: None,
+                    repair_rtp_read_stream: None,
+                    rtcp_read_stream: None,
+                    rtcp_interceptor: None,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// receive_for_rid is the sibling of receive for the tracks of a RID, which are
+    /// set up without streams. Once the SSRC of the RID has been probed, the
+    /// track gets its streams and the parameters of the probed payload type.
+    pub(crate) async fn receive_for_rid(
+        &self,
+        rid: &str,
+        params: RTPParameters,
+        ssrc: SSRC,
+        rtp_read_stream: Arc<SRTPStream>,
+    ) -> Result<Arc<TrackRemote>> {
+        let mut tracks = self.tracks.lock().await;
+        for t in &mut *tracks {
+            if t.track.rid() != rid {
+                continue;
+            }
+
+            let srtcp_session = match self.transport.get_srtcp_session().await {
+                Some(srtcp_session) => srtcp_session,
+                None => return Err(Error::ErrDtlsTransportNotStarted.into()),
+            };
+            let rtcp_read_stream = srtcp_session.open(ssrc).await;
+
+            let stream_info = match params.codecs.first() {
+                Some(codec) => StreamInfo::new(
+                    String::new(),
+                    ssrc,
+                    codec.payload_type,
+                    &codec.capability,
+                    &params.header_extensions,
+                ),
+                None => self.stream_info(ssrc).await,
+            };
+
+            t.track.set_kind(self.kind);
+            t.track.set_ssrc(ssrc);
+            t.track.set_params(params).await;
+            *t = self
+                .bind_interceptors(
+                    Arc::clone(&t.track),
+                    stream_info,
+                    rtp_read_stream,
+                    None,
+                    rtcp_read_stream,
+                )
+                .await;
+
+            return Ok(Arc::clone(&t.track));
+        }
+
+        Err(Error::ErrRTPReceiverForRIDTrackStreamNotFound.into())
+    }
+
+    /// read reads incoming RTCP for this RTPReceiver
+    pub async fn read(&self, b: &mut [u8]) -> Result<usize> {
+        let rtcp_interceptor = {
+            let tracks = self.wait_received().await?;
+            tracks.first().and_then(|t| t.rtcp_interceptor.clone())
+        };
+
+        match rtcp_interceptor {
+            // stop closes the streams, which unblocks any pending read
+            Some(rtcp_interceptor) => {
+                let (n, _) = rtcp_interceptor.read(b, &Attributes::new()).await?;
+                Ok(n)
+            }
+            None => Err(Error::ErrRTPReceiverWithSSRCTrackStreamNotFound.into()),
+        }
+    }
+
+    /// read_rtcp is a convenience method that wraps read and unmarshal for you.
+    pub async fn read_rtcp(&self) -> Result<Vec<Box<dyn rtcp::packet::Packet + Send + Sync>>> {
+        let mut b = vec![0u8; RECEIVE_MTU];
+        let n = self.read(&mut b).await?;
+
+        unmarshal_rtcp(&b[..n])
+    }
+
+    /// have_received tells if receive has been called for this instance
+    pub(crate) async fn have_received(&self) -> bool {
+        let received_tx = self.received_tx.lock().await;
+        received_tx.is_none()
+    }
+
+    /// stop irreversibly stops the RTPReceiver
+    pub async fn stop(&self) -> Result<()> {
+        {
+            let mut closed_tx = self.closed_tx.lock().await;
+            if closed_tx.take().is_none() {
+                return Ok(());
+            }
+        }
+
+        let mut errs = vec![];
+        if self.have_received().await {
+            let tracks = self.tracks.lock().await;
+            for t in &*tracks {
+                if let Some(rtcp_read_stream) = &t.rtcp_read_stream {
+                    if let Err(err) = rtcp_read_stream.close().await {
+                        errs.push(err);
+                    }
+                }
+
+                if let Some(rtp_read_stream) = &t.rtp_read_stream {
+                    if let Err(err) = rtp_read_stream.close().await {
+                        errs.push(err);
+                    }
+                }
+
+                if let Some(repair_rtp_read_stream) = &t.repair_rtp_read_stream {
+                    if let Err(err) = repair_rtp_read_stream.close().await {
+                        errs.push(err);
+                    }
+                }
+
+                if let Some(stream_info) = &t.stream_info {
+                    self.interceptor.unbind_remote_stream(stream_info).await;
+                }
+            }
+        }
+
+        flatten_errs(errs)
+    }
+
+    async fn is_closed(&self) -> bool {
+        let closed_tx = self.closed_tx.lock().await;
+        closed_tx.is_none()
+    }
+
+    /// wait_received blocks until receive has been called, and hands out the
+    /// track streams it has set up
+    async fn wait_received(&self) -> Result<tokio::sync::MutexGuard<'_, Vec<TrackStreams>>> {
+        if self.is_closed().await {
+            return Err(Error::ErrRTPReceiverStopped.into());
+        }
+
+        {
+            let mut received_rx = self.received_rx.lock().await;
+            let mut closed_rx = self.closed_rx.lock().await;
+            tokio::select! {
+                _ = received_rx.recv() => {}
+                _ = closed_rx.recv() => return Err(Error::ErrRTPReceiverStopped.into()),
+            }
+        }
+
+        Ok(self.tracks.lock().await)
+    }
+
+    /// stream_info describes the stream of a SSRC before its payload type is
+    /// known, with the first codec of the kind of the receiver
+    async fn stream_info(&self, ssrc: SSRC) -> StreamInfo {
+        let params = self.get_parameters().await;
+        let codec = params
+            .codecs
+            .first()
+            .map(|codec| codec.capability.clone())
+            .unwrap_or_default();
+
+        StreamInfo::new(String::new(), ssrc, 0, &codec, &params.header_extensions)
+    }
+
+    async fn streams_for_ssrc(&self, ssrc: SSRC) -> Result<(Arc<SRTPStream>, Arc<SRTPStream>)> {
+        let srtp_session = match self.transport.get_srtp_session().await {
+            Some(srtp_session) => srtp_session,
+            None => return Err(Error::ErrDtlsTransportNotStarted.into()),
+        };
+        let rtp_read_stream = srtp_session.open(ssrc).await;
+
+        let srtcp_session = match self.transport.get_srtcp_session().await {
+            Some(srtcp_session) => srtcp_session,
+            None => return Err(Error::ErrDtlsTransportNotStarted.into()),
+        };
+        let rtcp_read_stream = srtcp_session.open(ssrc).await;
+
+        Ok((rtp_read_stream, rtcp_read_stream))
+    }
+
+    /// repair_stream_for_ssrc opens the RTP stream of a RTX repair SSRC, its RTCP
+    /// is left to the undeclared media processor
+    async fn repair_stream_for_ssrc(&self, repair_ssrc: SSRC) -> Result<Arc<SRTPStream>> {
+        match self.transport.get_srtp_session().await {
+            Some(srtp_session) => Ok(srtp_session.open(repair_ssrc).await),
+            None => Err(Error::ErrDtlsTransportNotStarted.into()),
+        }
+    }
+
+    /// associated_payload_types maps the payload types of the RTX codecs of the
+    /// receiver to the payload types they retransmit
+    async fn associated_payload_types(&self) -> HashMap<PayloadType, PayloadType> {
+        self.get_parameters()
+            .await
+            .codecs
+            .iter()
+            .filter_map(|c| rtx_associated_payload_type(c).map(|apt| (c.payload_type, apt)))
+            .collect()
+    }
+
+    /// bind_interceptors binds the interceptors to the RTP and RTCP streams of
+    /// a track, the track reads its packets through them. The retransmissions
+    /// of the repair stream, if any, are read along with the RTP stream.
+    async fn bind_interceptors(
+        &self,
+        track: Arc<TrackRemote>,
+        stream_info: StreamInfo,
+        rtp_read_stream: Arc<SRTPStream>,
+        repair_rtp_read_stream: Option<Arc<SRTPStream>>,
+        rtcp_read_stream: Arc<SRTPStream>,
+    ) -> TrackStreams {
+        let rtp_reader: Arc<dyn RTPReader + Send + Sync> = match &repair_rtp_read_stream {
+            Some(repair_rtp_read_stream) => Arc::new(RTXReader::new(
+                stream_info.ssrc,
+                Arc::clone(&rtp_read_stream),
+                Arc::clone(repair_rtp_read_stream),
+                self.associated_payload_types().await,
+            )),
+            None => Arc::clone(&rtp_read_stream) as _,
+        };
+        let rtp_interceptor = self
+            .interceptor
+            .bind_remote_stream(&stream_info, rtp_reader)
+            .await;
+        {
+            let mut track_rtp_interceptor = track.rtp_interceptor.lock().await;
+            *track_rtp_interceptor = Some(rtp_interceptor);
+        }
+
+        let rtcp_interceptor = self
+            .interceptor
+            .bind_rtcp_reader(Arc::clone(&rtcp_read_stream) as _)
+            .await;
+
+        TrackStreams {
+            track,
+            stream_info: Some(stream_info),
+            rtp_read_stream: Some(rtp_read_stream),
+            repair_rtp_read_stream,
+            rtcp_read_stream: Some(rtcp_read_stream),
+            rtcp_interceptor: Some(rtcp_interceptor),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::interceptor::noop::NoOp;
+    use crate::media::rtp::RTPDecodingParameters;
+
+    #[tokio::test]
+    async fn test_rtp_receiver_receive_already_called() -> Result<()> {
+        let receiver = RTPReceiver::new(
+            RTPCodecType::Video,
+            Arc::new(DTLSTransport::default()),
+            Arc::new(MediaEngine::default()),
+            Arc::new(NoOp),
+        );
+
+        let parameters = RTPReceiveParameters {
+            encodings: vec![
+                RTPDecodingParameters {
+                    rid: "f".to_owned(),
+                    ..Default::default()
+                },
+                RTPDecodingParameters {
+                    rid: "h".to_owned(),
+                    ..Default::default()
+                },
+            ],
+        };
+
+        assert!(!receiver.have_received().await);
+        receiver.receive(&parameters).await?;
+        assert!(receiver.have_received().await);
+        {
+            let tracks = receiver.tracks.lock().await;
+            assert_eq!(tracks.len(), 2);
+            assert_eq!(tracks[0].track.rid(), "f");
+            assert_eq!(tracks[1].track.rid(), "h");
+            assert_eq!(tracks[0].track.kind(), RTPCodecType::Video);
+        }
+
+        if let Err(err) = receiver.receive(&parameters).await {
+            assert!(Error::ErrRTPReceiverReceiveAlreadyCalled.equal(&err));
+        } else {
+            panic!("expected error, but got ok");
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rtp_receiver_receive_without_srtp() -> Result<()> {
+        let receiver = RTPReceiver::new(
+            RTPCodecType::Audio,
+            Arc::new(DTLSTransport::default()),
+            Arc::new(MediaEngine::default()),
+            Arc::new(NoOp),
+        );
+
+        let parameters = RTPReceiveParameters {
+            encodings: vec![RTPDecodingParameters {
+                ssrc: 1234,
+                ..Default::default()
+            }],
+        };
+        if let Err(err) = receiver.receive(&parameters).await {
+            assert!(Error::ErrDtlsTransportNotStarted.equal(&err));
+        } else {
+            panic!("expected error, but got ok");
+        }
+
+        receiver.stop().await?;
+
+        let mut b = vec![0u8; RECEIVE_MTU];
+        if let Err(err) = receiver.read(&mut b).await {
+            assert!(Error::ErrRTPReceiverStopped.equal(&err));
+        } else {
+            panic!("expected error, but got ok");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unwrap_rtx() {
+        let mut associated_payload_types = HashMap::new();
+        associated_payload_types.insert(97, 96);
+
+        let rtx = |payload_type: PayloadType, payload: &'static [u8]| rtp::packet::Packet {
+            header: rtp::header::Header {
+                version: 2,
+                ssrc: 2000,
+                payload_type,
+                sequence_number: 500,
+                timestamp: 3000,
+                ..Default::default()
+            },
+            payload: Bytes::from_static(payload),
+        };
+
+        let pkt = unwrap_rtx(
+            rtx(97, &[0x01, 0x02, 0xAA, 0xBB]),
+            1000,
+            &associated_payload_types,
+        )
+        .expect("an unwrapped packet");
+        assert_eq!(pkt.header.ssrc, 1000);
+        assert_eq!(pkt.header.payload_type, 96);
+        assert_eq!(pkt.header.sequence_number, 0x0102);
+        assert_eq!(pkt.header.timestamp, 3000);
+        assert_eq!(&pkt.payload[..], &[0xAA, 0xBB]);
+
+        // the original sequence number is missing
+        assert!(unwrap_rtx(rtx(97, &[0x01]), 1000, &associated_payload_types).is_none());
+        // the payload type isn't a RTX one
+        assert!(unwrap_rtx(
+            rtx(98, &[0x01, 0x02, 0xAA]),
+            1000,
+            &associated_payload_types
+        )
+        .is_none());
+    }
+}
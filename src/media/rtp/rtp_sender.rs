@@ -0,0 +1,903 @@
+use crate::api::media_engine::MediaEngine;
+use crate::error::Error;
+use crate::interceptor::stream_info::StreamInfo;
+use crate::interceptor::{unmarshal_rtcp, Attributes, Interceptor, RTCPReader, RTPWriter};
+use crate::media::dtls_transport::srtp_session::SRTPStream;
+use crate::media::dtls_transport::DTLSTransport;
+use crate::media::rtp::rtp_codec::{
+    find_rtx_payload_type, rtx_associated_payload_type, RTPCodecType, RTPHeaderExtensionCapability,
+};
+use crate::media::rtp::rtp_transceiver_direction::RTPTransceiverDirection;
+use crate::media::rtp::{RTPEncodingParameters, RTPRtxParameters, RTPSendParameters, SSRC};
+use crate::media::track::track_local::{
+    InterceptorToTrackLocalWriter, TrackLocal, TrackLocalContext,
+};
+use crate::util::math_rand_alpha;
+use crate::RECEIVE_MTU;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use bytes::Bytes;
+use rtcp::payload_feedbacks::receiver_estimated_maximum_bitrate::ReceiverEstimatedMaximumBitrate;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+
+pub type OnRembHdlrFn =
+    Box<dyn (FnMut(u64) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>) + Send + Sync>;
+
+/// SRTPWriter writes the packets of a bound TrackLocal to the SRTP session
+/// of the DTLSTransport, it is at the end of the interceptor chain of each
+/// local stream. Packets written before the DTLS handshake has completed are
+/// dropped. The packets of a simulcast layer are stamped with its header
+/// extensions, so the remote can tell the layers apart
+pub(crate) struct SRTPWriter {
+    transport: Arc<DTLSTransport>,
+    header_extensions: Vec<(u8, Bytes)>,
+}
+
+#[async_trait]
+impl RTPWriter for SRTPWriter {
+    async fn write(&self, pkt: &rtp::packet::Packet, _attributes: &Attributes) -> Result<usize> {
+        let srtp_session = match self.transport.get_srtp_session().await {
+            Some(srtp_session) => srtp_session,
+            None => return Ok(0),
+        };
+
+        if self.header_extensions.is_empty() {
+            return srtp_session.write_rtp(pkt).await;
+        }
+
+        let mut pkt = pkt.clone();
+        for (id, payload) in &self.header_extensions {
+            pkt.header.set_extension(*id, payload.clone())?;
+        }
+        Ok(srtp_session.write_rtp(&pkt).await?)
+    }
+}
+
+/// TrackEncoding is a single layer of the media sent by a RTPSender. Each
+/// layer has its own track and SSRC, and is identified by the rid of its track.
+/// The retransmissions of the layer are sent with rtx_ssrc when RTX is negotiated,
+/// which is only done for a layer without a rid, see has_rid_layers
+struct TrackEncoding {
+    track: Option<Arc<dyn TrackLocal + Send + Sync>>,
+    rid: String,
+    ssrc: SSRC,
+    rtx_ssrc: SSRC,
+    context: TrackLocalContext,
+    stream_info: Option<StreamInfo>,
+}
+
+/// has_rid_layers tells if the layers are identified by their rid. Their SSRCs
+/// aren't declared in the SDP, so the remote tells them apart by the rid header
+/// extension of their packets. Their retransmissions are not sent: a RTX stream
+/// can't be declared with `a=ssrc-group:FID` either, and telling it apart would
+/// take the repaired-rtp-stream-id header extension, which isn't supported.
+fn has_rid_layers(track_encodings: &[TrackEncoding]) -> bool {
+    track_encodings.iter().any(|e| !e.rid.is_empty())
+}
+
+/// RTPSender allows an application to control how a given Track is encoded and transmitted to a remote peer
+pub struct RTPSender {
+    track_encodings: Mutex<Vec<TrackEncoding>>,
+    srtp_stream: Arc<SRTPWriter>,
+    mid: Mutex<String>,
+
+    pub(crate) id: String,
+    pub(crate) kind: RTPCodecType,
+    pub(crate) transport: Arc<DTLSTransport>,
+    pub(crate) media_engine: Arc<MediaEngine>,
+    pub(crate) interceptor: Arc<dyn Interceptor + Send + Sync>,
+
+    pub(crate) ssrc: SSRC,
+    pub(crate) payload_type: AtomicU8,
+    negotiated: AtomicBool,
+
+    rtcp_read_stream: Mutex<Option<Arc<SRTPStream>>>,
+    rtcp_interceptor: Mutex<Option<Arc<dyn RTCPReader + Send + Sync>>>,
+    on_remb_handler: Arc<Mutex<Option<OnRembHdlrFn>>>,
+
+    // the sender side of each pair is dropped to signal the event
+    send_called_tx: Mutex<Option<mpsc::Sender<()>>>,
+    send_called_rx: Mutex<mpsc::Receiver<()>>,
+    stop_called_tx: Mutex<Option<mpsc::Sender<()>>>,
+    stop_called_rx: Mutex<mpsc::Receiver<()>>,
+}
+
+impl RTPSender {
+    /// new creates a RTPSender of the given track, which sends over the given
+    /// DTLSTransport with a random SSRC through the given interceptor
+    pub fn new(
+        track: Arc<dyn TrackLocal + Send + Sync>,
+        transport: Arc<DTLSTransport>,
+        media_engine: Arc<MediaEngine>,
+        interceptor: Arc<dyn Interceptor + Send + Sync>,
+    ) -> Self {
+        let (send_called_tx, send_called_rx) = mpsc::channel(1);
+        let (stop_called_tx, stop_called_rx) = mpsc::channel(1);
+        let ssrc = rand::random::<u32>();
+
+        RTPSender {
+            kind: track.kind(),
+            track_encodings: Mutex::new(vec![TrackEncoding {
+                rid: track.rid().to_owned(),
+                track: Some(track),
+                ssrc,
+                rtx_ssrc: rand::random::<u32>(),
+                context: TrackLocalContext::default(),
+                stream_info: None,
+            }]),
+            srtp_stream: Arc::new(SRTPWriter {
+                transport: Arc::clone(&transport),
+                header_extensions: vec![],
+            }),
+            mid: Mutex::new(String::new()),
+
+            id: math_rand_alpha(32),
+            transport,
+            media_engine,
+            interceptor,
+
+            ssrc,
+            payload_type: AtomicU8::new(0),
+            negotiated: AtomicBool::new(false),
+
+            rtcp_read_stream: Mutex::new(None),
+            rtcp_interceptor: Mutex::new(None),
+            on_remb_handler: Arc::new(Mutex::new(None)),
+
+            send_called_tx: Mutex::new(Some(send_called_tx)),
+            send_called_rx: Mutex::new(send_called_rx),
+            stop_called_tx: Mutex::new(Some(stop_called_tx)),
+            stop_called_rx: Mutex::new(stop_called_rx),
+        }
+    }
+
+    pub(crate) fn is_negotiated(&self) -> bool {
+        self.negotiated.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn set_negotiated(&self) {
+        self.negotiated.store(true, Ordering::SeqCst);
+    }
+
+    /// set_mid sets the mid of the transceiver of this RTPSender, which is
+    /// stamped on the packets of its simulcast layers
+    pub(crate) async fn set_mid(&self, mid: String) {
+        let mut m = self.mid.lock().await;
+        *m = mid;
+    }
+
+    /// kind returns the kind of media this RTPSender sends
+    pub fn kind(&self) -> RTPCodecType {
+        self.kind
+    }
+
+    /// transport returns the currently-configured DTLSTransport
+    /// if one has not yet been configured
+    pub fn transport(&self) -> Arc<DTLSTransport> {
+        Arc::clone(&self.transport)
+    }
+
+    /// track returns the RTPTransceiver track, or None. With simulcast
+    /// this is the track of the base encoding
+    pub async fn track(&self) -> Option<Arc<dyn TrackLocal + Send + Sync>> {
+        let track_encodings = self.track_encodings.lock().await;
+        track_encodings.first().and_then(|e| e.track.clone())
+    }
+
+    /// add_encoding adds a simulcast layer to the RTPSender, which is sent with
+    /// its own SSRC. The track of the layer must have a rid, and must have the
+    /// same id, stream_id and kind as the track of the base encoding.
+    pub async fn add_encoding(&self, track: Arc<dyn TrackLocal + Send + Sync>) -> Result<()> {
+        if track.rid().is_empty() {
+            return Err(Error::ErrRTPSenderRidNil.into());
+        }
+
+        // locked in the same order as in send
+        let send_called_tx = self.send_called_tx.lock().await;
+        if send_called_tx.is_none() {
+            return Err(Error::ErrRTPSenderSendAlreadyCalled.into());
+        }
+        if self.is_stopped().await {
+            return Err(Error::ErrRTPSenderStopped.into());
+        }
+
+        let mut track_encodings = self.track_encodings.lock().await;
+        let base_track = match track_encodings.first() {
+            Some(TrackEncoding {
+                track: Some(base_track),
+                rid,
+                ..
+            }) if !rid.is_empty() => base_track,
+            _ => return Err(Error::ErrRTPSenderNoBaseEncoding.into()),
+        };
+        if base_track.id() != track.id()
+            || base_track.stream_id() != track.stream_id()
+            || base_track.kind() != track.kind()
+        {
+            return Err(Error::ErrRTPSenderBaseEncodingMismatch.into());
+        }
+        if track_encodings.iter().any(|e| e.rid == track.rid()) {
+            return Err(Error::ErrRTPSenderRIDCollision.into());
+        }
+
+        track_encodings.push(TrackEncoding {
+            rid: track.rid().to_owned(),
+            track: Some(track),
+            ssrc: rand::random::<u32>(),
+            rtx_ssrc: rand::random::<u32>(),
+            context: TrackLocalContext::default(),
+            stream_info: None,
+        });
+
+        Ok(())
+    }
+
+    /// replace_track replaces the track currently being used as the sender's source with a new TrackLocal.
+    /// The new track must be of the same media kind (audio, video, etc) and switching the track should not
+    /// require negotiation. With simulcast the new track replaces the layer of its rid, and None
+    /// replaces the tracks of all the layers.
+    pub async fn replace_track(
+        &self,
+        track: Option<Arc<dyn TrackLocal + Send + Sync>>,
+    ) -> Result<()> {
+        if let Some(t) = &track {
+            if t.kind() != self.kind {
+                return Err(Error::ErrRTPSenderNewTrackHasIncorrectKind.into());
+            }
+        }
+
+        // locked in the same order as in send
+        let send_called_tx = self.send_called_tx.lock().await;
+        let has_sent = send_called_tx.is_none();
+        let mut track_encodings = self.track_encodings.lock().await;
+
+        let encodings = match &track {
+            Some(t) if track_encodings.len() > 1 => {
+                match track_encodings.iter_mut().find(|e| e.rid == t.rid()) {
+                    Some(e) => vec![e],
+                    None => return Err(Error::ErrRTPSenderNoTrackForRID.into()),
+                }
+            }
+            _ => track_encodings.iter_mut().collect(),
+        };
+
+        for encoding in encodings {
+            if has_sent {
+                if let Some(t) = &encoding.track {
+                    t.unbind(&encoding.context).await?;
+                }
+
+                if let Some(t) = &track {
+                    if let Err(err) = t.bind(&encoding.context).await {
+                        // Re-bind the original track
+                        if let Some(t) = &encoding.track {
+                            t.bind(&encoding.context).await?;
+                        }
+                        return Err(err);
+                    }
+                }
+            }
+
+            encoding.track = track.clone();
+        }
+
+        Ok(())
+    }
+
+    /// get_parameters describes the current configuration for the encoding and
+    /// transmission of media on the sender's track, with one encoding per
+    /// simulcast layer. The encoding has a RTX SSRC when a RTX codec is
+    /// available, unless the layers are identified by their rid, in which
+    /// case no retransmissions are sent.
+    pub async fn get_parameters(&self) -> RTPSendParameters {
+        let rtp_parameters = self
+            .media_engine
+            .get_rtp_parameters_by_kind(self.kind, &[RTPTransceiverDirection::Sendonly])
+            .await;
+        let encodings = {
+            let track_encodings = self.track_encodings.lock().await;
+            let rtx_enabled = !has_rid_layers(&track_encodings)
+                && rtp_parameters
+                    .codecs
+                    .iter()
+                    .any(|c| rtx_associated_payload_type(c).is_some());
+            track_encodings
+                .iter()
+                .map(|e| RTPEncodingParameters {
+                    rid: e.rid.clone(),
+                    ssrc: e.ssrc,
+                    payload_type: self.payload_type.load(Ordering::SeqCst),
+                    rtx: RTPRtxParameters {
+                        ssrc: if rtx_enabled { e.rtx_ssrc } else { 0 },
+                    },
+                })
+                .collect()
+        };
+
+        RTPSendParameters {
+            rtp_parameters,
+            encodings,
+        }
+    }
+
+    /// send Attempts to set the parameters controlling the sending of media.
+    pub async fn send(&self, parameters: &RTPSendParameters) -> Result<()> {
+        let mut send_called_tx = self.send_called_tx.lock().await;
+        if send_called_tx.is_none() {
+            return Err(Error::ErrRTPSenderSendAlreadyCalled.into());
+        }
+        if self.is_stopped().await {
+            return Err(Error::ErrRTPSenderStopped.into());
+        }
+
+        let mut track_encodings = self.track_encodings.lock().await;
+        // the packets of layers with a rid carry it even when it is the only
+        // layer, and their retransmissions are not sent
+        let is_simulcast = has_rid_layers(&track_encodings);
+        for encoding in &mut *track_encodings {
+            let track = match &encoding.track {
+                Some(track) => track,
+                None => return Err(Error::ErrRTPSenderTrackNil.into()),
+            };
+
+            let srtp_writer = if is_simulcast {
+                Arc::new(SRTPWriter {
+                    transport: Arc::clone(&self.transport),
+                    header_extensions: self.simulcast_header_extensions(&encoding.rid).await,
+                })
+            } else {
+                Arc::clone(&self.srtp_stream)
+            };
+            let write_stream = Arc::new(InterceptorToTrackLocalWriter::default());
+
+            let mut context = TrackLocalContext {
+                id: self.id.clone(),
+                params: self
+                    .media_engine
+                    .get_rtp_parameters_by_kind(track.kind(), &[RTPTransceiverDirection::Sendonly])
+                    .await,
+                ssrc: parameters
+                    .encodings
+                    .iter()
+                    .find(|e| e.rid == encoding.rid)
+                    .map_or(encoding.ssrc, |e| e.ssrc),
+                write_stream: Some(Arc::clone(&write_stream) as _),
+            };
+
+            let codec = track.bind(&context).await?;
+            self.payload_type
+                .store(codec.payload_type, Ordering::SeqCst);
+
+            // the packets of the track flow through the interceptors once
+            // the negotiated codec of the stream is known
+            let mut stream_info = StreamInfo::new(
+                self.id.clone(),
+                context.ssrc,
+                codec.payload_type,
+                &codec.capability,
+                &context.params.header_extensions,
+            );
+            if !is_simulcast {
+                if let Some(rtx_payload_type) =
+                    find_rtx_payload_type(codec.payload_type, &context.params.codecs)
+                {
+                    stream_info.ssrc_retransmission = parameters
+                        .encodings
+                        .iter()
+                        .find(|e| e.rid == encoding.rid && e.rtx.ssrc != 0)
+                        .map_or(encoding.rtx_ssrc, |e| e.rtx.ssrc);
+                    stream_info.payload_type_retransmission = rtx_payload_type;
+                }
+            }
+            let rtp_writer = self
+                .interceptor
+                .bind_local_stream(&stream_info, srtp_writer as _)
+                .await;
+            write_stream.set_rtp_writer(rtp_writer).await;
+
+            context.params.codecs = vec![codec];
+            encoding.context = context;
+            encoding.stream_info = Some(stream_info);
+        }
+
+        send_called_tx.take();
+        Ok(())
+    }
+
+    /// simulcast_header_extensions returns the mid and rid header extensions
+    /// which are stamped on the packets of the simulcast layer of the given rid,
+    /// the extensions which haven't been negotiated are left out
+    async fn simulcast_header_extensions(&self, rid: &str) -> Vec<(u8, Bytes)> {
+        let mid = {
+            let mid = self.mid.lock().await;
+            mid.clone()
+        };
+
+        let mut header_extensions = vec![];
+        for (uri, value) in &[
+            (sdp::extmap::SDES_MID_URI, mid),
+            (sdp::extmap::SDES_RTP_STREAM_ID_URI, rid.to_owned()),
+        ] {
+            let (id, _, _) = self
+                .media_engine
+                .get_header_extension_id(RTPHeaderExtensionCapability {
+                    uri: (*uri).to_owned(),
+                })
+                .await;
+            if id != 0 && !value.is_empty() {
+                header_extensions.push((id as u8, Bytes::from(value.clone())));
+            }
+        }
+
+        header_extensions
+    }
+
+    /// stop irreversibly stops the RTPSender
+    pub async fn stop(&self) -> Result<()> {
+        {
+            let mut stop_called_tx = self.stop_called_tx.lock().await;
+            if stop_called_tx.take().is_none() {
+                return Ok(());
+            }
+        }
+
+        if self.has_sent().await {
+            self.replace_track(None).await?;
+        }
+
+        let stream_infos: Vec<StreamInfo> = {
+            let track_encodings = self.track_encodings.lock().await;
+            track_encodings
+                .iter()
+                .filter_map(|e| e.stream_info.clone())
+                .collect()
+        };
+        for stream_info in &stream_infos {
+            self.interceptor.unbind_local_stream(stream_info).await;
+        }
+
+        let rtcp_read_stream = {
+            let mut rtcp_read_stream = self.rtcp_read_stream.lock().await;
+            rtcp_read_stream.take()
+        };
+        if let Some(rtcp_read_stream) = rtcp_read_stream {
+            rtcp_read_stream.close().await?;
+        }
+
+        Ok(())
+    }
+
+    /// on_remb sets a handler that is fired with the bitrate of every REMB packet the
+    /// remote peer sends about the streams of this RTPSender. The REMB packets are
+    /// found in the RTCP read by read and read_rtcp, so the handler is only fired
+    /// while the application keeps reading the RTCP of the RTPSender.
+    pub async fn on_remb(&self, f: OnRembHdlrFn) {
+        let mut on_remb_handler = self.on_remb_handler.lock().await;
+        *on_remb_handler = Some(f);
+    }
+
+    /// handle_remb fires the on_remb handler for the REMB packets of a batch of
+    /// RTCP read for this RTPSender
+    async fn handle_remb(&self, raw: &[u8]) {
+        {
+            let handler = self.on_remb_handler.lock().await;
+            if handler.is_none() {
+                return;
+            }
+        }
+
+        let pkts = match unmarshal_rtcp(raw) {
+            Ok(pkts) => pkts,
+            Err(_) => return,
+        };
+        let ssrcs: Vec<SSRC> = {
+            let track_encodings = self.track_encodings.lock().await;
+            track_encodings.iter().map(|e| e.ssrc).collect()
+        };
+        for p in &pkts {
+            let remb = match p.as_any().downcast_ref::<ReceiverEstimatedMaximumBitrate>() {
+                Some(remb) if remb.ssrcs.iter().any(|ssrc| ssrcs.contains(ssrc)) => remb,
+                _ => continue,
+            };
+
+            // the handler future is awaited after the lock is released, so the
+            // handler is free to use the RTPSender
+            let fut = {
+                let mut handler = self.on_remb_handler.lock().await;
+                handler.as_mut().map(|f| f(remb.bitrate))
+            };
+            if let Some(fut) = fut {
+                fut.await;
+            }
+        }
+    }
+
+    /// read reads incoming RTCP for this RTPSender
+    pub async fn read(&self, b: &mut [u8]) -> Result<usize> {
+        if self.is_stopped().await {
+            return Err(Error::ErrRTPSenderStopped.into());
+        }
+
+        let mut stop_called_rx = self.stop_called_rx.lock().await;
+        tokio::select! {
+            _ = stop_called_rx.recv() => Err(Error::ErrRTPSenderStopped.into()),
+            result = self.read_rtcp_stream(b) => result,
+        }
+    }
+
+    /// read_rtcp is a convenience method that wraps read and unmarshals for you
+    pub async fn read_rtcp(&self) -> Result<Vec<Box<dyn rtcp::packet::Packet + Send + Sync>>> {
+        let mut b = vec![0u8; RECEIVE_MTU];
+        let n = self.read(&mut b).await?;
+
+        unmarshal_rtcp(&b[..n])
+    }
+
+    /// write_rtp sends a packet over the SRTP session, packets written before
+    /// the DTLS handshake has completed are dropped
+    pub(crate) async fn write_rtp(&self, pkt: &rtp::packet::Packet) -> Result<usize> {
+        if self.is_stopped().await {
+            return Err(Error::ErrRTPSenderStopped.into());
+        }
+
+        self.srtp_stream.write(pkt, &Attributes::new()).await
+    }
+
+    /// has_sent tells if data has been ever sent for this instance
+    pub(crate) async fn has_sent(&self) -> bool {
+        let send_called_tx = self.send_called_tx.lock().await;
+        send_called_tx.is_none()
+    }
+
+    async fn is_stopped(&self) -> bool {
+        let stop_called_tx = self.stop_called_tx.lock().await;
+        stop_called_tx.is_none()
+    }
+
+    /// read_rtcp_stream waits for send to be called and for the SRTCP session
+    /// to be ready before reading from the RTCP stream of our SSRC
+    async fn read_rtcp_stream(&self, b: &mut [u8]) -> Result<usize> {
+        {
+            let mut send_called_rx = self.send_called_rx.lock().await;
+            send_called_rx.recv().await;
+        }
+
+        let rtcp_interceptor = {
+            let mut rtcp_read_stream = self.rtcp_read_stream.lock().await;
+            let mut rtcp_interceptor = self.rtcp_interceptor.lock().await;
+            if rtcp_read_stream.is_none() {
+                self.transport.wait_srtp_ready().await;
+
+                let srtcp_session = match self.transport.get_srtcp_session().await {
+                    Some(srtcp_session) => srtcp_session,
+                    None => return Err(Error::ErrDtlsTransportNotStarted.into()),
+                };
+                let stream = srtcp_session.open(self.ssrc).await;
+                *rtcp_interceptor = Some(
+                    self.interceptor
+                        .bind_rtcp_reader(Arc::clone(&stream) as _)
+                        .await,
+                );
+                *rtcp_read_stream = Some(stream);
+            }
+            rtcp_interceptor.clone()
+        };
+
+        match rtcp_interceptor {
+            Some(rtcp_interceptor) => {
+                let (n, _) = rtcp_interceptor.read(b, &Attributes::new()).await?;
+                self.handle_remb(&b[..n]).await;
+                Ok(n)
+            }
+            None => Err(Error::ErrRTPSenderStopped.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::api::media_engine::{MIME_TYPE_OPUS, MIME_TYPE_VP8};
+    use crate::interceptor::noop::NoOp;
+    use crate::media::rtp::rtp_codec::RTPCodecCapability;
+    use crate::media::track::track_local::track_local_static_sample::TrackLocalStaticSample;
+    use util::marshal::Marshal;
+
+    fn new_track(mime_type: &str, id: &str) -> Arc<dyn TrackLocal + Send + Sync> {
+        Arc::new(TrackLocalStaticSample::new(
+            RTPCodecCapability {
+                mime_type: mime_type.to_owned(),
+                ..Default::default()
+            },
+            id.to_owned(),
+            "webrtc-rs".to_owned(),
+        ))
+    }
+
+    fn new_track_with_rid(id: &str, rid: &str) -> Arc<dyn TrackLocal + Send + Sync> {
+        Arc::new(TrackLocalStaticSample::new_with_rid(
+            RTPCodecCapability {
+                mime_type: MIME_TYPE_VP8.to_owned(),
+                ..Default::default()
+            },
+            id.to_owned(),
+            rid.to_owned(),
+            "webrtc-rs".to_owned(),
+        ))
+    }
+
+    fn new_media_engine() -> Result<Arc<MediaEngine>> {
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+        Ok(Arc::new(m))
+    }
+
+    #[tokio::test]
+    async fn test_rtp_sender_send_already_called() -> Result<()> {
+        let sender = RTPSender::new(
+            new_track(MIME_TYPE_VP8, "video"),
+            Arc::new(DTLSTransport::default()),
+            new_media_engine()?,
+            Arc::new(NoOp),
+        );
+        assert_eq!(sender.kind(), RTPCodecType::Video);
+
+        let parameters = sender.get_parameters().await;
+        assert_eq!(parameters.encodings.len(), 1);
+        assert_eq!(parameters.encodings[0].ssrc, sender.ssrc);
+
+        assert!(!sender.has_sent().await);
+        sender.send(&parameters).await?;
+        assert!(sender.has_sent().await);
+        assert_eq!(sender.get_parameters().await.encodings[0].payload_type, 96);
+
+        if let Err(err) = sender.send(&parameters).await {
+            assert!(Error::ErrRTPSenderSendAlreadyCalled.equal(&err));
+        } else {
+            panic!("expected error, but got ok");
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rtp_sender_replace_track() -> Result<()> {
+        let sender = RTPSender::new(
+            new_track(MIME_TYPE_VP8, "video"),
+            Arc::new(DTLSTransport::default()),
+            new_media_engine()?,
+            Arc::new(NoOp),
+        );
+
+        if let Err(err) = sender
+            .replace_track(Some(new_track(MIME_TYPE_OPUS, "audio")))
+            .await
+        {
+            assert!(Error::ErrRTPSenderNewTrackHasIncorrectKind.equal(&err));
+        } else {
+            panic!("expected error, but got ok");
+        }
+
+        sender.send(&sender.get_parameters().await).await?;
+
+        sender
+            .replace_track(Some(new_track(MIME_TYPE_VP8, "screen")))
+            .await?;
+        assert_eq!(sender.track().await.unwrap().id(), "screen");
+
+        sender.replace_track(None).await?;
+        assert!(sender.track().await.is_none());
+
+        sender.stop().await
+    }
+
+    #[tokio::test]
+    async fn test_rtp_sender_read_after_stop() -> Result<()> {
+        let sender = RTPSender::new(
+            new_track(MIME_TYPE_OPUS, "audio"),
+            Arc::new(DTLSTransport::default()),
+            new_media_engine()?,
+            Arc::new(NoOp),
+        );
+
+        sender.stop().await?;
+        // stopping twice is a no-op
+        sender.stop().await?;
+
+        let mut b = vec![0u8; RECEIVE_MTU];
+        if let Err(err) = sender.read(&mut b).await {
+            assert!(Error::ErrRTPSenderStopped.equal(&err));
+        } else {
+            panic!("expected error, but got ok");
+        }
+
+        if let Err(err) = sender.send(&sender.get_parameters().await).await {
+            assert!(Error::ErrRTPSenderStopped.equal(&err));
+        } else {
+            panic!("expected error, but got ok");
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rtp_sender_on_remb() -> Result<()> {
+        let sender = RTPSender::new(
+            new_track(MIME_TYPE_VP8, "video"),
+            Arc::new(DTLSTransport::default()),
+            new_media_engine()?,
+            Arc::new(NoOp),
+        );
+
+        let (remb_tx, mut remb_rx) = mpsc::channel(10);
+        sender
+            .on_remb(Box::new(move |bitrate: u64| {
+                let remb_tx2 = remb_tx.clone();
+                Box::pin(async move {
+                    let _ = remb_tx2.send(bitrate).await;
+                })
+            }))
+            .await;
+
+        // only the REMB packets about the streams of the sender are surfaced
+        for (bitrate, ssrcs) in &[
+            (100_000u64, vec![sender.ssrc.wrapping_add(1)]),
+            (200_000, vec![sender.ssrc.wrapping_add(1), sender.ssrc]),
+        ] {
+            let remb = ReceiverEstimatedMaximumBitrate {
+                sender_ssrc: 1,
+                bitrate: *bitrate,
+                ssrcs: ssrcs.clone(),
+            };
+            sender.handle_remb(&remb.marshal()?).await;
+        }
+
+        let timeout = std::time::Duration::from_millis(10);
+        assert_eq!(
+            tokio::time::timeout(timeout, remb_rx.recv()).await?,
+            Some(200_000)
+        );
+        assert!(tokio::time::timeout(timeout, remb_rx.recv()).await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rtp_sender_add_encoding() -> Result<()> {
+        // the base encoding of a simulcast sender must have a rid
+        let sender = RTPSender::new(
+            new_track(MIME_TYPE_VP8, "video"),
+            Arc::new(DTLSTransport::default()),
+            new_media_engine()?,
+            Arc::new(NoOp),
+        );
+        if let Err(err) = sender.add_encoding(new_track(MIME_TYPE_VP8, "video")).await {
+            assert!(Error::ErrRTPSenderRidNil.equal(&err));
+        } else {
+            panic!("expected error, but got ok");
+        }
+        if let Err(err) = sender.add_encoding(new_track_with_rid("video", "h")).await {
+            assert!(Error::ErrRTPSenderNoBaseEncoding.equal(&err));
+        } else {
+            panic!("expected error, but got ok");
+        }
+
+        let sender = RTPSender::new(
+            new_track_with_rid("video", "f"),
+            Arc::new(DTLSTransport::default()),
+            new_media_engine()?,
+            Arc::new(NoOp),
+        );
+        if let Err(err) = sender.add_encoding(new_track_with_rid("screen", "h")).await {
+            assert!(Error::ErrRTPSenderBaseEncodingMismatch.equal(&err));
+        } else {
+            panic!("expected error, but got ok");
+        }
+        if let Err(err) = sender.add_encoding(new_track_with_rid("video", "f")).await {
+            assert!(Error::ErrRTPSenderRIDCollision.equal(&err));
+        } else {
+            panic!("expected error, but got ok");
+        }
+
+        sender
+            .add_encoding(new_track_with_rid("video", "h"))
+            .await?;
+        sender
+            .add_encoding(new_track_with_rid("video", "q"))
+            .await?;
+
+        let parameters = sender.get_parameters().await;
+        let rids: Vec<&str> = parameters
+            .encodings
+            .iter()
+            .map(|e| e.rid.as_str())
+            .collect();
+        assert_eq!(rids, vec!["f", "h", "q"]);
+        assert_eq!(parameters.encodings[0].ssrc, sender.ssrc);
+        assert_ne!(parameters.encodings[0].ssrc, parameters.encodings[1].ssrc);
+        assert_ne!(parameters.encodings[1].ssrc, parameters.encodings[2].ssrc);
+
+        // a layer is replaced by the track of its rid
+        sender.send(&parameters).await?;
+        if let Err(err) = sender
+            .replace_track(Some(new_track_with_rid("video", "x")))
+            .await
+        {
+            assert!(Error::ErrRTPSenderNoTrackForRID.equal(&err));
+        } else {
+            panic!("expected error, but got ok");
+        }
+        sender
+            .replace_track(Some(new_track_with_rid("video", "h")))
+            .await?;
+
+        if let Err(err) = sender.add_encoding(new_track_with_rid("video", "l")).await {
+            assert!(Error::ErrRTPSenderSendAlreadyCalled.equal(&err));
+        } else {
+            panic!("expected error, but got ok");
+        }
+
+        sender.stop().await
+    }
+
+    #[tokio::test]
+    async fn test_rtp_sender_rtx_of_layers() -> Result<()> {
+        // the retransmissions of a layer without a rid are sent on its RTX stream
+        let sender = RTPSender::new(
+            new_track(MIME_TYPE_VP8, "video"),
+            Arc::new(DTLSTransport::default()),
+            new_media_engine()?,
+            Arc::new(NoOp),
+        );
+        let parameters = sender.get_parameters().await;
+        assert_eq!(parameters.encodings.len(), 1);
+        let rtx_ssrc = parameters.encodings[0].rtx.ssrc;
+        assert_ne!(rtx_ssrc, 0);
+
+        sender.send(&parameters).await?;
+        {
+            let track_encodings = sender.track_encodings.lock().await;
+            let stream_info = track_encodings[0].stream_info.as_ref().expect("bound");
+            assert_eq!(stream_info.ssrc_retransmission, rtx_ssrc);
+            assert_ne!(stream_info.payload_type_retransmission, 0);
+        }
+        sender.stop().await?;
+
+        // the layers with a rid, even a single one, don't send retransmissions
+        for rids in &[vec!["f"], vec!["f", "h"]] {
+            let sender = RTPSender::new(
+                new_track_with_rid("video", rids[0]),
+                Arc::new(DTLSTransport::default()),
+                new_media_engine()?,
+                Arc::new(NoOp),
+            );
+            for rid in &rids[1..] {
+                sender
+                    .add_encoding(new_track_with_rid("video", rid))
+                    .await?;
+            }
+
+            let parameters = sender.get_parameters().await;
+            assert_eq!(parameters.encodings.len(), rids.len());
+            assert!(parameters.encodings.iter().all(|e| e.rtx.ssrc == 0));
+
+            sender.send(&parameters).await?;
+            {
+                let track_encodings = sender.track_encodings.lock().await;
+                for encoding in &*track_encodings {
+                    let stream_info = encoding.stream_info.as_ref().expect("bound");
+                    assert_eq!(stream_info.ssrc_retransmission, 0);
+                    assert_eq!(stream_info.payload_type_retransmission, 0);
+                }
+            }
+            sender.stop().await?;
+        }
+
+        Ok(())
+    }
+}
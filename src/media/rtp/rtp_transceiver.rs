@@ -0,0 +1,532 @@
+use crate::api::media_engine::MediaEngine;
+use crate::error::Error;
+use crate::media::rtp::rtp_codec::{
+    codec_parameters_fuzzy_search, CodecMatchType, RTPCodecParameters, RTPCodecType,
+};
+use crate::media::rtp::rtp_receiver::RTPReceiver;
+use crate::media::rtp::rtp_sender::RTPSender;
+use crate::media::rtp::rtp_transceiver_direction::RTPTransceiverDirection;
+use crate::media::rtp::PayloadType;
+use crate::media::track::track_local::TrackLocal;
+
+use anyhow::Result;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use util::marshal::Unmarshal;
+
+/// RTPTransceiver represents a combination of an RTPSender and an RTPReceiver that share a common mid.
+pub struct RTPTransceiver {
+    mid: Mutex<String>,
+    sender: Mutex<Option<Arc<RTPSender>>>,
+    receiver: Mutex<Option<Arc<RTPReceiver>>>,
+    direction: AtomicU8,         //RTPTransceiverDirection,
+    current_direction: AtomicU8, //RTPTransceiverDirection,
+
+    codecs: Mutex<Vec<RTPCodecParameters>>, // User provided codecs via set_codec_preferences
+
+    pub(crate) stopped: AtomicBool,
+    pub(crate) kind: RTPCodecType,
+
+    media_engine: Arc<MediaEngine>,
+}
+
+impl RTPTransceiver {
+    pub(crate) fn new(
+        receiver: Option<Arc<RTPReceiver>>,
+        sender: Option<Arc<RTPSender>>,
+        direction: RTPTransceiverDirection,
+        kind: RTPCodecType,
+        codecs: Vec<RTPCodecParameters>,
+        media_engine: Arc<MediaEngine>,
+    ) -> Arc<Self> {
+        Arc::new(RTPTransceiver {
+            mid: Mutex::new(String::new()),
+            sender: Mutex::new(sender),
+            receiver: Mutex::new(receiver),
+            direction: AtomicU8::new(direction as u8),
+            current_direction: AtomicU8::new(RTPTransceiverDirection::Unspecified as u8),
+
+            codecs: Mutex::new(codecs),
+
+            stopped: AtomicBool::new(false),
+            kind,
+
+            media_engine,
+        })
+    }
+
+    /// set_codec_preferences sets preferred list of supported codecs
+    /// if codecs is empty or nil we reset to default from MediaEngine
+    pub async fn set_codec_preferences(&self, codecs: Vec<RTPCodecParameters>) -> Result<()> {
+        let media_engine_codecs = self.media_engine.get_codecs_by_kind(self.kind).await;
+        for codec in &codecs {
+            let (_, match_type) = codec_parameters_fuzzy_search(codec, &media_engine_codecs);
+            if match_type == CodecMatchType::None {
+                return Err(Error::ErrRTPTransceiverCodecUnsupported.into());
+            }
+        }
+
+        let mut c = self.codecs.lock().await;
+        *c = codecs;
+        Ok(())
+    }
+
+    /// get_codecs returns list of supported codecs
+    pub(crate) async fn get_codecs(&self) -> Vec<RTPCodecParameters> {
+        let media_engine_codecs = self.media_engine.get_codecs_by_kind(self.kind).await;
+
+        let codecs = self.codecs.lock().await;
+        if codecs.is_empty() {
+            return media_engine_codecs;
+        }
+
+        let mut filtered_codecs = vec![];
+        for codec in &*codecs {
+            let (c, match_type) = codec_parameters_fuzzy_search(codec, &media_engine_codecs);
+            if match_type != CodecMatchType::None {
+                let mut codec = codec.clone();
+                if codec.payload_type == 0 {
+                    codec.payload_type = c.payload_type;
+                }
+                filtered_codecs.push(codec);
+            }
+        }
+
+        filtered_codecs
+    }
+
+    /// sender returns the RTPTransceiver's RTPSender if it has one
+    pub async fn sender(&self) -> Option<Arc<RTPSender>> {
+        let sender = self.sender.lock().await;
+        sender.clone()
+    }
+
+    /// set_sender sets the RTPSender and Track to current transceiver
+    pub(crate) async fn set_sender(&self, sender: Option<Arc<RTPSender>>) {
+        let mut s = self.sender.lock().await;
+        *s = sender;
+    }
+
+    /// set_sending_track replaces the track of the RTPSender, and updates the
+    /// direction of the RTPTransceiver to tell whether it still sends
+    pub(crate) async fn set_sending_track(
+        &self,
+        track: Option<Arc<dyn TrackLocal + Send + Sync>>,
+    ) -> Result<()> {
+        let has_track = track.is_some();
+        if let Some(sender) = self.sender().await {
+            sender.replace_track(track).await?;
+        }
+        if !has_track {
+            self.set_sender(None).await;
+        }
+
+        let direction = self.direction();
+        if has_track && direction == RTPTransceiverDirection::Recvonly {
+            self.set_direction(RTPTransceiverDirection::Sendrecv);
+        } else if has_track && direction == RTPTransceiverDirection::Inactive {
+            self.set_direction(RTPTransceiverDirection::Sendonly);
+        } else if has_track
+            && (direction == RTPTransceiverDirection::Sendonly
+                || direction == RTPTransceiverDirection::Sendrecv)
+        {
+            // a transceiver created for a remote offer which asked to receive
+            // already has the direction to send the track with
+        } else if !has_track && direction == RTPTransceiverDirection::Sendrecv {
+            self.set_direction(RTPTransceiverDirection::Recvonly);
+        } else if !has_track && direction == RTPTransceiverDirection::Sendonly {
+            self.set_direction(RTPTransceiverDirection::Inactive);
+        } else {
+            return Err(Error::ErrRTPTransceiverSetSendingInvalidState.into());
+        }
+
+        Ok(())
+    }
+
+    /// receiver returns the RTPTransceiver's RTPReceiver if it has one
+    pub async fn receiver(&self) -> Option<Arc<RTPReceiver>> {
+        let receiver = self.receiver.lock().await;
+        receiver.clone()
+    }
+
+    pub(crate) async fn set_receiver(&self, receiver: Option<Arc<RTPReceiver>>) {
+        let mut r = self.receiver.lock().await;
+        *r = receiver;
+    }
+
+    /// set_mid sets the RTPTransceiver's mid. If it was already set, will return an error.
+    pub(crate) async fn set_mid(&self, mid: String) -> Result<()> {
+        let mut m = self.mid.lock().await;
+        if !m.is_empty() {
+            return Err(Error::ErrRTPTransceiverCannotChangeMid.into());
+        }
+        *m = mid;
+        Ok(())
+    }
+
+    /// restore_mid puts back the mid the RTPTransceiver had before a rolled back
+    /// negotiation assigned it one, which is the only time a mid may change
+    pub(crate) async fn restore_mid(&self, mid: String) {
+        let mut m = self.mid.lock().await;
+        *m = mid;
+    }
+
+    /// mid gets the Transceiver's mid value. When not already set, this value will be set in CreateOffer or CreateAnswer.
+    pub async fn mid(&self) -> String {
+        let mid = self.mid.lock().await;
+        mid.clone()
+    }
+
+    /// kind returns RTPTransceiver's kind.
+    pub fn kind(&self) -> RTPCodecType {
+        self.kind
+    }
+
+    /// direction returns the RTPTransceiver's current direction
+    pub fn direction(&self) -> RTPTransceiverDirection {
+        self.direction.load(Ordering::SeqCst).into()
+    }
+
+    pub(crate) fn set_direction(&self, d: RTPTransceiverDirection) {
+        self.direction.store(d as u8, Ordering::SeqCst);
+    }
+
+    /// current_direction returns the direction negotiated for the RTPTransceiver
+    /// the last time an offer and an answer were exchanged
+    pub fn current_direction(&self) -> RTPTransceiverDirection {
+        self.current_direction.load(Ordering::SeqCst).into()
+    }
+
+    pub(crate) fn set_current_direction(&self, d: RTPTransceiverDirection) {
+        self.current_direction.store(d as u8, Ordering::SeqCst);
+    }
+
+    /// stop irreversibly stops the RTPTransceiver
+    pub async fn stop(&self) -> Result<()> {
+        if let Some(sender) = self.sender().await {
+            sender.stop().await?;
+        }
+        if let Some(receiver) = self.receiver().await {
+            receiver.stop().await?;
+        }
+
+        self.stopped.store(true, Ordering::SeqCst);
+        self.set_direction(RTPTransceiverDirection::Inactive);
+        Ok(())
+    }
+}
+
+/// find_by_mid finds the transceiver with the given mid, and takes it out
+/// of local_transceivers
+pub(crate) async fn find_by_mid(
+    mid: &str,
+    local_transceivers: &mut Vec<Arc<RTPTransceiver>>,
+) -> Option<Arc<RTPTransceiver>> {
+    for i in 0..local_transceivers.len() {
+        if local_transceivers[i].mid().await == mid {
+            return Some(local_transceivers.remove(i));
+        }
+    }
+
+    None
+}
+
+/// satisfy_type_and_direction returns the first transceiver, with an unset mid,
+/// that can satisfy the remote type and direction, and takes it out of
+/// local_transceivers
+pub(crate) async fn satisfy_type_and_direction(
+    remote_kind: RTPCodecType,
+    remote_direction: RTPTransceiverDirection,
+    local_transceivers: &mut Vec<Arc<RTPTransceiver>>,
+) -> Option<Arc<RTPTransceiver>> {
+    // Get direction order from most preferred to least
+    let get_preferred_directions = || -> Vec<RTPTransceiverDirection> {
+        match remote_direction {
+            RTPTransceiverDirection::Sendrecv => vec![
+                RTPTransceiverDirection::Recvonly,
+                RTPTransceiverDirection::Sendrecv,
+            ],
+            RTPTransceiverDirection::Sendonly => vec![RTPTransceiverDirection::Recvonly],
+            RTPTransceiverDirection::Recvonly => vec![
+                RTPTransceiverDirection::Sendonly,
+                RTPTransceiverDirection::Sendrecv,
+            ],
+            _ => vec![],
+        }
+    };
+
+    for possible_direction in get_preferred_directions() {
+        for i in 0..local_transceivers.len() {
+            let t = &local_transceivers[i];
+            if t.mid().await.is_empty()
+                && t.kind == remote_kind
+                && possible_direction == t.direction()
+            {
+                return Some(local_transceivers.remove(i));
+            }
+        }
+    }
+
+    None
+}
+
+/// handle_unknown_rtp_packet reads the mid and rid header extensions, and the
+/// payload type of a RTP packet whose SSRC is not declared in the remote description
+pub(crate) fn handle_unknown_rtp_packet(
+    buf: &[u8],
+    mid_extension_id: u8,
+    sid_extension_id: u8,
+) -> Result<(String, String, PayloadType)> {
+    let header = rtp::header::Header::unmarshal(&mut &buf[..])?;
+
+    if !header.extension {
+        return Ok((String::new(), String::new(), header.payload_type));
+    }
+
+    let mid = match header.get_extension(mid_extension_id) {
+        Some(payload) => String::from_utf8(payload.to_vec())?,
+        None => String::new(),
+    };
+
+    let rid = match header.get_extension(sid_extension_id) {
+        Some(payload) => String::from_utf8(payload.to_vec())?,
+        None => String::new(),
+    };
+
+    Ok((mid, rid, header.payload_type))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::api::media_engine::MIME_TYPE_VP8;
+    use crate::media::rtp::rtp_codec::RTPCodecCapability;
+    use crate::media::track::track_local::track_local_static_sample::TrackLocalStaticSample;
+    use bytes::Bytes;
+    use util::marshal::Marshal;
+
+    fn new_transceiver(
+        kind: RTPCodecType,
+        direction: RTPTransceiverDirection,
+    ) -> Arc<RTPTransceiver> {
+        RTPTransceiver::new(
+            None,
+            None,
+            direction,
+            kind,
+            vec![],
+            Arc::new(MediaEngine::default()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_rtp_transceiver_set_mid() -> Result<()> {
+        let t = new_transceiver(RTPCodecType::Video, RTPTransceiverDirection::Recvonly);
+        assert_eq!(t.mid().await, "");
+
+        t.set_mid("0".to_owned()).await?;
+        assert_eq!(t.mid().await, "0");
+
+        if let Err(err) = t.set_mid("1".to_owned()).await {
+            assert!(Error::ErrRTPTransceiverCannotChangeMid.equal(&err));
+        } else {
+            panic!("expected error, but got ok");
+        }
+        assert_eq!(t.mid().await, "0");
+
+        t.restore_mid(String::new()).await;
+        t.set_mid("1".to_owned()).await?;
+        assert_eq!(t.mid().await, "1");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rtp_transceiver_set_codec_preferences() -> Result<()> {
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+        let media_engine = Arc::new(m);
+
+        let t = RTPTransceiver::new(
+            None,
+            None,
+            RTPTransceiverDirection::Recvonly,
+            RTPCodecType::Video,
+            vec![],
+            Arc::clone(&media_engine),
+        );
+
+        let media_engine_codecs = media_engine.get_codecs_by_kind(RTPCodecType::Video).await;
+        assert_eq!(t.get_codecs().await, media_engine_codecs);
+
+        let vp8 = media_engine_codecs
+            .iter()
+            .find(|c| c.capability.mime_type == MIME_TYPE_VP8)
+            .cloned()
+            .unwrap();
+        t.set_codec_preferences(vec![RTPCodecParameters {
+            capability: vp8.capability.clone(),
+            payload_type: 0,
+            ..Default::default()
+        }])
+        .await?;
+
+        let codecs = t.get_codecs().await;
+        assert_eq!(codecs.len(), 1);
+        assert_eq!(codecs[0].payload_type, vp8.payload_type);
+
+        let opus = media_engine
+            .get_codecs_by_kind(RTPCodecType::Audio)
+            .await
+            .first()
+            .cloned()
+            .unwrap();
+        if let Err(err) = t.set_codec_preferences(vec![opus]).await {
+            assert!(Error::ErrRTPTransceiverCodecUnsupported.equal(&err));
+        } else {
+            panic!("expected error, but got ok");
+        }
+
+        // an empty list resets to the MediaEngine codecs
+        t.set_codec_preferences(vec![]).await?;
+        assert_eq!(t.get_codecs().await, media_engine_codecs);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rtp_transceiver_set_sending_track() -> Result<()> {
+        let track: Arc<dyn TrackLocal + Send + Sync> = Arc::new(TrackLocalStaticSample::new(
+            RTPCodecCapability {
+                mime_type: MIME_TYPE_VP8.to_owned(),
+                ..Default::default()
+            },
+            "video".to_owned(),
+            "webrtc-rs".to_owned(),
+        ));
+
+        for (direction, with_track, without_track) in &[
+            (
+                RTPTransceiverDirection::Recvonly,
+                RTPTransceiverDirection::Sendrecv,
+                RTPTransceiverDirection::Recvonly,
+            ),
+            (
+                RTPTransceiverDirection::Inactive,
+                RTPTransceiverDirection::Sendonly,
+                RTPTransceiverDirection::Inactive,
+            ),
+            // the transceiver of a remote recvonly offer sends the track it gets
+            (
+                RTPTransceiverDirection::Sendonly,
+                RTPTransceiverDirection::Sendonly,
+                RTPTransceiverDirection::Inactive,
+            ),
+        ] {
+            let t = new_transceiver(RTPCodecType::Video, *direction);
+            t.set_sending_track(Some(Arc::clone(&track))).await?;
+            assert_eq!(t.direction(), *with_track);
+            t.set_sending_track(None).await?;
+            assert_eq!(t.direction(), *without_track);
+        }
+
+        // there is no track to remove
+        let t = new_transceiver(RTPCodecType::Video, RTPTransceiverDirection::Recvonly);
+        if let Err(err) = t.set_sending_track(None).await {
+            assert!(Error::ErrRTPTransceiverSetSendingInvalidState.equal(&err));
+        } else {
+            panic!("expected error, but got ok");
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_satisfy_type_and_direction() -> Result<()> {
+        let recvonly = new_transceiver(RTPCodecType::Video, RTPTransceiverDirection::Recvonly);
+        let sendrecv = new_transceiver(RTPCodecType::Video, RTPTransceiverDirection::Sendrecv);
+        let audio = new_transceiver(RTPCodecType::Audio, RTPTransceiverDirection::Sendrecv);
+
+        let mut local_transceivers = vec![
+            Arc::clone(&audio),
+            Arc::clone(&sendrecv),
+            Arc::clone(&recvonly),
+        ];
+
+        // recvonly is preferred for a remote sendrecv
+        let t = satisfy_type_and_direction(
+            RTPCodecType::Video,
+            RTPTransceiverDirection::Sendrecv,
+            &mut local_transceivers,
+        )
+        .await;
+        assert!(t.is_some_and(|t| Arc::ptr_eq(&t, &recvonly)));
+        assert_eq!(local_transceivers.len(), 2);
+
+        // a remote sendonly can't be satisfied by a local sendrecv
+        let t = satisfy_type_and_direction(
+            RTPCodecType::Video,
+            RTPTransceiverDirection::Sendonly,
+            &mut local_transceivers,
+        )
+        .await;
+        assert!(t.is_none());
+
+        // transceivers with a mid are never picked
+        audio.set_mid("0".to_owned()).await?;
+        let t = satisfy_type_and_direction(
+            RTPCodecType::Audio,
+            RTPTransceiverDirection::Recvonly,
+            &mut local_transceivers,
+        )
+        .await;
+        assert!(t.is_none());
+
+        let t = find_by_mid("0", &mut local_transceivers).await;
+        assert!(t.is_some_and(|t| Arc::ptr_eq(&t, &audio)));
+        assert_eq!(local_transceivers.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_handle_unknown_rtp_packet() -> Result<()> {
+        let mut header = rtp::header::Header {
+            version: 2,
+            payload_type: 96,
+            sequence_number: 1,
+            ssrc: 1234,
+            ..Default::default()
+        };
+        let raw = rtp::packet::Packet {
+            header: header.clone(),
+            payload: Bytes::from_static(&[0x00]),
+        }
+        .marshal()?;
+        assert_eq!(
+            handle_unknown_rtp_packet(&raw, 1, 2)?,
+            (String::new(), String::new(), 96)
+        );
+
+        header.set_extension(1, Bytes::from_static(b"0"))?;
+        header.set_extension(2, Bytes::from_static(b"f"))?;
+        let raw = rtp::packet::Packet {
+            header,
+            payload: Bytes::from_static(&[0x00]),
+        }
+        .marshal()?;
+        assert_eq!(
+            handle_unknown_rtp_packet(&raw, 1, 2)?,
+            ("0".to_owned(), "f".to_owned(), 96)
+        );
+
+        // extensions which were not negotiated are ignored
+        assert_eq!(
+            handle_unknown_rtp_packet(&raw, 3, 4)?,
+            (String::new(), String::new(), 96)
+        );
+
+        Ok(())
+    }
+}
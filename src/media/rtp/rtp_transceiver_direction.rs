@@ -0,0 +1,186 @@
+use std::fmt;
+
+/// RTPTransceiverDirection indicates the direction of the RTPTransceiver.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum RTPTransceiverDirection {
+    #[default]
+    Unspecified = 0,
+
+    /// RTPTransceiverDirectionSendrecv indicates the RTPSender will offer
+    /// to send RTP and RTPReceiver the will offer to receive RTP.
+    Sendrecv = 1,
+
+    /// RTPTransceiverDirectionSendonly indicates the RTPSender will offer to send RTP.
+    Sendonly = 2,
+
+    /// RTPTransceiverDirectionRecvonly indicates the RTPReceiver the will offer to receive RTP.
+    Recvonly = 3,
+
+    /// RTPTransceiverDirectionInactive indicates the RTPSender won't offer
+    /// to send RTP and RTPReceiver the won't offer to receive RTP.
+    Inactive = 4,
+}
+
+const RTP_TRANSCEIVER_DIRECTION_SENDRECV_STR: &str = "sendrecv";
+const RTP_TRANSCEIVER_DIRECTION_SENDONLY_STR: &str = "sendonly";
+const RTP_TRANSCEIVER_DIRECTION_RECVONLY_STR: &str = "recvonly";
+const RTP_TRANSCEIVER_DIRECTION_INACTIVE_STR: &str = "inactive";
+
+/// defines a procedure for creating a new
+/// RTPTransceiverDirection from a raw string naming the transceiver direction.
+impl From<&str> for RTPTransceiverDirection {
+    fn from(raw: &str) -> Self {
+        match raw {
+            RTP_TRANSCEIVER_DIRECTION_SENDRECV_STR => RTPTransceiverDirection::Sendrecv,
+            RTP_TRANSCEIVER_DIRECTION_SENDONLY_STR => RTPTransceiverDirection::Sendonly,
+            RTP_TRANSCEIVER_DIRECTION_RECVONLY_STR => RTPTransceiverDirection::Recvonly,
+            RTP_TRANSCEIVER_DIRECTION_INACTIVE_STR => RTPTransceiverDirection::Inactive,
+            _ => RTPTransceiverDirection::Unspecified,
+        }
+    }
+}
+
+impl From<u8> for RTPTransceiverDirection {
+    fn from(v: u8) -> Self {
+        match v {
+            1 => RTPTransceiverDirection::Sendrecv,
+            2 => RTPTransceiverDirection::Sendonly,
+            3 => RTPTransceiverDirection::Recvonly,
+            4 => RTPTransceiverDirection::Inactive,
+            _ => RTPTransceiverDirection::Unspecified,
+        }
+    }
+}
+
+impl fmt::Display for RTPTransceiverDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match *self {
+            RTPTransceiverDirection::Sendrecv => RTP_TRANSCEIVER_DIRECTION_SENDRECV_STR,
+            RTPTransceiverDirection::Sendonly => RTP_TRANSCEIVER_DIRECTION_SENDONLY_STR,
+            RTPTransceiverDirection::Recvonly => RTP_TRANSCEIVER_DIRECTION_RECVONLY_STR,
+            RTPTransceiverDirection::Inactive => RTP_TRANSCEIVER_DIRECTION_INACTIVE_STR,
+            RTPTransceiverDirection::Unspecified => crate::UNSPECIFIED_STR,
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl RTPTransceiverDirection {
+    /// reverse indicate the opposite direction
+    pub fn reverse(&self) -> RTPTransceiverDirection {
+        match *self {
+            RTPTransceiverDirection::Sendonly => RTPTransceiverDirection::Recvonly,
+            RTPTransceiverDirection::Recvonly => RTPTransceiverDirection::Sendonly,
+            _ => *self,
+        }
+    }
+
+    /// intersect returns the direction that both self and other allow
+    pub fn intersect(&self, other: RTPTransceiverDirection) -> RTPTransceiverDirection {
+        RTPTransceiverDirection::from_send_recv(
+            self.has_send() && other.has_send(),
+            self.has_recv() && other.has_recv(),
+        )
+    }
+
+    pub fn from_send_recv(send: bool, recv: bool) -> RTPTransceiverDirection {
+        match (send, recv) {
+            (true, true) => RTPTransceiverDirection::Sendrecv,
+            (true, false) => RTPTransceiverDirection::Sendonly,
+            (false, true) => RTPTransceiverDirection::Recvonly,
+            (false, false) => RTPTransceiverDirection::Inactive,
+        }
+    }
+
+    pub fn has_send(&self) -> bool {
+        matches!(
+            self,
+            RTPTransceiverDirection::Sendrecv | RTPTransceiverDirection::Sendonly
+        )
+    }
+
+    pub fn has_recv(&self) -> bool {
+        matches!(
+            self,
+            RTPTransceiverDirection::Sendrecv | RTPTransceiverDirection::Recvonly
+        )
+    }
+}
+
+pub(crate) fn have_rtp_transceiver_direction_intersection(
+    have_directions: &[RTPTransceiverDirection],
+    want_directions: &[RTPTransceiverDirection],
+) -> bool {
+    for n in want_directions {
+        if have_directions.contains(n) {
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_new_rtp_transceiver_direction() {
+        let tests = vec![
+            (crate::UNSPECIFIED_STR, RTPTransceiverDirection::Unspecified),
+            ("sendrecv", RTPTransceiverDirection::Sendrecv),
+            ("sendonly", RTPTransceiverDirection::Sendonly),
+            ("recvonly", RTPTransceiverDirection::Recvonly),
+            ("inactive", RTPTransceiverDirection::Inactive),
+        ];
+
+        for (ct_str, expected_type) in tests {
+            assert_eq!(expected_type, RTPTransceiverDirection::from(ct_str));
+        }
+    }
+
+    #[test]
+    fn test_rtp_transceiver_direction_string() {
+        let tests = vec![
+            (RTPTransceiverDirection::Unspecified, crate::UNSPECIFIED_STR),
+            (RTPTransceiverDirection::Sendrecv, "sendrecv"),
+            (RTPTransceiverDirection::Sendonly, "sendonly"),
+            (RTPTransceiverDirection::Recvonly, "recvonly"),
+            (RTPTransceiverDirection::Inactive, "inactive"),
+        ];
+
+        for (d, expected_string) in tests {
+            assert_eq!(expected_string, d.to_string());
+        }
+    }
+
+    #[test]
+    fn test_rtp_transceiver_direction_intersect() {
+        let tests = vec![
+            (
+                RTPTransceiverDirection::Sendrecv,
+                RTPTransceiverDirection::Recvonly,
+                RTPTransceiverDirection::Recvonly,
+            ),
+            (
+                RTPTransceiverDirection::Sendrecv,
+                RTPTransceiverDirection::Sendrecv,
+                RTPTransceiverDirection::Sendrecv,
+            ),
+            (
+                RTPTransceiverDirection::Sendonly,
+                RTPTransceiverDirection::Recvonly,
+                RTPTransceiverDirection::Inactive,
+            ),
+            (
+                RTPTransceiverDirection::Recvonly,
+                RTPTransceiverDirection::Inactive,
+                RTPTransceiverDirection::Inactive,
+            ),
+        ];
+
+        for (a, b, expected) in tests {
+            assert_eq!(a.intersect(b), expected, "{} intersect {}", a, b);
+            assert_eq!(b.intersect(a), expected, "{} intersect {}", b, a);
+        }
+    }
+}
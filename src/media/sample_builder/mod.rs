@@ -0,0 +1,354 @@
+#[cfg(test)]
+mod sample_builder_test;
+
+mod sample_sequence_location;
+
+use crate::media::rtp::codecs::Depacketizer;
+use crate::media::Sample;
+use sample_sequence_location::{Comparison, SampleSequenceLocation};
+
+use bytes::BytesMut;
+use std::collections::VecDeque;
+use std::time::{Duration, SystemTime};
+
+/// The reasons why build_sample could not produce a Sample
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum BuildError {
+    /// There's no active segment of RTP packets to consider yet
+    NoActiveSegment,
+    /// No frame boundary could be found in the active segment
+    NothingToConsume,
+    /// A frame has been found, but the packet after it is needed to know its duration
+    PendingTimestampPacket,
+    /// The packets of the frame have been dropped, because the frame doesn't
+    /// start at a partition head or couldn't be depacketized
+    InvalidPartition,
+    /// A packet of the frame is missing
+    GapInSegment,
+}
+
+/// SampleBuilder buffers packets until media frames are complete.
+pub struct SampleBuilder {
+    /// how many packets to wait until we get a valid Sample
+    max_late: u16,
+    /// max timestamp between old and new timestamps before dropping packets
+    max_late_timestamp: u32,
+    /// packets indexed by sequence number, boxed so that the empty slots of the
+    /// 65536 entries only take a pointer each
+    buffer: Vec<Option<Box<rtp::packet::Packet>>>,
+    prepared_samples: VecDeque<Sample>,
+
+    /// Interface that allows us to take RTP packets to samples
+    depacketizer: Box<dyn Depacketizer + Send + Sync>,
+
+    /// sample_rate allows us to compute the duration of a Sample
+    sample_rate: u32,
+
+    /// filled contains the head/tail of the packets inserted into the buffer
+    filled: SampleSequenceLocation,
+
+    /// active contains the active head/tail of the timestamp being actively processed
+    active: SampleSequenceLocation,
+
+    /// number of packets forced to be dropped since the last Sample
+    dropped_packets: u16,
+}
+
+impl SampleBuilder {
+    /// new constructs a new SampleBuilder.
+    /// max_late is how long to wait until we can construct a completed Sample,
+    /// measured in RTP packet sequence numbers. A large max_late will result in
+    /// less packet loss but higher latency.
+    /// The depacketizer extracts media samples from RTP packets, see
+    /// RTPCodecCapability::depacketizer_for_codec.
+    pub fn new(
+        max_late: u16,
+        depacketizer: Box<dyn Depacketizer + Send + Sync>,
+        sample_rate: u32,
+    ) -> Self {
+        SampleBuilder {
+            max_late,
+            max_late_timestamp: 0,
+            buffer: vec![None; u16::MAX as usize + 1],
+            prepared_samples: VecDeque::new(),
+            depacketizer,
+            sample_rate,
+            filled: SampleSequenceLocation::new(),
+            active: SampleSequenceLocation::new(),
+            dropped_packets: 0,
+        }
+    }
+
+    /// with_max_time_delay ensures that packets are not held for longer than
+    /// max_late_duration, even if max_late packets haven't been received yet
+    pub fn with_max_time_delay(mut self, max_late_duration: Duration) -> Self {
+        self.max_late_timestamp =
+            (self.sample_rate as f64 * max_late_duration.as_secs_f64()) as u32;
+        self
+    }
+
+    fn too_old(&self, location: &SampleSequenceLocation) -> bool {
+        if self.max_late_timestamp == 0 {
+            return false;
+        }
+
+        let mut found_head = None;
+        let mut i = location.head;
+        while i != location.tail {
+            if let Some(p) = &self.buffer[i as usize] {
+                found_head = Some(p.header.timestamp);
+                break;
+            }
+            i = i.wrapping_add(1);
+        }
+
+        let mut found_tail = None;
+        let mut i = location.tail;
+        while i != location.head {
+            i = i.wrapping_sub(1);
+            if let Some(p) = &self.buffer[i as usize] {
+                found_tail = Some(p.header.timestamp);
+                break;
+            }
+        }
+
+        match (found_head, found_tail) {
+            (Some(head), Some(tail)) => {
+                let diff = tail.wrapping_sub(head);
+                // a reordered packet may carry an older timestamp than the head
+                diff < u32::MAX / 2 && diff > self.max_late_timestamp
+            }
+            _ => false,
+        }
+    }
+
+    /// fetch_timestamp returns the timestamp associated with a given sample location
+    fn fetch_timestamp(&self, location: &SampleSequenceLocation) -> Option<u32> {
+        if location.empty() {
+            None
+        } else {
+            self.buffer[location.head as usize]
+                .as_ref()
+                .map(|p| p.header.timestamp)
+        }
+    }
+
+    fn release_packet(&mut self, i: u16) {
+        self.buffer[i as usize] = None;
+    }
+
+    /// purge_consumed_buffers clears all buffers that have already been consumed by popping.
+    fn purge_consumed_buffers(&mut self) {
+        let active = self.active;
+        self.purge_consumed_location(&active, false);
+    }
+
+    /// purge_consumed_location clears all buffers that have already been consumed
+    /// during a sample building method.
+    fn purge_consumed_location(&mut self, consume: &SampleSequenceLocation, force_consume: bool) {
+        while self.filled.has_data() {
+            let consumed = match consume.compare(self.filled.head) {
+                Comparison::Inside => force_consume,
+                Comparison::Before => true,
+                _ => false,
+            };
+            if !consumed {
+                break;
+            }
+
+            self.release_packet(self.filled.head);
+            self.filled.head = self.filled.head.wrapping_add(1);
+        }
+    }
+
+    /// purge_buffers flushes all buffers that are already consumed or those buffers
+    /// that are too late to consume.
+    fn purge_buffers(&mut self) {
+        self.purge_consumed_buffers();
+
+        while (self.too_old(&self.filled) || self.filled.count() > self.max_late)
+            && self.filled.has_data()
+        {
+            if self.active.empty() {
+                // refill the active based on the filled packets
+                self.active = self.filled;
+            }
+
+            if self.active.has_data() && self.active.head == self.filled.head {
+                // attempt to force the active packet to be consumed even though
+                // outstanding data may be pending arrival
+                match self.build_sample(true) {
+                    Ok(_) => continue,
+                    // the dropped packets have already been counted and released
+                    Err(BuildError::InvalidPartition) => continue,
+                    Err(_) => {}
+                };
+
+                // could not build the sample so drop it
+                self.dropped_packets = self.dropped_packets.wrapping_add(1);
+                self.active.head = self.active.head.wrapping_add(1);
+            }
+
+            self.release_packet(self.filled.head);
+            self.filled.head = self.filled.head.wrapping_add(1);
+        }
+    }
+
+    /// push adds a RTP Packet to the sample builder
+    pub fn push(&mut self, p: rtp::packet::Packet) {
+        let sequence_number = p.header.sequence_number;
+        self.buffer[sequence_number as usize] = Some(Box::new(p));
+
+        match self.filled.compare(sequence_number) {
+            Comparison::Void => {
+                self.filled.head = sequence_number;
+                self.filled.tail = sequence_number.wrapping_add(1);
+            }
+            Comparison::Before => {
+                self.filled.head = sequence_number;
+            }
+            Comparison::After => {
+                self.filled.tail = sequence_number.wrapping_add(1);
+            }
+            Comparison::Inside => {}
+        };
+
+        self.purge_buffers();
+    }
+
+    /// build_sample creates a sample from a valid collection of RTP Packets by
+    /// walking forwards building a sample if everything looks good clear and
+    /// update buffer+values
+    fn build_sample(&mut self, purging_buffers: bool) -> Result<(), BuildError> {
+        if self.active.empty() {
+            self.active = self.filled;
+        }
+
+        if self.active.empty() {
+            return Err(BuildError::NoActiveSegment);
+        }
+
+        if self.filled.compare(self.active.tail) == Comparison::Inside {
+            self.active.tail = self.filled.tail;
+        }
+
+        let mut consume = SampleSequenceLocation::new();
+
+        let head_timestamp = self.fetch_timestamp(&self.active);
+        let mut i = self.active.head;
+        while let Some(p) = &self.buffer[i as usize] {
+            if self.active.compare(i) == Comparison::After {
+                break;
+            }
+
+            // If the timestamp is not the same it might be because the next packet is both
+            // a start and end of the next partition, in which case a sample should be
+            // generated now, excluding that packet
+            let is_same_timestamp = match head_timestamp {
+                Some(ts) => p.header.timestamp == ts,
+                None => true,
+            };
+            if self
+                .depacketizer
+                .is_partition_tail(p.header.marker, &p.payload)
+                && is_same_timestamp
+            {
+                consume.head = self.active.head;
+                consume.tail = i.wrapping_add(1);
+                break;
+            }
+
+            if !is_same_timestamp {
+                consume.head = self.active.head;
+                consume.tail = i;
+                break;
+            }
+
+            i = i.wrapping_add(1);
+        }
+
+        if consume.empty() {
+            return Err(BuildError::NothingToConsume);
+        }
+
+        if !purging_buffers && self.buffer[consume.tail as usize].is_none() {
+            // wait for the next packet after this set of packets to arrive
+            // to ensure at least one post sample timestamp is known
+            // (unless we have to release right now)
+            return Err(BuildError::PendingTimestampPacket);
+        }
+
+        let sample_timestamp = head_timestamp.unwrap_or(0);
+        let mut after_timestamp = sample_timestamp;
+
+        // scan for any packet after the current and use that time stamp as the diff point
+        let mut i = consume.tail;
+        while i != self.active.tail {
+            if let Some(p) = &self.buffer[i as usize] {
+                after_timestamp = p.header.timestamp;
+                break;
+            }
+            i = i.wrapping_add(1);
+        }
+
+        // the head set of packets is now fully consumed
+        self.active.head = consume.tail;
+
+        // prior to decoding all the packets, check if this packet
+        // would end being disposed anyway
+        let is_partition_head = match &self.buffer[consume.head as usize] {
+            Some(p) => self.depacketizer.is_partition_head(&p.payload),
+            None => return Err(BuildError::GapInSegment),
+        };
+
+        // merge all the buffers into a sample
+        let mut data = BytesMut::new();
+        let mut depacketized = is_partition_head;
+        let mut i = consume.head;
+        while depacketized && i != consume.tail {
+            depacketized = match &self.buffer[i as usize] {
+                Some(p) => match self.depacketizer.depacketize(&p.payload) {
+                    Ok(payload) => {
+                        data.extend_from_slice(&payload);
+                        true
+                    }
+                    Err(_) => false,
+                },
+                None => false,
+            };
+            i = i.wrapping_add(1);
+        }
+
+        if !depacketized {
+            self.dropped_packets = self.dropped_packets.wrapping_add(consume.count());
+            self.purge_consumed_location(&consume, true);
+            self.purge_consumed_buffers();
+            return Err(BuildError::InvalidPartition);
+        }
+
+        let samples = after_timestamp.wrapping_sub(sample_timestamp);
+        self.prepared_samples.push_back(Sample {
+            data: data.freeze(),
+            timestamp: SystemTime::now(),
+            duration: Duration::from_secs_f64(samples as f64 / self.sample_rate as f64),
+            packet_timestamp: sample_timestamp,
+            prev_dropped_packets: self.dropped_packets,
+        });
+        self.dropped_packets = 0;
+
+        self.purge_consumed_location(&consume, true);
+        self.purge_consumed_buffers();
+
+        Ok(())
+    }
+
+    /// pop compiles pushed RTP packets into media samples and then
+    /// returns the next valid sample (or None if no sample is compiled).
+    pub fn pop(&mut self) -> Option<Sample> {
+        // packets that don't start at a partition head are dropped,
+        // a complete sample may follow them
+        while self.build_sample(false) == Err(BuildError::InvalidPartition) {}
+
+        self.prepared_samples.pop_front()
+    }
+}
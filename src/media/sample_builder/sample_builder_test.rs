@@ -0,0 +1,261 @@
+use super::*;
+
+use anyhow::Result;
+use bytes::Bytes;
+
+#[derive(Default, Debug, Clone)]
+struct FakeDepacketizer {
+    head_bytes: Vec<Bytes>,
+}
+
+impl Depacketizer for FakeDepacketizer {
+    fn depacketize(&mut self, payload: &Bytes) -> Result<Bytes> {
+        Ok(payload.clone())
+    }
+
+    fn is_partition_head(&self, payload: &Bytes) -> bool {
+        self.head_bytes.is_empty() || self.head_bytes.contains(payload)
+    }
+
+    fn is_partition_tail(&self, marker: bool, _payload: &Bytes) -> bool {
+        marker
+    }
+
+    fn is_keyframe(&self, _payload: &Bytes) -> bool {
+        false
+    }
+
+    fn clone_to(&self) -> Box<dyn Depacketizer + Send + Sync> {
+        Box::new(self.clone())
+    }
+}
+
+fn packet(sequence_number: u16, timestamp: u32, marker: bool, payload: u8) -> rtp::packet::Packet {
+    rtp::packet::Packet {
+        header: rtp::header::Header {
+            sequence_number,
+            timestamp,
+            marker,
+            ..Default::default()
+        },
+        payload: Bytes::from(vec![payload]),
+    }
+}
+
+struct SampleBuilderTest {
+    message: &'static str,
+    packets: Vec<rtp::packet::Packet>,
+    head_bytes: Vec<u8>,
+    /// (data, packet_timestamp, prev_dropped_packets, duration in samples)
+    samples: Vec<(Vec<u8>, u32, u16, u64)>,
+    max_late: u16,
+    max_late_duration: Duration,
+}
+
+#[test]
+fn test_sample_builder() {
+    let tests = vec![
+        SampleBuilderTest {
+            message: "SampleBuilder shouldn't emit anything if only one RTP packet has been pushed",
+            packets: vec![packet(5000, 5, false, 1)],
+            head_bytes: vec![],
+            samples: vec![],
+            max_late: 50,
+            max_late_duration: Duration::from_secs(0),
+        },
+        SampleBuilderTest {
+            message: "SampleBuilder shouldn't emit anything if only one RTP packet has been pushed even if the marker bit is set",
+            packets: vec![packet(5000, 5, true, 1)],
+            head_bytes: vec![],
+            samples: vec![],
+            max_late: 50,
+            max_late_duration: Duration::from_secs(0),
+        },
+        SampleBuilderTest {
+            message: "SampleBuilder should emit two samples, we had three packets with unique timestamps",
+            packets: vec![
+                packet(5000, 5, false, 1),
+                packet(5001, 6, false, 2),
+                packet(5002, 7, false, 3),
+            ],
+            head_bytes: vec![],
+            samples: vec![(vec![1], 5, 0, 1), (vec![2], 6, 0, 1)],
+            max_late: 50,
+            max_late_duration: Duration::from_secs(0),
+        },
+        SampleBuilderTest {
+            message: "SampleBuilder should merge the packets of a frame ending with the marker bit",
+            packets: vec![
+                packet(5000, 5, false, 1),
+                packet(5001, 5, true, 2),
+                packet(5002, 6, false, 3),
+                packet(5003, 6, true, 4),
+                packet(5004, 7, false, 5),
+            ],
+            head_bytes: vec![],
+            samples: vec![(vec![1, 2], 5, 0, 1), (vec![3, 4], 6, 0, 1)],
+            max_late: 50,
+            max_late_duration: Duration::from_secs(0),
+        },
+        SampleBuilderTest {
+            message: "SampleBuilder should reorder packets by sequence number",
+            packets: vec![
+                packet(5000, 1, false, 1),
+                packet(5002, 2, false, 3),
+                packet(5001, 1, true, 2),
+                packet(5003, 3, false, 4),
+            ],
+            head_bytes: vec![],
+            samples: vec![(vec![1, 2], 1, 0, 1), (vec![3], 2, 0, 1)],
+            max_late: 50,
+            max_late_duration: Duration::from_secs(0),
+        },
+        SampleBuilderTest {
+            message: "SampleBuilder should drop a lost packet once max_late is reached and report it",
+            packets: vec![
+                packet(5000, 1, false, 1),
+                packet(5001, 1, true, 2),
+                packet(5003, 3, false, 4),
+                packet(5004, 3, true, 5),
+                packet(5005, 5, false, 6),
+            ],
+            head_bytes: vec![],
+            samples: vec![(vec![1, 2], 1, 0, 2), (vec![4, 5], 3, 1, 2)],
+            max_late: 3,
+            max_late_duration: Duration::from_secs(0),
+        },
+        SampleBuilderTest {
+            message: "SampleBuilder should drop packets that don't start at a partition head",
+            packets: vec![
+                packet(5000, 1, false, 2),
+                packet(5001, 2, false, 1),
+                packet(5002, 3, false, 1),
+            ],
+            head_bytes: vec![1],
+            samples: vec![(vec![1], 2, 1, 1)],
+            max_late: 50,
+            max_late_duration: Duration::from_secs(0),
+        },
+        SampleBuilderTest {
+            message: "SampleBuilder should handle sequence number wraparound",
+            packets: vec![
+                packet(65534, 1, false, 1),
+                packet(65535, 1, true, 2),
+                packet(0, 2, false, 3),
+                packet(1, 2, true, 4),
+                packet(2, 3, false, 5),
+            ],
+            head_bytes: vec![],
+            samples: vec![(vec![1, 2], 1, 0, 1), (vec![3, 4], 2, 0, 1)],
+            max_late: 50,
+            max_late_duration: Duration::from_secs(0),
+        },
+        SampleBuilderTest {
+            message: "SampleBuilder should handle timestamp wraparound",
+            packets: vec![
+                packet(100, 0xFFFF_FFF0, false, 1),
+                packet(101, 0xFFFF_FFF0, true, 2),
+                packet(102, 0x10, true, 3),
+                packet(103, 0x30, false, 4),
+            ],
+            head_bytes: vec![],
+            samples: vec![(vec![1, 2], 0xFFFF_FFF0, 0, 0x20), (vec![3], 0x10, 0, 0x20)],
+            max_late: 50,
+            max_late_duration: Duration::from_secs(0),
+        },
+        SampleBuilderTest {
+            message: "SampleBuilder should drop packets older than the max time delay",
+            packets: vec![
+                packet(5000, 1, false, 1),
+                packet(5002, 2, false, 3),
+                packet(5003, 3, false, 4),
+                packet(5004, 4, true, 5),
+                packet(5005, 5, false, 6),
+            ],
+            head_bytes: vec![],
+            samples: vec![
+                (vec![3], 2, 2, 1),
+                (vec![4], 3, 0, 1),
+                (vec![5], 4, 0, 1),
+            ],
+            max_late: 50,
+            max_late_duration: Duration::from_secs(2),
+        },
+    ];
+
+    for t in tests {
+        let depacketizer = FakeDepacketizer {
+            head_bytes: t.head_bytes.iter().map(|b| Bytes::from(vec![*b])).collect(),
+        };
+        let mut s = SampleBuilder::new(t.max_late, Box::new(depacketizer), 1)
+            .with_max_time_delay(t.max_late_duration);
+
+        for p in t.packets {
+            s.push(p);
+        }
+
+        let mut samples = vec![];
+        while let Some(sample) = s.pop() {
+            samples.push((
+                sample.data.to_vec(),
+                sample.packet_timestamp,
+                sample.prev_dropped_packets,
+                sample.duration.as_secs(),
+            ));
+        }
+
+        assert_eq!(samples, t.samples, "{}", t.message);
+    }
+}
+
+#[test]
+fn test_sample_builder_clean_reference() {
+    for seq_start in &[0u16, 0xFFFF - 2, 0xFFFF - 1] {
+        let mut s = SampleBuilder::new(10, Box::new(FakeDepacketizer::default()), 1);
+
+        s.push(packet(*seq_start, 0, false, 1));
+        s.push(packet(seq_start.wrapping_add(1), 0, false, 2));
+        s.push(packet(seq_start.wrapping_add(2), 0, false, 3));
+        s.push(packet(seq_start.wrapping_add(14), 120, false, 4));
+
+        for i in 0..3u16 {
+            assert!(
+                s.buffer[seq_start.wrapping_add(i) as usize].is_none(),
+                "Old packet ({}) is not unreferenced (seq_start: {})",
+                i,
+                seq_start
+            );
+        }
+        assert!(
+            s.buffer[seq_start.wrapping_add(14) as usize].is_some(),
+            "New packet must be referenced after jump (seq_start: {})",
+            seq_start
+        );
+    }
+}
+
+#[test]
+fn test_sample_sequence_location_compare() {
+    let s1 = SampleSequenceLocation { head: 32, tail: 42 };
+    assert_eq!(s1.compare(16), Comparison::Before);
+    assert_eq!(s1.compare(32), Comparison::Inside);
+    assert_eq!(s1.compare(38), Comparison::Inside);
+    assert_eq!(s1.compare(41), Comparison::Inside);
+    assert_eq!(s1.compare(42), Comparison::After);
+    assert_eq!(s1.compare(0x57), Comparison::After);
+
+    let s2 = SampleSequenceLocation {
+        head: 0xffa0,
+        tail: 32,
+    };
+    assert_eq!(s2.compare(0xff00), Comparison::Before);
+    assert_eq!(s2.compare(0xffa0), Comparison::Inside);
+    assert_eq!(s2.compare(0xffff), Comparison::Inside);
+    assert_eq!(s2.compare(0), Comparison::Inside);
+    assert_eq!(s2.compare(31), Comparison::Inside);
+    assert_eq!(s2.compare(32), Comparison::After);
+    assert_eq!(s2.compare(128), Comparison::After);
+
+    assert_eq!(s2.count(), 0x60 + 32);
+    assert_eq!(SampleSequenceLocation::new().compare(0), Comparison::Void);
+}
@@ -0,0 +1,57 @@
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum Comparison {
+    Void,
+    Before,
+    Inside,
+    After,
+}
+
+/// SampleSequenceLocation is a range of sequence numbers in the SampleBuilder buffer
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub(crate) struct SampleSequenceLocation {
+    /// head is the first packet in a sequence
+    pub(crate) head: u16,
+    /// tail is always set to one after the final sequence number,
+    /// so if head == tail then the sequence is empty
+    pub(crate) tail: u16,
+}
+
+impl SampleSequenceLocation {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn empty(&self) -> bool {
+        self.head == self.tail
+    }
+
+    pub(crate) fn has_data(&self) -> bool {
+        self.head != self.tail
+    }
+
+    pub(crate) fn count(&self) -> u16 {
+        self.tail.wrapping_sub(self.head)
+    }
+
+    /// compare tells where pos is relative to the location, taking
+    /// sequence number wraparound into account
+    pub(crate) fn compare(&self, pos: u16) -> Comparison {
+        if self.head == self.tail {
+            return Comparison::Void;
+        }
+
+        if self.head < self.tail {
+            if self.head <= pos && pos < self.tail {
+                return Comparison::Inside;
+            }
+        } else if self.head <= pos || pos < self.tail {
+            return Comparison::Inside;
+        }
+
+        if self.head.wrapping_sub(pos) <= pos.wrapping_sub(self.tail) {
+            Comparison::Before
+        } else {
+            Comparison::After
+        }
+    }
+}
@@ -1 +1,2 @@
-
+pub mod track_local;
+pub mod track_remote;
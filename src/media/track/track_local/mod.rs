@@ -0,0 +1,146 @@
+#[cfg(test)]
+mod track_local_static_test;
+
+pub mod track_local_static_rtp;
+pub mod track_local_static_sample;
+
+use crate::interceptor::{Attributes, RTPWriter};
+use crate::media::rtp::rtp_codec::{
+    RTPCodecParameters, RTPCodecType, RTPHeaderExtensionParameter, RTPParameters,
+};
+use crate::media::rtp::{PayloadType, SSRC};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use std::any::Any;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use util::marshal::Unmarshal;
+
+/// TrackLocalWriter is the Writer for outbound RTP Packets
+#[async_trait]
+pub trait TrackLocalWriter {
+    /// write_rtp encrypts a RTP packet and writes to the connection
+    async fn write_rtp(&self, p: &rtp::packet::Packet) -> Result<usize>;
+
+    /// write encrypts and writes a full RTP packet
+    async fn write(&self, b: &[u8]) -> Result<usize>;
+}
+
+/// InterceptorToTrackLocalWriter is the write_stream of a TrackLocal bound to a
+/// RTPSender, the packets are written through the interceptors of the local
+/// stream. Packets written before the interceptors are bound are dropped
+#[derive(Default)]
+pub(crate) struct InterceptorToTrackLocalWriter {
+    interceptor_rtp_writer: Mutex<Option<Arc<dyn RTPWriter + Send + Sync>>>,
+}
+
+impl InterceptorToTrackLocalWriter {
+    pub(crate) async fn set_rtp_writer(&self, rtp_writer: Arc<dyn RTPWriter + Send + Sync>) {
+        let mut interceptor_rtp_writer = self.interceptor_rtp_writer.lock().await;
+        *interceptor_rtp_writer = Some(rtp_writer);
+    }
+}
+
+#[async_trait]
+impl TrackLocalWriter for InterceptorToTrackLocalWriter {
+    async fn write_rtp(&self, pkt: &rtp::packet::Packet) -> Result<usize> {
+        let interceptor_rtp_writer = {
+            let interceptor_rtp_writer = self.interceptor_rtp_writer.lock().await;
+            interceptor_rtp_writer.clone()
+        };
+
+        match interceptor_rtp_writer {
+            Some(rtp_writer) => rtp_writer.write(pkt, &Attributes::new()).await,
+            None => Ok(0),
+        }
+    }
+
+    async fn write(&self, b: &[u8]) -> Result<usize> {
+        let pkt = rtp::packet::Packet::unmarshal(&mut &b[..])?;
+        self.write_rtp(&pkt).await
+    }
+}
+
+/// TrackLocalContext is the Context passed when a TrackLocal has been Binded/Unbinded from a PeerConnection
+#[derive(Default, Clone)]
+pub struct TrackLocalContext {
+    pub(crate) id: String,
+    pub(crate) params: RTPParameters,
+    pub(crate) ssrc: SSRC,
+    pub(crate) write_stream: Option<Arc<dyn TrackLocalWriter + Send + Sync>>,
+}
+
+impl TrackLocalContext {
+    /// codec_parameters returns the negotiated RTPCodecParameters. These are the codecs supported by both
+    /// PeerConnections and the SSRC/PayloadTypes
+    pub fn codec_parameters(&self) -> &[RTPCodecParameters] {
+        &self.params.codecs
+    }
+
+    /// header_extensions returns the negotiated RTPHeaderExtensionParameters. These are the header extensions supported by
+    /// both PeerConnections and the SSRC/PayloadTypes
+    pub fn header_extensions(&self) -> &[RTPHeaderExtensionParameter] {
+        &self.params.header_extensions
+    }
+
+    /// ssrc requires the negotiated SSRC of this track
+    /// This track may have multiple if RTX is enabled
+    pub fn ssrc(&self) -> SSRC {
+        self.ssrc
+    }
+
+    /// write_stream returns the write_stream for this TrackLocal. The implementer writes the outbound
+    /// media packets to it
+    pub fn write_stream(&self) -> Option<Arc<dyn TrackLocalWriter + Send + Sync>> {
+        self.write_stream.clone()
+    }
+
+    /// id is a unique identifier that is used for both bind/unbind
+    pub fn id(&self) -> String {
+        self.id.clone()
+    }
+}
+
+/// TrackLocal is an interface that controls how the user can send media
+/// The user can provide their own TrackLocal implementations, or use
+/// the implementations in media::track::track_local
+#[async_trait]
+pub trait TrackLocal {
+    /// bind should implement the way how the media data flows from the Track to the PeerConnection
+    /// This will be called internally after signaling is complete and the list of available
+    /// codecs has been determined
+    async fn bind(&self, t: &TrackLocalContext) -> Result<RTPCodecParameters>;
+
+    /// unbind should implement the teardown logic when the track is no longer needed. This happens
+    /// because a track has been stopped.
+    async fn unbind(&self, t: &TrackLocalContext) -> Result<()>;
+
+    /// id is the unique identifier for this Track. This should be unique for the
+    /// stream, but doesn't have to globally unique. A common example would be 'audio' or 'video'
+    /// and stream_id would be 'desktop' or 'webcam'
+    fn id(&self) -> &str;
+
+    /// rid is the RTP stream identifier of this Track, which tells the simulcast
+    /// layers of a track apart. It is empty if the track isn't a simulcast layer
+    fn rid(&self) -> &str;
+
+    /// stream_id is the group this track belongs too. This must be unique
+    fn stream_id(&self) -> &str;
+
+    /// kind controls if this TrackLocal is audio or video
+    fn kind(&self) -> RTPCodecType;
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+/// TrackBinding is a single bind for a Track
+/// Bind can be called multiple times, this stores the
+/// result for a single bind call so that it can be used when writing
+#[derive(Clone)]
+pub(crate) struct TrackBinding {
+    id: String,
+    ssrc: SSRC,
+    payload_type: PayloadType,
+    write_stream: Option<Arc<dyn TrackLocalWriter + Send + Sync>>,
+}
@@ -0,0 +1,162 @@
+use super::*;
+use crate::error::{flatten_errs, Error};
+use crate::media::rtp::rtp_codec::{
+    codec_parameters_fuzzy_search, CodecMatchType, RTPCodecCapability,
+};
+
+use tokio::sync::Mutex;
+use util::marshal::Unmarshal;
+
+/// TrackLocalStaticRTP  is a TrackLocal that has a pre-set codec and accepts RTP Packets.
+/// If you wish to send a media.Sample use TrackLocalStaticSample
+pub struct TrackLocalStaticRTP {
+    pub(crate) bindings: Mutex<Vec<TrackBinding>>,
+    codec: RTPCodecCapability,
+    id: String,
+    rid: String,
+    stream_id: String,
+}
+
+impl TrackLocalStaticRTP {
+    /// returns a TrackLocalStaticRTP.
+    pub fn new(codec: RTPCodecCapability, id: String, stream_id: String) -> Self {
+        TrackLocalStaticRTP::new_with_rid(codec, id, String::new(), stream_id)
+    }
+
+    /// returns a TrackLocalStaticRTP, which is sent as the simulcast layer of the given rid
+    pub fn new_with_rid(
+        codec: RTPCodecCapability,
+        id: String,
+        rid: String,
+        stream_id: String,
+    ) -> Self {
+        TrackLocalStaticRTP {
+            codec,
+            bindings: Mutex::new(vec![]),
+            id,
+            rid,
+            stream_id,
+        }
+    }
+
+    /// codec gets the Codec of the track
+    pub fn codec(&self) -> RTPCodecCapability {
+        self.codec.clone()
+    }
+}
+
+#[async_trait]
+impl TrackLocal for TrackLocalStaticRTP {
+    /// bind is called by the PeerConnection after negotiation is complete
+    /// This asserts that the code requested is supported by the remote peer.
+    /// If so it setups all the state (SSRC and PayloadType) to have a call
+    async fn bind(&self, t: &TrackLocalContext) -> Result<RTPCodecParameters> {
+        let parameters = RTPCodecParameters {
+            capability: self.codec.clone(),
+            ..Default::default()
+        };
+
+        let (codec, match_type) = codec_parameters_fuzzy_search(&parameters, t.codec_parameters());
+        if match_type == CodecMatchType::None {
+            return Err(Error::ErrUnsupportedCodec.into());
+        }
+
+        let mut bindings = self.bindings.lock().await;
+        bindings.push(TrackBinding {
+            id: t.id(),
+            ssrc: t.ssrc(),
+            payload_type: codec.payload_type,
+            write_stream: t.write_stream(),
+        });
+
+        Ok(codec)
+    }
+
+    /// unbind implements the teardown logic when the track is no longer needed. This happens
+    /// because a track has been stopped.
+    async fn unbind(&self, t: &TrackLocalContext) -> Result<()> {
+        let mut bindings = self.bindings.lock().await;
+        if let Some(index) = bindings.iter().position(|b| b.id == t.id()) {
+            bindings.remove(index);
+            Ok(())
+        } else {
+            Err(Error::ErrUnbindFailed.into())
+        }
+    }
+
+    /// id is the unique identifier for this Track. This should be unique for the
+    /// stream, but doesn't have to globally unique. A common example would be 'audio' or 'video'
+    /// and stream_id would be 'desktop' or 'webcam'
+    fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    /// rid is the RTP stream identifier of this Track, it is empty if the track
+    /// isn't a simulcast layer
+    fn rid(&self) -> &str {
+        self.rid.as_str()
+    }
+
+    /// stream_id is the group this track belongs too. This must be unique
+    fn stream_id(&self) -> &str {
+        self.stream_id.as_str()
+    }
+
+    /// kind controls if this TrackLocal is audio or video
+    fn kind(&self) -> RTPCodecType {
+        if self.codec.mime_type.starts_with("audio/") {
+            RTPCodecType::Audio
+        } else if self.codec.mime_type.starts_with("video/") {
+            RTPCodecType::Video
+        } else {
+            RTPCodecType::Unspecified
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[async_trait]
+impl TrackLocalWriter for TrackLocalStaticRTP {
+    /// write_rtp writes a RTP Packet to the TrackLocalStaticRTP
+    /// If one PeerConnection fails the packets will still be sent to
+    /// all PeerConnections. The error message will contain the ID of the failed
+    /// PeerConnections so you can remove them
+    async fn write_rtp(&self, p: &rtp::packet::Packet) -> Result<usize> {
+        // the bindings are cloned, so a slow PeerConnection doesn't block bind/unbind
+        let bindings = {
+            let bindings = self.bindings.lock().await;
+            bindings.clone()
+        };
+
+        let mut n = 0;
+        let mut write_errs = vec![];
+        let mut pkt = p.clone();
+        for b in bindings {
+            pkt.header.ssrc = b.ssrc;
+            pkt.header.payload_type = b.payload_type;
+
+            if let Some(write_stream) = &b.write_stream {
+                match write_stream.write_rtp(&pkt).await {
+                    Ok(m) => n += m,
+                    Err(err) => write_errs.push(Error::new(format!("{}: {}", b.id, err)).into()),
+                }
+            }
+        }
+
+        flatten_errs(write_errs)?;
+        Ok(n)
+    }
+
+    /// write writes a RTP Packet as a buffer to the TrackLocalStaticRTP
+    /// If one PeerConnection fails the packets will still be sent to
+    /// all PeerConnections. The error message will contain the ID of the failed
+    /// PeerConnections so you can remove them
+    async fn write(&self, b: &[u8]) -> Result<usize> {
+        let pkt = rtp::packet::Packet::unmarshal(&mut &b[..])?;
+        self.write_rtp(&pkt).await?;
+        Ok(b.len())
+    }
+}
@@ -0,0 +1,160 @@
+use super::track_local_static_rtp::TrackLocalStaticRTP;
+use super::*;
+use crate::error::flatten_errs;
+use crate::media::rtp::rtp_codec::RTPCodecCapability;
+use crate::media::Sample;
+use crate::RTP_OUTBOUND_MTU;
+
+use tokio::sync::Mutex;
+
+struct TrackLocalStaticSampleInternal {
+    packetizer: Option<Box<dyn rtp::packetizer::Packetizer + Send + Sync>>,
+    sequencer: Option<Box<dyn rtp::sequence::Sequencer + Send + Sync>>,
+    clock_rate: f64,
+}
+
+/// TrackLocalStaticSample is a TrackLocal that has a pre-set codec and accepts Samples.
+/// If you wish to send a RTP Packet use TrackLocalStaticRTP
+pub struct TrackLocalStaticSample {
+    rtp_track: TrackLocalStaticRTP,
+    internal: Mutex<TrackLocalStaticSampleInternal>,
+}
+
+impl TrackLocalStaticSample {
+    /// returns a TrackLocalStaticSample
+    pub fn new(codec: RTPCodecCapability, id: String, stream_id: String) -> Self {
+        TrackLocalStaticSample::new_with_rid(codec, id, String::new(), stream_id)
+    }
+
+    /// returns a TrackLocalStaticSample, which is sent as the simulcast layer of the given rid
+    pub fn new_with_rid(
+        codec: RTPCodecCapability,
+        id: String,
+        rid: String,
+        stream_id: String,
+    ) -> Self {
+        let rtp_track = TrackLocalStaticRTP::new_with_rid(codec, id, rid, stream_id);
+
+        TrackLocalStaticSample {
+            rtp_track,
+            internal: Mutex::new(TrackLocalStaticSampleInternal {
+                packetizer: None,
+                sequencer: None,
+                clock_rate: 0.0f64,
+            }),
+        }
+    }
+
+    /// codec gets the Codec of the track
+    pub fn codec(&self) -> RTPCodecCapability {
+        self.rtp_track.codec()
+    }
+
+    /// write_sample writes a Sample to the TrackLocalStaticSample
+    /// If one PeerConnection fails the packets will still be sent to
+    /// all PeerConnections. The error message will contain the ID of the failed
+    /// PeerConnections so you can remove them
+    pub async fn write_sample(&self, sample: &Sample) -> Result<()> {
+        let packets = {
+            let mut internal = self.internal.lock().await;
+
+            // the track hasn't been bound to a PeerConnection yet
+            if internal.packetizer.is_none() || internal.sequencer.is_none() {
+                return Ok(());
+            }
+
+            // skip packets by the number of previously dropped packets
+            if let Some(sequencer) = &internal.sequencer {
+                for _ in 0..sample.prev_dropped_packets {
+                    sequencer.next_sequence_number();
+                }
+            }
+
+            let clock_rate = internal.clock_rate;
+            if let Some(packetizer) = &mut internal.packetizer {
+                let samples = (sample.duration.as_secs_f64() * clock_rate) as u32;
+                if sample.prev_dropped_packets > 0 {
+                    packetizer.skip_samples(samples * sample.prev_dropped_packets as u32);
+                }
+                packetizer.packetize(&sample.data, samples)?
+            } else {
+                vec![]
+            }
+        };
+
+        let mut write_errs = vec![];
+        for p in packets {
+            if let Err(err) = self.rtp_track.write_rtp(&p).await {
+                write_errs.push(err);
+            }
+        }
+
+        flatten_errs(write_errs)
+    }
+}
+
+#[async_trait]
+impl TrackLocal for TrackLocalStaticSample {
+    /// bind is called by the PeerConnection after negotiation is complete
+    /// This asserts that the code requested is supported by the remote peer.
+    /// If so it setups all the state (SSRC and PayloadType) to have a call
+    async fn bind(&self, t: &TrackLocalContext) -> Result<RTPCodecParameters> {
+        let codec = self.rtp_track.bind(t).await?;
+
+        let mut internal = self.internal.lock().await;
+
+        // We only need one packetizer
+        if internal.packetizer.is_some() {
+            return Ok(codec);
+        }
+
+        let payloader = codec.capability.payloader_for_codec()?;
+        let sequencer: Box<dyn rtp::sequence::Sequencer + Send + Sync> =
+            Box::new(rtp::sequence::new_random_sequencer());
+        internal.packetizer = Some(Box::new(rtp::packetizer::new_packetizer(
+            RTP_OUTBOUND_MTU,
+            0, // Value is handled when writing
+            0, // Value is handled when writing
+            payloader,
+            sequencer.clone(),
+            codec.capability.clock_rate,
+        )));
+        internal.sequencer = Some(sequencer);
+        internal.clock_rate = codec.capability.clock_rate as f64;
+
+        Ok(codec)
+    }
+
+    /// unbind implements the teardown logic when the track is no longer needed. This happens
+    /// because a track has been stopped.
+    async fn unbind(&self, t: &TrackLocalContext) -> Result<()> {
+        self.rtp_track.unbind(t).await
+    }
+
+    /// id is the unique identifier for this Track. This should be unique for the
+    /// stream, but doesn't have to globally unique. A common example would be 'audio' or 'video'
+    /// and stream_id would be 'desktop' or 'webcam'
+    fn id(&self) -> &str {
+        self.rtp_track.id()
+    }
+
+    /// rid is the RTP stream identifier of this Track, it is empty if the track
+    /// isn't a simulcast layer
+    fn rid(&self) -> &str {
+        self.rtp_track.rid()
+    }
+
+    /// stream_id is the group this track belongs too. This must be unique
+    fn stream_id(&self) -> &str {
+        self.rtp_track.stream_id()
+    }
+
+    /// kind controls if this TrackLocal is audio or video
+    fn kind(&self) -> RTPCodecType {
+        self.rtp_track.kind()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
@@ -0,0 +1,174 @@
+use super::track_local_static_rtp::*;
+use super::track_local_static_sample::*;
+use super::*;
+use crate::api::media_engine::{MIME_TYPE_OPUS, MIME_TYPE_VP8};
+use crate::error::Error;
+use crate::media::rtp::rtp_codec::RTPCodecCapability;
+use crate::media::Sample;
+
+use bytes::Bytes;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use util::marshal::Unmarshal;
+
+#[derive(Default)]
+struct RecordingWriter {
+    packets: Mutex<Vec<rtp::packet::Packet>>,
+}
+
+#[async_trait]
+impl TrackLocalWriter for RecordingWriter {
+    async fn write_rtp(&self, p: &rtp::packet::Packet) -> Result<usize> {
+        let mut packets = self.packets.lock().await;
+        packets.push(p.clone());
+        Ok(p.payload.len())
+    }
+
+    async fn write(&self, b: &[u8]) -> Result<usize> {
+        let pkt = rtp::packet::Packet::unmarshal(&mut &b[..])?;
+        self.write_rtp(&pkt).await
+    }
+}
+
+fn vp8_context(id: &str, ssrc: SSRC, writer: Arc<RecordingWriter>) -> TrackLocalContext {
+    TrackLocalContext {
+        id: id.to_owned(),
+        params: RTPParameters {
+            header_extensions: vec![],
+            codecs: vec![RTPCodecParameters {
+                capability: RTPCodecCapability {
+                    mime_type: MIME_TYPE_VP8.to_owned(),
+                    clock_rate: 90000,
+                    ..Default::default()
+                },
+                payload_type: 96,
+                ..Default::default()
+            }],
+        },
+        ssrc,
+        write_stream: Some(writer),
+    }
+}
+
+#[tokio::test]
+async fn test_track_local_static_rtp_bind_unsupported_codec() -> Result<()> {
+    let track = TrackLocalStaticRTP::new(
+        RTPCodecCapability {
+            mime_type: MIME_TYPE_OPUS.to_owned(),
+            ..Default::default()
+        },
+        "audio".to_owned(),
+        "webrtc-rs".to_owned(),
+    );
+    assert_eq!(track.kind(), RTPCodecType::Audio);
+
+    let writer = Arc::new(RecordingWriter::default());
+    if let Err(err) = track.bind(&vp8_context("a", 1234, writer)).await {
+        assert!(Error::ErrUnsupportedCodec.equal(&err));
+    } else {
+        panic!("expected error, but got ok");
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_track_local_static_rtp_rewrites_ssrc_and_payload_type() -> Result<()> {
+    let track = TrackLocalStaticRTP::new(
+        RTPCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    );
+    assert_eq!(track.kind(), RTPCodecType::Video);
+
+    let writer_a = Arc::new(RecordingWriter::default());
+    let writer_b = Arc::new(RecordingWriter::default());
+    let context_a = vp8_context("a", 1111, Arc::clone(&writer_a));
+    let context_b = vp8_context("b", 2222, Arc::clone(&writer_b));
+
+    let codec = track.bind(&context_a).await?;
+    assert_eq!(codec.payload_type, 96);
+    track.bind(&context_b).await?;
+
+    let mut pkt = rtp::packet::Packet::default();
+    pkt.header.ssrc = 5000;
+    pkt.header.payload_type = 111;
+    pkt.payload = Bytes::from_static(&[0x00, 0x01, 0x02]);
+    track.write_rtp(&pkt).await?;
+
+    {
+        let packets = writer_a.packets.lock().await;
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].header.ssrc, 1111);
+        assert_eq!(packets[0].header.payload_type, 96);
+    }
+    {
+        let packets = writer_b.packets.lock().await;
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].header.ssrc, 2222);
+    }
+
+    // once unbound, a PeerConnection doesn't get packets anymore
+    track.unbind(&context_b).await?;
+    track.write_rtp(&pkt).await?;
+    {
+        let packets = writer_b.packets.lock().await;
+        assert_eq!(packets.len(), 1);
+    }
+
+    if let Err(err) = track.unbind(&context_b).await {
+        assert!(Error::ErrUnbindFailed.equal(&err));
+    } else {
+        panic!("expected error, but got ok");
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_track_local_static_sample_packetizes() -> Result<()> {
+    let track = TrackLocalStaticSample::new(
+        RTPCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    );
+
+    let sample = Sample {
+        data: Bytes::from(vec![0xAAu8; 3000]),
+        duration: Duration::from_millis(33),
+        ..Default::default()
+    };
+
+    // writing before the track is bound is a no-op
+    track.write_sample(&sample).await?;
+
+    let writer = Arc::new(RecordingWriter::default());
+    track
+        .bind(&vp8_context("a", 1234, Arc::clone(&writer)))
+        .await?;
+    track.write_sample(&sample).await?;
+
+    let packets = writer.packets.lock().await;
+    assert!(
+        packets.len() > 1,
+        "a large sample is split over several packets"
+    );
+    for (i, p) in packets.iter().enumerate() {
+        assert_eq!(p.header.ssrc, 1234);
+        assert_eq!(p.header.payload_type, 96);
+        assert_eq!(p.header.marker, i == packets.len() - 1);
+        assert_eq!(p.header.timestamp, packets[0].header.timestamp);
+        assert_eq!(
+            p.header.sequence_number,
+            packets[0].header.sequence_number.wrapping_add(i as u16)
+        );
+    }
+
+    Ok(())
+}
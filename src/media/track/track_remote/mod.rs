@@ -0,0 +1,314 @@
+use crate::error::Error;
+use crate::interceptor::{Attributes, RTPReader};
+use crate::media::rtp::rtp_codec::{RTPCodecParameters, RTPCodecType, RTPParameters};
+use crate::media::rtp::{PayloadType, SSRC};
+use crate::RECEIVE_MTU;
+
+use anyhow::Result;
+use bytes::Bytes;
+use std::sync::atomic::{AtomicU32, AtomicU8, Ordering};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use util::marshal::Unmarshal;
+
+/// TrackRemote represents a single inbound source of media
+pub struct TrackRemote {
+    id: Mutex<String>,
+    stream_id: Mutex<String>,
+
+    payload_type: AtomicU8, //PayloadType,
+    kind: AtomicU8,         //RTPCodecType,
+    ssrc: AtomicU32,        //SSRC,
+    codec: Mutex<RTPCodecParameters>,
+    params: Mutex<RTPParameters>,
+    rid: String,
+
+    pub(crate) rtp_interceptor: Mutex<Option<Arc<dyn RTPReader + Send + Sync>>>,
+    peeked: Mutex<Option<Bytes>>,
+}
+
+impl TrackRemote {
+    pub(crate) fn new(
+        kind: RTPCodecType,
+        ssrc: SSRC,
+        rid: String,
+        rtp_interceptor: Option<Arc<dyn RTPReader + Send + Sync>>,
+    ) -> Self {
+        TrackRemote {
+            id: Mutex::new(String::new()),
+            stream_id: Mutex::new(String::new()),
+
+            payload_type: AtomicU8::new(0),
+            kind: AtomicU8::new(kind as u8),
+            ssrc: AtomicU32::new(ssrc),
+            codec: Mutex::new(RTPCodecParameters::default()),
+            params: Mutex::new(RTPParameters::default()),
+            rid,
+
+            rtp_interceptor: Mutex::new(rtp_interceptor),
+            peeked: Mutex::new(None),
+        }
+    }
+
+    /// id is the unique identifier for this Track. This should be unique for the
+    /// stream, but doesn't have to globally unique. A common example would be 'audio' or 'video'
+    /// and stream_id would be 'desktop' or 'webcam'
+    pub async fn id(&self) -> String {
+        let id = self.id.lock().await;
+        id.clone()
+    }
+
+    pub(crate) async fn set_id(&self, s: String) {
+        let mut id = self.id.lock().await;
+        *id = s;
+    }
+
+    /// stream_id is the group this track belongs too. This must be unique
+    pub async fn stream_id(&self) -> String {
+        let stream_id = self.stream_id.lock().await;
+        stream_id.clone()
+    }
+
+    pub(crate) async fn set_stream_id(&self, s: String) {
+        let mut stream_id = self.stream_id.lock().await;
+        *stream_id = s;
+    }
+
+    /// rid gets the RTP Stream ID of this Track
+    /// With Simulcast you will have multiple tracks with the same ID, but different RID values.
+    /// In many cases a TrackRemote will not have an RID, so it is important to assert it is non-zero
+    pub fn rid(&self) -> &str {
+        self.rid.as_str()
+    }
+
+    /// payload_type gets the PayloadType of the track
+    pub fn payload_type(&self) -> PayloadType {
+        self.payload_type.load(Ordering::SeqCst)
+    }
+
+    /// kind gets the Kind of the track
+    pub fn kind(&self) -> RTPCodecType {
+        self.kind.load(Ordering::SeqCst).into()
+    }
+
+    pub(crate) fn set_kind(&self, kind: RTPCodecType) {
+        self.kind.store(kind as u8, Ordering::SeqCst);
+    }
+
+    /// ssrc gets the SSRC of the track
+    pub fn ssrc(&self) -> SSRC {
+        self.ssrc.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn set_ssrc(&self, ssrc: SSRC) {
+        self.ssrc.store(ssrc, Ordering::SeqCst);
+    }
+
+    /// msid gets the Msid of the track
+    pub async fn msid(&self) -> String {
+        format!("{} {}", self.stream_id().await, self.id().await)
+    }
+
+    /// codec gets the Codec of the track
+    pub async fn codec(&self) -> RTPCodecParameters {
+        let codec = self.codec.lock().await;
+        codec.clone()
+    }
+
+    /// params gets the negotiated RTPParameters of the track
+    pub async fn params(&self) -> RTPParameters {
+        let params = self.params.lock().await;
+        params.clone()
+    }
+
+    /// set_params updates the codec, its payload type and the RTPParameters
+    /// once the payload type of the track is known
+    pub(crate) async fn set_params(&self, params: RTPParameters) {
+        if let Some(codec) = params.codecs.first() {
+            self.payload_type
+                .store(codec.payload_type, Ordering::SeqCst);
+            let mut c = self.codec.lock().await;
+            *c = codec.clone();
+        }
+
+        let mut p = self.params.lock().await;
+        *p = params;
+    }
+
+    /// read reads data from the track.
+    pub async fn read(&self, b: &mut [u8]) -> Result<usize> {
+        let peeked = {
+            let mut peeked = self.peeked.lock().await;
+            peeked.take()
+        };
+
+        // someone else may have stolen our packet when we
+        // released the lock. Deal with it.
+        if let Some(data) = peeked {
+            let n = std::cmp::min(b.len(), data.len());
+            b[..n].copy_from_slice(&data[..n]);
+            return Ok(n);
+        }
+
+        let rtp_interceptor = {
+            let rtp_interceptor = self.rtp_interceptor.lock().await;
+            rtp_interceptor.clone()
+        };
+
+        // RTPReceiver::stop closes the stream, which unblocks any pending read
+        match rtp_interceptor {
+            Some(rtp_interceptor) => {
+                let (n, _) = rtp_interceptor.read(b, &Attributes::new()).await?;
+                Ok(n)
+            }
+            None => Err(Error::ErrRTPReceiverForSSRCTrackStreamNotFound.into()),
+        }
+    }
+
+    /// read_rtp is a convenience method that wraps read and unmarshals for you.
+    pub async fn read_rtp(&self) -> Result<rtp::packet::Packet> {
+        let mut b = vec![0u8; RECEIVE_MTU];
+        let n = self.read(&mut b).await?;
+
+        rtp::packet::Packet::unmarshal(&mut &b[..n])
+    }
+
+    /// determine_payload_type blocks and reads a single packet to determine the PayloadType for this Track
+    /// this is useful because we can't announce it to the user until we know the payload_type
+    pub(crate) async fn determine_payload_type(&self) -> Result<()> {
+        let mut b = vec![0u8; RECEIVE_MTU];
+        let n = self.peek(&mut b).await?;
+
+        let r = rtp::packet::Packet::unmarshal(&mut &b[..n])?;
+        self.payload_type
+            .store(r.header.payload_type, Ordering::SeqCst);
+
+        Ok(())
+    }
+
+    /// peek is like read, but it doesn't discard the packet read
+    pub(crate) async fn peek(&self, b: &mut [u8]) -> Result<usize> {
+        let n = self.read(b).await?;
+
+        // this might overwrite data if somebody peeked between the read
+        // and us getting the lock. Oh well, we'll just drop a packet in
+        // that case.
+        let mut peeked = self.peeked.lock().await;
+        *peeked = Some(Bytes::copy_from_slice(&b[..n]));
+
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::api::media_engine::MIME_TYPE_OPUS;
+    use crate::media::rtp::rtp_codec::RTPCodecCapability;
+
+    use async_trait::async_trait;
+    use util::marshal::Marshal;
+
+    /// PacketReader reads the packets it was created with, one per read
+    struct PacketReader {
+        packets: Mutex<Vec<Bytes>>,
+    }
+
+    #[async_trait]
+    impl RTPReader for PacketReader {
+        async fn read(&self, buf: &mut [u8], a: &Attributes) -> Result<(usize, Attributes)> {
+            let mut packets = self.packets.lock().await;
+            if packets.is_empty() {
+                return Err(Error::ErrIoEOF.into());
+            }
+            let packet = packets.remove(0);
+            buf[..packet.len()].copy_from_slice(&packet);
+            Ok((packet.len(), a.clone()))
+        }
+    }
+
+    fn new_packet(payload_type: PayloadType, sequence_number: u16) -> rtp::packet::Packet {
+        rtp::packet::Packet {
+            header: rtp::header::Header {
+                version: 2,
+                payload_type,
+                sequence_number,
+                ssrc: 1234,
+                ..Default::default()
+            },
+            payload: Bytes::from_static(&[0x01, 0x02, 0x03]),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_track_remote_params() -> Result<()> {
+        let track = TrackRemote::new(RTPCodecType::Audio, 1234, String::new(), None);
+        track.set_id("audio".to_owned()).await;
+        track.set_stream_id("webrtc-rs".to_owned()).await;
+        assert_eq!(track.msid().await, "webrtc-rs audio");
+        assert_eq!(track.ssrc(), 1234);
+        assert_eq!(track.kind(), RTPCodecType::Audio);
+
+        let codec = RTPCodecParameters {
+            capability: RTPCodecCapability {
+                mime_type: MIME_TYPE_OPUS.to_owned(),
+                clock_rate: 48000,
+                channels: 2,
+                ..Default::default()
+            },
+            payload_type: 111,
+            ..Default::default()
+        };
+        track
+            .set_params(RTPParameters {
+                header_extensions: vec![],
+                codecs: vec![codec.clone()],
+            })
+            .await;
+        assert_eq!(track.codec().await, codec);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_track_remote_read_without_stream() -> Result<()> {
+        let track = TrackRemote::new(RTPCodecType::Video, 0, "f".to_owned(), None);
+
+        let mut b = vec![0u8; RECEIVE_MTU];
+        if let Err(err) = track.read(&mut b).await {
+            assert!(Error::ErrRTPReceiverForSSRCTrackStreamNotFound.equal(&err));
+        } else {
+            panic!("expected error, but got ok");
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_track_remote_read_rtp() -> Result<()> {
+        let first = new_packet(96, 1);
+        let second = new_packet(96, 2);
+        let reader = Arc::new(PacketReader {
+            packets: Mutex::new(vec![first.marshal()?, second.marshal()?]),
+        });
+        let track = TrackRemote::new(RTPCodecType::Video, 1234, String::new(), Some(reader));
+
+        // the packet peeked for the payload type is read again
+        track.determine_payload_type().await?;
+        assert_eq!(track.payload_type(), 96);
+
+        let mut b = vec![0u8; RECEIVE_MTU];
+        let n = track.read(&mut b).await?;
+        assert_eq!(&b[..n], &first.marshal()?[..]);
+
+        assert_eq!(track.read_rtp().await?, second);
+
+        if let Err(err) = track.read_rtp().await {
+            assert!(Error::ErrIoEOF.equal(&err));
+        } else {
+            panic!("expected error, but got ok");
+        }
+
+        Ok(())
+    }
+}
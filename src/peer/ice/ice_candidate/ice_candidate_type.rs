@@ -3,8 +3,9 @@ use serde::{Deserialize, Serialize};
 use std::fmt;
 
 /// ICECandidateType represents the type of the ICE candidate used.
-#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub enum ICECandidateType {
+    #[default]
     Unspecified,
 
     /// ICECandidateTypeHost indicates that the candidate is of Host type as
@@ -34,12 +35,6 @@ pub enum ICECandidateType {
     Relay,
 }
 
-impl Default for ICECandidateType {
-    fn default() -> Self {
-        ICECandidateType::Unspecified
-    }
-}
-
 const ICE_CANDIDATE_TYPE_HOST_STR: &str = "Host";
 const ICE_CANDIDATE_TYPE_SRFLX_STR: &str = "Srflx";
 const ICE_CANDIDATE_TYPE_PRFLX_STR: &str = "Prflx";
@@ -65,6 +65,19 @@ impl From<&Arc<dyn Candidate + Send + Sync>> for ICECandidate {
 }
 
 impl ICECandidate {
+    /// to_json returns an ICECandidateInit
+    /// as indicated by the spec https://w3c.github.io/webrtc-pc/#dom-rtcicecandidate-tojson
+    pub async fn to_json(&self) -> Result<ICECandidateInit> {
+        let candidate = self.to_ice().await?;
+
+        Ok(ICECandidateInit {
+            candidate: format!("candidate:{}", candidate.marshal()),
+            sdp_mid: String::new(),
+            sdp_mline_index: 0,
+            username_fragment: String::new(),
+        })
+    }
+
     pub(crate) async fn to_ice(&self) -> Result<impl Candidate> {
         let candidate_id = self.stats_id.clone();
         let c = match self.typ {
@@ -144,6 +157,64 @@ impl ICECandidate {
     }
 }
 
+/// unmarshal_candidate parses the string representation of a remote ICE candidate,
+/// as carried by an a=candidate attribute, without its "candidate:" prefix
+pub(crate) fn unmarshal_candidate(raw: &str) -> Result<ICECandidate> {
+    let split: Vec<&str> = raw.split_whitespace().collect();
+    if split.len() < 8 {
+        return Err(Error::new(format!(
+            "attribute not long enough to be ICE candidate ({})",
+            split.len()
+        ))
+        .into());
+    }
+
+    let mut related_address = String::new();
+    let mut related_port = 0;
+    let mut tcp_type = String::new();
+    let mut extensions = split[8..].iter();
+    while let Some(key) = extensions.next() {
+        let value = match extensions.next() {
+            Some(value) => *value,
+            None => return Err(Error::new(format!("ICE candidate {} has no value", key)).into()),
+        };
+        match *key {
+            "raddr" => related_address = value.to_owned(),
+            "rport" => related_port = value.parse()?,
+            "tcptype" => tcp_type = value.to_owned(),
+            _ => {}
+        }
+    }
+
+    // the protocol and the type are lowercase in SDP
+    let protocol = match split[2].to_lowercase().as_str() {
+        "udp" => ICEProtocol::Udp,
+        "tcp" => ICEProtocol::Tcp,
+        _ => return Err(Error::ErrICEProtocolUnknown.into()),
+    };
+    let typ = match split[7].to_lowercase().as_str() {
+        "host" => ICECandidateType::Host,
+        "srflx" => ICECandidateType::Srflx,
+        "prflx" => ICECandidateType::Prflx,
+        "relay" => ICECandidateType::Relay,
+        _ => return Err(Error::ErrICECandidateTypeUnknown.into()),
+    };
+
+    Ok(ICECandidate {
+        foundation: split[0].to_owned(),
+        component: split[1].parse()?,
+        protocol,
+        priority: split[3].parse()?,
+        address: split[4].to_owned(),
+        port: split[5].parse()?,
+        typ,
+        related_address,
+        related_port,
+        tcp_type,
+        ..Default::default()
+    })
+}
+
 impl fmt::Display for ICECandidate {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -163,23 +234,3 @@ pub struct ICECandidateInit {
     pub username_fragment: String,
 }
 
-/*TODO:
-// ToJSON returns an ICECandidateInit
-// as indicated by the spec https://w3c.github.io/webrtc-pc/#dom-rtcicecandidate-tojson
-func (c ICECandidate) ToJSON() ICECandidateInit {
-    zeroVal := uint16(0)
-    emptyStr := ""
-    candidateStr := ""
-
-    candidate, err := c.toICE()
-    if err == nil {
-        candidateStr = candidate.Marshal()
-    }
-
-    return ICECandidateInit{
-        Candidate:     fmt.Sprintf("candidate:%s", candidateStr),
-        SDPMid:        &emptyStr,
-        SDPMLineIndex: &zeroVal,
-    }
-}
-*/
@@ -2,12 +2,13 @@ use std::fmt;
 
 /// ICECredentialType indicates the type of credentials used to connect to
 /// an ICE server.
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
 pub enum ICECredentialType {
     Unspecified,
 
     /// ICECredential::Password describes username and password based
     /// credentials as described in https://tools.ietf.org/html/rfc5389.
+    #[default]
     Password,
 
     /// ICECredential::Oauth describes token based credential as described
@@ -16,12 +17,6 @@ pub enum ICECredentialType {
     Oauth,
 }
 
-impl Default for ICECredentialType {
-    fn default() -> Self {
-        ICECredentialType::Password
-    }
-}
-
 const ICE_CREDENTIAL_TYPE_PASSWORD_STR: &str = "Password";
 const ICE_CREDENTIAL_TYPE_OAUTH_STR: &str = "Oauth";
 
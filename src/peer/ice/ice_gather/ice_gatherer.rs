@@ -15,6 +15,7 @@ use std::future::Future;
 use std::pin::Pin;
 use std::sync::atomic::{AtomicU8, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
 
 pub type OnLocalCandidateHdlrFn = Box<
@@ -36,14 +37,14 @@ pub type OnGatheringCompleteHdlrFn =
 /// candidates, as well as enabling the retrieval of local Interactive
 /// Connectivity Establishment (ICE) parameters which can be
 /// exchanged in signaling.
-#[derive(Default, Clone)]
+#[derive(Default)]
 pub struct ICEGatherer {
     pub(crate) validated_servers: Vec<Url>,
     pub(crate) gather_policy: ICETransportPolicy,
     pub(crate) setting_engine: SettingEngine,
 
     pub(crate) state: Arc<AtomicU8>, //ICEGathererState,
-    pub(crate) agent: Option<Arc<ice::agent::Agent>>,
+    pub(crate) agent: Mutex<Option<Arc<ice::agent::Agent>>>,
 
     pub(crate) on_local_candidate_handler: Arc<Mutex<Option<OnLocalCandidateHdlrFn>>>,
     pub(crate) on_state_change_handler: Arc<Mutex<Option<OnStateChangeHdlrFn>>>,
@@ -52,6 +53,16 @@ pub struct ICEGatherer {
     pub(crate) on_gathering_complete_handler: Arc<Mutex<Option<OnGatheringCompleteHdlrFn>>>,
 }
 
+/// timeout_or_default leaves the timeouts the SettingEngine doesn't set to the defaults
+/// of the agent
+fn timeout_or_default(timeout: Duration) -> Option<Duration> {
+    if timeout == Duration::from_secs(0) {
+        None
+    } else {
+        Some(timeout)
+    }
+}
+
 impl ICEGatherer {
     pub fn new(
         validated_servers: Vec<Url>,
@@ -67,8 +78,9 @@ impl ICEGatherer {
         }
     }
 
-    pub(crate) async fn create_agent(&mut self) -> Result<()> {
-        if self.agent.is_some() || self.state() != ICEGathererState::New {
+    pub(crate) async fn create_agent(&self) -> Result<()> {
+        let mut agent = self.agent.lock().await;
+        if agent.is_some() || self.state() != ICEGathererState::New {
             return Ok(());
         }
 
@@ -98,27 +110,31 @@ impl ICEGatherer {
             urls: self.validated_servers.clone(),
             port_min: self.setting_engine.ephemeral_udp.port_min,
             port_max: self.setting_engine.ephemeral_udp.port_max,
-            disconnected_timeout: Some(self.setting_engine.timeout.ice_disconnected_timeout),
-            failed_timeout: Some(self.setting_engine.timeout.ice_failed_timeout),
-            keepalive_interval: Some(self.setting_engine.timeout.ice_keepalive_interval),
+            disconnected_timeout: timeout_or_default(
+                self.setting_engine.timeout.ice_disconnected_timeout,
+            ),
+            failed_timeout: timeout_or_default(self.setting_engine.timeout.ice_failed_timeout),
+            keepalive_interval: timeout_or_default(
+                self.setting_engine.timeout.ice_keepalive_interval,
+            ),
             //LoggerFactory:          self.setting_engine.LoggerFactory,
             candidate_types,
-            host_acceptance_min_wait: Some(
+            host_acceptance_min_wait: timeout_or_default(
                 self.setting_engine.timeout.ice_host_acceptance_min_wait,
             ),
-            srflx_acceptance_min_wait: Some(
+            srflx_acceptance_min_wait: timeout_or_default(
                 self.setting_engine.timeout.ice_srflx_acceptance_min_wait,
             ),
-            prflx_acceptance_min_wait: Some(
+            prflx_acceptance_min_wait: timeout_or_default(
                 self.setting_engine.timeout.ice_prflx_acceptance_min_wait,
             ),
-            relay_acceptance_min_wait: Some(
+            relay_acceptance_min_wait: timeout_or_default(
                 self.setting_engine.timeout.ice_relay_acceptance_min_wait,
             ),
             interface_filter: self.setting_engine.candidates.interface_filter.clone(),
             nat_1to1_ips: self.setting_engine.candidates.nat_1to1_ips.clone(),
             nat_1to1_ip_candidate_type: nat_1to1_cand_type,
-            net: self.setting_engine.net.take(),
+            net: self.setting_engine.net.clone(),
             multicast_dns_mode: mdns_mode,
             multicast_dns_host_name: self
                 .setting_engine
@@ -142,18 +158,17 @@ impl ICEGatherer {
 
         config.network_types.extend(requested_network_types);
 
-        let agent = ice::agent::Agent::new(config).await?;
-        self.agent = Some(Arc::new(agent));
+        *agent = Some(Arc::new(ice::agent::Agent::new(config).await?));
 
         Ok(())
     }
 
     /// Gather ICE candidates.
-    pub async fn gather(&mut self) -> Result<()> {
+    pub async fn gather(&self) -> Result<()> {
         self.create_agent().await?;
         self.set_state(ICEGathererState::Gathering).await;
 
-        if let Some(agent) = &self.agent {
+        if let Some(agent) = self.get_agent().await {
             let state = Arc::clone(&self.state);
             let on_local_candidate_handler = Arc::clone(&self.on_local_candidate_handler);
             let on_state_change_handler = Arc::clone(&self.on_state_change_handler);
@@ -218,8 +233,13 @@ impl ICEGatherer {
     }
 
     /// Close prunes all local candidates, and closes the ports.
-    pub async fn close(&mut self) -> Result<()> {
-        if let Some(agent) = self.agent.take() {
+    pub async fn close(&self) -> Result<()> {
+        let agent = {
+            let mut agent = self.agent.lock().await;
+            agent.take()
+        };
+
+        if let Some(agent) = agent {
             agent.close().await?;
             self.set_state(ICEGathererState::Closed).await;
         }
@@ -228,10 +248,10 @@ impl ICEGatherer {
     }
 
     /// get_local_parameters returns the ICE parameters of the ICEGatherer.
-    pub async fn get_local_parameters(&mut self) -> Result<ICEParameters> {
+    pub async fn get_local_parameters(&self) -> Result<ICEParameters> {
         self.create_agent().await?;
 
-        let (frag, pwd) = if let Some(agent) = &self.agent {
+        let (frag, pwd) = if let Some(agent) = self.get_agent().await {
             agent.get_local_user_credentials().await
         } else {
             return Err(Error::ErrICEAgentNotExist.into());
@@ -245,10 +265,10 @@ impl ICEGatherer {
     }
 
     /// get_local_candidates returns the sequence of valid local candidates associated with the ICEGatherer.
-    pub async fn get_local_candidates(&mut self) -> Result<Vec<ICECandidate>> {
+    pub async fn get_local_candidates(&self) -> Result<Vec<ICECandidate>> {
         self.create_agent().await?;
 
-        let ice_candidates = if let Some(agent) = &self.agent {
+        let ice_candidates = if let Some(agent) = self.get_agent().await {
             agent.get_local_candidates().await?
         } else {
             return Err(Error::ErrICEAgentNotExist.into());
@@ -290,8 +310,9 @@ impl ICEGatherer {
         }
     }
 
-    pub(crate) fn get_agent(&self) -> Option<Arc<Agent>> {
-        self.agent.clone()
+    pub(crate) async fn get_agent(&self) -> Option<Arc<Agent>> {
+        let agent = self.agent.lock().await;
+        agent.clone()
     }
 
     /*TODO:
@@ -430,7 +451,7 @@ mod test {
             ..Default::default()
         };
 
-        let mut gatherer = ApiBuilder::new().build().new_ice_gatherer(opts)?;
+        let gatherer = ApiBuilder::new().build().new_ice_gatherer(opts)?;
 
         assert_eq!(
             gatherer.state(),
@@ -477,7 +498,7 @@ mod test {
         let mut s = SettingEngine::default();
         s.set_ice_multicast_dns_mode(ice::mdns::MulticastDnsMode::QueryAndGather);
 
-        let mut gatherer = ApiBuilder::new()
+        let gatherer = ApiBuilder::new()
             .with_setting_engine(s)
             .build()
             .new_ice_gatherer(ICEGatherOptions::default())?;
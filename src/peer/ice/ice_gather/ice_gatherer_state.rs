@@ -1,8 +1,9 @@
 use std::fmt;
 
 /// ICEGathererState represents the current state of the ICE gatherer.
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
 pub enum ICEGathererState {
+    #[default]
     Unspecified,
 
     /// ICEGathererStateNew indicates object has been created but
@@ -21,12 +22,6 @@ pub enum ICEGathererState {
     Closed,
 }
 
-impl Default for ICEGathererState {
-    fn default() -> Self {
-        ICEGathererState::Unspecified
-    }
-}
-
 const ICE_GATHERED_STATE_NEW_STR: &str = "New";
 const ICE_GATHERED_STATE_GATHERING_STR: &str = "Gathering";
 const ICE_GATHERED_STATE_COMPLETE_STR: &str = "Complete";
@@ -1,8 +1,9 @@
 use std::fmt;
 
 /// ICEGatheringState describes the state of the candidate gathering process.
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
 pub enum ICEGatheringState {
+    #[default]
     Unspecified,
 
     /// ICEGatheringStateNew indicates that any of the ICETransports are
@@ -19,12 +20,6 @@ pub enum ICEGatheringState {
     Complete,
 }
 
-impl Default for ICEGatheringState {
-    fn default() -> Self {
-        ICEGatheringState::Unspecified
-    }
-}
-
 const ICE_GATHERING_STATE_NEW_STR: &str = "New";
 const ICE_GATHERING_STATE_GATHERING_STR: &str = "Gathering";
 const ICE_GATHERING_STATE_COMPLETE_STR: &str = "Complete";
@@ -3,8 +3,9 @@ use std::fmt;
 
 /// ICEProtocol indicates the transport protocol type that is used in the
 /// ice.URL structure.
-#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub enum ICEProtocol {
+    #[default]
     Unspecified,
 
     /// UDP indicates the URL uses a UDP transport.
@@ -14,12 +15,6 @@ pub enum ICEProtocol {
     Tcp,
 }
 
-impl Default for ICEProtocol {
-    fn default() -> Self {
-        ICEProtocol::Unspecified
-    }
-}
-
 const ICE_PROTOCOL_UDP_STR: &str = "Udp";
 const ICE_PROTOCOL_TCP_STR: &str = "Tcp";
 
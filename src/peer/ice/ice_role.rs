@@ -2,8 +2,9 @@ use std::fmt;
 
 /// ICERole describes the role ice.Agent is playing in selecting the
 /// preferred the candidate pair.
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
 pub enum ICERole {
+    #[default]
     Unspecified,
 
     /// ICERoleControlling indicates that the ICE agent that is responsible
@@ -17,12 +18,6 @@ pub enum ICERole {
     Controlled,
 }
 
-impl Default for ICERole {
-    fn default() -> Self {
-        ICERole::Unspecified
-    }
-}
-
 const ICE_ROLE_CONTROLLING_STR: &str = "Controlling";
 const ICE_ROLE_CONTROLLED_STR: &str = "Controlled";
 
@@ -36,6 +31,16 @@ impl From<&str> for ICERole {
     }
 }
 
+impl From<u8> for ICERole {
+    fn from(v: u8) -> Self {
+        match v {
+            1 => ICERole::Controlling,
+            2 => ICERole::Controlled,
+            _ => ICERole::Unspecified,
+        }
+    }
+}
+
 impl fmt::Display for ICERole {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
@@ -150,7 +150,7 @@ mod test {
                     err
                 );
             } else {
-                assert!(false, "expected error, but got ok");
+                panic!("expected error, but got ok");
             }
         }
     }
@@ -176,7 +176,7 @@ mod test {
                     err
                 );
             } else {
-                assert!(false, "expected error, but got ok");
+                panic!("expected error, but got ok");
             }
         }
     }
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 pub mod ice_candidate;
+pub mod ice_connection_state;
 pub mod ice_credential_type;
 pub mod ice_gather;
 pub mod ice_protocol;
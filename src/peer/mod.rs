@@ -1,7 +1,10 @@
 pub mod configuration;
 pub mod ice;
+pub mod offer_answer_options;
+pub(crate) mod operation;
 pub mod peer_connection;
 pub mod peer_connection_state;
+pub mod perfect_negotiation;
 pub mod policy;
 pub mod sdp;
 pub mod signaling_state;
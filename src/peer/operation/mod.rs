@@ -0,0 +1,170 @@
+#[cfg(test)]
+mod operation_test;
+
+use crate::error::Error;
+
+use anyhow::Result;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot, Mutex};
+
+/// Operation is a function to be run on the Operations queue. If the
+/// returned future resolves to true, the operation is requeued.
+pub(crate) struct Operation(
+    pub Box<dyn (FnMut() -> Pin<Box<dyn Future<Output = bool> + Send + 'static>>) + Send + Sync>,
+    pub &'static str,
+);
+
+impl Operation {
+    pub(crate) fn new(
+        op: impl FnMut() -> Pin<Box<dyn Future<Output = bool> + Send + 'static>> + Send + Sync + 'static,
+        description: &'static str,
+    ) -> Self {
+        Operation(Box::new(op), description)
+    }
+}
+
+impl fmt::Debug for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Operation")
+            .field(&"_")
+            .field(&self.1)
+            .finish()
+    }
+}
+
+/// Operations is a task executor which runs the enqueued operations one
+/// after another on a dedicated task. Enqueueing never waits for the queue
+/// to drain, so an operation (or an event handler fired from one) may
+/// enqueue further operations without deadlocking.
+pub(crate) struct Operations {
+    length: Arc<AtomicUsize>,
+    ops_tx: Arc<mpsc::UnboundedSender<Operation>>,
+    close_tx: Mutex<Option<mpsc::Sender<()>>>,
+}
+
+impl Operations {
+    pub(crate) fn new() -> Self {
+        let length = Arc::new(AtomicUsize::new(0));
+        let (ops_tx, ops_rx) = mpsc::unbounded_channel();
+        let (close_tx, close_rx) = mpsc::channel(1);
+        let ops_tx = Arc::new(ops_tx);
+
+        let l = Arc::clone(&length);
+        let ops_tx2 = Arc::clone(&ops_tx);
+        tokio::spawn(async move {
+            Operations::start(l, ops_tx2, ops_rx, close_rx).await;
+        });
+
+        Operations {
+            length,
+            ops_tx,
+            close_tx: Mutex::new(Some(close_tx)),
+        }
+    }
+
+    /// enqueue adds a new action to be executed. If there are no actions scheduled,
+    /// the execution will start immediately.
+    pub(crate) fn enqueue(&self, op: Operation) -> Result<()> {
+        Operations::enqueue_inner(op, &self.ops_tx, &self.length)
+    }
+
+    fn enqueue_inner(
+        op: Operation,
+        ops_tx: &Arc<mpsc::UnboundedSender<Operation>>,
+        length: &Arc<AtomicUsize>,
+    ) -> Result<()> {
+        length.fetch_add(1, Ordering::SeqCst);
+        if ops_tx.send(op).is_err() {
+            length.fetch_sub(1, Ordering::SeqCst);
+            return Err(Error::ErrConnectionClosed.into());
+        }
+
+        Ok(())
+    }
+
+    /// is_empty checks if there are tasks in the queue
+    pub(crate) fn is_empty(&self) -> bool {
+        self.length.load(Ordering::SeqCst) == 0
+    }
+
+    /// run waits for the operations enqueued before it, and then runs f in place of
+    /// an operation of the queue. The operations enqueued while f runs are executed
+    /// after it. Like done, it must not be awaited from within an operation.
+    pub(crate) async fn run<T>(
+        &self,
+        f: impl Future<Output = T>,
+        description: &'static str,
+    ) -> Result<T> {
+        let (started_tx, started_rx) = oneshot::channel();
+        let (finished_tx, finished_rx) = mpsc::channel::<()>(1);
+        let mut gate = Some((started_tx, finished_rx));
+        self.enqueue(Operation::new(
+            move || {
+                let gate2 = gate.take();
+                Box::pin(async move {
+                    if let Some((started_tx, mut finished_rx)) = gate2 {
+                        if started_tx.send(()).is_ok() {
+                            // resolves once run drops finished_tx
+                            let _ = finished_rx.recv().await;
+                        }
+                    }
+                    false
+                })
+            },
+            description,
+        ))?;
+
+        // fails if the queue got closed before reaching this operation
+        if started_rx.await.is_err() {
+            return Err(Error::ErrConnectionClosed.into());
+        }
+        let result = f.await;
+        drop(finished_tx);
+
+        Ok(result)
+    }
+
+    /// done blocks until all currently enqueued operations are finished executing,
+    /// including the ones requeued meanwhile.
+    #[cfg(test)]
+    pub(crate) async fn done(&self) {
+        while self.run(async {}, "Operations::done").await.is_ok() && !self.is_empty() {}
+    }
+
+    async fn start(
+        length: Arc<AtomicUsize>,
+        ops_tx: Arc<mpsc::UnboundedSender<Operation>>,
+        mut ops_rx: mpsc::UnboundedReceiver<Operation>,
+        mut close_rx: mpsc::Receiver<()>,
+    ) {
+        loop {
+            tokio::select! {
+                _ = close_rx.recv() => {
+                    break;
+                }
+                result = ops_rx.recv() => {
+                    if let Some(mut op) = result {
+                        length.fetch_sub(1, Ordering::SeqCst);
+                        log::trace!("run operation: {}", op.1);
+                        if op.0().await {
+                            // Requeue this operation
+                            let _ = Operations::enqueue_inner(op, &ops_tx, &length);
+                        }
+                    } else {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    /// close stops the executor, operations still in the queue are dropped.
+    pub(crate) async fn close(&self) {
+        let mut close_tx = self.close_tx.lock().await;
+        close_tx.take();
+    }
+}
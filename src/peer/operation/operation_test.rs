@@ -0,0 +1,169 @@
+use super::*;
+
+#[tokio::test]
+async fn test_operations_enqueue() -> Result<()> {
+    let ops = Operations::new();
+    for _ in 0..100 {
+        let results = Arc::new(Mutex::new(vec![0; 16]));
+        for k in 0..16 {
+            let r = Arc::clone(&results);
+            ops.enqueue(Operation::new(
+                move || {
+                    let r2 = Arc::clone(&r);
+                    Box::pin(async move {
+                        let mut r3 = r2.lock().await;
+                        r3[k] += k * k;
+                        r3[k] == 225
+                    })
+                },
+                "test_operations_enqueue",
+            ))?;
+        }
+
+        ops.done().await;
+        let expected = vec![
+            0, 1, 4, 9, 16, 25, 36, 49, 64, 81, 100, 121, 144, 169, 196, 450,
+        ];
+        {
+            let r = results.lock().await;
+            assert_eq!(expected.len(), r.len());
+            assert_eq!(&expected, &*r);
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_operations_done() -> Result<()> {
+    let ops = Operations::new();
+    ops.done().await;
+    assert!(ops.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_operations_enqueue_from_operation() -> Result<()> {
+    let ops = Arc::new(Operations::new());
+    let results = Arc::new(Mutex::new(vec![]));
+
+    let ops2 = Arc::clone(&ops);
+    let r = Arc::clone(&results);
+    ops.enqueue(Operation::new(
+        move || {
+            let ops3 = Arc::clone(&ops2);
+            let r2 = Arc::clone(&r);
+            Box::pin(async move {
+                {
+                    let mut r3 = r2.lock().await;
+                    r3.push(1);
+                }
+                let r4 = Arc::clone(&r2);
+                // enqueue from within a running operation must not block
+                assert!(ops3
+                    .enqueue(Operation::new(
+                        move || {
+                            let r5 = Arc::clone(&r4);
+                            Box::pin(async move {
+                                let mut r6 = r5.lock().await;
+                                r6.push(3);
+                                false
+                            })
+                        },
+                        "nested",
+                    ))
+                    .is_ok());
+                {
+                    let mut r3 = r2.lock().await;
+                    r3.push(2);
+                }
+                false
+            })
+        },
+        "outer",
+    ))?;
+
+    // the nested operation may be queued behind the first done, so wait twice
+    ops.done().await;
+    ops.done().await;
+
+    let r = results.lock().await;
+    assert_eq!(&vec![1, 2, 3], &*r);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_operations_close() -> Result<()> {
+    let ops = Operations::new();
+    ops.close().await;
+
+    // wait for the executor to exit
+    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+
+    if let Err(err) = ops.enqueue(Operation::new(|| Box::pin(async { false }), "closed")) {
+        assert!(Error::ErrConnectionClosed.equal(&err));
+    } else {
+        panic!("expected error, but got ok");
+    }
+
+    // done must return once the queue is closed
+    ops.done().await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_operations_run() -> Result<()> {
+    let ops = Operations::new();
+    let results = Arc::new(Mutex::new(vec![]));
+
+    let push = |v: usize| {
+        let r = Arc::clone(&results);
+        Operation::new(
+            move || {
+                let r2 = Arc::clone(&r);
+                Box::pin(async move {
+                    let mut r3 = r2.lock().await;
+                    r3.push(v);
+                    false
+                })
+            },
+            "test_operations_run",
+        )
+    };
+
+    ops.enqueue(push(1))?;
+    let n = ops
+        .run(
+            async {
+                // the operation enqueued before run has completed, the one
+                // enqueued here waits for run to complete
+                ops.enqueue(push(3))?;
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                let mut r = results.lock().await;
+                r.push(2);
+                Ok::<usize, anyhow::Error>(r.len())
+            },
+            "test_operations_run",
+        )
+        .await??;
+    assert_eq!(n, 2);
+
+    ops.done().await;
+    {
+        let r = results.lock().await;
+        assert_eq!(&*r, &[1, 2, 3]);
+    }
+
+    ops.close().await;
+    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    if let Err(err) = ops.run(async {}, "closed").await {
+        assert!(Error::ErrConnectionClosed.equal(&err));
+    } else {
+        panic!("expected error, but got ok");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,2979 @@
+#[cfg(test)]
+mod peer_connection_test;
+
+use crate::api::media_engine::{MediaEngine, NegotiatedState};
+use crate::api::setting_engine::SettingEngine;
+use crate::api::Api;
+use crate::data::data_channel::data_channel_config::DataChannelConfig;
+use crate::data::data_channel::data_channel_parameters::DataChannelParameters;
+use crate::data::data_channel::data_channel_state::DataChannelState;
+use crate::data::data_channel::DataChannel;
+use crate::data::sctp_transport::sctp_transport_capabilities::SCTPTransportCapabilities;
+use crate::data::sctp_transport::sctp_transport_state::SCTPTransportState;
+use crate::data::sctp_transport::{OnDataChannelHdlrFn, SCTPTransport};
+use crate::error::{flatten_errs, Error};
+use crate::interceptor::{Attributes, Interceptor, RTCPWriter};
+use crate::media::dtls_transport::dtls_fingerprint::DTLSFingerprint;
+use crate::media::dtls_transport::dtls_parameters::DTLSParameters;
+use crate::media::dtls_transport::dtls_role::{
+    DTLSRole, DEFAULT_DTLS_ROLE_ANSWER, DEFAULT_DTLS_ROLE_OFFER,
+};
+use crate::media::dtls_transport::dtls_transport_state::DTLSTransportState;
+use crate::media::dtls_transport::srtp_session::SRTPStream;
+use crate::media::dtls_transport::{get_fingerprints, DTLSTransport};
+use crate::media::ice_transport::ice_transport_state::ICETransportState;
+use crate::media::ice_transport::ICETransport;
+use crate::media::rtp::rtp_codec::{RTPCodecType, RTPHeaderExtensionCapability};
+use crate::media::rtp::rtp_receiver::RTPReceiver;
+use crate::media::rtp::rtp_sender::RTPSender;
+use crate::media::rtp::rtp_transceiver::{
+    find_by_mid, handle_unknown_rtp_packet, satisfy_type_and_direction, RTPTransceiver,
+};
+use crate::media::rtp::rtp_transceiver_direction::RTPTransceiverDirection;
+use crate::media::rtp::{
+    RTPDecodingParameters, RTPReceiveParameters, RTPRtxParameters, RTPTransceiverInit, SSRC,
+};
+use crate::media::track::track_local::track_local_static_sample::TrackLocalStaticSample;
+use crate::media::track::track_local::TrackLocal;
+use crate::media::track::track_remote::TrackRemote;
+use crate::peer::configuration::Configuration;
+use crate::peer::ice::ice_candidate::{unmarshal_candidate, ICECandidateInit};
+use crate::peer::ice::ice_connection_state::ICEConnectionState;
+use crate::peer::ice::ice_gather::ice_gatherer::{
+    ICEGatherer, OnLocalCandidateHdlrFn, OnStateChangeHdlrFn as OnICEGathererStateChangeHdlrFn,
+};
+use crate::peer::ice::ice_gather::ice_gatherer_state::ICEGathererState;
+use crate::peer::ice::ice_gather::ice_gathering_state::ICEGatheringState;
+use crate::peer::ice::ice_gather::ICEGatherOptions;
+use crate::peer::ice::ice_role::ICERole;
+use crate::peer::ice::ICEParameters;
+use crate::peer::offer_answer_options::{AnswerOptions, OfferOptions};
+use crate::peer::operation::{Operation, Operations};
+use crate::peer::peer_connection_state::{NegotiationNeededState, PeerConnectionState};
+use crate::peer::sdp::sdp_type::SDPType;
+use crate::peer::sdp::session_description::SessionDescription;
+use crate::peer::sdp::*;
+use crate::peer::signaling_state::{check_next_signaling_state, SignalingState, StateChangeOp};
+use crate::util::math_rand_alpha;
+use crate::{
+    MEDIA_SECTION_APPLICATION, RECEIVE_MTU, SIMULCAST_MAX_PROBE_ROUTINES, SIMULCAST_PROBE_COUNT,
+};
+
+use anyhow::Result;
+use sdp::session_description::Origin;
+use sdp::util::ConnectionRole;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicIsize, AtomicU8, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+pub type OnSignalingStateChangeHdlrFn = Box<
+    dyn (FnMut(SignalingState) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>) + Send + Sync,
+>;
+
+pub type OnICEConnectionStateChangeHdlrFn = Box<
+    dyn (FnMut(ICEConnectionState) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>)
+        + Send
+        + Sync,
+>;
+
+pub type OnPeerConnectionStateChangeHdlrFn = Box<
+    dyn (FnMut(PeerConnectionState) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>)
+        + Send
+        + Sync,
+>;
+
+pub type OnNegotiationNeededHdlrFn =
+    Box<dyn (FnMut() -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>) + Send + Sync>;
+
+pub type OnTrackHdlrFn = Box<
+    dyn (FnMut(
+            Arc<TrackRemote>,
+            Arc<RTPReceiver>,
+        ) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>)
+        + Send
+        + Sync,
+>;
+
+/// NegotiationNeededParams holds the state shared by the negotiation-needed
+/// operations, which run on the operations queue rather than on the caller.
+#[derive(Clone)]
+struct NegotiationNeededParams {
+    on_negotiation_needed_handler: Arc<Mutex<Option<OnNegotiationNeededHdlrFn>>>,
+    is_closed: Arc<AtomicBool>,
+    ops: Arc<Operations>,
+    negotiation_needed_state: Arc<AtomicU8>,
+    is_negotiation_needed: Arc<AtomicBool>,
+    signaling_state: Arc<AtomicU8>,
+    current_local_description: Arc<Mutex<Option<SessionDescription>>>,
+    current_remote_description: Arc<Mutex<Option<SessionDescription>>>,
+    sctp_transport: Arc<SCTPTransport>,
+    rtp_transceivers: Arc<Mutex<Vec<Arc<RTPTransceiver>>>>,
+}
+
+/// TransceiverSnapshot is the state of a transceiver before a remote offer
+/// was applied, which a rollback of that offer restores
+struct TransceiverSnapshot {
+    transceiver: Arc<RTPTransceiver>,
+    mid: String,
+    direction: RTPTransceiverDirection,
+    stopped: bool,
+}
+
+/// RemoteOfferSnapshot is what a rollback of the pending remote offer restores
+struct RemoteOfferSnapshot {
+    transceivers: Vec<TransceiverSnapshot>,
+    /// the transceivers created by applying the offer which haven't been
+    /// given a track since
+    created: Vec<Arc<RTPTransceiver>>,
+    negotiated: NegotiatedState,
+}
+
+/// StartTransportsParams holds everything the transports need to be started
+/// once a remote description has been applied, since that happens outside
+/// of the set_remote_description call.
+struct StartTransportsParams {
+    ice_transport: Arc<ICETransport>,
+    dtls_transport: Arc<DTLSTransport>,
+    sctp_transport: Arc<SCTPTransport>,
+    is_closed: Arc<AtomicBool>,
+    peer_connection_state: Arc<AtomicU8>,
+    ice_connection_state: Arc<AtomicU8>,
+    on_peer_connection_state_change_handler: Arc<Mutex<Option<OnPeerConnectionStateChangeHdlrFn>>>,
+
+    ice_role: ICERole,
+    dtls_role: DTLSRole,
+    remote_ufrag: String,
+    remote_pwd: String,
+    fingerprint: String,
+    fingerprint_hash: String,
+    start_sctp: bool,
+}
+
+/// StartRTPParams holds what start_rtp needs to start receiving the tracks of
+/// a remote description, once both descriptions have been applied
+struct StartRTPParams {
+    is_renegotiation: bool,
+    remote_desc: sdp::session_description::SessionDescription,
+    current_transceivers: Vec<Arc<RTPTransceiver>>,
+    undeclared_media: UndeclaredMediaParams,
+}
+
+/// UndeclaredMediaParams holds what is needed to bind the incoming SSRCs which
+/// are not declared in the remote description, for as long as the
+/// PeerConnection receives media
+#[derive(Clone)]
+struct UndeclaredMediaParams {
+    dtls_transport: Arc<DTLSTransport>,
+    media_engine: Arc<MediaEngine>,
+    is_closed: Arc<AtomicBool>,
+    rtp_transceivers: Arc<Mutex<Vec<Arc<RTPTransceiver>>>>,
+    pending_remote_description: Arc<Mutex<Option<SessionDescription>>>,
+    current_remote_description: Arc<Mutex<Option<SessionDescription>>>,
+    on_track_handler: Arc<Mutex<Option<OnTrackHdlrFn>>>,
+}
+
+impl UndeclaredMediaParams {
+    /// remote_description returns the pending remote description if there is
+    /// one, and otherwise the current one
+    async fn remote_description(&self) -> Option<SessionDescription> {
+        {
+            let pending_remote_description = self.pending_remote_description.lock().await;
+            if pending_remote_description.is_some() {
+                return pending_remote_description.clone();
+            }
+        }
+        let current_remote_description = self.current_remote_description.lock().await;
+        current_remote_description.clone()
+    }
+}
+
+/// PeerConnection represents a WebRTC connection that establishes a
+/// peer-to-peer communications with another PeerConnection instance in a
+/// browser, or to another endpoint implementing the required protocols.
+pub struct PeerConnection {
+    stats_id: String,
+
+    sdp_origin: Mutex<Origin>,
+
+    configuration: Configuration,
+
+    current_local_description: Arc<Mutex<Option<SessionDescription>>>,
+    pending_local_description: Arc<Mutex<Option<SessionDescription>>>,
+    current_remote_description: Arc<Mutex<Option<SessionDescription>>>,
+    pending_remote_description: Arc<Mutex<Option<SessionDescription>>>,
+    signaling_state: Arc<AtomicU8>,       // SignalingState
+    ice_connection_state: Arc<AtomicU8>,  // ICEConnectionState
+    peer_connection_state: Arc<AtomicU8>, // PeerConnectionState
+
+    is_closed: Arc<AtomicBool>,
+
+    /// ops is an operations queue which will ensure the enqueued actions are
+    /// executed in order. It is used for asynchronously, but serially processing
+    /// remote and local descriptions, and the changes of transceivers and data channels
+    ops: Arc<Operations>,
+    /// start_rtp_task waits for the SRTP sessions before RTP is started on ops
+    start_rtp_task: Mutex<Option<JoinHandle<()>>>,
+
+    negotiation_needed_state: Arc<AtomicU8>, // NegotiationNeededState
+    is_negotiation_needed: Arc<AtomicBool>,
+
+    last_offer: Mutex<String>,
+    last_answer: Mutex<String>,
+
+    rtp_transceivers: Arc<Mutex<Vec<Arc<RTPTransceiver>>>>,
+    /// the transceivers and negotiated codecs as they were before the pending
+    /// remote offer
+    remote_offer_snapshot: Mutex<Option<RemoteOfferSnapshot>>,
+    greater_mid: AtomicIsize,
+
+    on_signaling_state_change_handler: Arc<Mutex<Option<OnSignalingStateChangeHdlrFn>>>,
+    on_ice_connection_state_change_handler: Arc<Mutex<Option<OnICEConnectionStateChangeHdlrFn>>>,
+    on_peer_connection_state_change_handler: Arc<Mutex<Option<OnPeerConnectionStateChangeHdlrFn>>>,
+    on_data_channel_handler: Arc<Mutex<Option<OnDataChannelHdlrFn>>>,
+    on_negotiation_needed_handler: Arc<Mutex<Option<OnNegotiationNeededHdlrFn>>>,
+    on_track_handler: Arc<Mutex<Option<OnTrackHdlrFn>>>,
+
+    ice_gatherer: Arc<ICEGatherer>,
+    ice_transport: Arc<ICETransport>,
+    dtls_transport: Arc<DTLSTransport>,
+    sctp_transport: Arc<SCTPTransport>,
+
+    // A reference to the associated API state used by this connection
+    setting_engine: SettingEngine,
+    media_engine: Arc<MediaEngine>,
+    interceptor: Arc<dyn Interceptor + Send + Sync>,
+    interceptor_rtcp_writer: Arc<dyn RTCPWriter + Send + Sync>,
+}
+
+impl PeerConnection {
+    /// creates a PeerConnection with the provided configuration against the received API object
+    pub(crate) async fn new(api: &Api, configuration: Configuration) -> Result<Self> {
+        // https://w3c.github.io/webrtc-pc/#constructor (Step #2)
+        // Some variables defined explicitly despite their implicit zero values to
+        // allow better readability to understand what is happening.
+        let ice_connection_state = Arc::new(AtomicU8::new(ICEConnectionState::New as u8));
+        let peer_connection_state = Arc::new(AtomicU8::new(PeerConnectionState::New as u8));
+        let is_closed = Arc::new(AtomicBool::new(false));
+        let on_ice_connection_state_change_handler = Arc::new(Mutex::new(None));
+        let on_peer_connection_state_change_handler = Arc::new(Mutex::new(None));
+        let on_data_channel_handler = Arc::new(Mutex::new(None::<OnDataChannelHdlrFn>));
+
+        // Create the ice gatherer
+        let ice_gatherer = Arc::new(api.new_ice_gatherer(ICEGatherOptions {
+            ice_servers: configuration.get_ice_servers(),
+            ice_gather_policy: configuration.ice_transport_policy,
+        })?);
+
+        // Create the ice transport
+        let ice_transport = Arc::new(api.new_ice_transport(Arc::clone(&ice_gatherer))?);
+
+        // Create the DTLS transport
+        let dtls_transport = Arc::new(api.new_dtls_transport(
+            Arc::clone(&ice_transport),
+            configuration.certificates.clone(),
+        )?);
+
+        // Create the SCTP transport
+        let sctp_transport = Arc::new(api.new_sctp_transport(Arc::clone(&dtls_transport))?);
+
+        let stats_id = format!(
+            "PeerConnection-{}",
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |d| d.as_nanos())
+        );
+
+        // Create the interceptors, the RTCP packets of the PeerConnection are
+        // written through them to the DTLS transport
+        let interceptor = api.interceptor_registry.build(&stats_id)?;
+        let interceptor_rtcp_writer = interceptor
+            .bind_rtcp_writer(Arc::clone(&dtls_transport) as _)
+            .await;
+
+        // Wire up the ice connection state handler
+        {
+            let ice_connection_state = Arc::clone(&ice_connection_state);
+            let peer_connection_state = Arc::clone(&peer_connection_state);
+            let is_closed = Arc::clone(&is_closed);
+            let dtls_transport = Arc::clone(&dtls_transport);
+            let on_ice_connection_state_change_handler =
+                Arc::clone(&on_ice_connection_state_change_handler);
+            let on_peer_connection_state_change_handler =
+                Arc::clone(&on_peer_connection_state_change_handler);
+            ice_transport
+                .on_connection_state_change(Box::new(move |state: ICETransportState| {
+                    let cs = ICEConnectionState::from(state);
+                    if cs == ICEConnectionState::Unspecified {
+                        log::warn!("on_connection_state_change: unhandled ICE state: {}", state);
+                        return Box::pin(async {});
+                    }
+
+                    let ice_connection_state2 = Arc::clone(&ice_connection_state);
+                    let peer_connection_state2 = Arc::clone(&peer_connection_state);
+                    let is_closed2 = Arc::clone(&is_closed);
+                    let dtls_transport_state = dtls_transport.state();
+                    let on_ice_connection_state_change_handler2 =
+                        Arc::clone(&on_ice_connection_state_change_handler);
+                    let on_peer_connection_state_change_handler2 =
+                        Arc::clone(&on_peer_connection_state_change_handler);
+                    Box::pin(async move {
+                        PeerConnection::do_ice_connection_state_change(
+                            &on_ice_connection_state_change_handler2,
+                            &ice_connection_state2,
+                            cs,
+                        )
+                        .await;
+
+                        PeerConnection::update_connection_state(
+                            &on_peer_connection_state_change_handler2,
+                            &is_closed2,
+                            &peer_connection_state2,
+                            cs,
+                            dtls_transport_state,
+                        )
+                        .await;
+                    })
+                }))
+                .await;
+        }
+
+        // Wire up the on datachannel handler
+        {
+            let on_data_channel_handler = Arc::clone(&on_data_channel_handler);
+            sctp_transport
+                .on_data_channel(Box::new(move |d: Arc<DataChannel>| {
+                    let on_data_channel_handler2 = Arc::clone(&on_data_channel_handler);
+                    Box::pin(async move {
+                        let mut handler = on_data_channel_handler2.lock().await;
+                        if let Some(f) = &mut *handler {
+                            f(d).await;
+                        }
+                    })
+                }))
+                .await;
+        }
+
+        Ok(PeerConnection {
+            stats_id,
+            sdp_origin: Mutex::new(Origin::default()),
+            configuration,
+
+            current_local_description: Arc::new(Mutex::new(None)),
+            pending_local_description: Arc::new(Mutex::new(None)),
+            current_remote_description: Arc::new(Mutex::new(None)),
+            pending_remote_description: Arc::new(Mutex::new(None)),
+            signaling_state: Arc::new(AtomicU8::new(SignalingState::Stable as u8)),
+            ice_connection_state,
+            peer_connection_state,
+
+            is_closed,
+
+            ops: Arc::new(Operations::new()),
+            start_rtp_task: Mutex::new(None),
+
+            negotiation_needed_state: Arc::new(AtomicU8::new(NegotiationNeededState::Empty as u8)),
+            is_negotiation_needed: Arc::new(AtomicBool::new(false)),
+
+            last_offer: Mutex::new(String::new()),
+            last_answer: Mutex::new(String::new()),
+
+            rtp_transceivers: Arc::new(Mutex::new(vec![])),
+            remote_offer_snapshot: Mutex::new(None),
+            greater_mid: AtomicIsize::new(-1),
+
+            on_signaling_state_change_handler: Arc::new(Mutex::new(None)),
+            on_ice_connection_state_change_handler,
+            on_peer_connection_state_change_handler,
+            on_data_channel_handler,
+            on_negotiation_needed_handler: Arc::new(Mutex::new(None)),
+            on_track_handler: Arc::new(Mutex::new(None)),
+
+            ice_gatherer,
+            ice_transport,
+            dtls_transport,
+            sctp_transport,
+
+            setting_engine: api.setting_engine.clone(),
+            media_engine: if !api.setting_engine.disable_media_engine_copy {
+                Arc::new(api.media_engine.clone_to())
+            } else {
+                Arc::clone(&api.media_engine)
+            },
+            interceptor,
+            interceptor_rtcp_writer,
+        })
+    }
+
+    /// on_signaling_state_change sets an event handler which is invoked when the
+    /// peer connection's signaling state changes
+    pub async fn on_signaling_state_change(&self, f: OnSignalingStateChangeHdlrFn) {
+        let mut handler = self.on_signaling_state_change_handler.lock().await;
+        *handler = Some(f);
+    }
+
+    async fn do_signaling_state_change(&self, new_state: SignalingState) {
+        log::info!("signaling state changed to {}", new_state);
+        // the handler future is awaited after the lock is released, so the
+        // handler is free to call back into the PeerConnection
+        let fut = {
+            let mut handler = self.on_signaling_state_change_handler.lock().await;
+            handler.as_mut().map(|f| f(new_state))
+        };
+        if let Some(fut) = fut {
+            fut.await;
+        }
+    }
+
+    /// on_data_channel sets an event handler which is invoked when a data
+    /// channel message arrives from a remote peer.
+    pub async fn on_data_channel(&self, f: OnDataChannelHdlrFn) {
+        let mut handler = self.on_data_channel_handler.lock().await;
+        *handler = Some(f);
+    }
+
+    /// on_negotiation_needed sets an event handler which is invoked when
+    /// a change has occurred which requires session negotiation
+    pub async fn on_negotiation_needed(&self, f: OnNegotiationNeededHdlrFn) {
+        let mut handler = self.on_negotiation_needed_handler.lock().await;
+        *handler = Some(f);
+    }
+
+    /// on_track sets an event handler which is called when remote track
+    /// arrives from a remote peer.
+    pub async fn on_track(&self, f: OnTrackHdlrFn) {
+        let mut handler = self.on_track_handler.lock().await;
+        *handler = Some(f);
+    }
+
+    fn negotiation_needed_params(&self) -> NegotiationNeededParams {
+        NegotiationNeededParams {
+            on_negotiation_needed_handler: Arc::clone(&self.on_negotiation_needed_handler),
+            is_closed: Arc::clone(&self.is_closed),
+            ops: Arc::clone(&self.ops),
+            negotiation_needed_state: Arc::clone(&self.negotiation_needed_state),
+            is_negotiation_needed: Arc::clone(&self.is_negotiation_needed),
+            signaling_state: Arc::clone(&self.signaling_state),
+            current_local_description: Arc::clone(&self.current_local_description),
+            current_remote_description: Arc::clone(&self.current_remote_description),
+            sctp_transport: Arc::clone(&self.sctp_transport),
+            rtp_transceivers: Arc::clone(&self.rtp_transceivers),
+        }
+    }
+
+    fn undeclared_media_params(&self) -> UndeclaredMediaParams {
+        UndeclaredMediaParams {
+            dtls_transport: Arc::clone(&self.dtls_transport),
+            media_engine: Arc::clone(&self.media_engine),
+            is_closed: Arc::clone(&self.is_closed),
+            rtp_transceivers: Arc::clone(&self.rtp_transceivers),
+            pending_remote_description: Arc::clone(&self.pending_remote_description),
+            current_remote_description: Arc::clone(&self.current_remote_description),
+            on_track_handler: Arc::clone(&self.on_track_handler),
+        }
+    }
+
+    /// do_negotiation_needed enqueues negotiation_needed_op if necessary
+    fn do_negotiation_needed(params: NegotiationNeededParams) {
+        // https://w3c.github.io/webrtc-pc/#updating-the-negotiation-needed-flag
+        // non-canon step 1
+        let state: NegotiationNeededState = params
+            .negotiation_needed_state
+            .load(Ordering::SeqCst)
+            .into();
+        if state == NegotiationNeededState::Run {
+            params
+                .negotiation_needed_state
+                .store(NegotiationNeededState::Queue as u8, Ordering::SeqCst);
+            return;
+        } else if state == NegotiationNeededState::Queue {
+            return;
+        }
+        params
+            .negotiation_needed_state
+            .store(NegotiationNeededState::Run as u8, Ordering::SeqCst);
+
+        let ops = Arc::clone(&params.ops);
+        if let Err(err) = ops.enqueue(Operation::new(
+            move || {
+                let params2 = params.clone();
+                Box::pin(async move { PeerConnection::negotiation_needed_op(params2).await })
+            },
+            "negotiation_needed",
+        )) {
+            log::warn!("failed to enqueue negotiation_needed: {}", err);
+        }
+    }
+
+    /// after_negotiation_needed_op resets the negotiation needed state, and runs
+    /// the check again if it was requested while this one was running
+    fn after_negotiation_needed_op(params: NegotiationNeededParams) -> bool {
+        let old_negotiation_needed_state = params.negotiation_needed_state.load(Ordering::SeqCst);
+
+        params
+            .negotiation_needed_state
+            .store(NegotiationNeededState::Empty as u8, Ordering::SeqCst);
+
+        if old_negotiation_needed_state == NegotiationNeededState::Queue as u8 {
+            PeerConnection::do_negotiation_needed(params);
+        }
+
+        false
+    }
+
+    async fn negotiation_needed_op(params: NegotiationNeededParams) -> bool {
+        // Don't run NegotiatedNeeded checks if on_negotiation_needed is not set
+        let has_handler = {
+            let handler = params.on_negotiation_needed_handler.lock().await;
+            handler.is_some()
+        };
+        if !has_handler {
+            return PeerConnection::after_negotiation_needed_op(params);
+        }
+
+        // https://www.w3.org/TR/webrtc/#updating-the-negotiation-needed-flag
+        // Step 2.1
+        if params.is_closed.load(Ordering::SeqCst) {
+            return false;
+        }
+
+        // non-canon step 2.2
+        if !params.ops.is_empty() {
+            // requeue this operation behind the pending ones
+            return true;
+        }
+
+        // Step 2.3
+        if params.signaling_state.load(Ordering::SeqCst) != SignalingState::Stable as u8 {
+            return PeerConnection::after_negotiation_needed_op(params);
+        }
+
+        // Step 2.4
+        if !PeerConnection::check_negotiation_needed(&params).await {
+            params.is_negotiation_needed.store(false, Ordering::SeqCst);
+            return PeerConnection::after_negotiation_needed_op(params);
+        }
+
+        // Step 2.5
+        if params.is_negotiation_needed.load(Ordering::SeqCst) {
+            return PeerConnection::after_negotiation_needed_op(params);
+        }
+
+        // Step 2.6
+        params.is_negotiation_needed.store(true, Ordering::SeqCst);
+
+        // Step 2.7
+        // the handler future runs in a task of its own, so the handler is free
+        // to start a new negotiation, which is run on the operations queue
+        let fut = {
+            let mut handler = params.on_negotiation_needed_handler.lock().await;
+            handler.as_mut().map(|f| f())
+        };
+        if let Some(fut) = fut {
+            tokio::spawn(fut);
+        }
+
+        PeerConnection::after_negotiation_needed_op(params)
+    }
+
+    async fn check_negotiation_needed(params: &NegotiationNeededParams) -> bool {
+        // To check if negotiation is needed for connection, perform the following checks:
+        // Skip 1, 2 steps
+        // Step 3
+        let local_desc = {
+            let current_local_description = params.current_local_description.lock().await;
+            match &*current_local_description {
+                Some(local_desc) => local_desc.clone(),
+                None => return true,
+            }
+        };
+        let local_parsed = match local_desc.unmarshal() {
+            Ok(parsed) => parsed,
+            Err(_) => return true,
+        };
+
+        let len_data_channel = {
+            let data_channels = params.sctp_transport.data_channels.lock().await;
+            data_channels.len()
+        };
+
+        // Step 4
+        if len_data_channel != 0 && !have_application_media_section(&local_parsed) {
+            return true;
+        }
+
+        let remote_parsed = {
+            let current_remote_description = params.current_remote_description.lock().await;
+            current_remote_description
+                .as_ref()
+                .and_then(|remote_desc| remote_desc.unmarshal().ok())
+        };
+
+        let transceivers = {
+            let rtp_transceivers = params.rtp_transceivers.lock().await;
+            rtp_transceivers.clone()
+        };
+
+        // Step 5
+        for t in &transceivers {
+            let mid = t.mid().await;
+            let stopped = t.stopped.load(Ordering::SeqCst);
+            let m = get_by_mid(&mid, &local_parsed);
+            let rm = remote_parsed
+                .as_ref()
+                .and_then(|remote_parsed| get_by_mid(&mid, remote_parsed));
+
+            if !stopped {
+                // Step 5.2
+                let m = match m {
+                    Some(m) => m,
+                    None => return true,
+                };
+
+                // Step 5.3.1
+                if t.direction().has_send() {
+                    let desc_msid = media_attribute(m, ATTR_KEY_MSID);
+                    let track = match t.sender().await {
+                        Some(sender) => sender.track().await,
+                        None => None,
+                    };
+                    match (desc_msid, track) {
+                        (Some(desc_msid), Some(track)) => {
+                            if *desc_msid != format!("{} {}", track.stream_id(), track.id()) {
+                                return true;
+                            }
+                        }
+                        _ => return true,
+                    }
+                }
+
+                match local_desc.sdp_type {
+                    // Step 5.3.2
+                    SDPType::Offer => {
+                        let rm = match rm {
+                            Some(rm) => rm,
+                            None => return true,
+                        };
+
+                        if get_peer_direction(m) != t.direction()
+                            && get_peer_direction(rm) != t.direction().reverse()
+                        {
+                            return true;
+                        }
+                    }
+                    // Step 5.3.3
+                    SDPType::Answer if get_peer_direction(m) != t.direction() => {
+                        return true;
+                    }
+                    _ => {}
+                };
+            }
+
+            // Step 5.4
+            if stopped && !mid.is_empty() && (m.is_some() || rm.is_some()) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// on_ice_candidate sets an event handler which is invoked when a new ICE
+    /// candidate is found.
+    /// Take note that the handler is gonna be called with a nil pointer when
+    /// gathering is finished.
+    pub async fn on_ice_candidate(&self, f: OnLocalCandidateHdlrFn) {
+        self.ice_gatherer.on_local_candidate(f).await
+    }
+
+    /// on_ice_gathering_state_change sets an event handler which is invoked when the
+    /// ICE candidate gathering state has changed.
+    pub async fn on_ice_gathering_state_change(&self, f: OnICEGathererStateChangeHdlrFn) {
+        self.ice_gatherer.on_state_change(f).await
+    }
+
+    /// on_ice_connection_state_change sets an event handler which is called
+    /// when an ICE connection state is changed.
+    pub async fn on_ice_connection_state_change(&self, f: OnICEConnectionStateChangeHdlrFn) {
+        let mut handler = self.on_ice_connection_state_change_handler.lock().await;
+        *handler = Some(f);
+    }
+
+    async fn do_ice_connection_state_change(
+        on_ice_connection_state_change_handler: &Arc<
+            Mutex<Option<OnICEConnectionStateChangeHdlrFn>>,
+        >,
+        ice_connection_state: &Arc<AtomicU8>,
+        cs: ICEConnectionState,
+    ) {
+        ice_connection_state.store(cs as u8, Ordering::SeqCst);
+
+        log::info!("ICE connection state changed: {}", cs);
+        let mut handler = on_ice_connection_state_change_handler.lock().await;
+        if let Some(f) = &mut *handler {
+            f(cs).await;
+        }
+    }
+
+    /// on_peer_connection_state_change sets an event handler which is called
+    /// when the PeerConnectionState has changed
+    pub async fn on_peer_connection_state_change(&self, f: OnPeerConnectionStateChangeHdlrFn) {
+        let mut handler = self.on_peer_connection_state_change_handler.lock().await;
+        *handler = Some(f);
+    }
+
+    async fn do_peer_connection_state_change(
+        on_peer_connection_state_change_handler: &Arc<
+            Mutex<Option<OnPeerConnectionStateChangeHdlrFn>>,
+        >,
+        cs: PeerConnectionState,
+    ) {
+        let mut handler = on_peer_connection_state_change_handler.lock().await;
+        if let Some(f) = &mut *handler {
+            f(cs).await;
+        }
+    }
+
+    /// get_configuration returns a Configuration object representing the current
+    /// configuration of this PeerConnection object.
+    /// https://www.w3.org/TR/webrtc/#dom-rtcpeerconnection-getconfiguration
+    pub fn get_configuration(&self) -> &Configuration {
+        &self.configuration
+    }
+
+    pub fn get_stats_id(&self) -> &str {
+        self.stats_id.as_str()
+    }
+
+    /// create_offer starts the PeerConnection and generates the localDescription
+    /// https://w3c.github.io/webrtc-pc/#dom-rtcpeerconnection-createoffer
+    pub async fn create_offer(&self, options: Option<OfferOptions>) -> Result<SessionDescription> {
+        if self.is_closed.load(Ordering::SeqCst) {
+            return Err(Error::ErrConnectionClosed.into());
+        }
+
+        if let Some(options) = options {
+            if options.ice_restart {
+                self.ice_transport.restart().await?;
+            }
+        }
+
+        let current_remote_description = self.current_remote_description().await;
+        let current_remote_description_is_none = current_remote_description.is_none();
+
+        // update the greater mid if the remote description provides a greater one
+        if let Some(current_remote_description) = current_remote_description {
+            let parsed = current_remote_description.unmarshal()?;
+            for media in &parsed.media_descriptions {
+                if let Some(numeric_mid) = get_mid_value(media).and_then(|mid| mid.parse().ok()) {
+                    self.greater_mid.fetch_max(numeric_mid, Ordering::SeqCst);
+                }
+            }
+        }
+
+        // include unmatched local transceivers
+        for t in self.get_transceivers().await {
+            let mid = t.mid().await;
+            if !mid.is_empty() {
+                if let Ok(numeric_mid) = mid.parse() {
+                    self.greater_mid.fetch_max(numeric_mid, Ordering::SeqCst);
+                }
+                continue;
+            }
+
+            let numeric_mid = self.greater_mid.fetch_add(1, Ordering::SeqCst) + 1;
+            t.set_mid(numeric_mid.to_string()).await?;
+        }
+
+        let mut d = if current_remote_description_is_none {
+            self.generate_unmatched_sdp().await?
+        } else {
+            self.generate_matched_sdp(
+                true, /*includeUnmatched */
+                DEFAULT_DTLS_ROLE_OFFER.to_connection_role(),
+            )
+            .await?
+        };
+
+        {
+            let mut sdp_origin = self.sdp_origin.lock().await;
+            update_sdp_origin(&mut sdp_origin, &mut d);
+        }
+
+        let offer = SessionDescription {
+            sdp_type: SDPType::Offer,
+            sdp: d.marshal(),
+        };
+
+        {
+            let mut last_offer = self.last_offer.lock().await;
+            *last_offer = offer.sdp.clone();
+        }
+        Ok(offer)
+    }
+
+    /// Update the PeerConnectionState given the state of relevant transports
+    /// https://www.w3.org/TR/webrtc/#rtcpeerconnectionstate-enum
+    async fn update_connection_state(
+        on_peer_connection_state_change_handler: &Arc<
+            Mutex<Option<OnPeerConnectionStateChangeHdlrFn>>,
+        >,
+        is_closed: &Arc<AtomicBool>,
+        peer_connection_state: &Arc<AtomicU8>,
+        ice_connection_state: ICEConnectionState,
+        dtls_transport_state: DTLSTransportState,
+    ) {
+        let connection_state =
+            // The PeerConnection object's [[IsClosed]] slot is true.
+            if is_closed.load(Ordering::SeqCst) {
+                PeerConnectionState::Closed
+            } else if ice_connection_state == ICEConnectionState::Failed || dtls_transport_state == DTLSTransportState::Failed {
+                // Any of the ICETransports or DTLSTransports are in a "failed" state.
+                PeerConnectionState::Failed
+            } else if ice_connection_state == ICEConnectionState::Disconnected {
+                // Any of the ICETransports or DTLSTransports are in the "disconnected"
+                // state and none of them are in the "failed" or "connecting" or "checking" state.
+                PeerConnectionState::Disconnected
+            } else if ice_connection_state == ICEConnectionState::Connected && dtls_transport_state == DTLSTransportState::Connected {
+                // All ICETransports and DTLSTransports are in the "connected", "completed" or "closed"
+                // state and at least one of them is in the "connected" or "completed" state.
+                PeerConnectionState::Connected
+            } else if ice_connection_state == ICEConnectionState::Checking
+                || ice_connection_state == ICEConnectionState::Connected
+                || dtls_transport_state == DTLSTransportState::Connecting {
+                // Any of the ICETransports or DTLSTransports are in the "connecting" or
+                // "checking" state and none of them is in the "failed" state. The DTLS
+                // handshake only starts once the ICETransport is connected.
+                PeerConnectionState::Connecting
+            } else {
+                PeerConnectionState::New
+            };
+
+        if peer_connection_state.load(Ordering::SeqCst) == connection_state as u8 {
+            return;
+        }
+
+        log::info!("peer connection state changed: {}", connection_state);
+        peer_connection_state.store(connection_state as u8, Ordering::SeqCst);
+
+        PeerConnection::do_peer_connection_state_change(
+            on_peer_connection_state_change_handler,
+            connection_state,
+        )
+        .await;
+    }
+
+    /// create_answer starts the PeerConnection and generates the localDescription
+    pub async fn create_answer(
+        &self,
+        _options: Option<AnswerOptions>,
+    ) -> Result<SessionDescription> {
+        if self.remote_description().await.is_none() {
+            return Err(Error::ErrNoRemoteDescription.into());
+        } else if self.is_closed.load(Ordering::SeqCst) {
+            return Err(Error::ErrConnectionClosed.into());
+        } else if self.signaling_state() != SignalingState::HaveRemoteOffer
+            && self.signaling_state() != SignalingState::HaveLocalPranswer
+        {
+            return Err(Error::ErrIncorrectSignalingState.into());
+        }
+
+        let mut connection_role = self.setting_engine.answering_dtls_role.to_connection_role();
+        if connection_role == ConnectionRole::Unspecified {
+            connection_role = DEFAULT_DTLS_ROLE_ANSWER.to_connection_role();
+        }
+
+        let mut d = self
+            .generate_matched_sdp(false /*includeUnmatched */, connection_role)
+            .await?;
+
+        {
+            let mut sdp_origin = self.sdp_origin.lock().await;
+            update_sdp_origin(&mut sdp_origin, &mut d);
+        }
+
+        let answer = SessionDescription {
+            sdp_type: SDPType::Answer,
+            sdp: d.marshal(),
+        };
+
+        {
+            let mut last_answer = self.last_answer.lock().await;
+            *last_answer = answer.sdp.clone();
+        }
+        Ok(answer)
+    }
+
+    /// rollback discards the pending offer, together with any provisional answer
+    /// given to it, and returns to the last stable descriptions
+    async fn rollback(&self, cur: SignalingState) {
+        log::debug!("rolling back from {}", cur);
+        {
+            let mut pending_local_description = self.pending_local_description.lock().await;
+            *pending_local_description = None;
+        }
+        {
+            let mut pending_remote_description = self.pending_remote_description.lock().await;
+            *pending_remote_description = None;
+        }
+
+        // Rolling back a remote offer stops and removes the transceivers it
+        // created that weren't given a track since, clears the mids it assigned
+        // to the others, and restores the state it changed
+        // https://tools.ietf.org/html/rfc8829#section-4.1.8.2
+        let snapshot = {
+            let mut remote_offer_snapshot = self.remote_offer_snapshot.lock().await;
+            remote_offer_snapshot.take()
+        };
+        if let Some(snapshot) = snapshot {
+            if cur != SignalingState::HaveRemoteOffer && cur != SignalingState::HaveLocalPranswer {
+                return;
+            }
+
+            let mut rtp_transceivers = self.rtp_transceivers.lock().await;
+            let mut kept = vec![];
+            for t in rtp_transceivers.drain(..) {
+                if snapshot.created.iter().any(|c| Arc::ptr_eq(c, &t)) {
+                    if let Err(err) = t.stop().await {
+                        log::warn!("failed to stop transceiver: {}", err);
+                    }
+                    continue;
+                }
+                if !snapshot
+                    .transceivers
+                    .iter()
+                    .any(|s| Arc::ptr_eq(&s.transceiver, &t))
+                {
+                    t.restore_mid(String::new()).await;
+                }
+                kept.push(t);
+            }
+            *rtp_transceivers = kept;
+
+            for s in snapshot.transceivers {
+                s.transceiver.restore_mid(s.mid).await;
+                s.transceiver.set_direction(s.direction);
+                s.transceiver.stopped.store(s.stopped, Ordering::SeqCst);
+            }
+
+            self.media_engine
+                .restore_negotiated_state(snapshot.negotiated)
+                .await;
+        }
+    }
+
+    // 4.4.1.6 Set the SessionDescription
+    // The signaling state changes are pushed to state_changes, their handler is
+    // fired once the description is applied.
+    async fn set_description(
+        &self,
+        sd: &SessionDescription,
+        op: StateChangeOp,
+        state_changes: &mut Vec<SignalingState>,
+    ) -> Result<()> {
+        if self.is_closed.load(Ordering::SeqCst) {
+            return Err(Error::ErrConnectionClosed.into());
+        } else if sd.sdp_type == SDPType::Unspecified {
+            return Err(Error::ErrPeerConnSDPTypeInvalidValue.into());
+        }
+
+        let cur = self.signaling_state();
+        let next_state = match op {
+            StateChangeOp::SetLocal => {
+                match sd.sdp_type {
+                    // stable->SetLocal(offer)->have-local-offer
+                    SDPType::Offer => {
+                        let check = {
+                            let last_offer = self.last_offer.lock().await;
+                            sd.sdp != *last_offer
+                        };
+                        if check {
+                            return Err(Error::ErrSDPDoesNotMatchOffer.into());
+                        }
+
+                        let next_state = check_next_signaling_state(
+                            cur,
+                            SignalingState::HaveLocalOffer,
+                            StateChangeOp::SetLocal,
+                            sd.sdp_type,
+                        )?;
+                        let mut pending_local_description =
+                            self.pending_local_description.lock().await;
+                        *pending_local_description = Some(sd.clone());
+                        next_state
+                    }
+                    // have-remote-offer->SetLocal(answer)->stable
+                    SDPType::Answer => {
+                        let check = {
+                            let last_answer = self.last_answer.lock().await;
+                            sd.sdp != *last_answer
+                        };
+                        if check {
+                            return Err(Error::ErrSDPDoesNotMatchAnswer.into());
+                        }
+
+                        let next_state = check_next_signaling_state(
+                            cur,
+                            SignalingState::Stable,
+                            StateChangeOp::SetLocal,
+                            sd.sdp_type,
+                        )?;
+                        let pending_remote_description = {
+                            let mut pending_remote_description =
+                                self.pending_remote_description.lock().await;
+                            pending_remote_description.take()
+                        };
+                        {
+                            let mut pending_local_description =
+                                self.pending_local_description.lock().await;
+                            *pending_local_description = None;
+                        }
+                        {
+                            let mut current_local_description =
+                                self.current_local_description.lock().await;
+                            *current_local_description = Some(sd.clone());
+                        }
+                        {
+                            let mut current_remote_description =
+                                self.current_remote_description.lock().await;
+                            *current_remote_description = pending_remote_description;
+                        }
+                        next_state
+                    }
+                    // have-local-offer->SetLocal(rollback)->stable
+                    // have-remote-pranswer->SetLocal(rollback)->stable
+                    SDPType::Rollback => {
+                        let next_state = check_next_signaling_state(
+                            cur,
+                            SignalingState::Stable,
+                            StateChangeOp::SetLocal,
+                            sd.sdp_type,
+                        )?;
+                        self.rollback(cur).await;
+                        next_state
+                    }
+                    // have-remote-offer->SetLocal(pranswer)->have-local-pranswer
+                    SDPType::Pranswer => {
+                        let check = {
+                            let last_answer = self.last_answer.lock().await;
+                            sd.sdp != *last_answer
+                        };
+                        if check {
+                            return Err(Error::ErrSDPDoesNotMatchAnswer.into());
+                        }
+
+                        let next_state = check_next_signaling_state(
+                            cur,
+                            SignalingState::HaveLocalPranswer,
+                            StateChangeOp::SetLocal,
+                            sd.sdp_type,
+                        )?;
+                        let mut pending_local_description =
+                            self.pending_local_description.lock().await;
+                        *pending_local_description = Some(sd.clone());
+                        next_state
+                    }
+                    _ => return Err(Error::ErrPeerConnStateChangeInvalid.into()),
+                }
+            }
+            StateChangeOp::SetRemote => {
+                match sd.sdp_type {
+                    // stable->SetRemote(offer)->have-remote-offer
+                    SDPType::Offer => {
+                        // have-local-offer->SetRemote(offer) implicitly rolls back
+                        // the local offer first, without a negotiation-needed check
+                        // in between
+                        let cur = if cur == SignalingState::HaveLocalOffer {
+                            check_next_signaling_state(
+                                cur,
+                                SignalingState::Stable,
+                                StateChangeOp::SetLocal,
+                                SDPType::Rollback,
+                            )?;
+                            self.rollback(cur).await;
+                            self.signaling_state
+                                .store(SignalingState::Stable as u8, Ordering::SeqCst);
+                            state_changes.push(SignalingState::Stable);
+                            SignalingState::Stable
+                        } else {
+                            cur
+                        };
+
+                        let next_state = check_next_signaling_state(
+                            cur,
+                            SignalingState::HaveRemoteOffer,
+                            StateChangeOp::SetRemote,
+                            sd.sdp_type,
+                        )?;
+                        let mut pending_remote_description =
+                            self.pending_remote_description.lock().await;
+                        *pending_remote_description = Some(sd.clone());
+                        next_state
+                    }
+                    // have-local-offer->SetRemote(answer)->stable
+                    SDPType::Answer => {
+                        let next_state = check_next_signaling_state(
+                            cur,
+                            SignalingState::Stable,
+                            StateChangeOp::SetRemote,
+                            sd.sdp_type,
+                        )?;
+                        let pending_local_description = {
+                            let mut pending_local_description =
+                                self.pending_local_description.lock().await;
+                            pending_local_description.take()
+                        };
+                        {
+                            let mut pending_remote_description =
+                                self.pending_remote_description.lock().await;
+                            *pending_remote_description = None;
+                        }
+                        {
+                            let mut current_remote_description =
+                                self.current_remote_description.lock().await;
+                            *current_remote_description = Some(sd.clone());
+                        }
+                        {
+                            let mut current_local_description =
+                                self.current_local_description.lock().await;
+                            *current_local_description = pending_local_description;
+                        }
+                        next_state
+                    }
+                    // have-remote-offer->SetRemote(rollback)->stable
+                    // have-local-pranswer->SetRemote(rollback)->stable
+                    SDPType::Rollback => {
+                        let next_state = check_next_signaling_state(
+                            cur,
+                            SignalingState::Stable,
+                            StateChangeOp::SetRemote,
+                            sd.sdp_type,
+                        )?;
+                        self.rollback(cur).await;
+                        next_state
+                    }
+                    // have-local-offer->SetRemote(pranswer)->have-remote-pranswer
+                    SDPType::Pranswer => {
+                        let next_state = check_next_signaling_state(
+                            cur,
+                            SignalingState::HaveRemotePranswer,
+                            StateChangeOp::SetRemote,
+                            sd.sdp_type,
+                        )?;
+                        let mut pending_remote_description =
+                            self.pending_remote_description.lock().await;
+                        *pending_remote_description = Some(sd.clone());
+                        next_state
+                    }
+                    _ => return Err(Error::ErrPeerConnStateChangeInvalid.into()),
+                }
+            }
+        };
+
+        self.signaling_state
+            .store(next_state as u8, Ordering::SeqCst);
+        if next_state == SignalingState::Stable {
+            // The transceivers the answered offer stopped are only stopped for
+            // good now that it can't be rolled back anymore
+            let snapshot = {
+                let mut remote_offer_snapshot = self.remote_offer_snapshot.lock().await;
+                remote_offer_snapshot.take()
+            };
+            if let Some(snapshot) = snapshot {
+                for s in snapshot.transceivers {
+                    if !s.stopped && s.transceiver.stopped.load(Ordering::SeqCst) {
+                        if let Err(err) = s.transceiver.stop().await {
+                            log::warn!("failed to stop transceiver: {}", err);
+                        }
+                    }
+                }
+            }
+            self.is_negotiation_needed.store(false, Ordering::SeqCst);
+            PeerConnection::do_negotiation_needed(self.negotiation_needed_params());
+        }
+        state_changes.push(next_state);
+
+        Ok(())
+    }
+
+    /// set_local_description sets the SessionDescription of the local peer
+    pub async fn set_local_description(&self, desc: SessionDescription) -> Result<()> {
+        if self.is_closed.load(Ordering::SeqCst) {
+            return Err(Error::ErrConnectionClosed.into());
+        }
+
+        let mut state_changes = vec![];
+        let result = self
+            .ops
+            .run(
+                self.do_set_local_description(desc, &mut state_changes),
+                "set_local_description",
+            )
+            .await;
+        for state in state_changes {
+            self.do_signaling_state_change(state).await;
+        }
+        result?
+    }
+
+    async fn do_set_local_description(
+        &self,
+        mut desc: SessionDescription,
+        state_changes: &mut Vec<SignalingState>,
+    ) -> Result<()> {
+        // JSEP 5.4
+        if desc.sdp.is_empty() {
+            match desc.sdp_type {
+                SDPType::Answer | SDPType::Pranswer => {
+                    let last_answer = self.last_answer.lock().await;
+                    desc.sdp = last_answer.clone();
+                }
+                SDPType::Offer => {
+                    let last_offer = self.last_offer.lock().await;
+                    desc.sdp = last_offer.clone();
+                }
+                SDPType::Rollback => {}
+                _ => return Err(Error::ErrPeerConnSDPTypeInvalidValueSetLocalDescription.into()),
+            }
+        }
+
+        if desc.sdp_type == SDPType::Rollback {
+            return self
+                .set_description(&desc, StateChangeOp::SetLocal, state_changes)
+                .await;
+        }
+
+        // make sure the sdp is parseable before it gets applied
+        let parsed = desc.unmarshal()?;
+        let have_local_description = {
+            let current_local_description = self.current_local_description.lock().await;
+            current_local_description.is_some()
+        };
+        self.set_description(&desc, StateChangeOp::SetLocal, state_changes)
+            .await?;
+
+        let current_transceivers = self.get_transceivers().await;
+        if desc.sdp_type == SDPType::Answer {
+            PeerConnection::set_rtp_transceiver_current_direction(
+                &parsed,
+                &current_transceivers,
+                false,
+            )
+            .await;
+
+            if let Some(remote_desc) = self.remote_description().await {
+                PeerConnection::start_rtp_senders(&current_transceivers).await?;
+
+                self.enqueue_start_rtp(
+                    StartRTPParams {
+                        is_renegotiation: have_local_description,
+                        remote_desc: remote_desc.unmarshal()?,
+                        current_transceivers,
+                        undeclared_media: self.undeclared_media_params(),
+                    },
+                    "set_local_description",
+                )
+                .await;
+            }
+        }
+
+        if self.ice_gatherer.state() == ICEGathererState::New {
+            self.ice_gatherer.gather().await
+        } else {
+            Ok(())
+        }
+    }
+
+    /// local_description returns pending_local_description if it is not null and
+    /// otherwise it returns current_local_description. This property is used to
+    /// determine if set_local_description has already been called.
+    /// https://www.w3.org/TR/webrtc/#dom-rtcpeerconnection-localdescription
+    pub async fn local_description(&self) -> Option<SessionDescription> {
+        if let Some(pending_local_description) = self.pending_local_description().await {
+            return Some(pending_local_description);
+        }
+        self.current_local_description().await
+    }
+
+    /// set_remote_description sets the SessionDescription of the remote peer
+    pub async fn set_remote_description(&self, desc: SessionDescription) -> Result<()> {
+        if self.is_closed.load(Ordering::SeqCst) {
+            return Err(Error::ErrConnectionClosed.into());
+        }
+
+        let mut state_changes = vec![];
+        let result = self
+            .ops
+            .run(
+                self.do_set_remote_description(desc, &mut state_changes),
+                "set_remote_description",
+            )
+            .await;
+        for state in state_changes {
+            self.do_signaling_state_change(state).await;
+        }
+        result?
+    }
+
+    async fn do_set_remote_description(
+        &self,
+        desc: SessionDescription,
+        state_changes: &mut Vec<SignalingState>,
+    ) -> Result<()> {
+        if desc.sdp_type == SDPType::Rollback {
+            return self
+                .set_description(&desc, StateChangeOp::SetRemote, state_changes)
+                .await;
+        }
+
+        // A provisional answer has already started the transports, so the
+        // final answer is handled the same way as a renegotiation
+        let is_renegotation = self.signaling_state() == SignalingState::HaveRemotePranswer || {
+            let current_remote_description = self.current_remote_description.lock().await;
+            current_remote_description.is_some()
+        };
+
+        let parsed = desc.unmarshal()?;
+        self.set_description(&desc, StateChangeOp::SetRemote, state_changes)
+            .await?;
+
+        let we_offer = desc.sdp_type == SDPType::Answer || desc.sdp_type == SDPType::Pranswer;
+        if !we_offer {
+            let mut transceivers = vec![];
+            for t in self.get_transceivers().await {
+                transceivers.push(TransceiverSnapshot {
+                    mid: t.mid().await,
+                    direction: t.direction(),
+                    stopped: t.stopped.load(Ordering::SeqCst),
+                    transceiver: t,
+                });
+            }
+            let negotiated = self.media_engine.negotiated_state().await;
+            let mut remote_offer_snapshot = self.remote_offer_snapshot.lock().await;
+            *remote_offer_snapshot = Some(RemoteOfferSnapshot {
+                transceivers,
+                created: vec![],
+                negotiated,
+            });
+        }
+
+        self.media_engine
+            .update_from_remote_description(&parsed, desc.sdp_type == SDPType::Offer)
+            .await?;
+
+        for media in &parsed.media_descriptions {
+            if let Some(mid_value) = get_mid_value(media) {
+                if mid_value.is_empty() {
+                    return Err(Error::ErrPeerConnRemoteDescriptionWithoutMidValue.into());
+                }
+            }
+        }
+
+        if !we_offer {
+            self.match_remote_transceivers(&parsed).await?;
+        }
+
+        let current_transceivers = self.get_transceivers().await;
+        if desc.sdp_type == SDPType::Answer {
+            PeerConnection::set_rtp_transceiver_current_direction(
+                &parsed,
+                &current_transceivers,
+                true,
+            )
+            .await;
+        }
+
+        let (remote_ufrag, remote_pwd, candidates) = extract_ice_details(&parsed).await?;
+
+        if is_renegotation
+            && self
+                .ice_transport
+                .have_remote_credentials_change(remote_ufrag.clone(), remote_pwd.clone())
+                .await
+        {
+            // An ICE Restart only happens implicitly for a set_remote_description of type offer
+            if !we_offer {
+                self.ice_transport.restart().await?;
+            }
+
+            self.ice_transport
+                .set_remote_credentials(remote_ufrag.clone(), remote_pwd.clone())
+                .await?;
+        }
+
+        for candidate in candidates {
+            self.ice_transport
+                .add_remote_candidate(Some(candidate))
+                .await?;
+        }
+
+        if is_renegotation {
+            if we_offer {
+                PeerConnection::start_rtp_senders(&current_transceivers).await?;
+
+                self.enqueue_start_rtp(
+                    StartRTPParams {
+                        is_renegotiation: true,
+                        remote_desc: parsed,
+                        current_transceivers,
+                        undeclared_media: self.undeclared_media_params(),
+                    },
+                    "set_remote_description",
+                )
+                .await;
+            }
+            return Ok(());
+        }
+
+        let remote_is_lite = is_ice_lite(&parsed);
+
+        let (fingerprint, fingerprint_hash) = extract_fingerprint(&parsed)?;
+
+        // If one of the agents is lite and the other one is not, the lite agent must be the controlling agent.
+        // If both or neither agents are lite the offering agent is controlling.
+        // RFC 8445 S6.1.1
+        let ice_role = if (we_offer && remote_is_lite == self.setting_engine.candidates.ice_lite)
+            || (remote_is_lite && !self.setting_engine.candidates.ice_lite)
+        {
+            ICERole::Controlling
+        } else {
+            ICERole::Controlled
+        };
+
+        let params = StartTransportsParams {
+            ice_transport: Arc::clone(&self.ice_transport),
+            dtls_transport: Arc::clone(&self.dtls_transport),
+            sctp_transport: Arc::clone(&self.sctp_transport),
+            is_closed: Arc::clone(&self.is_closed),
+            peer_connection_state: Arc::clone(&self.peer_connection_state),
+            ice_connection_state: Arc::clone(&self.ice_connection_state),
+            on_peer_connection_state_change_handler: Arc::clone(
+                &self.on_peer_connection_state_change_handler,
+            ),
+
+            ice_role,
+            dtls_role: DTLSRole::from(&parsed),
+            remote_ufrag,
+            remote_pwd,
+            fingerprint,
+            fingerprint_hash,
+            start_sctp: have_application_media_section(&parsed),
+        };
+
+        // The answering side starts RTP once its answer is applied
+        if we_offer {
+            PeerConnection::start_rtp_senders(&current_transceivers).await?;
+
+            self.enqueue_start_rtp(
+                StartRTPParams {
+                    is_renegotiation: false,
+                    remote_desc: parsed,
+                    current_transceivers,
+                    undeclared_media: self.undeclared_media_params(),
+                },
+                "set_remote_description",
+            )
+            .await;
+        }
+
+        // Start the networking in a task of its own since it will block until the
+        // connection is actually established, the descriptions applied meanwhile
+        // must not wait for it.
+        tokio::spawn(async move {
+            let start_sctp = params.start_sctp;
+            let sctp_transport = Arc::clone(&params.sctp_transport);
+
+            PeerConnection::start_transports(params).await;
+
+            if start_sctp {
+                PeerConnection::start_sctp(&sctp_transport).await;
+            }
+        });
+
+        Ok(())
+    }
+
+    /// enqueue_start_rtp starts RTP on the operations queue once the SRTP sessions
+    /// are created. The answer of a remote offer may be applied before the transports
+    /// are started, so waiting on the DTLSTransport keeps the order of the two. The
+    /// wait happens in a task chained to the one of the previous call, so RTP is
+    /// started in order without holding back the descriptions applied meanwhile.
+    async fn enqueue_start_rtp(&self, params: StartRTPParams, description: &'static str) {
+        let ops = Arc::clone(&self.ops);
+        let mut start_rtp_task = self.start_rtp_task.lock().await;
+        let previous_task = start_rtp_task.take();
+        *start_rtp_task = Some(tokio::spawn(async move {
+            if let Some(previous_task) = previous_task {
+                let _ = previous_task.await;
+            }
+
+            let dtls_transport = Arc::clone(&params.undeclared_media.dtls_transport);
+            dtls_transport.wait_srtp_ready().await;
+            if dtls_transport.get_srtp_session().await.is_none() {
+                log::warn!("SRTP is not started, RTP will not be started");
+                return;
+            }
+
+            let mut params = Some(params);
+            if let Err(err) = ops.enqueue(Operation::new(
+                move || {
+                    let params2 = params.take();
+                    Box::pin(async move {
+                        if let Some(params) = params2 {
+                            PeerConnection::start_rtp(params).await;
+                        }
+                        false
+                    })
+                },
+                description,
+            )) {
+                log::warn!("Failed to start RTP: {}", err);
+            }
+        }));
+    }
+
+    /// start_transports starts all transports. PeerConnection now has enough state
+    async fn start_transports(params: StartTransportsParams) {
+        log::trace!(
+            "start_transports: ice_role={}, dtls_role={}",
+            params.ice_role,
+            params.dtls_role,
+        );
+
+        // Start the ice transport
+        if let Err(err) = params
+            .ice_transport
+            .start(
+                ICEParameters {
+                    username_fragment: params.remote_ufrag,
+                    password: params.remote_pwd,
+                    ice_lite: false,
+                },
+                Some(params.ice_role),
+            )
+            .await
+        {
+            log::warn!("Failed to start manager ice: {}", err);
+            return;
+        }
+
+        // Start the dtls_transport transport
+        let result = params
+            .dtls_transport
+            .start(DTLSParameters {
+                role: params.dtls_role,
+                fingerprints: vec![DTLSFingerprint {
+                    algorithm: params.fingerprint_hash,
+                    value: params.fingerprint,
+                }],
+            })
+            .await;
+
+        PeerConnection::update_connection_state(
+            &params.on_peer_connection_state_change_handler,
+            &params.is_closed,
+            &params.peer_connection_state,
+            params.ice_connection_state.load(Ordering::SeqCst).into(),
+            params.dtls_transport.state(),
+        )
+        .await;
+
+        if let Err(err) = result {
+            log::warn!("Failed to start manager dtls: {}", err);
+        }
+    }
+
+    /// start_sctp starts the SCTP subsystem
+    async fn start_sctp(sctp_transport: &Arc<SCTPTransport>) {
+        // Start sctp
+        if let Err(err) = sctp_transport
+            .start(SCTPTransportCapabilities {
+                max_message_size: 0,
+            })
+            .await
+        {
+            log::warn!("Failed to start SCTP: {}", err);
+            if let Err(err) = sctp_transport.stop().await {
+                log::warn!("Failed to stop SCTPTransport: {}", err);
+            }
+
+            return;
+        }
+
+        // DataChannels that need to be opened now that SCTP is available
+        // make a copy we may have incoming DataChannels mutating this while we open
+        let data_channels = {
+            let data_channels = sctp_transport.data_channels.lock().await;
+            data_channels.clone()
+        };
+
+        let mut opened_dc_count = 0;
+        for d in data_channels {
+            if d.ready_state() == DataChannelState::Connecting {
+                if let Err(err) = d.open(Arc::clone(sctp_transport)).await {
+                    log::warn!("failed to open data channel: {}", err);
+                    continue;
+                }
+                opened_dc_count += 1;
+            }
+        }
+
+        sctp_transport
+            .data_channels_opened
+            .fetch_add(opened_dc_count, Ordering::SeqCst);
+    }
+
+    /// match_remote_transceivers associates each media section of a remote
+    /// offer with a local transceiver, creating the missing ones
+    async fn match_remote_transceivers(
+        &self,
+        parsed: &sdp::session_description::SessionDescription,
+    ) -> Result<()> {
+        let mut local_transceivers = self.get_transceivers().await;
+        for media in &parsed.media_descriptions {
+            let mid_value = match get_mid_value(media) {
+                Some(mid_value) => mid_value,
+                None => continue,
+            };
+
+            if media.media_name.media == MEDIA_SECTION_APPLICATION {
+                continue;
+            }
+
+            let kind = RTPCodecType::from(media.media_name.media.as_str());
+            let direction = get_peer_direction(media);
+            if kind == RTPCodecType::Unspecified
+                || direction == RTPTransceiverDirection::Unspecified
+            {
+                continue;
+            }
+
+            let t = match find_by_mid(mid_value, &mut local_transceivers).await {
+                Some(t) => {
+                    // The transceiver is stopped for good once the offer is
+                    // answered, until then a rollback restores it
+                    if direction == RTPTransceiverDirection::Inactive {
+                        t.stopped.store(true, Ordering::SeqCst);
+                        t.set_direction(RTPTransceiverDirection::Inactive);
+                    }
+                    Some(t)
+                }
+                None => satisfy_type_and_direction(kind, direction, &mut local_transceivers).await,
+            };
+
+            let t = match t {
+                Some(t) => {
+                    if direction == RTPTransceiverDirection::Recvonly
+                        && t.direction() == RTPTransceiverDirection::Sendrecv
+                    {
+                        t.set_direction(RTPTransceiverDirection::Sendonly);
+                    }
+                    t
+                }
+                None => {
+                    let receiver = Arc::new(RTPReceiver::new(
+                        kind,
+                        Arc::clone(&self.dtls_transport),
+                        Arc::clone(&self.media_engine),
+                        Arc::clone(&self.interceptor),
+                    ));
+
+                    let local_direction = if direction == RTPTransceiverDirection::Recvonly {
+                        RTPTransceiverDirection::Sendonly
+                    } else {
+                        RTPTransceiverDirection::Recvonly
+                    };
+
+                    let t = RTPTransceiver::new(
+                        Some(receiver),
+                        None,
+                        local_direction,
+                        kind,
+                        vec![],
+                        Arc::clone(&self.media_engine),
+                    );
+                    self.add_rtp_transceiver(Arc::clone(&t)).await;
+                    if let Some(snapshot) = &mut *self.remote_offer_snapshot.lock().await {
+                        snapshot.created.push(Arc::clone(&t));
+                    }
+                    t
+                }
+            };
+
+            if t.mid().await.is_empty() {
+                t.set_mid(mid_value.to_owned()).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// set_rtp_transceiver_current_direction updates the negotiated direction of
+    /// the transceivers from an answer. The directions of a remote answer are
+    /// the ones of the remote side, so they get reversed.
+    async fn set_rtp_transceiver_current_direction(
+        answer: &sdp::session_description::SessionDescription,
+        current_transceivers: &[Arc<RTPTransceiver>],
+        we_offer: bool,
+    ) {
+        for t in current_transceivers {
+            let media = match get_by_mid(&t.mid().await, answer) {
+                Some(media) => media,
+                None => continue,
+            };
+
+            let direction = get_peer_direction(media);
+            if direction == RTPTransceiverDirection::Unspecified {
+                continue;
+            }
+
+            if we_offer {
+                t.set_current_direction(direction.reverse());
+            } else {
+                t.set_current_direction(direction);
+            }
+        }
+    }
+
+    /// start_rtp_senders starts all outbound RTP streams
+    async fn start_rtp_senders(current_transceivers: &[Arc<RTPTransceiver>]) -> Result<()> {
+        for t in current_transceivers {
+            if let Some(sender) = t.sender().await {
+                if sender.is_negotiated() && !sender.has_sent().await {
+                    sender.set_mid(t.mid().await).await;
+                    sender.send(&sender.get_parameters().await).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// start_rtp starts receiving the tracks of the remote description. On a
+    /// renegotiation the receivers of the tracks which went away are replaced.
+    async fn start_rtp(params: StartRTPParams) {
+        let mut track_details = track_details_from_sdp(&params.remote_desc);
+
+        if !params.is_renegotiation {
+            PeerConnection::undeclared_media_processor(params.undeclared_media.clone());
+        } else {
+            for t in &params.current_transceivers {
+                let receiver = match t.receiver().await {
+                    Some(receiver) => receiver,
+                    None => continue,
+                };
+                let tracks = receiver.tracks().await;
+                if tracks.is_empty() {
+                    continue;
+                }
+
+                // the tracks are still there, their id and stream id may have changed
+                let mut receiver_needs_stopped = false;
+                for track in tracks {
+                    let details = if !track.rid().is_empty() {
+                        track_details_for_rid(&track_details, track.rid())
+                    } else {
+                        track_details_for_ssrc(&track_details, track.ssrc())
+                    };
+
+                    match details {
+                        Some(details) => {
+                            track.set_id(details.id.clone()).await;
+                            track.set_stream_id(details.stream_id.clone()).await;
+                        }
+                        None => receiver_needs_stopped = true,
+                    }
+                }
+
+                if !receiver_needs_stopped {
+                    continue;
+                }
+
+                if let Err(err) = receiver.stop().await {
+                    log::warn!("Failed to stop RtpReceiver: {}", err);
+                    continue;
+                }
+
+                t.set_receiver(Some(Arc::new(RTPReceiver::new(
+                    receiver.kind,
+                    Arc::clone(&receiver.transport),
+                    Arc::clone(&receiver.media_engine),
+                    Arc::clone(&receiver.interceptor),
+                ))))
+                .await;
+            }
+        }
+
+        PeerConnection::start_rtp_receivers(&mut track_details, &params).await;
+    }
+
+    /// start_rtp_receivers opens known inbound SRTP streams from the remote_description
+    async fn start_rtp_receivers(incoming_tracks: &mut Vec<TrackDetails>, params: &StartRTPParams) {
+        // Ensure we haven't already started a transceiver for this ssrc
+        for t in &params.current_transceivers {
+            if let Some(receiver) = t.receiver().await {
+                for track in receiver.tracks().await {
+                    if track.ssrc() != 0 {
+                        filter_track_with_ssrc(incoming_tracks, track.ssrc());
+                    }
+                }
+            }
+        }
+
+        for incoming_track in incoming_tracks.iter() {
+            for t in &params.current_transceivers {
+                if t.mid().await != incoming_track.mid {
+                    continue;
+                }
+
+                let receiver = match t.receiver().await {
+                    Some(receiver) => receiver,
+                    None => continue,
+                };
+                if incoming_track.kind != t.kind()
+                    || (t.direction() != RTPTransceiverDirection::Recvonly
+                        && t.direction() != RTPTransceiverDirection::Sendrecv)
+                    || receiver.have_received().await
+                {
+                    continue;
+                }
+
+                PeerConnection::start_receiver(incoming_track, receiver, &params.undeclared_media)
+                    .await;
+                break;
+            }
+        }
+    }
+
+    async fn start_receiver(
+        incoming: &TrackDetails,
+        receiver: Arc<RTPReceiver>,
+        params: &UndeclaredMediaParams,
+    ) {
+        let mut encodings = vec![];
+        if incoming.ssrc != 0 {
+            encodings.push(RTPDecodingParameters {
+                ssrc: incoming.ssrc,
+                rtx: RTPRtxParameters {
+                    ssrc: incoming.repair_ssrc,
+                },
+                ..Default::default()
+            });
+        }
+        for rid in &incoming.rids {
+            encodings.push(RTPDecodingParameters {
+                rid: rid.to_owned(),
+                ..Default::default()
+            });
+        }
+
+        if let Err(err) = receiver.receive(&RTPReceiveParameters { encodings }).await {
+            log::warn!("RTPReceiver Receive failed {}", err);
+            return;
+        }
+
+        // set track id and label early so they can be set as new track information
+        // is received from the SDP.
+        for track in receiver.tracks().await {
+            track.set_id(incoming.id.clone()).await;
+            track.set_stream_id(incoming.stream_id.clone()).await;
+        }
+
+        // We can't block and wait for a single SSRC, the tracks of the rids
+        // are fired once undeclared_media_processor has probed their SSRC
+        if incoming.ssrc == 0 {
+            return;
+        }
+
+        let track = match receiver.track().await {
+            Some(track) => track,
+            None => return,
+        };
+
+        let media_engine = Arc::clone(&params.media_engine);
+        let on_track_handler = Arc::clone(&params.on_track_handler);
+        tokio::spawn(async move {
+            if let Err(err) = track.determine_payload_type().await {
+                log::warn!(
+                    "Could not determine PayloadType for SSRC {}: {}",
+                    track.ssrc(),
+                    err
+                );
+                return;
+            }
+
+            let params = match media_engine
+                .get_rtp_parameters_by_payload_type(track.payload_type())
+                .await
+            {
+                Ok(params) => params,
+                Err(err) => {
+                    log::warn!(
+                        "no codec could be found for payloadType {}: {}",
+                        track.payload_type(),
+                        err
+                    );
+                    return;
+                }
+            };
+
+            track.set_kind(receiver.kind());
+            track.set_params(params).await;
+
+            PeerConnection::do_track(&on_track_handler, track, receiver).await;
+        });
+    }
+
+    /// undeclared_media_processor handles RTP/RTCP packets that don't match any a:ssrc lines
+    fn undeclared_media_processor(params: UndeclaredMediaParams) {
+        let rtcp_dtls_transport = Arc::clone(&params.dtls_transport);
+
+        tokio::spawn(async move {
+            let simulcast_routine_count = Arc::new(AtomicUsize::new(0));
+            loop {
+                let srtp_session = match params.dtls_transport.get_srtp_session().await {
+                    Some(srtp_session) => srtp_session,
+                    None => {
+                        log::warn!("undeclared_media_processor failed to open SrtpSession");
+                        return;
+                    }
+                };
+
+                let stream = match srtp_session.accept().await {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        log::warn!("Failed to accept RTP {}", err);
+                        return;
+                    }
+                };
+
+                if params.is_closed.load(Ordering::SeqCst) {
+                    if let Err(err) = stream.close().await {
+                        log::warn!("Failed to close RTP stream {}", err);
+                    }
+                    continue;
+                }
+
+                if let Err(err) = reserve_simulcast_probe(&simulcast_routine_count) {
+                    log::warn!("{}", err);
+                    if let Err(err) = stream.close().await {
+                        log::warn!("Failed to close RTP stream {}", err);
+                    }
+                    continue;
+                }
+
+                let params2 = params.clone();
+                let simulcast_routine_count2 = Arc::clone(&simulcast_routine_count);
+                tokio::spawn(async move {
+                    let ssrc = stream.get_ssrc();
+
+                    params2
+                        .dtls_transport
+                        .store_simulcast_stream(Arc::clone(&stream))
+                        .await;
+
+                    if let Err(err) =
+                        PeerConnection::handle_incoming_ssrc(stream, ssrc, &params2).await
+                    {
+                        log::error!(
+                            "Incoming unhandled RTP ssrc({}), on_track will not be fired. {}",
+                            ssrc,
+                            err
+                        );
+                    }
+
+                    simulcast_routine_count2.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+
+        tokio::spawn(async move {
+            loop {
+                let srtcp_session = match rtcp_dtls_transport.get_srtcp_session().await {
+                    Some(srtcp_session) => srtcp_session,
+                    None => {
+                        log::warn!("undeclared_media_processor failed to open SrtcpSession");
+                        return;
+                    }
+                };
+
+                let stream = match srtcp_session.accept().await {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        log::warn!("Failed to accept RTCP {}", err);
+                        return;
+                    }
+                };
+                log::warn!(
+                    "Incoming unhandled RTCP ssrc({}), on_track will not be fired",
+                    stream.get_ssrc()
+                );
+            }
+        });
+    }
+
+    /// handle_undeclared_ssrc binds an undeclared SSRC when the remote description
+    /// has a single media section, in which case the SSRC doesn't have to be
+    /// explicitly declared. It returns false if the SSRC has to be probed instead.
+    async fn handle_undeclared_ssrc(
+        ssrc: SSRC,
+        remote_description: &sdp::session_description::SessionDescription,
+        params: &UndeclaredMediaParams,
+    ) -> Result<bool> {
+        if remote_description.media_descriptions.len() != 1 {
+            return Ok(false);
+        }
+
+        let only_media_section = &remote_description.media_descriptions[0];
+        let mut stream_id = "";
+        let mut id = "";
+
+        for a in &only_media_section.attributes {
+            match a.key.as_str() {
+                ATTR_KEY_MSID => {
+                    if let Some(value) = &a.value {
+                        let mut split = value.split(' ');
+                        if let (Some(sid), Some(tid), None) =
+                            (split.next(), split.next(), split.next())
+                        {
+                            stream_id = sid;
+                            id = tid;
+                        }
+                    }
+                }
+                ATTR_KEY_SSRC => {
+                    return Err(Error::ErrPeerConnSingleMediaSectionHasExplicitSSRC.into())
+                }
+                ATTR_KEY_RID => return Ok(false),
+                _ => {}
+            };
+        }
+
+        let mid = match get_mid_value(only_media_section) {
+            Some(mid) => mid,
+            None => return Ok(false),
+        };
+
+        let incoming = TrackDetails {
+            mid: mid.to_owned(),
+            kind: RTPCodecType::from(only_media_section.media_name.media.as_str()),
+            stream_id: stream_id.to_owned(),
+            id: id.to_owned(),
+            ssrc,
+            ..Default::default()
+        };
+
+        let transceivers = {
+            let rtp_transceivers = params.rtp_transceivers.lock().await;
+            rtp_transceivers.clone()
+        };
+        for t in transceivers {
+            if t.mid().await != incoming.mid || t.kind() != incoming.kind {
+                continue;
+            }
+
+            let receiver = match t.receiver().await {
+                Some(receiver) => receiver,
+                None => continue,
+            };
+            if receiver.have_received().await {
+                continue;
+            }
+
+            PeerConnection::start_receiver(&incoming, receiver, params).await;
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    /// handle_incoming_ssrc binds an undeclared SSRC to a track, the stream of the
+    /// SSRC is closed and forgotten by the DTLSTransport if that fails
+    async fn handle_incoming_ssrc(
+        rtp_stream: Arc<SRTPStream>,
+        ssrc: SSRC,
+        params: &UndeclaredMediaParams,
+    ) -> Result<()> {
+        let result = PeerConnection::probe_incoming_ssrc(&rtp_stream, ssrc, params).await;
+        if result.is_err() {
+            if let Err(err) = rtp_stream.close().await {
+                log::warn!("Failed to close RTP stream {}", err);
+            }
+            params.dtls_transport.remove_simulcast_stream(ssrc).await;
+        }
+        result
+    }
+
+    /// probe_incoming_ssrc probes the packets of an undeclared SSRC for the mid
+    /// and rid header extensions, and binds the SSRC to the track of that rid
+    async fn probe_incoming_ssrc(
+        rtp_stream: &Arc<SRTPStream>,
+        ssrc: SSRC,
+        params: &UndeclaredMediaParams,
+    ) -> Result<()> {
+        let remote_description = match params.remote_description().await {
+            Some(remote_description) => remote_description.unmarshal()?,
+            None => return Err(Error::ErrPeerConnRemoteDescriptionNil.into()),
+        };
+
+        // If the remote SDP was only one media section the ssrc doesn't have to be explicitly declared
+        if PeerConnection::handle_undeclared_ssrc(ssrc, &remote_description, params).await? {
+            return Ok(());
+        }
+
+        let (mid_extension_id, audio_supported, video_supported) = params
+            .media_engine
+            .get_header_extension_id(RTPHeaderExtensionCapability {
+                uri: sdp::extmap::SDES_MID_URI.to_owned(),
+            })
+            .await;
+        if !audio_supported && !video_supported {
+            return Err(Error::ErrPeerConnSimulcastMidRTPExtensionRequired.into());
+        }
+
+        let (sid_extension_id, audio_supported, video_supported) = params
+            .media_engine
+            .get_header_extension_id(RTPHeaderExtensionCapability {
+                uri: sdp::extmap::SDES_RTP_STREAM_ID_URI.to_owned(),
+            })
+            .await;
+        if !audio_supported && !video_supported {
+            return Err(Error::ErrPeerConnSimulcastStreamIDRTPExtensionRequired.into());
+        }
+
+        let mut b = vec![0u8; RECEIVE_MTU];
+        let mut payload_type = 0;
+        let mut mid = String::new();
+        let mut rid = String::new();
+
+        for _ in 0..SIMULCAST_PROBE_COUNT {
+            let n = rtp_stream.read(&mut b).await?;
+            let (m, r, pt) =
+                handle_unknown_rtp_packet(&b[..n], mid_extension_id as u8, sid_extension_id as u8)?;
+            payload_type = pt;
+            if !m.is_empty() {
+                mid = m;
+            }
+            if !r.is_empty() {
+                rid = r;
+            }
+
+            if !mid.is_empty() && !rid.is_empty() {
+                break;
+            }
+        }
+
+        if !mid.is_empty() && !rid.is_empty() {
+            let transceivers = {
+                let rtp_transceivers = params.rtp_transceivers.lock().await;
+                rtp_transceivers.clone()
+            };
+            for t in transceivers {
+                if t.mid().await != mid {
+                    continue;
+                }
+                let receiver = match t.receiver().await {
+                    Some(receiver) => receiver,
+                    None => continue,
+                };
+
+                let rtp_params = params
+                    .media_engine
+                    .get_rtp_parameters_by_payload_type(payload_type)
+                    .await?;
+                match receiver
+                    .receive_for_rid(&rid, rtp_params, ssrc, Arc::clone(rtp_stream))
+                    .await
+                {
+                    Ok(track) => {
+                        PeerConnection::do_track(&params.on_track_handler, track, receiver).await;
+                        return Ok(());
+                    }
+                    Err(err) => {
+                        log::warn!("Failed to receive rid {}: {}", rid, err);
+                        break;
+                    }
+                }
+            }
+        }
+
+        Err(Error::ErrPeerConnSimulcastIncomingSSRCFailed.into())
+    }
+
+    async fn do_track(
+        on_track_handler: &Arc<Mutex<Option<OnTrackHdlrFn>>>,
+        track: Arc<TrackRemote>,
+        receiver: Arc<RTPReceiver>,
+    ) {
+        log::debug!("got new track: {}", track.id().await);
+
+        // the handler future is awaited after the lock is released, so the
+        // handler is free to use the PeerConnection
+        let fut = {
+            let mut handler = on_track_handler.lock().await;
+            handler.as_mut().map(|f| f(track, receiver))
+        };
+        if let Some(fut) = fut {
+            fut.await;
+        }
+    }
+
+    /// generate_unmatched_sdp generates an SDP that doesn't take remote state into account
+    /// This is used for the initial call for create_offer
+    async fn generate_unmatched_sdp(&self) -> Result<sdp::session_description::SessionDescription> {
+        let d = sdp::session_description::SessionDescription::new_jsep_session_description(false);
+
+        let ice_params = self.ice_gatherer.get_local_parameters().await?;
+        let candidates = self.ice_gatherer.get_local_candidates().await?;
+
+        let mut media_sections = vec![];
+        for t in self.get_transceivers().await {
+            if t.stopped.load(Ordering::SeqCst) {
+                // An "m=" section is generated for each
+                // RtpTransceiver that has been added to the PeerConnection, excluding
+                // any stopped RtpTransceivers;
+                continue;
+            }
+
+            if let Some(sender) = t.sender().await {
+                sender.set_negotiated();
+            }
+            media_sections.push(MediaSection {
+                id: t.mid().await,
+                transceivers: vec![t],
+                ..Default::default()
+            });
+        }
+
+        if self
+            .sctp_transport
+            .data_channels_requested
+            .load(Ordering::SeqCst)
+            != 0
+        {
+            media_sections.push(MediaSection {
+                id: format!("{}", media_sections.len()),
+                data: true,
+                ..Default::default()
+            });
+        }
+
+        let dtls_fingerprints = self.dtls_fingerprints()?;
+
+        let params = PopulateSdpParams {
+            media_description_fingerprint: self.setting_engine.sdp_media_level_fingerprints,
+            is_icelite: self.setting_engine.candidates.ice_lite,
+            connection_role: DEFAULT_DTLS_ROLE_OFFER.to_connection_role(),
+            ice_gathering_state: self.ice_gathering_state(),
+        };
+        populate_sdp(
+            d,
+            &dtls_fingerprints,
+            &self.media_engine,
+            &candidates,
+            &ice_params,
+            &media_sections,
+            params,
+        )
+        .await
+    }
+
+    /// generate_matched_sdp generates a SDP and takes the remote state into account
+    /// this is used everytime we have a remote_description
+    async fn generate_matched_sdp(
+        &self,
+        include_unmatched: bool,
+        connection_role: ConnectionRole,
+    ) -> Result<sdp::session_description::SessionDescription> {
+        let d = sdp::session_description::SessionDescription::new_jsep_session_description(false);
+
+        let ice_params = self.ice_gatherer.get_local_parameters().await?;
+        let candidates = self.ice_gatherer.get_local_candidates().await?;
+
+        let mut local_transceivers = self.get_transceivers().await;
+        let remote_description = self.remote_description().await;
+        let mut media_sections = vec![];
+        let mut already_have_application_media_section = false;
+        if let Some(remote_description) = &remote_description {
+            let parsed = remote_description.unmarshal()?;
+            let remote_is_offer = remote_description.sdp_type == SDPType::Offer;
+            for media in &parsed.media_descriptions {
+                let mid_value = match get_mid_value(media) {
+                    Some(mid_value) => mid_value,
+                    None => continue,
+                };
+                if mid_value.is_empty() {
+                    return Err(Error::ErrPeerConnRemoteDescriptionWithoutMidValue.into());
+                }
+
+                if media.media_name.media == MEDIA_SECTION_APPLICATION {
+                    media_sections.push(MediaSection {
+                        id: mid_value.to_owned(),
+                        data: true,
+                        ..Default::default()
+                    });
+                    already_have_application_media_section = true;
+                    continue;
+                }
+
+                let t = match find_by_mid(mid_value, &mut local_transceivers).await {
+                    Some(t) => t,
+                    None => return Err(Error::ErrPeerConnTranscieverMidNil.into()),
+                };
+                if let Some(sender) = t.sender().await {
+                    sender.set_negotiated();
+                }
+
+                let offered_direction = if remote_is_offer {
+                    Some(get_peer_direction(media))
+                } else {
+                    None
+                };
+                media_sections.push(MediaSection {
+                    id: mid_value.to_owned(),
+                    transceivers: vec![t],
+                    offered_direction,
+                    ..Default::default()
+                });
+            }
+        }
+
+        // If we are offering also include unmatched local transceivers
+        if include_unmatched {
+            for t in local_transceivers {
+                if let Some(sender) = t.sender().await {
+                    sender.set_negotiated();
+                }
+                media_sections.push(MediaSection {
+                    id: t.mid().await,
+                    transceivers: vec![t],
+                    ..Default::default()
+                });
+            }
+        }
+
+        // If we are offering also include unmatched local data channels
+        if include_unmatched
+            && self
+                .sctp_transport
+                .data_channels_requested
+                .load(Ordering::SeqCst)
+                != 0
+            && !already_have_application_media_section
+        {
+            media_sections.push(MediaSection {
+                id: format!("{}", media_sections.len()),
+                data: true,
+                ..Default::default()
+            });
+        }
+
+        let dtls_fingerprints = self.dtls_fingerprints()?;
+
+        let params = PopulateSdpParams {
+            media_description_fingerprint: self.setting_engine.sdp_media_level_fingerprints,
+            is_icelite: self.setting_engine.candidates.ice_lite,
+            connection_role,
+            ice_gathering_state: self.ice_gathering_state(),
+        };
+        populate_sdp(
+            d,
+            &dtls_fingerprints,
+            &self.media_engine,
+            &candidates,
+            &ice_params,
+            &media_sections,
+            params,
+        )
+        .await
+    }
+
+    fn dtls_fingerprints(&self) -> Result<Vec<DTLSFingerprint>> {
+        if let Some(cert) = self.dtls_transport.certificates.first() {
+            get_fingerprints(cert)
+        } else {
+            Err(Error::ErrNonCertificate.into())
+        }
+    }
+
+    /// remote_description returns pending_remote_description if it is not null and
+    /// otherwise it returns current_remote_description. This property is used to
+    /// determine if set_remote_description has already been called.
+    /// https://www.w3.org/TR/webrtc/#dom-rtcpeerconnection-remotedescription
+    pub async fn remote_description(&self) -> Option<SessionDescription> {
+        if let Some(pending_remote_description) = self.pending_remote_description().await {
+            return Some(pending_remote_description);
+        }
+        self.current_remote_description().await
+    }
+
+    /// add_ice_candidate accepts an ICE candidate string and adds it
+    /// to the existing set of candidates.
+    pub async fn add_ice_candidate(&self, candidate: ICECandidateInit) -> Result<()> {
+        if self.remote_description().await.is_none() {
+            return Err(Error::ErrNoRemoteDescription.into());
+        }
+
+        let candidate_value = match candidate.candidate.strip_prefix("candidate:") {
+            Some(s) => s,
+            None => candidate.candidate.as_str(),
+        };
+
+        let ice_candidate = if !candidate_value.is_empty() {
+            Some(unmarshal_candidate(candidate_value)?)
+        } else {
+            None
+        };
+
+        self.ice_transport.add_remote_candidate(ice_candidate).await
+    }
+
+    /// ice_connection_state returns the ICE connection state of the
+    /// PeerConnection instance.
+    pub fn ice_connection_state(&self) -> ICEConnectionState {
+        self.ice_connection_state.load(Ordering::SeqCst).into()
+    }
+
+    /// create_data_channel creates a new DataChannel object with the given label
+    /// and optional DataChannelConfig used to configure properties of the
+    /// underlying channel such as data reliability.
+    pub async fn create_data_channel(
+        &self,
+        label: &str,
+        options: Option<DataChannelConfig>,
+    ) -> Result<Arc<DataChannel>> {
+        // https://w3c.github.io/webrtc-pc/#peer-to-peer-data-api (Step #2)
+        if self.is_closed.load(Ordering::SeqCst) {
+            return Err(Error::ErrConnectionClosed.into());
+        }
+
+        self.ops
+            .run(
+                self.do_create_data_channel(label, options),
+                "create_data_channel",
+            )
+            .await?
+    }
+
+    async fn do_create_data_channel(
+        &self,
+        label: &str,
+        options: Option<DataChannelConfig>,
+    ) -> Result<Arc<DataChannel>> {
+        // https://w3c.github.io/webrtc-pc/#peer-to-peer-data-api (Step #5)
+        if label.len() > 65535 {
+            return Err(Error::ErrStringSizeLimit.into());
+        }
+
+        let mut params = DataChannelParameters {
+            label: label.to_owned(),
+            ordered: true,
+            ..Default::default()
+        };
+
+        // https://w3c.github.io/webrtc-pc/#peer-to-peer-data-api (Step #19)
+        if let Some(options) = options {
+            params.id = options.id;
+
+            // https://w3c.github.io/webrtc-pc/#peer-to-peer-data-api (Step #9)
+            params.ordered = options.ordered;
+
+            // https://w3c.github.io/webrtc-pc/#peer-to-peer-data-api (Step #7)
+            params.max_packet_lifetime = options.max_packet_life_time;
+
+            // https://w3c.github.io/webrtc-pc/#peer-to-peer-data-api (Step #8)
+            params.max_retransmits = options.max_retransmits;
+
+            // https://w3c.github.io/webrtc-pc/#peer-to-peer-data-api (Step #10)
+            params.protocol = options.protocol;
+
+            // https://w3c.github.io/webrtc-pc/#peer-to-peer-data-api (Step #11)
+            if params.protocol.len() > 65535 {
+                return Err(Error::ErrProtocolTooLarge.into());
+            }
+
+            // https://w3c.github.io/webrtc-pc/#peer-to-peer-data-api (Step #12)
+            params.negotiated = options.negotiated;
+        }
+
+        // https://w3c.github.io/webrtc-pc/#peer-to-peer-data-api (Step #16)
+        if params.max_packet_lifetime != 0 && params.max_retransmits != 0 {
+            return Err(Error::ErrRetransmitsOrPacketLifeTime.into());
+        }
+
+        let d = Arc::new(DataChannel::new(params, self.setting_engine.clone()));
+
+        {
+            let mut data_channels = self.sctp_transport.data_channels.lock().await;
+            data_channels.push(Arc::clone(&d));
+        }
+        self.sctp_transport
+            .data_channels_requested
+            .fetch_add(1, Ordering::SeqCst);
+
+        // If SCTP already connected open all the channels
+        if self.sctp_transport.state() == SCTPTransportState::Connected {
+            d.open(Arc::clone(&self.sctp_transport)).await?;
+        }
+
+        PeerConnection::do_negotiation_needed(self.negotiation_needed_params());
+
+        Ok(d)
+    }
+
+    /// add_rtp_transceiver appends t into rtp_transceivers
+    /// and fires onNegotiationNeeded
+    async fn add_rtp_transceiver(&self, t: Arc<RTPTransceiver>) {
+        {
+            let mut rtp_transceivers = self.rtp_transceivers.lock().await;
+            rtp_transceivers.push(t);
+        }
+        PeerConnection::do_negotiation_needed(self.negotiation_needed_params());
+    }
+
+    /// get_transceivers returns the RTPTransceiver that are currently attached to this PeerConnection
+    pub async fn get_transceivers(&self) -> Vec<Arc<RTPTransceiver>> {
+        let rtp_transceivers = self.rtp_transceivers.lock().await;
+        rtp_transceivers.clone()
+    }
+
+    /// get_senders returns the RTPSender that are currently attached to this PeerConnection
+    pub async fn get_senders(&self) -> Vec<Arc<RTPSender>> {
+        let mut senders = vec![];
+        for t in self.get_transceivers().await {
+            if let Some(sender) = t.sender().await {
+                senders.push(sender);
+            }
+        }
+        senders
+    }
+
+    /// get_receivers returns the RTPReceivers that are currently attached to this PeerConnection
+    pub async fn get_receivers(&self) -> Vec<Arc<RTPReceiver>> {
+        let mut receivers = vec![];
+        for t in self.get_transceivers().await {
+            if let Some(receiver) = t.receiver().await {
+                receivers.push(receiver);
+            }
+        }
+        receivers
+    }
+
+    /// add_track adds a Track to the PeerConnection
+    pub async fn add_track(
+        &self,
+        track: Arc<dyn TrackLocal + Send + Sync>,
+    ) -> Result<Arc<RTPSender>> {
+        if self.is_closed.load(Ordering::SeqCst) {
+            return Err(Error::ErrConnectionClosed.into());
+        }
+
+        self.ops.run(self.do_add_track(track), "add_track").await?
+    }
+
+    async fn do_add_track(
+        &self,
+        track: Arc<dyn TrackLocal + Send + Sync>,
+    ) -> Result<Arc<RTPSender>> {
+        // reuse a transceiver of the same kind which doesn't send yet
+        for t in self.get_transceivers().await {
+            if !t.stopped.load(Ordering::SeqCst)
+                && t.kind() == track.kind()
+                && t.sender().await.is_none()
+            {
+                let sender = Arc::new(RTPSender::new(
+                    Arc::clone(&track),
+                    Arc::clone(&self.dtls_transport),
+                    Arc::clone(&self.media_engine),
+                    Arc::clone(&self.interceptor),
+                ));
+
+                t.set_sender(Some(Arc::clone(&sender))).await;
+                if let Err(err) = t.set_sending_track(Some(Arc::clone(&track))).await {
+                    let _ = sender.stop().await;
+                    t.set_sender(None).await;
+                    return Err(err);
+                }
+
+                // a transceiver created by a remote offer is kept by a rollback
+                // of that offer once it has been given a track
+                if let Some(snapshot) = &mut *self.remote_offer_snapshot.lock().await {
+                    snapshot.created.retain(|c| !Arc::ptr_eq(c, &t));
+                }
+
+                PeerConnection::do_negotiation_needed(self.negotiation_needed_params());
+                return Ok(sender);
+            }
+        }
+
+        let t = self
+            .new_transceiver_from_track(RTPTransceiverDirection::Sendrecv, track)
+            .await?;
+        self.add_rtp_transceiver(Arc::clone(&t)).await;
+
+        match t.sender().await {
+            Some(sender) => Ok(sender),
+            None => Err(Error::ErrRTPSenderTrackNil.into()),
+        }
+    }
+
+    /// remove_track removes a Track from the PeerConnection
+    pub async fn remove_track(&self, sender: &Arc<RTPSender>) -> Result<()> {
+        if self.is_closed.load(Ordering::SeqCst) {
+            return Err(Error::ErrConnectionClosed.into());
+        }
+
+        self.ops
+            .run(self.do_remove_track(sender), "remove_track")
+            .await?
+    }
+
+    async fn do_remove_track(&self, sender: &Arc<RTPSender>) -> Result<()> {
+        let mut transceiver = None;
+        for t in self.get_transceivers().await {
+            if let Some(s) = t.sender().await {
+                if Arc::ptr_eq(&s, sender) {
+                    transceiver = Some(t);
+                    break;
+                }
+            }
+        }
+
+        let t = match transceiver {
+            Some(t) => t,
+            None => return Err(Error::ErrSenderNotCreatedByConnection.into()),
+        };
+
+        sender.stop().await?;
+        t.set_sending_track(None).await?;
+        PeerConnection::do_negotiation_needed(self.negotiation_needed_params());
+
+        Ok(())
+    }
+
+    async fn new_transceiver_from_track(
+        &self,
+        direction: RTPTransceiverDirection,
+        track: Arc<dyn TrackLocal + Send + Sync>,
+    ) -> Result<Arc<RTPTransceiver>> {
+        let receiver = match direction {
+            RTPTransceiverDirection::Sendrecv => Some(Arc::new(RTPReceiver::new(
+                track.kind(),
+                Arc::clone(&self.dtls_transport),
+                Arc::clone(&self.media_engine),
+                Arc::clone(&self.interceptor),
+            ))),
+            RTPTransceiverDirection::Sendonly => None,
+            _ => return Err(Error::ErrPeerConnAddTransceiverFromTrackSupport.into()),
+        };
+
+        let kind = track.kind();
+        let sender = Arc::new(RTPSender::new(
+            track,
+            Arc::clone(&self.dtls_transport),
+            Arc::clone(&self.media_engine),
+            Arc::clone(&self.interceptor),
+        ));
+
+        Ok(RTPTransceiver::new(
+            receiver,
+            Some(sender),
+            direction,
+            kind,
+            vec![],
+            Arc::clone(&self.media_engine),
+        ))
+    }
+
+    /// add_transceiver_from_kind Create a new RTPTransceiver and adds it to the set of transceivers.
+    pub async fn add_transceiver_from_kind(
+        &self,
+        kind: RTPCodecType,
+        init: &[RTPTransceiverInit],
+    ) -> Result<Arc<RTPTransceiver>> {
+        if self.is_closed.load(Ordering::SeqCst) {
+            return Err(Error::ErrConnectionClosed.into());
+        }
+
+        self.ops
+            .run(
+                self.do_add_transceiver_from_kind(kind, init),
+                "add_transceiver_from_kind",
+            )
+            .await?
+    }
+
+    async fn do_add_transceiver_from_kind(
+        &self,
+        kind: RTPCodecType,
+        init: &[RTPTransceiverInit],
+    ) -> Result<Arc<RTPTransceiver>> {
+        let direction = match init.len() {
+            0 => RTPTransceiverDirection::Sendrecv,
+            1 => init[0].direction,
+            _ => return Err(Error::ErrPeerConnAddTransceiverFromKindOnlyAcceptsOne.into()),
+        };
+
+        let t = match direction {
+            RTPTransceiverDirection::Sendrecv | RTPTransceiverDirection::Sendonly => {
+                let codecs = self.media_engine.get_codecs_by_kind(kind).await;
+                let codec = match codecs.first() {
+                    Some(codec) => codec.capability.clone(),
+                    None => return Err(Error::ErrNoCodecsAvailable.into()),
+                };
+
+                let track = Arc::new(TrackLocalStaticSample::new(
+                    codec,
+                    math_rand_alpha(16),
+                    math_rand_alpha(16),
+                ));
+                self.new_transceiver_from_track(direction, track).await?
+            }
+            RTPTransceiverDirection::Recvonly => {
+                let receiver = Arc::new(RTPReceiver::new(
+                    kind,
+                    Arc::clone(&self.dtls_transport),
+                    Arc::clone(&self.media_engine),
+                    Arc::clone(&self.interceptor),
+                ));
+
+                RTPTransceiver::new(
+                    Some(receiver),
+                    None,
+                    RTPTransceiverDirection::Recvonly,
+                    kind,
+                    vec![],
+                    Arc::clone(&self.media_engine),
+                )
+            }
+            _ => return Err(Error::ErrPeerConnAddTransceiverFromKindSupport.into()),
+        };
+
+        self.add_rtp_transceiver(Arc::clone(&t)).await;
+
+        Ok(t)
+    }
+
+    /// add_transceiver_from_track Create a new RTPTransceiver(SendRecv or SendOnly) and add it to the set of transceivers.
+    pub async fn add_transceiver_from_track(
+        &self,
+        track: Arc<dyn TrackLocal + Send + Sync>,
+        init: &[RTPTransceiverInit],
+    ) -> Result<Arc<RTPTransceiver>> {
+        if self.is_closed.load(Ordering::SeqCst) {
+            return Err(Error::ErrConnectionClosed.into());
+        }
+
+        self.ops
+            .run(
+                self.do_add_transceiver_from_track(track, init),
+                "add_transceiver_from_track",
+            )
+            .await?
+    }
+
+    async fn do_add_transceiver_from_track(
+        &self,
+        track: Arc<dyn TrackLocal + Send + Sync>,
+        init: &[RTPTransceiverInit],
+    ) -> Result<Arc<RTPTransceiver>> {
+        let direction = match init.len() {
+            0 => RTPTransceiverDirection::Sendrecv,
+            1 => init[0].direction,
+            _ => return Err(Error::ErrPeerConnAddTransceiverFromTrackOnlyAcceptsOne.into()),
+        };
+
+        let t = self.new_transceiver_from_track(direction, track).await?;
+        self.add_rtp_transceiver(Arc::clone(&t)).await;
+
+        Ok(t)
+    }
+
+    /// write_rtcp sends a user provided RTCP packet to the connected peer. If no peer is connected the
+    /// packet is discarded. The packets are written through the interceptors of the PeerConnection.
+    pub async fn write_rtcp(
+        &self,
+        pkts: &[Box<dyn rtcp::packet::Packet + Send + Sync>],
+    ) -> Result<usize> {
+        self.interceptor_rtcp_writer
+            .write(pkts, &Attributes::new())
+            .await
+    }
+
+    /// close ends the PeerConnection
+    pub async fn close(&self) -> Result<()> {
+        // https://www.w3.org/TR/webrtc/#dom-rtcpeerconnection-close (step #1)
+        if self.is_closed.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        // https://www.w3.org/TR/webrtc/#dom-rtcpeerconnection-close (step #2)
+        self.is_closed.store(true, Ordering::SeqCst);
+
+        // https://www.w3.org/TR/webrtc/#dom-rtcpeerconnection-close (step #3)
+        self.signaling_state
+            .store(SignalingState::Closed as u8, Ordering::SeqCst);
+
+        // Try closing everything and collect the errors
+        // Shutdown strategy:
+        // 1. All Conn close by closing their underlying Conn.
+        // 2. A Mux stops this chain. It won't close the underlying
+        //    Conn if one of the endpoints is closed down. To
+        //    continue the chain the Mux has to be closed.
+        let mut close_errs = vec![];
+
+        // https://www.w3.org/TR/webrtc/#dom-rtcpeerconnection-close (step #4)
+        {
+            let mut rtp_transceivers = self.rtp_transceivers.lock().await;
+            for t in &*rtp_transceivers {
+                if let Err(err) = t.stop().await {
+                    close_errs.push(err);
+                }
+            }
+            rtp_transceivers.clear();
+        }
+
+        if let Err(err) = self.interceptor.close().await {
+            close_errs.push(err);
+        }
+
+        // https://www.w3.org/TR/webrtc/#dom-rtcpeerconnection-close (step #5)
+        {
+            let mut data_channels = self.sctp_transport.data_channels.lock().await;
+            for d in &*data_channels {
+                if let Err(err) = d.close().await {
+                    close_errs.push(err);
+                }
+            }
+            data_channels.clear();
+        }
+
+        // https://www.w3.org/TR/webrtc/#dom-rtcpeerconnection-close (step #6)
+        if let Err(err) = self.sctp_transport.stop().await {
+            close_errs.push(err);
+        }
+
+        // https://www.w3.org/TR/webrtc/#dom-rtcpeerconnection-close (step #7)
+        if let Err(err) = self.dtls_transport.stop().await {
+            close_errs.push(err);
+        }
+
+        // https://www.w3.org/TR/webrtc/#dom-rtcpeerconnection-close (step #8, #9, #10)
+        if let Err(err) = self.ice_transport.stop().await {
+            close_errs.push(err);
+        }
+
+        // Stop the operations queue after the transports, so a pending
+        // start_transports operation gets unblocked first
+        self.ops.close().await;
+
+        // https://www.w3.org/TR/webrtc/#dom-rtcpeerconnection-close (step #11)
+        PeerConnection::update_connection_state(
+            &self.on_peer_connection_state_change_handler,
+            &self.is_closed,
+            &self.peer_connection_state,
+            self.ice_connection_state(),
+            self.dtls_transport.state(),
+        )
+        .await;
+
+        flatten_errs(close_errs)
+    }
+
+    /// current_local_description represents the local description that was
+    /// successfully negotiated the last time the PeerConnection transitioned
+    /// into the stable state plus any local candidates that have been generated
+    /// by the ICEAgent since the offer or answer was created.
+    pub async fn current_local_description(&self) -> Option<SessionDescription> {
+        let local_description = {
+            let current_local_description = self.current_local_description.lock().await;
+            current_local_description.clone()
+        };
+        populate_local_candidates(
+            local_description,
+            &self.ice_gatherer,
+            self.ice_gathering_state(),
+        )
+        .await
+    }
+
+    /// pending_local_description represents a local description that is in the
+    /// process of being negotiated plus any local candidates that have been
+    /// generated by the ICEAgent since the offer or answer was created. If the
+    /// PeerConnection is in the stable state, the value is null.
+    pub async fn pending_local_description(&self) -> Option<SessionDescription> {
+        let local_description = {
+            let pending_local_description = self.pending_local_description.lock().await;
+            pending_local_description.clone()
+        };
+        populate_local_candidates(
+            local_description,
+            &self.ice_gatherer,
+            self.ice_gathering_state(),
+        )
+        .await
+    }
+
+    /// current_remote_description represents the last remote description that was
+    /// successfully negotiated the last time the PeerConnection transitioned
+    /// into the stable state plus any remote candidates that have been supplied
+    /// via add_ice_candidate() since the offer or answer was created.
+    pub async fn current_remote_description(&self) -> Option<SessionDescription> {
+        let current_remote_description = self.current_remote_description.lock().await;
+        current_remote_description.clone()
+    }
+
+    /// pending_remote_description represents a remote description that is in the
+    /// process of being negotiated, complete with any remote candidates that
+    /// have been supplied via add_ice_candidate() since the offer or answer was
+    /// created. If the PeerConnection is in the stable state, the value is
+    /// null.
+    pub async fn pending_remote_description(&self) -> Option<SessionDescription> {
+        let pending_remote_description = self.pending_remote_description.lock().await;
+        pending_remote_description.clone()
+    }
+
+    /// signaling_state attribute returns the signaling state of the
+    /// PeerConnection instance.
+    pub fn signaling_state(&self) -> SignalingState {
+        self.signaling_state.load(Ordering::SeqCst).into()
+    }
+
+    /// ice_gathering_state attribute returns the ICE gathering state of the
+    /// PeerConnection instance.
+    pub fn ice_gathering_state(&self) -> ICEGatheringState {
+        match self.ice_gatherer.state() {
+            ICEGathererState::New => ICEGatheringState::New,
+            ICEGathererState::Gathering => ICEGatheringState::Gathering,
+            _ => ICEGatheringState::Complete,
+        }
+    }
+
+    /// connection_state attribute returns the connection state of the
+    /// PeerConnection instance.
+    pub fn connection_state(&self) -> PeerConnectionState {
+        self.peer_connection_state.load(Ordering::SeqCst).into()
+    }
+
+    /// sctp returns the SCTPTransport for this PeerConnection
+    ///
+    /// The SCTP transport over which SCTP data is sent and received. If SCTP has not been negotiated, the value is nil.
+    /// https://www.w3.org/TR/webrtc/#attributes-15
+    pub fn sctp(&self) -> Arc<SCTPTransport> {
+        Arc::clone(&self.sctp_transport)
+    }
+}
+
+/// reserve_simulcast_probe takes one of the SIMULCAST_MAX_PROBE_ROUTINES slots for
+/// probing an undeclared SSRC, the slot is given back once the probing is done
+fn reserve_simulcast_probe(simulcast_routine_count: &AtomicUsize) -> Result<()> {
+    if simulcast_routine_count.fetch_add(1, Ordering::SeqCst) >= SIMULCAST_MAX_PROBE_ROUTINES {
+        simulcast_routine_count.fetch_sub(1, Ordering::SeqCst);
+        return Err(Error::ErrSimulcastProbeOverflow.into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,899 @@
+use super::*;
+use crate::api::ApiBuilder;
+use crate::media::rtp::rtp_codec::RTPCodecCapability;
+use crate::media::Sample;
+use bytes::Bytes;
+use tokio::sync::mpsc;
+
+async fn new_pair(api: &Api) -> Result<(PeerConnection, PeerConnection)> {
+    let pca = api.new_peer_connection(Configuration::default()).await?;
+    let pcb = api.new_peer_connection(Configuration::default()).await?;
+
+    Ok((pca, pcb))
+}
+
+/// gathering_complete returns a receiver that is closed once the ICE gathering
+/// of the PeerConnection is complete
+async fn gathering_complete(pc: &PeerConnection) -> mpsc::Receiver<()> {
+    let (complete_tx, complete_rx) = mpsc::channel::<()>(1);
+    let complete_tx = Arc::new(Mutex::new(Some(complete_tx)));
+    pc.on_ice_gathering_state_change(Box::new(move |s: ICEGathererState| {
+        let complete_tx2 = Arc::clone(&complete_tx);
+        Box::pin(async move {
+            if s == ICEGathererState::Complete {
+                let mut complete_tx = complete_tx2.lock().await;
+                complete_tx.take();
+            }
+        })
+    }))
+    .await;
+    complete_rx
+}
+
+/// signal_pair negotiates pca as the offerer and pcb as the answerer, each side
+/// receiving the description of the other with all of its candidates
+async fn signal_pair(pca: &PeerConnection, pcb: &PeerConnection) -> Result<()> {
+    let offer = pca.create_offer(None).await?;
+    let mut offer_gathering_complete = gathering_complete(pca).await;
+    pca.set_local_description(offer).await?;
+    let _ = offer_gathering_complete.recv().await;
+    let offer = pca
+        .local_description()
+        .await
+        .ok_or(Error::ErrNoRemoteDescription)?;
+
+    pcb.set_remote_description(offer).await?;
+    let answer = pcb.create_answer(None).await?;
+    let mut answer_gathering_complete = gathering_complete(pcb).await;
+    pcb.set_local_description(answer).await?;
+    let _ = answer_gathering_complete.recv().await;
+    let answer = pcb
+        .local_description()
+        .await
+        .ok_or(Error::ErrNoRemoteDescription)?;
+
+    pca.set_remote_description(answer).await
+}
+
+/// on_connected returns a receiver that is closed once the PeerConnection is
+/// connected, and the list its PeerConnectionStates are recorded to
+async fn on_connected(
+    pc: &PeerConnection,
+) -> (mpsc::Receiver<()>, Arc<Mutex<Vec<PeerConnectionState>>>) {
+    let (connected_tx, connected_rx) = mpsc::channel::<()>(1);
+    let connected_tx = Arc::new(Mutex::new(Some(connected_tx)));
+    let states = Arc::new(Mutex::new(vec![]));
+    let states2 = Arc::clone(&states);
+    pc.on_peer_connection_state_change(Box::new(move |s: PeerConnectionState| {
+        let connected_tx2 = Arc::clone(&connected_tx);
+        let states3 = Arc::clone(&states2);
+        Box::pin(async move {
+            {
+                let mut states = states3.lock().await;
+                states.push(s);
+            }
+            if s == PeerConnectionState::Connected {
+                let mut connected_tx = connected_tx2.lock().await;
+                connected_tx.take();
+            }
+        })
+    }))
+    .await;
+    (connected_rx, states)
+}
+
+#[tokio::test]
+async fn test_peer_connection_close() -> Result<()> {
+    let api = ApiBuilder::new().build();
+    let pc = api.new_peer_connection(Configuration::default()).await?;
+
+    assert_eq!(pc.signaling_state(), SignalingState::Stable);
+    assert_eq!(pc.connection_state(), PeerConnectionState::New);
+
+    pc.close().await?;
+
+    assert_eq!(pc.signaling_state(), SignalingState::Closed);
+    assert_eq!(pc.connection_state(), PeerConnectionState::Closed);
+
+    // closing twice is a no-op
+    pc.close().await?;
+
+    if let Err(err) = pc.create_offer(None).await {
+        assert!(Error::ErrConnectionClosed.equal(&err));
+    } else {
+        panic!("expected error, but got ok");
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_peer_connection_create_answer_without_remote_description() -> Result<()> {
+    let api = ApiBuilder::new().build();
+    let pc = api.new_peer_connection(Configuration::default()).await?;
+
+    if let Err(err) = pc.create_answer(None).await {
+        assert!(Error::ErrNoRemoteDescription.equal(&err));
+    } else {
+        panic!("expected error, but got ok");
+    }
+
+    pc.close().await
+}
+
+#[tokio::test]
+async fn test_peer_connection_set_local_description_mismatch() -> Result<()> {
+    let api = ApiBuilder::new().build();
+    let (pca, pcb) = new_pair(&api).await?;
+
+    let _ = pca.create_offer(None).await?;
+    let offer = pcb.create_offer(None).await?;
+
+    if let Err(err) = pca.set_local_description(offer).await {
+        assert!(Error::ErrSDPDoesNotMatchOffer.equal(&err));
+    } else {
+        panic!("expected error, but got ok");
+    }
+
+    pca.close().await?;
+    pcb.close().await
+}
+
+#[tokio::test]
+async fn test_peer_connection_offer_answer_signaling_state() -> Result<()> {
+    let api = ApiBuilder::new().build();
+    let (offer_pc, answer_pc) = new_pair(&api).await?;
+
+    let _ = offer_pc.create_data_channel("data", None).await?;
+
+    let offer = offer_pc.create_offer(None).await?;
+    let parsed = offer.unmarshal()?;
+    assert!(have_application_media_section(&parsed));
+
+    offer_pc.set_local_description(offer.clone()).await?;
+    assert_eq!(offer_pc.signaling_state(), SignalingState::HaveLocalOffer);
+    assert!(offer_pc.pending_local_description().await.is_some());
+
+    answer_pc.set_remote_description(offer).await?;
+    assert_eq!(answer_pc.signaling_state(), SignalingState::HaveRemoteOffer);
+
+    let answer = answer_pc.create_answer(None).await?;
+    assert_eq!(answer.sdp_type, SDPType::Answer);
+
+    answer_pc.set_local_description(answer.clone()).await?;
+    assert_eq!(answer_pc.signaling_state(), SignalingState::Stable);
+    assert!(answer_pc.pending_remote_description().await.is_none());
+    assert!(answer_pc.current_remote_description().await.is_some());
+
+    offer_pc.set_remote_description(answer).await?;
+    assert_eq!(offer_pc.signaling_state(), SignalingState::Stable);
+    assert!(offer_pc.pending_local_description().await.is_none());
+    assert!(offer_pc.current_local_description().await.is_some());
+
+    offer_pc.close().await?;
+    answer_pc.close().await
+}
+
+#[tokio::test]
+async fn test_peer_connection_signaling_handler_reentrancy() -> Result<()> {
+    let api = ApiBuilder::new().build();
+    let (offer_pc, answer_pc) = new_pair(&api).await?;
+    let answer_pc = Arc::new(answer_pc);
+
+    let (done_tx, mut done_rx) = tokio::sync::mpsc::channel::<()>(1);
+    let done_tx = Arc::new(Mutex::new(Some(done_tx)));
+    let answer_pc2 = Arc::clone(&answer_pc);
+    answer_pc
+        .on_signaling_state_change(Box::new(move |s: SignalingState| {
+            let answer_pc3 = Arc::clone(&answer_pc2);
+            let done_tx2 = Arc::clone(&done_tx);
+            Box::pin(async move {
+                if s != SignalingState::HaveRemoteOffer {
+                    return;
+                }
+                // answering from within the handler must not deadlock
+                let answer = answer_pc3.create_answer(None).await.unwrap();
+                answer_pc3.set_local_description(answer).await.unwrap();
+                let mut done_tx3 = done_tx2.lock().await;
+                done_tx3.take();
+            })
+        }))
+        .await;
+
+    let _ = offer_pc.create_data_channel("data", None).await?;
+    let offer = offer_pc.create_offer(None).await?;
+    offer_pc.set_local_description(offer.clone()).await?;
+    answer_pc.set_remote_description(offer).await?;
+
+    let _ = done_rx.recv().await;
+    assert_eq!(answer_pc.signaling_state(), SignalingState::Stable);
+
+    offer_pc.close().await?;
+    answer_pc.close().await
+}
+
+#[tokio::test]
+async fn test_peer_connection_on_negotiation_needed() -> Result<()> {
+    let api = ApiBuilder::new().build();
+    let pc = api.new_peer_connection(Configuration::default()).await?;
+
+    let (negotiation_tx, mut negotiation_rx) = tokio::sync::mpsc::channel::<()>(1);
+    let negotiation_tx = Arc::new(negotiation_tx);
+    pc.on_negotiation_needed(Box::new(move || {
+        let negotiation_tx2 = Arc::clone(&negotiation_tx);
+        Box::pin(async move {
+            let _ = negotiation_tx2.send(()).await;
+        })
+    }))
+    .await;
+
+    let _ = pc.create_data_channel("initial_data_channel", None).await?;
+    assert!(negotiation_rx.recv().await.is_some());
+
+    // negotiation is already flagged as needed, so the event doesn't fire again
+    let _ = pc.create_data_channel("second_data_channel", None).await?;
+    assert!(
+        tokio::time::timeout(std::time::Duration::from_millis(100), negotiation_rx.recv())
+            .await
+            .is_err()
+    );
+
+    pc.close().await
+}
+
+#[tokio::test]
+async fn test_peer_connection_rollback() -> Result<()> {
+    let api = ApiBuilder::new().build();
+    let (offer_pc, answer_pc) = new_pair(&api).await?;
+
+    let _ = offer_pc.create_data_channel("data", None).await?;
+    let offer = offer_pc.create_offer(None).await?;
+
+    // local rollback
+    offer_pc.set_local_description(offer.clone()).await?;
+    assert_eq!(offer_pc.signaling_state(), SignalingState::HaveLocalOffer);
+    offer_pc
+        .set_local_description(SessionDescription {
+            sdp_type: SDPType::Rollback,
+            sdp: String::new(),
+        })
+        .await?;
+    assert_eq!(offer_pc.signaling_state(), SignalingState::Stable);
+    assert!(offer_pc.pending_local_description().await.is_none());
+    assert!(offer_pc.local_description().await.is_none());
+
+    // nothing left to roll back
+    if let Err(err) = offer_pc
+        .set_local_description(SessionDescription {
+            sdp_type: SDPType::Rollback,
+            sdp: String::new(),
+        })
+        .await
+    {
+        assert!(Error::ErrSignalingStateCannotRollback.equal(&err));
+    } else {
+        panic!("expected error, but got ok");
+    }
+
+    // remote rollback
+    answer_pc.set_remote_description(offer).await?;
+    assert_eq!(answer_pc.signaling_state(), SignalingState::HaveRemoteOffer);
+    answer_pc
+        .set_remote_description(SessionDescription {
+            sdp_type: SDPType::Rollback,
+            sdp: String::new(),
+        })
+        .await?;
+    assert_eq!(answer_pc.signaling_state(), SignalingState::Stable);
+    assert!(answer_pc.remote_description().await.is_none());
+
+    offer_pc.close().await?;
+    answer_pc.close().await
+}
+
+#[tokio::test]
+async fn test_peer_connection_rollback_remote_offer() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = ApiBuilder::new().with_media_engine(m).build();
+    let (pca, pcb) = new_pair(&api).await?;
+
+    let recvonly = [RTPTransceiverInit {
+        direction: RTPTransceiverDirection::Recvonly,
+        send_encodings: vec![],
+    }];
+    let audio = pca
+        .add_transceiver_from_kind(RTPCodecType::Audio, &recvonly)
+        .await?;
+    signal_pair(&pca, &pcb).await?;
+
+    let transceivers = pcb.get_transceivers().await;
+    assert_eq!(transceivers.len(), 1);
+    let answered = Arc::clone(&transceivers[0]);
+    assert_eq!(answered.mid().await, "0");
+    let answered_direction = answered.direction();
+    assert!(pcb.media_engine.negotiated_audio.load(Ordering::SeqCst));
+    assert!(!pcb.media_engine.negotiated_video.load(Ordering::SeqCst));
+
+    // the new offer stops the audio, and adds two video sections
+    audio.set_direction(RTPTransceiverDirection::Inactive);
+    pca.add_transceiver_from_kind(RTPCodecType::Video, &recvonly)
+        .await?;
+    pca.add_transceiver_from_kind(RTPCodecType::Video, &recvonly)
+        .await?;
+    let offer = pca.create_offer(None).await?;
+    pca.set_local_description(offer.clone()).await?;
+
+    pcb.set_remote_description(offer.clone()).await?;
+    let transceivers = pcb.get_transceivers().await;
+    assert_eq!(transceivers.len(), 3);
+    assert!(answered.stopped.load(Ordering::SeqCst));
+    assert_eq!(answered.direction(), RTPTransceiverDirection::Inactive);
+    assert!(pcb.media_engine.negotiated_video.load(Ordering::SeqCst));
+    let (with_track, without_track) = (Arc::clone(&transceivers[1]), Arc::clone(&transceivers[2]));
+
+    // a created transceiver given a track, and one added by the application
+    let track: Arc<dyn TrackLocal + Send + Sync> = Arc::new(TrackLocalStaticSample::new(
+        RTPCodecCapability {
+            mime_type: "video/vp8".to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "pion".to_owned(),
+    ));
+    let sender = pcb.add_track(track).await?;
+    assert!(with_track
+        .sender()
+        .await
+        .is_some_and(|s| Arc::ptr_eq(&s, &sender)));
+    let added = pcb
+        .add_transceiver_from_kind(RTPCodecType::Audio, &recvonly)
+        .await?;
+
+    pcb.set_remote_description(SessionDescription {
+        sdp_type: SDPType::Rollback,
+        sdp: String::new(),
+    })
+    .await?;
+    assert_eq!(pcb.signaling_state(), SignalingState::Stable);
+
+    let transceivers = pcb.get_transceivers().await;
+    assert_eq!(transceivers.len(), 3);
+    assert!(Arc::ptr_eq(&transceivers[0], &answered));
+    assert!(Arc::ptr_eq(&transceivers[1], &with_track));
+    assert!(Arc::ptr_eq(&transceivers[2], &added));
+    assert_eq!(answered.mid().await, "0");
+    assert_eq!(answered.direction(), answered_direction);
+    assert!(!answered.stopped.load(Ordering::SeqCst));
+    assert_eq!(with_track.mid().await, "");
+    assert!(!with_track.stopped.load(Ordering::SeqCst));
+    assert_eq!(added.mid().await, "");
+    assert_eq!(added.direction(), RTPTransceiverDirection::Recvonly);
+    assert!(without_track.stopped.load(Ordering::SeqCst));
+    assert!(pcb.media_engine.negotiated_audio.load(Ordering::SeqCst));
+    assert!(!pcb.media_engine.negotiated_video.load(Ordering::SeqCst));
+
+    // answering the offer stops the audio for good
+    pcb.set_remote_description(offer).await?;
+    assert!(answered.stopped.load(Ordering::SeqCst));
+    let answer = pcb.create_answer(None).await?;
+    pcb.set_local_description(answer).await?;
+    assert!(answered.stopped.load(Ordering::SeqCst));
+    assert_eq!(answered.direction(), RTPTransceiverDirection::Inactive);
+
+    pca.close().await?;
+    pcb.close().await
+}
+
+#[tokio::test]
+async fn test_peer_connection_pranswer() -> Result<()> {
+    let api = ApiBuilder::new().build();
+    let (offer_pc, answer_pc) = new_pair(&api).await?;
+
+    let _ = offer_pc.create_data_channel("data", None).await?;
+    let offer = offer_pc.create_offer(None).await?;
+    offer_pc.set_local_description(offer.clone()).await?;
+    answer_pc.set_remote_description(offer).await?;
+
+    let answer = answer_pc.create_answer(None).await?;
+    let pranswer = SessionDescription {
+        sdp_type: SDPType::Pranswer,
+        sdp: answer.sdp.clone(),
+    };
+
+    answer_pc.set_local_description(pranswer.clone()).await?;
+    assert_eq!(
+        answer_pc.signaling_state(),
+        SignalingState::HaveLocalPranswer
+    );
+    offer_pc.set_remote_description(pranswer).await?;
+    assert_eq!(
+        offer_pc.signaling_state(),
+        SignalingState::HaveRemotePranswer
+    );
+    assert!(offer_pc.pending_remote_description().await.is_some());
+    assert!(offer_pc.current_remote_description().await.is_none());
+
+    answer_pc.set_local_description(answer.clone()).await?;
+    assert_eq!(answer_pc.signaling_state(), SignalingState::Stable);
+    offer_pc.set_remote_description(answer).await?;
+    assert_eq!(offer_pc.signaling_state(), SignalingState::Stable);
+    assert!(offer_pc.pending_remote_description().await.is_none());
+    assert!(offer_pc.current_remote_description().await.is_some());
+
+    offer_pc.close().await?;
+    answer_pc.close().await
+}
+
+#[tokio::test]
+async fn test_peer_connection_implicit_rollback() -> Result<()> {
+    let api = ApiBuilder::new().build();
+    let (pca, pcb) = new_pair(&api).await?;
+
+    let _ = pca.create_data_channel("a", None).await?;
+    let _ = pcb.create_data_channel("b", None).await?;
+
+    let offer_a = pca.create_offer(None).await?;
+    pca.set_local_description(offer_a).await?;
+    let offer_b = pcb.create_offer(None).await?;
+    pcb.set_local_description(offer_b.clone()).await?;
+
+    // a remote offer in have-local-offer discards the local one
+    pca.set_remote_description(offer_b).await?;
+    assert_eq!(pca.signaling_state(), SignalingState::HaveRemoteOffer);
+    assert!(pca.pending_local_description().await.is_none());
+
+    pca.close().await?;
+    pcb.close().await
+}
+
+#[tokio::test]
+async fn test_peer_connection_add_transceiver_from_kind() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = ApiBuilder::new().with_media_engine(m).build();
+    let pc = api.new_peer_connection(Configuration::default()).await?;
+
+    if let Err(err) = pc
+        .add_transceiver_from_kind(
+            RTPCodecType::Video,
+            &[RTPTransceiverInit {
+                direction: RTPTransceiverDirection::Inactive,
+                send_encodings: vec![],
+            }],
+        )
+        .await
+    {
+        assert!(Error::ErrPeerConnAddTransceiverFromKindSupport.equal(&err));
+    } else {
+        panic!("expected error, but got ok");
+    }
+
+    let t = pc
+        .add_transceiver_from_kind(
+            RTPCodecType::Video,
+            &[RTPTransceiverInit {
+                direction: RTPTransceiverDirection::Recvonly,
+                send_encodings: vec![],
+            }],
+        )
+        .await?;
+    assert_eq!(t.kind(), RTPCodecType::Video);
+    assert_eq!(t.direction(), RTPTransceiverDirection::Recvonly);
+    assert_eq!(pc.get_transceivers().await.len(), 1);
+    assert_eq!(pc.get_receivers().await.len(), 1);
+    assert!(pc.get_senders().await.is_empty());
+
+    let offer = pc.create_offer(None).await?;
+    assert!(offer.sdp.contains("m=video"));
+    assert!(offer.sdp.contains("a=recvonly"));
+    assert_eq!(t.mid().await, "0");
+
+    pc.close().await?;
+    assert!(t.stopped.load(Ordering::SeqCst));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_peer_connection_answer_creates_transceiver() -> Result<()> {
+    let mut ma = MediaEngine::default();
+    ma.register_default_codecs()?;
+    let mut mb = MediaEngine::default();
+    mb.register_default_codecs()?;
+    let pca = ApiBuilder::new()
+        .with_media_engine(ma)
+        .build()
+        .new_peer_connection(Configuration::default())
+        .await?;
+    let pcb = ApiBuilder::new()
+        .with_media_engine(mb)
+        .build()
+        .new_peer_connection(Configuration::default())
+        .await?;
+
+    pca.add_transceiver_from_kind(
+        RTPCodecType::Audio,
+        &[RTPTransceiverInit {
+            direction: RTPTransceiverDirection::Recvonly,
+            send_encodings: vec![],
+        }],
+    )
+    .await?;
+
+    let offer = pca.create_offer(None).await?;
+    pca.set_local_description(offer.clone()).await?;
+    pcb.set_remote_description(offer).await?;
+
+    // the recvonly offer is matched by a new sendonly transceiver
+    let transceivers = pcb.get_transceivers().await;
+    assert_eq!(transceivers.len(), 1);
+    assert_eq!(transceivers[0].kind(), RTPCodecType::Audio);
+    assert_eq!(transceivers[0].mid().await, "0");
+    assert_eq!(
+        transceivers[0].direction(),
+        RTPTransceiverDirection::Sendonly
+    );
+
+    let answer = pcb.create_answer(None).await?;
+    assert!(answer.sdp.contains("m=audio"));
+
+    pca.close().await?;
+    pcb.close().await
+}
+
+#[tokio::test]
+async fn test_peer_connection_add_and_remove_track() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = ApiBuilder::new().with_media_engine(m).build();
+    let pc = api.new_peer_connection(Configuration::default()).await?;
+
+    let track: Arc<dyn TrackLocal + Send + Sync> = Arc::new(TrackLocalStaticSample::new(
+        RTPCodecCapability {
+            mime_type: "video/VP8".to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+
+    let sender = pc.add_track(Arc::clone(&track)).await?;
+    let transceivers = pc.get_transceivers().await;
+    assert_eq!(transceivers.len(), 1);
+    assert_eq!(
+        transceivers[0].direction(),
+        RTPTransceiverDirection::Sendrecv
+    );
+
+    let offer = pc.create_offer(None).await?;
+    assert!(offer.sdp.contains("a=msid:webrtc-rs video"));
+    assert!(offer
+        .sdp
+        .contains(format!("a=ssrc:{} cname:webrtc-rs", sender.ssrc).as_str()));
+    pc.set_local_description(offer).await?;
+
+    pc.remove_track(&sender).await?;
+    assert!(transceivers[0].sender().await.is_none());
+    assert_eq!(
+        transceivers[0].direction(),
+        RTPTransceiverDirection::Recvonly
+    );
+
+    // the removed sender is no longer known to the PeerConnection
+    if let Err(err) = pc.remove_track(&sender).await {
+        assert!(Error::ErrSenderNotCreatedByConnection.equal(&err));
+    } else {
+        panic!("expected error, but got ok");
+    }
+
+    // a new track reuses the transceiver which doesn't send anymore
+    pc.add_track(track).await?;
+    assert_eq!(pc.get_transceivers().await.len(), 1);
+    assert_eq!(
+        transceivers[0].direction(),
+        RTPTransceiverDirection::Sendrecv
+    );
+
+    pc.close().await
+}
+
+#[tokio::test]
+async fn test_peer_connection_add_track_while_applying_remote_offer() -> Result<()> {
+    let mut ma = MediaEngine::default();
+    ma.register_default_codecs()?;
+    let mut mb = MediaEngine::default();
+    mb.register_default_codecs()?;
+    let pca = ApiBuilder::new()
+        .with_media_engine(ma)
+        .build()
+        .new_peer_connection(Configuration::default())
+        .await?;
+    let pcb = ApiBuilder::new()
+        .with_media_engine(mb)
+        .build()
+        .new_peer_connection(Configuration::default())
+        .await?;
+
+    pca.add_transceiver_from_kind(
+        RTPCodecType::Video,
+        &[RTPTransceiverInit {
+            direction: RTPTransceiverDirection::Recvonly,
+            send_encodings: vec![],
+        }],
+    )
+    .await?;
+    let offer = pca.create_offer(None).await?;
+    pca.set_local_description(offer.clone()).await?;
+
+    let track: Arc<dyn TrackLocal + Send + Sync> = Arc::new(TrackLocalStaticSample::new(
+        RTPCodecCapability {
+            mime_type: "video/VP8".to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+
+    // add_track is queued behind the remote offer, so it reuses the transceiver
+    // the offer created instead of racing with it
+    let (applied, added) = tokio::join!(pcb.set_remote_description(offer), async {
+        let sender = pcb.add_track(track).await;
+        (sender, pcb.remote_description().await.is_some())
+    });
+    applied?;
+    let (sender, offer_applied) = added;
+    let sender = sender?;
+    assert!(offer_applied, "add_track must wait for the remote offer");
+
+    let transceivers = pcb.get_transceivers().await;
+    assert_eq!(transceivers.len(), 1);
+    assert_eq!(transceivers[0].mid().await, "0");
+    let transceiver_sender = transceivers[0].sender().await.expect("a sender");
+    assert!(Arc::ptr_eq(&transceiver_sender, &sender));
+
+    pca.close().await?;
+    pcb.close().await
+}
+
+#[test]
+fn test_reserve_simulcast_probe() -> Result<()> {
+    let simulcast_routine_count = AtomicUsize::new(0);
+    for _ in 0..SIMULCAST_MAX_PROBE_ROUTINES {
+        reserve_simulcast_probe(&simulcast_routine_count)?;
+    }
+
+    // the SSRC is ignored while all the probe routines are busy
+    if let Err(err) = reserve_simulcast_probe(&simulcast_routine_count) {
+        assert!(Error::ErrSimulcastProbeOverflow.equal(&err));
+    } else {
+        panic!("expected error, but got ok");
+    }
+    assert_eq!(
+        simulcast_routine_count.load(Ordering::SeqCst),
+        SIMULCAST_MAX_PROBE_ROUTINES
+    );
+
+    // a finished probe gives its slot back
+    simulcast_routine_count.fetch_sub(1, Ordering::SeqCst);
+    reserve_simulcast_probe(&simulcast_routine_count)?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_peer_connection_simulcast_offer() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = ApiBuilder::new().with_media_engine(m).build();
+    let pc = api.new_peer_connection(Configuration::default()).await?;
+
+    let new_track = |rid: &str| -> Arc<dyn TrackLocal + Send + Sync> {
+        Arc::new(TrackLocalStaticSample::new_with_rid(
+            RTPCodecCapability {
+                mime_type: "video/VP8".to_owned(),
+                ..Default::default()
+            },
+            "video".to_owned(),
+            rid.to_owned(),
+            "webrtc-rs".to_owned(),
+        ))
+    };
+
+    let sender = pc.add_track(new_track("f")).await?;
+    sender.add_encoding(new_track("h")).await?;
+    sender.add_encoding(new_track("q")).await?;
+
+    let offer = pc.create_offer(None).await?;
+    assert!(offer.sdp.contains("a=msid:webrtc-rs video"));
+    assert!(offer.sdp.contains("a=rid:f send"));
+    assert!(offer.sdp.contains("a=rid:h send"));
+    assert!(offer.sdp.contains("a=rid:q send"));
+    assert!(offer.sdp.contains("a=simulcast:send f;h;q"));
+    // the SSRCs of the layers are not declared
+    assert!(!offer.sdp.contains("a=ssrc:"));
+
+    pc.close().await
+}
+
+#[tokio::test]
+async fn test_peer_connection_loopback_connection_state() -> Result<()> {
+    let api = ApiBuilder::new().build();
+    let (pca, pcb) = new_pair(&api).await?;
+    let _ = pca.create_data_channel("data", None).await?;
+
+    let (mut pca_connected, pca_states) = on_connected(&pca).await;
+    let (mut pcb_connected, pcb_states) = on_connected(&pcb).await;
+
+    signal_pair(&pca, &pcb).await?;
+
+    let timeout = std::time::Duration::from_secs(10);
+    tokio::time::timeout(timeout, pca_connected.recv()).await?;
+    tokio::time::timeout(timeout, pcb_connected.recv()).await?;
+    assert_eq!(pca.connection_state(), PeerConnectionState::Connected);
+    assert_eq!(pcb.connection_state(), PeerConnectionState::Connected);
+
+    pca.close().await?;
+    pcb.close().await?;
+
+    for states in &[pca_states, pcb_states] {
+        let states = states.lock().await;
+        assert_eq!(
+            &states[..],
+            &[
+                PeerConnectionState::Connecting,
+                PeerConnectionState::Connected,
+                PeerConnectionState::Closed,
+            ][..]
+        );
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_peer_connection_on_track() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = ApiBuilder::new().with_media_engine(m).build();
+    let (pca, pcb) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTPCodecCapability {
+            mime_type: "video/VP8".to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+    pca.add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    let (on_track_tx, mut on_track_rx) = mpsc::channel::<Arc<TrackRemote>>(1);
+    pcb.on_track(Box::new(move |t: Arc<TrackRemote>, _: Arc<RTPReceiver>| {
+        let on_track_tx2 = on_track_tx.clone();
+        Box::pin(async move {
+            let _ = on_track_tx2.send(t).await;
+        })
+    }))
+    .await;
+
+    signal_pair(&pca, &pcb).await?;
+
+    // samples are written until the first one arrives and fires on_track
+    let remote_track = tokio::time::timeout(std::time::Duration::from_secs(10), async {
+        loop {
+            tokio::select! {
+                t = on_track_rx.recv() => return t,
+                _ = tokio::time::sleep(std::time::Duration::from_millis(20)) => {
+                    let _ = track
+                        .write_sample(&Sample {
+                            data: Bytes::from_static(&[0x00]),
+                            duration: std::time::Duration::from_millis(20),
+                            ..Default::default()
+                        })
+                        .await;
+                }
+            }
+        }
+    })
+    .await?
+    .ok_or(Error::ErrConnectionClosed)?;
+
+    assert_eq!(remote_track.kind(), RTPCodecType::Video);
+    assert_eq!(remote_track.id().await, "video");
+    assert_eq!(remote_track.stream_id().await, "webrtc-rs");
+    assert_eq!(
+        remote_track
+            .codec()
+            .await
+            .capability
+            .mime_type
+            .to_lowercase(),
+        "video/vp8"
+    );
+
+    let pkt = remote_track.read_rtp().await?;
+    assert_eq!(pkt.header.ssrc, remote_track.ssrc());
+
+    pca.close().await?;
+    pcb.close().await
+}
+
+#[tokio::test]
+async fn test_peer_connection_simulcast_probe_rid() -> Result<()> {
+    let new_api = || -> Result<Api> {
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+        for uri in &[
+            sdp::extmap::SDES_MID_URI,
+            sdp::extmap::SDES_RTP_STREAM_ID_URI,
+        ] {
+            m.register_header_extension(
+                RTPHeaderExtensionCapability {
+                    uri: (*uri).to_owned(),
+                },
+                RTPCodecType::Video,
+                vec![],
+            )?;
+        }
+        Ok(ApiBuilder::new().with_media_engine(m).build())
+    };
+    let pca = new_api()?
+        .new_peer_connection(Configuration::default())
+        .await?;
+    let pcb = new_api()?
+        .new_peer_connection(Configuration::default())
+        .await?;
+
+    let track = Arc::new(TrackLocalStaticSample::new_with_rid(
+        RTPCodecCapability {
+            mime_type: "video/VP8".to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "f".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+    pca.add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    let (on_track_tx, mut on_track_rx) = mpsc::channel::<Arc<TrackRemote>>(1);
+    pcb.on_track(Box::new(move |t: Arc<TrackRemote>, _: Arc<RTPReceiver>| {
+        let on_track_tx2 = on_track_tx.clone();
+        Box::pin(async move {
+            let _ = on_track_tx2.send(t).await;
+        })
+    }))
+    .await;
+
+    signal_pair(&pca, &pcb).await?;
+
+    // the SSRC of the layer is not declared, it is bound to the track of its rid
+    // once the mid and rid header extensions of its packets have been probed
+    let remote_track = tokio::time::timeout(std::time::Duration::from_secs(10), async {
+        loop {
+            tokio::select! {
+                t = on_track_rx.recv() => return t,
+                _ = tokio::time::sleep(std::time::Duration::from_millis(20)) => {
+                    let _ = track
+                        .write_sample(&Sample {
+                            data: Bytes::from_static(&[0x00]),
+                            duration: std::time::Duration::from_millis(20),
+                            ..Default::default()
+                        })
+                        .await;
+                }
+            }
+        }
+    })
+    .await?
+    .ok_or(Error::ErrConnectionClosed)?;
+
+    assert_eq!(remote_track.rid(), "f");
+    assert_eq!(remote_track.kind(), RTPCodecType::Video);
+    assert_ne!(remote_track.ssrc(), 0);
+
+    pca.close().await?;
+    pcb.close().await
+}
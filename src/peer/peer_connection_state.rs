@@ -1,8 +1,9 @@
 use std::fmt;
 
 /// PeerConnectionState indicates the state of the PeerConnection.
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
 pub enum PeerConnectionState {
+    #[default]
     Unspecified,
 
     /// PeerConnectionStateNew indicates that any of the ICETransports or
@@ -35,12 +36,6 @@ pub enum PeerConnectionState {
     Closed,
 }
 
-impl Default for PeerConnectionState {
-    fn default() -> Self {
-        PeerConnectionState::Unspecified
-    }
-}
-
 const PEER_CONNECTION_STATE_NEW_STR: &str = "New";
 const PEER_CONNECTION_STATE_CONNECTING_STR: &str = "Connecting";
 const PEER_CONNECTION_STATE_CONNECTED_STR: &str = "Connected";
@@ -91,8 +86,10 @@ impl fmt::Display for PeerConnectionState {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
 pub(crate) enum NegotiationNeededState {
     /// NegotiationNeededStateEmpty not running and queue is empty
+    #[default]
     Empty,
     /// NegotiationNeededStateEmpty running and queue is empty
     Run,
@@ -100,6 +97,16 @@ pub(crate) enum NegotiationNeededState {
     Queue,
 }
 
+impl From<u8> for NegotiationNeededState {
+    fn from(v: u8) -> Self {
+        match v {
+            1 => NegotiationNeededState::Run,
+            2 => NegotiationNeededState::Queue,
+            _ => NegotiationNeededState::Empty,
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
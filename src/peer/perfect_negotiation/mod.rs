@@ -0,0 +1,199 @@
+#[cfg(test)]
+mod perfect_negotiation_test;
+
+use crate::peer::ice::ice_candidate::{ICECandidate, ICECandidateInit};
+use crate::peer::peer_connection::PeerConnection;
+use crate::peer::sdp::sdp_type::SDPType;
+use crate::peer::sdp::session_description::SessionDescription;
+use crate::peer::signaling_state::SignalingState;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use tokio::sync::Mutex;
+
+/// SignalingMessage is what PerfectNegotiation hands to the signaling sink,
+/// and what it expects to be fed back from the remote peer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SignalingMessage {
+    Description(SessionDescription),
+    Candidate(ICECandidateInit),
+}
+
+pub type OnSignalHdlrFn = Box<
+    dyn (FnMut(SignalingMessage) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>)
+        + Send
+        + Sync,
+>;
+
+/// PerfectNegotiation implements the W3C "perfect negotiation" pattern on top
+/// of a PeerConnection, so both peers can renegotiate at any time without
+/// caring about offer collisions (glare).
+/// https://w3c.github.io/webrtc-pc/#perfect-negotiation-example
+///
+/// One of the peers must be polite and the other one impolite. On a collision
+/// the polite peer rolls back its own offer and answers the remote one, while
+/// the impolite peer ignores the remote offer and waits for its own answer.
+pub struct PerfectNegotiation {
+    pc: Arc<PeerConnection>,
+    polite: bool,
+
+    making_offer: AtomicBool,
+    ignore_offer: AtomicBool,
+    is_setting_remote_answer_pending: AtomicBool,
+
+    on_signal_handler: Mutex<OnSignalHdlrFn>,
+}
+
+impl PerfectNegotiation {
+    /// new wraps the PeerConnection, taking over its on_negotiation_needed and
+    /// on_ice_candidate events. Every description and candidate which has to
+    /// reach the remote peer is passed to on_signal.
+    pub async fn new(
+        pc: Arc<PeerConnection>,
+        polite: bool,
+        on_signal: OnSignalHdlrFn,
+    ) -> Arc<Self> {
+        let pn = Arc::new(PerfectNegotiation {
+            pc: Arc::clone(&pc),
+            polite,
+
+            making_offer: AtomicBool::new(false),
+            ignore_offer: AtomicBool::new(false),
+            is_setting_remote_answer_pending: AtomicBool::new(false),
+
+            on_signal_handler: Mutex::new(on_signal),
+        });
+
+        // The PeerConnection only keeps weak references, so dropping the
+        // PerfectNegotiation doesn't leak through the event handlers.
+        let weak_pn = Arc::downgrade(&pn);
+        pc.on_negotiation_needed(Box::new(move || {
+            let weak_pn2: Weak<PerfectNegotiation> = Weak::clone(&weak_pn);
+            Box::pin(async move {
+                if let Some(pn) = weak_pn2.upgrade() {
+                    if let Err(err) = pn.negotiate().await {
+                        log::warn!("perfect negotiation failed to make an offer: {}", err);
+                    }
+                }
+            })
+        }))
+        .await;
+
+        let weak_pn = Arc::downgrade(&pn);
+        pc.on_ice_candidate(Box::new(move |c: Option<ICECandidate>| {
+            let weak_pn2: Weak<PerfectNegotiation> = Weak::clone(&weak_pn);
+            Box::pin(async move {
+                if let (Some(pn), Some(c)) = (weak_pn2.upgrade(), c) {
+                    match c.to_json().await {
+                        Ok(init) => pn.signal(SignalingMessage::Candidate(init)).await,
+                        Err(err) => log::warn!("failed to serialize ice candidate: {}", err),
+                    }
+                }
+            })
+        }))
+        .await;
+
+        pn
+    }
+
+    /// peer_connection returns the wrapped PeerConnection
+    pub fn peer_connection(&self) -> Arc<PeerConnection> {
+        Arc::clone(&self.pc)
+    }
+
+    /// polite returns whether this peer gives way on an offer collision
+    pub fn polite(&self) -> bool {
+        self.polite
+    }
+
+    async fn signal(&self, msg: SignalingMessage) {
+        let mut f = self.on_signal_handler.lock().await;
+        f(msg).await;
+    }
+
+    /// negotiate makes a new offer and hands it to the signaling sink. It is
+    /// invoked whenever the PeerConnection fires on_negotiation_needed.
+    async fn negotiate(&self) -> Result<()> {
+        self.making_offer.store(true, Ordering::SeqCst);
+        let result = self.set_local_offer().await;
+        if let Ok(Some(desc)) = &result {
+            self.signal(SignalingMessage::Description(desc.clone()))
+                .await;
+        }
+        self.making_offer.store(false, Ordering::SeqCst);
+
+        result.map(|_| ())
+    }
+
+    async fn set_local_offer(&self) -> Result<Option<SessionDescription>> {
+        let offer = self.pc.create_offer(None).await?;
+        self.pc.set_local_description(offer).await?;
+        Ok(self.pc.local_description().await)
+    }
+
+    /// handle_message applies a message received from the remote peer's
+    /// signaling sink.
+    pub async fn handle_message(&self, msg: SignalingMessage) -> Result<()> {
+        match msg {
+            SignalingMessage::Description(desc) => self.handle_description(desc).await,
+            SignalingMessage::Candidate(candidate) => self.handle_candidate(candidate).await,
+        }
+    }
+
+    async fn handle_description(&self, desc: SessionDescription) -> Result<()> {
+        // An offer may come in while we are busy processing set_remote_description(answer).
+        // In this case, we will be in "stable" by the time the offer is processed
+        // so it is safe to apply it right away.
+        let ready_for_offer = !self.making_offer.load(Ordering::SeqCst)
+            && (self.pc.signaling_state() == SignalingState::Stable
+                || self.is_setting_remote_answer_pending.load(Ordering::SeqCst));
+        let offer_collision = desc.sdp_type == SDPType::Offer && !ready_for_offer;
+
+        let ignore_offer = !self.polite && offer_collision;
+        self.ignore_offer.store(ignore_offer, Ordering::SeqCst);
+        if ignore_offer {
+            log::debug!("ignoring colliding remote offer");
+            return Ok(());
+        }
+
+        // The polite peer gives way: set_remote_description rolls back the
+        // local offer implicitly before applying the remote one
+        if offer_collision {
+            log::debug!("rolling back local offer on collision");
+        }
+
+        let is_answer = desc.sdp_type == SDPType::Answer;
+        let is_offer = desc.sdp_type == SDPType::Offer;
+        self.is_setting_remote_answer_pending
+            .store(is_answer, Ordering::SeqCst);
+        let result = self.pc.set_remote_description(desc).await;
+        self.is_setting_remote_answer_pending
+            .store(false, Ordering::SeqCst);
+        result?;
+
+        if is_offer {
+            let answer = self.pc.create_answer(None).await?;
+            self.pc.set_local_description(answer).await?;
+            if let Some(desc) = self.pc.local_description().await {
+                self.signal(SignalingMessage::Description(desc)).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn handle_candidate(&self, candidate: ICECandidateInit) -> Result<()> {
+        if let Err(err) = self.pc.add_ice_candidate(candidate).await {
+            // candidates of an offer we ignored are expected to fail
+            if !self.ignore_offer.load(Ordering::SeqCst) {
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+}
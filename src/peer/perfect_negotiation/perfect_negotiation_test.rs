@@ -0,0 +1,120 @@
+use super::*;
+use crate::api::ApiBuilder;
+use crate::peer::configuration::Configuration;
+use tokio::sync::mpsc;
+use tokio::time::Duration;
+
+/// new_negotiator wires the signaling sink into a channel, which the test
+/// forwards to the other peer
+async fn new_negotiator(
+    polite: bool,
+) -> Result<(
+    Arc<PerfectNegotiation>,
+    mpsc::UnboundedReceiver<SignalingMessage>,
+)> {
+    let api = ApiBuilder::new().build();
+    let pc = Arc::new(api.new_peer_connection(Configuration::default()).await?);
+
+    let (signal_tx, signal_rx) = mpsc::unbounded_channel();
+    let pn = PerfectNegotiation::new(
+        pc,
+        polite,
+        Box::new(move |msg: SignalingMessage| {
+            let _ = signal_tx.send(msg);
+            Box::pin(async {})
+        }),
+    )
+    .await;
+
+    Ok((pn, signal_rx))
+}
+
+fn forward(mut signal_rx: mpsc::UnboundedReceiver<SignalingMessage>, to: Arc<PerfectNegotiation>) {
+    tokio::spawn(async move {
+        while let Some(msg) = signal_rx.recv().await {
+            if let Err(err) = to.handle_message(msg).await {
+                log::warn!("handle_message: {}", err);
+            }
+        }
+    });
+}
+
+async fn wait_stable(pn: &Arc<PerfectNegotiation>) -> bool {
+    let pc = pn.peer_connection();
+    for _ in 0..100 {
+        if pc.signaling_state() == SignalingState::Stable
+            && pc.current_remote_description().await.is_some()
+        {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    false
+}
+
+#[tokio::test]
+async fn test_perfect_negotiation_single_offerer() -> Result<()> {
+    let (polite, polite_rx) = new_negotiator(true).await?;
+    let (impolite, impolite_rx) = new_negotiator(false).await?;
+    forward(polite_rx, Arc::clone(&impolite));
+    forward(impolite_rx, Arc::clone(&polite));
+
+    let _ = impolite
+        .peer_connection()
+        .create_data_channel("data", None)
+        .await?;
+
+    assert!(wait_stable(&polite).await);
+    assert!(wait_stable(&impolite).await);
+
+    polite.peer_connection().close().await?;
+    impolite.peer_connection().close().await
+}
+
+#[tokio::test]
+async fn test_perfect_negotiation_offer_collision() -> Result<()> {
+    let (polite, polite_rx) = new_negotiator(true).await?;
+    let (impolite, impolite_rx) = new_negotiator(false).await?;
+
+    // Both peers make an offer before seeing the remote one
+    let _ = polite
+        .peer_connection()
+        .create_data_channel("polite", None)
+        .await?;
+    let _ = impolite
+        .peer_connection()
+        .create_data_channel("impolite", None)
+        .await?;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(
+        polite.peer_connection().signaling_state(),
+        SignalingState::HaveLocalOffer
+    );
+    assert_eq!(
+        impolite.peer_connection().signaling_state(),
+        SignalingState::HaveLocalOffer
+    );
+
+    forward(polite_rx, Arc::clone(&impolite));
+    forward(impolite_rx, Arc::clone(&polite));
+
+    // The polite peer rolls back and answers, the impolite offer wins
+    assert!(wait_stable(&polite).await);
+    assert!(wait_stable(&impolite).await);
+
+    let polite_remote = polite
+        .peer_connection()
+        .current_remote_description()
+        .await
+        .unwrap();
+    assert_eq!(polite_remote.sdp_type, SDPType::Offer);
+    let impolite_remote = impolite
+        .peer_connection()
+        .current_remote_description()
+        .await
+        .unwrap();
+    assert_eq!(impolite_remote.sdp_type, SDPType::Answer);
+
+    polite.peer_connection().close().await?;
+    impolite.peer_connection().close().await
+}
@@ -5,8 +5,9 @@ use std::fmt;
 /// endpoint is not bundle-aware, and what ICE candidates are gathered. If the
 /// remote endpoint is bundle-aware, all media tracks and data channels are
 /// bundled onto the same transport.
-#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize, Default)]
 pub enum BundlePolicy {
+    #[default]
     Unspecified = 0,
 
     /// BundlePolicyBalanced indicates to gather ICE candidates for each
@@ -26,12 +27,6 @@ pub enum BundlePolicy {
     MaxBundle = 3,
 }
 
-impl Default for BundlePolicy {
-    fn default() -> Self {
-        BundlePolicy::Unspecified
-    }
-}
-
 /// This is done this way because of a linter.
 const BUNDLE_POLICY_BALANCED_STR: &str = "Balanced";
 const BUNDLE_POLICY_MAX_COMPAT_STR: &str = "MaxCompat";
@@ -3,8 +3,9 @@ use std::fmt;
 
 /// ICETransportPolicy defines the ICE candidate policy surface the
 /// permitted candidates. Only these candidates are used for connectivity checks.
-#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize, Default)]
 pub enum ICETransportPolicy {
+    #[default]
     Unspecified = 0,
 
     /// ICETransportPolicyAll indicates any type of candidate is used.
@@ -15,12 +16,6 @@ pub enum ICETransportPolicy {
     Relay = 2,
 }
 
-impl Default for ICETransportPolicy {
-    fn default() -> Self {
-        ICETransportPolicy::Unspecified
-    }
-}
-
 /// ICEGatherPolicy is the ORTC equivalent of ICETransportPolicy
 pub type ICEGatherPolicy = ICETransportPolicy;
 
@@ -3,7 +3,7 @@ use std::fmt;
 
 /// RTCPMuxPolicy affects what ICE candidates are gathered to support
 /// non-multiplexed RTCP.
-#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize, Default)]
 pub enum RTCPMuxPolicy {
     Unspecified = 0,
 
@@ -11,6 +11,7 @@ pub enum RTCPMuxPolicy {
     /// RTP and RTCP candidates. If the remote-endpoint is capable of
     /// multiplexing RTCP, multiplex RTCP on the RTP candidates. If it is not,
     /// use both the RTP and RTCP candidates separately.
+    #[default]
     Negotiate = 1,
 
     /// RTCPMuxPolicyRequire indicates to gather ICE candidates only for
@@ -19,12 +20,6 @@ pub enum RTCPMuxPolicy {
     Require = 2,
 }
 
-impl Default for RTCPMuxPolicy {
-    fn default() -> Self {
-        RTCPMuxPolicy::Negotiate
-    }
-}
-
 const RTCP_MUX_POLICY_NEGOTIATE_STR: &str = "Negotiate";
 const RTCP_MUX_POLICY_REQUIRE_STR: &str = "Require";
 
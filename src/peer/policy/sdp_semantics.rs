@@ -3,8 +3,9 @@ use std::fmt;
 
 /// SDPSemantics determines which style of SDP offers and answers
 /// can be used
-#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize, Default)]
 pub enum SDPSemantics {
+    #[default]
     Unspecified = 0,
 
     /// UnifiedPlan uses unified-plan offers and answers
@@ -23,12 +24,6 @@ pub enum SDPSemantics {
     UnifiedPlanWithFallback = 3,
 }
 
-impl Default for SDPSemantics {
-    fn default() -> Self {
-        SDPSemantics::Unspecified
-    }
-}
-
 const SDP_SEMANTICS_UNIFIED_PLAN_WITH_FALLBACK: &str = "UnifiedPlanWithFallback";
 const SDP_SEMANTICS_UNIFIED_PLAN: &str = "UnifiedPlan";
 const SDP_SEMANTICS_PLAN_B: &str = "PlanB";
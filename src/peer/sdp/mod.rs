@@ -1,2 +1,1263 @@
 pub mod sdp_type;
 pub mod session_description;
+
+use crate::api::media_engine::MediaEngine;
+use crate::error::Error;
+use crate::media::dtls_transport::dtls_fingerprint::DTLSFingerprint;
+use crate::media::rtp::rtp_codec::{RTPCodecCapability, RTPCodecParameters, RTPCodecType};
+use crate::media::rtp::rtp_transceiver::RTPTransceiver;
+use crate::media::rtp::rtp_transceiver_direction::RTPTransceiverDirection;
+use crate::media::rtp::{PayloadType, RTCPFeedback, SSRC};
+use crate::peer::ice::ice_candidate::{unmarshal_candidate, ICECandidate};
+use crate::peer::ice::ice_gather::ice_gatherer::ICEGatherer;
+use crate::peer::ice::ice_gather::ice_gathering_state::ICEGatheringState;
+use crate::peer::ice::ICEParameters;
+use crate::MEDIA_SECTION_APPLICATION;
+use session_description::SessionDescription as RTCSessionDescription;
+
+use anyhow::Result;
+use ice::candidate::Candidate;
+use sdp::media_description::MediaDescription;
+use sdp::session_description::{Origin, SessionDescription};
+use sdp::util::ConnectionRole;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub(crate) const ATTR_KEY_MID: &str = "mid";
+pub(crate) const ATTR_KEY_ICE_UFRAG: &str = "ice-ufrag";
+pub(crate) const ATTR_KEY_ICE_PWD: &str = "ice-pwd";
+pub(crate) const ATTR_KEY_CANDIDATE: &str = "candidate";
+pub(crate) const ATTR_KEY_END_OF_CANDIDATES: &str = "end-of-candidates";
+pub(crate) const ATTR_KEY_FINGERPRINT: &str = "fingerprint";
+pub(crate) const ATTR_KEY_CONNECTION_SETUP: &str = "setup";
+pub(crate) const ATTR_KEY_GROUP: &str = "group";
+pub(crate) const ATTR_KEY_ICELITE: &str = "ice-lite";
+pub(crate) const ATTR_KEY_RTPMAP: &str = "rtpmap";
+pub(crate) const ATTR_KEY_FMTP: &str = "fmtp";
+pub(crate) const ATTR_KEY_RTCPFB: &str = "rtcp-fb";
+pub(crate) const ATTR_KEY_EXTMAP: &str = "extmap";
+pub(crate) const ATTR_KEY_SSRC: &str = "ssrc";
+pub(crate) const ATTR_KEY_SSRCGROUP: &str = "ssrc-group";
+pub(crate) const ATTR_KEY_MSID: &str = "msid";
+pub(crate) const ATTR_KEY_RTCPMUX: &str = "rtcp-mux";
+pub(crate) const ATTR_KEY_RTCPRSIZE: &str = "rtcp-rsize";
+pub(crate) const ATTR_KEY_RID: &str = "rid";
+pub(crate) const ATTR_KEY_SIMULCAST: &str = "simulcast";
+
+pub(crate) const SEMANTIC_TOKEN_FLOW_IDENTIFICATION: &str = "FID";
+
+/// TrackDetails represents any media source that can be represented in a SDP
+/// This isn't keyed by SSRC because it also needs to support rid based sources
+#[derive(Default, Debug, Clone, PartialEq)]
+pub(crate) struct TrackDetails {
+    pub(crate) mid: String,
+    pub(crate) kind: RTPCodecType,
+    pub(crate) stream_id: String,
+    pub(crate) id: String,
+    pub(crate) ssrc: SSRC,
+    /// repair_ssrc is the SSRC of the RTX repair flow of the track, 0 without RTX
+    pub(crate) repair_ssrc: SSRC,
+    pub(crate) rids: Vec<String>,
+}
+
+/// MediaSection is a single m= line that populate_sdp will generate
+#[derive(Default, Clone)]
+pub(crate) struct MediaSection {
+    pub(crate) id: String,
+    pub(crate) transceivers: Vec<Arc<RTPTransceiver>>,
+    pub(crate) data: bool,
+    /// the direction of the matching section of a remote offer, which an
+    /// answer has to agree with
+    pub(crate) offered_direction: Option<RTPTransceiverDirection>,
+}
+
+pub(crate) struct PopulateSdpParams {
+    pub(crate) media_description_fingerprint: bool,
+    pub(crate) is_icelite: bool,
+    pub(crate) connection_role: ConnectionRole,
+    pub(crate) ice_gathering_state: ICEGatheringState,
+}
+
+pub(crate) struct AddTransceiverSdpParams {
+    should_add_candidates: bool,
+    mid_value: String,
+    dtls_role: ConnectionRole,
+    ice_gathering_state: ICEGatheringState,
+}
+
+pub(crate) struct AddDataMediaSectionParams {
+    should_add_candidates: bool,
+    mid_value: String,
+    ice_params: ICEParameters,
+    dtls_role: ConnectionRole,
+    ice_gathering_state: ICEGatheringState,
+}
+
+/// session_attribute returns the value of the first session level attribute with the given key
+fn session_attribute<'a>(desc: &'a SessionDescription, key: &str) -> Option<&'a String> {
+    for a in &desc.attributes {
+        if a.key == key {
+            return a.value.as_ref();
+        }
+    }
+    None
+}
+
+/// media_attribute returns the value of the first media level attribute with the given key
+pub(crate) fn media_attribute<'a>(media: &'a MediaDescription, key: &str) -> Option<&'a String> {
+    for a in &media.attributes {
+        if a.key == key {
+            return a.value.as_ref();
+        }
+    }
+    None
+}
+
+pub(crate) async fn add_candidates_to_media_descriptions(
+    candidates: &[ICECandidate],
+    mut m: MediaDescription,
+    ice_gathering_state: ICEGatheringState,
+) -> Result<MediaDescription> {
+    for c in candidates {
+        let candidate = c.to_ice().await?;
+        let marshaled = candidate.marshal();
+
+        let is_new = !m
+            .attributes
+            .iter()
+            .any(|a| a.key == ATTR_KEY_CANDIDATE && a.value.as_ref() == Some(&marshaled));
+        if is_new {
+            m = m.with_value_attribute(ATTR_KEY_CANDIDATE.to_owned(), marshaled);
+        }
+    }
+
+    if ice_gathering_state != ICEGatheringState::Complete {
+        return Ok(m);
+    }
+    for a in &m.attributes {
+        if a.key == ATTR_KEY_END_OF_CANDIDATES {
+            return Ok(m);
+        }
+    }
+
+    Ok(m.with_property_attribute(ATTR_KEY_END_OF_CANDIDATES.to_owned()))
+}
+
+pub(crate) async fn add_data_media_section(
+    d: SessionDescription,
+    dtls_fingerprints: &[DTLSFingerprint],
+    candidates: &[ICECandidate],
+    params: AddDataMediaSectionParams,
+) -> Result<SessionDescription> {
+    let mut media =
+        MediaDescription::new_jsep_media_description(MEDIA_SECTION_APPLICATION.to_owned(), vec![]);
+    media.media_name.protos = vec!["UDP".to_owned(), "DTLS".to_owned(), "SCTP".to_owned()];
+    media.media_name.formats = vec!["webrtc-datachannel".to_owned()];
+
+    let mut media = media
+        .with_value_attribute(
+            ATTR_KEY_CONNECTION_SETUP.to_owned(),
+            params.dtls_role.to_string(),
+        )
+        .with_value_attribute(ATTR_KEY_MID.to_owned(), params.mid_value)
+        .with_property_attribute("sendrecv".to_owned())
+        .with_property_attribute("sctp-port:5000".to_owned())
+        .with_ice_credentials(
+            params.ice_params.username_fragment,
+            params.ice_params.password,
+        );
+
+    for f in dtls_fingerprints {
+        media = media.with_fingerprint(f.algorithm.clone(), f.value.to_uppercase());
+    }
+
+    if params.should_add_candidates {
+        media = add_candidates_to_media_descriptions(candidates, media, params.ice_gathering_state)
+            .await?;
+    }
+
+    Ok(d.with_media(media))
+}
+
+/// add_transceiver_sdp adds the media section of the transceivers, and returns
+/// false if the section was rejected, because none of its codecs is supported
+pub(crate) async fn add_transceiver_sdp(
+    d: SessionDescription,
+    dtls_fingerprints: &[DTLSFingerprint],
+    media_engine: &Arc<MediaEngine>,
+    ice_params: &ICEParameters,
+    candidates: &[ICECandidate],
+    media_section: &MediaSection,
+    params: AddTransceiverSdpParams,
+) -> Result<(SessionDescription, bool)> {
+    // Use the first transceiver to generate the section attributes
+    let t = match media_section.transceivers.first() {
+        Some(t) => t,
+        None => return Err(Error::ErrSDPZeroTransceivers.into()),
+    };
+
+    let mut media = MediaDescription::new_jsep_media_description(t.kind().to_string(), vec![]);
+
+    let codecs = t.get_codecs().await;
+    if codecs.is_empty() {
+        // Explicitly reject track if we don't have the codec
+        media.media_name.port.value = 0;
+        media.media_name.formats = vec!["0".to_owned()];
+        return Ok((d.with_media(media), false));
+    }
+
+    let mut media = media
+        .with_value_attribute(
+            ATTR_KEY_CONNECTION_SETUP.to_owned(),
+            params.dtls_role.to_string(),
+        )
+        .with_value_attribute(ATTR_KEY_MID.to_owned(), params.mid_value)
+        .with_ice_credentials(
+            ice_params.username_fragment.clone(),
+            ice_params.password.clone(),
+        )
+        .with_property_attribute(ATTR_KEY_RTCPMUX.to_owned())
+        .with_property_attribute(ATTR_KEY_RTCPRSIZE.to_owned());
+
+    for codec in &codecs {
+        let capability = &codec.capability;
+        let name = match capability.mime_type.split_once('/') {
+            Some((_, name)) => name,
+            None => capability.mime_type.as_str(),
+        };
+
+        let mut rtpmap = format!("{} {}/{}", codec.payload_type, name, capability.clock_rate);
+        if capability.channels > 0 {
+            rtpmap += format!("/{}", capability.channels).as_str();
+        }
+
+        media
+            .media_name
+            .formats
+            .push(codec.payload_type.to_string());
+        media = media.with_value_attribute(ATTR_KEY_RTPMAP.to_owned(), rtpmap);
+        if !capability.sdp_fmtp_line.is_empty() {
+            media = media.with_value_attribute(
+                ATTR_KEY_FMTP.to_owned(),
+                format!("{} {}", codec.payload_type, capability.sdp_fmtp_line),
+            );
+        }
+
+        for feedback in &capability.rtcp_feedback {
+            let mut value = format!("{} {}", codec.payload_type, feedback.typ);
+            if !feedback.parameter.is_empty() {
+                value += format!(" {}", feedback.parameter).as_str();
+            }
+            media = media.with_value_attribute(ATTR_KEY_RTCPFB.to_owned(), value);
+        }
+    }
+
+    let mut directions = vec![];
+    if t.sender().await.is_some() {
+        directions.push(RTPTransceiverDirection::Sendonly);
+    }
+    if t.receiver().await.is_some() {
+        directions.push(RTPTransceiverDirection::Recvonly);
+    }
+
+    let parameters = media_engine
+        .get_rtp_parameters_by_kind(t.kind(), &directions)
+        .await;
+    for extension in &parameters.header_extensions {
+        media = media.with_value_attribute(
+            ATTR_KEY_EXTMAP.to_owned(),
+            format!("{} {}", extension.id, extension.uri),
+        );
+    }
+
+    if let Some(sender) = t.sender().await {
+        if let Some(track) = sender.track().await {
+            let encodings = sender.get_parameters().await.encodings;
+            let has_rids = encodings.iter().any(|e| !e.rid.is_empty());
+
+            // The SSRCs of simulcast layers aren't declared, the remote tells
+            // the layers apart by the rid header extension of their packets
+            if !has_rids {
+                // the RTX repair flow of the stream is declared along with it (RFC 4588)
+                let rtx_ssrc = encodings.first().map_or(0, |e| e.rtx.ssrc);
+                if rtx_ssrc != 0 {
+                    media = media.with_value_attribute(
+                        ATTR_KEY_SSRCGROUP.to_owned(),
+                        format!(
+                            "{} {} {}",
+                            SEMANTIC_TOKEN_FLOW_IDENTIFICATION, sender.ssrc, rtx_ssrc
+                        ),
+                    );
+                }
+                media = media.with_media_source(
+                    sender.ssrc,
+                    track.stream_id().to_owned(), /* cname */
+                    track.stream_id().to_owned(), /* stream_label */
+                    track.id().to_owned(),
+                );
+                if rtx_ssrc != 0 {
+                    media = media.with_media_source(
+                        rtx_ssrc,
+                        track.stream_id().to_owned(), /* cname */
+                        track.stream_id().to_owned(), /* stream_label */
+                        track.id().to_owned(),
+                    );
+                }
+            }
+            media = media.with_property_attribute(format!(
+                "{}:{} {}",
+                ATTR_KEY_MSID,
+                track.stream_id(),
+                track.id()
+            ));
+
+            if has_rids {
+                let mut rids = vec![];
+                for encoding in &encodings {
+                    media = media.with_value_attribute(
+                        ATTR_KEY_RID.to_owned(),
+                        format!("{} send", encoding.rid),
+                    );
+                    rids.push(encoding.rid.as_str());
+                }
+                media = media.with_value_attribute(
+                    ATTR_KEY_SIMULCAST.to_owned(),
+                    format!("send {}", rids.join(";")),
+                );
+            }
+        }
+    }
+
+    let direction = match media_section.offered_direction {
+        // If a stream is offered as sendonly or recvonly, the answer MUST be
+        // marked as the reverse direction or inactive. Other offered directions
+        // leave the answer to the transceiver, except for inactive which
+        // MUST be answered as inactive.
+        // https://tools.ietf.org/html/rfc3264#section-6.1
+        Some(offered_direction)
+            if offered_direction == RTPTransceiverDirection::Sendonly
+                || offered_direction == RTPTransceiverDirection::Recvonly =>
+        {
+            offered_direction.reverse().intersect(t.direction())
+        }
+        Some(RTPTransceiverDirection::Inactive) => RTPTransceiverDirection::Inactive,
+        // When creating offers, the transceiver direction is directly reflected
+        // in the output, even for re-offers.
+        _ => t.direction(),
+    };
+    media = media.with_property_attribute(direction.to_string());
+
+    for f in dtls_fingerprints {
+        media = media.with_fingerprint(f.algorithm.clone(), f.value.to_uppercase());
+    }
+
+    if params.should_add_candidates {
+        media = add_candidates_to_media_descriptions(candidates, media, params.ice_gathering_state)
+            .await?;
+    }
+
+    Ok((d.with_media(media), true))
+}
+
+/// populate_local_candidates adds the candidates gathered so far to the first
+/// media section of a local description
+pub(crate) async fn populate_local_candidates(
+    session_description: Option<RTCSessionDescription>,
+    ice_gatherer: &Arc<ICEGatherer>,
+    ice_gathering_state: ICEGatheringState,
+) -> Option<RTCSessionDescription> {
+    let sd = session_description?;
+
+    let candidates = match ice_gatherer.get_local_candidates().await {
+        Ok(candidates) => candidates,
+        Err(_) => return Some(sd),
+    };
+
+    let mut parsed = match sd.unmarshal() {
+        Ok(parsed) => parsed,
+        Err(_) => return Some(sd),
+    };
+
+    if !parsed.media_descriptions.is_empty() {
+        let m = parsed.media_descriptions.remove(0);
+        let m =
+            match add_candidates_to_media_descriptions(&candidates, m, ice_gathering_state).await {
+                Ok(m) => m,
+                Err(_) => return Some(sd),
+            };
+        parsed.media_descriptions.insert(0, m);
+    }
+
+    Some(RTCSessionDescription {
+        sdp_type: sd.sdp_type,
+        sdp: parsed.marshal(),
+    })
+}
+
+/// populate_sdp serializes a PeerConnections state into an SDP
+pub(crate) async fn populate_sdp(
+    mut d: SessionDescription,
+    dtls_fingerprints: &[DTLSFingerprint],
+    media_engine: &Arc<MediaEngine>,
+    candidates: &[ICECandidate],
+    ice_params: &ICEParameters,
+    media_sections: &[MediaSection],
+    params: PopulateSdpParams,
+) -> Result<SessionDescription> {
+    let media_dtls_fingerprints = if params.media_description_fingerprint {
+        dtls_fingerprints.to_vec()
+    } else {
+        vec![]
+    };
+
+    let mut bundle_value = "BUNDLE".to_owned();
+    for (i, m) in media_sections.iter().enumerate() {
+        if m.data && !m.transceivers.is_empty() {
+            return Err(Error::ErrSDPMediaSectionMediaDataChanInvalid.into());
+        } else if m.transceivers.len() > 1 {
+            return Err(Error::ErrSDPMediaSectionMultipleTrackInvalid.into());
+        }
+
+        let should_add_id = if m.data {
+            let data_params = AddDataMediaSectionParams {
+                should_add_candidates: i == 0,
+                mid_value: m.id.clone(),
+                ice_params: ice_params.clone(),
+                dtls_role: params.connection_role,
+                ice_gathering_state: params.ice_gathering_state,
+            };
+            d = add_data_media_section(d, &media_dtls_fingerprints, candidates, data_params)
+                .await?;
+            true
+        } else {
+            let transceiver_params = AddTransceiverSdpParams {
+                should_add_candidates: i == 0,
+                mid_value: m.id.clone(),
+                dtls_role: params.connection_role,
+                ice_gathering_state: params.ice_gathering_state,
+            };
+            let (d1, should_add_id) = add_transceiver_sdp(
+                d,
+                &media_dtls_fingerprints,
+                media_engine,
+                ice_params,
+                candidates,
+                m,
+                transceiver_params,
+            )
+            .await?;
+            d = d1;
+            should_add_id
+        };
+
+        if should_add_id {
+            bundle_value += " ";
+            bundle_value += m.id.as_str();
+        }
+    }
+
+    if !params.media_description_fingerprint {
+        for fingerprint in dtls_fingerprints {
+            d = d.with_fingerprint(
+                fingerprint.algorithm.clone(),
+                fingerprint.value.to_uppercase(),
+            );
+        }
+    }
+
+    if params.is_icelite {
+        // RFC 5245 S15.3
+        d = d.with_value_attribute(ATTR_KEY_ICELITE.to_owned(), ATTR_KEY_ICELITE.to_owned());
+    }
+
+    Ok(d.with_value_attribute(ATTR_KEY_GROUP.to_owned(), bundle_value))
+}
+
+pub(crate) fn get_mid_value(media: &MediaDescription) -> Option<&String> {
+    media_attribute(media, ATTR_KEY_MID)
+}
+
+/// get_peer_direction returns the direction of a media section, which is
+/// Unspecified when it has no direction attribute
+pub(crate) fn get_peer_direction(media: &MediaDescription) -> RTPTransceiverDirection {
+    for a in &media.attributes {
+        let direction = RTPTransceiverDirection::from(a.key.as_str());
+        if direction != RTPTransceiverDirection::Unspecified {
+            return direction;
+        }
+    }
+    RTPTransceiverDirection::Unspecified
+}
+
+/// get_by_mid returns the media section of a description with the given mid
+pub(crate) fn get_by_mid<'a>(
+    search_mid: &str,
+    desc: &'a SessionDescription,
+) -> Option<&'a MediaDescription> {
+    desc.media_descriptions
+        .iter()
+        .find(|m| get_mid_value(m).is_some_and(|mid| mid == search_mid))
+}
+
+pub(crate) fn track_details_for_ssrc(
+    track_details: &[TrackDetails],
+    ssrc: SSRC,
+) -> Option<&TrackDetails> {
+    track_details.iter().find(|x| x.ssrc == ssrc)
+}
+
+pub(crate) fn track_details_for_rid<'a>(
+    track_details: &'a [TrackDetails],
+    rid: &str,
+) -> Option<&'a TrackDetails> {
+    track_details
+        .iter()
+        .find(|x| x.rids.iter().any(|r| r == rid))
+}
+
+pub(crate) fn filter_track_with_ssrc(incoming_tracks: &mut Vec<TrackDetails>, ssrc: SSRC) {
+    incoming_tracks.retain(|x| x.ssrc != ssrc);
+}
+
+/// track_details_from_sdp extracts all TrackDetails from an SDP.
+pub(crate) fn track_details_from_sdp(s: &SessionDescription) -> Vec<TrackDetails> {
+    let mut incoming_tracks = vec![];
+    let mut rtx_repair_flows = vec![];
+    // the (ssrc, repair ssrc) pairs of the FID groups
+    let mut repair_ssrcs: Vec<(SSRC, SSRC)> = vec![];
+
+    for media in &s.media_descriptions {
+        let mut stream_id = "";
+        let mut track_id = "";
+
+        // If media section is recvonly or inactive skip
+        let direction = get_peer_direction(media);
+        if direction == RTPTransceiverDirection::Recvonly
+            || direction == RTPTransceiverDirection::Inactive
+        {
+            continue;
+        }
+
+        let mid_value = match get_mid_value(media) {
+            Some(mid_value) => mid_value,
+            None => continue,
+        };
+
+        let codec_type = RTPCodecType::from(media.media_name.media.as_str());
+        if codec_type == RTPCodecType::Unspecified {
+            continue;
+        }
+
+        for attr in &media.attributes {
+            let value = match &attr.value {
+                Some(value) => value.as_str(),
+                None => continue,
+            };
+
+            match attr.key.as_str() {
+                ATTR_KEY_SSRCGROUP => {
+                    let split: Vec<&str> = value.split(' ').collect();
+                    if split[0] == SEMANTIC_TOKEN_FLOW_IDENTIFICATION {
+                        // Add rtx ssrcs to blacklist, to avoid adding them as tracks
+                        // Essentially lines like `a=ssrc-group:FID 2231627014 632943048` are processed by this section
+                        // as this declares that the second SSRC (632943048) is a rtx repair flow (RFC4588) for the first
+                        // (2231627014) as specified in RFC5576
+                        if split.len() == 3 {
+                            match (split[1].parse::<SSRC>(), split[2].parse::<SSRC>()) {
+                                (Ok(base_ssrc), Ok(rtx_repair_flow)) => {
+                                    rtx_repair_flows.push(rtx_repair_flow);
+                                    repair_ssrcs.push((base_ssrc, rtx_repair_flow));
+                                    // Remove if rtx was added as track before
+                                    filter_track_with_ssrc(&mut incoming_tracks, rtx_repair_flow);
+                                }
+                                (Err(err), _) | (_, Err(err)) => {
+                                    log::warn!("Failed to parse SSRC: {}", err)
+                                }
+                            }
+                        }
+                    }
+                }
+
+                // Handle `a=msid:<stream_id> <track_label>` for Unified plan. The first value is the same as MediaStream.id
+                // in the browser and can be used to figure out which tracks belong to the same stream. The browser should
+                // figure this out automatically when an ontrack event is emitted on RTCPeerConnection.
+                ATTR_KEY_MSID => {
+                    let mut split = value.split(' ');
+                    if let (Some(sid), Some(tid), None) = (split.next(), split.next(), split.next())
+                    {
+                        stream_id = sid;
+                        track_id = tid;
+                    }
+                }
+
+                ATTR_KEY_SSRC => {
+                    let split: Vec<&str> = value.split(' ').collect();
+                    let ssrc = match split[0].parse::<SSRC>() {
+                        Ok(ssrc) => ssrc,
+                        Err(err) => {
+                            log::warn!("Failed to parse SSRC: {}", err);
+                            continue;
+                        }
+                    };
+
+                    if rtx_repair_flows.contains(&ssrc) {
+                        continue; // This ssrc is a RTX repair flow, ignore
+                    }
+
+                    // Handle `a=ssrc:<ssrc> msid:<stream_id> <track_label>` for Plan B
+                    if split.len() == 3 && split[1].starts_with("msid:") {
+                        stream_id = &split[1]["msid:".len()..];
+                        track_id = split[2];
+                    }
+
+                    let track_details = TrackDetails {
+                        mid: mid_value.to_owned(),
+                        kind: codec_type,
+                        stream_id: stream_id.to_owned(),
+                        id: track_id.to_owned(),
+                        ssrc,
+                        ..Default::default()
+                    };
+
+                    match incoming_tracks.iter_mut().find(|t| t.ssrc == ssrc) {
+                        Some(t) => *t = track_details,
+                        None => incoming_tracks.push(track_details),
+                    }
+                }
+                _ => {}
+            };
+        }
+
+        // Simulcast layers are announced with `a=rid` lines instead of SSRCs,
+        // the SSRC of each layer is only known once its packets are probed
+        let rids = get_rids(media);
+        if !rids.is_empty() && !track_id.is_empty() && !stream_id.is_empty() {
+            incoming_tracks.push(TrackDetails {
+                mid: mid_value.to_owned(),
+                kind: codec_type,
+                stream_id: stream_id.to_owned(),
+                id: track_id.to_owned(),
+                rids,
+                ..Default::default()
+            });
+        }
+    }
+
+    for t in &mut incoming_tracks {
+        if let Some((_, repair_ssrc)) = repair_ssrcs.iter().find(|(ssrc, _)| *ssrc == t.ssrc) {
+            t.repair_ssrc = *repair_ssrc;
+        }
+    }
+
+    incoming_tracks
+}
+
+/// get_rids returns the rids of the `a=rid:<rid> <direction>` lines of a media section
+pub(crate) fn get_rids(media: &MediaDescription) -> Vec<String> {
+    let mut rids = vec![];
+    for attr in &media.attributes {
+        if attr.key != ATTR_KEY_RID {
+            continue;
+        }
+        if let Some(rid) = attr.value.as_ref().and_then(|v| v.split(' ').next()) {
+            if !rid.is_empty() && !rids.iter().any(|r| r == rid) {
+                rids.push(rid.to_owned());
+            }
+        }
+    }
+    rids
+}
+
+pub(crate) fn extract_fingerprint(desc: &SessionDescription) -> Result<(String, String)> {
+    let mut fingerprints = vec![];
+
+    if let Some(fingerprint) = session_attribute(desc, ATTR_KEY_FINGERPRINT) {
+        fingerprints.push(fingerprint.clone());
+    }
+
+    for m in &desc.media_descriptions {
+        if let Some(fingerprint) = media_attribute(m, ATTR_KEY_FINGERPRINT) {
+            fingerprints.push(fingerprint.clone());
+        }
+    }
+
+    if fingerprints.is_empty() {
+        return Err(Error::ErrSessionDescriptionNoFingerprint.into());
+    }
+
+    for m in 1..fingerprints.len() {
+        if fingerprints[m] != fingerprints[0] {
+            return Err(Error::ErrSessionDescriptionConflictingFingerprints.into());
+        }
+    }
+
+    let parts: Vec<&str> = fingerprints[0].split(' ').collect();
+    if parts.len() != 2 {
+        return Err(Error::ErrSessionDescriptionInvalidFingerprint.into());
+    }
+
+    Ok((parts[1].to_owned(), parts[0].to_owned()))
+}
+
+pub(crate) async fn extract_ice_details(
+    desc: &SessionDescription,
+) -> Result<(String, String, Vec<ICECandidate>)> {
+    let mut candidates = vec![];
+    let mut remote_pwds = vec![];
+    let mut remote_ufrags = vec![];
+
+    if let Some(ufrag) = session_attribute(desc, ATTR_KEY_ICE_UFRAG) {
+        remote_ufrags.push(ufrag.clone());
+    }
+    if let Some(pwd) = session_attribute(desc, ATTR_KEY_ICE_PWD) {
+        remote_pwds.push(pwd.clone());
+    }
+
+    for m in &desc.media_descriptions {
+        if let Some(ufrag) = media_attribute(m, ATTR_KEY_ICE_UFRAG) {
+            remote_ufrags.push(ufrag.clone());
+        }
+        if let Some(pwd) = media_attribute(m, ATTR_KEY_ICE_PWD) {
+            remote_pwds.push(pwd.clone());
+        }
+
+        for a in &m.attributes {
+            if a.key == ATTR_KEY_CANDIDATE {
+                if let Some(value) = &a.value {
+                    candidates.push(unmarshal_candidate(value)?);
+                }
+            }
+        }
+    }
+
+    if remote_ufrags.is_empty() {
+        return Err(Error::ErrSessionDescriptionMissingIceUfrag.into());
+    } else if remote_pwds.is_empty() {
+        return Err(Error::ErrSessionDescriptionMissingIcePwd.into());
+    }
+
+    for m in 1..remote_ufrags.len() {
+        if remote_ufrags[m] != remote_ufrags[0] {
+            return Err(Error::ErrSessionDescriptionConflictingIceUfrag.into());
+        }
+    }
+
+    for m in 1..remote_pwds.len() {
+        if remote_pwds[m] != remote_pwds[0] {
+            return Err(Error::ErrSessionDescriptionConflictingIcePwd.into());
+        }
+    }
+
+    Ok((remote_ufrags[0].clone(), remote_pwds[0].clone(), candidates))
+}
+
+pub(crate) fn have_application_media_section(desc: &SessionDescription) -> bool {
+    for m in &desc.media_descriptions {
+        if m.media_name.media == MEDIA_SECTION_APPLICATION {
+            return true;
+        }
+    }
+
+    false
+}
+
+pub(crate) fn is_ice_lite(desc: &SessionDescription) -> bool {
+    for a in &desc.attributes {
+        if a.key.trim() == ATTR_KEY_ICELITE {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// static_codec returns the codec for the static audio payload types which
+/// may be offered without an a=rtpmap line
+/// https://tools.ietf.org/html/rfc3551#section-6
+fn static_codec(payload_type: PayloadType) -> Option<(&'static str, u32)> {
+    match payload_type {
+        0 => Some(("PCMU", 8000)),
+        8 => Some(("PCMA", 8000)),
+        9 => Some(("G722", 8000)),
+        _ => None,
+    }
+}
+
+/// codecs_from_media_description collects the codecs of a media section from
+/// its formats and the matching a=rtpmap, a=fmtp and a=rtcp-fb lines
+pub(crate) fn codecs_from_media_description(
+    m: &MediaDescription,
+) -> Result<Vec<RTPCodecParameters>> {
+    let parse_payload_type = |s: &str| -> Result<PayloadType> {
+        s.parse::<PayloadType>()
+            .map_err(|_| Error::ErrSessionDescriptionInvalidPayloadType.into())
+    };
+
+    let mut out = vec![];
+    for format in &m.media_name.formats {
+        let payload_type = parse_payload_type(format)?;
+
+        let mut name = String::new();
+        let mut clock_rate = 0;
+        let mut channels = 0;
+        let mut sdp_fmtp_line = String::new();
+        let mut rtcp_feedback = vec![];
+
+        if let Some((n, c)) = static_codec(payload_type) {
+            name = n.to_owned();
+            clock_rate = c;
+        }
+
+        for a in &m.attributes {
+            let value = match &a.value {
+                Some(value) => value,
+                None => continue,
+            };
+            // <payload type> <parameters>
+            let mut split = value.splitn(2, ' ');
+            let (pt, params) = match (split.next(), split.next()) {
+                (Some(pt), Some(params)) => (pt, params.trim()),
+                _ => continue,
+            };
+
+            if a.key == ATTR_KEY_RTCPFB {
+                // a=rtcp-fb:* applies to all payload types
+                if pt != "*" && parse_payload_type(pt)? != payload_type {
+                    continue;
+                }
+                let mut fb = params.splitn(2, ' ');
+                rtcp_feedback.push(RTCPFeedback {
+                    typ: fb.next().unwrap_or_default().to_owned(),
+                    parameter: fb.next().unwrap_or_default().to_owned(),
+                });
+                continue;
+            }
+
+            if (a.key != ATTR_KEY_RTPMAP && a.key != ATTR_KEY_FMTP)
+                || parse_payload_type(pt)? != payload_type
+            {
+                continue;
+            }
+
+            if a.key == ATTR_KEY_RTPMAP {
+                // <encoding name>/<clock rate>[/<encoding parameters>]
+                let fields: Vec<&str> = params.split('/').collect();
+                name = fields[0].to_owned();
+                if fields.len() > 1 {
+                    clock_rate = fields[1]
+                        .parse()
+                        .map_err(|_| Error::ErrSessionDescriptionInvalidPayloadType)?;
+                }
+                if fields.len() > 2 {
+                    channels = fields[2]
+                        .parse()
+                        .map_err(|_| Error::ErrSessionDescriptionInvalidPayloadType)?;
+                }
+            } else {
+                sdp_fmtp_line = params.to_owned();
+            }
+        }
+
+        // formats without a codec name can't be matched, so they are skipped
+        if name.is_empty() {
+            continue;
+        }
+
+        out.push(RTPCodecParameters {
+            capability: RTPCodecCapability {
+                mime_type: format!("{}/{}", m.media_name.media, name),
+                clock_rate,
+                channels,
+                sdp_fmtp_line,
+                rtcp_feedback,
+            },
+            payload_type,
+            ..Default::default()
+        });
+    }
+
+    Ok(out)
+}
+
+/// rtp_extensions_from_media_description returns the negotiated ID of each
+/// header extension URI from the a=extmap lines of a media section
+pub(crate) fn rtp_extensions_from_media_description(
+    m: &MediaDescription,
+) -> Result<HashMap<String, usize>> {
+    let mut out = HashMap::new();
+
+    for a in &m.attributes {
+        if a.key != ATTR_KEY_EXTMAP {
+            continue;
+        }
+
+        // <value>["/"<direction>] <URI> <extensionattributes>
+        let value = a
+            .value
+            .as_ref()
+            .ok_or(Error::ErrSessionDescriptionInvalidExtMap)?;
+        let fields: Vec<&str> = value.split_whitespace().collect();
+        if fields.len() < 2 {
+            return Err(Error::ErrSessionDescriptionInvalidExtMap.into());
+        }
+        let id = fields[0]
+            .split('/')
+            .next()
+            .unwrap_or_default()
+            .parse::<usize>()
+            .map_err(|_| Error::ErrSessionDescriptionInvalidExtMap)?;
+
+        out.insert(fields[1].to_owned(), id);
+    }
+
+    Ok(out)
+}
+
+/// update_sdp_origin saves sdp.Origin in PeerConnection when creating 1st local SDP;
+/// for subsequent calling, it updates Origin for SessionDescription from saved one
+/// and increments session version by one.
+/// https://tools.ietf.org/html/draft-ietf-rtcweb-jsep-25#section-5.2.2
+pub(crate) fn update_sdp_origin(origin: &mut Origin, d: &mut SessionDescription) {
+    if origin.session_version == 0 {
+        // store
+        origin.session_version = d.origin.session_version;
+        origin.session_id = d.origin.session_id;
+    } else {
+        // load
+        d.origin.session_id = origin.session_id;
+        origin.session_version += 1;
+        d.origin.session_version = origin.session_version;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use sdp::common_description::Attribute;
+
+    fn attribute(key: &str, value: &str) -> Attribute {
+        Attribute {
+            key: key.to_owned(),
+            value: Some(value.to_owned()),
+        }
+    }
+
+    fn media(attributes: Vec<Attribute>) -> MediaDescription {
+        let mut m = MediaDescription::new_jsep_media_description(
+            MEDIA_SECTION_APPLICATION.to_owned(),
+            vec![],
+        );
+        m.attributes = attributes;
+        m
+    }
+
+    #[test]
+    fn test_extract_fingerprint() -> Result<()> {
+        // Good Session Fingerprint
+        {
+            let s = SessionDescription {
+                attributes: vec![attribute("fingerprint", "foo bar")],
+                ..Default::default()
+            };
+
+            let (fingerprint, hash) = extract_fingerprint(&s)?;
+            assert_eq!(fingerprint, "bar");
+            assert_eq!(hash, "foo");
+        }
+
+        // Good Media Fingerprint
+        {
+            let s = SessionDescription {
+                media_descriptions: vec![media(vec![attribute("fingerprint", "foo bar")])],
+                ..Default::default()
+            };
+
+            let (fingerprint, hash) = extract_fingerprint(&s)?;
+            assert_eq!(fingerprint, "bar");
+            assert_eq!(hash, "foo");
+        }
+
+        // No Fingerprint
+        {
+            let s = SessionDescription::default();
+
+            let result = extract_fingerprint(&s);
+            assert!(result.is_err());
+            if let Err(err) = result {
+                assert!(Error::ErrSessionDescriptionNoFingerprint.equal(&err));
+            }
+        }
+
+        // Invalid Fingerprint
+        {
+            let s = SessionDescription {
+                attributes: vec![attribute("fingerprint", "foo")],
+                ..Default::default()
+            };
+
+            let result = extract_fingerprint(&s);
+            assert!(result.is_err());
+            if let Err(err) = result {
+                assert!(Error::ErrSessionDescriptionInvalidFingerprint.equal(&err));
+            }
+        }
+
+        // Conflicting Fingerprint
+        {
+            let s = SessionDescription {
+                attributes: vec![attribute("fingerprint", "foo")],
+                media_descriptions: vec![media(vec![attribute("fingerprint", "foo blah")])],
+                ..Default::default()
+            };
+
+            let result = extract_fingerprint(&s);
+            assert!(result.is_err());
+            if let Err(err) = result {
+                assert!(Error::ErrSessionDescriptionConflictingFingerprints.equal(&err));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_extract_ice_details() -> Result<()> {
+        const DEFAULT_UFRAG: &str = "defaultPwd";
+        const DEFAULT_PWD: &str = "defaultUfrag";
+
+        // Missing ice-pwd
+        {
+            let s = SessionDescription {
+                media_descriptions: vec![media(vec![attribute("ice-ufrag", DEFAULT_UFRAG)])],
+                ..Default::default()
+            };
+
+            let result = extract_ice_details(&s).await;
+            assert!(result.is_err());
+            if let Err(err) = result {
+                assert!(Error::ErrSessionDescriptionMissingIcePwd.equal(&err));
+            }
+        }
+
+        // Missing ice-ufrag
+        {
+            let s = SessionDescription {
+                media_descriptions: vec![media(vec![attribute("ice-pwd", DEFAULT_PWD)])],
+                ..Default::default()
+            };
+
+            let result = extract_ice_details(&s).await;
+            assert!(result.is_err());
+            if let Err(err) = result {
+                assert!(Error::ErrSessionDescriptionMissingIceUfrag.equal(&err));
+            }
+        }
+
+        // ice-pwd and ice-ufrag at session level
+        {
+            let s = SessionDescription {
+                attributes: vec![
+                    attribute("ice-ufrag", DEFAULT_UFRAG),
+                    attribute("ice-pwd", DEFAULT_PWD),
+                ],
+                ..Default::default()
+            };
+
+            let (ufrag, pwd, _) = extract_ice_details(&s).await?;
+            assert_eq!(ufrag, DEFAULT_UFRAG);
+            assert_eq!(pwd, DEFAULT_PWD);
+        }
+
+        // ice-pwd and ice-ufrag at media level
+        {
+            let s = SessionDescription {
+                media_descriptions: vec![media(vec![
+                    attribute("ice-ufrag", DEFAULT_UFRAG),
+                    attribute("ice-pwd", DEFAULT_PWD),
+                ])],
+                ..Default::default()
+            };
+
+            let (ufrag, pwd, _) = extract_ice_details(&s).await?;
+            assert_eq!(ufrag, DEFAULT_UFRAG);
+            assert_eq!(pwd, DEFAULT_PWD);
+        }
+
+        // Conflicting ice-ufrag
+        {
+            let s = SessionDescription {
+                attributes: vec![attribute("ice-ufrag", "invalidUfrag")],
+                media_descriptions: vec![media(vec![
+                    attribute("ice-ufrag", DEFAULT_UFRAG),
+                    attribute("ice-pwd", DEFAULT_PWD),
+                ])],
+                ..Default::default()
+            };
+
+            let result = extract_ice_details(&s).await;
+            assert!(result.is_err());
+            if let Err(err) = result {
+                assert!(Error::ErrSessionDescriptionConflictingIceUfrag.equal(&err));
+            }
+        }
+
+        // Conflicting ice-pwd
+        {
+            let s = SessionDescription {
+                attributes: vec![attribute("ice-pwd", "invalidPwd")],
+                media_descriptions: vec![media(vec![
+                    attribute("ice-ufrag", DEFAULT_UFRAG),
+                    attribute("ice-pwd", DEFAULT_PWD),
+                ])],
+                ..Default::default()
+            };
+
+            let result = extract_ice_details(&s).await;
+            assert!(result.is_err());
+            if let Err(err) = result {
+                assert!(Error::ErrSessionDescriptionConflictingIcePwd.equal(&err));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_have_application_media_section() {
+        let s = SessionDescription::default();
+        assert!(!have_application_media_section(&s));
+
+        let s = SessionDescription {
+            media_descriptions: vec![media(vec![])],
+            ..Default::default()
+        };
+        assert!(have_application_media_section(&s));
+    }
+
+    #[test]
+    fn test_codecs_from_media_description() -> Result<()> {
+        let mut m = media(vec![
+            attribute("rtpmap", "111 opus/48000/2"),
+            attribute("fmtp", "111 minptime=10;useinbandfec=1"),
+            attribute("rtcp-fb", "111 transport-cc"),
+            attribute("rtcp-fb", "* nack"),
+        ]);
+        m.media_name.media = "audio".to_owned();
+        m.media_name.formats = vec!["111".to_owned(), "0".to_owned(), "126".to_owned()];
+
+        let codecs = codecs_from_media_description(&m)?;
+        assert_eq!(codecs.len(), 2, "format without rtpmap is skipped");
+
+        assert_eq!(codecs[0].payload_type, 111);
+        assert_eq!(codecs[0].capability.mime_type, "audio/opus");
+        assert_eq!(codecs[0].capability.clock_rate, 48000);
+        assert_eq!(codecs[0].capability.channels, 2);
+        assert_eq!(
+            codecs[0].capability.sdp_fmtp_line,
+            "minptime=10;useinbandfec=1"
+        );
+        assert_eq!(codecs[0].capability.rtcp_feedback.len(), 2);
+
+        // static payload types don't need a rtpmap
+        assert_eq!(codecs[1].payload_type, 0);
+        assert_eq!(codecs[1].capability.mime_type, "audio/PCMU");
+        assert_eq!(codecs[1].capability.clock_rate, 8000);
+
+        m.media_name.formats = vec!["opus".to_owned()];
+        if let Err(err) = codecs_from_media_description(&m) {
+            assert!(Error::ErrSessionDescriptionInvalidPayloadType.equal(&err));
+        } else {
+            panic!("expected error, but got ok");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rtp_extensions_from_media_description() -> Result<()> {
+        let m = media(vec![
+            attribute("extmap", "1 urn:ietf:params:rtp-hdrext:sdes:mid"),
+            attribute(
+                "extmap",
+                "3/sendonly urn:ietf:params:rtp-hdrext:sdes:rtp-stream-id",
+            ),
+        ]);
+
+        let extensions = rtp_extensions_from_media_description(&m)?;
+        assert_eq!(extensions.len(), 2);
+        assert_eq!(extensions["urn:ietf:params:rtp-hdrext:sdes:mid"], 1);
+        assert_eq!(
+            extensions["urn:ietf:params:rtp-hdrext:sdes:rtp-stream-id"],
+            3
+        );
+
+        let m = media(vec![attribute("extmap", "invalid")]);
+        if let Err(err) = rtp_extensions_from_media_description(&m) {
+            assert!(Error::ErrSessionDescriptionInvalidExtMap.equal(&err));
+        } else {
+            panic!("expected error, but got ok");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_track_details_from_sdp_rids() {
+        let mut m = MediaDescription::new_jsep_media_description("video".to_owned(), vec![]);
+        m.attributes = vec![
+            attribute("mid", "0"),
+            Attribute {
+                key: "sendonly".to_owned(),
+                value: None,
+            },
+            attribute("msid", "webrtc-rs video"),
+            attribute("rid", "f send"),
+            attribute("rid", "h send"),
+            attribute("rid", "q send"),
+            attribute("simulcast", "send f;h;q"),
+        ];
+        let s = SessionDescription {
+            media_descriptions: vec![m],
+            ..Default::default()
+        };
+
+        let track_details = track_details_from_sdp(&s);
+        assert_eq!(track_details.len(), 1);
+        assert_eq!(track_details[0].mid, "0");
+        assert_eq!(track_details[0].kind, RTPCodecType::Video);
+        assert_eq!(track_details[0].stream_id, "webrtc-rs");
+        assert_eq!(track_details[0].id, "video");
+        assert_eq!(track_details[0].ssrc, 0);
+        assert_eq!(track_details[0].rids, vec!["f", "h", "q"]);
+
+        assert!(track_details_for_rid(&track_details, "h").is_some());
+        assert!(track_details_for_rid(&track_details, "x").is_none());
+    }
+
+    #[test]
+    fn test_track_details_from_sdp_rtx() {
+        let mut m = MediaDescription::new_jsep_media_description("video".to_owned(), vec![]);
+        m.attributes = vec![
+            attribute("mid", "0"),
+            Attribute {
+                key: "sendrecv".to_owned(),
+                value: None,
+            },
+            attribute("msid", "webrtc-rs video"),
+            attribute("ssrc-group", "FID 1000 2000"),
+            attribute("ssrc", "1000 cname:webrtc-rs"),
+            attribute("ssrc", "2000 cname:webrtc-rs"),
+        ];
+        let s = SessionDescription {
+            media_descriptions: vec![m],
+            ..Default::default()
+        };
+
+        // the repair flow isn't a track of its own
+        let track_details = track_details_from_sdp(&s);
+        assert_eq!(track_details.len(), 1);
+        assert_eq!(track_details[0].ssrc, 1000);
+        assert_eq!(track_details[0].repair_ssrc, 2000);
+    }
+}
@@ -2,8 +2,9 @@ use serde::{Deserialize, Serialize};
 use std::fmt;
 
 /// SDPType describes the type of an SessionDescription.
-#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize, Default)]
 pub enum SDPType {
+    #[default]
     Unspecified = 0,
 
     /// indicates that a description MUST be treated as an SDP offer.
@@ -29,12 +30,6 @@ pub enum SDPType {
     Rollback,
 }
 
-impl Default for SDPType {
-    fn default() -> Self {
-        SDPType::Unspecified
-    }
-}
-
 const SDP_TYPE_OFFER_STR: &str = "Offer";
 const SDP_TYPE_PRANSWER_STR: &str = "Pranswer";
 const SDP_TYPE_ANSWER_STR: &str = "Answer";
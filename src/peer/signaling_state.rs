@@ -4,18 +4,13 @@ use crate::peer::sdp::sdp_type::SDPType;
 use anyhow::Result;
 use std::fmt;
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
 pub(crate) enum StateChangeOp {
+    #[default]
     SetLocal,
     SetRemote,
 }
 
-impl Default for StateChangeOp {
-    fn default() -> Self {
-        StateChangeOp::SetLocal
-    }
-}
-
 impl fmt::Display for StateChangeOp {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
@@ -27,8 +22,9 @@ impl fmt::Display for StateChangeOp {
 }
 
 /// SignalingState indicates the signaling state of the offer/answer process.
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
 pub enum SignalingState {
+    #[default]
     Unspecified = 0,
 
     /// SignalingStateStable indicates there is no offer/answer exchange in
@@ -58,12 +54,6 @@ pub enum SignalingState {
     Closed,
 }
 
-impl Default for SignalingState {
-    fn default() -> Self {
-        SignalingState::Unspecified
-    }
-}
-
 const SIGNALING_STATE_STABLE_STR: &str = "Stable";
 const SIGNALING_STATE_HAVE_LOCAL_OFFER_STR: &str = "HaveLocalOffer";
 const SIGNALING_STATE_HAVE_REMOTE_OFFER_STR: &str = "HaveRemoteOffer";
@@ -130,6 +120,27 @@ pub(crate) fn check_next_signaling_state(
         return Err(Error::ErrSignalingStateCannotRollback.into());
     }
 
+    // Rollbacks discard the pending offer, along with any provisional answer,
+    // and always go back to stable
+    if sdp_type == SDPType::Rollback {
+        if next == SignalingState::Stable {
+            match (cur, op) {
+                // have-local-offer->SetLocal(rollback)->stable
+                // have-remote-pranswer->SetLocal(rollback)->stable
+                (SignalingState::HaveLocalOffer, StateChangeOp::SetLocal)
+                | (SignalingState::HaveRemotePranswer, StateChangeOp::SetLocal)
+                // have-remote-offer->SetRemote(rollback)->stable
+                // have-local-pranswer->SetRemote(rollback)->stable
+                | (SignalingState::HaveRemoteOffer, StateChangeOp::SetRemote)
+                | (SignalingState::HaveLocalPranswer, StateChangeOp::SetRemote) => {
+                    return Ok(next)
+                }
+                _ => {}
+            }
+        }
+        return Err(Error::ErrSignalingStateProposedTransitionInvalid.into());
+    }
+
     // 4.3.1 valid state transitions
     match cur {
         SignalingState::Stable => {
@@ -152,16 +163,12 @@ pub(crate) fn check_next_signaling_state(
             if op == StateChangeOp::SetRemote {
                 match sdp_type {
                     // have-local-offer->SetRemote(answer)->stable
-                    SDPType::Answer => {
-                        if next == SignalingState::Stable {
-                            return Ok(next);
-                        }
+                    SDPType::Answer if next == SignalingState::Stable => {
+                        return Ok(next);
                     }
                     // have-local-offer->SetRemote(pranswer)->have-remote-pranswer
-                    SDPType::Pranswer => {
-                        if next == SignalingState::HaveRemotePranswer {
-                            return Ok(next);
-                        }
+                    SDPType::Pranswer if next == SignalingState::HaveRemotePranswer => {
+                        return Ok(next);
                     }
                     _ => {}
                 }
@@ -179,16 +186,12 @@ pub(crate) fn check_next_signaling_state(
             if op == StateChangeOp::SetLocal {
                 match sdp_type {
                     // have-remote-offer->SetLocal(answer)->stable
-                    SDPType::Answer => {
-                        if next == SignalingState::Stable {
-                            return Ok(next);
-                        }
+                    SDPType::Answer if next == SignalingState::Stable => {
+                        return Ok(next);
                     }
                     // have-remote-offer->SetLocal(pranswer)->have-local-pranswer
-                    SDPType::Pranswer => {
-                        if next == SignalingState::HaveLocalPranswer {
-                            return Ok(next);
-                        }
+                    SDPType::Pranswer if next == SignalingState::HaveLocalPranswer => {
+                        return Ok(next);
                     }
                     _ => {}
                 }
@@ -315,6 +318,46 @@ mod test {
                 SDPType::Answer,
                 None,
             ),
+            (
+                "have-local-offer->SetLocal(rollback)->stable",
+                SignalingState::HaveLocalOffer,
+                SignalingState::Stable,
+                StateChangeOp::SetLocal,
+                SDPType::Rollback,
+                None,
+            ),
+            (
+                "have-remote-pranswer->SetLocal(rollback)->stable",
+                SignalingState::HaveRemotePranswer,
+                SignalingState::Stable,
+                StateChangeOp::SetLocal,
+                SDPType::Rollback,
+                None,
+            ),
+            (
+                "have-remote-offer->SetRemote(rollback)->stable",
+                SignalingState::HaveRemoteOffer,
+                SignalingState::Stable,
+                StateChangeOp::SetRemote,
+                SDPType::Rollback,
+                None,
+            ),
+            (
+                "have-local-pranswer->SetRemote(rollback)->stable",
+                SignalingState::HaveLocalPranswer,
+                SignalingState::Stable,
+                StateChangeOp::SetRemote,
+                SDPType::Rollback,
+                None,
+            ),
+            (
+                "(invalid) have-local-offer->SetRemote(rollback)->stable",
+                SignalingState::HaveLocalOffer,
+                SignalingState::Stable,
+                StateChangeOp::SetRemote,
+                SDPType::Rollback,
+                Some(Error::ErrSignalingStateProposedTransitionInvalid),
+            ),
             (
                 "(invalid) stable->SetRemote(pranswer)->have-remote-pranswer",
                 SignalingState::Stable,
@@ -343,8 +386,7 @@ mod test {
                     assert_eq!(err.to_string(), got.to_string(), "{} error mismatch", desc);
                 }
                 _ => {
-                    assert!(
-                        false,
+                    panic!(
                         "{}: expected {:?}, but got {:?}",
                         desc, expected_err, result
                     );
@@ -1 +1,14 @@
 pub mod mux;
+
+use rand::{thread_rng, Rng};
+
+const RUNES_ALPHA: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// math_rand_alpha generates a random alphabet sequence of the requested length,
+/// it is not suitable for anything security related
+pub(crate) fn math_rand_alpha(n: usize) -> String {
+    let mut rng = thread_rng();
+    (0..n)
+        .map(|_| RUNES_ALPHA[rng.gen_range(0..RUNES_ALPHA.len())] as char)
+        .collect()
+}
@@ -14,7 +14,7 @@ use std::sync::Arc;
 use tokio::sync::{mpsc, Mutex};
 use util::{Buffer, Conn};
 
-/// mux multiplexes packets on a single socket (RFC7983)
+// mux multiplexes packets on a single socket (RFC7983)
 
 /// The maximum amount of data that can be buffered before returning errors.
 const MAX_BUFFER_SIZE: usize = 1000 * 1000; // 1MB
@@ -127,7 +127,7 @@ impl Mux {
             let eps = endpoints.lock().await;
             for ep in eps.values() {
                 if (ep.match_fn)(buf) {
-                    endpoint = Some(Arc::clone(&ep));
+                    endpoint = Some(Arc::clone(ep));
                     break;
                 }
             }